@@ -0,0 +1,508 @@
+//! Periodic benchmarks of the host, published as Offer properties.
+//!
+//! Measured scores let Requestors constrain Offers on actual performance instead of
+//! nominal resources. Results are saved in the data directory and reused after restart
+//! until the next scheduled run. Benchmarks load the whole host, so they are disabled
+//! by default.
+//!
+//! Published results are signed with the node identity through `POST /me/sign` of yagna
+//! REST API: `golem.inf.benchmark.signature` is a hex encoded signature of SHA-256 digest
+//! of all other `golem.inf.benchmark.*` properties, serialized as a flat JSON object with
+//! sorted keys.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+use tokio::sync::watch;
+
+use ya_core_model::NodeId;
+use ya_utils_path::SwapSave;
+
+use crate::events::Event;
+
+pub const BENCHMARK_PROPERTY_PREFIX: &str = "golem.inf.benchmark";
+pub const SIGNATURE_PROPERTY: &str = "golem.inf.benchmark.signature";
+
+const MIB: usize = 1024 * 1024;
+/// How long each CPU and memory test runs.
+const TEST_DURATION: Duration = Duration::from_secs(3);
+const CPU_BLOCK_SIZE: usize = 64 * 1024;
+const MEM_BUFFER_SIZE: usize = 64 * MIB;
+const DISK_FILE_SIZE: usize = 256 * MIB;
+const DISK_CHUNK_SIZE: usize = MIB;
+const NET_TRANSFER_LIMIT: usize = 256 * MIB;
+const NET_UPLOAD_SIZE: usize = 32 * MIB;
+const NET_TIMEOUT: Duration = Duration::from_secs(60);
+const SIGN_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_API_URL: &str = "http://127.0.0.1:7465";
+/// Delay before failed benchmarks are retried, unless the interval is shorter.
+const RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Configuration of host benchmarks.
+#[derive(StructOpt, Clone, Debug)]
+pub struct BenchmarkConfig {
+    /// Runs benchmarks and publishes their results in Offers
+    #[structopt(long, env, parse(try_from_str), default_value = "false")]
+    pub benchmark_enabled: bool,
+    #[structopt(long, env, parse(try_from_str = humantime::parse_duration), default_value = "7d")]
+    pub benchmark_interval: Duration,
+    /// Endpoint downloaded (GET) and uploaded to (POST) by the network test.
+    /// Network isn't benchmarked if not set.
+    #[structopt(long, env)]
+    pub benchmark_net_url: Option<url::Url>,
+}
+
+/// Scores measured on the host.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResults {
+    /// SHA-256 throughput of a single thread (MiB/s).
+    pub cpu_single: f64,
+    /// SHA-256 throughput of all threads (MiB/s).
+    pub cpu_multi: f64,
+    /// Memory copy throughput (MiB/s).
+    pub mem_bandwidth: f64,
+    /// Sequential disk read throughput (MiB/s).
+    pub disk_read: f64,
+    /// Sequential disk write throughput, including sync (MiB/s).
+    pub disk_write: f64,
+    /// Download throughput (Mbit/s).
+    pub net_down: Option<f64>,
+    /// Upload throughput (Mbit/s).
+    pub net_up: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl BenchmarkResults {
+    /// Offer properties of results in flat form, without the signature.
+    pub fn properties(&self) -> BTreeMap<String, Value> {
+        let mut properties = BTreeMap::new();
+        let mut set = |name: &str, value: Value| {
+            properties.insert(format!("{BENCHMARK_PROPERTY_PREFIX}.{name}"), value);
+        };
+        set("cpu.single-mib-s", round(self.cpu_single));
+        set("cpu.multi-mib-s", round(self.cpu_multi));
+        set("mem.bandwidth-mib-s", round(self.mem_bandwidth));
+        set("disk.read-mib-s", round(self.disk_read));
+        set("disk.write-mib-s", round(self.disk_write));
+        if let Some(net_down) = self.net_down {
+            set("net.down-mbit-s", round(net_down));
+        }
+        if let Some(net_up) = self.net_up {
+            set("net.up-mbit-s", round(net_up));
+        }
+        set("timestamp", Value::from(self.timestamp.timestamp_millis()));
+        properties
+    }
+
+    /// Digest of properties, which is signed.
+    pub fn digest(&self) -> anyhow::Result<Vec<u8>> {
+        let properties = serde_json::to_vec(&self.properties())?;
+        Ok(Sha256::digest(&properties).to_vec())
+    }
+}
+
+fn round(value: f64) -> Value {
+    Value::from((value * 100.0).round() / 100.0)
+}
+
+/// Results signed by the node.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedResults {
+    node_id: NodeId,
+    results: BenchmarkResults,
+    signature: String,
+}
+
+impl SignedResults {
+    fn properties(&self) -> BTreeMap<String, Value> {
+        let mut properties = self.results.properties();
+        properties.insert(
+            SIGNATURE_PROPERTY.to_string(),
+            Value::String(self.signature.clone()),
+        );
+        properties
+    }
+}
+
+/// Signs results with the identity of the app-key, through yagna REST API.
+#[derive(Clone)]
+pub struct Signer {
+    client: awc::Client,
+    url: String,
+}
+
+impl Signer {
+    pub fn new(app_key: &str) -> Self {
+        let api_url =
+            std::env::var("YAGNA_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        Signer {
+            client: awc::Client::builder()
+                .bearer_auth(app_key)
+                .timeout(SIGN_TIMEOUT)
+                .finish(),
+            url: format!("{}/me/sign", api_url.trim_end_matches('/')),
+        }
+    }
+
+    /// Hex encoded signature of `payload`.
+    async fn sign(&self, payload: &[u8]) -> anyhow::Result<String> {
+        let mut response = self
+            .client
+            .post(&self.url)
+            .send_json(&SignPayload {
+                data: hex::encode(payload),
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Signing request failed: {}", e))?;
+        if !response.status().is_success() {
+            anyhow::bail!("Signing failed with status {}", response.status());
+        }
+        let signature: SignPayload = response.json().await?;
+        Ok(signature.data)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignPayload {
+    data: String,
+}
+
+pub struct Manager {
+    config: BenchmarkConfig,
+    results_file: PathBuf,
+    work_dir: PathBuf,
+    node_id: NodeId,
+    signer: Signer,
+    state: Arc<Mutex<Option<SignedResults>>>,
+    sender: Option<watch::Sender<Event>>,
+    receiver: watch::Receiver<Event>,
+}
+
+impl Manager {
+    pub fn new(config: BenchmarkConfig, data_dir: &Path, node_id: NodeId, signer: Signer) -> Self {
+        let results_file = data_dir.join(crate::startup_config::BENCHMARK_JSON);
+        let results = match config.benchmark_enabled {
+            true => load_results(&results_file, node_id),
+            false => None,
+        };
+        let (tx, rx) = watch::channel(Event::Initialized);
+        Manager {
+            config,
+            results_file,
+            work_dir: data_dir.to_path_buf(),
+            node_id,
+            signer,
+            state: Arc::new(Mutex::new(results)),
+            sender: Some(tx),
+            receiver: rx,
+        }
+    }
+
+    /// Runs benchmarks on schedule. Offers are expected to be recreated on
+    /// `Event::BenchmarkChanged`.
+    pub fn spawn_scheduler(&mut self) {
+        if !self.config.benchmark_enabled {
+            return;
+        }
+        let tx = match self.sender.take() {
+            Some(tx) => tx,
+            None => return,
+        };
+
+        let config = self.config.clone();
+        let results_file = self.results_file.clone();
+        let work_dir = self.work_dir.clone();
+        let node_id = self.node_id;
+        let signer = self.signer.clone();
+        let state = self.state.clone();
+
+        tokio::task::spawn_local(async move {
+            loop {
+                let last = state
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map(|signed| signed.results.timestamp);
+                tokio::time::sleep(next_run(last, Utc::now(), config.benchmark_interval)).await;
+
+                log::info!("Running host benchmarks...");
+                let signed = match run(&config, &work_dir, node_id, &signer).await {
+                    Ok(signed) => signed,
+                    Err(e) => {
+                        log::warn!("Host benchmarks failed: {:?}", e);
+                        tokio::time::sleep(config.benchmark_interval.min(RETRY_DELAY)).await;
+                        continue;
+                    }
+                };
+                log::info!("Host benchmark results: {:?}", signed.results);
+
+                if let Err(e) = serde_json::to_string_pretty(&signed)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|json| Ok(results_file.swap_save(json)?))
+                {
+                    log::warn!("Failed to save benchmark results: {}", e);
+                }
+                *state.lock().unwrap() = Some(signed);
+                tx.send(Event::BenchmarkChanged).unwrap_or_default();
+            }
+        });
+    }
+
+    /// Signed properties of the latest results, if there are any.
+    pub fn properties(&self) -> BTreeMap<String, Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(SignedResults::properties)
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn event_receiver(&self) -> watch::Receiver<Event> {
+        self.receiver.clone()
+    }
+}
+
+fn load_results(path: &Path, node_id: NodeId) -> Option<SignedResults> {
+    if !path.exists() {
+        return None;
+    }
+    let signed = std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(serde_json::from_str::<SignedResults>(&json)?));
+    match signed {
+        // Results signed by other identity can't be published.
+        Ok(signed) if signed.node_id == node_id => Some(signed),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Failed to load benchmark results from {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Time until the next run, which is due `interval` after the last one.
+fn next_run(last: Option<DateTime<Utc>>, now: DateTime<Utc>, interval: Duration) -> Duration {
+    match last {
+        Some(last) => interval.saturating_sub((now - last).to_std().unwrap_or(Duration::ZERO)),
+        None => Duration::ZERO,
+    }
+}
+
+async fn run(
+    config: &BenchmarkConfig,
+    work_dir: &Path,
+    node_id: NodeId,
+    signer: &Signer,
+) -> anyhow::Result<SignedResults> {
+    let work_dir = work_dir.to_path_buf();
+    let (cpu_single, cpu_multi, mem_bandwidth, (disk_read, disk_write)) =
+        tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            Ok((
+                cpu_throughput(1),
+                cpu_throughput(num_cpus::get()),
+                mem_bandwidth(),
+                disk_throughput(&work_dir)?,
+            ))
+        })
+        .await??;
+
+    let (net_down, net_up) = match &config.benchmark_net_url {
+        Some(url) => match net_throughput(url).await {
+            Ok((down, up)) => (Some(down), Some(up)),
+            Err(e) => {
+                log::warn!("Network benchmark failed: {}", e);
+                (None, None)
+            }
+        },
+        None => (None, None),
+    };
+
+    let results = BenchmarkResults {
+        cpu_single,
+        cpu_multi,
+        mem_bandwidth,
+        disk_read,
+        disk_write,
+        net_down,
+        net_up,
+        timestamp: Utc::now(),
+    };
+    let signature = signer.sign(&results.digest()?).await?;
+
+    Ok(SignedResults {
+        node_id,
+        results,
+        signature,
+    })
+}
+
+fn mib_per_sec(bytes: usize, elapsed: Duration) -> f64 {
+    bytes as f64 / MIB as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+/// SHA-256 throughput of given number of threads hashing concurrently.
+fn cpu_throughput(threads: usize) -> f64 {
+    let started = Instant::now();
+    let handles = (0..threads.max(1))
+        .map(|_| {
+            std::thread::spawn(|| {
+                let block = vec![0x5au8; CPU_BLOCK_SIZE];
+                let started = Instant::now();
+                let mut hashed = 0;
+                while started.elapsed() < TEST_DURATION {
+                    std::hint::black_box(Sha256::digest(&block));
+                    hashed += block.len();
+                }
+                hashed
+            })
+        })
+        .collect::<Vec<_>>();
+    let hashed = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap_or(0))
+        .sum();
+    mib_per_sec(hashed, started.elapsed())
+}
+
+fn mem_bandwidth() -> f64 {
+    let src = vec![0xa5u8; MEM_BUFFER_SIZE];
+    let mut dst = vec![0u8; MEM_BUFFER_SIZE];
+    let started = Instant::now();
+    let mut copied = 0;
+    while started.elapsed() < TEST_DURATION {
+        dst.copy_from_slice(std::hint::black_box(&src));
+        std::hint::black_box(&mut dst);
+        copied += MEM_BUFFER_SIZE;
+    }
+    mib_per_sec(copied, started.elapsed())
+}
+
+/// Sequential read and write throughput of a file in `dir`.
+fn disk_throughput(dir: &Path) -> anyhow::Result<(f64, f64)> {
+    let path = dir.join("benchmark.tmp");
+    let chunk = vec![0x3cu8; DISK_CHUNK_SIZE];
+
+    let result = (|| -> anyhow::Result<(f64, f64)> {
+        let started = Instant::now();
+        let mut file = std::fs::File::create(&path)?;
+        for _ in 0..DISK_FILE_SIZE / DISK_CHUNK_SIZE {
+            file.write_all(&chunk)?;
+        }
+        file.sync_all()?;
+        let write = mib_per_sec(DISK_FILE_SIZE, started.elapsed());
+        drop(file);
+
+        let started = Instant::now();
+        let mut file = std::fs::File::open(&path)?;
+        let mut buf = vec![0u8; DISK_CHUNK_SIZE];
+        let mut read = 0;
+        loop {
+            match file.read(&mut buf)? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        Ok((mib_per_sec(read, started.elapsed()), write))
+    })();
+
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Download and upload throughput in Mbit/s.
+async fn net_throughput(url: &url::Url) -> anyhow::Result<(f64, f64)> {
+    let client = awc::Client::builder().timeout(NET_TIMEOUT).finish();
+    let to_mbit = |bytes: usize, elapsed: Duration| {
+        (bytes * 8) as f64 / 1_000_000.0 / elapsed.as_secs_f64().max(f64::EPSILON)
+    };
+
+    let started = Instant::now();
+    let downloaded = client
+        .get(url.as_str())
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Download failed: {}", e))?
+        .body()
+        .limit(NET_TRANSFER_LIMIT)
+        .await?
+        .len();
+    let down = to_mbit(downloaded, started.elapsed());
+
+    let started = Instant::now();
+    let response = client
+        .post(url.as_str())
+        .send_body(vec![0u8; NET_UPLOAD_SIZE])
+        .await
+        .map_err(|e| anyhow::anyhow!("Upload failed: {}", e))?;
+    if !response.status().is_success() {
+        anyhow::bail!("Upload failed with status {}", response.status());
+    }
+    let up = to_mbit(NET_UPLOAD_SIZE, started.elapsed());
+
+    Ok((down, up))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> BenchmarkResults {
+        BenchmarkResults {
+            cpu_single: 512.123,
+            cpu_multi: 4096.0,
+            mem_bandwidth: 10240.0,
+            disk_read: 2000.0,
+            disk_write: 500.0,
+            net_down: Some(100.0),
+            net_up: None,
+            timestamp: DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_properties() {
+        let properties = results().properties();
+        assert_eq!(
+            properties.get("golem.inf.benchmark.cpu.single-mib-s"),
+            Some(&Value::from(512.12))
+        );
+        assert_eq!(
+            properties.get("golem.inf.benchmark.net.down-mbit-s"),
+            Some(&Value::from(100.0))
+        );
+        assert!(!properties.contains_key("golem.inf.benchmark.net.up-mbit-s"));
+        assert_eq!(
+            properties.get("golem.inf.benchmark.timestamp"),
+            Some(&Value::from(1704067200000i64))
+        );
+        assert_eq!(results().digest().unwrap(), results().digest().unwrap());
+    }
+
+    #[test]
+    fn test_next_run() {
+        let now = Utc::now();
+        let day = Duration::from_secs(24 * 3600);
+
+        assert_eq!(next_run(None, now, day), Duration::ZERO);
+        assert_eq!(
+            next_run(Some(now - chrono::Duration::days(2)), now, day),
+            Duration::ZERO
+        );
+        assert_eq!(
+            next_run(Some(now - chrono::Duration::hours(12)), now, day),
+            Duration::from_secs(12 * 3600)
+        );
+    }
+}
@@ -5,6 +5,7 @@ pub mod exe_unit;
 pub mod keystore;
 pub mod pre_install;
 pub mod preset;
+pub mod preset_wizard;
 pub mod profile;
 pub mod rule;
 pub mod whitelist;
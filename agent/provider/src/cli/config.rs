@@ -1,4 +1,9 @@
+use crate::cli::println_conditional;
 use crate::config::globals::GlobalsState;
+use crate::config::profiles::{ConfigProfile, ConfigProfiles};
+use crate::config::validate::{validate, Severity};
+use crate::hardware::Profiles;
+use crate::market::PresetManager;
 use crate::startup_config::{NodeConfig, ProviderConfig};
 use structopt::StructOpt;
 
@@ -9,6 +14,24 @@ pub enum ConfigConfig {
         name: Option<String>,
     },
     Set(NodeConfig),
+    /// List config profiles
+    Profiles,
+    /// Create a config profile with overrides layered on the base config
+    CreateProfile {
+        name: String,
+        #[structopt(flatten)]
+        profile: ConfigProfile,
+    },
+    /// Remove a config profile
+    RemoveProfile {
+        name: String,
+    },
+    /// Switch to a config profile ('base' restores the base config)
+    UseProfile {
+        name: String,
+    },
+    /// Check presets, runtimes, hardware profiles and rules before starting the agent
+    Validate,
 }
 
 impl ConfigConfig {
@@ -20,12 +43,30 @@ impl ConfigConfig {
                 state.update_and_save(node_config, &config.globals_file)?;
                 Ok(())
             }
+            ConfigConfig::Profiles => {
+                let profiles = ConfigProfiles::load(&config.config_profiles_file)?;
+                println!("{}", serde_json::to_string_pretty(&profiles)?);
+                Ok(())
+            }
+            ConfigConfig::CreateProfile { name, profile } => {
+                let mut profiles = ConfigProfiles::load_or_create(&config.config_profiles_file)?;
+                profiles.add(name, profile)?;
+                profiles.save(&config.config_profiles_file)
+            }
+            ConfigConfig::RemoveProfile { name } => {
+                let mut profiles = ConfigProfiles::load_or_create(&config.config_profiles_file)?;
+                profiles.remove(&name)?;
+                profiles.save(&config.config_profiles_file)
+            }
+            ConfigConfig::UseProfile { name } => use_profile(config, name),
+            ConfigConfig::Validate => config_validate(config),
         }
     }
 }
 
 pub fn config_get(config: ProviderConfig, name: Option<String>) -> anyhow::Result<()> {
-    let globals_state = GlobalsState::load(&config.globals_file)?;
+    let globals_state =
+        GlobalsState::load_effective(&config.globals_file, &config.config_profiles_file)?;
     match name {
         None => {
             if config.json {
@@ -48,3 +89,56 @@ pub fn config_get(config: ProviderConfig, name: Option<String>) -> anyhow::Resul
     }
     Ok(())
 }
+
+/// Activates config profile together with hardware profile and presets it refers to.
+/// Node name, subnet and account overrides are applied by the agent when loading globals.
+fn use_profile(config: ProviderConfig, name: String) -> anyhow::Result<()> {
+    let mut profiles = ConfigProfiles::load_or_create(&config.config_profiles_file)?;
+    profiles.set_active(&name)?;
+
+    if let Some(profile) = profiles.active_profile()? {
+        if let Some(hw_profile) = &profile.hardware_profile {
+            let mut hardware = Profiles::load_or_create(&config)?;
+            hardware.set_active(hw_profile)?;
+            hardware.save(&config.hardware_file)?;
+        }
+        if let Some(preset_names) = &profile.presets {
+            let mut presets = PresetManager::load_or_create(&config.presets_file)?;
+            presets.list_matching(preset_names)?;
+            for active in presets.active() {
+                presets.deactivate(&active)?;
+            }
+            for preset in preset_names {
+                presets.activate(preset)?;
+            }
+            presets.save_to_file(&config.presets_file)?;
+        }
+    }
+
+    profiles.save(&config.config_profiles_file)?;
+    println_conditional(&config, &format!("Using config profile: {name}"));
+    Ok(())
+}
+
+fn config_validate(config: ProviderConfig) -> anyhow::Result<()> {
+    let diagnostics = validate(&config);
+
+    if config.json {
+        println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+    } else if diagnostics.is_empty() {
+        println!("Configuration is valid.");
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{diagnostic}");
+        }
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        anyhow::bail!("Configuration has {errors} error(s).");
+    }
+    Ok(())
+}
@@ -4,6 +4,7 @@ use anyhow::{anyhow, bail, Result};
 use dialoguer::{Input, Select};
 use structopt::StructOpt;
 
+use crate::cli::preset_wizard::{self, WizardParams};
 use crate::market::{Preset, PresetManager};
 use crate::startup_config::{PresetNoInteractive, ProviderConfig, UpdateNames};
 
@@ -21,6 +22,11 @@ pub enum PresetsConfig {
         #[structopt(flatten)]
         params: PresetNoInteractive,
     },
+    /// Create a preset with prices proposed for detected hardware
+    Wizard {
+        #[structopt(flatten)]
+        params: WizardParams,
+    },
     /// Remove a preset
     Remove { name: String },
     /// Update a preset
@@ -53,6 +59,7 @@ impl PresetsConfig {
                     create_interactive(config)
                 }
             }
+            PresetsConfig::Wizard { params } => preset_wizard::run(config, params),
             PresetsConfig::Remove { name } => remove_preset(config, name),
             PresetsConfig::Update {
                 no_interactive,
@@ -93,6 +100,14 @@ impl PresetUpdater {
         }
     }
 
+    pub fn preset_mut(&mut self) -> &mut Preset {
+        &mut self.preset
+    }
+
+    pub fn into_preset(self) -> Preset {
+        self.preset
+    }
+
     pub fn update_exeunit(&mut self) -> Result<()> {
         let prev_exeunit = self
             .exeunits
@@ -217,6 +232,7 @@ pub fn create(config: ProviderConfig, params: PresetNoInteractive) -> anyhow::Re
             .exe_unit
             .ok_or_else(|| anyhow!("ExeUnit is required."))?,
         pricing_model: params.pricing.unwrap_or_else(|| "linear".to_string()),
+        variants: params.variants.unwrap_or_default(),
         ..Default::default()
     };
 
@@ -315,6 +331,9 @@ fn update_presets(
             if let Some(new_pricing_model) = params.pricing {
                 preset.pricing_model = new_pricing_model;
             }
+            if let Some(variants) = params.variants.clone() {
+                preset.variants = variants;
+            }
             let exe_unit_desc = registry.find_exeunit(&preset.exeunit_name)?;
 
             for (name, price) in params.price.iter() {
@@ -346,7 +365,7 @@ fn remove_preset(config: ProviderConfig, name: String) -> anyhow::Result<()> {
     presets.save_to_file(&config.presets_file)
 }
 
-fn validate_preset(config: &ProviderConfig, preset: &Preset) -> anyhow::Result<()> {
+pub(crate) fn validate_preset(config: &ProviderConfig, preset: &Preset) -> anyhow::Result<()> {
     // Validate ExeUnit existence and pricing model.
     let registry = config.registry()?;
     registry.find_exeunit(&preset.exeunit_name)?;
@@ -354,6 +373,9 @@ fn validate_preset(config: &ProviderConfig, preset: &Preset) -> anyhow::Result<(
     if preset.pricing_model != "linear" {
         bail!("Not supported pricing model.")
     }
+    if preset.variants.iter().any(|threads| *threads <= 0) {
+        bail!("Variants must have positive number of CPU threads.")
+    }
 
     Ok(())
 }
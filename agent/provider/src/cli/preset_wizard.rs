@@ -0,0 +1,274 @@
+//! Guided preset creation.
+//!
+//! Wizard probes hardware offered by the active profile and GPU, proposes prices
+//! from the reference pricing table below and writes validated preset. Prices can be
+//! adjusted interactively before saving.
+use anyhow::{anyhow, bail};
+use dialoguer::Confirmation;
+use serde::Serialize;
+use std::process::Command;
+use structopt::StructOpt;
+
+use crate::cli::preset::{validate_preset, PresetUpdater};
+use crate::hardware::{Profiles, Resources};
+use crate::market::{Preset, PresetManager};
+use crate::startup_config::ProviderConfig;
+
+const SECONDS_PER_HOUR: f64 = 3600.;
+
+/// Reference prices in GLM per hour. Bigger machines get lower price per thread.
+struct PricingTier {
+    max_threads: i32,
+    cpu_per_hour: f64,
+    env_per_hour: f64,
+}
+
+const REFERENCE_PRICING: [PricingTier; 3] = [
+    PricingTier {
+        max_threads: 4,
+        cpu_per_hour: 0.1,
+        env_per_hour: 0.02,
+    },
+    PricingTier {
+        max_threads: 16,
+        cpu_per_hour: 0.08,
+        env_per_hour: 0.015,
+    },
+    PricingTier {
+        max_threads: i32::MAX,
+        cpu_per_hour: 0.06,
+        env_per_hour: 0.01,
+    },
+];
+/// Memory above this amount per thread makes CPU time more valuable.
+const HIGH_MEM_PER_THREAD_GIB: f64 = 4.;
+const HIGH_MEM_MULTIPLIER: f64 = 1.2;
+/// Added to environment price per hour for each detected GPU.
+const GPU_ENV_PER_HOUR: f64 = 0.5;
+const START_PRICE: f64 = 0.;
+
+#[derive(StructOpt, Clone, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct WizardParams {
+    /// Name of created preset. Defaults to ExeUnit name.
+    #[structopt(long)]
+    pub preset_name: Option<String>,
+    /// ExeUnit, which the preset is created for. Asked for, if not set.
+    #[structopt(long)]
+    pub exe_unit: Option<String>,
+    /// Save proposed preset without asking.
+    #[structopt(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DetectedHardware {
+    #[serde(flatten)]
+    pub resources: Resources,
+    pub gpus: Vec<String>,
+}
+
+impl DetectedHardware {
+    pub fn probe(config: &ProviderConfig) -> anyhow::Result<Self> {
+        let profiles = Profiles::load_or_create(config)?;
+        let resources = *profiles
+            .get(profiles.active())
+            .ok_or_else(|| anyhow!("Active hardware profile not found"))?;
+
+        Ok(DetectedHardware {
+            resources,
+            gpus: detect_gpus(),
+        })
+    }
+}
+
+/// Names of NVIDIA GPUs. Empty, if there are none or drivers aren't installed.
+fn detect_gpus() -> Vec<String> {
+    match Command::new("nvidia-smi")
+        .args(["--query-gpu=name", "--format=csv,noheader"])
+        .output()
+    {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(ToString::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Proposed prices in GLM per hour.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProposedPricing {
+    pub cpu_per_hour: f64,
+    pub env_per_hour: f64,
+    pub start_price: f64,
+}
+
+impl ProposedPricing {
+    pub fn from_hardware(hardware: &DetectedHardware) -> Self {
+        let threads = hardware.resources.cpu_threads.max(1);
+        let tier = REFERENCE_PRICING
+            .iter()
+            .find(|tier| threads <= tier.max_threads)
+            .unwrap_or(&REFERENCE_PRICING[REFERENCE_PRICING.len() - 1]);
+
+        let mut cpu_per_hour = tier.cpu_per_hour;
+        if hardware.resources.mem_gib / threads as f64 > HIGH_MEM_PER_THREAD_GIB {
+            cpu_per_hour *= HIGH_MEM_MULTIPLIER;
+        }
+
+        ProposedPricing {
+            cpu_per_hour,
+            env_per_hour: tier.env_per_hour + GPU_ENV_PER_HOUR * hardware.gpus.len() as f64,
+            start_price: START_PRICE,
+        }
+    }
+
+    /// Fills linear pricing coefficients of ExeUnit, which are priced.
+    fn apply(&self, preset: &mut Preset, config: &ProviderConfig) -> anyhow::Result<()> {
+        let registry = config.registry()?;
+        let exe_unit_desc = registry.find_exeunit(&preset.exeunit_name)?;
+
+        preset.initial_price = self.start_price;
+        for (prop_name, counter) in exe_unit_desc.coefficients() {
+            if !counter.price {
+                continue;
+            }
+            let per_hour = match counter.name.as_str() {
+                "cpu" => self.cpu_per_hour,
+                "duration" => self.env_per_hour,
+                _ => 0.,
+            };
+            preset
+                .usage_coeffs
+                .insert(prop_name, per_hour / SECONDS_PER_HOUR);
+        }
+        Ok(())
+    }
+}
+
+pub fn run(config: ProviderConfig, params: WizardParams) -> anyhow::Result<()> {
+    if config.json && !params.yes {
+        bail!("json output is supported only with --yes");
+    }
+
+    let mut presets = PresetManager::load_or_create(&config.presets_file)?;
+    let registry = config.registry()?;
+
+    let hardware = DetectedHardware::probe(&config)?;
+    let pricing = ProposedPricing::from_hardware(&hardware);
+    if !config.json {
+        print_proposal(&hardware, &pricing);
+    }
+
+    let exeunits: Vec<String> = registry.list().into_iter().map(|desc| desc.name).collect();
+    let mut preset = Preset {
+        exeunit_name: params
+            .exe_unit
+            .clone()
+            .or_else(|| exeunits.first().cloned())
+            .ok_or_else(|| anyhow!("No ExeUnits installed"))?,
+        pricing_model: "linear".to_string(),
+        ..Default::default()
+    };
+
+    let preset = if params.yes {
+        preset.name = params
+            .preset_name
+            .unwrap_or_else(|| preset.exeunit_name.clone());
+        pricing.apply(&mut preset, &config)?;
+        preset
+    } else {
+        preset.name = params.preset_name.unwrap_or_default();
+        let mut updater = PresetUpdater::new(preset, exeunits, vec!["linear".to_string()]);
+        if params.exe_unit.is_none() {
+            updater.update_exeunit()?;
+        }
+        updater.update_name()?;
+        pricing.apply(updater.preset_mut(), &config)?;
+        updater.update_metrics(&config)?;
+
+        let preset = updater.into_preset();
+        println!();
+        println!("{}", preset.display(&registry));
+        if !Confirmation::new()
+            .with_text("Save preset?")
+            .default(true)
+            .interact()?
+        {
+            bail!("Preset not saved");
+        }
+        preset
+    };
+
+    validate_preset(&config, &preset)?;
+    presets.add_preset(preset.clone())?;
+    presets.save_to_file(&config.presets_file)?;
+
+    if config.json {
+        println!("{}", serde_json::to_string_pretty(&preset)?);
+    } else {
+        println!();
+        println!("Preset created:");
+        println!("{}", preset.display(&registry));
+    }
+    Ok(())
+}
+
+fn print_proposal(hardware: &DetectedHardware, pricing: &ProposedPricing) {
+    let resources = &hardware.resources;
+    println!("Detected hardware (active profile):");
+    println!("  CPU threads: {}", resources.cpu_threads);
+    println!("  Memory:      {:.2} GiB", resources.mem_gib);
+    println!("  Storage:     {:.2} GiB", resources.storage_gib);
+    match hardware.gpus.is_empty() {
+        true => println!("  GPU:         none"),
+        false => println!("  GPU:         {}", hardware.gpus.join(", ")),
+    }
+    println!();
+    println!("Proposed prices:");
+    println!("  CPU:         {:.4} GLM/h", pricing.cpu_per_hour);
+    println!("  Duration:    {:.4} GLM/h", pricing.env_per_hour);
+    println!("  Start price: {:.4} GLM", pricing.start_price);
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hardware(cpu_threads: i32, mem_gib: f64, gpus: usize) -> DetectedHardware {
+        DetectedHardware {
+            resources: Resources {
+                cpu_threads,
+                mem_gib,
+                storage_gib: 100.,
+            },
+            gpus: vec!["GPU".to_string(); gpus],
+        }
+    }
+
+    #[test]
+    fn pricing_follows_reference_table() {
+        let small = ProposedPricing::from_hardware(&hardware(2, 4., 0));
+        assert_eq!(small.cpu_per_hour, 0.1);
+        assert_eq!(small.env_per_hour, 0.02);
+
+        let large = ProposedPricing::from_hardware(&hardware(32, 64., 0));
+        assert_eq!(large.cpu_per_hour, 0.06);
+        assert_eq!(large.env_per_hour, 0.01);
+    }
+
+    #[test]
+    fn pricing_adjustments() {
+        let high_mem = ProposedPricing::from_hardware(&hardware(4, 32., 0));
+        assert!((high_mem.cpu_per_hour - 0.12).abs() < 1e-9);
+
+        let gpu = ProposedPricing::from_hardware(&hardware(8, 16., 2));
+        assert!((gpu.env_per_hour - 1.015).abs() < 1e-9);
+    }
+}
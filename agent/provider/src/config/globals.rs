@@ -1,3 +1,4 @@
+use crate::config::profiles::ConfigProfiles;
 use crate::startup_config::NodeConfig;
 use std::path::Path;
 use ya_client::model::NodeId;
@@ -78,6 +79,15 @@ impl GlobalsState {
         }
     }
 
+    /// Loads base state with overrides of the active config profile applied.
+    pub fn load_effective(path: &Path, profiles_path: &Path) -> anyhow::Result<Self> {
+        let mut state = Self::load(path)?;
+        if let Some(profile) = ConfigProfiles::load(profiles_path)?.active_profile()? {
+            profile.apply(&mut state);
+        }
+        Ok(state)
+    }
+
     pub fn load_or_create(path: &Path) -> anyhow::Result<Self> {
         if path.exists() {
             Self::load(path)
@@ -92,6 +102,19 @@ impl GlobalsState {
         }
     }
 
+    /// Overrides values with ones given explicitly on the command line.
+    pub fn apply_cli(&mut self, node_config: &NodeConfig) {
+        if node_config.node_name.is_some() {
+            self.node_name.clone_from(&node_config.node_name);
+        }
+        if node_config.subnet.is_some() {
+            self.subnet.clone_from(&node_config.subnet);
+        }
+        if node_config.account.account.is_some() {
+            self.account = node_config.account.account;
+        }
+    }
+
     pub fn update_and_save(&mut self, node_config: NodeConfig, path: &Path) -> anyhow::Result<()> {
         if node_config.node_name.is_some() {
             self.node_name = node_config.node_name;
@@ -188,4 +211,23 @@ mod test {
         assert_eq!(g.subnet, Some("community.4".into()));
         assert!(g.account.is_none())
     }
+
+    #[test]
+    fn cli_overrides_profile() {
+        use crate::config::profiles::ConfigProfile;
+        use structopt::StructOpt;
+
+        let mut g: GlobalsState = serde_json::from_str(GLOBALS_JSON_ALPHA_4).unwrap();
+        let profile = ConfigProfile {
+            node_name: Some("profile-name".into()),
+            subnet: Some("profile-subnet".into()),
+            ..Default::default()
+        };
+        let cli = NodeConfig::from_iter(&["provider", "--subnet", "cli-subnet"]);
+
+        profile.apply(&mut g);
+        g.apply_cli(&cli);
+        assert_eq!(g.node_name, Some("profile-name".into()));
+        assert_eq!(g.subnet, Some("cli-subnet".into()));
+    }
 }
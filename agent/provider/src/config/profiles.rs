@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+use ya_client::model::NodeId;
+use ya_utils_path::SwapSave;
+
+use crate::config::globals::GlobalsState;
+
+pub(crate) const CONFIG_PROFILES_JSON: &str = "config_profiles.json";
+/// Reserved name selecting plain base configuration without any overrides.
+pub const BASE_PROFILE_NAME: &str = "base";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigProfileError {
+    #[error("unknown config profile: '{0}'")]
+    Unknown(String),
+    #[error("config profile already exists: '{0}'")]
+    AlreadyExists(String),
+    #[error("config profile name '{0}' is reserved")]
+    Reserved(String),
+    #[error("config profile is active: '{0}'")]
+    Active(String),
+    #[error("config profile '{0}' is inherited by '{1}'")]
+    Inherited(String, String),
+    #[error("config profile inheritance cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Overrides layered on top of the base configuration.
+/// Fields left empty are inherited from the parent profile or base config.
+#[derive(StructOpt, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ConfigProfile {
+    /// Profile to inherit unset values from
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherits: Option<String>,
+    /// Node name override
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// Subnet override
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<String>,
+    /// Payment account override
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<NodeId>,
+    /// Hardware profile to activate
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardware_profile: Option<String>,
+    /// Presets to activate, replacing currently active ones
+    #[structopt(long = "preset")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presets: Option<Vec<String>>,
+}
+
+impl ConfigProfile {
+    /// Values set in `self` take precedence over values from `parent`.
+    fn merge(self, parent: ConfigProfile) -> ConfigProfile {
+        ConfigProfile {
+            inherits: parent.inherits,
+            node_name: self.node_name.or(parent.node_name),
+            subnet: self.subnet.or(parent.subnet),
+            account: self.account.or(parent.account),
+            hardware_profile: self.hardware_profile.or(parent.hardware_profile),
+            presets: self.presets.or(parent.presets),
+        }
+    }
+
+    pub fn apply(&self, globals: &mut GlobalsState) {
+        if self.node_name.is_some() {
+            globals.node_name.clone_from(&self.node_name);
+        }
+        if self.subnet.is_some() {
+            globals.subnet.clone_from(&self.subnet);
+        }
+        if self.account.is_some() {
+            globals.account = self.account;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConfigProfiles {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ConfigProfile>,
+}
+
+impl ConfigProfiles {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            log::debug!("Loading config profiles from: {}", path.display());
+            Ok(serde_json::from_reader(io::BufReader::new(
+                fs::OpenOptions::new().read(true).open(path)?,
+            ))?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn load_or_create(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let profiles = Self::default();
+            profiles.save(path)?;
+            Ok(profiles)
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        Ok(path.swap_save(serde_json::to_string_pretty(self)?)?)
+    }
+
+    pub fn add(&mut self, name: String, profile: ConfigProfile) -> Result<(), ConfigProfileError> {
+        if name == BASE_PROFILE_NAME {
+            return Err(ConfigProfileError::Reserved(name));
+        }
+        if self.profiles.contains_key(&name) {
+            return Err(ConfigProfileError::AlreadyExists(name));
+        }
+        self.profiles.insert(name.clone(), profile);
+
+        if let Err(e) = self.resolve(&name) {
+            self.profiles.remove(&name);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), ConfigProfileError> {
+        if self.active.as_deref() == Some(name) {
+            return Err(ConfigProfileError::Active(name.to_string()));
+        }
+        if let Some((child, _)) = self
+            .profiles
+            .iter()
+            .find(|(_, profile)| profile.inherits.as_deref() == Some(name))
+        {
+            return Err(ConfigProfileError::Inherited(
+                name.to_string(),
+                child.clone(),
+            ));
+        }
+        self.profiles
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| ConfigProfileError::Unknown(name.to_string()))
+    }
+
+    pub fn set_active(&mut self, name: &str) -> Result<(), ConfigProfileError> {
+        if name == BASE_PROFILE_NAME {
+            self.active = None;
+            return Ok(());
+        }
+        self.resolve(name)?;
+        self.active = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Flattens inheritance chain of profile `name` into single set of overrides.
+    pub fn resolve(&self, name: &str) -> Result<ConfigProfile, ConfigProfileError> {
+        let mut chain = vec![name.to_string()];
+        let mut resolved = self
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ConfigProfileError::Unknown(name.to_string()))?;
+
+        while let Some(parent_name) = resolved.inherits.clone() {
+            if chain.contains(&parent_name) {
+                chain.push(parent_name);
+                return Err(ConfigProfileError::Cycle(chain));
+            }
+            let parent = self
+                .profiles
+                .get(&parent_name)
+                .cloned()
+                .ok_or_else(|| ConfigProfileError::Unknown(parent_name.clone()))?;
+            chain.push(parent_name);
+            resolved = resolved.merge(parent);
+        }
+        Ok(resolved)
+    }
+
+    /// Resolved overrides of the active profile, if any.
+    pub fn active_profile(&self) -> Result<Option<ConfigProfile>, ConfigProfileError> {
+        self.active
+            .as_ref()
+            .map(|name| self.resolve(name))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn profile(
+        inherits: Option<&str>,
+        subnet: Option<&str>,
+        node_name: Option<&str>,
+    ) -> ConfigProfile {
+        ConfigProfile {
+            inherits: inherits.map(str::to_string),
+            subnet: subnet.map(str::to_string),
+            node_name: node_name.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolve_inherited_profile() {
+        let mut profiles = ConfigProfiles::default();
+        profiles
+            .add(
+                "testnet".into(),
+                profile(None, Some("testnet"), Some("node")),
+            )
+            .unwrap();
+        profiles
+            .add("dev".into(), profile(Some("testnet"), Some("dev"), None))
+            .unwrap();
+
+        let resolved = profiles.resolve("dev").unwrap();
+        assert_eq!(resolved.subnet, Some("dev".into()));
+        assert_eq!(resolved.node_name, Some("node".into()));
+        assert_eq!(resolved.inherits, None);
+
+        let mut globals = GlobalsState::default();
+        resolved.apply(&mut globals);
+        assert_eq!(globals.subnet, Some("dev".into()));
+        assert_eq!(globals.node_name, Some("node".into()));
+    }
+
+    #[test]
+    fn reject_invalid_inheritance() {
+        let mut profiles = ConfigProfiles::default();
+        assert!(matches!(
+            profiles.add("dev".into(), profile(Some("missing"), None, None)),
+            Err(ConfigProfileError::Unknown(_))
+        ));
+        assert!(matches!(
+            profiles.add(BASE_PROFILE_NAME.into(), profile(None, None, None)),
+            Err(ConfigProfileError::Reserved(_))
+        ));
+
+        profiles
+            .profiles
+            .insert("a".into(), profile(Some("b"), None, None));
+        profiles
+            .profiles
+            .insert("b".into(), profile(Some("a"), None, None));
+        assert!(matches!(
+            profiles.set_active("a"),
+            Err(ConfigProfileError::Cycle(_))
+        ));
+        assert!(profiles.active.is_none());
+    }
+}
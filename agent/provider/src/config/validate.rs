@@ -0,0 +1,435 @@
+//! Offline consistency checks of provider configuration files.
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::BufReader;
+
+use serde::Serialize;
+
+use ya_manifest_utils::CompositeKeystore;
+
+use crate::config::globals::GlobalsState;
+use crate::config::presets::Presets;
+use crate::config::profiles::{ConfigProfiles, BASE_PROFILE_NAME};
+use crate::execution::ExeUnitsRegistry;
+use crate::hardware::{Profiles, Resources};
+use crate::rules::store::RulesConfig;
+use crate::startup_config::ProviderConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, derive_more::Display)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[display(fmt = "ERROR")]
+    Error,
+    #[display(fmt = "WARN")]
+    Warning,
+}
+
+/// Single problem found in configuration, with a hint how to fix it.
+#[derive(Clone, Debug, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub source: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.severity, self.source, self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n        hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn error(&mut self, source: &'static str, message: impl ToString, hint: Option<String>) {
+        self.push(Severity::Error, source, message, hint)
+    }
+
+    fn warn(&mut self, source: &'static str, message: impl ToString, hint: Option<String>) {
+        self.push(Severity::Warning, source, message, hint)
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        source: &'static str,
+        message: impl ToString,
+        hint: Option<String>,
+    ) {
+        self.0.push(Diagnostic {
+            severity,
+            source,
+            message: message.to_string(),
+            hint,
+        })
+    }
+}
+
+/// Checks globals, config profiles, runtimes, presets, hardware profiles and rules
+/// against each other. Doesn't modify any configuration file.
+pub fn validate(config: &ProviderConfig) -> Vec<Diagnostic> {
+    let mut diag = Diagnostics::default();
+
+    if let Err(e) = GlobalsState::load(&config.globals_file) {
+        diag.error(
+            "globals",
+            format!("Can't load {}: {}", config.globals_file.display(), e),
+            Some("Fix the file or set values again with `ya-provider config set`".into()),
+        );
+    }
+
+    let registry = validate_runtimes(config, &mut diag);
+    let presets = validate_presets(config, registry.as_ref(), &mut diag);
+    let hardware = validate_hardware(config, &mut diag);
+    validate_profiles(config, presets.as_ref(), hardware.as_ref(), &mut diag);
+    validate_rules(config, &mut diag);
+
+    diag.0
+}
+
+fn validate_runtimes(config: &ProviderConfig, diag: &mut Diagnostics) -> Option<ExeUnitsRegistry> {
+    let registry = match config.registry() {
+        Ok(registry) => registry,
+        Err(e) => {
+            diag.error(
+                "runtimes",
+                format!("Can't load ExeUnit descriptors: {}", e),
+                Some("Check --exe-unit-path (EXE_UNIT_PATH) points to runtime descriptors".into()),
+            );
+            return None;
+        }
+    };
+
+    if registry.list().is_empty() {
+        diag.error(
+            "runtimes",
+            format!("No runtimes found in {}", config.exe_unit_path.display()),
+            Some("Install at least one runtime or fix --exe-unit-path".into()),
+        );
+    }
+    for desc in registry.list() {
+        if let Err(e) = desc.validate() {
+            diag.error(
+                "runtimes",
+                e,
+                Some(format!("Reinstall runtime [{}]", desc.name)),
+            );
+        }
+    }
+    Some(registry)
+}
+
+fn validate_presets(
+    config: &ProviderConfig,
+    registry: Option<&ExeUnitsRegistry>,
+    diag: &mut Diagnostics,
+) -> Option<Presets> {
+    if !config.presets_file.exists() {
+        diag.warn(
+            "presets",
+            "Presets file doesn't exist, default presets will be created on start",
+            None,
+        );
+        return None;
+    }
+
+    let presets = match Presets::load_from_file(&config.presets_file) {
+        Ok(presets) => presets,
+        Err(e) => {
+            diag.error(
+                "presets",
+                e,
+                Some("Fix the file or recreate presets with `ya-provider preset create`".into()),
+            );
+            return None;
+        }
+    };
+
+    if presets.active.is_empty() {
+        diag.error(
+            "presets",
+            "No active presets, no Offers will be published",
+            Some("Activate preset with `ya-provider preset activate <name>`".into()),
+        );
+    }
+
+    for preset in presets.presets.values() {
+        // Problems with inactive presets don't prevent agent from starting.
+        let severity = if presets.active.contains(&preset.name) {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+
+        if preset.pricing_model != "linear" {
+            diag.push(
+                severity,
+                "presets",
+                format!(
+                    "Preset [{}] uses unsupported pricing model [{}]",
+                    preset.name, preset.pricing_model
+                ),
+                Some(format!(
+                    "Run `ya-provider preset update --name {}`",
+                    preset.name
+                )),
+            );
+        }
+        if preset.initial_price < 0.0 || preset.usage_coeffs.values().any(|price| *price < 0.0) {
+            diag.push(
+                severity,
+                "presets",
+                format!("Preset [{}] has negative prices", preset.name),
+                Some(format!(
+                    "Run `ya-provider preset update --name {}`",
+                    preset.name
+                )),
+            );
+        }
+
+        let registry = match registry {
+            Some(registry) => registry,
+            None => continue,
+        };
+        let desc = match registry.find_exeunit(&preset.exeunit_name) {
+            Ok(desc) => desc,
+            Err(e) => {
+                diag.push(
+                    severity,
+                    "presets",
+                    format!("Preset [{}]: {}", preset.name, e),
+                    Some(format!(
+                        "Install runtime [{}] or change ExeUnit of the preset",
+                        preset.exeunit_name
+                    )),
+                );
+                continue;
+            }
+        };
+        for coeff in preset.usage_coeffs.keys() {
+            if desc.is_custom_counter(coeff) {
+                continue;
+            }
+            if !desc
+                .coefficients()
+                .any(|(prop_name, _)| &prop_name == coeff)
+            {
+                diag.warn(
+                    "presets",
+                    format!(
+                        "Preset [{}] prices usage counter [{}] not supported by runtime [{}]",
+                        preset.name, coeff, desc.name
+                    ),
+                    Some("The price will be ignored".into()),
+                );
+            }
+        }
+    }
+    Some(presets)
+}
+
+fn validate_hardware(config: &ProviderConfig, diag: &mut Diagnostics) -> Option<Profiles> {
+    if !config.hardware_file.exists() {
+        diag.warn(
+            "hardware",
+            "Hardware profiles file doesn't exist, default profile will be created on start",
+            None,
+        );
+        return None;
+    }
+
+    let profiles = match Profiles::load(&config.hardware_file) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            diag.error(
+                "hardware",
+                e,
+                Some("Fix the file or remove it to recreate default profile".into()),
+            );
+            return None;
+        }
+    };
+
+    let active = profiles.active();
+    if let Some(resources) = profiles.get(active) {
+        if resources.depleted() {
+            diag.error(
+                "hardware",
+                format!("Active hardware profile [{}] has no resources", active),
+                Some(format!(
+                    "Run `ya-provider profile update --name {}` with positive values",
+                    active
+                )),
+            );
+        }
+        if let Ok(caps) = Resources::try_with_config(&config.hardware_file, config) {
+            if resources.cpu_threads > caps.cpu_threads
+                || resources.mem_gib > caps.mem_gib
+                || resources.storage_gib > caps.storage_gib
+            {
+                diag.warn(
+                    "hardware",
+                    format!(
+                        "Active hardware profile [{}] exceeds available resources ({:?})",
+                        active, caps
+                    ),
+                    Some("Resources will be capped to available ones".into()),
+                );
+            }
+        }
+    }
+    Some(profiles)
+}
+
+fn validate_profiles(
+    config: &ProviderConfig,
+    presets: Option<&Presets>,
+    hardware: Option<&Profiles>,
+    diag: &mut Diagnostics,
+) {
+    let profiles = match ConfigProfiles::load(&config.config_profiles_file) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            diag.error(
+                "config-profiles",
+                format!(
+                    "Can't load {}: {}",
+                    config.config_profiles_file.display(),
+                    e
+                ),
+                Some("Fix or remove the file".into()),
+            );
+            return;
+        }
+    };
+
+    for name in profiles.profiles.keys() {
+        let severity = if profiles.active.as_ref() == Some(name) {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+
+        let profile = match profiles.resolve(name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                diag.push(
+                    severity,
+                    "config-profiles",
+                    format!("Config profile [{}]: {}", name, e),
+                    Some("Fix `inherits` field of the profile".into()),
+                );
+                continue;
+            }
+        };
+
+        if let (Some(hw_profile), Some(hardware)) = (&profile.hardware_profile, hardware) {
+            if hardware.get(hw_profile).is_none() {
+                diag.push(
+                    severity,
+                    "config-profiles",
+                    format!(
+                        "Config profile [{}] refers to unknown hardware profile [{}]",
+                        name, hw_profile
+                    ),
+                    Some("Create it with `ya-provider profile create`".into()),
+                );
+            }
+        }
+        if let (Some(names), Some(presets)) = (&profile.presets, presets) {
+            for preset in names
+                .iter()
+                .filter(|preset| !presets.presets.contains_key(*preset))
+            {
+                diag.push(
+                    severity,
+                    "config-profiles",
+                    format!(
+                        "Config profile [{}] refers to unknown preset [{}]",
+                        name, preset
+                    ),
+                    Some("Create it with `ya-provider preset create`".into()),
+                );
+            }
+        }
+    }
+
+    if let Some(active) = &profiles.active {
+        if !profiles.profiles.contains_key(active) {
+            diag.error(
+                "config-profiles",
+                format!("Active config profile [{}] doesn't exist", active),
+                Some(format!(
+                    "Run `ya-provider config use-profile {}`",
+                    BASE_PROFILE_NAME
+                )),
+            );
+        }
+    }
+}
+
+fn validate_rules(config: &ProviderConfig, diag: &mut Diagnostics) {
+    if !config.rules_file.exists() {
+        return;
+    }
+
+    let rules = match OpenOptions::new()
+        .read(true)
+        .open(&config.rules_file)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| {
+            Ok(serde_json::from_reader::<_, RulesConfig>(BufReader::new(
+                file,
+            ))?)
+        }) {
+        Ok(rules) => rules,
+        Err(e) => {
+            diag.error(
+                "rules",
+                format!("Can't load {}: {}", config.rules_file.display(), e),
+                Some("Fix the file or remove it to restore default rules".into()),
+            );
+            return;
+        }
+    };
+
+    let cert_ids = match config
+        .cert_dir_path()
+        .and_then(|cert_dir| CompositeKeystore::load(&cert_dir))
+    {
+        Ok(keystore) => keystore.list_ids(),
+        Err(e) => {
+            diag.error(
+                "rules",
+                format!("Can't load keystore: {}", e),
+                Some("Check --cert-dir (PROVIDER_CERT_DIR)".into()),
+            );
+            return;
+        }
+    };
+
+    let rule_certs = rules
+        .outbound
+        .partner
+        .keys()
+        .chain(rules.outbound.audited_payload.keys())
+        .chain(rules.blacklist.certified.iter())
+        .chain(rules.allow_only.certified.iter());
+    for cert_id in rule_certs.filter(|cert_id| !cert_ids.contains(cert_id)) {
+        diag.warn(
+            "rules",
+            format!("Rule refers to certificate [{}] missing in keystore", cert_id),
+            Some(
+                "Add certificate with `ya-provider keystore add` or the rule will be removed on start"
+                    .into(),
+            ),
+        );
+    }
+}
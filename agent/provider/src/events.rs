@@ -4,6 +4,7 @@ use crate::config::presets::Presets;
 pub enum Event {
     Initialized,
     HardwareChanged,
+    BenchmarkChanged,
     PresetsChanged {
         presets: Presets,
         updated: Vec<String>,
@@ -3,12 +3,17 @@ pub use task_runner::{
     TaskRunner, TaskRunnerConfig, TerminateActivity, UpdateActivity,
 };
 
+pub use self::exit_policy::{ActivityOutcome, ExitPolicy};
 pub use self::registry::Configuration;
 pub use self::registry::{ExeUnitDesc, ExeUnitsRegistry};
 pub use self::task_runner::exe_unit_cache_dir;
 pub use self::task_runner::exe_unit_work_dir;
+pub use self::workdir::CleanupPolicy;
 
 mod exeunit_instance;
+mod exit_policy;
+mod recovery;
 mod registry;
 mod task;
 mod task_runner;
+mod workdir;
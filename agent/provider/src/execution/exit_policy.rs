@@ -0,0 +1,209 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use strum_macros::{Display, EnumString};
+
+use ya_utils_process::ExeUnitExitStatus;
+
+use super::recovery::is_crash;
+
+/// Keeps behavior from before the policy was configurable.
+pub const DEFAULT_EXIT_POLICY: &str = "signal=retryable,error=retryable,*=success";
+
+/// What exit of the workload means for Activity and its Agreement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ActivityOutcome {
+    /// Activity is finished and Agreement stays alive for next Activities.
+    Success,
+    /// Activity is restarted, if Agreement allows it, otherwise terminated.
+    /// Agreement stays alive.
+    Retryable,
+    /// Activity is terminated and Agreement is broken, so its resources are offered again.
+    Permanent,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ExitMatch {
+    Codes(RangeInclusive<i32>),
+    /// ExeUnit was killed by a signal.
+    Signal,
+    /// Waiting for ExeUnit process failed.
+    Error,
+    Any,
+}
+
+impl ExitMatch {
+    fn matches(&self, status: &ExeUnitExitStatus) -> bool {
+        match (self, status) {
+            (ExitMatch::Any, _) => true,
+            (ExitMatch::Signal, ExeUnitExitStatus::Aborted(_)) => true,
+            (ExitMatch::Error, ExeUnitExitStatus::Error(_)) => true,
+            (ExitMatch::Codes(codes), ExeUnitExitStatus::Finished(exit_status)) => exit_status
+                .code()
+                .map(|code| codes.contains(&code))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for ExitMatch {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "*" => ExitMatch::Any,
+            "signal" => ExitMatch::Signal,
+            "error" => ExitMatch::Error,
+            codes => {
+                // Negative codes are not supported, since Windows codes are unsigned anyway.
+                let (start, end) = codes.split_once('-').unwrap_or((codes, codes));
+                let start = start.trim().parse::<i32>()?;
+                let end = end.trim().parse::<i32>()?;
+                if start > end {
+                    return Err(anyhow!("Invalid exit code range: {}", codes));
+                }
+                ExitMatch::Codes(start..=end)
+            }
+        })
+    }
+}
+
+impl fmt::Display for ExitMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitMatch::Codes(codes) if codes.start() == codes.end() => {
+                write!(f, "{}", codes.start())
+            }
+            ExitMatch::Codes(codes) => write!(f, "{}-{}", codes.start(), codes.end()),
+            ExitMatch::Signal => write!(f, "signal"),
+            ExitMatch::Error => write!(f, "error"),
+            ExitMatch::Any => write!(f, "*"),
+        }
+    }
+}
+
+/// Maps ExeUnit exit statuses to Activity outcomes.
+/// Policy is a comma separated list of `<match>=<outcome>` rules, where `<match>` is
+/// an exit code, range of exit codes (`1-9`), `signal`, `error` or `*`.
+/// The first matching rule wins. Crashes not matched by any rule are `retryable`,
+/// other exits are `success`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExitPolicy {
+    rules: Vec<(ExitMatch, ActivityOutcome)>,
+}
+
+impl ExitPolicy {
+    pub fn outcome(&self, status: &ExeUnitExitStatus) -> ActivityOutcome {
+        self.rules
+            .iter()
+            .find(|(exit_match, _)| exit_match.matches(status))
+            .map(|(_, outcome)| *outcome)
+            .unwrap_or(match is_crash(status) {
+                true => ActivityOutcome::Retryable,
+                false => ActivityOutcome::Success,
+            })
+    }
+}
+
+impl Default for ExitPolicy {
+    fn default() -> Self {
+        DEFAULT_EXIT_POLICY.parse().unwrap()
+    }
+}
+
+impl FromStr for ExitPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules = s
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| {
+                let (exit_match, outcome) = rule
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("Expected <match>=<outcome>, got: {}", rule))?;
+                let exit_match = exit_match
+                    .trim()
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid exit match in rule '{}': {}", rule, e))?;
+                let outcome = outcome
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid outcome in rule '{}'", rule))?;
+                Ok((exit_match, outcome))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(ExitPolicy { rules })
+    }
+}
+
+impl fmt::Display for ExitPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules = self
+            .rules
+            .iter()
+            .map(|(exit_match, outcome)| format!("{}={}", exit_match, outcome))
+            .collect::<Vec<_>>();
+        write!(f, "{}", rules.join(","))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn exited(code: i32) -> ExeUnitExitStatus {
+        ExeUnitExitStatus::Finished(ExitStatus::from_raw(code << 8))
+    }
+
+    fn killed(signal: i32) -> ExeUnitExitStatus {
+        ExeUnitExitStatus::Aborted(ExitStatus::from_raw(signal))
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let policy: ExitPolicy = "0=success, 75-78=retryable, signal=retryable, *=permanent"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            policy.to_string(),
+            "0=success,75-78=retryable,signal=retryable,*=permanent"
+        );
+
+        assert_eq!(policy.outcome(&exited(0)), ActivityOutcome::Success);
+        assert_eq!(policy.outcome(&exited(76)), ActivityOutcome::Retryable);
+        assert_eq!(policy.outcome(&exited(1)), ActivityOutcome::Permanent);
+        assert_eq!(policy.outcome(&killed(9)), ActivityOutcome::Retryable);
+        assert_eq!(
+            policy.outcome(&ExeUnitExitStatus::Error(std::io::ErrorKind::Other.into())),
+            ActivityOutcome::Permanent
+        );
+    }
+
+    #[test]
+    fn unmatched_exits_keep_default_behavior() {
+        let policy: ExitPolicy = "3=permanent".parse().unwrap();
+        assert_eq!(policy.outcome(&exited(3)), ActivityOutcome::Permanent);
+        assert_eq!(policy.outcome(&exited(1)), ActivityOutcome::Success);
+        assert_eq!(policy.outcome(&killed(9)), ActivityOutcome::Retryable);
+
+        let default = ExitPolicy::default();
+        assert_eq!(default.outcome(&exited(1)), ActivityOutcome::Success);
+        assert_eq!(default.outcome(&killed(9)), ActivityOutcome::Retryable);
+    }
+
+    #[test]
+    fn invalid_policies_are_rejected() {
+        assert!("0".parse::<ExitPolicy>().is_err());
+        assert!("0=ok".parse::<ExitPolicy>().is_err());
+        assert!("9-1=success".parse::<ExitPolicy>().is_err());
+        assert!("sigkill=success".parse::<ExitPolicy>().is_err());
+    }
+}
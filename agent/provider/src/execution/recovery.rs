@@ -0,0 +1,174 @@
+use derive_more::Display;
+use serde_json::json;
+use std::collections::HashMap;
+
+use ya_agreement_utils::{AgreementView, Error};
+use ya_utils_process::ExeUnitExitStatus;
+
+const RESTART_ALLOWED_KEY: &str = "/demand/properties/golem/srv/caps/restart-allowed";
+
+/// Reason codes set in Activity state, when ExeUnit process crashed.
+#[derive(Display, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrashReason {
+    /// ExeUnit was restarted. Activity state is reset and Requestor must deploy again.
+    #[display(fmt = "exeunit-restarted")]
+    Restarted,
+    /// Agreement doesn't allow restarting Activity.
+    #[display(fmt = "exeunit-crashed")]
+    Crashed,
+    /// Activity was already restarted allowed number of times.
+    #[display(fmt = "exeunit-restart-limit")]
+    RestartLimit,
+    /// Spawning new ExeUnit process failed.
+    #[display(fmt = "exeunit-restart-failed")]
+    RestartFailed,
+    /// Exit policy classified failure as permanent. Agreement is broken.
+    #[display(fmt = "exeunit-permanent-failure")]
+    PermanentFailure,
+}
+
+/// Decision what to do with Activity, which ExeUnit crashed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recovery {
+    Restart { attempt: u32 },
+    Terminate(CrashReason),
+}
+
+/// Counts restarts of crashed Activities.
+/// Activity can be restarted only if Requestor allowed it in Demand
+/// (`golem.srv.caps.restart-allowed`) and the limit wasn't reached.
+#[derive(Default)]
+pub struct RecoveryTracker {
+    restarts: HashMap<String, u32>,
+}
+
+impl RecoveryTracker {
+    pub fn decide(
+        &mut self,
+        agreement: Option<&AgreementView>,
+        activity_id: &str,
+        restart_limit: u32,
+    ) -> Recovery {
+        if !agreement.map(restart_allowed).unwrap_or(false) {
+            return Recovery::Terminate(CrashReason::Crashed);
+        }
+
+        let restarts = self.restarts.entry(activity_id.to_string()).or_default();
+        if *restarts >= restart_limit {
+            return Recovery::Terminate(CrashReason::RestartLimit);
+        }
+        *restarts += 1;
+        Recovery::Restart { attempt: *restarts }
+    }
+
+    /// Number of restarts of Activity done so far.
+    pub fn restarts(&self, activity_id: &str) -> Option<u32> {
+        self.restarts.get(activity_id).copied()
+    }
+
+    pub fn forget(&mut self, activity_id: &str) {
+        self.restarts.remove(activity_id);
+    }
+}
+
+/// Exit status is crash, if ExeUnit didn't finish on its own.
+pub fn is_crash(status: &ExeUnitExitStatus) -> bool {
+    matches!(
+        status,
+        ExeUnitExitStatus::Aborted(_) | ExeUnitExitStatus::Error(_)
+    )
+}
+
+/// Structured message describing failure, set together with reason code in Activity state.
+pub fn crash_details(status: &ExeUnitExitStatus, attempt: Option<u32>, limit: u32) -> String {
+    let (exit_code, error) = match status {
+        ExeUnitExitStatus::Aborted(exit_status) | ExeUnitExitStatus::Finished(exit_status) => {
+            (exit_status.code(), None)
+        }
+        ExeUnitExitStatus::Error(error) => (None, Some(error.to_string())),
+    };
+    json!({
+        "exitCode": exit_code,
+        "error": error,
+        "restartAttempt": attempt,
+        "restartLimit": limit,
+    })
+    .to_string()
+}
+
+fn restart_allowed(agreement: &AgreementView) -> bool {
+    match agreement.pointer_typed::<bool>(RESTART_ALLOWED_KEY) {
+        Ok(allowed) => allowed,
+        Err(Error::NoKey(_)) => false,
+        Err(e) => {
+            log::warn!(
+                "Invalid restart-allowed property in Agreement [{}]: {}",
+                agreement.id,
+                e
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agreement(restart_allowed: Option<bool>) -> AgreementView {
+        let mut json = json!({
+            "agreementId": "agreement",
+            "demand": { "properties": { "golem": { "srv": { "caps": {} } } } },
+            "offer": { "properties": {} },
+        });
+        if let Some(allowed) = restart_allowed {
+            json["demand"]["properties"]["golem"]["srv"]["caps"]["restart-allowed"] =
+                json!(allowed);
+        }
+        AgreementView {
+            id: "agreement".to_string(),
+            json,
+        }
+    }
+
+    #[test]
+    fn restarts_only_when_allowed_and_within_limit() {
+        let mut tracker = RecoveryTracker::default();
+        assert_eq!(
+            tracker.decide(Some(&agreement(None)), "a", 2),
+            Recovery::Terminate(CrashReason::Crashed)
+        );
+        assert_eq!(
+            tracker.decide(Some(&agreement(Some(false))), "a", 2),
+            Recovery::Terminate(CrashReason::Crashed)
+        );
+        assert_eq!(
+            tracker.decide(None, "a", 2),
+            Recovery::Terminate(CrashReason::Crashed)
+        );
+
+        let allowed = agreement(Some(true));
+        assert_eq!(
+            tracker.decide(Some(&allowed), "a", 2),
+            Recovery::Restart { attempt: 1 }
+        );
+        assert_eq!(
+            tracker.decide(Some(&allowed), "a", 2),
+            Recovery::Restart { attempt: 2 }
+        );
+        assert_eq!(
+            tracker.decide(Some(&allowed), "a", 2),
+            Recovery::Terminate(CrashReason::RestartLimit)
+        );
+        assert_eq!(
+            tracker.decide(Some(&allowed), "b", 2),
+            Recovery::Restart { attempt: 1 }
+        );
+
+        tracker.forget("a");
+        assert_eq!(
+            tracker.decide(Some(&allowed), "a", 2),
+            Recovery::Restart { attempt: 1 }
+        );
+    }
+}
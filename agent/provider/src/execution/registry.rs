@@ -17,6 +17,11 @@ use thiserror::Error;
 use ya_agreement_utils::OfferBuilder;
 
 use super::{exe_unit_work_dir, exeunit_instance::ExeUnitInstance};
+use crate::simulation;
+
+/// Prefix of usage counter properties. Priced counters with this prefix, which the ExeUnit
+/// doesn't define, are custom counters reported by the runtime, e.g. `golem.usage.gpu-sec`.
+pub const USAGE_COUNTER_PREFIX: &str = "golem.usage.";
 
 pub fn default_counter_config() -> HashMap<String, CounterDefinition> {
     let mut counters = HashMap::new();
@@ -115,9 +120,22 @@ impl ExeUnitDesc {
                     }
                 })
             })
+            .or_else(|| {
+                self.is_custom_counter(coefficient_name)
+                    .then(|| coefficient_name.to_string())
+            })
             .ok_or_else(|| anyhow!("invalid coefficient name = {}", coefficient_name))
     }
 
+    /// Counter not defined by the ExeUnit, which its runtime reports on its own.
+    pub fn is_custom_counter(&self, property_name: &str) -> bool {
+        property_name.len() > USAGE_COUNTER_PREFIX.len()
+            && property_name.starts_with(USAGE_COUNTER_PREFIX)
+            && !self
+                .coefficients()
+                .any(|(prop_name, _)| prop_name == property_name)
+    }
+
     pub fn coefficient_name(&self, propery_name: &str) -> Option<String> {
         Some(
             self.config
@@ -268,6 +286,20 @@ impl ExeUnitsRegistry {
         Ok(())
     }
 
+    /// Replaces supervisors of all ExeUnits with the simulation stub run by `provider_binary`.
+    /// Names and counters are kept, so Presets and pricing still apply.
+    pub fn simulate(&mut self, provider_binary: &Path) {
+        for desc in self.descriptors.values_mut() {
+            desc.supervisor_path = provider_binary.to_path_buf();
+            desc.runtime_path = None;
+            desc.extra_args = vec![
+                simulation::STUB_COMMAND.to_string(),
+                "--runtime".to_string(),
+                desc.name.clone(),
+            ];
+        }
+    }
+
     pub fn find_exeunit(&self, name: &str) -> Result<ExeUnitDesc> {
         Ok(self
             .descriptors
@@ -534,4 +566,26 @@ mod tests {
             .unwrap()
             .contains("/usr/lib/yagna/plugins/exe-unit"));
     }
+
+    #[test]
+    fn test_resolve_custom_coefficient() {
+        let mut registry = ExeUnitsRegistry::default();
+        registry
+            .register_exeunits_from_file(&resources_directory().join("example-exeunits.json"))
+            .unwrap();
+        let desc = registry.find_exeunit("wasm").unwrap();
+
+        assert_eq!(
+            desc.resolve_coefficient("cpu").unwrap(),
+            "golem.usage.cpu_sec"
+        );
+        assert_eq!(
+            desc.resolve_coefficient("golem.usage.gpu-sec").unwrap(),
+            "golem.usage.gpu-sec"
+        );
+        assert!(desc.is_custom_counter("golem.usage.inference-tokens"));
+        assert!(!desc.is_custom_counter("golem.usage.duration_sec"));
+        assert!(desc.resolve_coefficient("gpu-sec").is_err());
+        assert!(desc.resolve_coefficient("golem.usage.").is_err());
+    }
 }
@@ -13,14 +13,22 @@ pub struct Task {
     pub exeunit: ExeUnitInstance,
     pub agreement_id: String,
     pub activity_id: String,
+    /// Needed to spawn ExeUnit again, when Activity is restarted.
+    pub requestor_pub_key: Option<String>,
 }
 
 impl Task {
-    pub fn new(exeunit: ExeUnitInstance, agreement_id: &str, activity_id: &str) -> Task {
+    pub fn new(
+        exeunit: ExeUnitInstance,
+        agreement_id: &str,
+        activity_id: &str,
+        requestor_pub_key: Option<&str>,
+    ) -> Task {
         Task {
             exeunit,
             agreement_id: agreement_id.to_string(),
             activity_id: activity_id.to_string(),
+            requestor_pub_key: requestor_pub_key.map(ToString::to_string),
         }
     }
 }
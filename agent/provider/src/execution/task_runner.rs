@@ -13,6 +13,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::{fs, iter};
 use structopt::StructOpt;
+use strum::VariantNames;
 
 use ya_agreement_utils::{AgreementView, OfferTemplate};
 use ya_client::activity::ActivityProviderApi;
@@ -23,13 +24,17 @@ use ya_utils_actix::actix_handler::ResultTypeGetter;
 use ya_utils_actix::actix_signal::{Signal, SignalSlot};
 use ya_utils_actix::{actix_signal_handler, forward_actix_handler};
 use ya_utils_path::SecurePath;
-use ya_utils_process::ExeUnitExitStatus;
+use ya_utils_process::{ExeUnitExitStatus, ProcessHandle};
 
+use super::exit_policy::{ActivityOutcome, ExitPolicy, DEFAULT_EXIT_POLICY};
+use super::recovery::{crash_details, is_crash, CrashReason, Recovery, RecoveryTracker};
 use super::registry::{ExeUnitDesc, ExeUnitsRegistry};
 use super::task::Task;
+use super::workdir::{self, CleanupPolicy};
 use crate::market::provider_market::NewAgreement;
+use crate::market::termination_reason::BreakReason;
 use crate::market::Preset;
-use crate::tasks::{AgreementBroken, AgreementClosed};
+use crate::tasks::{AgreementBroken, AgreementClosed, BreakAgreement};
 
 const EXE_UNIT_DIR: &str = "exe-unit";
 const WORK_DIR: &str = "work";
@@ -108,7 +113,8 @@ pub struct TerminateActivity {
 
 /// Called when process exited. There are 2 reasons for process to exit:
 /// - We got DestroyActivity event and killed process.
-/// - ExeUnit crashed.
+/// - ExeUnit exited on its own or crashed. Exit policy decides, whether Activity
+///   is finished, restarted (if Agreement allows it) or Agreement is broken.
 #[derive(Message)]
 #[rtype(result = "Result<()>")]
 struct ExeUnitProcessFinished {
@@ -133,12 +139,44 @@ pub struct TaskRunnerConfig {
     pub auto_cleanup_activity: bool,
     /// Removes directory content after `Agreement` is terminated.
     /// Use this option to save disk space. Shouldn't be used when debugging.
+    /// Overrides `--workdir-cleanup-policy` with `immediate`.
     #[structopt(long, env)]
     pub auto_cleanup_agreement: bool,
+    /// What happens with `Agreement` directory after `Agreement` is terminated.
+    #[structopt(long, env, default_value = "keep", possible_values = CleanupPolicy::VARIANTS)]
+    pub workdir_cleanup_policy: CleanupPolicy,
+    /// How long `Agreement` directory is kept for `delayed` and `keep-on-dispute` policies.
+    /// Directories scheduled for removal before Provider restart are removed on next startup,
+    /// when they are older than 30 days.
+    #[structopt(long, env, parse(try_from_str = humantime::parse_duration), default_value = "24h")]
+    pub workdir_retention: Duration,
+    /// How often `Agreement` directories are checked against storage negotiated in `Agreement`.
+    /// Activities of `Agreement` exceeding its storage are terminated.
+    #[structopt(long, env, parse(try_from_str = humantime::parse_duration), default_value = "1min")]
+    pub workdir_quota_check_interval: Duration,
+    /// How many times Activity is restarted after ExeUnit crash.
+    /// Restart must be allowed by Requestor (`golem.srv.caps.restart-allowed` Demand property).
+    #[structopt(long, env, default_value = "1")]
+    pub activity_restart_limit: u32,
+    /// Maps ExeUnit exit statuses to Activity outcomes: `success`, `retryable` or `permanent`.
+    /// Comma separated `<match>=<outcome>` rules, where `<match>` is an exit code,
+    /// range of codes (`1-9`), `signal`, `error` or `*`. The first matching rule wins.
+    /// Permanent failures terminate Activity and break Agreement.
+    #[structopt(long, env, default_value = DEFAULT_EXIT_POLICY)]
+    pub exit_code_policy: ExitPolicy,
     #[structopt(skip = "you-forgot-to-set-session-id")]
     pub session_id: String,
 }
 
+impl TaskRunnerConfig {
+    pub fn cleanup_policy(&self) -> CleanupPolicy {
+        match self.auto_cleanup_agreement {
+            true => CleanupPolicy::Immediate,
+            false => self.workdir_cleanup_policy,
+        }
+    }
+}
+
 // =========================================== //
 // TaskRunner declaration
 // =========================================== //
@@ -152,10 +190,12 @@ pub struct TaskRunner {
     /// Spawned tasks.
     tasks: Vec<Task>,
     active_agreements: HashMap<String, AgreementView>,
+    recovery: RecoveryTracker,
 
     /// External actors can listen on these signals.
     pub activity_created: SignalSlot<CreateActivity>,
     pub activity_destroyed: SignalSlot<ActivityDestroyed>,
+    pub break_agreement: SignalSlot<BreakAgreement>,
 
     config: Arc<TaskRunnerConfig>,
 
@@ -207,8 +247,10 @@ impl TaskRunner {
             registry,
             tasks: vec![],
             active_agreements: HashMap::new(),
+            recovery: RecoveryTracker::default(),
             activity_created: SignalSlot::<CreateActivity>::default(),
             activity_destroyed: SignalSlot::<ActivityDestroyed>::default(),
+            break_agreement: SignalSlot::<BreakAgreement>::default(),
             config: Arc::new(config),
             event_ts: Utc::now(),
             tasks_dir,
@@ -292,9 +334,52 @@ impl TaskRunner {
 
         let process = task.exeunit.get_process_handle();
         self.tasks.push(task);
+        self.watch_exeunit(process, &msg.activity_id, &msg.agreement_id, ctx);
+        Ok(())
+    }
 
+    /// Spawns new ExeUnit in place of crashed one. Activity keeps its id
+    /// and working directory.
+    fn restart_task(
+        &mut self,
+        agreement_id: &str,
+        activity_id: &str,
+        ctx: &mut Context<Self>,
+    ) -> Result<()> {
+        let position = self
+            .tasks
+            .iter()
+            .position(|task| task.agreement_id == agreement_id && task.activity_id == activity_id)
+            .ok_or_else(|| anyhow!("Activity [{}] was already destroyed.", activity_id))?;
+        let agreement = self
+            .active_agreements
+            .get(agreement_id)
+            .ok_or_else(|| anyhow!("Agreement [{}] is no longer active.", agreement_id))?;
+
+        let exeunit_name = exe_unit_name_from(agreement)?;
+        let requestor_pub_key = self.tasks[position].requestor_pub_key.clone();
+        let task = self.create_task(
+            &exeunit_name,
+            activity_id,
+            agreement_id,
+            requestor_pub_key.as_deref(),
+        )?;
+
+        let process = task.exeunit.get_process_handle();
+        self.tasks[position] = task;
+        self.watch_exeunit(process, activity_id, agreement_id, ctx);
+        Ok(())
+    }
+
+    fn watch_exeunit(
+        &self,
+        process: ProcessHandle,
+        activity_id: &str,
+        agreement_id: &str,
+        ctx: &mut Context<Self>,
+    ) {
         // Log ExeUnit initialization message
-        let activity_id = msg.activity_id.clone();
+        let activity_id = activity_id.to_string();
         let api = self.api.clone();
         let proc = process.clone();
 
@@ -317,42 +402,11 @@ impl TaskRunner {
         // We need to discover that ExeUnit process finished.
         // We can't be sure that Requestor will send DestroyActivity.
         let myself = ctx.address();
-        let activity_id = msg.activity_id.clone();
-        let agreement_id = msg.agreement_id.clone();
-        let state_retry_interval = self.config.exeunit_state_retry_interval;
-        let api = self.api.clone();
+        let activity_id = activity_id.to_string();
+        let agreement_id = agreement_id.to_string();
 
         tokio::task::spawn_local(async move {
             let status = process.wait_until_finished().await;
-
-            // If it was brutal termination than ExeUnit probably didn't set state.
-            // We must do it instead of him. Repeat until it will succeed.
-            match &status {
-                ExeUnitExitStatus::Aborted(exit_status) => {
-                    log::warn!(
-                        "ExeUnit [{}] execution aborted. Setting activity [{}] state to Terminated",
-                        exeunit_name,
-                        activity_id
-                    );
-
-                    let reason = "execution aborted";
-                    let msg = format!("exit code {:?}", exit_status.code());
-                    set_activity_terminated(api, &activity_id, reason, msg, state_retry_interval)
-                        .await;
-                }
-                ExeUnitExitStatus::Error(error) => {
-                    log::warn!(
-                        "ExeUnit [{}] execution failed: {}. Setting activity [{}] state to Terminated",
-                        error, exeunit_name, activity_id
-                    );
-
-                    let reason = "execution error";
-                    set_activity_terminated(api, &activity_id, reason, error, state_retry_interval)
-                        .await;
-                }
-                _ => (),
-            }
-
             let msg = ExeUnitProcessFinished {
                 activity_id,
                 agreement_id,
@@ -361,14 +415,12 @@ impl TaskRunner {
 
             myself.do_send(msg);
         });
-
-        Ok(())
     }
 
     fn on_exeunit_exited(
         &mut self,
         msg: ExeUnitProcessFinished,
-        _ctx: &mut Context<Self>,
+        ctx: &mut Context<Self>,
     ) -> Result<()> {
         log::info!(
             "ExeUnit process exited with status {}, agreement [{}], activity [{}].",
@@ -377,25 +429,154 @@ impl TaskRunner {
             msg.activity_id
         );
 
+        // Task is removed before killing ExeUnit, if Activity was destroyed on purpose.
+        // Exit status tells nothing about the workload then, so exit policy isn't applied.
+        let destroyed = !self.tasks.iter().any(|task| {
+            task.agreement_id == msg.agreement_id && task.activity_id == msg.activity_id
+        });
+        let outcome = self.config.exit_code_policy.outcome(&msg.status);
+        let limit = self.config.activity_restart_limit;
+        let recovery = match (destroyed, outcome) {
+            (true, _) if is_crash(&msg.status) => Recovery::Terminate(CrashReason::Crashed),
+            (true, _) | (false, ActivityOutcome::Success) => {
+                self.on_activity_finished(msg.agreement_id, msg.activity_id);
+                return Ok(());
+            }
+            (false, ActivityOutcome::Permanent) => {
+                Recovery::Terminate(CrashReason::PermanentFailure)
+            }
+            (false, ActivityOutcome::Retryable) => self.recovery.decide(
+                self.active_agreements.get(&msg.agreement_id),
+                &msg.activity_id,
+                limit,
+            ),
+        };
+
+        let ExeUnitProcessFinished {
+            activity_id,
+            agreement_id,
+            status,
+        } = msg;
+
+        match recovery {
+            Recovery::Restart { attempt } => {
+                log::warn!(
+                    "ExeUnit of activity [{}] crashed ({}). Restarting activity, attempt {}/{}.",
+                    activity_id,
+                    status,
+                    attempt,
+                    limit
+                );
+
+                // Reset state before new ExeUnit starts, because it will set state on its own.
+                let api = self.api.clone();
+                let state = ActivityState {
+                    state: State::New.into(),
+                    reason: Some(CrashReason::Restarted.to_string()),
+                    error_message: Some(crash_details(&status, Some(attempt), limit)),
+                };
+                let future = async move {
+                    api.set_activity_state(&activity_id, &state)
+                        .await
+                        .map_err(|e| {
+                            log::warn!("Can't reset state of activity [{}]: {}", activity_id, e)
+                        })
+                        .ok();
+                    (agreement_id, activity_id)
+                }
+                .into_actor(self)
+                .map(move |(agreement_id, activity_id), myself, ctx| {
+                    if let Err(error) = myself.restart_task(&agreement_id, &activity_id, ctx) {
+                        log::warn!("Failed to restart activity [{}]: {}", activity_id, error);
+                        myself.terminate_crashed(
+                            agreement_id,
+                            activity_id,
+                            CrashReason::RestartFailed,
+                            crash_details(&status, Some(attempt), limit),
+                            ctx,
+                        );
+                    }
+                });
+                ctx.spawn(future);
+            }
+            Recovery::Terminate(reason) => {
+                log::warn!(
+                    "ExeUnit of activity [{}] failed ({}). Setting activity state to Terminated, reason: {}.",
+                    activity_id,
+                    status,
+                    reason
+                );
+
+                let attempt = self.recovery.restarts(&activity_id);
+                let details = crash_details(&status, attempt, limit);
+                self.terminate_crashed(agreement_id, activity_id, reason, details, ctx);
+            }
+        }
+        Ok(())
+    }
+
+    /// If it was brutal termination than ExeUnit probably didn't set state.
+    /// We must do it instead of him. Repeat until it will succeed.
+    fn terminate_crashed(
+        &self,
+        agreement_id: String,
+        activity_id: String,
+        reason: CrashReason,
+        details: String,
+        ctx: &mut Context<Self>,
+    ) {
+        let api = self.api.clone();
+        let retry_interval = self.config.exeunit_state_retry_interval;
+        let message = details.clone();
+        let future = async move {
+            set_activity_terminated(api, &activity_id, reason, details, retry_interval).await;
+            activity_id
+        }
+        .into_actor(self)
+        .map(move |activity_id, myself, _| {
+            // Payments must learn about destroyed Activity before Agreement is broken.
+            myself.on_activity_finished(agreement_id.clone(), activity_id);
+            if reason == CrashReason::PermanentFailure {
+                myself.break_on_failure(agreement_id, message);
+            }
+        });
+        ctx.spawn(future);
+    }
+
+    /// Agreement is broken after the failed Activity, so it won't be used by Requestor
+    /// anymore and its resources are offered again.
+    fn break_on_failure(&self, agreement_id: String, details: String) {
+        self.break_agreement
+            .send_signal(BreakAgreement {
+                agreement_id: agreement_id.clone(),
+                reason: BreakReason::WorkloadFailed(details),
+            })
+            .log_err_msg(&format!(
+                "Failed to send BreakAgreement for [{}] after workload failure",
+                agreement_id
+            ))
+            .ok();
+    }
+
+    fn on_activity_finished(&mut self, agreement_id: String, activity_id: String) {
+        self.recovery.forget(&activity_id);
+
         if self.config.auto_cleanup_activity {
-            let workdir = self
-                .agreement_dir(&msg.agreement_id)
-                .secure_join(&msg.activity_id);
+            let workdir = self.agreement_dir(&agreement_id).secure_join(&activity_id);
             log::info!(
                 "Cleaning directory {} for agreement [{}], activity [{}].",
                 workdir.display(),
-                msg.agreement_id,
-                msg.activity_id
+                agreement_id,
+                activity_id
             );
             fs::remove_dir_all(workdir).ok();
         }
 
         let destroy_msg = ActivityDestroyed {
-            agreement_id: msg.agreement_id.to_string(),
-            activity_id: msg.activity_id,
+            agreement_id,
+            activity_id,
         };
         let _ = self.activity_destroyed.send_signal(destroy_msg);
-        Ok(())
     }
 
     pub fn on_agreement_approved(
@@ -419,20 +600,147 @@ impl TaskRunner {
             .map_err(|error| error.context("ExeUnit offer-template command failed".to_string()))
     }
 
-    fn exeunit_coeffs(&self, exeunit_name: &str) -> Result<Vec<String>> {
-        Ok(match self.registry.find_exeunit(exeunit_name)?.config {
+    /// Priced counters of the ExeUnit and custom counters declared by the Preset.
+    fn exeunit_coeffs(&self, preset: &Preset) -> Result<Vec<String>> {
+        let desc = self.registry.find_exeunit(&preset.exeunit_name)?;
+        let mut coeffs: Vec<String> = match desc.config {
             Some(ref config) => (config.counters.iter())
                 .filter_map(|(prop, cnt)| cnt.price.then_some(prop))
+                .filter(|prop| preset.usage_coeffs.contains_key(*prop))
                 .cloned()
                 .collect(),
             _ => Default::default(),
-        })
+        };
+        coeffs.extend(
+            (preset.usage_coeffs.keys())
+                .filter(|prop| desc.is_custom_counter(prop))
+                .cloned(),
+        );
+        Ok(coeffs)
     }
 
     fn agreement_dir(&self, agreement_id: &str) -> PathBuf {
         self.tasks_dir.secure_join(agreement_id)
     }
 
+    fn cleanup_agreement_dir(&self, agreement_id: String, disputed: bool, ctx: &mut Context<Self>) {
+        let policy = self.config.cleanup_policy();
+        let workdir = self.agreement_dir(&agreement_id);
+        let delay = match policy.removal_delay(self.config.workdir_retention, disputed) {
+            Some(delay) => delay,
+            None => {
+                log::info!(
+                    "Keeping directory {} for agreement [{}] (cleanup policy: {}, disputed: {}).",
+                    workdir.display(),
+                    agreement_id,
+                    policy,
+                    disputed
+                );
+                return;
+            }
+        };
+
+        let remove = move || {
+            log::info!(
+                "Cleaning directory {} for agreement [{}].",
+                workdir.display(),
+                agreement_id
+            );
+            fs::remove_dir_all(workdir).ok();
+        };
+
+        if delay.is_zero() {
+            remove();
+        } else {
+            log::info!(
+                "Directory {} for agreement [{}] will be cleaned in {}.",
+                workdir.display(),
+                agreement_id,
+                humantime::format_duration(delay)
+            );
+            ctx.run_later(delay, move |_, _| remove());
+        }
+    }
+
+    /// Terminates activities of Agreements, which directories exceed storage negotiated in Agreement.
+    fn check_workdir_quotas(&mut self, ctx: &mut Context<Self>) {
+        let mut checked = self
+            .tasks
+            .iter()
+            .map(|task| task.agreement_id.clone())
+            .collect::<Vec<_>>();
+        checked.sort();
+        checked.dedup();
+
+        let checked = checked
+            .into_iter()
+            .filter_map(|agreement_id| {
+                let quota = workdir::storage_quota(self.active_agreements.get(&agreement_id)?)?;
+                Some((self.agreement_dir(&agreement_id), agreement_id, quota))
+            })
+            .collect::<Vec<_>>();
+        if checked.is_empty() {
+            return;
+        }
+
+        let measure = tokio::task::spawn_blocking(move || {
+            checked
+                .into_iter()
+                .map(|(dir, agreement_id, quota)| (agreement_id, workdir::dir_size(&dir), quota))
+                .filter(|(_, size, quota)| size > quota)
+                .collect::<Vec<_>>()
+        });
+
+        let future = async move { measure.await.unwrap_or_default() }
+            .into_actor(self)
+            .map(|exceeded, myself, ctx| {
+                for (agreement_id, size, quota) in exceeded {
+                    myself.on_workdir_quota_exceeded(agreement_id, size, quota, ctx);
+                }
+            });
+        ctx.spawn(future);
+    }
+
+    fn on_workdir_quota_exceeded(
+        &mut self,
+        agreement_id: String,
+        size: u64,
+        quota: u64,
+        ctx: &mut Context<Self>,
+    ) {
+        let message = format!(
+            "Working directory size {} exceeds negotiated storage {} bytes",
+            size, quota
+        );
+        log::warn!("Agreement [{}]: {}.", agreement_id, message);
+
+        let api = self.api.clone();
+        let retry_interval = self.config.exeunit_state_retry_interval;
+        let myself = ctx.address();
+        let activities = self.list_activities(&agreement_id);
+
+        let future = async move {
+            for activity_id in activities {
+                set_activity_terminated(
+                    api.clone(),
+                    &activity_id,
+                    "workdir quota exceeded",
+                    &message,
+                    retry_interval,
+                )
+                .await;
+                myself
+                    .send(DestroyActivity {
+                        activity_id,
+                        agreement_id: agreement_id.clone(),
+                    })
+                    .await
+                    .ok();
+            }
+        };
+        ctx.spawn(future.into_actor(self));
+    }
+
     #[logfn(Debug, fmt = "Task created: {}")]
     fn create_task(
         &self,
@@ -504,7 +812,12 @@ impl TaskRunner {
                 )
             })?;
 
-        Ok(Task::new(exeunit_instance, agreement_id, activity_id))
+        Ok(Task::new(
+            exeunit_instance,
+            agreement_id,
+            activity_id,
+            requestor_pub_key,
+        ))
     }
 
     fn save_agreement(&self, agreement_path: &Path, agreement_id: &str) -> Result<()> {
@@ -615,6 +928,12 @@ async fn remove_remaining_tasks(
 
 impl Actor for TaskRunner {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(self.config.workdir_quota_check_interval, |myself, ctx| {
+            myself.check_workdir_quotas(ctx)
+        });
+    }
 }
 
 forward_actix_handler!(TaskRunner, NewAgreement, on_agreement_approved);
@@ -622,6 +941,7 @@ forward_actix_handler!(TaskRunner, ExeUnitProcessFinished, on_exeunit_exited);
 forward_actix_handler!(TaskRunner, GetExeUnit, get_exeunit);
 actix_signal_handler!(TaskRunner, CreateActivity, activity_created);
 actix_signal_handler!(TaskRunner, ActivityDestroyed, activity_destroyed);
+actix_signal_handler!(TaskRunner, BreakAgreement, break_agreement);
 
 const PROPERTY_USAGE_VECTOR: &str = "golem.com.usage.vector";
 
@@ -633,16 +953,12 @@ impl Handler<GetOfferTemplates> for TaskRunner {
         let entries = (msg.0.into_iter())
             .map(|preset| {
                 let fut = self.offer_template(&preset.exeunit_name);
-                let coeffs = self
-                    .exeunit_coeffs(&preset.exeunit_name)
-                    .map(|mut coll| {
-                        coll.retain(|prop| preset.usage_coeffs.contains_key(prop));
-                        coll
-                    })
-                    .unwrap_or_else(|_| Default::default());
+                let coeffs = self.exeunit_coeffs(&preset).unwrap_or_default();
                 (preset, coeffs, fut)
             })
             .collect::<Vec<_>>();
+        let cleanup_policy = self.config.cleanup_policy();
+        let retention = self.config.workdir_retention;
 
         async move {
             for (preset, coeffs, fut) in entries {
@@ -666,6 +982,17 @@ impl Handler<GetOfferTemplates> for TaskRunner {
                     _ => anyhow::bail!("offer template: invalid usage vector format"),
                 }
 
+                template.set_property(
+                    workdir::PROPERTY_CLEANUP_POLICY,
+                    serde_json::Value::String(cleanup_policy.to_string()),
+                );
+                if cleanup_policy.removal_delay(retention, false) == Some(retention) {
+                    template.set_property(
+                        workdir::PROPERTY_RETENTION_SEC,
+                        serde_json::Value::from(retention.as_secs()),
+                    );
+                }
+
                 log::debug!("offer-template: {} = {:?}", preset.name, template);
                 result.insert(preset.name, template);
             }
@@ -807,25 +1134,18 @@ impl Handler<DestroyActivity> for TaskRunner {
     }
 }
 
-impl Handler<AgreementClosed> for TaskRunner {
-    type Result = ActorResponse<Self, Result<(), Error>>;
-
-    fn handle(&mut self, msg: AgreementClosed, ctx: &mut Context<Self>) -> Self::Result {
-        let agreement_id = msg.agreement_id;
+impl TaskRunner {
+    fn on_agreement_terminated(
+        &mut self,
+        agreement_id: String,
+        disputed: bool,
+        ctx: &mut Context<Self>,
+    ) -> ActorResponse<Self, Result<(), Error>> {
         let myself = ctx.address();
         let activities = self.list_activities(&agreement_id);
 
         self.active_agreements.remove(&agreement_id);
-
-        if self.config.auto_cleanup_agreement {
-            let workdir = self.agreement_dir(&agreement_id);
-            log::info!(
-                "Cleaning directory {} for agreement [{}].",
-                workdir.display(),
-                agreement_id
-            );
-            fs::remove_dir_all(workdir).ok();
-        }
+        self.cleanup_agreement_dir(agreement_id.clone(), disputed, ctx);
 
         // All activities should be destroyed by now, so it is only sanity call.
         let remove_future = async move {
@@ -837,20 +1157,21 @@ impl Handler<AgreementClosed> for TaskRunner {
     }
 }
 
+impl Handler<AgreementClosed> for TaskRunner {
+    type Result = ActorResponse<Self, Result<(), Error>>;
+
+    fn handle(&mut self, msg: AgreementClosed, ctx: &mut Context<Self>) -> Self::Result {
+        self.on_agreement_terminated(msg.agreement_id, false, ctx)
+    }
+}
+
 impl Handler<AgreementBroken> for TaskRunner {
-    type Result = ResponseFuture<Result<(), Error>>;
+    type Result = ActorResponse<Self, Result<(), Error>>;
 
     fn handle(&mut self, msg: AgreementBroken, ctx: &mut Context<Self>) -> Self::Result {
-        // We don't distinguish between `AgreementClosed` and `AgreementBroken`.
-        let addr = ctx.address();
-        async move {
-            addr.send(AgreementClosed {
-                agreement_id: msg.agreement_id,
-                send_terminate: false,
-            })
-            .await?
-        }
-        .boxed_local()
+        // Broken Agreements differ only in cleanup of working directory.
+        let disputed = workdir::is_dispute(&msg.reason);
+        self.on_agreement_terminated(msg.agreement_id, disputed, ctx)
     }
 }
 
@@ -0,0 +1,106 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString, EnumVariantNames};
+use walkdir::WalkDir;
+
+use ya_agreement_utils::AgreementView;
+
+use crate::market::termination_reason::BreakReason;
+
+pub const PROPERTY_CLEANUP_POLICY: &str = "golem.provider.workdir.cleanup-policy";
+pub const PROPERTY_RETENTION_SEC: &str = "golem.provider.workdir.retention-sec";
+
+const STORAGE_QUOTA_POINTER: &str = "/offer/properties/golem/inf/storage/gib";
+
+/// Decides what happens with Agreement working directory after Agreement ends.
+#[derive(
+    Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Display, EnumString, EnumVariantNames,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum CleanupPolicy {
+    /// Directory is never removed by TaskRunner.
+    Keep,
+    /// Directory is removed as soon as Agreement is terminated.
+    Immediate,
+    /// Directory is removed after retention period, so Requestor can still retrieve results.
+    Delayed,
+    /// Like `delayed`, but directories of Agreements broken because of payment
+    /// disputes are kept for investigation.
+    KeepOnDispute,
+}
+
+impl CleanupPolicy {
+    /// Time after which directory should be removed. `None` means directory is kept.
+    pub fn removal_delay(&self, retention: Duration, disputed: bool) -> Option<Duration> {
+        match self {
+            CleanupPolicy::Keep => None,
+            CleanupPolicy::Immediate => Some(Duration::ZERO),
+            CleanupPolicy::Delayed => Some(retention),
+            CleanupPolicy::KeepOnDispute if disputed => None,
+            CleanupPolicy::KeepOnDispute => Some(retention),
+        }
+    }
+}
+
+/// Agreement broken because Requestor didn't handle payments properly.
+pub fn is_dispute(reason: &BreakReason) -> bool {
+    matches!(
+        reason,
+        BreakReason::DebitNoteRejected(_)
+            | BreakReason::DebitNoteNotPaid(_)
+            | BreakReason::DebitNotesDeadline(_)
+    )
+}
+
+/// Storage negotiated in Agreement in bytes.
+pub fn storage_quota(agreement: &AgreementView) -> Option<u64> {
+    agreement
+        .pointer_typed::<f64>(STORAGE_QUOTA_POINTER)
+        .ok()
+        .filter(|gib| *gib > 0.)
+        .map(|gib| (gib * 1024. * 1024. * 1024.) as u64)
+}
+
+/// Total size of files in directory. Entries which can't be read are skipped.
+pub fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_removal_delay() {
+        let retention = Duration::from_secs(3600);
+        let policy = CleanupPolicy::from_str("keep-on-dispute").unwrap();
+
+        assert_eq!(policy.removal_delay(retention, false), Some(retention));
+        assert_eq!(policy.removal_delay(retention, true), None);
+        assert_eq!(
+            CleanupPolicy::Immediate.removal_delay(retention, true),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(CleanupPolicy::Keep.removal_delay(retention, false), None);
+    }
+
+    #[test]
+    fn test_dir_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a"), [0u8; 10]).unwrap();
+        std::fs::write(dir.path().join("sub").join("b"), [0u8; 5]).unwrap();
+
+        assert_eq!(dir_size(dir.path()), 15);
+    }
+}
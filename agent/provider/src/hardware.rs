@@ -135,6 +135,20 @@ impl Resources {
         })
     }
 
+    /// Slice of resources with given number of CPU threads. Memory and storage
+    /// are divided proportionally.
+    pub fn slice(&self, cpu_threads: i32) -> Option<Resources> {
+        if cpu_threads <= 0 || cpu_threads > self.cpu_threads {
+            return None;
+        }
+        let fraction = cpu_threads as f64 / self.cpu_threads as f64;
+        Some(Resources {
+            cpu_threads,
+            mem_gib: self.mem_gib * fraction,
+            storage_gib: self.storage_gib * fraction,
+        })
+    }
+
     pub fn depleted(&self) -> bool {
         self.cpu_threads <= 0 || self.mem_gib <= 0. || self.storage_gib <= 0.
     }
@@ -424,8 +438,38 @@ impl Manager {
         state.res_cap
     }
 
+    /// Handle sharing resources accounting with this `Manager`.
+    pub fn allocator(&self) -> Allocator {
+        Allocator {
+            state: self.state.clone(),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn allocate(&mut self, id: String, res: Resources) -> Result<(), Error> {
+        self.allocator().allocate(id, res)
+    }
+
+    #[allow(dead_code)]
+    pub fn release(&mut self, id: String) -> Result<(), Error> {
+        self.allocator().release(id)
+    }
+}
+
+/// Tracks resources allocated to running Agreements, so capacity of the host
+/// can be shared between multiple Offers.
+#[derive(Clone, Debug)]
+pub struct Allocator {
+    state: Arc<Mutex<ManagerState>>,
+}
+
+impl Allocator {
+    pub fn remaining(&self) -> Resources {
+        let state = self.state.lock().unwrap();
+        state.res_remaining
+    }
+
+    pub fn allocate(&self, id: String, res: Resources) -> Result<(), Error> {
         let mut state = self.state.lock().unwrap();
         if state.res_alloc.contains_key(&id) {
             return Err(Error::AlreadyAllocated(id));
@@ -438,8 +482,7 @@ impl Manager {
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub fn release(&mut self, id: String) -> Result<(), Error> {
+    pub fn release(&self, id: String) -> Result<(), Error> {
         let mut state = self.state.lock().unwrap();
         match state.res_alloc.remove(&id) {
             Some(res) => state.res_remaining = state.res_remaining + res,
@@ -573,6 +616,22 @@ mod tests {
         assert_eq!(res.storage_gib, 0.1);
     }
 
+    #[test]
+    fn slices() {
+        let res = Resources {
+            cpu_threads: 8,
+            mem_gib: 16.,
+            storage_gib: 200.,
+        };
+
+        let slice = res.slice(2).unwrap();
+        assert_eq!(slice.cpu_threads, 2);
+        assert_eq!(slice.mem_gib, 4.);
+        assert_eq!(slice.storage_gib, 50.);
+        assert!(res.slice(0).is_none());
+        assert!(res.slice(16).is_none());
+    }
+
     #[test]
     fn allocation() {
         let res = Resources {
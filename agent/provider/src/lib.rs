@@ -1,3 +1,4 @@
+pub mod benchmark;
 pub mod cli;
 pub mod config;
 pub mod dir;
@@ -11,6 +12,7 @@ pub mod payments;
 pub mod provider_agent;
 pub mod rules;
 pub mod signal;
+pub mod simulation;
 pub mod startup_config;
 pub mod tasks;
 
@@ -25,6 +25,7 @@ async fn main() -> anyhow::Result<()> {
 
     config.domain_whitelist_file = data_dir.join(config.domain_whitelist_file);
     config.globals_file = data_dir.join(config.globals_file);
+    config.config_profiles_file = data_dir.join(config.config_profiles_file);
     config.presets_file = data_dir.join(config.presets_file);
     config.hardware_file = data_dir.join(config.hardware_file);
     config.rules_file = data_dir.join(config.rules_file);
@@ -58,5 +59,6 @@ async fn main() -> anyhow::Result<()> {
         Commands::Whitelist(whitelist_cmd) => whitelist_cmd.run(config),
         Commands::Clean(clean_cmd) => clean_cmd.run(config),
         Commands::Rule(outbound_cmd) => outbound_cmd.run(config),
+        Commands::SimulateExeUnit(stub) => stub.run().await,
     }
 }
@@ -4,12 +4,13 @@ mod common;
 mod component;
 mod composite;
 pub mod factory;
+pub mod redaction;
 
 pub use accept_all::AcceptAllNegotiator;
 pub use composite::CompositeNegotiator;
 
 pub use common::{
-    AgreementResponse, AgreementResult, Negotiator, NegotiatorAddr, ProposalResponse,
+    AgreementResponse, AgreementResult, CreatedOffer, Negotiator, NegotiatorAddr, ProposalResponse,
 };
 
 pub use component::{NegotiationResult, NegotiatorComponent, NegotiatorsPack, ProposalView};
@@ -1,21 +1,22 @@
 use actix::{Actor, Context, Handler};
 
-use ya_client::model::market::NewOffer;
-
 use super::common::offer_definition_to_offer;
-use super::common::{AgreementResponse, Negotiator, ProposalResponse};
+use super::common::{AgreementResponse, CreatedOffer, Negotiator, ProposalResponse};
 use crate::market::negotiator::common::{
-    AgreementFinalized, CreateOffer, ReactToAgreement, ReactToProposal,
+    AgreementFinalized, CreateOffer, OfferUnsubscribed, ReactToAgreement, ReactToProposal,
 };
 
 #[derive(Debug, Default)]
 pub struct AcceptAllNegotiator;
 
 impl Handler<CreateOffer> for AcceptAllNegotiator {
-    type Result = anyhow::Result<NewOffer>;
+    type Result = anyhow::Result<CreatedOffer>;
 
     fn handle(&mut self, msg: CreateOffer, _: &mut Context<Self>) -> Self::Result {
-        Ok(offer_definition_to_offer(msg.offer_definition))
+        Ok(CreatedOffer {
+            offer: offer_definition_to_offer(msg.offer_definition),
+            precise: Default::default(),
+        })
     }
 }
 
@@ -43,6 +44,14 @@ impl Handler<AgreementFinalized> for AcceptAllNegotiator {
     }
 }
 
+impl Handler<OfferUnsubscribed> for AcceptAllNegotiator {
+    type Result = anyhow::Result<()>;
+
+    fn handle(&mut self, _: OfferUnsubscribed, _: &mut Context<Self>) -> Self::Result {
+        Ok(())
+    }
+}
+
 impl Negotiator for AcceptAllNegotiator {}
 impl Actor for AcceptAllNegotiator {
     type Context = Context<Self>;
@@ -1,16 +1,24 @@
 pub mod allow_only;
 pub mod blacklist;
+pub mod capacity;
 pub mod demand_validation;
+pub mod devices;
 pub mod expiration;
 pub mod manifest;
 pub mod max_agreements;
 pub mod note_interval;
 pub mod payment_timeout;
 pub mod price;
+pub mod requestor_limit;
+pub mod security_profile;
 
+pub use capacity::SharedCapacity;
+pub use devices::Devices;
 pub use expiration::LimitExpiration;
 pub use manifest::ManifestSignature;
 pub use max_agreements::MaxAgreements;
 pub use note_interval::DebitNoteInterval;
 pub use payment_timeout::PaymentTimeout;
 pub use price::PriceNego;
+pub use requestor_limit::RequestorAgreementsLimit;
+pub use security_profile::SecurityProfile;
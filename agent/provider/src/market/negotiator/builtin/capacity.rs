@@ -0,0 +1,113 @@
+use anyhow::bail;
+
+use ya_agreement_utils::Error;
+
+use crate::hardware::{Allocator, Resources};
+use crate::market::negotiator::{
+    AgreementResult, NegotiationResult, NegotiatorComponent, ProposalView,
+};
+
+pub const VARIANT_GROUP_PROPERTY_FLAT: &str = "golem.inf.variant-group";
+const VARIANT_GROUP_PROPERTY: &str = "/golem/inf/variant-group";
+const CPU_THREADS_PROPERTY: &str = "/golem/inf/cpu/threads";
+const MEM_PROPERTY: &str = "/golem/inf/mem/gib";
+const STORAGE_PROPERTY: &str = "/golem/inf/storage/gib";
+
+/// Negotiator accounting resources of Agreements made on Offer variants.
+/// Variants of the same preset are published as separate Offers, which share
+/// host capacity, so Proposals are rejected when the variant doesn't fit
+/// into resources remaining after already approved Agreements.
+pub struct SharedCapacity {
+    allocator: Allocator,
+    /// Resources of the last checked variant. `CompositeNegotiator` calls
+    /// `negotiate_step` directly before `on_agreement_approved`.
+    last_checked: Option<Resources>,
+}
+
+impl SharedCapacity {
+    pub fn new(allocator: Allocator) -> SharedCapacity {
+        SharedCapacity {
+            allocator,
+            last_checked: None,
+        }
+    }
+}
+
+fn variant_resources(offer: &ProposalView) -> anyhow::Result<Option<Resources>> {
+    match offer.pointer_typed::<String>(VARIANT_GROUP_PROPERTY) {
+        Ok(_) => (),
+        Err(Error::NoKey { .. }) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    Ok(Some(Resources {
+        cpu_threads: offer.pointer_typed(CPU_THREADS_PROPERTY)?,
+        mem_gib: offer.pointer_typed(MEM_PROPERTY)?,
+        storage_gib: offer.pointer_typed(STORAGE_PROPERTY)?,
+    }))
+}
+
+impl NegotiatorComponent for SharedCapacity {
+    fn negotiate_step(
+        &mut self,
+        demand: &ProposalView,
+        offer: ProposalView,
+    ) -> anyhow::Result<NegotiationResult> {
+        let required = match variant_resources(&offer)? {
+            Some(required) => required,
+            None => {
+                self.last_checked = None;
+                return Ok(NegotiationResult::Ready { offer });
+            }
+        };
+
+        let remaining = self.allocator.remaining();
+        if remaining < required {
+            log::info!(
+                "'SharedCapacity' negotiator: Reject proposal [{}]. Variant {:?} doesn't fit into remaining resources {:?}.",
+                demand.id,
+                required,
+                remaining,
+            );
+            self.last_checked = None;
+            return Ok(NegotiationResult::Reject {
+                message: format!(
+                    "No capacity available for variant with {} CPU threads",
+                    required.cpu_threads
+                ),
+                is_final: false,
+            });
+        }
+
+        self.last_checked = Some(required);
+        Ok(NegotiationResult::Ready { offer })
+    }
+
+    fn on_agreement_terminated(
+        &mut self,
+        agreement_id: &str,
+        _result: &AgreementResult,
+    ) -> anyhow::Result<()> {
+        // Agreements on Offers without variants have nothing allocated.
+        if self.allocator.release(agreement_id.to_string()).is_ok() {
+            log::info!(
+                "Negotiator: released resources of Agreement [{}]. Remaining: {:?}",
+                agreement_id,
+                self.allocator.remaining()
+            );
+        }
+        Ok(())
+    }
+
+    fn on_agreement_approved(&mut self, agreement_id: &str) -> anyhow::Result<()> {
+        if let Some(required) = self.last_checked.take() {
+            if let Err(e) = self.allocator.allocate(agreement_id.to_string(), required) {
+                bail!(
+                    "Agreement [{}] approved despite not available capacity: {}",
+                    agreement_id,
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+}
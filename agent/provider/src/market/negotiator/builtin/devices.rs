@@ -0,0 +1,163 @@
+use ya_agreement_utils::{Error, OfferDefinition};
+
+use crate::market::negotiator::factory::DevicesNegotiatorConfig;
+use crate::market::negotiator::{NegotiationResult, NegotiatorComponent, ProposalView};
+
+const DEVICES_PROPERTY_FLAT: &str = "golem.runtime.devices";
+pub const DEVICES_PROPERTY: &str = "/golem/runtime/devices";
+
+/// Negotiator offering host devices passthrough (e.g. `/dev/video0`).
+/// Only devices on the allowlist can be requested and the Offer is narrowed
+/// to devices requested by the Demand.
+pub struct Devices {
+    allowed: Vec<String>,
+}
+
+impl Devices {
+    pub fn new(config: &DevicesNegotiatorConfig) -> Devices {
+        let allowed = config
+            .allowed_devices
+            .iter()
+            .map(|device| device.trim().to_string())
+            .filter(|device| !device.is_empty())
+            .collect();
+        Self { allowed }
+    }
+}
+
+impl NegotiatorComponent for Devices {
+    fn negotiate_step(
+        &mut self,
+        demand: &ProposalView,
+        mut offer: ProposalView,
+    ) -> anyhow::Result<NegotiationResult> {
+        let requested = read_devices(demand)?;
+        let offered = read_devices(&offer)?;
+
+        let forbidden = requested
+            .iter()
+            .filter(|device| !self.allowed.contains(device))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !forbidden.is_empty() {
+            log::info!(
+                "'Devices' negotiator: Reject proposal [{}] due to not allowed devices: {}",
+                demand.id,
+                forbidden.join(",")
+            );
+            return Ok(NegotiationResult::Reject {
+                message: format!("Devices not allowed: {}", forbidden.join(",")),
+                is_final: true,
+            });
+        }
+
+        if requested == offered {
+            return Ok(NegotiationResult::Ready { offer });
+        }
+
+        if requested.is_empty() {
+            let _ = offer.remove_property(DEVICES_PROPERTY);
+        } else {
+            let value = serde_json::to_value(requested)?;
+            match offer.pointer_mut(DEVICES_PROPERTY) {
+                Some(property) => *property = value,
+                None => anyhow::bail!("Devices not found in the Offer"),
+            }
+        }
+        Ok(NegotiationResult::Negotiating { offer })
+    }
+
+    fn fill_template(
+        &mut self,
+        mut offer_template: OfferDefinition,
+    ) -> anyhow::Result<OfferDefinition> {
+        if !self.allowed.is_empty() {
+            offer_template
+                .offer
+                .set_property(DEVICES_PROPERTY_FLAT, serde_json::to_value(&self.allowed)?);
+        }
+        Ok(offer_template)
+    }
+}
+
+fn read_devices(proposal: &ProposalView) -> anyhow::Result<Vec<String>> {
+    match proposal.pointer_typed::<Vec<String>>(DEVICES_PROPERTY) {
+        Ok(devices) => Ok(devices),
+        Err(Error::NoKey { .. }) => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+    use ya_agreement_utils::agreement::expand;
+    use ya_agreement_utils::OfferTemplate;
+    use ya_client_model::market::proposal::State;
+
+    fn config() -> DevicesNegotiatorConfig {
+        DevicesNegotiatorConfig {
+            allowed_devices: vec!["/dev/video0".to_string(), "/dev/ttyUSB0".to_string()],
+        }
+    }
+
+    fn properties_to_proposal(properties: serde_json::Value) -> ProposalView {
+        ProposalView {
+            content: OfferTemplate {
+                properties: expand(properties),
+                constraints: "()".to_string(),
+            },
+            id: "proposalId".to_string(),
+            issuer: Default::default(),
+            state: State::Initial,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Negotiator narrows Offer to requested devices
+    #[test]
+    fn test_requested_devices_allowed() {
+        let mut negotiator = Devices::new(&config());
+
+        let offer = properties_to_proposal(json!({
+            "golem.runtime.devices": ["/dev/video0", "/dev/ttyUSB0"],
+        }));
+        let demand = properties_to_proposal(json!({
+            "golem.runtime.devices": ["/dev/ttyUSB0"],
+        }));
+
+        match negotiator.negotiate_step(&demand, offer).unwrap() {
+            NegotiationResult::Negotiating { offer } => assert_eq!(
+                offer
+                    .pointer_typed::<Vec<String>>(DEVICES_PROPERTY)
+                    .unwrap(),
+                vec!["/dev/ttyUSB0".to_string()]
+            ),
+            result => panic!("Expected Negotiating, got {result:?}"),
+        }
+    }
+
+    /// Negotiator rejects demand requesting devices outside of allowlist
+    #[test]
+    fn test_requested_devices_not_allowed() {
+        let mut negotiator = Devices::new(&config());
+
+        let offer = properties_to_proposal(json!({
+            "golem.runtime.devices": ["/dev/video0", "/dev/ttyUSB0"],
+        }));
+        let demand = properties_to_proposal(json!({
+            "golem.runtime.devices": ["/dev/video0", "/dev/sda"],
+        }));
+
+        let expected_result = NegotiationResult::Reject {
+            message: "Devices not allowed: /dev/sda".to_string(),
+            is_final: true,
+        };
+        assert_eq!(
+            negotiator.negotiate_step(&demand, offer).unwrap(),
+            expected_result
+        );
+    }
+}
@@ -8,8 +8,10 @@ use ya_manifest_utils::{
     DEMAND_MANIFEST_SIG_ALGORITHM_PROPERTY, DEMAND_MANIFEST_SIG_PROPERTY,
 };
 
+use crate::market::negotiator::builtin::devices::DEVICES_PROPERTY;
 use crate::market::negotiator::*;
 use crate::provider_agent::AgentNegotiatorsConfig;
+use crate::rules::policy::ManifestFeature;
 use crate::rules::{ManifestSignatureProps, RulesManager};
 
 pub struct ManifestSignature {
@@ -66,6 +68,12 @@ impl NegotiatorComponent for ManifestSignature {
             .get_property::<serde_json::Value>(DEMAND_MANIFEST_NODE_DESCRIPTOR_PROPERTY)
             .ok();
 
+        let devices_requested = demand
+            .pointer_typed::<Vec<String>>(DEVICES_PROPERTY)
+            .map(|devices| devices.is_empty().not())
+            .unwrap_or(false);
+        let features = ManifestFeature::declared(&manifest, devices_requested);
+
         if let Some(outbound_access) = manifest.get_outbound_access() {
             if outbound_access.is_outbound_requested() {
                 return match self.rules_manager.check_outbound_rules(
@@ -73,6 +81,7 @@ impl NegotiatorComponent for ManifestSignature {
                     demand.issuer,
                     manifest_sig,
                     node_descriptor,
+                    features,
                 ) {
                     crate::rules::CheckRulesResult::Accept => acceptance(offer),
                     crate::rules::CheckRulesResult::Reject(msg) => rejection(msg),
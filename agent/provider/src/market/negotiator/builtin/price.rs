@@ -1,13 +1,105 @@
-use crate::market::negotiator::factory::AgreementExpirationNegotiatorConfig;
-use crate::market::negotiator::{NegotiationResult, NegotiatorComponent, ProposalView};
+use anyhow::{anyhow, bail};
+use serde_json::json;
+use std::collections::HashSet;
+use std::str::FromStr;
 
-pub struct PriceNego {}
+use crate::market::negotiator::factory::{LimitAgreementsNegotiatorConfig, PriceNegotiatorConfig};
+use crate::market::negotiator::{
+    AgreementResult, NegotiationResult, NegotiatorComponent, ProposalView,
+};
 
 static PRICE_PROPERTY: &str = "/golem/com/pricing/model/linear/coeffs";
+static PRICING_PROPERTY: &str = "/golem/com/pricing";
+/// Multiplier already applied to prices of the Offer in previous counter Proposal.
+/// Present only in counter Proposals.
+static SURGE_MULTIPLIER: &str = "surge-multiplier";
+
+/// Price multiplier used, when utilization of Agreement slots reaches the threshold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurgeRule {
+    /// Fraction of Agreement slots in use, between 0 and 1.
+    pub utilization: f64,
+    pub multiplier: f64,
+}
+
+impl FromStr for SurgeRule {
+    type Err = anyhow::Error;
+
+    /// Parses `<utilization>%=<multiplier>`, e.g. `80%=1.5`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (utilization, multiplier) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected <utilization>%=<multiplier>, got: {s}"))?;
+        let utilization: f64 = utilization.trim().trim_end_matches('%').parse()?;
+        let multiplier: f64 = multiplier.trim().parse()?;
+
+        if !(0.0..=100.0).contains(&utilization) {
+            bail!("Utilization has to be between 0% and 100%, got: {utilization}%");
+        }
+        if !multiplier.is_finite() || multiplier <= 0.0 {
+            bail!("Price multiplier has to be positive, got: {multiplier}");
+        }
+        Ok(SurgeRule {
+            utilization: utilization / 100.0,
+            multiplier,
+        })
+    }
+}
+
+/// Negotiates linear pricing coefficients. Proposals paying at least Offer prices
+/// are accepted, cheaper ones are rejected. With auto-counter enabled, cheaper
+/// Proposals are countered with Offer prices multiplied by the surge rule matching
+/// current utilization of Agreement slots. Proposal is rejected only if Requestor
+/// doesn't accept the counter.
+pub struct PriceNego {
+    auto_counter: bool,
+    /// Sorted by utilization threshold.
+    surge: Vec<SurgeRule>,
+    max_agreements: u32,
+    active_agreements: HashSet<String>,
+}
 
 impl PriceNego {
-    pub fn new(_config: &AgreementExpirationNegotiatorConfig) -> anyhow::Result<Self> {
-        Ok(PriceNego {})
+    pub fn new(
+        config: &PriceNegotiatorConfig,
+        limit_config: &LimitAgreementsNegotiatorConfig,
+    ) -> PriceNego {
+        let mut surge = config.price_surge.clone();
+        surge.sort_by(|a, b| a.utilization.total_cmp(&b.utilization));
+
+        PriceNego {
+            auto_counter: config.auto_counter || !surge.is_empty(),
+            surge,
+            max_agreements: limit_config.max_simultaneous_agreements,
+            active_agreements: HashSet::new(),
+        }
+    }
+
+    fn utilization(&self) -> f64 {
+        match self.max_agreements {
+            0 => 1.0,
+            max => self.active_agreements.len() as f64 / max as f64,
+        }
+    }
+
+    /// Multiplier of the highest threshold reached.
+    fn multiplier(&self) -> f64 {
+        let utilization = self.utilization();
+        self.surge
+            .iter()
+            .rev()
+            .find(|rule| utilization >= rule.utilization)
+            .map(|rule| rule.multiplier)
+            .unwrap_or(1.0)
+    }
+}
+
+fn set_multiplier(offer: &mut ProposalView, multiplier: f64) {
+    if let Some(pricing) = offer
+        .pointer_mut(PRICING_PROPERTY)
+        .and_then(|pricing| pricing.as_object_mut())
+    {
+        pricing.insert(SURGE_MULTIPLIER.to_string(), json!(multiplier));
     }
 }
 
@@ -17,36 +109,216 @@ impl NegotiatorComponent for PriceNego {
         demand: &ProposalView,
         mut offer: ProposalView,
     ) -> anyhow::Result<NegotiationResult> {
-        if let (Ok(demand_prices), Ok(offer_prices)) = (
+        let (demand_prices, offer_prices) = match (
             demand.pointer_typed::<Vec<f64>>(PRICE_PROPERTY),
             offer.pointer_typed::<Vec<f64>>(PRICE_PROPERTY),
         ) {
+            (Ok(demand_prices), Ok(offer_prices)) => (demand_prices, offer_prices),
+            _ => return Ok(NegotiationResult::Ready { offer }),
+        };
+        if demand_prices.len() != offer_prices.len() {
+            return Ok(NegotiationResult::Reject {
+                message: "invalid price vector".to_string(),
+                is_final: false,
+            });
+        }
+
+        // Prices of our previous counter Proposal include the multiplier applied then.
+        let countered = offer
+            .pointer_typed::<f64>(&format!("{PRICING_PROPERTY}/{SURGE_MULTIPLIER}"))
+            .ok();
+        let applied = countered.unwrap_or(1.0);
+        let multiplier = match self.auto_counter {
+            true => self.multiplier(),
+            false => 1.0,
+        };
+        let expected = match applied == multiplier {
+            true => offer_prices.clone(),
+            false => offer_prices
+                .iter()
+                .map(|price| price / applied * multiplier)
+                .collect(),
+        };
+
+        if demand_prices.iter().zip(&expected).all(|(dp, ep)| dp >= ep) {
             if demand_prices == offer_prices {
                 return Ok(NegotiationResult::Ready { offer });
             }
-            if demand_prices.len() != offer_prices.len() {
-                return Ok(NegotiationResult::Reject {
-                    message: "invalid price vector".to_string(),
-                    is_final: false,
-                });
+            if let Some(p) = offer.pointer_mut(PRICE_PROPERTY) {
+                *p = demand.pointer(PRICE_PROPERTY).unwrap().clone();
             }
-            if demand_prices
-                .iter()
-                .zip(&offer_prices)
-                .all(|(dp, op)| dp >= op)
-            {
-                if let Some(p) = offer.pointer_mut(PRICE_PROPERTY) {
-                    *p = demand.pointer(PRICE_PROPERTY).unwrap().clone();
-                }
-                Ok(NegotiationResult::Negotiating { offer })
-            } else {
-                Ok(NegotiationResult::Reject {
-                    message: format!("{:?} < {:?}", demand_prices, offer_prices),
-                    is_final: true,
-                })
+            Ok(NegotiationResult::Negotiating { offer })
+        } else if self.auto_counter
+            // Requestor, which didn't accept our counter, gets rejected, unless prices changed.
+            && (offer_prices != expected || countered.is_none())
+        {
+            log::info!(
+                "'Price' negotiator: Countering proposal [{}] with prices {:?} (utilization: {:.0}%, multiplier: {}).",
+                demand.id,
+                expected,
+                self.utilization() * 100.0,
+                multiplier,
+            );
+            if let Some(p) = offer.pointer_mut(PRICE_PROPERTY) {
+                *p = json!(expected);
             }
+            set_multiplier(&mut offer, multiplier);
+            Ok(NegotiationResult::Negotiating { offer })
         } else {
-            Ok(NegotiationResult::Ready { offer })
+            Ok(NegotiationResult::Reject {
+                message: format!("{:?} < {:?}", demand_prices, expected),
+                is_final: true,
+            })
+        }
+    }
+
+    fn on_agreement_terminated(
+        &mut self,
+        agreement_id: &str,
+        _result: &AgreementResult,
+    ) -> anyhow::Result<()> {
+        self.active_agreements.remove(agreement_id);
+        Ok(())
+    }
+
+    fn on_agreement_approved(&mut self, agreement_id: &str) -> anyhow::Result<()> {
+        self.active_agreements.insert(agreement_id.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use ya_agreement_utils::agreement::expand;
+    use ya_agreement_utils::OfferTemplate;
+    use ya_client_model::market::proposal::State;
+
+    fn proposal(coeffs: &[f64]) -> ProposalView {
+        ProposalView {
+            content: OfferTemplate {
+                properties: expand(json!({
+                    "golem.com.pricing.model": "linear",
+                    "golem.com.pricing.model.linear.coeffs": coeffs,
+                })),
+                constraints: "()".to_string(),
+            },
+            id: "proposalId".to_string(),
+            issuer: Default::default(),
+            state: State::Initial,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn negotiator(auto_counter: bool, surge: &str) -> PriceNego {
+        let config = PriceNegotiatorConfig {
+            auto_counter,
+            price_surge: surge
+                .split(',')
+                .filter(|rule| !rule.is_empty())
+                .map(|rule| rule.parse().unwrap())
+                .collect(),
+        };
+        PriceNego::new(
+            &config,
+            &LimitAgreementsNegotiatorConfig {
+                max_simultaneous_agreements: 5,
+                max_agreements_per_requestor: None,
+            },
+        )
+    }
+
+    fn prices(result: &NegotiationResult) -> Vec<f64> {
+        match result {
+            NegotiationResult::Negotiating { offer } | NegotiationResult::Ready { offer } => {
+                offer.pointer_typed(PRICE_PROPERTY).unwrap()
+            }
+            result => panic!("Expected counter Proposal, got {result:?}"),
         }
     }
+
+    #[test]
+    fn test_surge_rule_parsing() {
+        assert_eq!(
+            "80%=1.5".parse::<SurgeRule>().unwrap(),
+            SurgeRule {
+                utilization: 0.8,
+                multiplier: 1.5
+            }
+        );
+        assert!("80%".parse::<SurgeRule>().is_err());
+        assert!("120%=2".parse::<SurgeRule>().is_err());
+        assert!("80%=0".parse::<SurgeRule>().is_err());
+    }
+
+    /// Without auto-counter cheaper Proposals are rejected.
+    #[test]
+    fn test_reject_cheaper_proposal() {
+        let mut negotiator = negotiator(false, "");
+        let result = negotiator
+            .negotiate_step(&proposal(&[0.5, 1.0]), proposal(&[1.0, 1.0]))
+            .unwrap();
+        assert!(matches!(
+            result,
+            NegotiationResult::Reject { is_final: true, .. }
+        ));
+    }
+
+    /// Cheaper Proposal is countered with Offer prices once, then rejected.
+    #[test]
+    fn test_auto_counter_cheaper_proposal() {
+        let mut negotiator = negotiator(true, "");
+        let result = negotiator
+            .negotiate_step(&proposal(&[0.5, 1.0]), proposal(&[1.0, 1.0]))
+            .unwrap();
+        assert_eq!(prices(&result), vec![1.0, 1.0]);
+
+        // Requestor answers our counter with the same prices.
+        let NegotiationResult::Negotiating { offer } = result else {
+            panic!("Expected counter Proposal");
+        };
+        let result = negotiator
+            .negotiate_step(&proposal(&[0.5, 1.0]), offer)
+            .unwrap();
+        assert!(matches!(
+            result,
+            NegotiationResult::Reject { is_final: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_surge_multiplier_above_utilization() {
+        let mut negotiator = negotiator(false, "80%=1.5,40%=1.2");
+        let result = negotiator
+            .negotiate_step(&proposal(&[1.0, 2.0]), proposal(&[1.0, 2.0]))
+            .unwrap();
+        assert!(matches!(result, NegotiationResult::Ready { .. }));
+
+        for id in 0..4 {
+            negotiator.on_agreement_approved(&id.to_string()).unwrap();
+        }
+        let result = negotiator
+            .negotiate_step(&proposal(&[1.0, 2.0]), proposal(&[1.0, 2.0]))
+            .unwrap();
+        assert_eq!(prices(&result), vec![1.5, 3.0]);
+
+        // Requestor accepts the counter.
+        let NegotiationResult::Negotiating { offer } = result else {
+            panic!("Expected counter Proposal");
+        };
+        let result = negotiator
+            .negotiate_step(&proposal(&[1.5, 3.0]), offer.clone())
+            .unwrap();
+        assert!(matches!(result, NegotiationResult::Ready { .. }));
+
+        // Utilization dropped, multiplier isn't applied twice.
+        negotiator
+            .on_agreement_terminated("0", &AgreementResult::ClosedByRequestor)
+            .unwrap();
+        let result = negotiator
+            .negotiate_step(&proposal(&[1.0, 2.0]), offer)
+            .unwrap();
+        assert_eq!(prices(&result), vec![1.2, 2.4]);
+    }
 }
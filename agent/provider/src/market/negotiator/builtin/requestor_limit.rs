@@ -0,0 +1,180 @@
+use serde_json::json;
+use std::collections::HashMap;
+
+use ya_client_model::NodeId;
+
+use crate::market::negotiator::factory::LimitAgreementsNegotiatorConfig;
+use crate::market::negotiator::{
+    AgreementResult, NegotiationResult, NegotiatorComponent, ProposalView,
+};
+
+pub const REJECTION_CODE: &str = "requestor-agreements-limit";
+
+/// Negotiator limiting number of simultaneous Agreements with a single Requestor,
+/// so that one Requestor can't occupy whole Provider's capacity.
+pub struct RequestorAgreementsLimit {
+    limit: Option<u32>,
+    /// Requestors of active Agreements.
+    agreements: HashMap<String, NodeId>,
+    /// Requestor of the last accepted Proposal. `CompositeNegotiator` calls
+    /// `negotiate_step` directly before `on_agreement_approved`.
+    last_checked: Option<NodeId>,
+}
+
+impl RequestorAgreementsLimit {
+    pub fn new(config: &LimitAgreementsNegotiatorConfig) -> RequestorAgreementsLimit {
+        RequestorAgreementsLimit {
+            limit: config.max_agreements_per_requestor,
+            agreements: HashMap::new(),
+            last_checked: None,
+        }
+    }
+
+    fn active_agreements(&self, requestor: &NodeId) -> usize {
+        self.agreements
+            .values()
+            .filter(|node_id| *node_id == requestor)
+            .count()
+    }
+}
+
+impl NegotiatorComponent for RequestorAgreementsLimit {
+    fn negotiate_step(
+        &mut self,
+        demand: &ProposalView,
+        offer: ProposalView,
+    ) -> anyhow::Result<NegotiationResult> {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return Ok(NegotiationResult::Ready { offer }),
+        };
+
+        if self.active_agreements(&demand.issuer) >= limit as usize {
+            log::info!(
+                "'RequestorAgreementsLimit' negotiator: Reject proposal [{}]. Requestor [{}] reached Agreements limit: {}",
+                demand.id,
+                demand.issuer,
+                limit
+            );
+            self.last_checked = None;
+            return Ok(NegotiationResult::RejectWithReason {
+                message: format!("Reached limit of {limit} simultaneous Agreements per Requestor"),
+                extra: json!({
+                    "golem.proposal.rejection.code": REJECTION_CODE,
+                    "golem.proposal.rejection.requestor-agreements-limit": limit,
+                }),
+                is_final: false,
+            });
+        }
+
+        self.last_checked = Some(demand.issuer);
+        Ok(NegotiationResult::Ready { offer })
+    }
+
+    fn on_agreement_terminated(
+        &mut self,
+        agreement_id: &str,
+        _result: &AgreementResult,
+    ) -> anyhow::Result<()> {
+        self.agreements.remove(agreement_id);
+        Ok(())
+    }
+
+    fn on_agreement_approved(&mut self, agreement_id: &str) -> anyhow::Result<()> {
+        if let Some(requestor) = self.last_checked.take() {
+            self.agreements.insert(agreement_id.to_string(), requestor);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+    use ya_agreement_utils::OfferTemplate;
+    use ya_client_model::market::proposal::State;
+
+    fn config(limit: Option<u32>) -> LimitAgreementsNegotiatorConfig {
+        LimitAgreementsNegotiatorConfig {
+            max_simultaneous_agreements: 10,
+            max_agreements_per_requestor: limit,
+        }
+    }
+
+    fn proposal(issuer: NodeId) -> ProposalView {
+        ProposalView {
+            content: OfferTemplate {
+                properties: json!({}),
+                constraints: "()".to_string(),
+            },
+            id: "proposalId".to_string(),
+            issuer,
+            state: State::Initial,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn node_id(n: u8) -> NodeId {
+        format!("0x{:040x}", n).parse().unwrap()
+    }
+
+    fn is_ready(result: NegotiationResult) -> bool {
+        matches!(result, NegotiationResult::Ready { .. })
+    }
+
+    /// Agreements above the limit are rejected only for the Requestor holding them.
+    #[test]
+    fn test_limit_per_requestor() {
+        let mut negotiator = RequestorAgreementsLimit::new(&config(Some(1)));
+        let requestor = node_id(1);
+        let other = node_id(2);
+
+        let result = negotiator
+            .negotiate_step(&proposal(requestor), proposal(Default::default()))
+            .unwrap();
+        assert!(is_ready(result));
+        negotiator.on_agreement_approved("agreement-1").unwrap();
+
+        match negotiator
+            .negotiate_step(&proposal(requestor), proposal(Default::default()))
+            .unwrap()
+        {
+            NegotiationResult::RejectWithReason {
+                extra, is_final, ..
+            } => {
+                assert!(!is_final);
+                assert_eq!(extra["golem.proposal.rejection.code"], REJECTION_CODE);
+            }
+            result => panic!("Expected RejectWithReason, got {result:?}"),
+        }
+
+        let result = negotiator
+            .negotiate_step(&proposal(other), proposal(Default::default()))
+            .unwrap();
+        assert!(is_ready(result));
+
+        negotiator
+            .on_agreement_terminated("agreement-1", &AgreementResult::ClosedByRequestor)
+            .unwrap();
+        let result = negotiator
+            .negotiate_step(&proposal(requestor), proposal(Default::default()))
+            .unwrap();
+        assert!(is_ready(result));
+    }
+
+    #[test]
+    fn test_no_limit_by_default() {
+        let mut negotiator = RequestorAgreementsLimit::new(&config(None));
+        let requestor = node_id(1);
+
+        for id in ["agreement-1", "agreement-2"] {
+            let result = negotiator
+                .negotiate_step(&proposal(requestor), proposal(Default::default()))
+                .unwrap();
+            assert!(is_ready(result));
+            negotiator.on_agreement_approved(id).unwrap();
+        }
+    }
+}
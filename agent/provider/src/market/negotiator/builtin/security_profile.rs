@@ -0,0 +1,185 @@
+use anyhow::bail;
+use ya_agreement_utils::{Error, OfferDefinition};
+
+use crate::market::negotiator::factory::SecurityProfileNegotiatorConfig;
+use crate::market::negotiator::{NegotiationResult, NegotiatorComponent, ProposalView};
+
+const PROFILES_PROPERTY_FLAT: &str = "golem.runtime.security.profiles";
+const PROFILE_PROPERTY_FLAT: &str = "golem.runtime.security.profile";
+pub const PROFILE_PROPERTY: &str = "/golem/runtime/security/profile";
+
+/// Profiles understood by ExeUnit. `custom` profile is read from file
+/// configured for ExeUnit with `EXE_UNIT_SECURITY_PROFILE_FILE`.
+const KNOWN_PROFILES: [&str; 4] = ["strict", "default", "permissive", "custom"];
+
+/// Negotiator of seccomp/AppArmor profile, which ExeUnit applies to the runtime.
+/// Offer lists allowed profiles and is narrowed to the profile requested by the Demand.
+pub struct SecurityProfile {
+    allowed: Vec<String>,
+    default: String,
+}
+
+impl SecurityProfile {
+    pub fn new(config: &SecurityProfileNegotiatorConfig) -> anyhow::Result<SecurityProfile> {
+        let allowed = config
+            .allowed_security_profiles
+            .iter()
+            .map(|profile| profile.trim().to_lowercase())
+            .filter(|profile| !profile.is_empty())
+            .collect::<Vec<_>>();
+        let default = config.default_security_profile.trim().to_lowercase();
+
+        if let Some(unknown) = allowed
+            .iter()
+            .find(|profile| !KNOWN_PROFILES.contains(&profile.as_str()))
+        {
+            bail!(
+                "Unknown security profile: {unknown}. Expected one of: {}",
+                KNOWN_PROFILES.join(",")
+            );
+        }
+        if !allowed.contains(&default) {
+            bail!("Default security profile {default} is not on the allowed list");
+        }
+        Ok(Self { allowed, default })
+    }
+}
+
+impl NegotiatorComponent for SecurityProfile {
+    fn negotiate_step(
+        &mut self,
+        demand: &ProposalView,
+        mut offer: ProposalView,
+    ) -> anyhow::Result<NegotiationResult> {
+        let requested = read_profile(demand)?.unwrap_or_else(|| self.default.clone());
+        if !self.allowed.contains(&requested) {
+            log::info!(
+                "'SecurityProfile' negotiator: Reject proposal [{}] due to not allowed security profile: {}",
+                demand.id,
+                requested
+            );
+            return Ok(NegotiationResult::Reject {
+                message: format!("Security profile not allowed: {requested}"),
+                is_final: true,
+            });
+        }
+
+        if read_profile(&offer)?.as_ref() == Some(&requested) {
+            return Ok(NegotiationResult::Ready { offer });
+        }
+
+        match offer.pointer_mut(PROFILE_PROPERTY) {
+            Some(property) => *property = serde_json::Value::String(requested),
+            None => bail!("Security profile not found in the Offer"),
+        }
+        Ok(NegotiationResult::Negotiating { offer })
+    }
+
+    fn fill_template(
+        &mut self,
+        mut offer_template: OfferDefinition,
+    ) -> anyhow::Result<OfferDefinition> {
+        offer_template
+            .offer
+            .set_property(PROFILES_PROPERTY_FLAT, serde_json::to_value(&self.allowed)?);
+        offer_template
+            .offer
+            .set_property(PROFILE_PROPERTY_FLAT, serde_json::to_value(&self.default)?);
+        Ok(offer_template)
+    }
+}
+
+fn read_profile(proposal: &ProposalView) -> anyhow::Result<Option<String>> {
+    match proposal.pointer_typed::<String>(PROFILE_PROPERTY) {
+        Ok(profile) => Ok(Some(profile.to_lowercase())),
+        Err(Error::NoKey { .. }) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+    use ya_agreement_utils::agreement::expand;
+    use ya_agreement_utils::OfferTemplate;
+    use ya_client_model::market::proposal::State;
+
+    fn config() -> SecurityProfileNegotiatorConfig {
+        SecurityProfileNegotiatorConfig {
+            allowed_security_profiles: vec!["strict".to_string(), "default".to_string()],
+            default_security_profile: "default".to_string(),
+        }
+    }
+
+    fn properties_to_proposal(properties: serde_json::Value) -> ProposalView {
+        ProposalView {
+            content: OfferTemplate {
+                properties: expand(properties),
+                constraints: "()".to_string(),
+            },
+            id: "proposalId".to_string(),
+            issuer: Default::default(),
+            state: State::Initial,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Negotiator narrows Offer to requested profile
+    #[test]
+    fn test_requested_profile_allowed() {
+        let mut negotiator = SecurityProfile::new(&config()).unwrap();
+
+        let offer = properties_to_proposal(json!({
+            "golem.runtime.security.profiles": ["strict", "default"],
+            "golem.runtime.security.profile": "default",
+        }));
+        let demand = properties_to_proposal(json!({
+            "golem.runtime.security.profile": "strict",
+        }));
+
+        match negotiator.negotiate_step(&demand, offer).unwrap() {
+            NegotiationResult::Negotiating { offer } => assert_eq!(
+                offer.pointer_typed::<String>(PROFILE_PROPERTY).unwrap(),
+                "strict"
+            ),
+            result => panic!("Expected Negotiating, got {result:?}"),
+        }
+    }
+
+    /// Negotiator rejects demand requesting profile outside of allowed list
+    #[test]
+    fn test_requested_profile_not_allowed() {
+        let mut negotiator = SecurityProfile::new(&config()).unwrap();
+
+        let offer = properties_to_proposal(json!({
+            "golem.runtime.security.profiles": ["strict", "default"],
+            "golem.runtime.security.profile": "default",
+        }));
+        let demand = properties_to_proposal(json!({
+            "golem.runtime.security.profile": "permissive",
+        }));
+
+        let expected_result = NegotiationResult::Reject {
+            message: "Security profile not allowed: permissive".to_string(),
+            is_final: true,
+        };
+        assert_eq!(
+            negotiator.negotiate_step(&demand, offer).unwrap(),
+            expected_result
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut config = config();
+        config.default_security_profile = "permissive".to_string();
+        assert!(SecurityProfile::new(&config).is_err());
+
+        config
+            .allowed_security_profiles
+            .push("unconfined".to_string());
+        assert!(SecurityProfile::new(&config).is_err());
+    }
+}
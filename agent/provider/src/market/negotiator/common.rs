@@ -2,6 +2,7 @@ use actix::prelude::*;
 use actix::{Actor, Handler};
 use anyhow::Result;
 use derive_more::Display;
+use serde_json::{Map, Value};
 
 use ya_agreement_utils::{AgreementView, OfferDefinition};
 use ya_client::model::market::Reason;
@@ -60,11 +61,21 @@ pub enum AgreementResult {
 /// Negotiator can modify offer, that was generated for him. He can save
 /// information about this offer, that are necessary for negotiations.
 #[derive(Message)]
-#[rtype(result = "Result<NewOffer>")]
+#[rtype(result = "Result<CreatedOffer>")]
 pub struct CreateOffer {
     pub offer_definition: OfferDefinition,
 }
 
+/// Offer created by Negotiator.
+#[derive(Clone, Debug)]
+pub struct CreatedOffer {
+    /// Offer published on market.
+    pub offer: NewOffer,
+    /// Precise values of properties redacted in the published Offer. They are kept
+    /// with the subscription and passed back with each Proposal.
+    pub precise: Map<String, Value>,
+}
+
 /// Reactions to events from market. These function make market decisions
 /// related to incoming Proposals.
 #[derive(Message)]
@@ -72,6 +83,9 @@ pub struct CreateOffer {
 pub struct ReactToProposal {
     pub prev_proposal: Proposal,
     pub demand: Proposal,
+    pub subscription_id: String,
+    /// Precise values of properties redacted in the Offer of the subscription.
+    pub precise: Map<String, Value>,
 }
 
 /// Reactions to events from market. These function make market decisions
@@ -79,6 +93,7 @@ pub struct ReactToProposal {
 #[derive(Message)]
 #[rtype(result = "Result<AgreementResponse>")]
 pub struct ReactToAgreement {
+    pub subscription_id: String,
     pub agreement: AgreementView,
 }
 
@@ -90,6 +105,14 @@ pub struct AgreementFinalized {
     pub result: AgreementResult,
 }
 
+/// Offer was unsubscribed from market. Negotiator should drop state kept for
+/// the subscription.
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct OfferUnsubscribed {
+    pub subscription_id: String,
+}
+
 /// Actor implementing Negotiation logic.
 ///
 /// Direction:
@@ -112,6 +135,7 @@ pub trait Negotiator:
     + Handler<AgreementFinalized, Result = <AgreementFinalized as Message>::Result>
     + Handler<ReactToProposal, Result = <ReactToProposal as Message>::Result>
     + Handler<ReactToAgreement, Result = <ReactToAgreement as Message>::Result>
+    + Handler<OfferUnsubscribed, Result = <OfferUnsubscribed as Message>::Result>
 {
 }
 
@@ -126,10 +150,11 @@ pub struct NegotiatorAddr {
     pub on_finalized: Recipient<AgreementFinalized>,
     pub on_proposal: Recipient<ReactToProposal>,
     pub on_agreement: Recipient<ReactToAgreement>,
+    pub on_unsubscribed: Recipient<OfferUnsubscribed>,
 }
 
 impl NegotiatorAddr {
-    pub async fn create_offer(&self, offer_definition: &OfferDefinition) -> Result<NewOffer> {
+    pub async fn create_offer(&self, offer_definition: &OfferDefinition) -> Result<CreatedOffer> {
         self.on_create
             .send(CreateOffer {
                 offer_definition: offer_definition.clone(),
@@ -141,26 +166,38 @@ impl NegotiatorAddr {
         &self,
         prev_proposal: Proposal,
         demand: Proposal,
+        subscription_id: &str,
+        precise: &Map<String, Value>,
     ) -> Result<ProposalResponse> {
         self.on_proposal
             .send(ReactToProposal {
                 demand,
                 prev_proposal,
+                subscription_id: subscription_id.to_string(),
+                precise: precise.clone(),
             })
             .await?
     }
 
     pub async fn react_to_agreement(
         &self,
+        subscription_id: &str,
         agreement_view: &AgreementView,
     ) -> Result<AgreementResponse> {
         self.on_agreement
             .send(ReactToAgreement {
+                subscription_id: subscription_id.to_string(),
                 agreement: agreement_view.clone(),
             })
             .await?
     }
 
+    pub fn offer_unsubscribed(&self, subscription_id: &str) {
+        self.on_unsubscribed.do_send(OfferUnsubscribed {
+            subscription_id: subscription_id.to_string(),
+        })
+    }
+
     pub async fn agreement_finalized(
         &self,
         agreement_id: &str,
@@ -180,7 +217,8 @@ impl NegotiatorAddr {
             on_create: addr.clone().recipient(),
             on_finalized: addr.clone().recipient(),
             on_proposal: addr.clone().recipient(),
-            on_agreement: addr.recipient(),
+            on_agreement: addr.clone().recipient(),
+            on_unsubscribed: addr.recipient(),
         }
     }
 }
@@ -19,6 +19,13 @@ pub enum NegotiationResult {
     /// Proposal is not acceptable and should be rejected.
     /// Negotiations can't be continued.
     Reject { message: String, is_final: bool },
+    /// Like `Reject`, but with additional properties describing the reason,
+    /// which Requestor can interpret without parsing the message.
+    RejectWithReason {
+        message: String,
+        extra: serde_json::Value,
+        is_final: bool,
+    },
 }
 
 /// `NegotiatorComponent` implements negotiation logic for part of Agreement
@@ -100,9 +107,7 @@ impl NegotiatorComponent for NegotiatorsPack {
                     all_ready = false;
                     offer
                 }
-                NegotiationResult::Reject { message, is_final } => {
-                    return Ok(NegotiationResult::Reject { message, is_final })
-                }
+                rejection => return Ok(rejection),
             }
         }
 
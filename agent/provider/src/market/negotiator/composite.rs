@@ -5,22 +5,28 @@ use std::convert::TryFrom;
 
 use ya_agreement_utils::agreement::{expand, flatten_value};
 use ya_agreement_utils::{AgreementView, OfferTemplate};
-use ya_client::model::market::NewOffer;
+use ya_client::model::market::{NewOffer, Reason};
 use ya_client_model::market::proposal::State;
 
 use super::builtin::{
-    DebitNoteInterval, LimitExpiration, ManifestSignature, MaxAgreements, PaymentTimeout,
+    DebitNoteInterval, Devices, LimitExpiration, ManifestSignature, MaxAgreements, PaymentTimeout,
+    RequestorAgreementsLimit, SecurityProfile, SharedCapacity,
+};
+use super::common::{
+    offer_definition_to_offer, AgreementResponse, CreatedOffer, Negotiator, ProposalResponse,
 };
-use super::common::{offer_definition_to_offer, AgreementResponse, Negotiator, ProposalResponse};
 use super::{NegotiationResult, NegotiatorsPack};
+use crate::hardware::Allocator;
 use crate::market::negotiator::builtin::allow_only::AllowOnly;
 use crate::market::negotiator::builtin::blacklist::Blacklist;
 use crate::market::negotiator::builtin::demand_validation::DemandValidation;
 use crate::market::negotiator::builtin::PriceNego;
 use crate::market::negotiator::common::{
-    reason_with_extra, AgreementFinalized, CreateOffer, ReactToAgreement, ReactToProposal,
+    reason_with_extra, AgreementFinalized, CreateOffer, OfferUnsubscribed, ReactToAgreement,
+    ReactToProposal,
 };
 use crate::market::negotiator::factory::CompositeNegotiatorConfig;
+use crate::market::negotiator::redaction::PropertyRedaction;
 use crate::market::negotiator::{NegotiatorComponent, ProposalView};
 use crate::market::ProviderMarket;
 use crate::provider_agent::AgentNegotiatorsConfig;
@@ -28,6 +34,7 @@ use crate::provider_agent::AgentNegotiatorsConfig;
 /// Negotiator that can limit number of running agreements.
 pub struct CompositeNegotiator {
     components: NegotiatorsPack,
+    redaction: PropertyRedaction,
 }
 
 impl CompositeNegotiator {
@@ -35,6 +42,7 @@ impl CompositeNegotiator {
         _market: Addr<ProviderMarket>,
         config: &CompositeNegotiatorConfig,
         agent_negotiators_cfg: AgentNegotiatorsConfig,
+        allocator: Allocator,
     ) -> anyhow::Result<CompositeNegotiator> {
         let components = NegotiatorsPack::default()
             .add_component(
@@ -53,6 +61,13 @@ impl CompositeNegotiator {
                 "LimitAgreements",
                 Box::new(MaxAgreements::new(&config.limit_agreements_config)),
             )
+            .add_component(
+                "LimitRequestorAgreements",
+                Box::new(RequestorAgreementsLimit::new(
+                    &config.limit_agreements_config,
+                )),
+            )
+            .add_component("SharedCapacity", Box::new(SharedCapacity::new(allocator)))
             .add_component(
                 "LimitExpiration",
                 Box::new(LimitExpiration::new(&config.expire_agreements_config)?),
@@ -65,6 +80,11 @@ impl CompositeNegotiator {
                 "PaymentTimeout",
                 Box::new(PaymentTimeout::new(&config.payment_timeout_config)?),
             )
+            .add_component("Devices", Box::new(Devices::new(&config.devices_config)))
+            .add_component(
+                "SecurityProfile",
+                Box::new(SecurityProfile::new(&config.security_profile_config)?),
+            )
             .add_component(
                 "ManifestSignature",
                 Box::new(ManifestSignature::new(
@@ -74,19 +94,27 @@ impl CompositeNegotiator {
             )
             .add_component(
                 "Price",
-                Box::new(PriceNego::new(&config.expire_agreements_config)?),
+                Box::new(PriceNego::new(
+                    &config.price_config,
+                    &config.limit_agreements_config,
+                )),
             );
 
-        Ok(CompositeNegotiator { components })
+        Ok(CompositeNegotiator {
+            components,
+            redaction: PropertyRedaction::new(&config.redaction_config),
+        })
     }
 }
 
 impl Handler<CreateOffer> for CompositeNegotiator {
-    type Result = anyhow::Result<NewOffer>;
+    type Result = anyhow::Result<CreatedOffer>;
 
     fn handle(&mut self, msg: CreateOffer, _: &mut Context<Self>) -> Self::Result {
         let offer = self.components.fill_template(msg.offer_definition)?;
-        Ok(offer_definition_to_offer(offer))
+        Ok(self
+            .redaction
+            .redact_offer(offer_definition_to_offer(offer)))
     }
 }
 
@@ -114,24 +142,47 @@ impl Handler<ReactToProposal> for CompositeNegotiator {
         match result {
             NegotiationResult::Reject { message, is_final } => {
                 Ok(ProposalResponse::RejectProposal {
-                    reason: Some(reason_with_extra(
-                        message,
-                        serde_json::json!({ "golem.proposal.rejection.is-final": is_final }),
-                    )),
+                    reason: Some(rejection_reason(message, Value::Null, is_final)),
                     is_final,
                 })
             }
+            NegotiationResult::RejectWithReason {
+                message,
+                extra,
+                is_final,
+            } => Ok(ProposalResponse::RejectProposal {
+                reason: Some(rejection_reason(message, extra, is_final)),
+                is_final,
+            }),
             NegotiationResult::Ready { offer } | NegotiationResult::Negotiating { offer } => {
                 let offer = NewOffer {
                     properties: flatten_value(offer.content.properties),
                     constraints,
                 };
+                let offer = self.redaction.redact_proposal(
+                    &msg.subscription_id,
+                    &their.issuer,
+                    &msg.precise,
+                    offer,
+                );
                 Ok(ProposalResponse::CounterProposal { offer })
             }
         }
     }
 }
 
+fn rejection_reason(message: String, extra: Value, is_final: bool) -> Reason {
+    let mut extra = match extra {
+        Value::Object(extra) => extra,
+        _ => Default::default(),
+    };
+    extra.insert(
+        "golem.proposal.rejection.is-final".to_string(),
+        Value::Bool(is_final),
+    );
+    reason_with_extra(message, Value::Object(extra))
+}
+
 pub fn to_proposal_views(
     mut agreement: AgreementView,
 ) -> anyhow::Result<(ProposalView, ProposalView)> {
@@ -194,17 +245,24 @@ impl Handler<ReactToAgreement> for CompositeNegotiator {
         {
             NegotiationResult::Ready { .. } => {
                 self.components.on_agreement_approved(&agreement_id)?;
+                self.redaction
+                    .on_agreement_approved(&msg.subscription_id, &demand_proposal.issuer);
                 Ok(AgreementResponse::ApproveAgreement)
             }
             NegotiationResult::Reject { message, is_final } => {
                 Ok(AgreementResponse::RejectAgreement {
-                    reason: Some(reason_with_extra(
-                        message,
-                        serde_json::json!({ "golem.proposal.rejection.is-final": is_final }),
-                    )),
+                    reason: Some(rejection_reason(message, Value::Null, is_final)),
                     is_final,
                 })
             }
+            NegotiationResult::RejectWithReason {
+                message,
+                extra,
+                is_final,
+            } => Ok(AgreementResponse::RejectAgreement {
+                reason: Some(rejection_reason(message, extra, is_final)),
+                is_final,
+            }),
             NegotiationResult::Negotiating { .. } => Ok(AgreementResponse::RejectAgreement {
                 reason: Some(reason_with_extra(
                     "Negotiations aren't finished.".to_string(),
@@ -225,6 +283,15 @@ impl Handler<AgreementFinalized> for CompositeNegotiator {
     }
 }
 
+impl Handler<OfferUnsubscribed> for CompositeNegotiator {
+    type Result = anyhow::Result<()>;
+
+    fn handle(&mut self, msg: OfferUnsubscribed, _: &mut Context<Self>) -> Self::Result {
+        self.redaction.on_unsubscribed(&msg.subscription_id);
+        Ok(())
+    }
+}
+
 impl Negotiator for CompositeNegotiator {}
 impl Actor for CompositeNegotiator {
     type Context = Context<Self>;
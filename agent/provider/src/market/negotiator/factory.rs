@@ -5,7 +5,10 @@ use structopt::StructOpt;
 
 use ya_manifest_utils::PolicyConfig;
 
+use super::builtin::price::SurgeRule;
 use super::common::NegotiatorAddr;
+use super::redaction::RedactionRule;
+use crate::hardware::Allocator;
 use crate::market::config::MarketConfig;
 use crate::market::negotiator::{AcceptAllNegotiator, CompositeNegotiator};
 use crate::market::ProviderMarket;
@@ -23,6 +26,9 @@ pub struct DemandValidationNegotiatorConfig {
 pub struct LimitAgreementsNegotiatorConfig {
     #[structopt(long, env, default_value = "1")]
     pub max_simultaneous_agreements: u32,
+    /// Limit of simultaneous Agreements with a single Requestor. Unlimited if not set.
+    #[structopt(long, env)]
+    pub max_agreements_per_requestor: Option<u32>,
 }
 
 /// Configuration for LimitAgreements Negotiator.
@@ -62,6 +68,46 @@ pub struct PaymentTimeoutConfig {
     pub payment_timeout_required_duration: std::time::Duration,
 }
 
+/// Configuration for Devices negotiator
+#[derive(StructOpt, Clone, Debug)]
+pub struct DevicesNegotiatorConfig {
+    /// Host devices, which can be exposed to ExeUnits. No devices are offered by default.
+    #[structopt(long, env, use_delimiter = true)]
+    pub allowed_devices: Vec<String>,
+}
+
+/// Configuration for SecurityProfile negotiator
+#[derive(StructOpt, Clone, Debug)]
+pub struct SecurityProfileNegotiatorConfig {
+    /// Security profiles (strict, default, permissive, custom), which Requestors can choose from.
+    #[structopt(long, env, use_delimiter = true, default_value = "strict,default")]
+    pub allowed_security_profiles: Vec<String>,
+    /// Security profile applied, when Requestor doesn't request any.
+    #[structopt(long, env, default_value = "default")]
+    pub default_security_profile: String,
+}
+
+/// Configuration for Price negotiator
+#[derive(StructOpt, Clone, Debug, Default)]
+pub struct PriceNegotiatorConfig {
+    /// Counter Proposals below the Offer prices with Offer prices instead of rejecting them.
+    #[structopt(long, env)]
+    pub auto_counter: bool,
+    /// Price multipliers applied to counter Proposals, when utilization of Agreement slots
+    /// reaches given percentage, e.g. `80%=1.5,95%=2`. Enables `--auto-counter`.
+    #[structopt(long, env, use_delimiter = true)]
+    pub price_surge: Vec<SurgeRule>,
+}
+
+/// Configuration of sensitive properties redaction
+#[derive(StructOpt, Clone, Debug, Default)]
+pub struct RedactionConfig {
+    /// Offer properties hidden or transformed until Agreement with the Requestor is approved,
+    /// e.g. `golem.node.geo.latitude=round:1,golem.node.geo.city=hide`.
+    #[structopt(long, env, use_delimiter = true)]
+    pub redact_properties: Vec<RedactionRule>,
+}
+
 /// Configuration for LimitAgreements Negotiator.
 #[derive(StructOpt, Clone, Debug)]
 pub struct CompositeNegotiatorConfig {
@@ -76,6 +122,14 @@ pub struct CompositeNegotiatorConfig {
     #[structopt(flatten)]
     pub payment_timeout_config: PaymentTimeoutConfig,
     #[structopt(flatten)]
+    pub devices_config: DevicesNegotiatorConfig,
+    #[structopt(flatten)]
+    pub security_profile_config: SecurityProfileNegotiatorConfig,
+    #[structopt(flatten)]
+    pub price_config: PriceNegotiatorConfig,
+    #[structopt(flatten)]
+    pub redaction_config: RedactionConfig,
+    #[structopt(flatten)]
     pub policy_config: PolicyConfig,
 }
 
@@ -89,6 +143,7 @@ pub fn create_negotiator(
     market: Addr<ProviderMarket>,
     config: &MarketConfig,
     agent_negotiators_cfg: &AgentNegotiatorsConfig,
+    allocator: &Allocator,
 ) -> Arc<NegotiatorAddr> {
     let negotiator = match &config.negotiator_type[..] {
         "Composite" => NegotiatorAddr::from(
@@ -96,6 +151,7 @@ pub fn create_negotiator(
                 market,
                 &config.negotiator_config.composite_config,
                 agent_negotiators_cfg.clone(),
+                allocator.clone(),
             )
             .unwrap(),
         ),
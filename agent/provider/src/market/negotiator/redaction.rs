@@ -0,0 +1,367 @@
+use anyhow::{anyhow, bail};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use ya_agreement_utils::agreement::flatten_value;
+use ya_client::model::market::NewOffer;
+use ya_client_model::NodeId;
+
+use crate::market::negotiator::common::CreatedOffer;
+use crate::market::negotiator::factory::RedactionConfig;
+
+/// How sensitive property is presented to Requestors, which can't see its precise value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transform {
+    /// Property is removed.
+    Hide,
+    /// Numeric property is rounded to given number of decimal places.
+    Round(u32),
+    /// Property is replaced with constant value.
+    Replace(Value),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RedactionRule {
+    pub property: String,
+    pub transform: Transform,
+}
+
+impl FromStr for RedactionRule {
+    type Err = anyhow::Error;
+
+    /// Parses `<property>[=hide|round:<decimals>|<value>]`,
+    /// e.g. `golem.node.geo.latitude=round:1`. Property is hidden by default.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (property, transform) = match s.split_once('=') {
+            Some((property, transform)) => (property.trim(), transform.trim()),
+            None => (s.trim(), "hide"),
+        };
+        if property.is_empty() {
+            bail!("Expected <property>[=<transform>], got: {s}");
+        }
+
+        let transform = match transform.split_once(':') {
+            _ if transform == "hide" => Transform::Hide,
+            Some(("round", decimals)) => Transform::Round(
+                decimals
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid decimal places in {s}: {e}"))?,
+            ),
+            _ => Transform::Replace(
+                serde_json::from_str(transform).unwrap_or_else(|_| json!(transform)),
+            ),
+        };
+        Ok(RedactionRule {
+            property: property.to_string(),
+            transform,
+        })
+    }
+}
+
+impl RedactionRule {
+    fn apply(&self, properties: &mut Map<String, Value>) {
+        let value = match properties.get_mut(&self.property) {
+            Some(value) => value,
+            None => return,
+        };
+        match &self.transform {
+            Transform::Replace(replacement) => *value = replacement.clone(),
+            Transform::Round(decimals) => match value.as_f64() {
+                Some(precise) => {
+                    let factor = 10f64.powi(*decimals as i32);
+                    *value = json!((precise * factor).round() / factor);
+                }
+                // Value we can't transform mustn't leak.
+                None => {
+                    properties.remove(&self.property);
+                }
+            },
+            Transform::Hide => {
+                properties.remove(&self.property);
+            }
+        }
+    }
+}
+
+/// Per-Requestor hook deciding, whether precise values of redacted properties
+/// are revealed in Proposals negotiated on the subscription.
+pub trait RevealPolicy {
+    fn reveals(&self, subscription_id: &str, requestor: &NodeId) -> bool;
+
+    /// Called when Agreement with the Requestor was approved.
+    fn on_agreement_approved(&mut self, _subscription_id: &str, _requestor: &NodeId) {}
+
+    /// Called when Offer was unsubscribed, so state kept for it can be dropped.
+    fn on_unsubscribed(&mut self, _subscription_id: &str) {}
+}
+
+/// Reveals precise values to Requestors, with whom Agreement on the same subscription
+/// was already approved.
+#[derive(Default)]
+pub struct RevealAfterAgreement {
+    requestors: HashMap<String, HashSet<NodeId>>,
+}
+
+impl RevealPolicy for RevealAfterAgreement {
+    fn reveals(&self, subscription_id: &str, requestor: &NodeId) -> bool {
+        self.requestors
+            .get(subscription_id)
+            .map(|requestors| requestors.contains(requestor))
+            .unwrap_or(false)
+    }
+
+    fn on_agreement_approved(&mut self, subscription_id: &str, requestor: &NodeId) {
+        self.requestors
+            .entry(subscription_id.to_string())
+            .or_default()
+            .insert(*requestor);
+    }
+
+    fn on_unsubscribed(&mut self, subscription_id: &str) {
+        self.requestors.remove(subscription_id);
+    }
+}
+
+/// Middleware redacting sensitive properties of outgoing Offers and Proposals.
+/// It runs after all `NegotiatorComponent`s, so it sees the final properties.
+/// Offers published on market are always redacted, counter Proposals are redacted
+/// unless any `RevealPolicy` reveals precise values to the Requestor.
+pub struct PropertyRedaction {
+    rules: Vec<RedactionRule>,
+    policies: Vec<Box<dyn RevealPolicy>>,
+}
+
+impl PropertyRedaction {
+    pub fn new(config: &RedactionConfig) -> PropertyRedaction {
+        PropertyRedaction {
+            rules: config.redact_properties.clone(),
+            policies: vec![Box::<RevealAfterAgreement>::default()],
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Box<dyn RevealPolicy>) -> PropertyRedaction {
+        self.policies.push(policy);
+        self
+    }
+
+    fn redact(&self, properties: Value) -> Value {
+        let mut properties = flatten_value(properties);
+        if let Value::Object(properties) = &mut properties {
+            for rule in &self.rules {
+                rule.apply(properties);
+            }
+        }
+        properties
+    }
+
+    /// Properties, which we don't know precise values of, stay redacted.
+    fn reveal(&self, precise: &Map<String, Value>, properties: Value) -> Value {
+        let mut properties = self.redact(properties);
+        if let Value::Object(properties) = &mut properties {
+            for (property, value) in precise {
+                properties.insert(property.clone(), value.clone());
+            }
+        }
+        properties
+    }
+
+    /// Redacts Offer, which is visible to all Requestors. Precise values are returned
+    /// with it, so they can be revealed in Proposals negotiated on its subscription.
+    pub fn redact_offer(&self, mut offer: NewOffer) -> CreatedOffer {
+        if self.rules.is_empty() {
+            return CreatedOffer {
+                offer,
+                precise: Map::new(),
+            };
+        }
+        offer.properties = flatten_value(offer.properties);
+        let precise = match &offer.properties {
+            Value::Object(properties) => self
+                .rules
+                .iter()
+                .filter_map(|rule| {
+                    properties
+                        .get(&rule.property)
+                        .map(|value| (rule.property.clone(), value.clone()))
+                })
+                .collect(),
+            _ => Map::new(),
+        };
+        offer.properties = self.redact(offer.properties);
+        CreatedOffer { offer, precise }
+    }
+
+    /// Counter Proposal is based on the previous one, which might have been redacted,
+    /// so `precise` values of the subscribed Offer are restored for Requestors allowed
+    /// to see them.
+    pub fn redact_proposal(
+        &self,
+        subscription_id: &str,
+        requestor: &NodeId,
+        precise: &Map<String, Value>,
+        mut proposal: NewOffer,
+    ) -> NewOffer {
+        if self.rules.is_empty() {
+            return proposal;
+        }
+        let reveals = self
+            .policies
+            .iter()
+            .any(|p| p.reveals(subscription_id, requestor));
+        proposal.properties = match reveals {
+            true => self.reveal(precise, proposal.properties),
+            false => self.redact(proposal.properties),
+        };
+        proposal
+    }
+
+    pub fn on_agreement_approved(&mut self, subscription_id: &str, requestor: &NodeId) {
+        for policy in &mut self.policies {
+            policy.on_agreement_approved(subscription_id, requestor);
+        }
+    }
+
+    pub fn on_unsubscribed(&mut self, subscription_id: &str) {
+        for policy in &mut self.policies {
+            policy.on_unsubscribed(subscription_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(rules: &[&str]) -> RedactionConfig {
+        RedactionConfig {
+            redact_properties: rules.iter().map(|rule| rule.parse().unwrap()).collect(),
+        }
+    }
+
+    fn offer() -> NewOffer {
+        offer_in("Warsaw", 52.2297, 4)
+    }
+
+    fn offer_in(city: &str, latitude: f64, threads: u32) -> NewOffer {
+        NewOffer::new(
+            json!({
+                "golem.node.geo.country_code": "PL",
+                "golem.node.geo.latitude": latitude,
+                "golem.node.geo.city": city,
+                "golem.inf.cpu.threads": threads,
+            }),
+            "()".to_string(),
+        )
+    }
+
+    fn node_id(n: u8) -> NodeId {
+        format!("0x{:040x}", n).parse().unwrap()
+    }
+
+    #[test]
+    fn test_rule_parsing() {
+        assert_eq!(
+            "golem.node.geo.latitude=round:1"
+                .parse::<RedactionRule>()
+                .unwrap()
+                .transform,
+            Transform::Round(1)
+        );
+        assert_eq!(
+            "golem.node.geo.city".parse::<RedactionRule>().unwrap(),
+            RedactionRule {
+                property: "golem.node.geo.city".to_string(),
+                transform: Transform::Hide,
+            }
+        );
+        assert_eq!(
+            "golem.node.geo.country_code=EU"
+                .parse::<RedactionRule>()
+                .unwrap()
+                .transform,
+            Transform::Replace(json!("EU"))
+        );
+        assert!("=hide".parse::<RedactionRule>().is_err());
+    }
+
+    #[test]
+    fn test_redact_until_agreement() {
+        let mut redaction = PropertyRedaction::new(&config(&[
+            "golem.node.geo.latitude=round:0",
+            "golem.node.geo.city",
+            "golem.node.geo.country_code=EU",
+        ]));
+
+        let CreatedOffer {
+            offer: published,
+            precise,
+        } = redaction.redact_offer(offer());
+        assert_eq!(
+            published.properties,
+            json!({
+                "golem.node.geo.country_code": "EU",
+                "golem.node.geo.latitude": 52.0,
+                "golem.inf.cpu.threads": 4,
+            })
+        );
+
+        let requestor = node_id(1);
+        let proposal =
+            redaction.redact_proposal("subscription", &requestor, &precise, published.clone());
+        assert_eq!(proposal.properties, published.properties);
+
+        redaction.on_agreement_approved("subscription", &requestor);
+        let proposal =
+            redaction.redact_proposal("subscription", &requestor, &precise, published.clone());
+        assert_eq!(proposal.properties, offer().properties);
+
+        // Other Requestors and subscriptions still see redacted values.
+        let proposal = redaction.redact_proposal("subscription", &node_id(2), &precise, offer());
+        assert_eq!(proposal.properties, published.properties);
+        let proposal = redaction.redact_proposal("other", &requestor, &precise, offer());
+        assert_eq!(proposal.properties, published.properties);
+
+        redaction.on_unsubscribed("subscription");
+        let proposal = redaction.redact_proposal("subscription", &requestor, &precise, offer());
+        assert_eq!(proposal.properties, published.properties);
+    }
+
+    #[test]
+    fn test_reveal_values_of_countered_offer() {
+        let mut redaction = PropertyRedaction::new(&config(&[
+            "golem.node.geo.latitude=round:0",
+            "golem.node.geo.city",
+        ]));
+        let requestor = node_id(1);
+        redaction.on_agreement_approved("warsaw", &requestor);
+        redaction.on_agreement_approved("krakow", &requestor);
+
+        let warsaw = redaction.redact_offer(offer_in("Warsaw", 52.2297, 4));
+        let krakow = redaction.redact_offer(offer_in("Krakow", 50.0647, 8));
+
+        let proposal =
+            redaction.redact_proposal("warsaw", &requestor, &warsaw.precise, warsaw.offer);
+        assert_eq!(
+            proposal.properties,
+            offer_in("Warsaw", 52.2297, 4).properties
+        );
+        let proposal =
+            redaction.redact_proposal("krakow", &requestor, &krakow.precise, krakow.offer);
+        assert_eq!(
+            proposal.properties,
+            offer_in("Krakow", 50.0647, 8).properties
+        );
+
+        // Values we don't know stay redacted.
+        let proposal = redaction.redact_proposal("warsaw", &requestor, &Map::new(), offer());
+        assert_eq!(
+            proposal.properties,
+            json!({
+                "golem.node.geo.country_code": "PL",
+                "golem.node.geo.latitude": 52.0,
+                "golem.inf.cpu.threads": 4,
+            })
+        );
+    }
+}
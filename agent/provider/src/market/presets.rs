@@ -23,6 +23,11 @@ pub struct Preset {
     pub initial_price: f64,
     // It's important that all values are sorted, so that other tools can easily detect changes.
     pub usage_coeffs: BTreeMap<String, f64>,
+    /// CPU threads of resource-size variants. Each variant is published as separate
+    /// Offer sharing host capacity with other variants. Empty means single Offer
+    /// with all available resources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<i32>,
 }
 
 impl Preset {
@@ -226,6 +231,7 @@ impl Default for Preset {
             exeunit_name: "wasmtime".to_string(),
             pricing_model: "linear".to_string(),
             usage_coeffs,
+            variants: Vec::new(),
         }
     }
 }
@@ -236,6 +242,7 @@ impl PartialEq for Preset {
             && self.exeunit_name == other.exeunit_name
             && self.pricing_model == other.pricing_model
             && self.usage_coeffs == other.usage_coeffs
+            && self.variants == other.variants
     }
 }
 
@@ -273,6 +280,20 @@ fn display_preset(
         preset.pricing_model,
         width = align
     )?;
+    if !preset.variants.is_empty() {
+        let variants = preset
+            .variants
+            .iter()
+            .map(|threads| threads.to_string())
+            .collect::<Vec<_>>();
+        writeln!(
+            f,
+            "{:width$}{} CPU threads",
+            "Variants:",
+            variants.join(", "),
+            width = align
+        )?;
+    }
     writeln!(f, "Coefficients:")?;
 
     let exe_unit = registry.find_exeunit(&preset.exeunit_name).ok();
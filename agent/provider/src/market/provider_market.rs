@@ -6,6 +6,7 @@ use chrono::Utc;
 use derive_more::Display;
 use futures::prelude::*;
 use futures_util::FutureExt;
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::str::FromStr;
@@ -27,9 +28,12 @@ use ya_utils_actix::{
 };
 
 use super::negotiator::factory;
-use super::negotiator::{AgreementResponse, AgreementResult, NegotiatorAddr, ProposalResponse};
+use super::negotiator::{
+    AgreementResponse, AgreementResult, CreatedOffer, NegotiatorAddr, ProposalResponse,
+};
 use super::Preset;
 use crate::display::EnableDisplay;
+use crate::hardware::Allocator;
 use crate::market::config::MarketConfig;
 use crate::market::termination_reason::GolemReason;
 use crate::provider_agent::AgentNegotiatorsConfig;
@@ -81,6 +85,8 @@ struct Subscription {
     id: String,
     preset: Preset,
     offer: NewOffer,
+    /// Precise values of properties redacted in `offer`.
+    precise: Map<String, Value>,
 }
 
 #[derive(Message)]
@@ -117,6 +123,7 @@ pub struct ProviderMarket {
     postponed_demands: Vec<SubscriptionProposal>,
     config: Arc<MarketConfig>,
     agent_negotiators_cfg: Arc<AgentNegotiatorsConfig>,
+    allocator: Allocator,
 
     /// External actors can listen on this signal.
     pub agreement_signed_signal: SignalSlot<NewAgreement>,
@@ -143,6 +150,7 @@ impl ProviderMarket {
         api: MarketProviderApi,
         config: MarketConfig,
         agent_negotiators_cfg: AgentNegotiatorsConfig,
+        allocator: Allocator,
     ) -> ProviderMarket {
         ProviderMarket {
             negotiator: Arc::new(NegotiatorAddr::default()),
@@ -151,6 +159,7 @@ impl ProviderMarket {
             postponed_demands: Vec::new(),
             config: Arc::new(config),
             agent_negotiators_cfg: Arc::new(agent_negotiators_cfg),
+            allocator,
             agreement_signed_signal: SignalSlot::<NewAgreement>::default(),
             agreement_terminated_signal: SignalSlot::<CloseAgreement>::default(),
             handles: HashMap::new(),
@@ -196,12 +205,20 @@ impl ProviderMarket {
 async fn subscribe(
     market: Addr<ProviderMarket>,
     api: Arc<MarketProviderApi>,
-    offer: NewOffer,
+    offer: CreatedOffer,
     preset: Preset,
 ) -> Result<()> {
+    let CreatedOffer { offer, precise } = offer;
     let id = api.subscribe(&offer).await?;
 
-    let _ = market.send(Subscription { id, offer, preset }).await?;
+    let _ = market
+        .send(Subscription {
+            id,
+            offer,
+            precise,
+            preset,
+        })
+        .await?;
     Ok(())
 }
 
@@ -324,7 +341,12 @@ async fn process_proposal(
 
     let action = ctx
         .negotiator
-        .react_to_proposal(prev_proposal, demand.clone())
+        .react_to_proposal(
+            prev_proposal,
+            demand.clone(),
+            &subscription.id,
+            &subscription.precise,
+        )
         .await
         .map_err(|e| {
             anyhow!(
@@ -393,7 +415,7 @@ async fn process_agreement(
 
     let action = ctx
         .negotiator
-        .react_to_agreement(&agreement)
+        .react_to_agreement(&subscription.id, &agreement)
         .await
         .map_err(|e| {
             anyhow!(
@@ -594,8 +616,12 @@ impl Actor for ProviderMarket {
             ctx.spawn(collect_agreement_events(actx).into_actor(self)),
         );
 
-        self.negotiator =
-            factory::create_negotiator(ctx.address(), &self.config, &self.agent_negotiators_cfg);
+        self.negotiator = factory::create_negotiator(
+            ctx.address(),
+            &self.config,
+            &self.agent_negotiators_cfg,
+            &self.allocator,
+        );
     }
 }
 
@@ -677,7 +703,7 @@ impl Handler<CreateOffer> for ProviderMarket {
             log::info!(
                 "Offer for preset: {} = {}",
                 msg.preset.name,
-                offer.display()
+                offer.offer.display()
             );
 
             log::info!("Subscribing to events... [{}]", msg.preset.name);
@@ -756,7 +782,10 @@ async fn resubscribe_offers(
     }
 
     for (_, sub) in subscriptions {
-        let offer = sub.offer;
+        let offer = CreatedOffer {
+            offer: sub.offer,
+            precise: sub.precise,
+        };
         let preset = sub.preset;
         let preset_name = preset.name.clone();
 
@@ -900,6 +929,7 @@ impl Handler<Unsubscribe> for ProviderMarket {
 
         subscriptions.iter().for_each(|id| {
             self.subscriptions.remove(id);
+            self.negotiator.offer_unsubscribed(id);
         });
         subscriptions
             .iter()
@@ -1,7 +1,7 @@
 use anyhow::{anyhow, bail, Result};
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::watch;
 
@@ -59,6 +59,8 @@ pub struct AgreementPayment {
     // If at least one deadline elapses, we don't want to generate any
     // new unnecessary events.
     pub deadline_elapsed: bool,
+    // Deadlines, which elapsed already and are extended by grace period.
+    pub deadlines_in_grace: HashSet<String>,
     // If we are unable to send DebitNotes, we should break Agreement the same
     // way as in case, when Requestor doesn't accept them.
     pub last_send_debit_note: DateTime<Utc>,
@@ -113,6 +115,7 @@ impl AgreementPayment {
             accept_timeout,
             payment_timeout,
             deadline_elapsed: false,
+            deadlines_in_grace: HashSet::new(),
             last_send_debit_note: approved_ts,
             last_payable_debit_note: approved_ts,
             watch_sender: sender,
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::sync::Arc;
 use std::time::Duration;
@@ -13,6 +13,7 @@ use humantime;
 use log;
 use serde_json::json;
 use structopt::StructOpt;
+use strum::EnumMessage;
 use ya_client::activity::ActivityProviderApi;
 use ya_client::model::payment::{DebitNote, Invoice, NewDebitNote, NewInvoice};
 use ya_client::model::payment::{DebitNoteEvent, DebitNoteEventType, InvoiceEventType};
@@ -86,6 +87,14 @@ struct InvoiceSettled {
     pub invoice_id: String,
 }
 
+/// Breaks Agreement, which Requestor doesn't follow negotiated debit note terms.
+#[derive(Message, Clone)]
+#[rtype(result = "()")]
+struct EnforceTerms {
+    pub agreement_id: String,
+    pub reason: BreakReason,
+}
+
 /// Gets costs summary for agreement.
 #[derive(Message, Clone)]
 #[rtype(result = "Result<CostsSummary>")]
@@ -122,6 +131,14 @@ pub struct PaymentsConfig {
     pub get_events_error_timeout: Duration,
     #[structopt(long, env, parse(try_from_str = humantime::parse_duration), default_value = "5s")]
     pub invoice_reissue_interval: Duration,
+    /// Additional time for Requestor to accept DebitNote after negotiated deadline elapsed.
+    /// Agreement is terminated when grace period ends.
+    #[structopt(long, env, parse(try_from_str = humantime::parse_duration), default_value = "0s")]
+    pub debit_note_accept_grace: Duration,
+    /// Additional time for Requestor to pay DebitNote after its payment due date.
+    /// Agreement is terminated when grace period ends.
+    #[structopt(long, env, parse(try_from_str = humantime::parse_duration), default_value = "0s")]
+    pub debit_note_payment_grace: Duration,
     #[structopt(skip = "you-forgot-to-set-session-id")]
     pub session_id: String,
 }
@@ -143,6 +160,8 @@ pub struct Payments {
 
     invoices_to_pay: Vec<Invoice>,
     earnings: BigDecimal,
+    /// Agreements terminated due to debit note terms violation by cause.
+    terminations: BTreeMap<&'static str, u64>,
 
     break_agreement_signal: SignalSlot<BreakAgreement>,
 }
@@ -168,6 +187,7 @@ impl Payments {
             context: Arc::new(provider_ctx),
             invoices_to_pay: vec![],
             earnings: BigDecimal::zero(),
+            terminations: BTreeMap::new(),
             break_agreement_signal: SignalSlot::<BreakAgreement>::default(),
         }
     }
@@ -197,6 +217,29 @@ impl Payments {
             }
         }
     }
+
+    /// Breaks Agreement violating debit note terms and counts terminations by cause.
+    fn enforce_terms(&mut self, agreement_id: String, reason: BreakReason) {
+        let cause = reason.get_message().unwrap_or("Unknown");
+        *self.terminations.entry(cause).or_default() += 1;
+        log::info!(
+            "Breaking Agreement [{}]: {}. Terminations by cause: {:?}",
+            agreement_id,
+            reason,
+            self.terminations
+        );
+
+        self.break_agreement_signal
+            .send_signal(BreakAgreement {
+                agreement_id: agreement_id.clone(),
+                reason: reason.clone(),
+            })
+            .log_err_msg(&format!(
+                "Failed to send BreakAgreement for [{}] with reason: {}",
+                agreement_id, reason,
+            ))
+            .ok();
+    }
 }
 
 async fn send_debit_note(
@@ -351,10 +394,7 @@ async fn check_invoice_events(provider_ctx: Arc<ProviderCtx>, payments_addr: Add
     }
 }
 
-async fn check_debit_notes_events(
-    provider_ctx: Arc<ProviderCtx>,
-    provider_signal: SignalSlot<BreakAgreement>,
-) {
+async fn check_debit_notes_events(provider_ctx: Arc<ProviderCtx>, payments_addr: Addr<Payments>) {
     let config = &provider_ctx.config;
     let timeout = config.get_events_timeout;
     let error_timeout = config.get_events_error_timeout;
@@ -374,7 +414,7 @@ async fn check_debit_notes_events(
             Ok(events) => {
                 for event in events {
                     lather_than = event.event_date;
-                    handle_debit_note_event(event, &provider_ctx, &provider_signal).await;
+                    handle_debit_note_event(event, &provider_ctx, &payments_addr).await;
                 }
             }
             Err(e) => {
@@ -388,7 +428,7 @@ async fn check_debit_notes_events(
 async fn handle_debit_note_event(
     event: DebitNoteEvent,
     provider_ctx: &Arc<ProviderCtx>,
-    provider_signal: &SignalSlot<BreakAgreement>,
+    payments_addr: &Addr<Payments>,
 ) {
     match &event.event_type {
         DebitNoteEventType::DebitNoteAcceptedEvent => provider_ctx
@@ -447,16 +487,11 @@ async fn handle_debit_note_event(
             });
 
             let reason = BreakReason::try_from(event.event_type.clone()).unwrap();
-            provider_signal
-                .send_signal(BreakAgreement {
-                    agreement_id: debit_note.agreement_id.clone(),
-                    reason: reason.clone(),
-                })
-                .log_err_msg(&format!(
-                    "Failed to send BreakAgreement for [{}] with reason: {}",
-                    debit_note.agreement_id, reason
-                ))
-                .ok()
+            payments_addr.do_send(EnforceTerms {
+                agreement_id: debit_note.agreement_id.clone(),
+                reason,
+            });
+            Some(())
         }
         _ => None,
     };
@@ -949,6 +984,7 @@ impl Handler<DeadlineElapsed> for Payments {
     type Result = ();
 
     fn handle(&mut self, msg: DeadlineElapsed, _ctx: &mut Context<Self>) -> Self::Result {
+        let config = &self.context.config;
         let agreement = match self.agreements.get_mut(&msg.category) {
             Some(agreement) => {
                 // If at least one deadline elapses, we don't want to generate any
@@ -966,32 +1002,22 @@ impl Handler<DeadlineElapsed> for Payments {
                 return;
             }
         };
-        let reason = if msg.id.starts_with(ACCEPT_PREFIX) {
+        let (reason, grace, checker) = if msg.id.starts_with(ACCEPT_PREFIX) {
             match agreement.accept_timeout {
-                Some(timeout) => {
-                    log::warn!(
-                        "Deadline {} elapsed for accepting DebitNote [{}] for Agreement [{}]",
-                        msg.deadline,
-                        msg.id,
-                        msg.category,
-                    );
-
-                    agreement.deadline_elapsed = true;
-                    BreakReason::DebitNotesDeadline(timeout)
-                }
+                Some(timeout) => (
+                    BreakReason::DebitNotesDeadline(timeout),
+                    config.debit_note_accept_grace,
+                    &self.context.debit_checker,
+                ),
                 None => return,
             }
         } else if msg.id.starts_with(PAYMENT_PREFIX) {
             match agreement.payment_timeout {
-                Some(timeout) => {
-                    log::warn!(
-                        "Deadline {} elapsed for DebitNote [{}] payment for Agreement [{}]",
-                        msg.deadline,
-                        msg.id,
-                        msg.category,
-                    );
-                    BreakReason::DebitNoteNotPaid(timeout)
-                }
+                Some(timeout) => (
+                    BreakReason::DebitNoteNotPaid(timeout),
+                    config.debit_note_payment_grace,
+                    &self.context.payment_checker,
+                ),
                 None => return,
             }
         } else {
@@ -1002,16 +1028,49 @@ impl Handler<DeadlineElapsed> for Payments {
             return;
         };
 
-        self.break_agreement_signal
-            .send_signal(BreakAgreement {
-                agreement_id: msg.category.clone(),
-                reason: reason.clone(),
-            })
-            .log_err_msg(&format!(
-                "Failed to send BreakAgreement for [{}] with reason: {}",
-                msg.category, reason,
-            ))
-            .ok();
+        // Deadline is tracked once more with the same id, so it is still
+        // cancelled when Requestor accepts or pays DebitNote late.
+        let grace_deadline = chrono::Duration::from_std(grace)
+            .ok()
+            .and_then(|grace| msg.deadline.checked_add_signed(grace));
+        if let Some(grace_deadline) = grace_deadline {
+            if !grace.is_zero() && agreement.deadlines_in_grace.insert(msg.id.clone()) {
+                log::warn!(
+                    "Deadline {} elapsed for DebitNote [{}] for Agreement [{}] ({}). Agreement will be terminated at {}.",
+                    msg.deadline,
+                    msg.id,
+                    msg.category,
+                    reason,
+                    grace_deadline,
+                );
+                checker.do_send(TrackDeadline {
+                    category: msg.category,
+                    deadline: grace_deadline,
+                    id: msg.id,
+                });
+                return;
+            }
+        }
+
+        log::warn!(
+            "Deadline {} elapsed for DebitNote [{}] for Agreement [{}] ({}).",
+            msg.deadline,
+            msg.id,
+            msg.category,
+            reason,
+        );
+        if let BreakReason::DebitNotesDeadline(_) = reason {
+            agreement.deadline_elapsed = true;
+        }
+        self.enforce_terms(msg.category, reason);
+    }
+}
+
+impl Handler<EnforceTerms> for Payments {
+    type Result = ();
+
+    fn handle(&mut self, msg: EnforceTerms, _ctx: &mut Context<Self>) -> Self::Result {
+        self.enforce_terms(msg.agreement_id, msg.reason);
     }
 }
 
@@ -1039,7 +1098,6 @@ impl Actor for Payments {
 
     fn started(&mut self, ctx: &mut Context<Self>) {
         // Start checking incoming payments.
-        let provider_signal = self.break_agreement_signal.clone();
         let provider_ctx = self.context.clone();
         let payment_addr = ctx.address();
 
@@ -1054,7 +1112,7 @@ impl Actor for Payments {
                     .await
                     .map_err(|_| log::error!("Subscribing to DebitNotes deadline checker failed."));
             }
-            check_debit_notes_events(provider_ctx, provider_signal).await;
+            check_debit_notes_events(provider_ctx, payment_addr).await;
         });
     }
 }
@@ -15,6 +15,8 @@ pub struct AccountView {
     pub address: NodeId,
     pub network: NetworkName,
     pub platform: String,
+    /// Payments relayed on behalf of Requestors without gas are accepted.
+    pub gasless: bool,
 }
 
 impl From<Account> for AccountView {
@@ -23,6 +25,7 @@ impl From<Account> for AccountView {
             address: account.address.parse().unwrap(), // TODO: use TryFrom
             network: account.network.parse().unwrap(), // TODO: use TryFrom
             platform: account.platform,
+            gasless: false,
         }
     }
 }
@@ -139,12 +142,16 @@ impl PricingOffer for LinearPricingOffer {
         });
 
         for account in accounts {
-            params.as_object_mut().unwrap().insert(
-                format!("payment.platform.{}", account.platform),
-                json!({
-                    "address".to_string(): account.address,
-                }),
-            );
+            let mut platform = json!({
+                "address".to_string(): account.address,
+            });
+            if account.gasless {
+                platform["gasless"] = json!(true);
+            }
+            params
+                .as_object_mut()
+                .unwrap()
+                .insert(format!("payment.platform.{}", account.platform), platform);
         }
 
         Ok(ComInfo { params })
@@ -4,6 +4,7 @@ use futures::{FutureExt, StreamExt, TryFutureExt};
 use ya_client::net::NetApi;
 use ya_core_model::NodeId;
 
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -16,12 +17,16 @@ use ya_client::cli::ProviderApi;
 use ya_core_model::payment::local::NetworkName;
 use ya_file_logging::{start_logger, LoggerHandle};
 use ya_manifest_utils::{manifest, Feature};
+use ya_property_schema::com;
 
+use crate::benchmark;
 use crate::config::globals::GlobalsState;
+use crate::config::profiles::ConfigProfiles;
 use crate::dir::clean_provider_dir;
 use crate::events::Event;
 use crate::execution::{ExeUnitDesc, GetExeUnit, GetOfferTemplates, TaskRunner, UpdateActivity};
 use crate::hardware;
+use crate::market::negotiator::builtin::capacity::VARIANT_GROUP_PROPERTY_FLAT;
 use crate::market::provider_market::{OfferKind, Shutdown as MarketShutdown, Unsubscribe};
 use crate::market::{CreateOffer, Preset, PresetManager, ProviderMarket};
 use crate::payments::{AccountView, LinearPricingOffer, Payments, PricingOffer};
@@ -33,30 +38,53 @@ use crate::tasks::task_manager::{
 
 struct GlobalsManager {
     state: Arc<Mutex<GlobalsState>>,
-    monitor: Option<FileMonitor>,
+    monitors: Vec<FileMonitor>,
+    /// Values given explicitly on the command line take precedence over config profiles.
+    cli: NodeConfig,
 }
 
 impl GlobalsManager {
-    fn try_new(globals_file: &Path, node_config: NodeConfig) -> anyhow::Result<Self> {
+    fn try_new(
+        globals_file: &Path,
+        profiles_file: &Path,
+        node_config: NodeConfig,
+    ) -> anyhow::Result<Self> {
         let mut state = GlobalsState::load_or_create(globals_file)?;
-        state.update_and_save(node_config, globals_file)?;
+        state.update_and_save(node_config.clone(), globals_file)?;
+
+        if let Some(profile) = ConfigProfiles::load_or_create(profiles_file)?.active_profile()? {
+            profile.apply(&mut state);
+            state.apply_cli(&node_config);
+        }
 
         Ok(Self {
             state: Arc::new(Mutex::new(state)),
-            monitor: None,
+            monitors: Vec::new(),
+            cli: node_config,
         })
     }
 
-    fn spawn_monitor(&mut self, globals_file: &Path) -> anyhow::Result<()> {
-        let state = self.state.clone();
-        let handler = move |p: PathBuf| match GlobalsState::load(&p) {
-            Ok(new_state) => {
-                *state.lock().unwrap() = new_state;
-            }
-            Err(e) => log::warn!("Error updating global configuration from {:?}: {:?}", p, e),
-        };
-        let monitor = FileMonitor::spawn(globals_file, FileMonitor::on_modified(handler))?;
-        self.monitor = Some(monitor);
+    /// Reloads state when either base config or config profiles change.
+    fn spawn_monitor(&mut self, globals_file: &Path, profiles_file: &Path) -> anyhow::Result<()> {
+        for path in [globals_file, profiles_file] {
+            let state = self.state.clone();
+            let globals_file = globals_file.to_path_buf();
+            let profiles_file = profiles_file.to_path_buf();
+            let cli = self.cli.clone();
+            let handler =
+                move |p: PathBuf| match GlobalsState::load_effective(&globals_file, &profiles_file)
+                {
+                    Ok(mut new_state) => {
+                        new_state.apply_cli(&cli);
+                        *state.lock().unwrap() = new_state;
+                    }
+                    Err(e) => {
+                        log::warn!("Error updating global configuration from {:?}: {:?}", p, e)
+                    }
+                };
+            let monitor = FileMonitor::spawn(path, FileMonitor::on_modified(handler))?;
+            self.monitors.push(monitor);
+        }
         Ok(())
     }
 
@@ -77,9 +105,11 @@ pub struct ProviderAgent {
     task_manager: Addr<TaskManager>,
     presets: PresetManager,
     hardware: hardware::Manager,
+    benchmark: benchmark::Manager,
     account: NodeId,
     log_handler: LoggerHandle,
     networks: Vec<PaymentPlatform>,
+    gasless: Vec<PaymentPlatform>,
     rulestore_monitor: FileMonitor,
     keystore_monitor: FileMonitor,
     whitelist_monitor: FileMonitor,
@@ -118,7 +148,11 @@ impl ProviderAgent {
         log::info!("Loading payment accounts...");
         let account = api.identity.me().await?.identity;
         log::info!("Payment account: {:#?}", account);
-        let registry = config.registry()?;
+        let mut registry = config.registry()?;
+        if args.simulate {
+            log::warn!("Simulation mode: Agreements are accepted, but no workloads are run");
+            registry.simulate(&std::env::current_exe()?);
+        }
         registry.validate()?;
         registry
             .test_runtimes(&data_dir)
@@ -145,6 +179,7 @@ impl ProviderAgent {
         args.payment.session_id = args.market.session_id.clone();
 
         let networks = args.node.account.networks.clone();
+        let gasless = args.node.account.gasless.clone();
         for n in networks.iter() {
             let net_color = match n.network {
                 NetworkName::Mainnet => yansi::Color::Magenta,
@@ -158,18 +193,31 @@ impl ProviderAgent {
             log::info!("Using payment network: {}", net_color.paint(&n.network));
         }
 
-        let mut globals = GlobalsManager::try_new(&config.globals_file, args.node)?;
-        globals.spawn_monitor(&config.globals_file)?;
+        let mut globals = GlobalsManager::try_new(
+            &config.globals_file,
+            &config.config_profiles_file,
+            args.node,
+        )?;
+        globals.spawn_monitor(&config.globals_file, &config.config_profiles_file)?;
         let mut presets = PresetManager::load_or_create(&config.presets_file)?;
         presets.spawn_monitor(&config.presets_file)?;
         let mut hardware = hardware::Manager::try_new(&config)?;
         hardware.spawn_monitor(&config.hardware_file)?;
+        let signer = benchmark::Signer::new(&args.api.app_key);
+        let mut benchmark = benchmark::Manager::new(args.benchmark, &data_dir, account, signer);
+        benchmark.spawn_scheduler();
         let (rulestore_monitor, keystore_monitor, whitelist_monitor) =
             rules_manager.spawn_file_monitors()?;
 
         let agent_negotiators_cfg = AgentNegotiatorsConfig { rules_manager };
 
-        let market = ProviderMarket::new(api.market, args.market, agent_negotiators_cfg).start();
+        let market = ProviderMarket::new(
+            api.market,
+            args.market,
+            agent_negotiators_cfg,
+            hardware.allocator(),
+        )
+        .start();
         let payments = Payments::new(api.activity.clone(), api.payment, args.payment).start();
         let runner = TaskRunner::new(api.activity, args.runner, registry, data_dir)?.start();
         let task_manager =
@@ -183,9 +231,11 @@ impl ProviderAgent {
             task_manager,
             presets,
             hardware,
+            benchmark,
             account,
             log_handler,
             networks,
+            gasless,
             rulestore_monitor,
             keystore_monitor,
             whitelist_monitor,
@@ -196,7 +246,8 @@ impl ProviderAgent {
     async fn create_offers(
         presets: Vec<Preset>,
         node_info: NodeInfo,
-        inf_node_info: InfNodeInfo,
+        resources: hardware::Resources,
+        benchmark: BTreeMap<String, serde_json::Value>,
         runner: Addr<TaskRunner>,
         market: Addr<ProviderMarket>,
         accounts: Vec<AccountView>,
@@ -226,20 +277,63 @@ impl ProviderAgent {
                     )
                 })?;
 
-            let offer = Self::build_offer(
-                node_info.clone(),
-                inf_node_info.clone(),
-                &accounts,
-                preset,
-                offer,
-                exeunit_desc,
-            )?;
-
-            market.send(offer).await??;
+            for (resources, mut offer) in Self::offer_variants(&preset, resources, offer) {
+                for (name, value) in benchmark.iter() {
+                    offer.set_property(name, value.clone());
+                }
+                let offer = Self::build_offer(
+                    node_info.clone(),
+                    InfNodeInfo::from(resources),
+                    &accounts,
+                    preset.clone(),
+                    offer,
+                    exeunit_desc.clone(),
+                )?;
+
+                market.send(offer).await??;
+            }
         }
         Ok(())
     }
 
+    /// Splits resources into variants defined in the preset. Variant Offers are linked
+    /// by `golem.inf.variant-group` property, so Agreements made on them are accounted
+    /// against the shared host capacity.
+    fn offer_variants(
+        preset: &Preset,
+        resources: hardware::Resources,
+        offer: OfferTemplate,
+    ) -> Vec<(hardware::Resources, OfferTemplate)> {
+        if preset.variants.is_empty() {
+            return vec![(resources, offer)];
+        }
+
+        preset
+            .variants
+            .iter()
+            .filter_map(|threads| {
+                let slice = resources.slice(*threads);
+                if slice.is_none() {
+                    log::warn!(
+                        "Skipping variant with {} CPU threads of preset [{}]. Only {} available.",
+                        threads,
+                        preset.name,
+                        resources.cpu_threads
+                    );
+                }
+                slice
+            })
+            .map(|slice| {
+                let mut offer = offer.clone();
+                offer.set_property(
+                    VARIANT_GROUP_PROPERTY_FLAT,
+                    serde_json::Value::String(preset.name.clone()),
+                );
+                (slice, offer)
+            })
+            .collect()
+    }
+
     fn build_offer(
         node_info: NodeInfo,
         inf_node_info: InfNodeInfo,
@@ -253,11 +347,8 @@ impl ProviderAgent {
             other => return Err(anyhow!("Unsupported pricing model: {}", other)),
         };
         let (initial_price, prices) = get_prices(pricing_model.as_ref(), &preset, &offer)?;
-        offer.set_property("golem.com.usage.vector", get_usage_vector_value(&prices));
-        offer.set_property(
-            "golem.com.payment.protocol.version",
-            node_info.protocol_version.into(),
-        );
+        offer.set_typed(com::USAGE_VECTOR, get_usage_vector(&prices))?;
+        offer.set_typed(com::PAYMENT_PROTOCOL_VERSION, node_info.protocol_version)?;
         offer.add_constraints(Self::build_constraints(node_info.subnet.clone())?);
         let com_info = pricing_model.build(accounts, initial_price, prices)?;
         let srv_info = Self::build_service_info(inf_node_info, exeunit_desc, &offer)?;
@@ -325,6 +416,10 @@ impl ProviderAgent {
                 address: account,
                 network: network.network.clone(),
                 platform: network.platform(),
+                gasless: self
+                    .gasless
+                    .iter()
+                    .any(|gasless| gasless.platform() == network.platform()),
             })
             .collect();
 
@@ -366,12 +461,8 @@ fn get_prices(
     Ok((initial_price, prices))
 }
 
-fn get_usage_vector_value(prices: &[(String, f64)]) -> serde_json::Value {
-    let vec = prices
-        .iter()
-        .map(|(p, _)| serde_json::Value::String(p.clone()))
-        .collect::<Vec<_>>();
-    serde_json::Value::Array(vec)
+fn get_usage_vector(prices: &[(String, f64)]) -> Vec<String> {
+    prices.iter().map(|(p, _)| p.clone()).collect()
 }
 
 async fn process_activity_events(runner: Addr<TaskRunner>) {
@@ -409,12 +500,13 @@ impl Handler<Initialize> for ProviderAgent {
         let rx = futures::stream::select_all(vec![
             WatchStream::new(self.hardware.event_receiver()),
             WatchStream::new(self.presets.event_receiver()),
+            WatchStream::new(self.benchmark.event_receiver()),
         ]);
 
         tokio::task::spawn_local(async move {
             rx.for_each(|e| async {
                 match e {
-                    Event::HardwareChanged => {
+                    Event::HardwareChanged | Event::BenchmarkChanged => {
                         let _ = market
                             .send(Unsubscribe(OfferKind::Any))
                             .map_err(|e| log::error!("Cannot unsubscribe offers: {}", e))
@@ -506,7 +598,8 @@ impl Handler<CreateOffers> for ProviderAgent {
             Ok(acc) => acc,
             Err(e) => return Box::pin(async { Err(e) }),
         };
-        let inf_node_info = InfNodeInfo::from(self.hardware.capped());
+        let resources = self.hardware.capped();
+        let benchmark = self.benchmark.properties();
         let preset_names = match msg.0 {
             OfferKind::Any => self.presets.active(),
             OfferKind::WithPresets(names) => names,
@@ -521,7 +614,10 @@ impl Handler<CreateOffers> for ProviderAgent {
 
         async move {
             let node_info = Self::build_node_info(globals, net_api).await?;
-            Self::create_offers(presets?, node_info, inf_node_info, runner, market, accounts).await
+            Self::create_offers(
+                presets?, node_info, resources, benchmark, runner, market, accounts,
+            )
+            .await
         }
         .boxed_local()
     }
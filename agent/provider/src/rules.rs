@@ -1,8 +1,10 @@
 pub mod outbound;
+pub mod policy;
 pub mod restrict;
-mod store;
+pub(crate) mod store;
 
 use crate::rules::outbound::{CertRule, Mode, OutboundRules};
+use crate::rules::policy::ManifestFeature;
 use crate::rules::restrict::{AllowOnly, Blacklist, RestrictRule, RuleAccessor};
 use crate::rules::store::Rulestore;
 use crate::startup_config::FileMonitor;
@@ -12,6 +14,7 @@ use golem_certificate::schemas::certificate::Fingerprint;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashSet,
     convert::TryFrom,
     path::{Path, PathBuf},
 };
@@ -81,9 +84,15 @@ impl RulesManager {
         requestor_id: NodeId,
         manifest_sig: Option<ManifestSignatureProps>,
         node_descriptor: Option<serde_json::Value>,
+        features: HashSet<ManifestFeature>,
     ) -> CheckRulesResult {
-        self.outbound()
-            .check_outbound_rules(access, requestor_id, manifest_sig, node_descriptor)
+        self.outbound().check_outbound_rules(
+            access,
+            requestor_id,
+            manifest_sig,
+            node_descriptor,
+            features,
+        )
     }
 
     /// TODO: function should be able to distinguish x509 and golem certificates and import
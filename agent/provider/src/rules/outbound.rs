@@ -1,10 +1,11 @@
+use crate::rules::policy::{FeaturePolicy, ManifestFeature, PolicyContext};
 use crate::rules::store::Rulestore;
 use crate::rules::{CheckRulesResult, ManifestSignatureProps};
 use anyhow::anyhow;
 use golem_certificate::schemas::permissions::Permissions;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Not;
 use structopt::StructOpt;
 use strum_macros::{Display, EnumString, EnumVariantNames};
@@ -24,6 +25,10 @@ pub struct OutboundConfig {
     pub audited_payload: HashMap<String, CertRule>,
     #[serde(default)]
     pub partner: HashMap<String, CertRule>,
+    /// Policies depending on manifest features. Outbound is rejected,
+    /// when any of them isn't satisfied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<FeaturePolicy>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -91,6 +96,7 @@ impl OutboundRules {
         requestor_id: NodeId,
         manifest_sig: Option<ManifestSignatureProps>,
         node_descriptor: Option<serde_json::Value>,
+        features: HashSet<ManifestFeature>,
     ) -> CheckRulesResult {
         if self.rulestore.is_outbound_disabled() {
             log::trace!("Checking rules: outbound is disabled.");
@@ -98,9 +104,17 @@ impl OutboundRules {
             return CheckRulesResult::Reject("outbound is disabled".into());
         }
 
+        let signer_chain = manifest_sig
+            .as_ref()
+            .map(|props| self.verify_manifest_signature(props));
+
+        if let Err(e) = self.check_policies(features, signer_chain.as_ref()) {
+            return CheckRulesResult::Reject(format!("Outbound rejected because: {e}"));
+        }
+
         let (accepts, rejects): (Vec<_>, Vec<_>) = vec![
             self.check_everyone_rule(&access),
-            self.check_audited_payload_rule(&access, manifest_sig),
+            self.check_audited_payload_rule(&access, signer_chain),
             self.check_partner_rule(&access, node_descriptor, requestor_id),
         ]
         .into_iter()
@@ -124,18 +138,44 @@ impl OutboundRules {
             .map_err(|e| anyhow!("Everyone {e}"))
     }
 
+    fn verify_manifest_signature(
+        &self,
+        props: &ManifestSignatureProps,
+    ) -> anyhow::Result<Vec<String>> {
+        self.keystore
+            .verifier(&props.cert)?
+            .with_alg(&props.sig_alg)
+            .verify(&props.manifest_encoded, &props.sig)
+    }
+
+    fn check_policies(
+        &self,
+        features: HashSet<ManifestFeature>,
+        signer_chain: Option<&anyhow::Result<Vec<String>>>,
+    ) -> anyhow::Result<()> {
+        let ctx = PolicyContext {
+            features,
+            signer_chain: match signer_chain {
+                Some(Ok(chain)) => chain.clone(),
+                _ => Vec::new(),
+            },
+        };
+
+        let config = self.rulestore.config.read().unwrap();
+        for policy in config.outbound.policies.iter() {
+            policy.check(&ctx)?;
+        }
+        Ok(())
+    }
+
     fn check_audited_payload_rule(
         &self,
         access: &OutboundAccess,
-        manifest_sig: Option<ManifestSignatureProps>,
+        signer_chain: Option<anyhow::Result<Vec<String>>>,
     ) -> anyhow::Result<()> {
-        if let Some(props) = manifest_sig {
-            let cert_chain_ids = self
-                .keystore
-                .verifier(&props.cert)?
-                .with_alg(&props.sig_alg)
-                .verify(&props.manifest_encoded, &props.sig)
-                .map_err(|e| anyhow!("Audited-Payload rule: {e}"))?;
+        if let Some(cert_chain_ids) = signer_chain {
+            let cert_chain_ids =
+                cert_chain_ids.map_err(|e| anyhow!("Audited-Payload rule: {e}"))?;
 
             let rulestore_config = self.rulestore.config.read().unwrap();
             // Rule set for certificate closes to leaf takes precedence
@@ -307,6 +347,7 @@ impl Default for OutboundConfig {
             everyone: Mode::Whitelist,
             audited_payload: HashMap::new(),
             partner: HashMap::new(),
+            policies: Vec::new(),
         }
     }
 }
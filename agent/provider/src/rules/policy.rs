@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use strum_macros::Display;
+
+use ya_manifest_utils::{AppManifest, OutboundAccess};
+
+/// Properties of the Demand and its manifest, which policies can depend on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum ManifestFeature {
+    /// Manifest declares internet access.
+    Inet,
+    /// Manifest requests outbound access to any URL.
+    UnrestrictedOutbound,
+    /// Manifest restricts ExeScript commands, which Requestor can run.
+    Script,
+    /// Demand requests host devices passthrough (e.g. GPU).
+    Devices,
+}
+
+impl ManifestFeature {
+    pub fn declared(manifest: &AppManifest, devices_requested: bool) -> HashSet<ManifestFeature> {
+        let mut features = HashSet::new();
+        let comp = manifest.comp_manifest.as_ref();
+
+        if comp
+            .and_then(|comp| comp.net.as_ref())
+            .map(|net| net.inet.is_some())
+            == Some(true)
+        {
+            features.insert(ManifestFeature::Inet);
+        }
+        if manifest.get_outbound_access() == Some(OutboundAccess::Unrestricted) {
+            features.insert(ManifestFeature::UnrestrictedOutbound);
+        }
+        if comp.map(|comp| comp.script.is_some()) == Some(true) {
+            features.insert(ManifestFeature::Script);
+        }
+        if devices_requested {
+            features.insert(ManifestFeature::Devices);
+        }
+        features
+    }
+}
+
+/// Condition composed of manifest features and manifest signature, e.g.
+/// `{"all": [{"feature": "devices"}, {"not": {"feature": "inet"}}]}`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Condition {
+    Feature(ManifestFeature),
+    /// Manifest is signed with certificate, which has given cert id (or its prefix)
+    /// in its chain.
+    AuditedPayload(String),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn matches(&self, ctx: &PolicyContext) -> bool {
+        match self {
+            Condition::Feature(feature) => ctx.features.contains(feature),
+            Condition::AuditedPayload(cert_id) => ctx
+                .signer_chain
+                .iter()
+                .any(|id| id.starts_with(cert_id.as_str())),
+            Condition::Not(condition) => !condition.matches(ctx),
+            Condition::All(conditions) => conditions.iter().all(|c| c.matches(ctx)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.matches(ctx)),
+        }
+    }
+}
+
+/// When Demand matches `when` condition, it also has to match `require` condition
+/// to get outbound access. Policies apply on top of other outbound rules.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FeaturePolicy {
+    #[serde(default)]
+    pub description: String,
+    pub when: Condition,
+    pub require: Condition,
+}
+
+impl FeaturePolicy {
+    pub fn check(&self, ctx: &PolicyContext) -> anyhow::Result<()> {
+        if self.when.matches(ctx) && !self.require.matches(ctx) {
+            anyhow::bail!("manifest policy '{}' not satisfied", self.description);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PolicyContext {
+    pub features: HashSet<ManifestFeature>,
+    /// Cert ids of verified manifest signature chain. Empty for unsigned manifests.
+    pub signer_chain: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(json: &str) -> FeaturePolicy {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn devices_require_audited_payload() {
+        let policy = policy(
+            r#"{
+                "description": "audited devices",
+                "when": {"feature": "devices"},
+                "require": {"any": [{"audited-payload": "cafe"}, {"feature": "script"}]}
+            }"#,
+        );
+
+        let mut ctx = PolicyContext::default();
+        assert!(policy.check(&ctx).is_ok());
+
+        ctx.features.insert(ManifestFeature::Devices);
+        assert!(policy.check(&ctx).is_err());
+
+        ctx.signer_chain = vec!["beef01".into(), "cafe01".into()];
+        assert!(policy.check(&ctx).is_ok());
+    }
+
+    #[test]
+    fn unrestricted_outbound_forbidden() {
+        let policy = policy(
+            r#"{
+                "when": {"feature": "inet"},
+                "require": {"not": {"feature": "unrestricted-outbound"}}
+            }"#,
+        );
+
+        let ctx = PolicyContext {
+            features: HashSet::from([ManifestFeature::Inet, ManifestFeature::UnrestrictedOutbound]),
+            signer_chain: vec![],
+        };
+        assert!(policy.check(&ctx).is_err());
+    }
+}
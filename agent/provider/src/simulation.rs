@@ -0,0 +1,384 @@
+//! Agreement simulation mode.
+//!
+//! With `--simulate` the Provider negotiates Agreements and creates activities as usual,
+//! but spawns a stub ExeUnit (`ya-provider simulate-exe-unit`) instead of the configured
+//! ones. The stub binds the ExeUnit GSB API, completes every command successfully after
+//! a fixed delay and reports usage growing with time, so market and payment pipelines
+//! can be load tested without consuming real compute.
+use anyhow::anyhow;
+use chrono::Utc;
+use serde_json::json;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+use tokio::sync::watch;
+
+use ya_agreement_utils::agreement::{try_from_path, AgreementView};
+use ya_agreement_utils::OfferTemplate;
+use ya_client_model::activity::{
+    ActivityState, ActivityUsage, CommandResult, ExeScriptCommand, ExeScriptCommandResult, State,
+    StatePair,
+};
+use ya_core_model::activity::{self, local, RpcMessageError};
+use ya_service_bus::typed as bus;
+
+use crate::signal::SignalMonitor;
+
+/// Provider subcommand running the stub ExeUnit.
+pub const STUB_COMMAND: &str = "simulate-exe-unit";
+
+const DURATION_COUNTER: &str = "golem.usage.duration_sec";
+const CPU_COUNTER: &str = "golem.usage.cpu_sec";
+const MEM_COUNTER: &str = "golem.usage.gib";
+const STORAGE_COUNTER: &str = "golem.usage.storage_gib";
+const USAGE_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Stub ExeUnit faking execution and usage. Accepts the ExeUnit command line.
+#[derive(StructOpt, Clone, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct StubConfig {
+    /// Name of the simulated runtime
+    #[structopt(long)]
+    pub runtime: String,
+    /// How long each ExeScript command takes
+    #[structopt(
+        long,
+        env = "YA_SIMULATION_COMMAND_DURATION",
+        parse(try_from_str = humantime::parse_duration),
+        default_value = "1s"
+    )]
+    pub command_duration: Duration,
+    /// Simulated CPU load as a fraction of CPU threads from the Agreement
+    #[structopt(long, env = "YA_SIMULATION_CPU_LOAD", default_value = "0.5")]
+    pub cpu_load: f64,
+    #[structopt(subcommand)]
+    pub command: StubCommand,
+}
+
+#[derive(StructOpt, Clone, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub enum StubCommand {
+    /// Bind to Service Bus
+    ServiceBus {
+        service_id: String,
+        report_url: String,
+        #[structopt(long, short)]
+        agreement: PathBuf,
+        #[structopt(long, short)]
+        work_dir: PathBuf,
+        #[structopt(long, short)]
+        cache_dir: PathBuf,
+        #[structopt(long)]
+        requestor_pub_key: Option<String>,
+    },
+    /// Print an offer template in JSON format
+    OfferTemplate,
+    /// Check, that the ExeUnit works
+    Test,
+}
+
+impl StubConfig {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match &self.command {
+            StubCommand::OfferTemplate => {
+                println!("{}", serde_json::to_string(&offer_template())?);
+                Ok(())
+            }
+            StubCommand::Test => Ok(()),
+            StubCommand::ServiceBus {
+                service_id,
+                report_url,
+                agreement,
+                ..
+            } => {
+                let agreement = try_from_path(agreement)
+                    .and_then(AgreementView::try_from)
+                    .map_err(|e| anyhow!("Invalid Agreement file: {e}"))?;
+                let stub = Stub::new(&self, service_id.clone(), report_url.clone(), &agreement)?;
+                stub.run().await
+            }
+        }
+    }
+}
+
+/// Offers of simulated ExeUnits are marked, so Requestors can tell them apart.
+fn offer_template() -> OfferTemplate {
+    OfferTemplate::new(json!({
+        "golem.com.usage.vector": [DURATION_COUNTER, CPU_COUNTER],
+        "golem.runtime.simulated": true,
+    }))
+}
+
+/// Simulated resources of the Agreement.
+#[derive(Clone, Debug, PartialEq)]
+struct Usage {
+    usage_vector: Vec<String>,
+    cpu_threads: f64,
+    mem_gib: f64,
+    storage_gib: f64,
+    cpu_load: f64,
+}
+
+impl Usage {
+    fn new(agreement: &AgreementView, cpu_load: f64) -> anyhow::Result<Self> {
+        let inf = |name: &str| {
+            agreement
+                .pointer_typed::<f64>(&format!("/offer/properties/golem/inf/{name}"))
+                .unwrap_or(0.)
+        };
+        Ok(Usage {
+            usage_vector: agreement
+                .pointer_typed::<Vec<String>>("/offer/properties/golem/com/usage/vector")?,
+            cpu_threads: inf("cpu/threads"),
+            mem_gib: inf("mem/gib"),
+            storage_gib: inf("storage/gib"),
+            cpu_load,
+        })
+    }
+
+    /// Values of the usage vector after running for `elapsed`.
+    fn current(&self, elapsed: Duration) -> Vec<f64> {
+        let secs = elapsed.as_secs_f64();
+        self.usage_vector
+            .iter()
+            .map(|counter| match counter.as_str() {
+                DURATION_COUNTER => secs,
+                CPU_COUNTER => secs * self.cpu_threads * self.cpu_load,
+                MEM_COUNTER => self.mem_gib,
+                STORAGE_COUNTER => self.storage_gib,
+                _ => 0.,
+            })
+            .collect()
+    }
+}
+
+struct Batch {
+    commands: Vec<ExeScriptCommand>,
+    /// Number of completed commands.
+    completed: watch::Receiver<usize>,
+}
+
+struct Stub {
+    activity_id: String,
+    report_url: String,
+    command_duration: Duration,
+    usage: Usage,
+    started: Instant,
+    state: RefCell<StatePair>,
+    batches: RefCell<HashMap<String, Batch>>,
+}
+
+impl Stub {
+    fn new(
+        config: &StubConfig,
+        activity_id: String,
+        report_url: String,
+        agreement: &AgreementView,
+    ) -> anyhow::Result<Rc<Self>> {
+        Ok(Rc::new(Stub {
+            activity_id,
+            report_url,
+            command_duration: config.command_duration,
+            usage: Usage::new(agreement, config.cpu_load)?,
+            started: Instant::now(),
+            state: RefCell::new(StatePair(State::New, None)),
+            batches: Default::default(),
+        }))
+    }
+
+    async fn run(self: Rc<Self>) -> anyhow::Result<()> {
+        log::info!("Simulating activity [{}]", self.activity_id);
+        self.bind();
+        self.set_state(StatePair(State::Initialized, None)).await?;
+
+        let stub = self.clone();
+        tokio::task::spawn_local(async move {
+            loop {
+                tokio::time::sleep(USAGE_REPORT_INTERVAL).await;
+                if let Err(e) = stub.report_usage().await {
+                    log::error!("Reporting usage failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        });
+
+        let signal = SignalMonitor::default().recv().await?;
+        log::info!("{signal} received, terminating simulated activity");
+        if let Err(e) = self.set_state(StatePair(State::Terminated, None)).await {
+            log::debug!("Reporting activity termination failed: {e}");
+        }
+        Ok(())
+    }
+
+    fn bind(self: &Rc<Self>) {
+        let service_id = activity::exeunit::bus_id(&self.activity_id);
+
+        let stub = self.clone();
+        bus::bind(&service_id, move |_: activity::GetState| {
+            let state = *stub.state.borrow();
+            async move {
+                Ok(ActivityState {
+                    state,
+                    reason: None,
+                    error_message: None,
+                })
+            }
+        });
+
+        let stub = self.clone();
+        bus::bind(&service_id, move |_: activity::GetUsage| {
+            let usage = stub.usage();
+            async move { Ok(usage) }
+        });
+
+        let stub = self.clone();
+        bus::bind(&service_id, move |msg: activity::Exec| {
+            let result = stub.exec(msg);
+            async move { result }
+        });
+
+        let stub = self.clone();
+        bus::bind(&service_id, move |msg: activity::GetExecBatchResults| {
+            let stub = stub.clone();
+            async move { stub.batch_results(msg).await }
+        });
+    }
+
+    fn exec(self: &Rc<Self>, msg: activity::Exec) -> Result<String, RpcMessageError> {
+        let batch_id = msg.batch_id.clone();
+        if self.batches.borrow().contains_key(&batch_id) {
+            return Err(RpcMessageError::BadRequest(format!(
+                "Batch {batch_id} already exists"
+            )));
+        }
+
+        let (tx, completed) = watch::channel(0);
+        let commands = msg.exe_script;
+        self.batches.borrow_mut().insert(
+            batch_id.clone(),
+            Batch {
+                commands: commands.clone(),
+                completed,
+            },
+        );
+
+        let stub = self.clone();
+        tokio::task::spawn_local(async move {
+            for (idx, command) in commands.iter().enumerate() {
+                tokio::time::sleep(stub.command_duration).await;
+                let state = match command {
+                    ExeScriptCommand::Deploy { .. } => Some(StatePair(State::Deployed, None)),
+                    ExeScriptCommand::Start { .. } => Some(StatePair(State::Ready, None)),
+                    _ => None,
+                };
+                if let Some(state) = state {
+                    if let Err(e) = stub.set_state(state).await {
+                        log::warn!("Reporting activity state failed: {e}");
+                    }
+                }
+                let _ = tx.send(idx + 1);
+            }
+        });
+        Ok(batch_id)
+    }
+
+    async fn batch_results(
+        &self,
+        msg: activity::GetExecBatchResults,
+    ) -> Result<Vec<ExeScriptCommandResult>, RpcMessageError> {
+        let (commands, mut completed) = match self.batches.borrow().get(&msg.batch_id) {
+            Some(batch) => (batch.commands.clone(), batch.completed.clone()),
+            None => {
+                return Err(RpcMessageError::NotFound(format!(
+                    "batch_id = {}",
+                    msg.batch_id
+                )))
+            }
+        };
+        if commands.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let awaited = msg.command_index.unwrap_or(commands.len() - 1) + 1;
+        let timeout = Duration::from_secs_f32(msg.timeout.unwrap_or(0.));
+        let wait = completed.wait_for(|done| *done >= awaited);
+        if tokio::time::timeout(timeout, wait).await.is_err() && msg.command_index.is_some() {
+            return Err(RpcMessageError::Timeout);
+        }
+
+        let done = *completed.borrow();
+        let last = msg.command_index.unwrap_or(commands.len() - 1);
+        Ok((0..done.min(last + 1))
+            .map(|idx| ExeScriptCommandResult {
+                index: idx as u32,
+                result: CommandResult::Ok,
+                stdout: None,
+                stderr: None,
+                message: None,
+                is_batch_finished: idx == commands.len() - 1,
+                event_date: Utc::now(),
+            })
+            .collect())
+    }
+
+    fn usage(&self) -> ActivityUsage {
+        ActivityUsage {
+            current_usage: Some(self.usage.current(self.started.elapsed())),
+            timestamp: Utc::now().timestamp(),
+        }
+    }
+
+    async fn set_state(&self, state: StatePair) -> anyhow::Result<()> {
+        *self.state.borrow_mut() = state;
+        let msg = local::SetState::new(
+            self.activity_id.clone(),
+            ActivityState {
+                state,
+                reason: None,
+                error_message: None,
+            },
+            None,
+        );
+        bus::service(&self.report_url).send(msg).await??;
+        Ok(())
+    }
+
+    async fn report_usage(&self) -> anyhow::Result<()> {
+        let msg = local::SetUsage {
+            activity_id: self.activity_id.clone(),
+            usage: self.usage(),
+            timeout: None,
+        };
+        bus::service(&self.report_url).send(msg).await??;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_grows_with_time() {
+        let usage = Usage {
+            usage_vector: vec![
+                CPU_COUNTER.to_string(),
+                DURATION_COUNTER.to_string(),
+                MEM_COUNTER.to_string(),
+                "golem.usage.custom".to_string(),
+            ],
+            cpu_threads: 4.,
+            mem_gib: 2.,
+            storage_gib: 10.,
+            cpu_load: 0.5,
+        };
+        assert_eq!(
+            usage.current(Duration::from_secs(10)),
+            vec![20., 10., 2., 0.]
+        );
+    }
+}
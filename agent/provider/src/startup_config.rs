@@ -16,6 +16,7 @@ use ya_client::{cli::ApiOpts, model::node_id::NodeId};
 use ya_core_model::payment::local::{DriverName, NetworkName, DEFAULT_PAYMENT_DRIVER};
 use ya_utils_path::data_dir::DataDir;
 
+use crate::benchmark::BenchmarkConfig;
 use crate::cli::clean::CleanConfig;
 use crate::cli::config::ConfigConfig;
 use crate::cli::exe_unit::ExeUnitsConfig;
@@ -26,9 +27,11 @@ use crate::cli::profile::ProfileConfig;
 use crate::cli::rule::RuleCommand;
 use crate::cli::whitelist::WhitelistConfig;
 pub(crate) use crate::config::globals::GLOBALS_JSON;
+pub(crate) use crate::config::profiles::CONFIG_PROFILES_JSON;
 use crate::execution::{ExeUnitsRegistry, TaskRunnerConfig};
 use crate::market::config::MarketConfig;
 use crate::payments::PaymentsConfig;
+use crate::simulation::StubConfig;
 use crate::tasks::config::TaskConfig;
 
 lazy_static::lazy_static! {
@@ -39,6 +42,7 @@ pub(crate) const DOMAIN_WHITELIST_JSON: &str = "domain_whitelist.json";
 pub(crate) const RULES_JSON: &str = "rules.json";
 pub(crate) const PRESETS_JSON: &str = "presets.json";
 pub(crate) const HARDWARE_JSON: &str = "hardware.json";
+pub(crate) const BENCHMARK_JSON: &str = "benchmark.json";
 pub(crate) const CERT_DIR: &str = "cert-dir";
 
 const DATA_DIR_ENV: &str = "DATA_DIR";
@@ -82,6 +86,8 @@ pub struct ProviderConfig {
     pub domain_whitelist_file: PathBuf,
     #[structopt(skip = GLOBALS_JSON)]
     pub globals_file: PathBuf,
+    #[structopt(skip = CONFIG_PROFILES_JSON)]
+    pub config_profiles_file: PathBuf,
     #[structopt(skip = PRESETS_JSON)]
     pub presets_file: PathBuf,
     #[structopt(skip = HARDWARE_JSON)]
@@ -215,6 +221,10 @@ pub struct ReceiverAccount {
         help = "Specify platforms to collect funds, e.g. erc20-mainnet-glm. Network name can be passed as well, in which case the default driver will be used"
     )]
     pub networks: Vec<PaymentPlatform>,
+    /// Accept payments relayed on behalf of Requestors without gas on given platforms,
+    /// e.g. erc20-polygon-glm. Relayer is paid by the Requestor.
+    #[structopt(long = "accept-gasless", env = "YA_ACCEPT_GASLESS")]
+    pub gasless: Vec<PaymentPlatform>,
 }
 
 #[derive(StructOpt, Clone, Debug)]
@@ -245,9 +255,14 @@ pub struct RunConfig {
     pub payment: PaymentsConfig,
     #[structopt(flatten)]
     pub tasks: TaskConfig,
+    #[structopt(flatten)]
+    pub benchmark: BenchmarkConfig,
     ///changes log level from info to debug
     #[structopt(long)]
     pub debug: bool,
+    /// Negotiate and accept Agreements, but run a stub ExeUnit faking execution and usage
+    #[structopt(long, env = "YA_PROVIDER_SIMULATE")]
+    pub simulate: bool,
 }
 
 #[derive(StructOpt, Clone, Debug)]
@@ -260,6 +275,9 @@ pub struct PresetNoInteractive {
     pub pricing: Option<String>,
     #[structopt(long, parse(try_from_str = parse_key_val))]
     pub price: Vec<(String, f64)>,
+    /// CPU threads of Offer variants sharing host capacity, e.g. `--variants 2,4,8`
+    #[structopt(long, use_delimiter = true)]
+    pub variants: Option<Vec<i32>>,
 }
 
 #[derive(StructOpt, Clone, Debug)]
@@ -310,6 +328,9 @@ pub enum Commands {
     Clean(CleanConfig),
     /// Manage Rule config
     Rule(RuleCommand),
+    /// Stub ExeUnit spawned in simulation mode
+    #[structopt(name = "simulate-exe-unit", setting = clap::AppSettings::Hidden)]
+    SimulateExeUnit(StubConfig),
 }
 
 #[derive(Debug)]
@@ -304,6 +304,10 @@ impl Handler<InitializeTaskManager> for TaskManager {
             let msg = Subscribe::<BreakAgreement>(actx.myself.clone().recipient());
             actx.payments.send(msg).await?;
 
+            // Listen to BreakAgreement signals emitted by TaskRunner on permanent workload failures
+            let msg = Subscribe::<BreakAgreement>(actx.myself.clone().recipient());
+            actx.runner.send(msg).await?;
+
             // Get info about Activity creation and destruction.
             let msg = Subscribe::<CreateActivity>(actx.myself.clone().recipient());
             actx.runner.send(msg).await?;
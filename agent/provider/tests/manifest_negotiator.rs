@@ -114,6 +114,18 @@ fn manifest_negotiator_test_accepted_because_of_no_payload() {
     None; // error msg
     "Accepted because everyone whitelist matched"
 )]
+#[test_case(
+    r#"{"outbound": {"enabled": true, "everyone": "all", "policies": [{"description": "signed inet", "when": {"feature": "inet"}, "require": {"audited-payload": "55e451bd"}}]}}"#, // rulestore config
+    &["https://domain.com"],
+    Some("manifest policy 'signed inet' not satisfied"); // error msg
+    "Rejected because manifest policy requires audited payload"
+)]
+#[test_case(
+    r#"{"outbound": {"enabled": true, "everyone": "all", "policies": [{"when": {"feature": "devices"}, "require": {"feature": "script"}}]}}"#, // rulestore config
+    &["https://domain.com"],
+    None; // error msg
+    "Accepted because manifest policy doesn't apply"
+)]
 #[serial]
 fn manifest_negotiator_test_manifest_with_urls(
     rulestore: &str,
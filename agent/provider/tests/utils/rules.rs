@@ -137,7 +137,8 @@ pub fn setup_identity_rules<G: RuleAccessor>(rules: RestrictRule<G>, ids: &[&str
 pub fn expect_accept(result: NegotiationResult) {
     match result {
         NegotiationResult::Ready { .. } => {}
-        NegotiationResult::Reject { message, .. } => {
+        NegotiationResult::Reject { message, .. }
+        | NegotiationResult::RejectWithReason { message, .. } => {
             panic!("Expected negotiations accepted, got: {}", message)
         }
         NegotiationResult::Negotiating { .. } => {
@@ -152,7 +153,10 @@ pub fn expect_reject(result: NegotiationResult, error: Option<&str>) {
         NegotiationResult::Negotiating { .. } => {
             panic!("Expected negotiations rejected, got: Negotiating")
         }
-        NegotiationResult::Reject { message, is_final } => {
+        NegotiationResult::Reject { message, is_final }
+        | NegotiationResult::RejectWithReason {
+            message, is_final, ..
+        } => {
             assert!(is_final);
             if let Some(expected_error) = error {
                 if !message.contains(expected_error) {
@@ -0,0 +1,4 @@
+CREATE TABLE activity_security_profile (
+	activity_id TEXT NOT NULL PRIMARY KEY,
+	profile TEXT NOT NULL
+);
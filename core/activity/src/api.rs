@@ -1,19 +1,22 @@
 use actix_web::web::Data;
 use actix_web::Scope;
 
-use crate::TrackerRef;
+use crate::{ExecGraphs, TrackerRef};
 
 use ya_persistence::executor::DbExecutor;
 use ya_service_api_web::scope::ExtendableScope;
 
-pub fn web_scope(db: &DbExecutor, tracker: TrackerRef) -> Scope {
+pub fn web_scope(db: &DbExecutor, tracker: TrackerRef, graphs: ExecGraphs) -> Scope {
     actix_web::web::scope(crate::ACTIVITY_API_PATH)
         .app_data(Data::new(db.clone()))
         .app_data(Data::new(tracker))
+        .app_data(Data::new(graphs))
         .extend(common::extend_web_scope)
         .extend(crate::provider::extend_web_scope)
         .extend(crate::requestor::control::extend_web_scope)
+        .extend(crate::requestor::graph::extend_web_scope)
         .extend(crate::requestor::state::extend_web_scope)
+        .extend(crate::requestor::terminal::extend_web_scope)
         .extend(crate::http_proxy::extend_web_scope)
 }
 
@@ -35,7 +35,11 @@ impl ActivityCli {
                             Self::get_identity(idm::Get::ByAlias(id)).await?.node_id
                         }
                     }
-                    None => Self::get_identity(idm::Get::ByDefault).await?.node_id,
+                    None => {
+                        Self::get_identity(idm::Get::ByModule(idm::Module::Activity))
+                            .await?
+                            .node_id
+                    }
                 };
                 let result = bus::service(acm::BUS_ID)
                     .send(acm::Stats { identity })
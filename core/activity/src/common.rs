@@ -11,6 +11,7 @@ use ya_client_model::{
     market::{Agreement, AgreementListEntry, Role},
     NodeId,
 };
+use ya_core_model::events::EventPayload;
 use ya_core_model::{activity, market};
 use ya_net::RemoteEndpoint;
 use ya_persistence::executor::DbExecutor;
@@ -106,10 +107,18 @@ pub(crate) async fn set_persisted_state(
     activity_id: &str,
     activity_state: ActivityState,
 ) -> Result<ActivityState, Error> {
-    Ok(db
+    let state = db
         .as_dao::<ActivityStateDao>()
         .set(activity_id, activity_state)
-        .await?)
+        .await?;
+    ya_events::publish(
+        None,
+        EventPayload::ActivityStateChanged {
+            activity_id: activity_id.to_string(),
+            state: format!("{:?}", state.state.0),
+        },
+    );
+    Ok(state)
 }
 
 pub(crate) fn agreement_provider_service(
@@ -0,0 +1,47 @@
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+use crate::dao::Result;
+use crate::db::{models::ActivitySecurityProfile, schema};
+use diesel::{OptionalExtension, QueryDsl, RunQueryDsl};
+
+pub struct ActivitySecurityProfileDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'a> AsDao<'a> for ActivitySecurityProfileDao<'a> {
+    fn as_dao(pool: &'a PoolType) -> Self {
+        ActivitySecurityProfileDao { pool }
+    }
+}
+
+impl<'c> ActivitySecurityProfileDao<'c> {
+    pub async fn get(&self, activity_id: &str) -> Result<Option<String>> {
+        use schema::activity_security_profile::dsl;
+        let activity_id = activity_id.to_owned();
+
+        readonly_transaction(self.pool, "activity_security_profile_get", move |conn| {
+            Ok(dsl::activity_security_profile
+                .find(&activity_id)
+                .first::<ActivitySecurityProfile>(conn)
+                .optional()?
+                .map(|entry| entry.profile))
+        })
+        .await
+    }
+
+    pub async fn set(&self, activity_id: &str, profile: String) -> Result<()> {
+        use schema::activity_security_profile::dsl;
+        let entry = ActivitySecurityProfile {
+            activity_id: activity_id.to_owned(),
+            profile,
+        };
+
+        do_with_transaction(self.pool, "activity_security_profile_set", move |conn| {
+            diesel::replace_into(dsl::activity_security_profile)
+                .values(&entry)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+}
@@ -1,11 +1,13 @@
 mod activity;
 mod activity_credentials;
+mod activity_security_profile;
 mod activity_state;
 mod activity_usage;
 mod event;
 
 pub use activity::ActivityDao;
 pub use activity_credentials::ActivityCredentialsDao;
+pub use activity_security_profile::ActivitySecurityProfileDao;
 pub use activity_state::ActivityStateDao;
 pub use activity_usage::ActivityUsageDao;
 pub use event::EventDao;
@@ -159,6 +159,14 @@ pub struct ActivityCredentials {
     pub credentials: String,
 }
 
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable)]
+#[table_name = "activity_security_profile"]
+#[primary_key(activity_id)]
+pub struct ActivitySecurityProfile {
+    pub activity_id: String,
+    pub profile: String,
+}
+
 impl TryFrom<ActivityCredentials> for Option<ya_client_model::activity::Credentials> {
     type Error = ya_persistence::Error;
 
@@ -34,6 +34,13 @@ table! {
     }
 }
 
+table! {
+    activity_security_profile (activity_id) {
+        activity_id -> Text,
+        profile -> Text,
+    }
+}
+
 table! {
     activity_state (id) {
         id -> Integer,
@@ -85,6 +92,7 @@ allow_tables_to_appear_in_same_query!(
     activity_credentials,
     activity_event,
     activity_event_type,
+    activity_security_profile,
     activity_state,
     activity_usage,
     runtime_event,
@@ -21,4 +21,5 @@ pub mod service;
 mod tracker;
 
 pub type Result<T> = std::result::Result<T, error::Error>;
+pub use self::requestor::graph::ExecGraphs;
 pub use self::tracker::TrackerRef;
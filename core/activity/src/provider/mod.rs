@@ -9,13 +9,16 @@ use ya_persistence::executor::DbExecutor;
 use ya_service_api_web::middleware::Identity;
 
 use crate::common::{authorize_activity_executor, set_persisted_state, PathActivity, QueryEvents};
-use crate::dao::EventDao;
+use crate::dao::{ActivitySecurityProfileDao, EventDao};
 use crate::error::Error;
 
 pub mod service;
 
 pub fn extend_web_scope(scope: actix_web::Scope) -> actix_web::Scope {
-    scope.service(get_events).service(set_activity_state)
+    scope
+        .service(get_events)
+        .service(set_activity_state)
+        .service(get_security_profile)
 }
 
 #[actix_web::put("/activity/{activity_id}/state")]
@@ -33,6 +36,24 @@ async fn set_activity_state(
         .map(|_| web::Json(()))
 }
 
+/// Security profile, which the ExeUnit confined the runtime with.
+#[actix_web::get("/activity/{activity_id}/security-profile")]
+async fn get_security_profile(
+    db: web::Data<DbExecutor>,
+    path: web::Path<PathActivity>,
+    id: Identity,
+) -> impl Responder {
+    authorize_activity_executor(&db, id.identity, &path.activity_id, Role::Provider).await?;
+
+    db.as_dao::<ActivitySecurityProfileDao>()
+        .get(&path.activity_id)
+        .await?
+        .map(web::Json)
+        .ok_or_else(|| {
+            Error::NotFound(format!("Security profile of activity {}", path.activity_id))
+        })
+}
+
 /// Fetch Requestor command events.
 #[actix_web::get("/events")]
 async fn get_events(
@@ -12,6 +12,7 @@ use ya_client_model::market::{agreement::State as AgreementState, Role};
 use ya_client_model::NodeId;
 use ya_core_model::activity::local::Credentials;
 use ya_core_model::activity::RpcMessageError;
+use ya_core_model::events::EventPayload;
 use ya_core_model::market::Agreement;
 use ya_core_model::{activity, market};
 use ya_persistence::executor::DbExecutor;
@@ -219,6 +220,13 @@ async fn create_activity_gsb(
         "Requestor [{caller}] created Activity [{activity_id}] for Agreement [{agreement_id}]"
     );
     counter!("activity.provider.created", 1);
+    ya_events::publish(
+        Some(*agreement.provider_id()),
+        EventPayload::ActivityCreated {
+            activity_id: activity_id.clone(),
+            agreement_id: agreement_id,
+        },
+    );
 
     Ok(if credentials.is_none() {
         activity::CreateResponseCompat::ActivityId(activity_id)
@@ -539,6 +547,12 @@ mod local {
                 .await
                 .map_err(Error::from)?;
         }
+        if let Some(profile) = msg.security_profile {
+            db.as_dao::<ActivitySecurityProfileDao>()
+                .set(&msg.activity_id, profile)
+                .await
+                .map_err(Error::from)?;
+        }
         let _ = tracker
             .update_state(msg.activity_id.clone(), msg.state.state.0)
             .await;
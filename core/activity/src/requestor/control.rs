@@ -9,8 +9,8 @@ use std::time::Duration;
 use tokio_stream::wrappers::IntervalStream;
 
 use ya_client_model::activity::{
-    ActivityState, CreateActivityRequest, CreateActivityResult, Credentials, ExeScriptCommand,
-    ExeScriptRequest, SgxCredentials, State,
+    ActivityState, CreateActivityRequest, CreateActivityResult, Credentials, ExeScriptRequest,
+    SgxCredentials, State,
 };
 use ya_client_model::market::{Agreement, Role};
 use ya_core_model::activity;
@@ -28,7 +28,9 @@ pub fn extend_web_scope(scope: actix_web::Scope) -> actix_web::Scope {
         .service(create_activity)
         .service(destroy_activity)
         .service(exec)
+        .service(write_stdin)
         .service(get_batch_results)
+        .service(get_batch_state)
         .service(encrypted)
 }
 
@@ -168,14 +170,15 @@ async fn destroy_activity(
 async fn exec(
     db: web::Data<DbExecutor>,
     path: web::Path<PathActivity>,
-    query: web::Query<QueryTimeout>,
+    query: web::Query<QueryExec>,
     body: web::Json<ExeScriptRequest>,
     id: Identity,
 ) -> impl Responder {
     authorize_activity_initiator(&db, id.identity, &path.activity_id, Role::Requestor).await?;
 
-    let commands: Vec<ExeScriptCommand> =
-        serde_json::from_str(&body.text).map_err(|e| Error::BadRequest(format!("{:?}", e)))?;
+    let (commands, run_options) = activity::exe_script::parse(&body.text)
+        .map_err(|e| Error::BadRequest(format!("{:?}", e)))?;
+    let interactive = query.interactive()?;
     let agreement = get_activity_agreement(&db, &path.activity_id, Role::Requestor).await?;
     let batch_id = generate_id();
     let msg = activity::Exec {
@@ -183,6 +186,9 @@ async fn exec(
         batch_id: batch_id.clone(),
         exe_script: commands,
         timeout: query.timeout,
+        interactive,
+        metrics_push_url: query.metrics_push_url.clone(),
+        run_options,
     };
 
     ya_net::from(id.identity)
@@ -196,6 +202,36 @@ async fn exec(
     Ok::<_, Error>(web::Json(batch_id))
 }
 
+/// Writes request body to stdin of a running interactive command.
+#[actix_web::post("/activity/{activity_id}/exec/{batch_id}/stdin/{command_index}")]
+async fn write_stdin(
+    db: web::Data<DbExecutor>,
+    path: web::Path<PathActivityBatchCommand>,
+    query: web::Query<QueryStdin>,
+    body: web::Bytes,
+    id: Identity,
+) -> impl Responder {
+    authorize_activity_initiator(&db, id.identity, &path.activity_id, Role::Requestor).await?;
+
+    let agreement = get_activity_agreement(&db, &path.activity_id, Role::Requestor).await?;
+    let msg = activity::WriteStdin {
+        activity_id: path.activity_id.clone(),
+        batch_id: path.batch_id.clone(),
+        idx: path.command_index,
+        data: body.to_vec(),
+        close: query.close,
+    };
+
+    let written = ya_net::from(id.identity)
+        .to(*agreement.provider_id())
+        .service(&activity::exeunit::bus_id(&path.activity_id))
+        .send(msg)
+        .timeout(timeout_margin(query.timeout))
+        .await???;
+
+    Ok::<_, Error>(web::Json(written))
+}
+
 /// Queries for ExeScript batch results.
 #[actix_web::get("/activity/{activity_id}/exec/{batch_id}")]
 async fn get_batch_results(
@@ -218,6 +254,33 @@ async fn get_batch_results(
     ))
 }
 
+/// Returns state and timing of all ExeScript batch commands without waiting for them.
+#[actix_web::get("/activity/{activity_id}/exec/{batch_id}/state")]
+async fn get_batch_state(
+    db: web::Data<DbExecutor>,
+    path: web::Path<PathActivityBatch>,
+    query: web::Query<QueryBatchState>,
+    id: Identity,
+) -> impl Responder {
+    authorize_activity_initiator(&db, id.identity, &path.activity_id, Role::Requestor).await?;
+
+    let agreement = get_activity_agreement(&db, &path.activity_id, Role::Requestor).await?;
+    let msg = activity::GetExecBatchState {
+        activity_id: path.activity_id.clone(),
+        batch_id: path.batch_id.clone(),
+        output_limit: query.output_limit,
+    };
+
+    let state = ya_net::from(id.identity)
+        .to(*agreement.provider_id())
+        .service_transfer(&activity::exeunit::bus_id(&path.activity_id))
+        .send(msg)
+        .timeout(timeout_margin(query.timeout))
+        .await???;
+
+    Ok::<_, Error>(web::Json(state))
+}
+
 async fn await_results(
     agreement: Agreement,
     path: web::Path<PathActivityBatch>,
@@ -332,6 +395,57 @@ struct PathActivityBatch {
     batch_id: String,
 }
 
+#[derive(Deserialize)]
+struct PathActivityBatchCommand {
+    activity_id: String,
+    batch_id: String,
+    command_index: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryExec {
+    #[serde(default = "default_query_timeout")]
+    timeout: Option<f32>,
+    /// Comma separated indices of `Run` commands accepting streamed stdin.
+    interactive: Option<String>,
+    /// Collector endpoint, which the Provider pushes activity metrics to.
+    metrics_push_url: Option<String>,
+}
+
+impl QueryExec {
+    fn interactive(&self) -> Result<Vec<usize>> {
+        self.interactive
+            .iter()
+            .flat_map(|s| s.split(','))
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| {
+                s.trim()
+                    .parse()
+                    .map_err(|_| Error::BadRequest(format!("Invalid command index: {}", s)))
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct QueryStdin {
+    #[serde(default = "default_query_timeout")]
+    timeout: Option<f32>,
+    /// Close stdin after writing the body.
+    #[serde(default)]
+    close: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryBatchState {
+    #[serde(default = "default_query_timeout")]
+    timeout: Option<f32>,
+    /// Maximal number of trailing characters of each command output.
+    output_limit: Option<usize>,
+}
+
 fn convert_credentials(
     credentials: &ya_core_model::activity::local::Credentials,
 ) -> Result<Credentials> {
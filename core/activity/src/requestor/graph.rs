@@ -0,0 +1,466 @@
+//! Execution of ExeScript batches ordered by declared dependencies.
+//!
+//! Requestor submits the whole graph at once. ExeUnit executes a single batch at a time,
+//! so batches of an Activity are sent one by one, each after all batches it depends on
+//! finish successfully. This saves the client a round-trip per batch. Batches depending
+//! on a failed batch are skipped.
+use actix_web::{web, Responder};
+use chrono::{DateTime, Duration, Utc};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, Weak};
+
+use ya_client_model::activity::{CommandResult, ExeScriptCommand};
+use ya_client_model::market::Role;
+use ya_client_model::NodeId;
+use ya_core_model::activity;
+use ya_net::RemoteEndpoint;
+use ya_persistence::executor::DbExecutor;
+use ya_service_api_web::middleware::Identity;
+use ya_service_bus::{timeout::IntoTimeoutFuture, RpcEndpoint};
+
+use crate::common::*;
+use crate::{error::Error, Result};
+
+/// Timeout of a single poll for batch results.
+const POLL_TIMEOUT: f32 = 10.0;
+/// Statuses of finished graphs are kept for this long.
+const FINISHED_RETENTION_HOURS: i64 = 1;
+
+/// Exec graphs submitted by Requestors, shared by all REST workers.
+#[derive(Clone, Default)]
+pub struct ExecGraphs {
+    inner: Arc<Mutex<GraphsInner>>,
+}
+
+#[derive(Default)]
+struct GraphsInner {
+    graphs: HashMap<String, GraphStatus>,
+    /// Held while a batch of the Activity is executed.
+    activities: HashMap<String, Weak<tokio::sync::Mutex<()>>>,
+}
+
+impl ExecGraphs {
+    /// Registers a new graph and returns the lock serializing batches of its Activity.
+    fn insert(&self, status: GraphStatus) -> Arc<tokio::sync::Mutex<()>> {
+        let mut inner = self.inner.lock().unwrap();
+        let retention = Duration::hours(FINISHED_RETENTION_HOURS);
+        inner.graphs.retain(|_, graph| {
+            graph
+                .finished_date
+                .map_or(true, |date| Utc::now() - date < retention)
+        });
+        inner.activities.retain(|_, lock| lock.strong_count() > 0);
+
+        let activity_lock = inner
+            .activities
+            .get(&status.activity_id)
+            .and_then(Weak::upgrade)
+            .unwrap_or_default();
+        inner
+            .activities
+            .insert(status.activity_id.clone(), Arc::downgrade(&activity_lock));
+        inner.graphs.insert(status.graph_id.clone(), status);
+        activity_lock
+    }
+
+    fn get(&self, graph_id: &str) -> Option<GraphStatus> {
+        self.inner.lock().unwrap().graphs.get(graph_id).cloned()
+    }
+
+    fn update<T>(&self, graph_id: &str, f: impl FnOnce(&mut GraphStatus) -> T) -> Option<T> {
+        self.inner.lock().unwrap().graphs.get_mut(graph_id).map(f)
+    }
+}
+
+pub fn extend_web_scope(scope: actix_web::Scope) -> actix_web::Scope {
+    scope.service(exec_graph).service(get_graph_status)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExeScriptGraphRequest {
+    pub nodes: Vec<ExeScriptNode>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExeScriptNode {
+    pub id: String,
+    /// ExeScript in the same format as in `ExeScriptRequest`.
+    pub text: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeState {
+    Pending,
+    Running,
+    Finished,
+    Failed,
+    Skipped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatus {
+    pub id: String,
+    pub depends_on: Vec<String>,
+    pub state: NodeState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphStatus {
+    pub graph_id: String,
+    pub activity_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_date: Option<DateTime<Utc>>,
+    pub nodes: Vec<NodeStatus>,
+}
+
+impl GraphStatus {
+    fn node_mut(&mut self, id: &str) -> Option<&mut NodeStatus> {
+        self.nodes.iter_mut().find(|node| node.id == id)
+    }
+
+    /// Skips nodes depending on failed ones and starts the first node with all dependencies
+    /// finished. Returns id of the started node.
+    fn advance(&mut self) -> Option<String> {
+        loop {
+            let states = self
+                .nodes
+                .iter()
+                .map(|node| (node.id.clone(), node.state))
+                .collect::<HashMap<_, _>>();
+            let mut skipped = false;
+            for node in self.nodes.iter_mut() {
+                let failed_dep = node
+                    .depends_on
+                    .iter()
+                    .any(|dep| matches!(states[dep], NodeState::Failed | NodeState::Skipped));
+                if node.state == NodeState::Pending && failed_dep {
+                    node.state = NodeState::Skipped;
+                    skipped = true;
+                }
+            }
+            if !skipped {
+                break;
+            }
+        }
+
+        let states = self
+            .nodes
+            .iter()
+            .map(|node| (node.id.clone(), node.state))
+            .collect::<HashMap<_, _>>();
+        self.nodes
+            .iter_mut()
+            .filter(|node| node.state == NodeState::Pending)
+            .find(|node| {
+                node.depends_on
+                    .iter()
+                    .all(|dep| states[dep] == NodeState::Finished)
+            })
+            .map(|node| {
+                node.state = NodeState::Running;
+                node.id.clone()
+            })
+    }
+}
+
+/// Checks, that node ids are unique and dependencies are known and acyclic.
+fn validate(nodes: &[ExeScriptNode]) -> std::result::Result<(), String> {
+    let mut ids = HashSet::new();
+    for node in nodes {
+        if !ids.insert(node.id.as_str()) {
+            return Err(format!("Duplicated node id: {}", node.id));
+        }
+    }
+    for node in nodes {
+        if let Some(dep) = node
+            .depends_on
+            .iter()
+            .find(|dep| !ids.contains(dep.as_str()))
+        {
+            return Err(format!("Node {} depends on unknown node {}", node.id, dep));
+        }
+    }
+
+    // Kahn's algorithm: all nodes can be ordered only if there are no cycles.
+    let mut remaining = nodes
+        .iter()
+        .map(|node| {
+            (
+                node.id.as_str(),
+                node.depends_on.iter().collect::<HashSet<_>>(),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    while !remaining.is_empty() {
+        let ready = remaining
+            .iter()
+            .filter(|(_, deps)| deps.is_empty())
+            .map(|(id, _)| id.to_string())
+            .collect::<Vec<_>>();
+        if ready.is_empty() {
+            let mut cycle = remaining.keys().collect::<Vec<_>>();
+            cycle.sort();
+            return Err(format!("Dependency cycle between nodes: {:?}", cycle));
+        }
+        for id in ready {
+            remaining.remove(id.as_str());
+            for deps in remaining.values_mut() {
+                deps.remove(&id);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct PathActivityGraph {
+    activity_id: String,
+    graph_id: String,
+}
+
+/// Executes a graph of ExeScript batches within a given Activity.
+#[actix_web::post("/activity/{activity_id}/exec-graph")]
+async fn exec_graph(
+    db: web::Data<DbExecutor>,
+    graphs: web::Data<ExecGraphs>,
+    path: web::Path<PathActivity>,
+    query: web::Query<QueryTimeout>,
+    body: web::Json<ExeScriptGraphRequest>,
+    id: Identity,
+) -> impl Responder {
+    authorize_activity_initiator(&db, id.identity, &path.activity_id, Role::Requestor).await?;
+
+    let request = body.into_inner();
+    validate(&request.nodes).map_err(Error::BadRequest)?;
+    let mut scripts = HashMap::new();
+    for node in &request.nodes {
+        let commands: Vec<ExeScriptCommand> = serde_json::from_str(&node.text)
+            .map_err(|e| Error::BadRequest(format!("Node {}: {:?}", node.id, e)))?;
+        scripts.insert(node.id.clone(), commands);
+    }
+
+    let agreement = get_activity_agreement(&db, &path.activity_id, Role::Requestor).await?;
+    let graph_id = generate_id();
+    let status = GraphStatus {
+        graph_id: graph_id.clone(),
+        activity_id: path.activity_id.clone(),
+        finished_date: None,
+        nodes: request
+            .nodes
+            .into_iter()
+            .map(|node| NodeStatus {
+                id: node.id,
+                depends_on: node.depends_on,
+                state: NodeState::Pending,
+                batch_id: None,
+                error: None,
+            })
+            .collect(),
+    };
+    let activity_lock = graphs.insert(status);
+
+    let runner = GraphRunner {
+        graphs: graphs.as_ref().clone(),
+        activity_lock,
+        graph_id: graph_id.clone(),
+        activity_id: path.activity_id.clone(),
+        caller: id.identity,
+        provider_id: *agreement.provider_id(),
+        timeout: query.timeout,
+    };
+    tokio::task::spawn_local(runner.run(scripts));
+
+    counter!("activity.requestor.run-exescript-graph", 1);
+    Ok::<_, Error>(web::Json(graph_id))
+}
+
+/// Returns status of each batch in the graph.
+#[actix_web::get("/activity/{activity_id}/exec-graph/{graph_id}")]
+async fn get_graph_status(
+    db: web::Data<DbExecutor>,
+    graphs: web::Data<ExecGraphs>,
+    path: web::Path<PathActivityGraph>,
+    id: Identity,
+) -> impl Responder {
+    authorize_activity_initiator(&db, id.identity, &path.activity_id, Role::Requestor).await?;
+
+    graphs
+        .get(&path.graph_id)
+        .filter(|graph| graph.activity_id == path.activity_id)
+        .map(web::Json)
+        .ok_or_else(|| Error::NotFound(format!("Exec graph {}", path.graph_id)))
+}
+
+struct GraphRunner {
+    graphs: ExecGraphs,
+    activity_lock: Arc<tokio::sync::Mutex<()>>,
+    graph_id: String,
+    activity_id: String,
+    caller: NodeId,
+    provider_id: NodeId,
+    timeout: Option<f32>,
+}
+
+impl GraphRunner {
+    fn update<T>(&self, f: impl FnOnce(&mut GraphStatus) -> T) -> Option<T> {
+        self.graphs.update(&self.graph_id, f)
+    }
+
+    async fn run(self, mut scripts: HashMap<String, Vec<ExeScriptCommand>>) {
+        while let Some(node_id) = self.update(GraphStatus::advance).flatten() {
+            let script = scripts.remove(&node_id).unwrap_or_default();
+            let result = {
+                // Batches of other graphs of the same Activity can't run meanwhile.
+                let _guard = self.activity_lock.lock().await;
+                self.exec(&node_id, script).await
+            };
+            self.update(|graph| {
+                if let Some(node) = graph.node_mut(&node_id) {
+                    match result {
+                        Ok(()) => node.state = NodeState::Finished,
+                        Err(e) => {
+                            log::debug!(
+                                "Exec graph [{}] node [{}] failed: {}",
+                                self.graph_id,
+                                node_id,
+                                e
+                            );
+                            node.state = NodeState::Failed;
+                            node.error = Some(e.to_string());
+                        }
+                    }
+                }
+            });
+        }
+
+        self.update(|graph| graph.finished_date = Some(Utc::now()));
+        log::debug!("Exec graph [{}] finished", self.graph_id);
+    }
+
+    async fn exec(&self, node_id: &str, exe_script: Vec<ExeScriptCommand>) -> Result<()> {
+        let service = ya_net::from(self.caller)
+            .to(self.provider_id)
+            .service(&activity::exeunit::bus_id(&self.activity_id));
+
+        let batch_id = generate_id();
+        let msg = activity::Exec {
+            activity_id: self.activity_id.clone(),
+            batch_id: batch_id.clone(),
+            exe_script,
+            timeout: self.timeout,
+            interactive: vec![],
+            metrics_push_url: None,
+            run_options: Default::default(),
+        };
+        service
+            .send(msg)
+            .timeout(timeout_margin(self.timeout))
+            .await???;
+        self.update(|graph| {
+            if let Some(node) = graph.node_mut(node_id) {
+                node.batch_id = Some(batch_id.clone());
+            }
+        });
+
+        loop {
+            let msg = activity::GetExecBatchResults {
+                activity_id: self.activity_id.clone(),
+                batch_id: batch_id.clone(),
+                timeout: Some(POLL_TIMEOUT),
+                command_index: None,
+            };
+            let results = service
+                .send(msg)
+                .timeout(timeout_margin(Some(POLL_TIMEOUT)))
+                .await???;
+
+            if let Some(failed) = results
+                .iter()
+                .find(|result| result.result == CommandResult::Error)
+            {
+                return Err(Error::Service(format!(
+                    "Command {} failed: {}",
+                    failed.index,
+                    failed.message.as_deref().unwrap_or_default()
+                )));
+            }
+            if results.iter().any(|result| result.is_batch_finished) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, depends_on: &[&str]) -> ExeScriptNode {
+        ExeScriptNode {
+            id: id.to_string(),
+            text: "[]".to_string(),
+            depends_on: depends_on.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn status(nodes: Vec<ExeScriptNode>) -> GraphStatus {
+        GraphStatus {
+            graph_id: "graph".to_string(),
+            activity_id: "activity".to_string(),
+            finished_date: None,
+            nodes: nodes
+                .into_iter()
+                .map(|node| NodeStatus {
+                    id: node.id,
+                    depends_on: node.depends_on,
+                    state: NodeState::Pending,
+                    batch_id: None,
+                    error: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn validate_graph() {
+        assert!(validate(&[node("a", &[]), node("b", &["a"]), node("c", &["a", "b"])]).is_ok());
+        assert!(validate(&[node("a", &[]), node("a", &[])]).is_err());
+        assert!(validate(&[node("a", &["x"])]).is_err());
+        assert!(validate(&[node("a", &["b"]), node("b", &["a"]), node("c", &[])]).is_err());
+        assert!(validate(&[node("a", &["a"])]).is_err());
+    }
+
+    #[test]
+    fn advance_graph() {
+        let mut graph = status(vec![
+            node("a", &[]),
+            node("b", &[]),
+            node("c", &["a"]),
+            node("d", &["b"]),
+            node("e", &["d"]),
+        ]);
+        assert_eq!(graph.advance().as_deref(), Some("a"));
+        assert_eq!(graph.advance().as_deref(), Some("b"));
+        assert_eq!(graph.advance(), None);
+
+        graph.node_mut("a").unwrap().state = NodeState::Finished;
+        graph.node_mut("b").unwrap().state = NodeState::Failed;
+        assert_eq!(graph.advance().as_deref(), Some("c"));
+        assert_eq!(graph.advance(), None);
+        assert_eq!(graph.node_mut("d").unwrap().state, NodeState::Skipped);
+        assert_eq!(graph.node_mut("e").unwrap().state, NodeState::Skipped);
+    }
+}
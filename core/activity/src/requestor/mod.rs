@@ -1,3 +1,5 @@
 //! Provider side operations
 pub mod control;
+pub mod graph;
 pub mod state;
+pub mod terminal;
@@ -0,0 +1,288 @@
+//! Interactive terminal sessions within Requestor's activities, over WebSocket.
+//!
+//! Binary frames are written to the terminal as input and its output is sent back in binary
+//! frames. Text frames carry JSON control messages (`{"resize":{"cols":120,"rows":40}}`);
+//! other text is written as input. The terminal is closed together with the socket.
+//! Activities of service mode runtimes and ExeUnits on non-unix hosts refuse to open terminals.
+use actix::prelude::*;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use futures::{Future, StreamExt};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use ya_client_model::market::Role;
+use ya_client_model::NodeId;
+use ya_core_model::activity::{self, TerminalSize, STDIN_CHUNK_MAX_SIZE};
+use ya_net::RemoteEndpoint;
+use ya_persistence::executor::DbExecutor;
+use ya_service_api_web::middleware::Identity;
+use ya_service_bus::{timeout::IntoTimeoutFuture, RpcEndpoint};
+
+use crate::common::*;
+use crate::{error::Error, Result};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn extend_web_scope(scope: actix_web::Scope) -> actix_web::Scope {
+    scope.service(open_terminal)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryTerminal {
+    /// Command to run instead of the default shell.
+    pub entry_point: Option<String>,
+    /// Command arguments, split like in a shell.
+    pub args: Option<String>,
+    pub cols: Option<u16>,
+    pub rows: Option<u16>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Control {
+    Resize(TerminalSize),
+}
+
+/// Opens an interactive terminal within a given Activity.
+#[actix_web::get("/activity/{activity_id}/terminal")]
+async fn open_terminal(
+    db: web::Data<DbExecutor>,
+    path: web::Path<PathActivity>,
+    query: web::Query<QueryTerminal>,
+    id: Identity,
+    req: HttpRequest,
+    stream: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    let terminal = TerminalSession::open(&db, path.into_inner(), query.into_inner(), id).await?;
+    ws::start(TerminalWebSocket::new(terminal), &req, stream)
+}
+
+#[derive(Clone)]
+struct TerminalSession {
+    caller: NodeId,
+    provider: NodeId,
+    activity_id: String,
+    terminal_id: String,
+}
+
+impl TerminalSession {
+    async fn open(
+        db: &DbExecutor,
+        path: PathActivity,
+        query: QueryTerminal,
+        id: Identity,
+    ) -> Result<Self> {
+        authorize_activity_initiator(db, id.identity, &path.activity_id, Role::Requestor).await?;
+        let agreement = get_activity_agreement(db, &path.activity_id, Role::Requestor).await?;
+
+        let args = match query.args {
+            Some(args) => shlex::split(&args)
+                .ok_or_else(|| Error::BadRequest(format!("Invalid arguments: {}", args)))?,
+            None => Vec::new(),
+        };
+        let default = TerminalSize::default();
+        let msg = activity::OpenTerminal {
+            activity_id: path.activity_id.clone(),
+            entry_point: query.entry_point,
+            args,
+            size: TerminalSize {
+                cols: query.cols.unwrap_or(default.cols),
+                rows: query.rows.unwrap_or(default.rows),
+            },
+        };
+
+        let terminal_id = ya_net::from(id.identity)
+            .to(*agreement.provider_id())
+            .service(&activity::exeunit::bus_id(&path.activity_id))
+            .send(msg)
+            .timeout(timeout_margin(Some(DEFAULT_REQUEST_TIMEOUT)))
+            .await???;
+
+        log::info!(
+            "Terminal {} opened within activity {}",
+            terminal_id,
+            path.activity_id
+        );
+        Ok(TerminalSession {
+            caller: id.identity,
+            provider: *agreement.provider_id(),
+            activity_id: path.activity_id,
+            terminal_id,
+        })
+    }
+
+    async fn write(self, data: Vec<u8>) -> Result<()> {
+        for chunk in data.chunks(STDIN_CHUNK_MAX_SIZE) {
+            let msg = activity::WriteTerminal {
+                activity_id: self.activity_id.clone(),
+                terminal_id: self.terminal_id.clone(),
+                data: chunk.to_vec(),
+            };
+            ya_net::from(self.caller)
+                .to(self.provider)
+                .service(&activity::exeunit::bus_id(&self.activity_id))
+                .send(msg)
+                .await??;
+        }
+        Ok(())
+    }
+
+    async fn resize(self, size: TerminalSize) -> Result<()> {
+        let msg = activity::ResizeTerminal {
+            activity_id: self.activity_id.clone(),
+            terminal_id: self.terminal_id.clone(),
+            size,
+        };
+        ya_net::from(self.caller)
+            .to(self.provider)
+            .service(&activity::exeunit::bus_id(&self.activity_id))
+            .send(msg)
+            .await??;
+        Ok(())
+    }
+
+    async fn close(self) -> Result<()> {
+        let msg = activity::CloseTerminal {
+            activity_id: self.activity_id.clone(),
+            terminal_id: self.terminal_id.clone(),
+        };
+        ya_net::from(self.caller)
+            .to(self.provider)
+            .service(&activity::exeunit::bus_id(&self.activity_id))
+            .send(msg)
+            .await??;
+        Ok(())
+    }
+}
+
+struct TerminalWebSocket {
+    session: TerminalSession,
+    heartbeat: Instant,
+}
+
+impl TerminalWebSocket {
+    fn new(session: TerminalSession) -> Self {
+        TerminalWebSocket {
+            session,
+            heartbeat: Instant::now(),
+        }
+    }
+
+    /// Requests are awaited one by one, so the input isn't reordered.
+    fn forward<F>(&self, request: F, ctx: &mut <Self as Actor>::Context)
+    where
+        F: Future<Output = Result<()>> + 'static,
+    {
+        request
+            .into_actor(self)
+            .map(|result, this, ctx| {
+                if let Err(e) = result {
+                    log::warn!("Terminal {} error: {}", this.session.terminal_id, e);
+                    ctx.close(Some(ws::CloseReason {
+                        code: ws::CloseCode::Error,
+                        description: Some(e.to_string()),
+                    }));
+                    ctx.stop();
+                }
+            })
+            .wait(ctx);
+    }
+}
+
+impl Actor for TerminalWebSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if Instant::now().duration_since(act.heartbeat) > CLIENT_TIMEOUT {
+                log::warn!("Terminal {} connection timed out", act.session.terminal_id);
+                ctx.stop();
+            } else {
+                ctx.ping(b"");
+            }
+        });
+
+        let msg = activity::StreamTerminal {
+            activity_id: self.session.activity_id.clone(),
+            terminal_id: self.session.terminal_id.clone(),
+        };
+        let output = ya_net::from(self.session.caller)
+            .to(self.session.provider)
+            .service_transfer(&activity::exeunit::bus_id(&self.session.activity_id))
+            .call_streaming(msg)
+            .map(|item| match item {
+                Ok(result) => result.map_err(Error::from),
+                Err(e) => Err(Error::from(e)),
+            });
+        ctx.add_stream(output);
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        let session = self.session.clone();
+        actix::spawn(async move {
+            let terminal_id = session.terminal_id.clone();
+            match session.close().await {
+                Ok(_) => log::info!("Terminal {} closed", terminal_id),
+                Err(e) => log::debug!("Terminal {} already closed: {}", terminal_id, e),
+            }
+        });
+    }
+}
+
+impl StreamHandler<Result<Vec<u8>>> for TerminalWebSocket {
+    fn handle(&mut self, item: Result<Vec<u8>>, ctx: &mut Self::Context) {
+        match item {
+            Ok(data) => ctx.binary(data),
+            Err(e) => {
+                log::warn!("Terminal {} output error: {}", self.session.terminal_id, e);
+                ctx.close(Some(ws::CloseReason {
+                    code: ws::CloseCode::Error,
+                    description: Some(e.to_string()),
+                }));
+                ctx.stop();
+            }
+        }
+    }
+
+    /// Output ends, when processes attached to the terminal exit.
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.close(Some(ws::CloseCode::Normal.into()));
+        ctx.stop();
+    }
+}
+
+impl StreamHandler<std::result::Result<ws::Message, ws::ProtocolError>> for TerminalWebSocket {
+    fn handle(
+        &mut self,
+        msg: std::result::Result<ws::Message, ws::ProtocolError>,
+        ctx: &mut Self::Context,
+    ) {
+        self.heartbeat = Instant::now();
+        match msg {
+            Ok(ws::Message::Binary(bytes)) => {
+                self.forward(self.session.clone().write(bytes.to_vec()), ctx)
+            }
+            Ok(ws::Message::Text(text)) => match serde_json::from_str::<Control>(&text) {
+                Ok(Control::Resize(size)) => self.forward(self.session.clone().resize(size), ctx),
+                Err(_) => {
+                    let data = text.as_bytes().to_vec();
+                    self.forward(self.session.clone().write(data), ctx)
+                }
+            },
+            Ok(ws::Message::Ping(msg)) => {
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {}
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => {
+                ctx.stop();
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use ya_persistence::executor::DbExecutor;
 use ya_service_api_interfaces::{Provider, Service};
 
-use crate::{api, db::migrations, provider, TrackerRef};
+use crate::{api, db::migrations, provider, ExecGraphs, TrackerRef};
 
 pub struct Activity;
 
@@ -20,9 +20,11 @@ impl Activity {
         Ok(())
     }
 
-    pub fn rest<Context: Provider<Self, DbExecutor> + Provider<Self, TrackerRef>>(
+    pub fn rest<
+        Context: Provider<Self, DbExecutor> + Provider<Self, TrackerRef> + Provider<Self, ExecGraphs>,
+    >(
         ctx: &Context,
     ) -> actix_web::Scope {
-        api::web_scope(&ctx.component(), ctx.component())
+        api::web_scope(&ctx.component(), ctx.component(), ctx.component())
     }
 }
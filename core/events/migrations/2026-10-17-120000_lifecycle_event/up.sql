@@ -0,0 +1,6 @@
+-- AUTOINCREMENT keeps sequence numbers increasing, also after old events are removed.
+CREATE TABLE lifecycle_event (
+    sequence INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    event_date DATETIME NOT NULL,
+    data TEXT NOT NULL
+);
@@ -0,0 +1,147 @@
+use diesel::prelude::*;
+
+use ya_core_model::events::LifecycleEvent;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+use crate::db::schema::lifecycle_event::dsl;
+
+no_arg_sql_function!(last_insert_rowid, diesel::sql_types::BigInt);
+
+/// Number of latest events retained for replay.
+pub const RETAINED_EVENTS: i64 = 10_000;
+
+/// Retained events published after a known sequence.
+#[derive(Debug)]
+pub struct Replay {
+    /// Sequence of the oldest retained event, 0 if there are none.
+    pub oldest: u64,
+    /// Sequence of the latest event, 0 if there are none.
+    pub latest: u64,
+    pub events: Vec<LifecycleEvent>,
+}
+
+pub struct EventDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'a> AsDao<'a> for EventDao<'a> {
+    fn as_dao(pool: &'a PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> EventDao<'c> {
+    /// Stores event with the next sequence number and removes events, which
+    /// are no longer retained.
+    pub async fn insert(&self, mut event: LifecycleEvent) -> anyhow::Result<LifecycleEvent> {
+        do_with_transaction(self.pool, "lifecycle_event_insert", move |conn| {
+            diesel::insert_into(dsl::lifecycle_event)
+                .values((
+                    dsl::event_date.eq(event.event_date.naive_utc()),
+                    dsl::data.eq(serde_json::to_string(&event)?),
+                ))
+                .execute(conn)?;
+            let sequence: i64 = diesel::select(last_insert_rowid).first(conn)?;
+
+            diesel::delete(
+                dsl::lifecycle_event.filter(dsl::sequence.le(sequence - RETAINED_EVENTS)),
+            )
+            .execute(conn)?;
+
+            event.sequence = sequence as u64;
+            Ok(event)
+        })
+        .await
+    }
+
+    /// Sequence of the latest event, 0 if there are none.
+    pub async fn latest(&self) -> anyhow::Result<u64> {
+        readonly_transaction(self.pool, "lifecycle_event_latest", move |conn| {
+            let latest: Option<i64> = dsl::lifecycle_event
+                .select(diesel::dsl::max(dsl::sequence))
+                .first(conn)?;
+            Ok(latest.unwrap_or_default() as u64)
+        })
+        .await
+    }
+
+    /// Events with sequence greater than `since`, in order.
+    pub async fn replay(&self, since: u64) -> anyhow::Result<Replay> {
+        readonly_transaction(self.pool, "lifecycle_event_replay", move |conn| {
+            let oldest: Option<i64> = dsl::lifecycle_event
+                .select(diesel::dsl::min(dsl::sequence))
+                .first(conn)?;
+            let latest: Option<i64> = dsl::lifecycle_event
+                .select(diesel::dsl::max(dsl::sequence))
+                .first(conn)?;
+            let events = dsl::lifecycle_event
+                .select((dsl::sequence, dsl::data))
+                .filter(dsl::sequence.gt(since as i64))
+                .order(dsl::sequence.asc())
+                .load::<(i64, String)>(conn)?
+                .into_iter()
+                .map(|(sequence, data)| {
+                    let mut event = serde_json::from_str::<LifecycleEvent>(&data)?;
+                    event.sequence = sequence as u64;
+                    Ok(event)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            Ok(Replay {
+                oldest: oldest.unwrap_or_default() as u64,
+                latest: latest.unwrap_or_default() as u64,
+                events,
+            })
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ya_core_model::events::EventPayload;
+    use ya_persistence::executor::DbExecutor;
+
+    use crate::db::migrations;
+
+    fn event(activity_id: &str) -> LifecycleEvent {
+        LifecycleEvent::new(
+            None,
+            EventPayload::ActivityStateChanged {
+                activity_id: activity_id.to_string(),
+                state: "Ready".to_string(),
+            },
+        )
+    }
+
+    #[actix_rt::test]
+    async fn events_are_numbered_in_order() {
+        let db = DbExecutor::in_memory("events_are_numbered_in_order").unwrap();
+        db.apply_migration(migrations::run_with_output).unwrap();
+        let dao = db.as_dao::<EventDao>();
+
+        let replay = dao.replay(0).await.unwrap();
+        assert_eq!((replay.oldest, replay.latest), (0, 0));
+        assert!(replay.events.is_empty());
+        assert_eq!(dao.latest().await.unwrap(), 0);
+
+        for idx in 1..=3 {
+            let event = dao.insert(event(&idx.to_string())).await.unwrap();
+            assert_eq!(event.sequence, idx);
+        }
+
+        assert_eq!(dao.latest().await.unwrap(), 3);
+        let replay = dao.replay(1).await.unwrap();
+        assert_eq!((replay.oldest, replay.latest), (1, 3));
+        assert_eq!(
+            replay
+                .events
+                .iter()
+                .map(|event| event.sequence)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(replay.events[1].payload, event("3").payload);
+    }
+}
@@ -0,0 +1,7 @@
+table! {
+    lifecycle_event (sequence) {
+        sequence -> BigInt,
+        event_date -> Timestamp,
+        data -> Text,
+    }
+}
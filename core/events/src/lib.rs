@@ -0,0 +1,51 @@
+//! Local event bus.
+//!
+//! Market, Activity and Payment services publish their lifecycle events here and the
+//! service forwards them to all subscribed GSB endpoints, which filters match.
+//! Events are numbered and stored, so subscribers can replay the ones they missed.
+//! See `ya_core_model::events` for the API.
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
+
+use ya_client_model::NodeId;
+use ya_core_model::events::{EventPayload, LifecycleEvent, Publish, BUS_ID};
+use ya_persistence::executor::DbExecutor;
+use ya_service_api_interfaces::Provider;
+use ya_service_bus::typed as bus;
+
+mod db;
+mod service;
+pub mod webhook;
+
+pub struct EventsService;
+
+impl EventsService {
+    pub async fn gsb<Context: Provider<Self, DbExecutor>>(ctx: &Context) -> anyhow::Result<()> {
+        let db = ctx.component();
+        db.apply_migration(db::migrations::run_with_output)?;
+        service::bind_gsb(db);
+        Ok(())
+    }
+
+    /// Waits until events already published are delivered to subscribers.
+    /// Undelivered events can be replayed after restart.
+    pub async fn shut_down() {
+        service::drain().await
+    }
+}
+
+/// Publishes event without waiting for delivery.
+/// Events are dropped, when events service isn't running.
+pub fn publish(node_id: Option<NodeId>, payload: EventPayload) {
+    let event = LifecycleEvent::new(node_id, payload);
+    tokio::task::spawn_local(async move {
+        let event_type = event.payload.event_type();
+        match bus::service(BUS_ID).send(Publish(event)).await {
+            Ok(Ok(())) => log::trace!("{} event published", event_type),
+            Ok(Err(e)) => log::debug!("Failed to publish {} event: {}", event_type, e),
+            Err(e) => log::trace!("Failed to publish {} event: {}", event_type, e),
+        }
+    });
+}
@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+use ya_client_model::ErrorMessage;
+use ya_core_model::events::{EventFilter, LifecycleEvent, Publish, Subscribe, Unsubscribe, BUS_ID};
+use ya_persistence::executor::DbExecutor;
+use ya_service_bus::typed as bus;
+
+use crate::db::dao::{EventDao, Replay};
+
+/// How often `drain` checks for events still waiting for delivery.
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Events queued for subscribers, but not delivered yet.
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+type Sender = mpsc::UnboundedSender<LifecycleEvent>;
+
+struct Subscription {
+    /// Distinguishes subscriptions of the same endpoint.
+    id: u64,
+    filter: EventFilter,
+    sender: Sender,
+}
+
+#[derive(Clone)]
+struct State {
+    db: DbExecutor,
+    subscriptions: Rc<RefCell<HashMap<String, Subscription>>>,
+    /// Id of the last subscription. Held while events are stored and queued,
+    /// so subscribers receive them in order of their sequence.
+    ordering: Rc<Mutex<u64>>,
+}
+
+pub(crate) fn bind_gsb(db: DbExecutor) {
+    let state = State {
+        db,
+        subscriptions: Default::default(),
+        ordering: Default::default(),
+    };
+
+    let this = state.clone();
+    let _ = bus::bind(BUS_ID, move |msg: Subscribe| {
+        let this = this.clone();
+        async move { this.subscribe(msg).await }
+    });
+
+    let this = state.clone();
+    let _ = bus::bind(BUS_ID, move |msg: Unsubscribe| {
+        log::debug!("Endpoint {} unsubscribed from events", msg.endpoint);
+        this.subscriptions.borrow_mut().remove(&msg.endpoint);
+        async { Ok(()) }
+    });
+
+    let this = state;
+    let _ = bus::bind(BUS_ID, move |Publish(event)| {
+        let this = this.clone();
+        async move { this.publish(event).await }
+    });
+}
+
+impl State {
+    async fn subscribe(&self, msg: Subscribe) -> Result<u64, ErrorMessage> {
+        let mut last_id = self.ordering.lock().await;
+        let dao = self.db.as_dao::<EventDao>();
+        let (latest, replay) = match msg.since {
+            Some(since) => {
+                let replay = dao.replay(since).await.map_err(ErrorMessage::new)?;
+                check_replay(since, &replay)?;
+                (replay.latest, replay.events)
+            }
+            None => (dao.latest().await.map_err(ErrorMessage::new)?, vec![]),
+        };
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut replayed = 0;
+        for event in replay {
+            if msg.filter.matches(&event) && queue(&sender, event) {
+                replayed += 1;
+            }
+        }
+        log::debug!(
+            "Endpoint {} subscribed to events: {:?}, replaying {} of them",
+            msg.endpoint,
+            msg.filter,
+            replayed
+        );
+
+        *last_id += 1;
+        let subscription = Subscription {
+            id: *last_id,
+            filter: msg.filter,
+            sender,
+        };
+        self.subscriptions
+            .borrow_mut()
+            .insert(msg.endpoint.clone(), subscription);
+        tokio::task::spawn_local(forward(self.clone(), msg.endpoint, *last_id, receiver));
+        Ok(latest)
+    }
+
+    async fn publish(&self, event: LifecycleEvent) -> Result<(), ErrorMessage> {
+        let _ordered = self.ordering.lock().await;
+        let event_type = event.payload.event_type();
+        let event = self
+            .db
+            .as_dao::<EventDao>()
+            .insert(event)
+            .await
+            .map_err(|e| {
+                log::warn!("Failed to store {} event: {}", event_type, e);
+                ErrorMessage::new(e)
+            })?;
+
+        self.subscriptions.borrow_mut().retain(|_, subscription| {
+            !subscription.filter.matches(&event) || queue(&subscription.sender, event.clone())
+        });
+        Ok(())
+    }
+}
+
+/// Fails, if `since` is ahead of the latest event, or some of the events after it
+/// are no longer retained.
+fn check_replay(since: u64, replay: &Replay) -> Result<(), ErrorMessage> {
+    if since > replay.latest {
+        return Err(ErrorMessage::new(format!(
+            "Sequence {} is ahead of the latest event {}",
+            since, replay.latest
+        )));
+    }
+    if since + 1 < replay.oldest {
+        return Err(ErrorMessage::new(format!(
+            "Events since {} are no longer retained, the oldest is {}",
+            since, replay.oldest
+        )));
+    }
+    Ok(())
+}
+
+/// Returns false, if subscriber is gone.
+fn queue(sender: &Sender, event: LifecycleEvent) -> bool {
+    IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+    let queued = sender.send(event).is_ok();
+    if !queued {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+    queued
+}
+
+/// Delivers events one by one, so subscriber receives them in order.
+async fn forward(
+    state: State,
+    endpoint: String,
+    id: u64,
+    mut receiver: mpsc::UnboundedReceiver<LifecycleEvent>,
+) {
+    while let Some(event) = receiver.recv().await {
+        let result = bus::service(&endpoint).call(event).await;
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        match result {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => log::debug!("Subscriber {} failed to handle event: {}", endpoint, e),
+            // Endpoint is gone, so there is no point in sending further events.
+            Err(e) => {
+                log::debug!("Removing events subscriber {}: {}", endpoint, e);
+                {
+                    let mut subscriptions = state.subscriptions.borrow_mut();
+                    // Endpoint could have subscribed again in the meantime.
+                    if subscriptions.get(&endpoint).map(|s| s.id) == Some(id) {
+                        subscriptions.remove(&endpoint);
+                    }
+                }
+                // Events queued for this subscription will never be delivered.
+                receiver.close();
+                while receiver.try_recv().is_ok() {
+                    IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Waits until events already published are delivered to subscribers.
+pub(crate) async fn drain() {
+    loop {
+        let in_flight = IN_FLIGHT.load(Ordering::SeqCst);
+        if in_flight == 0 {
+            return;
+        }
+        log::debug!("Waiting for {} events to be delivered", in_flight);
+        tokio::time::sleep(DRAIN_CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(oldest: u64, latest: u64) -> Replay {
+        Replay {
+            oldest,
+            latest,
+            events: vec![],
+        }
+    }
+
+    #[test]
+    fn replay_since_sequence() {
+        check_replay(0, &replay(0, 0)).unwrap();
+        assert!(check_replay(1, &replay(0, 0)).is_err());
+
+        check_replay(4, &replay(5, 20)).unwrap();
+        check_replay(20, &replay(5, 20)).unwrap();
+        assert!(check_replay(3, &replay(5, 20)).is_err());
+        assert!(check_replay(21, &replay(5, 20)).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Delivery of events to webhooks, shared by services exposing them.
+//!
+//! Event is POSTed as JSON body with its type in `X-Yagna-Event` header. Body is signed
+//! with HMAC-SHA256, if secret is given. Failed deliveries are retried with exponential
+//! backoff, unless receiver rejected the event with client error.
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::future::Future;
+use std::time::Duration;
+
+pub const SIGNATURE_HEADER: &str = "X-Yagna-Signature";
+pub const EVENT_HEADER: &str = "X-Yagna-Event";
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub event_type: String,
+    pub body: Vec<u8>,
+    pub signature: Option<String>,
+}
+
+impl WebhookRequest {
+    pub fn new(url: String, event_type: String, body: Vec<u8>, secret: Option<&str>) -> Self {
+        let signature = secret.map(|secret| sign(secret, &body));
+        WebhookRequest {
+            url,
+            event_type,
+            body,
+            signature,
+        }
+    }
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum DeliveryError {
+    /// Receiver rejected the event, so there is no point in repeating it.
+    #[error("Webhook rejected event: {0}")]
+    Rejected(String),
+    #[error("Webhook delivery failed: {0}")]
+    Failed(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub retry_delay: Duration,
+}
+
+/// Single delivery attempt.
+pub async fn post(request: &WebhookRequest) -> Result<(), DeliveryError> {
+    let mut client_request = awc::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .finish()
+        .post(request.url.as_str())
+        .content_type("application/json")
+        .insert_header((EVENT_HEADER, request.event_type.as_str()));
+    if let Some(signature) = &request.signature {
+        client_request = client_request.insert_header((SIGNATURE_HEADER, signature.as_str()));
+    }
+
+    match client_request.send_body(request.body.clone()).await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) if response.status().is_client_error() => {
+            Err(DeliveryError::Rejected(response.status().to_string()))
+        }
+        Ok(response) => Err(DeliveryError::Failed(response.status().to_string())),
+        Err(e) => Err(DeliveryError::Failed(e.to_string())),
+    }
+}
+
+/// Delivers the request, retrying failed attempts according to `retry`.
+/// `on_attempt` is called after each attempt with its number (starting from 1), result
+/// and flag telling, if it was the last one. Returns true, if request was delivered.
+pub async fn deliver<F, Fut>(request: WebhookRequest, retry: RetryPolicy, mut on_attempt: F) -> bool
+where
+    F: FnMut(u32, Result<(), DeliveryError>, bool) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut delay = retry.retry_delay;
+    for attempt in 1..=retry.max_retries + 1 {
+        let result = post(&request).await;
+        let delivered = result.is_ok();
+        let last = delivered
+            || attempt > retry.max_retries
+            || matches!(result, Err(DeliveryError::Rejected(_)));
+
+        match &result {
+            Ok(()) => log::debug!(
+                "Webhook {} delivered to {}.",
+                request.event_type,
+                request.url
+            ),
+            Err(e) => log::debug!(
+                "Webhook {} delivery to {} failed (attempt {}): {}",
+                request.event_type,
+                request.url,
+                attempt,
+                e
+            ),
+        }
+        on_attempt(attempt, result, last).await;
+
+        if last {
+            return delivered;
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+    false
+}
+
+/// Value of `X-Yagna-Signature` header: `sha256=<hex encoded HMAC of body>`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_signature() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn gives_up_after_retries() {
+        let request = WebhookRequest::new(
+            "http://127.0.0.1:1/webhook".to_string(),
+            "Test".to_string(),
+            b"{}".to_vec(),
+            None,
+        );
+        let retry = RetryPolicy {
+            max_retries: 2,
+            retry_delay: Duration::from_millis(1),
+        };
+
+        let mut attempts = vec![];
+        let delivered = deliver(request, retry, |attempt, result, last| {
+            attempts.push((attempt, result.is_ok(), last));
+            async {}
+        })
+        .await;
+        assert!(!delivered);
+        assert_eq!(
+            attempts,
+            vec![(1, false, false), (2, false, false), (3, false, true)]
+        );
+    }
+}
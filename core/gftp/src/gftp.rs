@@ -19,6 +19,14 @@ use ya_core_model::NodeId;
 use ya_service_bus::{typed as bus, RpcEndpoint};
 
 pub const DEFAULT_CHUNK_SIZE: u64 = 40 * 1024;
+/// Size of independently verified blocks listed in file manifest.
+pub const DEFAULT_BLOCK_SIZE: u64 = 4 * 1024 * 1024;
+/// Number of blocks downloaded concurrently.
+pub const DEFAULT_BLOCK_CONCURRENCY: usize = 4;
+/// Number of chunk requests in flight within a single block.
+const CHUNK_CONCURRENCY: usize = 4;
+/// Number of attempts to download a block which fails hash verification.
+const BLOCK_ATTEMPTS: usize = 3;
 
 // =========================================== //
 // File download - publisher side ("requestor")
@@ -28,25 +36,31 @@ struct FileDesc {
     hash: String,
     file: Mutex<fs::File>,
     meta: model::GftpMetadata,
+    manifest: model::GftpManifest,
 }
 
 impl FileDesc {
-    fn new(file: fs::File, hash: String, meta: model::GftpMetadata) -> Arc<Self> {
+    fn new(file: fs::File, manifest: model::GftpManifest) -> Arc<Self> {
         let file = Mutex::new(file);
+        let hash = manifest.hash.clone();
+        let meta = model::GftpMetadata {
+            file_size: manifest.file_size,
+        };
 
-        Arc::new(FileDesc { hash, file, meta })
+        Arc::new(FileDesc {
+            hash,
+            file,
+            meta,
+            manifest,
+        })
     }
 
     pub fn open(path: &Path) -> Result<Arc<FileDesc>> {
         let mut file =
             fs::File::open(path).with_context(|| format!("Can't open file {}.", path.display()))?;
 
-        let hash = hash_file_sha256(&mut file)?;
-        let meta = model::GftpMetadata {
-            file_size: file.metadata()?.len(),
-        };
-
-        Ok(FileDesc::new(file, hash, meta))
+        let manifest = hash_file_blocks(&mut file, DEFAULT_BLOCK_SIZE)?;
+        Ok(FileDesc::new(file, manifest))
     }
 
     pub fn bind_handlers(self: &Arc<Self>) {
@@ -56,6 +70,11 @@ impl FileDesc {
             future::ok(desc.meta.clone())
         });
 
+        let desc = self.clone();
+        let _ = bus::bind(&gsb_address, move |_msg: model::GetManifest| {
+            future::ok(desc.manifest.clone())
+        });
+
         let desc = self.clone();
         let _ = bus::bind(&gsb_address, move |msg: model::GetChunk| {
             let desc = desc.clone();
@@ -132,9 +151,132 @@ pub async fn download_file(node_id: NodeId, hash: &str, dst_path: &Path) -> Resu
     let remote = node_id.service_transfer(&model::file_bus_id(hash));
     log::debug!("Creating target file {}", dst_path.display());
 
-    let mut file = create_dest_file(dst_path)?;
+    let file = create_dest_file(dst_path)?;
+
+    match fetch_manifest(&remote, hash).await? {
+        Some(manifest) => download_blocks(&remote, &manifest, file).await,
+        None => download_chunks(&remote, file).await,
+    }
+}
+
+/// Fetches the file manifest. Returns `None` if the publisher doesn't support manifests.
+pub async fn fetch_manifest(
+    remote: &bus::Endpoint,
+    hash: &str,
+) -> Result<Option<model::GftpManifest>> {
+    let manifest = match remote.send(model::GetManifest {}).await {
+        Ok(result) => result?,
+        Err(e) => {
+            log::debug!("Manifest not available, falling back to sequential download: {e}");
+            return Ok(None);
+        }
+    };
+
+    check_manifest(&manifest, hash)?;
+    Ok(Some(manifest))
+}
+
+/// Checks that the manifest describes the file with given hash and that its blocks
+/// cover the whole file.
+fn check_manifest(manifest: &model::GftpManifest, hash: &str) -> Result<()> {
+    if manifest.hash != hash {
+        return Err(anyhow!(model::Error::IntegrityError)
+            .context(format!("Manifest hash {} doesn't match url", manifest.hash)));
+    }
+    if manifest.block_size == 0
+        || manifest.num_blocks()
+            != (manifest.file_size + manifest.block_size - 1) / manifest.block_size
+    {
+        return Err(anyhow!(model::Error::IntegrityError).context("Malformed manifest"));
+    }
+    Ok(())
+}
+
+/// Downloads a single block of the file and verifies it against the manifest.
+/// Chunks of the block are requested concurrently.
+pub async fn download_block(
+    remote: &bus::Endpoint,
+    manifest: &model::GftpManifest,
+    index: u64,
+) -> Result<Vec<u8>> {
+    let (offset, size) = manifest.block_range(index);
+    let expected = &manifest.block_hashes[index as usize];
+    let chunk_size = DEFAULT_CHUNK_SIZE;
+    let num_chunks = (size + chunk_size - 1) / chunk_size;
+
+    for attempt in 1..=BLOCK_ATTEMPTS {
+        let chunks: Vec<model::GftpChunk> = futures::stream::iter(0..num_chunks)
+            .map(|chunk_number| {
+                let chunk_offset = chunk_number * chunk_size;
+                remote.call(model::GetChunk {
+                    offset: offset + chunk_offset,
+                    size: chunk_size.min(size - chunk_offset),
+                })
+            })
+            .buffered(CHUNK_CONCURRENCY)
+            .map_err(anyhow::Error::from)
+            .and_then(|result| future::ready(result.map_err(anyhow::Error::from)))
+            .try_collect()
+            .await?;
+
+        let block = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.content)
+            .collect::<Vec<_>>();
+        let hash = format!("{:x}", Sha3_256::digest(&block));
+        if hash == *expected {
+            return Ok(block);
+        }
+
+        log::warn!(
+            "Block {} hash mismatch (attempt {}/{}): {} != {}",
+            index,
+            attempt,
+            BLOCK_ATTEMPTS,
+            hash,
+            expected
+        );
+    }
+    Err(model::Error::IntegrityError.into())
+}
+
+async fn download_blocks(
+    remote: &bus::Endpoint,
+    manifest: &model::GftpManifest,
+    mut file: File,
+) -> Result<()> {
+    log::debug!(
+        "Manifest: file size {}, {} blocks of {} B.",
+        manifest.file_size,
+        manifest.num_blocks(),
+        manifest.block_size
+    );
+
+    file.set_len(manifest.file_size)?;
+
+    futures::stream::iter(0..manifest.num_blocks())
+        .map(|index| async move {
+            download_block(remote, manifest, index)
+                .await
+                .map(|block| (index, block))
+        })
+        .buffer_unordered(DEFAULT_BLOCK_CONCURRENCY)
+        .try_for_each(|(index, block)| {
+            future::ready((|| {
+                let (offset, _) = manifest.block_range(index);
+                file.seek(SeekFrom::Start(offset))?;
+                file.write_all(&block)?;
+                Ok(())
+            })())
+        })
+        .await?;
+
+    file.flush()?;
+    Ok(())
+}
 
-    log::debug!("Loading file {} metadata.", dst_path.display());
+async fn download_chunks(remote: &bus::Endpoint, mut file: File) -> Result<()> {
+    log::debug!("Loading file metadata.");
     let metadata = remote.send(model::GetMetadata {}).await??;
 
     log::debug!("Metadata: file size {}.", metadata.file_size);
@@ -316,6 +458,33 @@ fn get_chunks(
     }))
 }
 
+/// Computes hashes of the whole file and of its consecutive blocks in a single pass.
+fn hash_file_blocks(file: &mut fs::File, block_size: u64) -> Result<model::GftpManifest> {
+    let mut hasher = Sha3_256::new();
+    let mut block_hashes = Vec::new();
+    let mut buffer = vec![0u8; block_size as usize];
+
+    file.seek(SeekFrom::Start(0))
+        .with_context(|| "Can't seek file at offset 0.".to_string())?;
+
+    let file_size = file.metadata()?.len();
+    let mut remaining = file_size;
+    while remaining > 0 {
+        let block = &mut buffer[..block_size.min(remaining) as usize];
+        file.read_exact(block)?;
+        hasher.input(&*block);
+        block_hashes.push(format!("{:x}", Sha3_256::digest(&*block)));
+        remaining -= block.len() as u64;
+    }
+
+    Ok(model::GftpManifest {
+        file_size,
+        block_size,
+        block_hashes,
+        hash: format!("{:x}", hasher.result()),
+    })
+}
+
 fn hash_file_sha256(mut file: &mut fs::File) -> Result<String> {
     let mut hasher = Sha3_256::new();
 
@@ -376,3 +545,133 @@ fn create_dest_file(file_path: &Path) -> Result<File> {
         .open(file_path)
         .with_context(|| format!("Can't create destination file: [{}].", file_path.display()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const BLOCK_SIZE: u64 = 100 * 1024;
+
+    fn temp_file(dir: &tempdir::TempDir, content: &[u8]) -> fs::File {
+        let path = dir.path().join("file");
+        fs::write(&path, content).unwrap();
+        fs::File::open(path).unwrap()
+    }
+
+    fn content(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn is_integrity_error(error: &Error) -> bool {
+        matches!(
+            error.downcast_ref::<model::Error>(),
+            Some(model::Error::IntegrityError)
+        )
+    }
+
+    #[test]
+    fn test_block_range_last_partial_block() {
+        let dir = tempdir::TempDir::new("gftp").unwrap();
+        let mut file = temp_file(&dir, &content(10));
+
+        let manifest = hash_file_blocks(&mut file, 4).unwrap();
+        assert_eq!(manifest.file_size, 10);
+        assert_eq!(manifest.num_blocks(), 3);
+        assert_eq!(manifest.block_range(0), (0, 4));
+        assert_eq!(manifest.block_range(1), (4, 4));
+        assert_eq!(manifest.block_range(2), (8, 2));
+        assert_eq!(manifest.hash, hash_file_sha256(&mut file).unwrap());
+        check_manifest(&manifest, &manifest.hash).unwrap();
+
+        let mut file = temp_file(&dir, &content(8));
+        let manifest = hash_file_blocks(&mut file, 4).unwrap();
+        assert_eq!(manifest.num_blocks(), 2);
+        assert_eq!(manifest.block_range(1), (4, 4));
+    }
+
+    #[test]
+    fn test_empty_file_manifest() {
+        let dir = tempdir::TempDir::new("gftp").unwrap();
+        let mut file = temp_file(&dir, &[]);
+
+        let manifest = hash_file_blocks(&mut file, 4).unwrap();
+        assert_eq!(manifest.file_size, 0);
+        assert_eq!(manifest.num_blocks(), 0);
+        assert_eq!(manifest.hash, format!("{:x}", Sha3_256::digest(b"")));
+        check_manifest(&manifest, &manifest.hash).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_mismatch() {
+        let dir = tempdir::TempDir::new("gftp").unwrap();
+        let mut file = temp_file(&dir, &content(10));
+        let manifest = hash_file_blocks(&mut file, 4).unwrap();
+        let hash = manifest.hash.clone();
+
+        let mut bigger = manifest.clone();
+        bigger.file_size = 13;
+        assert!(is_integrity_error(
+            &check_manifest(&bigger, &hash).unwrap_err()
+        ));
+
+        let mut smaller = manifest.clone();
+        smaller.file_size = 8;
+        assert!(is_integrity_error(
+            &check_manifest(&smaller, &hash).unwrap_err()
+        ));
+
+        let mut no_blocks = manifest.clone();
+        no_blocks.block_size = 0;
+        assert!(is_integrity_error(
+            &check_manifest(&no_blocks, &hash).unwrap_err()
+        ));
+
+        assert!(is_integrity_error(
+            &check_manifest(&manifest, "other").unwrap_err()
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_download_blocks() {
+        let dir = tempdir::TempDir::new("gftp").unwrap();
+        let expected = content(2 * BLOCK_SIZE as usize + 1000);
+        let mut file = temp_file(&dir, &expected);
+        let manifest = hash_file_blocks(&mut file, BLOCK_SIZE).unwrap();
+        FileDesc::new(file, manifest.clone()).bind_handlers();
+
+        let remote = bus::service(model::file_bus_id(&manifest.hash));
+        let mut downloaded = Vec::new();
+        for index in 0..manifest.num_blocks() {
+            let block = download_block(&remote, &manifest, index).await.unwrap();
+            assert_eq!(block.len() as u64, manifest.block_range(index).1);
+            downloaded.extend(block);
+        }
+        assert_eq!(downloaded, expected);
+    }
+
+    #[actix_rt::test]
+    async fn test_download_block_hash_mismatch() {
+        let dir = tempdir::TempDir::new("gftp").unwrap();
+        let mut file = temp_file(&dir, &content(BLOCK_SIZE as usize + 10));
+        let manifest = hash_file_blocks(&mut file, BLOCK_SIZE).unwrap();
+
+        // Publisher serving corrupted content.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let address = model::file_bus_id(&manifest.hash);
+        let counter = calls.clone();
+        let _ = bus::bind(&address, move |msg: model::GetChunk| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            future::ok(model::GftpChunk {
+                offset: msg.offset,
+                content: vec![0u8; msg.size as usize],
+            })
+        });
+
+        let remote = bus::service(&address);
+        let error = download_block(&remote, &manifest, 1).await.unwrap_err();
+        assert!(is_integrity_error(&error));
+        // Single chunk of the last block requested on each attempt.
+        assert_eq!(calls.load(Ordering::SeqCst), BLOCK_ATTEMPTS);
+    }
+}
@@ -2,6 +2,7 @@ mod gftp;
 pub mod rpc;
 
 pub use self::gftp::{
-    close, download_file, download_from_url, extract_url, open_for_upload, publish, upload_file,
+    close, download_block, download_file, download_from_url, extract_url, fetch_manifest,
+    open_for_upload, publish, upload_file, DEFAULT_BLOCK_CONCURRENCY, DEFAULT_BLOCK_SIZE,
     DEFAULT_CHUNK_SIZE,
 };
@@ -180,6 +180,7 @@ mod tests {
             identity: NodeId::default(),
             name: "dummy_node".to_string(),
             role: "dummy".to_string(),
+            delegated_by: None,
         };
         DummyAuth::new(id)
     }
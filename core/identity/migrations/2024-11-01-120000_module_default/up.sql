@@ -0,0 +1,5 @@
+CREATE TABLE "module_default"(
+	"module_id" VARCHAR(50) NOT NULL PRIMARY KEY,
+	"identity_id" VARCHAR(255) NOT NULL,
+    FOREIGN KEY (identity_id) REFERENCES identity(identity_id)
+);
@@ -0,0 +1,8 @@
+-- Usage of app-keys in REST API requests. Keyed by app-key name, which is unique,
+-- so usage of preconfigured app-key, not stored in `app_key`, is tracked too.
+CREATE TABLE "app_key_usage"(
+	"name" VARCHAR(255) NOT NULL PRIMARY KEY,
+	"request_count" BIGINT NOT NULL DEFAULT 0,
+	"last_used_date" DATETIME NOT NULL,
+	"last_source_ip" VARCHAR(64)
+);
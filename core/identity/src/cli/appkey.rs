@@ -128,6 +128,9 @@ impl AppKeyCommand {
                         "id".into(),
                         "role".into(),
                         "created".into(),
+                        "requests".into(),
+                        "last used".into(),
+                        "source ip".into(),
                     ],
                     values: result
                         .0
@@ -136,6 +139,8 @@ impl AppKeyCommand {
                             serde_json::json! {[
                                 app_key.name, app_key.key, app_key.identity,
                                 app_key.role, app_key.created_date,
+                                app_key.request_count, app_key.last_used_date,
+                                app_key.last_source_ip,
                             ]}
                         })
                         .collect(),
@@ -4,6 +4,7 @@ use std::convert::TryInto;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
 use ethsign::{KeyFile, Protected};
 use rustc_hex::ToHex;
 use sha2::Digest;
@@ -161,6 +162,18 @@ pub enum IdentityCommand {
         set_default: bool,
     },
 
+    /// Shows or sets identities used by modules instead of the default one
+    ModuleDefault {
+        /// Module (market, payment or activity). Lists all overrides if not given
+        module: Option<identity::Module>,
+        /// Identity to be used by the module. Shows the current one if not given
+        #[structopt(conflicts_with = "reset")]
+        node_or_alias: Option<NodeOrAlias>,
+        /// Makes the module use the default identity again
+        #[structopt(long, requires = "module")]
+        reset: bool,
+    },
+
     /// Drop given identity
     Drop {
         /// Identity alias to drop
@@ -169,6 +182,44 @@ pub enum IdentityCommand {
         force: bool,
     },
 
+    /// Allows other identity to use selected APIs on behalf of given identity.
+    /// Prints token, which should be passed in `X-Yagna-Delegation` header.
+    Delegate {
+        /// Identity, which is delegate
+        delegate: NodeId,
+
+        /// Identity granting permission
+        #[structopt(long, default_value = "")]
+        owner: NodeOrAlias,
+
+        /// REST APIs, which delegate can use
+        #[structopt(long = "scope", default_value = "market")]
+        scopes: Vec<String>,
+
+        /// How long delegation is valid
+        #[structopt(long, parse(try_from_str = humantime::parse_duration), default_value = "7d")]
+        valid_for: std::time::Duration,
+    },
+
+    /// Shows or sets signed operator metadata, which market attaches to Offers
+    Metadata {
+        /// Identity described by metadata
+        #[structopt(long, default_value = "")]
+        node_or_alias: NodeOrAlias,
+
+        /// Operator contact email
+        #[structopt(long)]
+        contact_email: Option<String>,
+
+        /// Operator homepage
+        #[structopt(long)]
+        homepage: Option<String>,
+
+        /// URL of SLA terms
+        #[structopt(long)]
+        sla_url: Option<String>,
+    },
+
     /// Exports given identity to a file | stdout
     Export {
         /// Identity alias to export
@@ -437,6 +488,41 @@ impl IdentityCommand {
                         .map_err(anyhow::Error::msg)?,
                 )
             }
+            IdentityCommand::ModuleDefault { module: None, .. } => CommandOutput::object(
+                gsb.local()
+                    .send(identity::ListModuleDefaults::default())
+                    .await
+                    .map_err(anyhow::Error::msg)?,
+            ),
+            IdentityCommand::ModuleDefault {
+                module: Some(module),
+                node_or_alias: None,
+                reset: false,
+            } => CommandOutput::object(
+                gsb.local()
+                    .send(identity::Get::ByModule(*module))
+                    .await
+                    .map_err(anyhow::Error::msg)?,
+            ),
+            IdentityCommand::ModuleDefault {
+                module: Some(module),
+                node_or_alias,
+                ..
+            } => {
+                let node_id = match node_or_alias {
+                    Some(node_or_alias) => Some(node_or_alias.resolve().await?),
+                    None => None,
+                };
+                CommandOutput::object(
+                    gsb.local()
+                        .send(identity::SetModuleDefault {
+                            module: *module,
+                            node_id,
+                        })
+                        .await
+                        .map_err(anyhow::Error::msg)?,
+                )
+            }
             IdentityCommand::Drop {
                 node_or_alias,
                 force,
@@ -445,6 +531,76 @@ impl IdentityCommand {
 
                 drop_id::drop_id(&gsb, node_or_alias, *force).await
             }
+            IdentityCommand::Delegate {
+                delegate,
+                owner,
+                scopes,
+                valid_for,
+            } => {
+                let delegation = identity::Delegation {
+                    owner: owner.resolve().await?,
+                    delegate: *delegate,
+                    scopes: scopes.clone(),
+                    valid_to: Utc::now() + chrono::Duration::from_std(*valid_for)?,
+                };
+                CommandOutput::object(
+                    gsb.local()
+                        .send(identity::CreateDelegation(delegation))
+                        .await
+                        .map_err(anyhow::Error::msg)?
+                        .map(|token| serde_json::json! {{ "token": token }})?,
+                )
+            }
+            IdentityCommand::Metadata {
+                node_or_alias,
+                contact_email: None,
+                homepage: None,
+                sla_url: None,
+            } => {
+                let node_id = node_or_alias.resolve().await?;
+                let document = gsb
+                    .local()
+                    .send(identity::GetMetadata(node_id))
+                    .await
+                    .map_err(anyhow::Error::msg)??;
+                let metadata = match &document {
+                    Some(document) => Some(
+                        gsb.local()
+                            .send(identity::VerifyMetadata {
+                                document: document.clone(),
+                            })
+                            .await
+                            .map_err(anyhow::Error::msg)??,
+                    ),
+                    None => None,
+                };
+                CommandOutput::object(serde_json::json! {{
+                    "metadata": metadata,
+                    "document": document,
+                }})
+            }
+            IdentityCommand::Metadata {
+                node_or_alias,
+                contact_email,
+                homepage,
+                sla_url,
+            } => {
+                let metadata = identity::NodeMetadata {
+                    contact_email: contact_email.clone(),
+                    homepage: homepage.clone(),
+                    sla_url: sla_url.clone(),
+                };
+                CommandOutput::object(
+                    gsb.local()
+                        .send(identity::SetMetadata {
+                            node_id: node_or_alias.resolve().await?,
+                            metadata,
+                        })
+                        .await
+                        .map_err(anyhow::Error::msg)?
+                        .map(|document| serde_json::json! {{ "document": document }})?,
+                )
+            }
             IdentityCommand::Export {
                 node_or_alias,
                 file_path,
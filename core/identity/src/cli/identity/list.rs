@@ -8,6 +8,13 @@ pub async fn list(gsb: &GsbBindPoints) -> Result<CommandOutput> {
         .map_err(anyhow::Error::msg)
         .context("sending id List to BUS")?
         .unwrap();
+    let module_defaults: Vec<identity::ModuleDefault> = gsb
+        .local()
+        .send(identity::ListModuleDefaults::default())
+        .await
+        .map_err(anyhow::Error::msg)
+        .context("sending id ListModuleDefaults to BUS")?
+        .map_err(anyhow::Error::msg)?;
     identities.sort_by_key(|id| Reverse((id.is_default, id.alias.clone())));
     Ok(ResponseTable {
         columns: vec![
@@ -16,16 +23,24 @@ pub async fn list(gsb: &GsbBindPoints) -> Result<CommandOutput> {
             "delete in progress".into(),
             "alias".into(),
             "address".into(),
+            "default for".into(),
         ],
         values: identities
             .into_iter()
             .map(|identity| {
+                let modules = module_defaults
+                    .iter()
+                    .filter(|default| default.node_id == identity.node_id)
+                    .map(|default| default.module.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
                 serde_json::json! {[
                     if identity.is_default { "X" } else { "" },
                     if identity.is_locked { "X" } else { "" },
                     if identity.deleted { "X" } else { "" },
                     identity.alias,
-                    identity.node_id
+                    identity.node_id,
+                    modules
                 ]}
             })
             .collect(),
@@ -1,11 +1,13 @@
 pub use crate::dao::Error as DaoError;
-pub use crate::db::models::{AppKey, Role};
+pub use crate::db::models::{AppKey, AppKeyUsage, Role};
 use chrono::Utc;
 use diesel::prelude::*;
 
 use diesel::{ExpressionMethods, RunQueryDsl};
 use std::cmp::max;
+use std::collections::HashMap;
 use ya_client_model::NodeId;
+use ya_core_model::appkey as model;
 use ya_persistence::executor::{
     do_with_transaction, readonly_transaction, AsDao, ConnType, PoolType,
 };
@@ -157,18 +159,73 @@ impl<'c> AppKeyDao<'c> {
 
     pub async fn remove(&self, name: String, identity: Option<String>) -> Result<()> {
         use crate::db::schema::app_key as app_key_dsl;
+        use crate::db::schema::app_key_usage as usage_dsl;
 
         self.with_transaction("app_key_dao_remove", move |conn| {
             let filter = app_key_dsl::table.filter(app_key_dsl::name.eq(name.as_str()));
-            if let Some(id) = identity {
+            let removed = if let Some(id) = identity {
                 diesel::delete(filter.filter(app_key_dsl::identity_id.eq(id.as_str())))
                     .execute(conn)
             } else {
                 diesel::delete(filter).execute(conn)
             }?;
 
+            if removed > 0 {
+                diesel::delete(usage_dsl::table.filter(usage_dsl::name.eq(name.as_str())))
+                    .execute(conn)?;
+            }
             Ok(())
         })
         .await
     }
+
+    /// Adds usage reported since the previous report to the stored one.
+    pub async fn record_usage(&self, usage: Vec<model::AppKeyUsage>) -> Result<()> {
+        use crate::db::schema::app_key_usage as usage_dsl;
+
+        self.with_transaction("app_key_dao_record_usage", move |conn| {
+            for entry in usage {
+                let stored: Option<AppKeyUsage> = usage_dsl::table
+                    .filter(usage_dsl::name.eq(&entry.name))
+                    .first(conn)
+                    .optional()?;
+
+                let usage = match stored {
+                    Some(stored) => AppKeyUsage {
+                        request_count: stored.request_count + entry.request_count as i64,
+                        last_used_date: max(stored.last_used_date, entry.last_used_date),
+                        last_source_ip: entry.last_source_ip.or(stored.last_source_ip),
+                        ..stored
+                    },
+                    None => AppKeyUsage {
+                        name: entry.name,
+                        request_count: entry.request_count as i64,
+                        last_used_date: entry.last_used_date,
+                        last_source_ip: entry.last_source_ip,
+                    },
+                };
+                diesel::replace_into(usage_dsl::table)
+                    .values(usage)
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Usage of app-keys with given names. Keys never used are missing.
+    pub async fn usage(&self, names: Vec<String>) -> Result<HashMap<String, AppKeyUsage>> {
+        use crate::db::schema::app_key_usage as usage_dsl;
+
+        readonly_transaction(self.pool, "app_key_dao_usage", move |conn| {
+            let usage: Vec<AppKeyUsage> = usage_dsl::table
+                .filter(usage_dsl::name.eq_any(names))
+                .load(conn)?;
+            Ok(usage
+                .into_iter()
+                .map(|usage| (usage.name.clone(), usage))
+                .collect())
+        })
+        .await
+    }
 }
@@ -1,6 +1,7 @@
 use diesel::prelude::*;
 
 use crate::dao::Error;
+use ya_client_model::NodeId;
 use ya_persistence::executor::{
     do_with_transaction, readonly_transaction, AsDao, ConnType, PoolType,
 };
@@ -97,6 +98,11 @@ impl<'c> IdentityDao<'c> {
                 app_key_dsl::table.filter(app_key_dsl::identity_id.eq(identity_id.as_str())),
             )
             .execute(conn)?;
+            diesel::delete(
+                s::module_default::table
+                    .filter(s::module_default::identity_id.eq(identity_id.as_str())),
+            )
+            .execute(conn)?;
             Ok(())
         })
         .await?;
@@ -113,6 +119,79 @@ impl<'c> IdentityDao<'c> {
         .await
     }
 
+    /// Returns `(module_id, identity_id)` pairs.
+    pub async fn list_module_defaults(&self) -> Result<Vec<(String, NodeId)>> {
+        readonly_transaction(self.pool, "identity_dao_list_module_defaults", |conn| {
+            Ok(s::module_default::table
+                .select((s::module_default::module_id, s::module_default::identity_id))
+                .load::<(String, NodeId)>(conn)?)
+        })
+        .await
+    }
+
+    /// Removes default identity of the module, when `identity_id` is `None`.
+    pub async fn set_module_default(
+        &self,
+        module_id: String,
+        identity_id: Option<NodeId>,
+    ) -> Result<()> {
+        self.with_transaction("identity_dao_set_module_default", move |conn| {
+            match identity_id {
+                Some(identity_id) => {
+                    diesel::replace_into(s::module_default::table)
+                        .values((
+                            s::module_default::module_id.eq(module_id),
+                            s::module_default::identity_id.eq(identity_id),
+                        ))
+                        .execute(conn)?;
+                }
+                None => {
+                    diesel::delete(
+                        s::module_default::table.filter(s::module_default::module_id.eq(module_id)),
+                    )
+                    .execute(conn)?;
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Per identity data stored by `module_id`.
+    pub async fn get_data(&self, identity_id: NodeId, module_id: &str) -> Result<Option<String>> {
+        let module_id = module_id.to_string();
+        readonly_transaction(self.pool, "identity_dao_get_data", move |conn| {
+            Ok(s::identity_data::table
+                .filter(s::identity_data::identity_id.eq(identity_id.to_string()))
+                .filter(s::identity_data::module_id.eq(module_id))
+                .select(s::identity_data::configuration)
+                .first::<Option<String>>(conn)
+                .optional()?
+                .flatten())
+        })
+        .await
+    }
+
+    pub async fn set_data(
+        &self,
+        identity_id: NodeId,
+        module_id: &str,
+        configuration: String,
+    ) -> Result<()> {
+        let module_id = module_id.to_string();
+        self.with_transaction("identity_dao_set_data", move |conn| {
+            diesel::replace_into(s::identity_data::table)
+                .values((
+                    s::identity_data::identity_id.eq(identity_id.to_string()),
+                    s::identity_data::module_id.eq(module_id),
+                    s::identity_data::configuration.eq(configuration),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
     pub async fn init_preconfigured(&self, preconfigured_identity: Identity) -> Result<Identity> {
         use crate::db::schema::identity::dsl as id_dsl;
         self.with_transaction("identity_dao_init_preconfigured", move |conn| {
@@ -1,7 +1,7 @@
 #![allow(unused)]
 #![allow(clippy::all)]
 
-use crate::db::schema::{app_key, identity, role};
+use crate::db::schema::{app_key, app_key_usage, identity, role};
 use chrono::NaiveDateTime;
 use diesel::{Associations, Identifiable, Insertable, Queryable};
 use ya_client_model::NodeId;
@@ -32,6 +32,16 @@ pub struct AppKey {
     pub allow_origins: Option<String>,
 }
 
+#[derive(Queryable, Debug, Identifiable, Insertable, Clone)]
+#[table_name = "app_key_usage"]
+#[primary_key(name)]
+pub struct AppKeyUsage {
+    pub name: String,
+    pub request_count: i64,
+    pub last_used_date: NaiveDateTime,
+    pub last_source_ip: Option<String>,
+}
+
 #[derive(Queryable, Debug, Identifiable)]
 #[table_name = "role"]
 pub struct Role {
@@ -51,6 +61,17 @@ impl AppKey {
                 .allow_origins
                 .map(|allowed| serde_json::from_str(&allowed).unwrap_or(vec![]))
                 .unwrap_or(vec![]),
+            request_count: 0,
+            last_used_date: None,
+            last_source_ip: None,
         }
     }
 }
+
+impl AppKeyUsage {
+    pub fn apply_to(self, app_key: &mut ya_core_model::appkey::AppKey) {
+        app_key.request_count = self.request_count as u64;
+        app_key.last_used_date = Some(self.last_used_date);
+        app_key.last_source_ip = self.last_source_ip;
+    }
+}
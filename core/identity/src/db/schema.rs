@@ -12,6 +12,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    app_key_usage (name) {
+        name -> Text,
+        request_count -> BigInt,
+        last_used_date -> Timestamp,
+        last_source_ip -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     identity (identity_id) {
         identity_id -> Text,
@@ -33,6 +42,13 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    module_default (module_id) {
+        module_id -> Text,
+        identity_id -> Text,
+    }
+}
+
 diesel::table! {
     role (id) {
         id -> Integer,
@@ -54,11 +70,14 @@ diesel::table! {
 diesel::joinable!(app_key -> identity (identity_id));
 diesel::joinable!(app_key -> role (role_id));
 diesel::joinable!(identity_data -> identity (identity_id));
+diesel::joinable!(module_default -> identity (identity_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     app_key,
+    app_key_usage,
     identity,
     identity_data,
+    module_default,
     role,
     version_release,
 );
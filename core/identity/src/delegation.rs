@@ -0,0 +1,110 @@
+/// Delegation tokens.
+///
+/// Owner identity signs `Delegation`, which allows delegate identity to use selected
+/// REST APIs on its behalf. Token is hex encoded JSON containing the delegation and
+/// owner signature, so it can be verified without access to the owner key.
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::convert::TryInto;
+
+use ya_core_model::identity as model;
+use ya_core_model::identity::Delegation;
+
+#[derive(Serialize, Deserialize)]
+struct DelegationToken {
+    delegation: Delegation,
+    /// Hex encoded `v`, `r`, `s` signature of owner.
+    signature: String,
+}
+
+fn invalid(e: impl std::fmt::Display) -> model::Error {
+    model::Error::InvalidDelegation(e.to_string())
+}
+
+/// Hash of the delegation signed by the owner.
+pub(crate) fn signature_payload(delegation: &Delegation) -> Result<Vec<u8>, model::Error> {
+    let bytes = serde_json::to_vec(delegation).map_err(invalid)?;
+    Ok(sha2::Sha256::digest(&bytes).to_vec())
+}
+
+pub(crate) fn encode(delegation: Delegation, signature: &[u8]) -> Result<String, model::Error> {
+    let token = DelegationToken {
+        delegation,
+        signature: hex::encode(signature),
+    };
+    Ok(hex::encode(serde_json::to_vec(&token).map_err(invalid)?))
+}
+
+pub(crate) fn verify(token: &str) -> Result<Delegation, model::Error> {
+    let bytes = hex::decode(token.trim()).map_err(invalid)?;
+    let token: DelegationToken = serde_json::from_slice(&bytes).map_err(invalid)?;
+    let signature = hex::decode(&token.signature).map_err(invalid)?;
+
+    if signature.len() != 65 {
+        return Err(invalid(format!(
+            "invalid signature length: {}",
+            signature.len()
+        )));
+    }
+    let signature = ethsign::Signature {
+        v: signature[0],
+        r: signature[1..33].try_into().map_err(invalid)?,
+        s: signature[33..65].try_into().map_err(invalid)?,
+    };
+    let signer = signature
+        .recover(&signature_payload(&token.delegation)?)
+        .map_err(invalid)?;
+
+    let delegation = token.delegation;
+    if signer.address() != &delegation.owner.into_array() {
+        return Err(invalid("not signed by the owner"));
+    }
+    if delegation.is_expired() {
+        return Err(invalid(format!("expired at {}", delegation.valid_to)));
+    }
+    Ok(delegation)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use ethsign::SecretKey;
+    use ya_client_model::NodeId;
+
+    fn signed(secret: &SecretKey, delegation: Delegation) -> String {
+        let signature = secret
+            .sign(&signature_payload(&delegation).unwrap())
+            .unwrap();
+        let mut bytes = vec![signature.v];
+        bytes.extend_from_slice(&signature.r);
+        bytes.extend_from_slice(&signature.s);
+        encode(delegation, &bytes).unwrap()
+    }
+
+    #[test]
+    fn test_verify_delegation() {
+        let secret = SecretKey::from_raw(&[0xab; 32]).unwrap();
+        let delegation = Delegation {
+            owner: NodeId::from(secret.public().address().as_ref()),
+            delegate: NodeId::from([0xca; 20].as_ref()),
+            scopes: vec!["market".to_string()],
+            valid_to: Utc::now() + Duration::hours(1),
+        };
+
+        let token = signed(&secret, delegation.clone());
+        assert_eq!(verify(&token).unwrap(), delegation);
+
+        let foreign = Delegation {
+            owner: delegation.delegate,
+            ..delegation.clone()
+        };
+        assert!(verify(&signed(&secret, foreign)).is_err());
+
+        let expired = Delegation {
+            valid_to: Utc::now() - Duration::hours(1),
+            ..delegation
+        };
+        assert!(verify(&signed(&secret, expired)).is_err());
+    }
+}
@@ -9,4 +9,6 @@ pub mod service;
 mod autoconf;
 pub mod dao;
 mod db;
+mod delegation;
 mod id_key;
+mod metadata;
@@ -0,0 +1,106 @@
+/// Signed node metadata.
+///
+/// Identity signs `SignedMetadata` describing its operator, which market attaches to
+/// Offers. Document is hex encoded JSON with the signature, same as delegation token,
+/// so Requestors can verify it without access to the Provider.
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::convert::TryInto;
+
+use ya_core_model::identity as model;
+use ya_core_model::identity::SignedMetadata;
+
+/// Module id, under which the document is kept in `identity_data`.
+pub(crate) const MODULE_ID: &str = "node-metadata";
+
+#[derive(Serialize, Deserialize)]
+struct MetadataDocument {
+    metadata: SignedMetadata,
+    /// Hex encoded `v`, `r`, `s` signature of the node.
+    signature: String,
+}
+
+fn invalid(e: impl std::fmt::Display) -> model::Error {
+    model::Error::InvalidMetadata(e.to_string())
+}
+
+/// Hash of the metadata signed by the node.
+pub(crate) fn signature_payload(metadata: &SignedMetadata) -> Result<Vec<u8>, model::Error> {
+    let bytes = serde_json::to_vec(metadata).map_err(invalid)?;
+    Ok(sha2::Sha256::digest(&bytes).to_vec())
+}
+
+pub(crate) fn encode(metadata: SignedMetadata, signature: &[u8]) -> Result<String, model::Error> {
+    let document = MetadataDocument {
+        metadata,
+        signature: hex::encode(signature),
+    };
+    Ok(hex::encode(serde_json::to_vec(&document).map_err(invalid)?))
+}
+
+pub(crate) fn verify(document: &str) -> Result<SignedMetadata, model::Error> {
+    let bytes = hex::decode(document.trim()).map_err(invalid)?;
+    let document: MetadataDocument = serde_json::from_slice(&bytes).map_err(invalid)?;
+    let signature = hex::decode(&document.signature).map_err(invalid)?;
+
+    if signature.len() != 65 {
+        return Err(invalid(format!(
+            "invalid signature length: {}",
+            signature.len()
+        )));
+    }
+    let signature = ethsign::Signature {
+        v: signature[0],
+        r: signature[1..33].try_into().map_err(invalid)?,
+        s: signature[33..65].try_into().map_err(invalid)?,
+    };
+    let signer = signature
+        .recover(&signature_payload(&document.metadata)?)
+        .map_err(invalid)?;
+
+    if signer.address() != &document.metadata.node_id.into_array() {
+        return Err(invalid("not signed by the node"));
+    }
+    Ok(document.metadata)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+    use ethsign::SecretKey;
+    use ya_client_model::NodeId;
+    use ya_core_model::identity::NodeMetadata;
+
+    fn signed(secret: &SecretKey, metadata: SignedMetadata) -> String {
+        let signature = secret.sign(&signature_payload(&metadata).unwrap()).unwrap();
+        let mut bytes = vec![signature.v];
+        bytes.extend_from_slice(&signature.r);
+        bytes.extend_from_slice(&signature.s);
+        encode(metadata, &bytes).unwrap()
+    }
+
+    #[test]
+    fn test_verify_metadata() {
+        let secret = SecretKey::from_raw(&[0xab; 32]).unwrap();
+        let metadata = SignedMetadata {
+            node_id: NodeId::from(secret.public().address().as_ref()),
+            metadata: NodeMetadata {
+                contact_email: Some("ops@example.com".to_string()),
+                homepage: Some("https://example.com".to_string()),
+                sla_url: None,
+            },
+            signed_at: Utc::now(),
+        };
+
+        let document = signed(&secret, metadata.clone());
+        assert_eq!(verify(&document).unwrap(), metadata);
+
+        let foreign = SignedMetadata {
+            node_id: NodeId::from([0xca; 20].as_ref()),
+            ..metadata
+        };
+        assert!(verify(&signed(&secret, foreign)).is_err());
+        assert!(verify("not a document").is_err());
+    }
+}
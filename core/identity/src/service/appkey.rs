@@ -71,9 +71,23 @@ pub async fn preconfigured_to_appkey_model(
         identity: node_id,
         created_date,
         allow_origins: vec![],
+        request_count: 0,
+        last_used_date: None,
+        last_source_ip: None,
     })
 }
 
+async fn with_usage(dao: &AppKeyDao<'_>, keys: &mut [model::AppKey]) -> Result<(), model::Error> {
+    let names = keys.iter().map(|key| key.name.clone()).collect();
+    let mut usage = dao.usage(names).await.map_err(Into::<model::Error>::into)?;
+    for key in keys {
+        if let Some(usage) = usage.remove(&key.name) {
+            usage.apply_to(key);
+        }
+    }
+    Ok(())
+}
+
 pub async fn activate(db: &DbExecutor, gsb: Arc<GsbBindPoints>) -> anyhow::Result<()> {
     let (tx, rx) = futures::channel::mpsc::unbounded();
 
@@ -193,22 +207,27 @@ pub async fn activate(db: &DbExecutor, gsb: Arc<GsbBindPoints>) -> anyhow::Resul
             async move {
                 if let Some(preconfigured_appkey) = preconfigured_appkey {
                     if model::AUTOCONFIGURED_KEY_NAME == get.name {
-                        return preconfigured_to_appkey_model(
+                        let mut appkey = preconfigured_to_appkey_model(
                             preconfigured_node_id,
                             preconfigured_appkey,
                             start_datetime,
                         )
-                        .await;
+                        .await?;
+                        let dao = db.as_dao::<AppKeyDao>();
+                        with_usage(&dao, std::slice::from_mut(&mut appkey)).await?;
+                        return Ok(appkey);
                     }
                 }
 
-                let (appkey, role) = db
-                    .as_dao::<AppKeyDao>()
+                let dao = db.as_dao::<AppKeyDao>();
+                let (appkey, role) = dao
                     .get_for_name(get.name)
                     .await
                     .map_err(|e| model::Error::internal(e.to_string()))?;
 
-                Ok(appkey.to_core_model(role))
+                let mut appkey = appkey.to_core_model(role);
+                with_usage(&dao, std::slice::from_mut(&mut appkey)).await?;
+                Ok(appkey)
             }
         });
     }
@@ -242,12 +261,26 @@ pub async fn activate(db: &DbExecutor, gsb: Arc<GsbBindPoints>) -> anyhow::Resul
                         .await?,
                     );
                 }
+                with_usage(&db.as_dao::<AppKeyDao>(), &mut keys).await?;
 
                 Ok((keys.clone(), result.1))
             }
         });
     }
 
+    {
+        let db = db.clone();
+        let _ = bus::bind(gsb.local_addr(), move |record: model::RecordUsage| {
+            let db = db.clone();
+            async move {
+                db.as_dao::<AppKeyDao>()
+                    .record_usage(record.usage)
+                    .await
+                    .map_err(Into::<model::Error>::into)
+            }
+        });
+    }
+
     {
         let create_tx = tx;
         let db = db.clone();
@@ -20,7 +20,9 @@ use ya_persistence::executor::DbExecutor;
 
 use crate::dao::identity::Identity;
 use crate::dao::{Error as DaoError, IdentityDao};
+use crate::delegation;
 use crate::id_key::{default_password, generate_identity_key, IdentityKey};
+use crate::metadata;
 
 #[derive(Default)]
 struct Subscription {
@@ -41,6 +43,8 @@ pub struct IdentityService {
     default_key: NodeId,
     ids: HashMap<NodeId, IdentityKey>,
     alias_to_id: HashMap<String, NodeId>,
+    /// Overrides of the default identity for particular modules.
+    module_defaults: HashMap<model::Module, NodeId>,
     sender: futures::channel::mpsc::UnboundedSender<IdentityEvent>,
     subscription: Rc<RefCell<Subscription>>,
     db: DbExecutor,
@@ -139,6 +143,17 @@ impl IdentityService {
             let _ = ids.insert(key.id(), key);
         }
 
+        let mut module_defaults: HashMap<model::Module, _> = Default::default();
+        for (module_id, node_id) in db.as_dao::<IdentityDao>().list_module_defaults().await? {
+            match module_id.parse() {
+                Ok(module) => {
+                    log::info!("using identity {} for module {}", node_id, module);
+                    let _ = module_defaults.insert(module, node_id);
+                }
+                Err(_) => log::warn!("unknown module {} with default identity", module_id),
+            }
+        }
+
         Ok(IdentityService {
             default_key,
             db,
@@ -146,6 +161,7 @@ impl IdentityService {
             sender,
             subscription,
             alias_to_id,
+            module_defaults,
         })
     }
 
@@ -181,6 +197,56 @@ impl IdentityService {
         Ok(Some(to_info(&self.default_key, id)))
     }
 
+    pub fn get_module_default_id(
+        &self,
+        module: model::Module,
+    ) -> Result<Option<model::IdentityInfo>, model::Error> {
+        match self
+            .module_defaults
+            .get(&module)
+            .and_then(|node_id| self.ids.get(node_id))
+            .filter(|id| !id.is_deleted())
+        {
+            Some(id) => Ok(Some(to_info(&self.default_key, id))),
+            None => self.get_default_id(),
+        }
+    }
+
+    pub async fn set_module_default(
+        &mut self,
+        set: model::SetModuleDefault,
+    ) -> Result<Option<model::IdentityInfo>, model::Error> {
+        if let Some(node_id) = &set.node_id {
+            match self.ids.get(node_id) {
+                Some(id) if !id.is_deleted() => (),
+                _ => return Err(model::Error::NodeNotFound(Box::new(*node_id))),
+            }
+        }
+
+        self.db
+            .as_dao::<IdentityDao>()
+            .set_module_default(set.module.to_string(), set.node_id)
+            .await
+            .map_err(model::Error::new_err_msg)?;
+
+        match set.node_id {
+            Some(node_id) => self.module_defaults.insert(set.module, node_id),
+            None => self.module_defaults.remove(&set.module),
+        };
+        self.get_module_default_id(set.module)
+    }
+
+    pub fn list_module_defaults(&self) -> Result<Vec<model::ModuleDefault>, model::Error> {
+        Ok(self
+            .module_defaults
+            .iter()
+            .map(|(module, node_id)| model::ModuleDefault {
+                module: *module,
+                node_id: *node_id,
+            })
+            .collect())
+    }
+
     pub fn list_ids(&self) -> Result<Vec<model::IdentityInfo>, model::Error> {
         Ok(self
             .ids
@@ -334,6 +400,49 @@ impl IdentityService {
         }
     }
 
+    pub async fn create_delegation(
+        &mut self,
+        delegation: model::Delegation,
+    ) -> Result<String, model::Error> {
+        if delegation.is_expired() {
+            return Err(model::Error::InvalidDelegation(
+                "validity already ended".to_string(),
+            ));
+        }
+        let payload = delegation::signature_payload(&delegation)?;
+        let signature = self.sign(delegation.owner, payload).await?;
+        delegation::encode(delegation, &signature)
+    }
+
+    pub async fn set_metadata(&mut self, set: model::SetMetadata) -> Result<String, model::Error> {
+        let metadata = model::SignedMetadata {
+            node_id: set.node_id,
+            metadata: set.metadata,
+            signed_at: Utc::now(),
+        };
+        let payload = metadata::signature_payload(&metadata)?;
+        let signature = self.sign(set.node_id, payload).await?;
+        let document = metadata::encode(metadata, &signature)?;
+
+        self.db
+            .as_dao::<IdentityDao>()
+            .set_data(set.node_id, metadata::MODULE_ID, document.clone())
+            .await
+            .map_err(model::Error::new_err_msg)?;
+        Ok(document)
+    }
+
+    pub async fn get_metadata(&self, node_id: NodeId) -> Result<Option<String>, model::Error> {
+        if !self.ids.contains_key(&node_id) {
+            return Err(model::Error::NodeNotFound(Box::new(node_id)));
+        }
+        self.db
+            .as_dao::<IdentityDao>()
+            .get_data(node_id, metadata::MODULE_ID)
+            .await
+            .map_err(model::Error::new_err_msg)
+    }
+
     pub async fn update_identity(
         &mut self,
         update: model::Update,
@@ -431,6 +540,8 @@ impl IdentityService {
                 {
                     Ok(_) => {
                         id.mark_deleted();
+                        self.module_defaults
+                            .retain(|_, node_id| *node_id != drop_id.node_id);
                         let removed = to_info(&self.default_key, id);
 
                         if !was_locked {
@@ -480,6 +591,7 @@ impl IdentityService {
                     model::Get::ByAlias(alias) => this.lock().await.get_by_alias(&alias),
                     model::Get::ByNodeId(node_id) => this.lock().await.get_by_id(&node_id),
                     model::Get::ByDefault => this.lock().await.get_default_id(),
+                    model::Get::ByModule(module) => this.lock().await.get_module_default_id(module),
                     _ => Err(model::Error::InternalErr("unsupported query".to_string())),
                 }
             }
@@ -518,6 +630,16 @@ impl IdentityService {
             async move { this.lock().await.update_identity(update).await }
         });
         let this = me.clone();
+        let _ = bus::bind(gsb.local_addr(), move |set: model::SetModuleDefault| {
+            let this = this.clone();
+            async move { this.lock().await.set_module_default(set).await }
+        });
+        let this = me.clone();
+        let _ = bus::bind(gsb.local_addr(), move |_list: model::ListModuleDefaults| {
+            let this = this.clone();
+            async move { this.lock().await.list_module_defaults() }
+        });
+        let this = me.clone();
         let _ = bus::bind(gsb.local_addr(), move |lock: model::Lock| {
             let this = this.clone();
             async move {
@@ -566,6 +688,27 @@ impl IdentityService {
             async move { this.lock().await.sign(sign.node_id, sign.payload).await }
         });
         let this = me.clone();
+        let _ = bus::bind(gsb.local_addr(), move |create: model::CreateDelegation| {
+            let this = this.clone();
+            async move { this.lock().await.create_delegation(create.0).await }
+        });
+        let _ = bus::bind(gsb.local_addr(), move |verify: model::VerifyDelegation| {
+            future::ready(delegation::verify(&verify.token))
+        });
+        let this = me.clone();
+        let _ = bus::bind(gsb.local_addr(), move |set: model::SetMetadata| {
+            let this = this.clone();
+            async move { this.lock().await.set_metadata(set).await }
+        });
+        let this = me.clone();
+        let _ = bus::bind(gsb.local_addr(), move |get: model::GetMetadata| {
+            let this = this.clone();
+            async move { this.lock().await.get_metadata(get.0).await }
+        });
+        let _ = bus::bind(gsb.local_addr(), move |verify: model::VerifyMetadata| {
+            future::ready(metadata::verify(&verify.document))
+        });
+        let this = me.clone();
         let _ = bus::bind(gsb.local_addr(), move |subscribe: model::Subscribe| {
             let this = this.clone();
             async move { this.lock().await.subscribe(subscribe).await }
@@ -0,0 +1,44 @@
+use test_context::test_context;
+
+use ya_core_model::identity::Module;
+use ya_framework_basic::async_drop::DroppableTestContext;
+use ya_framework_basic::log::enable_logs;
+use ya_framework_basic::temp_dir;
+use ya_framework_mocks::net::MockNet;
+use ya_framework_mocks::node::MockNode;
+
+#[cfg_attr(not(feature = "framework-test"), ignore)]
+#[test_context(DroppableTestContext)]
+#[serial_test::serial]
+async fn test_module_default(_ctx: &mut DroppableTestContext) -> anyhow::Result<()> {
+    enable_logs(false);
+
+    let dir = temp_dir!("test_module_default")?;
+    let dir = dir.path();
+
+    let net = MockNet::new().bind();
+
+    let node1 = MockNode::new(net.clone(), "node-1", dir)
+        .with_prefixed_gsb()
+        .with_identity();
+    node1.bind_gsb().await?;
+
+    let identity = node1.get_identity()?;
+    let default = identity.module_default(Module::Market).await?;
+    let payment = identity.create_identity_key("payment").await?.identity;
+
+    identity
+        .set_module_default(Module::Payment, Some(payment))
+        .await?;
+    assert_eq!(identity.module_default(Module::Payment).await?, payment);
+    assert_eq!(identity.module_default(Module::Market).await?, default);
+
+    // Module defaults are persisted.
+    node1.stop().await;
+    node1.bind_gsb().await?;
+    assert_eq!(identity.module_default(Module::Payment).await?, payment);
+
+    identity.set_module_default(Module::Payment, None).await?;
+    assert_eq!(identity.module_default(Module::Payment).await?, default);
+    Ok(())
+}
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS market_agreement_label_key_value_idx;
+DROP TABLE market_agreement_label;
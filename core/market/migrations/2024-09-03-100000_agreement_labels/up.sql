@@ -0,0 +1,10 @@
+CREATE TABLE market_agreement_label(
+    agreement_id VARCHAR(100) NOT NULL,
+    key VARCHAR(63) NOT NULL,
+    value VARCHAR(255) NOT NULL,
+
+    PRIMARY KEY(agreement_id, key),
+    FOREIGN KEY(agreement_id) REFERENCES market_agreement (id)
+);
+
+CREATE INDEX IF NOT EXISTS market_agreement_label_key_value_idx ON market_agreement_label (key, value);
@@ -0,0 +1,8 @@
+-- Node, from which foreign Offer was retrieved (last hop of its propagation).
+CREATE TABLE market_offer_origin(
+    id VARCHAR(97) NOT NULL PRIMARY KEY,
+    received_from VARCHAR(20) NOT NULL,
+
+    received_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW')),
+    expiration_ts DATETIME NOT NULL
+);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS market_agreement_session_idx;
+DROP INDEX IF EXISTS market_session_subscription_session_idx;
+DROP TABLE market_session_subscription;
+DROP TABLE market_app_session;
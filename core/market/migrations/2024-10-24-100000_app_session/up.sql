@@ -0,0 +1,21 @@
+-- Sessions of applications using market, with subscriptions created in their scope.
+CREATE TABLE market_app_session(
+    id VARCHAR(100) NOT NULL,
+    node_id VARCHAR(20) NOT NULL,
+
+    created_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW')),
+    last_active_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW')),
+
+    PRIMARY KEY(node_id, id)
+);
+
+CREATE TABLE market_session_subscription(
+    subscription_id VARCHAR(97) NOT NULL PRIMARY KEY,
+    node_id VARCHAR(20) NOT NULL,
+    session_id VARCHAR(100) NOT NULL,
+
+    FOREIGN KEY(node_id, session_id) REFERENCES market_app_session (node_id, id)
+);
+
+CREATE INDEX IF NOT EXISTS market_session_subscription_session_idx ON market_session_subscription (node_id, session_id);
+CREATE INDEX IF NOT EXISTS market_agreement_session_idx ON market_agreement (session_id);
@@ -0,0 +1,7 @@
+-- Sources of propagated Offers, which are dropped before being stored.
+CREATE TABLE market_offer_filter(
+    kind VARCHAR(16) NOT NULL,
+    value VARCHAR(100) NOT NULL,
+
+    PRIMARY KEY(kind, value)
+);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS market_webhook_delivery_webhook_idx;
+DROP INDEX IF EXISTS market_webhook_subscription_idx;
+DROP TABLE market_webhook_delivery;
+DROP TABLE market_webhook;
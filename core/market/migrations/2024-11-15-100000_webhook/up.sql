@@ -0,0 +1,29 @@
+-- Callbacks registered for negotiation and Agreement events of subscriptions.
+CREATE TABLE market_webhook(
+    id VARCHAR(36) NOT NULL PRIMARY KEY,
+    subscription_id VARCHAR(97) NOT NULL,
+    owner_id VARCHAR(20) NOT NULL,
+    url TEXT NOT NULL,
+    -- Comma separated event types. Empty means all events.
+    event_types TEXT NOT NULL,
+
+    created_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW'))
+);
+
+CREATE TABLE market_webhook_delivery(
+    id VARCHAR(36) NOT NULL PRIMARY KEY,
+    webhook_id VARCHAR(36) NOT NULL,
+    event_type VARCHAR(32) NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    attempts INTEGER NOT NULL,
+    last_error TEXT,
+
+    created_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW')),
+    delivered_ts DATETIME,
+
+    FOREIGN KEY(webhook_id) REFERENCES market_webhook (id)
+);
+
+CREATE INDEX IF NOT EXISTS market_webhook_subscription_idx ON market_webhook (subscription_id);
+CREATE INDEX IF NOT EXISTS market_webhook_delivery_webhook_idx ON market_webhook_delivery (webhook_id);
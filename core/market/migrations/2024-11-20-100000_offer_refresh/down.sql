@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE market_offer DROP COLUMN subscribed_expiration_ts;
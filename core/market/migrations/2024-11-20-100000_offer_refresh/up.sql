@@ -0,0 +1,3 @@
+-- Expiration set at Offer subscription, which is covered by subscription id hash.
+-- Filled only after Offer was refreshed, when `expiration_ts` is extended.
+ALTER TABLE market_offer ADD COLUMN subscribed_expiration_ts DATETIME;
@@ -0,0 +1,3 @@
+-- Private Offers aren't propagated to other nodes. Requestors can only
+-- negotiate them directly, knowing their subscription id.
+ALTER TABLE market_offer ADD COLUMN private BOOLEAN NOT NULL DEFAULT 0;
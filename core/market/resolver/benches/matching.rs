@@ -0,0 +1,101 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use ya_market_resolver::{
+    match_compiled, match_demand_offer, CompiledSubscription, Demand, Match, Offer, OfferIndex,
+};
+
+const DEMAND_PROPERTIES: &str = r#"{
+    "golem.node.debug.subnet": "public",
+    "golem.srv.comp.expiration": 1700000000000
+}"#;
+const DEMAND_CONSTRAINTS: &str =
+    "(&(golem.inf.mem.gib>=4)(golem.inf.cpu.threads>=8)(golem.runtime.name=vm)(golem.node.debug.subnet=public))";
+const OFFER_CONSTRAINTS: &str = "(golem.srv.comp.expiration>0)";
+
+fn offer_properties(i: usize) -> String {
+    format!(
+        r#"{{
+            "golem.inf.mem.gib": {},
+            "golem.inf.cpu.threads": {},
+            "golem.inf.storage.gib": {},
+            "golem.runtime.name": "{}",
+            "golem.runtime.version@v": "0.{}.0",
+            "golem.node.debug.subnet": "{}",
+            "golem.node.id.name": "provider-{}",
+            "golem.com.scheme": "payu",
+            "golem.com.pricing.model": "linear"
+        }}"#,
+        i % 16,
+        i % 32 + 1,
+        i % 100,
+        if i % 3 == 0 { "vm" } else { "wasmtime" },
+        i % 4,
+        if i % 2 == 0 { "public" } else { "private" },
+        i
+    )
+}
+
+fn matching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("match_demand");
+    group.sample_size(10);
+
+    for size in [1_000, 10_000, 30_000] {
+        let offers = (0..size).map(offer_properties).collect::<Vec<_>>();
+
+        group.bench_with_input(BenchmarkId::new("resolver", size), &offers, |b, offers| {
+            b.iter(|| {
+                offers
+                    .iter()
+                    .filter(|properties| {
+                        match_demand_offer(
+                            DEMAND_PROPERTIES,
+                            DEMAND_CONSTRAINTS,
+                            properties,
+                            OFFER_CONSTRAINTS,
+                        )
+                        .unwrap()
+                            == Match::Yes
+                    })
+                    .count()
+            })
+        });
+
+        let compiled = offers
+            .iter()
+            .map(|properties| CompiledSubscription::compile(properties, OFFER_CONSTRAINTS).unwrap())
+            .collect::<Vec<_>>();
+        let compiled_demand =
+            CompiledSubscription::compile(DEMAND_PROPERTIES, DEMAND_CONSTRAINTS).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("compiled", size),
+            &compiled,
+            |b, offers| {
+                b.iter(|| {
+                    offers
+                        .iter()
+                        .filter(|offer| {
+                            match_compiled(&compiled_demand, offer).unwrap() == Match::Yes
+                        })
+                        .count()
+                })
+            },
+        );
+
+        let mut index = OfferIndex::new();
+        for properties in &offers {
+            index
+                .insert(Offer::from(properties, OFFER_CONSTRAINTS).unwrap())
+                .unwrap();
+        }
+        let demand = Demand::from(DEMAND_PROPERTIES, DEMAND_CONSTRAINTS).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("index", size), &index, |b, index| {
+            b.iter(|| black_box(index.match_demand(&demand).unwrap()).len())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, matching);
+criterion_main!(benches);
@@ -8,7 +8,11 @@ use resolver::error::MatchError as InternalMatchErorr;
 
 use crate::resolver::properties::PropertyRef;
 use flatten::{flatten_properties, FlattenError};
+pub use resolver::compiled::CompiledSubscription;
 use resolver::error::PrepareError;
+use resolver::explain::explain_weak;
+pub use resolver::explain::{ClauseOutcome, ClauseTrace, MatchExplanation};
+pub use resolver::index::OfferIndex;
 pub use resolver::matching::{match_weak, MatchResult};
 pub use resolver::prepare::{PreparedDemand, PreparedOffer};
 
@@ -46,7 +50,25 @@ pub fn match_demand_offer(
     let offer = Offer::from(offer_properties, offer_constraints)?;
     let prep_offer_result = PreparedOffer::from(&offer)?;
 
-    match match_weak(&prep_demand_result, &prep_offer_result)? {
+    let result = match_weak(&prep_demand_result, &prep_offer_result)?;
+    to_match(result)
+}
+
+/// Matches subscriptions compiled earlier. Equivalent of `match_demand_offer`,
+/// which parses only property values again.
+pub fn match_compiled(
+    demand: &CompiledSubscription,
+    offer: &CompiledSubscription,
+) -> Result<Match, MatchError> {
+    let prep_demand_result = demand.as_demand();
+    let prep_offer_result = offer.as_offer();
+
+    let result = match_weak(&prep_demand_result, &prep_offer_result)?;
+    to_match(result)
+}
+
+fn to_match(result: MatchResult) -> Result<Match, MatchError> {
+    match result {
         MatchResult::True => Ok(Match::Yes),
         MatchResult::False(from_offer, from_demand) => Ok(Match::No {
             offer_mismatch: extract_names(&from_offer),
@@ -60,6 +82,22 @@ pub fn match_demand_offer(
     }
 }
 
+/// Explain mode of `match_demand_offer`. Returns trace of constraint clauses of both
+/// sides with their outcomes, instead of bare result.
+pub fn explain_demand_offer(
+    demand_properties: &str,
+    demand_constraints: &str,
+    offer_properties: &str,
+    offer_constraints: &str,
+) -> Result<MatchExplanation, MatchError> {
+    let demand = Demand::from(demand_properties, demand_constraints)?;
+    let prep_demand_result = PreparedDemand::from(&demand)?;
+    let offer = Offer::from(offer_properties, offer_constraints)?;
+    let prep_offer_result = PreparedOffer::from(&offer)?;
+
+    Ok(explain_weak(&prep_demand_result, &prep_offer_result)?)
+}
+
 fn extract_names(props_vec: &[&PropertyRef]) -> Vec<String> {
     props_vec
         .iter()
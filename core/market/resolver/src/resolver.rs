@@ -1,12 +1,19 @@
+pub mod compiled;
+pub mod diff;
 pub mod error;
+pub mod explain;
 pub mod expression;
+pub mod index;
 pub mod ldap_parser;
+pub mod lint;
 pub mod matching;
 pub mod prepare;
 pub mod prop_parser;
 pub mod properties;
 
+pub use self::compiled::CompiledSubscription;
 pub use self::expression::Expression;
+pub use self::index::OfferIndex;
 pub use self::matching::match_weak;
 pub use self::prepare::{PreparedDemand, PreparedOffer};
 pub use self::properties::PropertySet;
@@ -0,0 +1,57 @@
+use super::super::flatten::flatten_properties;
+use super::super::MatchError;
+use super::error::PrepareError;
+use super::expression::{build_expression, Expression};
+use super::ldap_parser;
+use super::prepare::{PreparedDemand, PreparedOffer};
+use super::properties::PropertySet;
+
+// CompiledSubscription
+// Offer or Demand with flattened properties and parsed, folded constraints expression.
+// Unlike Prepared structures it owns its data, so it can be cached and matched repeatedly
+// against many counterparties without parsing JSON and LDAP filter again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledSubscription {
+    // Properties (expressed in flat form, ie. as lines of text)
+    properties: Vec<String>,
+
+    // Folded filter expression
+    constraints: Expression,
+}
+
+impl CompiledSubscription {
+    pub fn compile(properties: &str, constraints: &str) -> Result<Self, MatchError> {
+        let cons_tags = ldap_parser::parse(constraints)
+            .map_err(|error| PrepareError::new(&format!("Error parsing constraints: {}", error)))?;
+        let constraints = build_expression(&cons_tags).map_err(|error| {
+            PrepareError::new(&format!("Error building constraints expression: {}", error))
+        })?;
+
+        Ok(CompiledSubscription {
+            properties: flatten_properties(properties)?,
+            constraints: constraints.fold(),
+        })
+    }
+
+    pub fn properties(&self) -> &[String] {
+        &self.properties
+    }
+
+    pub fn constraints(&self) -> &Expression {
+        &self.constraints
+    }
+
+    pub fn as_offer(&self) -> PreparedOffer {
+        PreparedOffer {
+            properties: PropertySet::from_flat_props(&self.properties),
+            constraints: self.constraints.clone(),
+        }
+    }
+
+    pub fn as_demand(&self) -> PreparedDemand {
+        PreparedDemand {
+            properties: PropertySet::from_flat_props(&self.properties),
+            constraints: self.constraints.clone(),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Differences between consecutive Proposals of a negotiation.
+//!
+//! Properties are compared in flat form (dot separated names). Constraints are compared
+//! by top level clauses of their conjunction, so reformatting of the filter or reordering
+//! of its clauses isn't reported as a change.
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use ya_agreement_utils::agreement::flatten;
+
+use crate::resolver::expression::{build_expression, Expression};
+use crate::resolver::ldap_parser;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertiesDiff {
+    pub added: BTreeMap<String, Value>,
+    pub removed: Vec<String>,
+    /// New values of changed properties.
+    pub changed: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConstraintsDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalDiff {
+    pub properties: PropertiesDiff,
+    pub constraints: ConstraintsDiff,
+}
+
+impl ProposalDiff {
+    pub fn is_empty(&self) -> bool {
+        self.properties.added.is_empty()
+            && self.properties.removed.is_empty()
+            && self.properties.changed.is_empty()
+            && self.constraints.added.is_empty()
+            && self.constraints.removed.is_empty()
+    }
+}
+
+/// Computes changes of `properties` and `constraints` relative to the previous Proposal.
+pub fn diff_proposals(
+    prev_properties: &Value,
+    prev_constraints: &str,
+    properties: &Value,
+    constraints: &str,
+) -> ProposalDiff {
+    ProposalDiff {
+        properties: diff_properties(prev_properties, properties),
+        constraints: diff_constraints(prev_constraints, constraints),
+    }
+}
+
+pub fn diff_properties(prev: &Value, next: &Value) -> PropertiesDiff {
+    let prev = flatten_object(prev);
+    let next = flatten_object(next);
+
+    let mut diff = PropertiesDiff::default();
+    for (name, value) in next.iter() {
+        match prev.get(name) {
+            None => {
+                diff.added.insert(name.clone(), value.clone());
+            }
+            Some(prev_value) if prev_value != value => {
+                diff.changed.insert(name.clone(), value.clone());
+            }
+            _ => (),
+        }
+    }
+    diff.removed = prev
+        .keys()
+        .filter(|name| !next.contains_key(*name))
+        .cloned()
+        .collect();
+    diff.removed.sort();
+    diff
+}
+
+pub fn diff_constraints(prev: &str, next: &str) -> ConstraintsDiff {
+    let prev = constraint_clauses(prev);
+    let next = constraint_clauses(next);
+    ConstraintsDiff {
+        added: next
+            .iter()
+            .filter(|clause| !prev.contains(clause))
+            .cloned()
+            .collect(),
+        removed: prev
+            .iter()
+            .filter(|clause| !next.contains(clause))
+            .cloned()
+            .collect(),
+    }
+}
+
+fn flatten_object(value: &Value) -> Map<String, Value> {
+    match value {
+        Value::Object(_) => flatten(value.clone()),
+        Value::Null => Map::new(),
+        value => {
+            let mut map = Map::new();
+            map.insert(String::new(), value.clone());
+            map
+        }
+    }
+}
+
+/// Top level clauses of the constraints in normalized notation. Constraints, which can't
+/// be parsed, are treated as a single clause.
+fn constraint_clauses(constraints: &str) -> Vec<String> {
+    if constraints.trim().is_empty() {
+        return Vec::new();
+    }
+
+    let expression = match ldap_parser::parse(constraints)
+        .ok()
+        .and_then(|tag| build_expression(&tag).ok())
+    {
+        Some(expression) => expression,
+        None => return vec![constraints.trim().to_string()],
+    };
+    let mut clauses = match expression {
+        Expression::And(operands) => operands.iter().map(|e| e.to_string()).collect(),
+        Expression::Empty(_) => Vec::new(),
+        expression => vec![expression.to_string()],
+    };
+    clauses.dedup();
+    clauses
+}
@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::fmt;
+
+use super::error::MatchError;
+use super::expression::{Expression, ResolveResult};
+use super::prepare::{PreparedDemand, PreparedOffer};
+use super::properties::{PropertyRef, PropertyRefType, PropertySet};
+
+// Outcome of a single constraint clause resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClauseOutcome {
+    Matched,
+    Failed,
+    Undefined,
+}
+
+// Trace of constraint expression resolution.
+// Unlike regular resolution, all operands of And/Or are resolved (no short-circuit),
+// so that every clause of the expression gets its outcome.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClauseTrace {
+    pub clause: String,
+    pub outcome: ClauseOutcome,
+    // Properties, which couldn't be resolved, when outcome is Undefined
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub undefined_properties: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub operands: Vec<ClauseTrace>,
+}
+
+// Explanation of matching relation between Demand and Offer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchExplanation {
+    pub outcome: ClauseOutcome,
+    // Demand constraints resolved with Offer properties
+    pub demand_constraints: ClauseTrace,
+    // Offer constraints resolved with Demand properties
+    pub offer_constraints: ClauseTrace,
+}
+
+// Explain weak match relation.
+// Overall outcome is consistent with match_weak: Undefined wins over Failed.
+pub fn explain_weak(
+    demand: &PreparedDemand,
+    offer: &PreparedOffer,
+) -> Result<MatchExplanation, MatchError> {
+    let demand_constraints = explain_expression(&demand.constraints, &offer.properties)
+        .map_err(|e| MatchError::new(&format!("Error resolving Demand constraints: {}", e)))?;
+    let offer_constraints = explain_expression(&offer.constraints, &demand.properties)
+        .map_err(|e| MatchError::new(&format!("Error resolving Offer constraints: {}", e)))?;
+
+    let outcomes = [demand_constraints.outcome, offer_constraints.outcome];
+    let outcome = if outcomes.contains(&ClauseOutcome::Undefined) {
+        ClauseOutcome::Undefined
+    } else if outcomes.contains(&ClauseOutcome::Failed) {
+        ClauseOutcome::Failed
+    } else {
+        ClauseOutcome::Matched
+    };
+
+    Ok(MatchExplanation {
+        outcome,
+        demand_constraints,
+        offer_constraints,
+    })
+}
+
+pub fn explain_expression(
+    expression: &Expression,
+    property_set: &PropertySet,
+) -> Result<ClauseTrace, String> {
+    let operands = match expression {
+        Expression::And(exprs) | Expression::Or(exprs) => exprs
+            .iter()
+            .map(|expr| explain_expression(expr, property_set))
+            .collect::<Result<Vec<_>, _>>()?,
+        Expression::Not(expr) => vec![explain_expression(expr, property_set)?],
+        _ => vec![],
+    };
+
+    let (outcome, undefined_properties) = match expression.resolve(property_set) {
+        ResolveResult::True => (ClauseOutcome::Matched, vec![]),
+        ResolveResult::False(_, _) => (ClauseOutcome::Failed, vec![]),
+        ResolveResult::Undefined(un_props, _) => (
+            ClauseOutcome::Undefined,
+            un_props.into_iter().map(describe_prop_ref).collect(),
+        ),
+        ResolveResult::Err(err) => return Err(err.msg),
+    };
+
+    Ok(ClauseTrace {
+        clause: expression.to_string(),
+        outcome,
+        undefined_properties,
+        operands,
+    })
+}
+
+fn describe_prop_ref(prop_ref: &PropertyRef) -> String {
+    match prop_ref {
+        PropertyRef::Value(name, _) => name.to_string(),
+        PropertyRef::Aspect(name, aspect, _) => format!("{}[{}]", name, aspect),
+    }
+}
+
+fn format_prop_ref(prop_ref: &PropertyRef) -> String {
+    let (name, impl_type) = match prop_ref {
+        PropertyRef::Value(name, impl_type) => (name.to_string(), impl_type),
+        PropertyRef::Aspect(name, aspect, impl_type) => {
+            (format!("{}[{}]", name, aspect), impl_type)
+        }
+    };
+    match impl_type {
+        PropertyRefType::Any => name,
+        PropertyRefType::Decimal => format!("{}$d", name),
+        PropertyRefType::Version => format!("{}$v", name),
+        PropertyRefType::DateTime => format!("{}$t", name),
+    }
+}
+
+// Formats expression back to LDAP filter notation
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Equals(prop, val) => write!(f, "({}={})", format_prop_ref(prop), val),
+            Expression::Greater(prop, val) => write!(f, "({}>{})", format_prop_ref(prop), val),
+            Expression::GreaterEqual(prop, val) => {
+                write!(f, "({}>={})", format_prop_ref(prop), val)
+            }
+            Expression::Less(prop, val) => write!(f, "({}<{})", format_prop_ref(prop), val),
+            Expression::LessEqual(prop, val) => write!(f, "({}<={})", format_prop_ref(prop), val),
+            Expression::Present(prop) => write!(f, "({}=*)", format_prop_ref(prop)),
+            Expression::And(exprs) | Expression::Or(exprs) => {
+                let operator = match self {
+                    Expression::And(_) => "&",
+                    _ => "|",
+                };
+                write!(f, "({}", operator)?;
+                for expr in exprs {
+                    write!(f, "{}", expr)?;
+                }
+                write!(f, ")")
+            }
+            Expression::Not(expr) => write!(f, "(!{})", expr),
+            Expression::Empty(_) => write!(f, "()"),
+        }
+    }
+}
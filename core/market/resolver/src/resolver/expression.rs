@@ -80,7 +80,42 @@ impl Expression {
         }
     }
 
-    // TODO: Implement ultimate reduction of AND and OR expressions where only one factor remains
+    // Fold constant sub-expressions and unwrap AND and OR expressions with a single factor.
+    // Folded expression resolves to the same logical value, but reports fewer unresolved properties
+    // and skips errors in operands, which are short-circuited by a constant.
+    pub fn fold(self) -> Expression {
+        match self {
+            Expression::Not(inner) => match inner.fold() {
+                Expression::Empty(val) => Expression::Empty(!val),
+                inner => Expression::Not(Box::new(inner)),
+            },
+            Expression::And(seq) => Expression::fold_multi(seq, true, Expression::And),
+            Expression::Or(seq) => Expression::fold_multi(seq, false, Expression::Or),
+            expr => expr,
+        }
+    }
+
+    // Neutral operand is dropped, absorbing operand (its negation) decides the whole expression.
+    fn fold_multi(
+        seq: Vec<Expression>,
+        neutral: bool,
+        build: fn(Vec<Expression>) -> Expression,
+    ) -> Expression {
+        let mut folded = vec![];
+        for expr in seq {
+            match expr.fold() {
+                Expression::Empty(val) if val == neutral => {}
+                Expression::Empty(_) => return Expression::Empty(!neutral),
+                expr => folded.push(expr),
+            }
+        }
+
+        match folded.len() {
+            0 => Expression::Empty(neutral),
+            1 => folded.pop().unwrap(),
+            _ => build(folded),
+        }
+    }
 
     // (DONE) Rework for adjusted property definition syntax (property types derived form literals)
     // (DONE) Implement strong resolution and expression 'reduce' (ie. undefined results are propagated rather than ignored)
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use semver::Version;
+
+use super::super::{Demand, MatchError, Offer};
+use super::expression::{Expression, ResolveResult};
+use super::prepare::{PreparedDemand, PreparedOffer};
+use super::properties::{Property, PropertyRef, PropertyRefType, PropertySet, PropertyValue};
+
+// OfferIndex
+// Offers with properties pre-parsed into typed columns (one column per property name).
+// Demand constraints are resolved against whole columns at once, so matching a Demand
+// doesn't parse properties of each cached Offer again. Only Offers, for which typed
+// resolution is not supported (lists, aspects, implied types) fall back to regular resolver.
+#[derive(Default)]
+pub struct OfferIndex {
+    offers: Vec<IndexedOffer>,
+    columns: HashMap<String, Vec<Cell>>,
+}
+
+struct IndexedOffer {
+    offer: Offer,
+    constraints: Expression,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Missing,
+    Implicit,
+    Str(String),
+    Number(f64),
+    Boolean(bool),
+    Version(Version),
+    DateTime(DateTime<Utc>),
+    // Value types not supported by typed resolution (List, Decimal).
+    Other,
+}
+
+// Result of typed resolution of a single Offer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tri {
+    True,
+    False,
+    Undefined,
+    // Requires regular resolution.
+    Unknown,
+}
+
+// Constraint value parsed once for all typed comparisons.
+struct Operand<'a> {
+    raw: &'a str,
+    number: Option<f64>,
+    boolean: Option<bool>,
+    version: Option<Version>,
+    datetime: Option<DateTime<Utc>>,
+    wildcard: Option<Option<Regex>>,
+}
+
+#[derive(Clone, Copy)]
+enum Cmp {
+    Equals,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl OfferIndex {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.offers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offers.is_empty()
+    }
+
+    pub fn offer(&self, row: usize) -> Option<&Offer> {
+        self.offers.get(row).map(|indexed| &indexed.offer)
+    }
+
+    // Adds Offer to the index and returns its row number.
+    pub fn insert(&mut self, offer: Offer) -> Result<usize, MatchError> {
+        let row = self.offers.len();
+        let constraints = PreparedOffer::from(&offer)?.constraints;
+
+        for (name, property) in PropertySet::from_flat_props(&offer.properties).properties {
+            let column = self.columns.entry(name.to_string()).or_default();
+            column.resize(row, Cell::Missing);
+            column.push(Cell::from(&property));
+        }
+
+        self.offers.push(IndexedOffer { offer, constraints });
+        Ok(row)
+    }
+
+    // Rows of Offers, which match the Demand (equivalent of `Match::Yes`).
+    pub fn match_demand(&self, demand: &Demand) -> Result<Vec<usize>, MatchError> {
+        let prepared = PreparedDemand::from(demand)?;
+        let typed = self.resolve(&prepared.constraints);
+
+        let mut rows = Vec::new();
+        for (row, result) in typed.into_iter().enumerate() {
+            let indexed = &self.offers[row];
+            let demand_side = match result {
+                Tri::True => true,
+                Tri::False | Tri::Undefined => false,
+                Tri::Unknown => {
+                    let properties = PropertySet::from_flat_props(&indexed.offer.properties);
+                    matches!(
+                        prepared.constraints.resolve(&properties),
+                        ResolveResult::True
+                    )
+                }
+            };
+
+            if demand_side
+                && matches!(
+                    indexed.constraints.resolve(&prepared.properties),
+                    ResolveResult::True
+                )
+            {
+                rows.push(row);
+            }
+        }
+        Ok(rows)
+    }
+
+    // Resolves expression against all Offers at once.
+    fn resolve(&self, expression: &Expression) -> Vec<Tri> {
+        let len = self.offers.len();
+        match expression {
+            Expression::Equals(prop, val) => self.resolve_cmp(prop, val, Cmp::Equals),
+            Expression::Less(prop, val) => self.resolve_cmp(prop, val, Cmp::Less),
+            Expression::LessEqual(prop, val) => self.resolve_cmp(prop, val, Cmp::LessEqual),
+            Expression::Greater(prop, val) => self.resolve_cmp(prop, val, Cmp::Greater),
+            Expression::GreaterEqual(prop, val) => self.resolve_cmp(prop, val, Cmp::GreaterEqual),
+            Expression::Present(PropertyRef::Value(name, _)) => (0..len)
+                .map(|row| match self.cell(name, row) {
+                    Cell::Missing => Tri::False,
+                    _ => Tri::True,
+                })
+                .collect(),
+            Expression::Present(PropertyRef::Aspect(..)) => vec![Tri::Unknown; len],
+            Expression::And(exprs) => exprs
+                .iter()
+                .map(|expr| self.resolve(expr))
+                .fold(vec![Tri::True; len], |acc, next| zip(acc, next, Tri::and)),
+            Expression::Or(exprs) => exprs
+                .iter()
+                .map(|expr| self.resolve(expr))
+                .fold(vec![Tri::False; len], |acc, next| zip(acc, next, Tri::or)),
+            Expression::Not(expr) => self.resolve(expr).into_iter().map(Tri::not).collect(),
+            Expression::Empty(true) => vec![Tri::True; len],
+            Expression::Empty(false) => vec![Tri::False; len],
+        }
+    }
+
+    fn resolve_cmp(&self, prop: &PropertyRef, val: &str, cmp: Cmp) -> Vec<Tri> {
+        let len = self.offers.len();
+        let name = match prop {
+            PropertyRef::Value(name, PropertyRefType::Any) => name,
+            _ => return vec![Tri::Unknown; len],
+        };
+        let column = match self.columns.get(name) {
+            Some(column) => column,
+            None => return vec![Tri::Undefined; len],
+        };
+
+        let operand = Operand::new(val, cmp);
+        (0..len)
+            .map(|row| operand.compare(column.get(row).unwrap_or(&Cell::Missing), cmp))
+            .collect()
+    }
+
+    fn cell(&self, name: &str, row: usize) -> &Cell {
+        self.columns
+            .get(name)
+            .and_then(|column| column.get(row))
+            .unwrap_or(&Cell::Missing)
+    }
+}
+
+impl<'a> From<&Property<'a>> for Cell {
+    fn from(property: &Property<'a>) -> Self {
+        match property {
+            Property::Implicit(_) => Cell::Implicit,
+            Property::Explicit(_, _, aspects) if !aspects.is_empty() => Cell::Other,
+            Property::Explicit(_, value, _) => match value {
+                PropertyValue::Str(val) => Cell::Str(val.to_string()),
+                PropertyValue::Number(val) => Cell::Number(*val),
+                PropertyValue::Boolean(val) => Cell::Boolean(*val),
+                PropertyValue::Version(val) => Cell::Version(val.clone()),
+                PropertyValue::DateTime(val) => Cell::DateTime(*val),
+                PropertyValue::Decimal(_) | PropertyValue::List(_) => Cell::Other,
+            },
+        }
+    }
+}
+
+impl<'a> Operand<'a> {
+    fn new(raw: &'a str, cmp: Cmp) -> Self {
+        // Same wildcard semantics as `PropertyValue::equals`.
+        let wildcard = match cmp {
+            Cmp::Equals if raw.contains('*') => {
+                Some(Regex::new(&format!("^{}$", raw.replace('*', ".*"))).ok())
+            }
+            _ => None,
+        };
+        Operand {
+            raw,
+            number: raw.parse().ok(),
+            boolean: raw.parse().ok(),
+            version: Version::parse(raw).ok(),
+            datetime: DateTime::parse_from_rfc3339(raw)
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc)),
+            wildcard,
+        }
+    }
+
+    fn compare(&self, cell: &Cell, cmp: Cmp) -> Tri {
+        let result = match cell {
+            Cell::Missing | Cell::Implicit => return Tri::Undefined,
+            Cell::Other => return Tri::Unknown,
+            Cell::Str(value) => match (cmp, &self.wildcard) {
+                (Cmp::Equals, Some(regex)) => regex
+                    .as_ref()
+                    .map(|regex| regex.is_match(value))
+                    .unwrap_or(false),
+                _ => cmp.apply(value.as_str(), self.raw),
+            },
+            Cell::Number(value) => self.number.map(|n| cmp.apply(value, &n)).unwrap_or(false),
+            Cell::Boolean(value) => match cmp {
+                Cmp::Equals => self.boolean == Some(*value),
+                _ => false,
+            },
+            Cell::Version(value) => self
+                .version
+                .as_ref()
+                .map(|v| cmp.apply(value, v))
+                .unwrap_or(false),
+            Cell::DateTime(value) => self
+                .datetime
+                .as_ref()
+                .map(|dt| cmp.apply(value, dt))
+                .unwrap_or(false),
+        };
+        if result {
+            Tri::True
+        } else {
+            Tri::False
+        }
+    }
+}
+
+impl Cmp {
+    fn apply<T: PartialOrd + ?Sized>(self, value: &T, other: &T) -> bool {
+        match self {
+            Cmp::Equals => value == other,
+            Cmp::Less => value < other,
+            Cmp::LessEqual => value <= other,
+            Cmp::Greater => value > other,
+            Cmp::GreaterEqual => value >= other,
+        }
+    }
+}
+
+impl Tri {
+    fn and(self, other: Tri) -> Tri {
+        match (self, other) {
+            (Tri::False, _) | (_, Tri::False) => Tri::False,
+            (Tri::Unknown, _) | (_, Tri::Unknown) => Tri::Unknown,
+            (Tri::Undefined, _) | (_, Tri::Undefined) => Tri::Undefined,
+            _ => Tri::True,
+        }
+    }
+
+    fn or(self, other: Tri) -> Tri {
+        match (self, other) {
+            (Tri::True, _) | (_, Tri::True) => Tri::True,
+            (Tri::Unknown, _) | (_, Tri::Unknown) => Tri::Unknown,
+            (Tri::Undefined, _) | (_, Tri::Undefined) => Tri::Undefined,
+            _ => Tri::False,
+        }
+    }
+
+    fn not(self) -> Tri {
+        match self {
+            Tri::True => Tri::False,
+            Tri::False => Tri::True,
+            other => other,
+        }
+    }
+}
+
+fn zip(acc: Vec<Tri>, next: Vec<Tri>, op: fn(Tri, Tri) -> Tri) -> Vec<Tri> {
+    acc.into_iter().zip(next).map(|(a, b)| op(a, b)).collect()
+}
@@ -0,0 +1,185 @@
+//! Syntax check of constraint filters reporting positions of errors,
+//! which the `ldap_parser` doesn't provide.
+use serde::Serialize;
+use std::fmt;
+
+use crate::resolver::expression::build_expression;
+use crate::resolver::ldap_parser;
+use crate::resolver::properties::{parse_prop_ref, PropertyRef};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SyntaxError {
+    /// Byte offset in the constraints.
+    pub position: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    fn new(position: usize, message: impl ToString) -> Self {
+        SyntaxError {
+            position,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+/// Checks syntax of constraints. Returns names of referenced properties in order
+/// of appearance (without duplicates). Empty constraints are valid.
+pub fn check_constraints(constraints: &str) -> Result<Vec<String>, SyntaxError> {
+    if constraints.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut scanner = Scanner {
+        input: constraints,
+        pos: 0,
+        properties: Vec::new(),
+    };
+    scanner.filter()?;
+    scanner.skip_whitespace();
+    if scanner.pos < constraints.len() {
+        return Err(SyntaxError::new(
+            scanner.pos,
+            "unexpected text after the filter",
+        ));
+    }
+
+    // Scanner mirrors the grammar, but the parser has the final word.
+    let tag = ldap_parser::parse(constraints).map_err(|e| SyntaxError::new(0, e))?;
+    build_expression(&tag).map_err(|e| SyntaxError::new(0, e))?;
+    Ok(scanner.properties)
+}
+
+struct Scanner<'a> {
+    input: &'a str,
+    pos: usize,
+    properties: Vec<String>,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .map(|c| c.is_ascii_whitespace())
+            .unwrap_or(false)
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), SyntaxError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(SyntaxError::new(
+                self.pos,
+                format!("expected `{}`, found `{}`", expected as char, c as char),
+            )),
+            None => Err(SyntaxError::new(
+                self.pos,
+                format!("expected `{}`, found end of input", expected as char),
+            )),
+        }
+    }
+
+    /// `()` or `(<content>)`
+    fn filter(&mut self) -> Result<(), SyntaxError> {
+        self.expect(b'(')?;
+        if self.peek() == Some(b')') {
+            self.pos += 1;
+            return Ok(());
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'&') | Some(b'|') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                while self.peek() == Some(b'(') {
+                    self.filter()?;
+                    self.skip_whitespace();
+                }
+            }
+            Some(b'!') => {
+                self.pos += 1;
+                self.filter()?;
+            }
+            _ => self.clause()?,
+        }
+        self.expect(b')')
+    }
+
+    /// `<property><operator><value>`
+    fn clause(&mut self) -> Result<(), SyntaxError> {
+        let start = self.pos;
+        let end = self.input[start..]
+            .find(|c| matches!(c, '=' | '<' | '>' | '~' | '(' | ')'))
+            .map(|offset| start + offset)
+            .unwrap_or_else(|| self.input.len());
+        self.pos = end;
+
+        match self.peek() {
+            Some(b'=') | Some(b'<') | Some(b'>') => (),
+            Some(b'~') => return Err(SyntaxError::new(end, "approximate match is not supported")),
+            Some(c) => {
+                return Err(SyntaxError::new(
+                    end,
+                    format!("expected comparison operator, found `{}`", c as char),
+                ))
+            }
+            None => {
+                return Err(SyntaxError::new(
+                    end,
+                    "expected comparison operator, found end of input",
+                ))
+            }
+        }
+
+        let property = self.input[start..end].trim();
+        if property.is_empty() {
+            return Err(SyntaxError::new(start, "missing property name"));
+        }
+        let name = match parse_prop_ref(property) {
+            Ok(PropertyRef::Value(name, _)) | Ok(PropertyRef::Aspect(name, _, _)) => name,
+            Err(e) => {
+                return Err(SyntaxError::new(
+                    start,
+                    format!("invalid property reference `{}`: {}", property, e),
+                ))
+            }
+        };
+        if !self.properties.contains(&name) {
+            self.properties.push(name);
+        }
+
+        self.pos += match self.input[self.pos..].starts_with("<=")
+            || self.input[self.pos..].starts_with(">=")
+        {
+            true => 2,
+            false => 1,
+        };
+        match self.input[self.pos..].find(')') {
+            Some(offset) => {
+                self.pos += offset;
+                Ok(())
+            }
+            None => Err(SyntaxError::new(
+                self.input.len(),
+                "expected `)`, found end of input",
+            )),
+        }
+    }
+}
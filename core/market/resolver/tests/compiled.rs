@@ -0,0 +1,110 @@
+use ya_market_resolver::resolver::expression::build_expression;
+use ya_market_resolver::resolver::ldap_parser::parse;
+use ya_market_resolver::resolver::properties::{PropertyRef, PropertyRefType};
+use ya_market_resolver::resolver::Expression;
+use ya_market_resolver::{match_compiled, match_demand_offer, CompiledSubscription};
+
+mod sample;
+
+use sample::{
+    POC_DEMAND_CONSTRAINTS, POC_DEMAND_PROPERTIES_JSON, POC_OFFER_CONSTRAINTS,
+    POC_OFFER_PROPERTIES_JSON,
+};
+
+fn folded(filter: &str) -> Expression {
+    build_expression(&parse(filter).unwrap()).unwrap().fold()
+}
+
+fn equals(name: &str, value: &str) -> Expression {
+    Expression::Equals(
+        PropertyRef::Value(name.to_string(), PropertyRefType::Any),
+        value.to_string(),
+    )
+}
+
+#[test]
+fn fold_drops_neutral_operands() {
+    assert_eq!(folded("(&()(a=1))"), equals("a", "1"));
+    assert_eq!(
+        folded("(|(!())(a=1)(b=2))"),
+        Expression::Or(vec![equals("a", "1"), equals("b", "2")])
+    );
+    assert_eq!(folded("(&()())"), Expression::Empty(true));
+}
+
+#[test]
+fn fold_short_circuits_absorbing_operands() {
+    assert_eq!(folded("(&(a=1)(!()))"), Expression::Empty(false));
+    assert_eq!(folded("(|(a=1)(&()()))"), Expression::Empty(true));
+    assert_eq!(folded("(!(&(!())(a=1)))"), Expression::Empty(true));
+}
+
+#[test]
+fn fold_keeps_unresolvable_expressions() {
+    let expression = folded("(&(a=1)(!(b=2)))");
+    assert_eq!(expression.clone().fold(), expression);
+    assert_eq!(
+        expression,
+        Expression::And(vec![
+            equals("a", "1"),
+            Expression::Not(Box::new(equals("b", "2")))
+        ])
+    );
+}
+
+#[test]
+fn match_compiled_same_as_match_demand_offer() {
+    let demand_properties = r#"{"golem.node.debug.subnet": "public", "x.y": 3}"#;
+    let offer_properties = r#"{"golem.runtime.name": "vm", "golem.inf.mem.gib": 4}"#;
+    let cases = [
+        ("(&()(golem.runtime.name=vm))", "(x.y>1)"),
+        ("(&(golem.runtime.name=vm)(!()))", "()"),
+        ("(|(golem.inf.mem.gib>8)(golem.runtime.name=wasm*))", "()"),
+        (
+            "(golem.inf.mem.gib<=4)",
+            "(&(x.y=3)(golem.node.debug.subnet=public))",
+        ),
+        ("(golem.inf.cpu.threads>1)", "()"),
+    ];
+
+    for (demand_constraints, offer_constraints) in cases.iter() {
+        let demand = CompiledSubscription::compile(demand_properties, demand_constraints).unwrap();
+        let offer = CompiledSubscription::compile(offer_properties, offer_constraints).unwrap();
+        let expected = match_demand_offer(
+            demand_properties,
+            demand_constraints,
+            offer_properties,
+            offer_constraints,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::mem::discriminant(&match_compiled(&demand, &offer).unwrap()),
+            std::mem::discriminant(&expected),
+            "Demand: {}, Offer: {}",
+            demand_constraints,
+            offer_constraints
+        );
+    }
+
+    let demand =
+        CompiledSubscription::compile(POC_DEMAND_PROPERTIES_JSON, POC_DEMAND_CONSTRAINTS).unwrap();
+    let offer =
+        CompiledSubscription::compile(POC_OFFER_PROPERTIES_JSON, POC_OFFER_CONSTRAINTS).unwrap();
+    assert_eq!(
+        match_compiled(&demand, &offer).unwrap(),
+        match_demand_offer(
+            POC_DEMAND_PROPERTIES_JSON,
+            POC_DEMAND_CONSTRAINTS,
+            POC_OFFER_PROPERTIES_JSON,
+            POC_OFFER_CONSTRAINTS
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn compile_invalid_constraints_should_fail() {
+    assert!(CompiledSubscription::compile("{}", "").is_err());
+    assert!(CompiledSubscription::compile("", "()").is_err());
+}
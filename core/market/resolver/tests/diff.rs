@@ -0,0 +1,71 @@
+use serde_json::json;
+
+use ya_market_resolver::resolver::diff::{diff_constraints, diff_proposals, ConstraintsDiff};
+
+#[test]
+fn diff_properties_of_proposals() {
+    let prev = json!({
+        "golem": {
+            "inf": {"mem": {"gib": 2}, "cpu": {"threads": 4}},
+            "com": {"pricing": {"model": "linear"}},
+        }
+    });
+    let next = json!({
+        "golem": {
+            "inf": {"mem": {"gib": 4}},
+            "com": {"pricing": {"model": "linear"}},
+            "srv": {"comp": {"expiration": 1700000000000u64}},
+        }
+    });
+
+    let diff = diff_proposals(&prev, "", &next, "");
+    assert_eq!(
+        diff.properties.added.into_iter().collect::<Vec<_>>(),
+        vec![(
+            "golem.srv.comp.expiration".to_string(),
+            json!(1700000000000u64)
+        )]
+    );
+    assert_eq!(
+        diff.properties.changed.into_iter().collect::<Vec<_>>(),
+        vec![("golem.inf.mem.gib".to_string(), json!(4))]
+    );
+    assert_eq!(
+        diff.properties.removed,
+        vec!["golem.inf.cpu.threads".to_string()]
+    );
+    assert!(diff.constraints.added.is_empty());
+}
+
+#[test]
+fn diff_constraints_by_clauses() {
+    let prev = "(&(golem.inf.mem.gib>=0.5)\n  (golem.runtime.name=vm))";
+    let next = "(&(golem.runtime.name=vm)(golem.inf.mem.gib>=1)(golem.node.debug.subnet=*))";
+
+    assert_eq!(
+        diff_constraints(prev, next),
+        ConstraintsDiff {
+            added: vec![
+                "(golem.inf.mem.gib>=1)".to_string(),
+                "(golem.node.debug.subnet=*)".to_string(),
+            ],
+            removed: vec!["(golem.inf.mem.gib>=0.5)".to_string()],
+        }
+    );
+    assert_eq!(diff_constraints(prev, prev), ConstraintsDiff::default());
+    assert_eq!(
+        diff_constraints("", "(golem.runtime.name=vm)"),
+        ConstraintsDiff {
+            added: vec!["(golem.runtime.name=vm)".to_string()],
+            removed: vec![],
+        }
+    );
+}
+
+#[test]
+fn empty_diff_of_same_proposal() {
+    let properties = json!({"golem.inf.mem.gib": 2, "golem.runtime": {"name": "vm"}});
+    let constraints = "(golem.runtime.name=vm)";
+
+    assert!(diff_proposals(&properties, constraints, &properties, constraints).is_empty());
+}
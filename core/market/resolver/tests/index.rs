@@ -0,0 +1,81 @@
+use ya_market_resolver::{match_demand_offer, Demand, Match, Offer, OfferIndex};
+
+fn offers() -> Vec<(String, String)> {
+    (0..40)
+        .map(|i| {
+            let properties = format!(
+                r#"{{
+                    "golem.inf.mem.gib": {},
+                    "golem.inf.cpu.threads": {},
+                    "golem.runtime.name": "{}",
+                    "golem.runtime.version@v": "0.{}.0",
+                    "golem.node.debug.subnet": "{}",
+                    "golem.com.usage.vector": ["golem.usage.duration_sec", "golem.usage.cpu_sec"]
+                }}"#,
+                i % 8,
+                i % 5 + 1,
+                if i % 3 == 0 { "vm" } else { "wasmtime" },
+                i % 4,
+                if i % 2 == 0 { "public" } else { "private" },
+            );
+            let constraints = if i % 7 == 0 {
+                "(golem.srv.comp.expiration>0)".to_string()
+            } else {
+                "(golem.node.debug.subnet=public*)".to_string()
+            };
+            (properties, constraints)
+        })
+        .collect()
+}
+
+#[test]
+fn index_matches_same_offers_as_resolver() {
+    let demand_properties = r#"{
+        "golem.node.debug.subnet": "public",
+        "golem.srv.comp.expiration": 1700000000000
+    }"#;
+    let demand_constraints = [
+        "(&(golem.inf.mem.gib>=2)(golem.runtime.name=vm))",
+        "(|(golem.inf.cpu.threads=3)(golem.runtime.name=wasm*))",
+        "(&(golem.runtime.version@v>=0.2.0)(!(golem.node.debug.subnet=private)))",
+        "(&(golem.com.usage.vector=golem.usage.cpu_sec)(golem.inf.mem.gib<4))",
+        "(&(golem.inf.storage.gib>1)(golem.inf.mem.gib>1))",
+        "(golem.inf.cpu.threads=*)",
+        "()",
+    ];
+
+    let offers = offers();
+    let mut index = OfferIndex::new();
+    for (properties, constraints) in &offers {
+        index
+            .insert(Offer::from(properties, constraints).unwrap())
+            .unwrap();
+    }
+    assert_eq!(index.len(), offers.len());
+
+    for constraints in demand_constraints.iter() {
+        let expected = offers
+            .iter()
+            .enumerate()
+            .filter(|(_, (properties, offer_constraints))| {
+                match_demand_offer(
+                    demand_properties,
+                    constraints,
+                    properties,
+                    offer_constraints,
+                )
+                .unwrap()
+                    == Match::Yes
+            })
+            .map(|(row, _)| row)
+            .collect::<Vec<_>>();
+
+        let demand = Demand::from(demand_properties, constraints).unwrap();
+        assert_eq!(
+            index.match_demand(&demand).unwrap(),
+            expected,
+            "constraints: {}",
+            constraints
+        );
+    }
+}
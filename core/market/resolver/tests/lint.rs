@@ -0,0 +1,50 @@
+use ya_market_resolver::resolver::lint::check_constraints;
+
+#[test]
+fn lint_valid_constraints() {
+    let constraints = "(&(golem.inf.mem.gib>=0.5)\n  (golem.runtime.name=vm)\n  (golem.inf.mem.gib<=8)\n  (golem.srv.caps.payload-manifest=*))";
+    assert_eq!(
+        check_constraints(constraints),
+        Ok(vec![
+            "golem.inf.mem.gib".to_string(),
+            "golem.runtime.name".to_string(),
+            "golem.srv.caps.payload-manifest".to_string(),
+        ])
+    );
+    assert_eq!(check_constraints(""), Ok(vec![]));
+    assert_eq!(check_constraints("()"), Ok(vec![]));
+    assert_eq!(
+        check_constraints("(!(golem.node.debug.subnet=public))"),
+        Ok(vec!["golem.node.debug.subnet".to_string()])
+    );
+}
+
+#[test]
+fn lint_reports_error_positions() {
+    let error = |constraints: &str| {
+        let error = check_constraints(constraints).unwrap_err();
+        (error.position, error.message)
+    };
+
+    assert_eq!(
+        error("(&(golem.inf.mem.gib>=0.5)(golem.runtime.name=vm)"),
+        (49, "expected `)`, found end of input".to_string())
+    );
+    assert_eq!(
+        error("(&(golem.inf.mem.gib)(golem.runtime.name=vm))"),
+        (20, "expected comparison operator, found `)`".to_string())
+    );
+    assert_eq!(error("(&(=vm))"), (3, "missing property name".to_string()));
+    assert_eq!(
+        error("(golem.runtime.name~=vm)"),
+        (19, "approximate match is not supported".to_string())
+    );
+    assert_eq!(
+        error("(golem.runtime.name=vm) x"),
+        (24, "unexpected text after the filter".to_string())
+    );
+    assert_eq!(
+        error("golem.runtime.name=vm"),
+        (0, "expected `(`, found `g`".to_string())
+    );
+}
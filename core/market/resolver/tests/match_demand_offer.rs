@@ -1,4 +1,6 @@
-use ya_market_resolver::{match_demand_offer, Match, MatchError};
+use ya_market_resolver::{
+    explain_demand_offer, match_demand_offer, ClauseOutcome, Match, MatchError,
+};
 
 mod sample;
 
@@ -119,3 +121,49 @@ fn match_poc_offer_demand_samples_deep() {
         Match::Yes
     );
 }
+
+#[test]
+fn explain_reports_outcome_of_each_clause() {
+    let explanation = explain_demand_offer(
+        "{\"foo\": \"bar\"}",
+        "(&(qux=baz)(mem>=8)(gpu=nvidia))",
+        "{\"qux\": \"baz\", \"mem\": 4}",
+        "(foo=bar)",
+    )
+    .unwrap();
+
+    assert_eq!(explanation.outcome, ClauseOutcome::Failed);
+    assert_eq!(
+        explanation.offer_constraints.outcome,
+        ClauseOutcome::Matched
+    );
+
+    let demand = &explanation.demand_constraints;
+    assert_eq!(demand.clause, "(&(qux=baz)(mem>=8)(gpu=nvidia))");
+    let outcomes: Vec<_> = demand
+        .operands
+        .iter()
+        .map(|trace| (trace.clause.as_str(), trace.outcome))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("(qux=baz)", ClauseOutcome::Matched),
+            ("(mem>=8)", ClauseOutcome::Failed),
+            ("(gpu=nvidia)", ClauseOutcome::Undefined),
+        ]
+    );
+    assert_eq!(demand.operands[2].undefined_properties, vec!["gpu"]);
+}
+
+#[test]
+fn explain_agrees_with_match() {
+    let explanation = explain_demand_offer(
+        POC_DEMAND_PROPERTIES_JSON,
+        POC_DEMAND_CONSTRAINTS,
+        POC_OFFER_PROPERTIES_JSON,
+        POC_OFFER_CONSTRAINTS,
+    )
+    .unwrap();
+    assert_eq!(explanation.outcome, ClauseOutcome::Matched);
+}
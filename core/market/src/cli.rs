@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use structopt::StructOpt;
 use ya_client::model::market::{agreement::State, Role};
-use ya_core_model::market::{GetAgreement, ListAgreements};
+use ya_client::model::NodeId;
+use ya_core_model::market::{
+    AddOfferFilter, GetAgreement, GetSubscriptionStats, ListAgreements, ListOfferFilters,
+    OfferFilterRule, RemoveOfferFilter,
+};
 use ya_service_api::{CliCtx, CommandOutput, ResponseTable};
 use ya_service_bus::{typed as bus, RpcEndpoint};
 
@@ -9,12 +13,92 @@ use ya_service_bus::{typed as bus, RpcEndpoint};
 #[derive(StructOpt, Debug)]
 pub enum Command {
     Agreements(AgreementsCommand),
+    /// Manage rules filtering propagated Offers
+    Rule(RuleCommand),
+    /// Show negotiation statistics of the Offer or Demand
+    Stats {
+        /// Subscription id of the Offer or Demand
+        subscription_id: String,
+    },
 }
 
 impl Command {
     pub async fn run_command(self, ctx: &CliCtx) -> anyhow::Result<CommandOutput> {
         match self {
             Command::Agreements(agreements_cmd) => agreements_cmd.run_command(ctx).await,
+            Command::Rule(rule_cmd) => rule_cmd.run_command(ctx).await,
+            Command::Stats { subscription_id } => {
+                let stats = bus::service(ya_core_model::market::local::BUS_ID)
+                    .send(GetSubscriptionStats { subscription_id })
+                    .await??;
+                CommandOutput::object(stats)
+            }
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub enum RuleCommand {
+    /// Add new rule.
+    Add(RuleKind),
+    /// Remove existing rule.
+    Remove(RuleKind),
+    /// List active rules.
+    List,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum RuleKind {
+    /// Drop Offers propagated from given sources before they are stored.
+    MarketFilter(MarketFilterDesc),
+}
+
+#[derive(StructOpt, Debug)]
+pub enum MarketFilterDesc {
+    /// Offers created by or received from the node.
+    Identity { address: NodeId },
+    /// Offers from the subnet.
+    Subnet { subnet: String },
+}
+
+impl From<MarketFilterDesc> for OfferFilterRule {
+    fn from(desc: MarketFilterDesc) -> Self {
+        match desc {
+            MarketFilterDesc::Identity { address } => OfferFilterRule::Identity(address),
+            MarketFilterDesc::Subnet { subnet } => OfferFilterRule::Subnet(subnet),
+        }
+    }
+}
+
+impl RuleCommand {
+    pub async fn run_command(self, _ctx: &CliCtx) -> anyhow::Result<CommandOutput> {
+        let market = bus::service(ya_core_model::market::local::BUS_ID);
+        match self {
+            RuleCommand::Add(RuleKind::MarketFilter(desc)) => {
+                market.send(AddOfferFilter(desc.into())).await??;
+                Ok(CommandOutput::NoOutput)
+            }
+            RuleCommand::Remove(RuleKind::MarketFilter(desc)) => {
+                market.send(RemoveOfferFilter(desc.into())).await??;
+                Ok(CommandOutput::NoOutput)
+            }
+            RuleCommand::List => {
+                let rules = market.send(ListOfferFilters {}).await??;
+                let values = rules
+                    .into_iter()
+                    .map(|rule| match rule {
+                        OfferFilterRule::Identity(node_id) => {
+                            serde_json::json!(["identity", node_id])
+                        }
+                        OfferFilterRule::Subnet(subnet) => serde_json::json!(["subnet", subnet]),
+                    })
+                    .collect();
+                Ok(ResponseTable {
+                    columns: vec!["rule".to_owned(), "value".to_owned()],
+                    values,
+                }
+                .with_header("\nMarket filter:\n".to_owned()))
+            }
         }
     }
 }
@@ -30,6 +114,11 @@ pub enum AgreementsCommand {
         after: Option<DateTime<Utc>>,
         #[structopt(long, help = "Only show agreements with this app session id")]
         app_session_id: Option<String>,
+        #[structopt(
+            long,
+            help = "Only show agreements with matching labels, e.g. job=1234,team"
+        )]
+        label: Option<String>,
     },
     Get {
         #[structopt(long, help = "Agreement ID, may be obtained via list-agreements")]
@@ -47,12 +136,14 @@ impl AgreementsCommand {
                 before,
                 after,
                 app_session_id,
+                label,
             } => {
                 let request = ListAgreements {
                     state,
                     before_date: before,
                     after_date: after,
                     app_session_id,
+                    label,
                 };
 
                 let agreements = bus::service(ya_core_model::market::BUS_ID)
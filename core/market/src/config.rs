@@ -1,6 +1,8 @@
 use std::time::Duration;
 use structopt::StructOpt;
 
+use crate::matcher::validation::ValidationMode;
+
 #[derive(StructOpt, Clone)]
 pub struct Config {
     #[structopt(flatten)]
@@ -11,6 +13,10 @@ pub struct Config {
     pub events: EventsConfig,
     #[structopt(flatten)]
     pub db: DbConfig,
+    #[structopt(flatten)]
+    pub negotiation: NegotiationConfig,
+    #[structopt(flatten)]
+    pub blacklist: BlacklistConfig,
 }
 
 #[derive(StructOpt, Clone)]
@@ -41,6 +47,18 @@ pub struct DiscoveryConfig {
 pub struct SubscriptionConfig {
     #[structopt(env = "DEFAULT_SUBSCRIPTION_TTL", parse(try_from_str = parse_chrono_duration), default_value = "1h")]
     pub default_ttl: chrono::Duration,
+    /// Offer/Demand content validation at subscription time: reject, warn or off.
+    #[structopt(env = "MARKET_PROPERTY_VALIDATION", default_value = "warn")]
+    pub property_validation: ValidationMode,
+    /// Maximal number of Offers and Demands (each) kept in memory cache. Disabled if set to 0.
+    #[structopt(env = "MARKET_SUBSCRIPTION_CACHE_ENTRIES", default_value = "1000")]
+    pub cache_max_entries: usize,
+    /// Approximate memory limit in bytes for Offers and Demands (each) kept in memory cache.
+    #[structopt(env = "MARKET_SUBSCRIPTION_CACHE_BYTES", default_value = "16777216")]
+    pub cache_max_bytes: usize,
+    /// Maximal number of compiled Offers and Demands kept by resolver. Disabled if set to 0.
+    #[structopt(env = "MARKET_COMPILED_CACHE_ENTRIES", default_value = "2000")]
+    pub compiled_cache_max_entries: usize,
 }
 
 #[derive(StructOpt, Clone)]
@@ -62,6 +80,44 @@ pub struct DbConfig {
     /// Number of days to persist Negotiation Events
     #[structopt(env = "MARKET_EVENT_STORE_DAYS", default_value = "1")]
     pub event_store_days: i32,
+    /// Inactivity period, after which app session is removed
+    #[structopt(env = "MARKET_SESSION_TTL", parse(try_from_str = parse_chrono_duration), default_value = "7d")]
+    pub session_ttl: chrono::Duration,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct NegotiationConfig {
+    /// Time after which Requestor cancels Agreement not approved by Provider and counters
+    /// the next best Draft Proposal from the same Demand. Disabled if not set.
+    #[structopt(env = "MARKET_APPROVAL_TIMEOUT", parse(try_from_str = humantime::parse_duration))]
+    pub approval_timeout: Option<Duration>,
+    /// Difficulty (in bits) of proof of interest, which Requestor attaches to the first
+    /// Proposal sent to Provider. Each bit doubles the cost of computing it.
+    #[structopt(env = "MARKET_PROOF_OF_INTEREST_DIFFICULTY", default_value = "12")]
+    pub proof_of_interest_difficulty: u32,
+    /// Provider rejects first Proposals without proof of interest of at least this
+    /// difficulty. Disabled if set to 0.
+    #[structopt(env = "MARKET_REQUIRED_PROOF_OF_INTEREST", default_value = "0")]
+    pub required_proof_of_interest: u32,
+    /// Time after which Draft Proposals, which weren't answered by the other side, expire.
+    /// Disabled if not set.
+    #[structopt(env = "MARKET_PROPOSAL_TTL", parse(try_from_str = humantime::parse_duration))]
+    pub proposal_ttl: Option<Duration>,
+    /// Interval in which expired Proposals are looked up, when `proposal_ttl` is set.
+    #[structopt(env = "MARKET_PROPOSAL_EXPIRY_INTERVAL", parse(try_from_str = humantime::parse_duration), default_value = "10s")]
+    pub proposal_expiry_interval: Duration,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct BlacklistConfig {
+    /// Number of Provider failures within the window, after which Requestor stops
+    /// negotiating with the Provider for the cooldown period. Disabled if set to 0.
+    #[structopt(env = "MARKET_BLACKLIST_MAX_FAILURES", default_value = "3")]
+    pub max_failures: u32,
+    #[structopt(env = "MARKET_BLACKLIST_WINDOW", parse(try_from_str = humantime::parse_duration), default_value = "1h")]
+    pub window: Duration,
+    #[structopt(env = "MARKET_BLACKLIST_COOLDOWN", parse(try_from_str = humantime::parse_duration), default_value = "1h")]
+    pub cooldown: Duration,
 }
 
 impl Config {
@@ -99,5 +155,24 @@ mod test {
         assert_eq!(4 * 3600, c.db.cleanup_interval.as_secs());
         assert_eq!(90, c.db.agreement_store_days);
         assert_eq!(1, c.db.event_store_days);
+        assert_eq!(7, c.db.session_ttl.num_days());
+    }
+
+    #[test]
+    fn test_default_structopt_negotiation_config() {
+        let c = Config::from_env().unwrap();
+        assert!(c.negotiation.approval_timeout.is_none());
+        assert_eq!(12, c.negotiation.proof_of_interest_difficulty);
+        assert_eq!(0, c.negotiation.required_proof_of_interest);
+        assert!(c.negotiation.proposal_ttl.is_none());
+        assert_eq!(10, c.negotiation.proposal_expiry_interval.as_secs());
+    }
+
+    #[test]
+    fn test_default_structopt_blacklist_config() {
+        let c = Config::from_env().unwrap();
+        assert_eq!(3, c.blacklist.max_failures);
+        assert_eq!(3600, c.blacklist.window.as_secs());
+        assert_eq!(3600, c.blacklist.cooldown.as_secs());
     }
 }
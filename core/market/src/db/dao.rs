@@ -1,5 +1,7 @@
 mod agreement;
 mod agreement_events;
+mod agreement_labels;
+mod app_session;
 pub mod cleaner;
 mod demand;
 mod negotiation_events;
@@ -12,11 +14,17 @@ pub mod sql_functions {
     );
 }
 mod offer;
+mod offer_filter;
 mod proposal;
+mod webhook;
 
-pub use agreement::{AgreementDao, AgreementDaoError, SaveAgreementError};
+pub use agreement::{AgreementDao, AgreementDaoError, AgreementFilter, SaveAgreementError};
 pub use agreement_events::AgreementEventsDao;
+pub use agreement_labels::AgreementLabelsDao;
+pub use app_session::AppSessionDao;
 pub use demand::{DemandDao, DemandState};
 pub use negotiation_events::{NegotiationEventsDao, TakeEventsError};
 pub use offer::{OfferDao, OfferState};
+pub use offer_filter::OfferFilterDao;
 pub use proposal::{ChangeProposalStateError, ProposalDao, SaveProposalError};
+pub use webhook::WebhookDao;
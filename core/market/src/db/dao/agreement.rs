@@ -1,5 +1,6 @@
 use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
 
 use ya_client::model::market::Reason;
 use ya_client::model::NodeId;
@@ -7,17 +8,20 @@ use ya_persistence::executor::{do_with_transaction, readonly_transaction, ConnTy
 
 use crate::config::DbConfig;
 use crate::db::dao::agreement_events::create_event;
+use crate::db::dao::agreement_labels::filter_by_labels;
 use crate::db::dao::proposal::{has_counter_proposal, update_proposal_state};
 use crate::db::dao::sql_functions::datetime;
 use crate::db::model::{
-    check_transition, Agreement, AgreementId, AgreementState, AppSessionId, Owner, ProposalId,
-    ProposalIdParseError, ProposalState,
+    check_transition, Agreement, AgreementId, AgreementState, AppSessionId, LabelSelector, Owner,
+    ProposalId, ProposalIdParseError, ProposalState, SubscriptionId,
 };
 use crate::db::schema::market_agreement::dsl as agreement;
 use crate::db::schema::market_agreement::dsl::market_agreement;
 use crate::db::schema::market_agreement_event::dsl as event;
 use crate::db::schema::market_agreement_event::dsl::market_agreement_event;
-use crate::db::{AsMixedDao, DbError, DbResult};
+use crate::db::schema::market_agreement_label::dsl as label;
+use crate::db::schema::market_agreement_label::dsl::market_agreement_label;
+use crate::db::{schema, AsMixedDao, DbError, DbResult};
 
 #[derive(thiserror::Error, Debug)]
 pub enum SaveAgreementError {
@@ -43,6 +47,80 @@ impl<'a> AsMixedDao<'a> for AgreementDao<'a> {
     }
 }
 
+/// Conditions narrowing listed Agreements.
+#[derive(Clone, Debug, Default)]
+pub struct AgreementFilter {
+    /// Agreements, which the node is party of.
+    pub node_id: Option<NodeId>,
+    /// Agreements concluded with this node. If `node_id` is set, it must be the other party.
+    pub counterparty: Option<NodeId>,
+    pub role: Option<Owner>,
+    /// Agreements negotiated on this Offer or Demand.
+    pub subscription_id: Option<SubscriptionId>,
+    pub state: Option<AgreementState>,
+    pub before: Option<DateTime<Utc>>,
+    pub after: Option<DateTime<Utc>>,
+    pub app_session_id: Option<String>,
+    pub labels: LabelSelector,
+}
+
+impl AgreementFilter {
+    fn query<'a>(self) -> schema::market_agreement::BoxedQuery<'a, Sqlite> {
+        let mut query = market_agreement.into_boxed();
+
+        match (self.node_id, self.counterparty) {
+            (Some(node_id), Some(counterparty)) => {
+                query = query.filter(
+                    agreement::provider_id
+                        .eq(node_id)
+                        .and(agreement::requestor_id.eq(counterparty))
+                        .or(agreement::requestor_id
+                            .eq(node_id)
+                            .and(agreement::provider_id.eq(counterparty))),
+                )
+            }
+            (Some(node_id), None) | (None, Some(node_id)) => {
+                query = query.filter(
+                    agreement::provider_id
+                        .eq(node_id)
+                        .or(agreement::requestor_id.eq(node_id)),
+                )
+            }
+            (None, None) => (),
+        };
+
+        if let Some(role) = self.role {
+            query = query.filter(agreement::id.like(format!("{}-%", role)));
+        }
+
+        if let Some(subscription_id) = self.subscription_id {
+            query = query.filter(
+                agreement::offer_id
+                    .eq(subscription_id.clone())
+                    .or(agreement::demand_id.eq(subscription_id)),
+            );
+        }
+
+        if let Some(app_session_id) = self.app_session_id {
+            query = query.filter(agreement::session_id.eq(app_session_id))
+        }
+
+        if let Some(state) = self.state {
+            query = query.filter(agreement::state.eq(state));
+        }
+
+        if let Some(before) = self.before {
+            query = query.filter(agreement::creation_ts.lt(before.naive_utc()));
+        }
+
+        if let Some(after) = self.after {
+            query = query.filter(agreement::creation_ts.gt(after.naive_utc()));
+        }
+
+        filter_by_labels(query, self.labels)
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum AgreementDaoError {
     #[error("Can't update Agreement state from {from} to {to}.")]
@@ -61,44 +139,34 @@ pub enum AgreementDaoError {
 }
 
 impl<'c> AgreementDao<'c> {
+    /// Agreements matching filter, the oldest first.
     pub async fn list(
         &self,
-        node_id: Option<NodeId>,
-        state: Option<AgreementState>,
-        before: Option<DateTime<Utc>>,
-        after: Option<DateTime<Utc>>,
-        app_session_id: Option<String>,
+        filter: AgreementFilter,
+        offset: Option<i64>,
+        limit: Option<i64>,
     ) -> Result<Vec<Agreement>, AgreementDaoError> {
-        do_with_transaction(self.pool, "agreement_dao_list", move |conn| {
-            let mut query = market_agreement.into_boxed();
+        readonly_transaction(self.pool, "agreement_dao_list", move |conn| {
+            let mut query = filter
+                .query()
+                .order_by((agreement::creation_ts.asc(), agreement::id.asc()));
 
-            if let Some(node_id) = node_id {
-                query = query.filter(
-                    agreement::provider_id
-                        .eq(node_id)
-                        .or(agreement::requestor_id.eq(node_id)),
-                );
-            };
-
-            if let Some(app_session_id) = app_session_id {
-                query = query.filter(agreement::session_id.eq(app_session_id))
-            }
-
-            if let Some(state) = state {
-                query = query.filter(agreement::state.eq(state));
+            if let Some(offset) = offset {
+                query = query.offset(offset);
             }
-
-            if let Some(before) = before {
-                query = query.filter(agreement::creation_ts.lt(before.naive_utc()));
-            }
-
-            if let Some(after) = after {
-                query = query.filter(agreement::creation_ts.gt(after.naive_utc()));
+            if let Some(limit) = limit {
+                query = query.limit(limit);
             }
 
-            let agreements = query.get_results::<Agreement>(conn)?;
+            Ok(query.get_results::<Agreement>(conn)?)
+        })
+        .await
+    }
 
-            Ok(agreements)
+    /// Number of Agreements matching filter.
+    pub async fn count(&self, filter: AgreementFilter) -> Result<i64, AgreementDaoError> {
+        readonly_transaction(self.pool, "agreement_dao_count", move |conn| {
+            Ok(filter.query().count().get_result(conn)?)
         })
         .await
     }
@@ -399,7 +467,12 @@ impl<'c> AgreementDao<'c> {
                     event::agreement_id.eq_any(agreements_to_clean.clone().select(agreement::id)),
                 );
 
+                let related_labels = market_agreement_label.filter(
+                    label::agreement_id.eq_any(agreements_to_clean.clone().select(agreement::id)),
+                );
+
                 let num_events = diesel::delete(related_events).execute(conn)?;
+                diesel::delete(related_labels).execute(conn)?;
                 let num_agreements = diesel::delete(agreements_to_clean).execute(conn)?;
                 Result::<(usize, usize), DbError>::Ok((num_agreements, num_events))
             })
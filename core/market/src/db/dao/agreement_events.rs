@@ -7,9 +7,10 @@ use ya_persistence::executor::PoolType;
 use ya_persistence::executor::{readonly_transaction, ConnType};
 use ya_persistence::types::AdaptTimestamp;
 
+use crate::db::dao::agreement_labels::filter_by_labels;
 use crate::db::dao::AgreementDaoError;
 use crate::db::model::{Agreement, AgreementEvent, AgreementId, NewAgreementEvent};
-use crate::db::model::{AppSessionId, Owner};
+use crate::db::model::{AppSessionId, LabelSelector, Owner};
 use crate::db::schema::market_agreement::dsl as agreement;
 use crate::db::schema::market_agreement::dsl::market_agreement;
 use crate::db::schema::market_agreement_event::dsl as event;
@@ -33,8 +34,10 @@ impl<'c> AgreementEventsDao<'c> {
         session_id: &AppSessionId,
         max_events: i32,
         after_timestamp: NaiveDateTime,
+        labels: &LabelSelector,
     ) -> DbResult<Vec<AgreementEvent>> {
         let session_id = session_id.clone();
+        let labels = labels.clone();
         let node_id = *node_id;
         readonly_transaction(self.pool, "agreement_events_dao_select", move |conn| {
             // We will get only one Agreement, by using this filter.
@@ -55,6 +58,10 @@ impl<'c> AgreementEventsDao<'c> {
                     select_corresponding_agreement.filter(agreement::session_id.eq(session_id));
             };
 
+            // Optionally filter by Agreement labels.
+            let select_corresponding_agreement =
+                filter_by_labels(select_corresponding_agreement, labels);
+
             Ok(market_agreement_event
                 .filter(event::agreement_id.eq_any(select_corresponding_agreement))
                 .filter(event::timestamp.gt(after_timestamp.adapt()))
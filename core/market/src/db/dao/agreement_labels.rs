@@ -0,0 +1,80 @@
+use diesel::sqlite::Sqlite;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use ya_persistence::executor::PoolType;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction};
+
+use crate::db::model::{AgreementId, AgreementLabel, AgreementLabels, LabelSelector};
+use crate::db::schema::market_agreement;
+use crate::db::schema::market_agreement::dsl as agreement;
+use crate::db::schema::market_agreement_label::dsl as label;
+use crate::db::schema::market_agreement_label::dsl::market_agreement_label;
+use crate::db::{AsMixedDao, DbResult};
+
+pub struct AgreementLabelsDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'a> AsMixedDao<'a> for AgreementLabelsDao<'a> {
+    fn as_dao(disk_pool: &'a PoolType, _ram_pool: &'a PoolType) -> Self {
+        Self { pool: disk_pool }
+    }
+}
+
+impl<'c> AgreementLabelsDao<'c> {
+    pub async fn get(&self, agreement_id: &AgreementId) -> DbResult<AgreementLabels> {
+        let agreement_id = agreement_id.clone();
+        readonly_transaction(self.pool, "agreement_labels_dao_get", move |conn| {
+            Ok(market_agreement_label
+                .filter(label::agreement_id.eq(agreement_id))
+                .load::<AgreementLabel>(conn)?
+                .into_iter()
+                .map(|label| (label.key, label.value))
+                .collect())
+        })
+        .await
+    }
+
+    /// Replaces all labels of Agreement with new set.
+    pub async fn set(&self, agreement_id: &AgreementId, labels: AgreementLabels) -> DbResult<()> {
+        let agreement_id = agreement_id.clone();
+        do_with_transaction(self.pool, "agreement_labels_dao_set", move |conn| {
+            diesel::delete(market_agreement_label.filter(label::agreement_id.eq(&agreement_id)))
+                .execute(conn)?;
+
+            let labels = labels
+                .into_iter()
+                .map(|(key, value)| AgreementLabel {
+                    agreement_id: agreement_id.clone(),
+                    key,
+                    value,
+                })
+                .collect::<Vec<_>>();
+
+            diesel::insert_into(market_agreement_label)
+                .values(&labels)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Narrows Agreements query to Agreements matching all conditions of `selector`.
+pub(crate) fn filter_by_labels<'a, ST>(
+    mut query: market_agreement::BoxedQuery<'a, Sqlite, ST>,
+    selector: LabelSelector,
+) -> market_agreement::BoxedQuery<'a, Sqlite, ST> {
+    for filter in selector.0 {
+        let mut labeled = market_agreement_label
+            .select(label::agreement_id)
+            .filter(label::key.eq(filter.key))
+            .into_boxed();
+
+        if let Some(value) = filter.value {
+            labeled = labeled.filter(label::value.eq(value));
+        }
+        query = query.filter(agreement::id.eq_any(labeled));
+    }
+    query
+}
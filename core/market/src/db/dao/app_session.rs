@@ -0,0 +1,202 @@
+use chrono::Utc;
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use ya_client::model::NodeId;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, ConnType, PoolType};
+
+use crate::config::DbConfig;
+use crate::db::model::{AppSession, SessionSubscription, SubscriptionId};
+use crate::db::schema::market_agreement::dsl as agreement;
+use crate::db::schema::market_agreement::dsl::market_agreement;
+use crate::db::schema::market_app_session::dsl as session;
+use crate::db::schema::market_app_session::dsl::market_app_session;
+use crate::db::schema::market_session_subscription::dsl as binding;
+use crate::db::schema::market_session_subscription::dsl::market_session_subscription;
+use crate::db::{AsMixedDao, DbError, DbResult};
+
+pub struct AppSessionDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'a> AsMixedDao<'a> for AppSessionDao<'a> {
+    fn as_dao(disk_pool: &'a PoolType, _ram_pool: &'a PoolType) -> Self {
+        Self { pool: disk_pool }
+    }
+}
+
+impl<'c> AppSessionDao<'c> {
+    /// Registers session on first use or marks it as active.
+    pub async fn touch(&self, session_id: &str, node_id: NodeId) -> DbResult<()> {
+        let session_id = session_id.to_string();
+        do_with_transaction(self.pool, "app_session_dao_touch", move |conn| {
+            touch(conn, &session_id, node_id)
+        })
+        .await
+    }
+
+    /// Subscription will be visible only in scope of this session.
+    pub async fn bind(
+        &self,
+        session_id: &str,
+        node_id: NodeId,
+        subscription_id: &SubscriptionId,
+    ) -> DbResult<()> {
+        let entry = SessionSubscription {
+            subscription_id: subscription_id.clone(),
+            node_id,
+            session_id: session_id.to_string(),
+        };
+        do_with_transaction(self.pool, "app_session_dao_bind", move |conn| {
+            touch(conn, &entry.session_id, node_id)?;
+            diesel::replace_into(market_session_subscription)
+                .values(&entry)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Session in which subscription was created.
+    pub async fn session_of(&self, subscription_id: &SubscriptionId) -> DbResult<Option<String>> {
+        let subscription_id = subscription_id.clone();
+        readonly_transaction(self.pool, "app_session_dao_session_of", move |conn| {
+            Ok(market_session_subscription
+                .select(binding::session_id)
+                .filter(binding::subscription_id.eq(subscription_id))
+                .first(conn)
+                .optional()?)
+        })
+        .await
+    }
+
+    /// Sessions of node with their subscriptions and number of Agreements.
+    pub async fn list(
+        &self,
+        node_id: NodeId,
+    ) -> DbResult<Vec<(AppSession, Vec<SubscriptionId>, i64)>> {
+        readonly_transaction(self.pool, "app_session_dao_list", move |conn| {
+            market_app_session
+                .filter(session::node_id.eq(node_id))
+                .order_by(session::created_ts.asc())
+                .load::<AppSession>(conn)?
+                .into_iter()
+                .map(|app_session| {
+                    let subscriptions = subscriptions(conn, &app_session.id, node_id)?;
+                    let agreements = market_agreement
+                        .filter(agreement::session_id.eq(&app_session.id))
+                        .filter(
+                            agreement::provider_id
+                                .eq(node_id)
+                                .or(agreement::requestor_id.eq(node_id)),
+                        )
+                        .count()
+                        .get_result::<i64>(conn)?;
+                    Ok::<_, DbError>((app_session, subscriptions, agreements))
+                })
+                .collect()
+        })
+        .await
+    }
+
+    /// Removes session. Returns subscriptions created in its scope
+    /// or `None`, if there was no such session.
+    pub async fn remove(
+        &self,
+        session_id: &str,
+        node_id: NodeId,
+    ) -> DbResult<Option<Vec<SubscriptionId>>> {
+        let session_id = session_id.to_string();
+        do_with_transaction(self.pool, "app_session_dao_remove", move |conn| {
+            let subscriptions = subscriptions(conn, &session_id, node_id)?;
+            diesel::delete(
+                market_session_subscription
+                    .filter(binding::node_id.eq(node_id))
+                    .filter(binding::session_id.eq(&session_id)),
+            )
+            .execute(conn)?;
+            let removed = diesel::delete(
+                market_app_session
+                    .filter(session::node_id.eq(node_id))
+                    .filter(session::id.eq(&session_id)),
+            )
+            .execute(conn)?;
+            Ok(if removed > 0 {
+                Some(subscriptions)
+            } else {
+                None
+            })
+        })
+        .await
+    }
+
+    pub async fn clean(&self, db_config: &DbConfig) -> DbResult<()> {
+        log::trace!("Clean market app sessions: start");
+        let inactive_since = Utc::now().naive_utc() - db_config.session_ttl;
+        let num_sessions = do_with_transaction(self.pool, "app_session_dao_clean", move |conn| {
+            // Session ids are chosen by applications, so they are unique only per node.
+            let expired: Vec<(NodeId, String)> = market_app_session
+                .select((session::node_id, session::id))
+                .filter(session::last_active_ts.lt(inactive_since))
+                .load(conn)?;
+
+            let num_sessions = expired.len();
+            for (node_id, session_id) in expired {
+                diesel::delete(
+                    market_session_subscription
+                        .filter(binding::node_id.eq(node_id))
+                        .filter(binding::session_id.eq(&session_id)),
+                )
+                .execute(conn)?;
+                diesel::delete(
+                    market_app_session
+                        .filter(session::node_id.eq(node_id))
+                        .filter(session::id.eq(&session_id)),
+                )
+                .execute(conn)?;
+            }
+            Result::<usize, DbError>::Ok(num_sessions)
+        })
+        .await?;
+
+        if num_sessions > 0 {
+            log::info!("Cleaned {} inactive market app sessions", num_sessions);
+        }
+        log::trace!("Clean market app sessions: done");
+        Ok(())
+    }
+}
+
+fn touch(conn: &ConnType, session_id: &str, node_id: NodeId) -> DbResult<()> {
+    let now = Utc::now().naive_utc();
+    let updated = diesel::update(
+        market_app_session
+            .filter(session::node_id.eq(node_id))
+            .filter(session::id.eq(session_id)),
+    )
+    .set(session::last_active_ts.eq(now))
+    .execute(conn)?;
+
+    if updated == 0 {
+        diesel::insert_into(market_app_session)
+            .values(AppSession {
+                id: session_id.to_string(),
+                node_id,
+                created_ts: now,
+                last_active_ts: now,
+            })
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+fn subscriptions(
+    conn: &ConnType,
+    session_id: &str,
+    node_id: NodeId,
+) -> DbResult<Vec<SubscriptionId>> {
+    Ok(market_session_subscription
+        .select(binding::subscription_id)
+        .filter(binding::node_id.eq(node_id))
+        .filter(binding::session_id.eq(session_id))
+        .load(conn)?)
+}
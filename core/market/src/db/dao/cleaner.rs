@@ -2,7 +2,9 @@ use futures::join;
 use tokio::time;
 
 use crate::config::DbConfig;
-use crate::db::dao::{AgreementDao, DemandDao, NegotiationEventsDao, OfferDao, ProposalDao};
+use crate::db::dao::{
+    AgreementDao, AppSessionDao, DemandDao, NegotiationEventsDao, OfferDao, ProposalDao, WebhookDao,
+};
 use crate::db::DbMixedExecutor;
 
 pub async fn clean(db: DbMixedExecutor, cfg: &DbConfig) {
@@ -11,6 +13,8 @@ pub async fn clean(db: DbMixedExecutor, cfg: &DbConfig) {
     let offer_db = db.clone();
     let agreement_db = db.clone();
     let proposal_db = db.clone();
+    let session_db = db.clone();
+    let webhook_db = db.clone();
 
     let results = join!(
         async move { demand_db.as_dao::<DemandDao>().clean().await },
@@ -18,8 +22,12 @@ pub async fn clean(db: DbMixedExecutor, cfg: &DbConfig) {
         async move { agreement_db.as_dao::<AgreementDao>().clean(cfg).await },
         async move { proposal_db.as_dao::<ProposalDao>().clean().await },
         async move { events_db.as_dao::<NegotiationEventsDao>().clean(cfg).await },
+        async move { session_db.as_dao::<AppSessionDao>().clean(cfg).await },
+        async move { webhook_db.as_dao::<WebhookDao>().clean(cfg).await },
     );
-    let v_results = vec![results.0, results.1, results.2, results.3, results.4];
+    let v_results = vec![
+        results.0, results.1, results.2, results.3, results.4, results.5, results.6,
+    ];
     for db_result in v_results.into_iter() {
         if let Err(e) = db_result {
             log::error!("Market database cleaner error: {}", e)
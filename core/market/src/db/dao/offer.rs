@@ -7,9 +7,11 @@ use ya_client::model::NodeId;
 use ya_persistence::executor::{do_with_transaction, readonly_transaction, ConnType, PoolType};
 
 use crate::db::model::SubscriptionId;
-use crate::db::model::{Offer, OfferUnsubscribed};
+use crate::db::model::{Offer, OfferOrigin, OfferUnsubscribed};
 use crate::db::schema::market_offer::dsl as offer;
 use crate::db::schema::market_offer::dsl::market_offer;
+use crate::db::schema::market_offer_origin::dsl as origin;
+use crate::db::schema::market_offer_origin::dsl::market_offer_origin;
 use crate::db::schema::market_offer_unsubscribed::dsl as unsubscribed;
 use crate::db::schema::market_offer_unsubscribed::dsl::market_offer_unsubscribed;
 use crate::db::{AsMixedDao, DbError, DbResult};
@@ -73,7 +75,7 @@ impl<'c> OfferDao<'c> {
         .await
     }
 
-    /// Returns Offers for given criteria.
+    /// Returns public Offers for given criteria.
     pub async fn get_scan_offers(
         &self,
         inserted_after_ts: Option<NaiveDateTime>,
@@ -81,8 +83,9 @@ impl<'c> OfferDao<'c> {
         limit: Option<i64>,
     ) -> DbResult<Vec<Offer>> {
         readonly_transaction(self.pool, "get_scan_offers", move |conn| {
-            let mut query =
-                active_market_offers(expiry_validation_ts).order_by(offer::insertion_ts.asc());
+            let mut query = active_market_offers(expiry_validation_ts)
+                .filter(offer::private.eq(false))
+                .order_by(offer::insertion_ts.asc());
 
             if let Some(limit) = limit {
                 query = query.limit(limit);
@@ -134,7 +137,8 @@ impl<'c> OfferDao<'c> {
         readonly_transaction(self.pool, "offer_dao_query_offers", move |conn| {
             //let max_ts : Option<NaiveDateTime> = active_market_offers(expiry_validation_ts).select(offer::insertion_ts.max()).get_result(conn).optional()?;
 
-            let mut query = active_market_offers(expiry_validation_ts);
+            let mut query =
+                active_market_offers(expiry_validation_ts).filter(offer::private.eq(false));
             if let Some(after_insert_ts) = after_insert_ts {
                 query = query.filter(offer::insertion_ts.gt(after_insert_ts));
             }
@@ -159,7 +163,7 @@ impl<'c> OfferDao<'c> {
         .await
     }
 
-    /// Returns Offer ids for given `node_ids` or all.
+    /// Returns public Offer ids for given `node_ids` or all.
     pub async fn get_offer_ids(
         &self,
         node_ids: Option<Vec<NodeId>>,
@@ -169,6 +173,7 @@ impl<'c> OfferDao<'c> {
             let mut query = market_offer
                 .select(offer::id)
                 .filter(offer::expiration_ts.ge(expiry_validation_ts))
+                .filter(offer::private.eq(false))
                 .filter(
                     offer::id.ne_all(
                         market_offer_unsubscribed
@@ -270,6 +275,28 @@ impl<'c> OfferDao<'c> {
         .await
     }
 
+    /// Stores node, from which Offer was retrieved. First known origin is kept.
+    pub async fn put_origin(&self, entry: OfferOrigin) -> DbResult<()> {
+        do_with_transaction(self.pool, "offer_dao_put_origin", move |conn| {
+            diesel::insert_or_ignore_into(market_offer_origin)
+                .values(entry)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn get_origin(&self, id: &SubscriptionId) -> DbResult<Option<OfferOrigin>> {
+        let id = id.clone();
+        readonly_transaction(self.pool, "offer_dao_get_origin", move |conn| {
+            Ok(market_offer_origin
+                .filter(origin::id.eq(&id))
+                .first(conn)
+                .optional()?)
+        })
+        .await
+    }
+
     /// Inserts Offer unsubscription marker.
     /// Returns Offer state as before operation
     /// (`Active` means unsubscription has succeeded).
@@ -292,6 +319,54 @@ impl<'c> OfferDao<'c> {
         .await?
     }
 
+    /// Extends expiration of active Offer.
+    /// Returns pair `(true, Active(offer))` with refreshed Offer on success,
+    /// or `(false, offer_state)`, if Offer isn't active or new expiration isn't later.
+    pub async fn refresh(
+        &self,
+        id: &SubscriptionId,
+        expiration_ts: NaiveDateTime,
+        expiry_validation_ts: NaiveDateTime,
+    ) -> DbResult<(bool, OfferState)> {
+        let id = id.clone();
+        do_with_transaction(self.pool, "offer_dao_refresh", move |conn| {
+            let mut offer = match query_state(conn, &id, &expiry_validation_ts)? {
+                OfferState::Active(offer) if expiration_ts > offer.expiration_ts => offer,
+                state => return Ok((false, state)),
+            };
+
+            offer.refresh(expiration_ts);
+            diesel::update(market_offer.filter(offer::id.eq(&id)))
+                .set((
+                    offer::expiration_ts.eq(offer.expiration_ts),
+                    offer::subscribed_expiration_ts.eq(offer.subscribed_expiration_ts),
+                ))
+                .execute(conn)?;
+            Ok((true, OfferState::Active(offer)))
+        })
+        .await
+    }
+
+    /// Returns active refreshed public Offers for given `node_ids` or all.
+    pub async fn get_refreshed_offers(
+        &self,
+        node_ids: Option<Vec<NodeId>>,
+        expiry_validation_ts: NaiveDateTime,
+    ) -> DbResult<Vec<Offer>> {
+        readonly_transaction(self.pool, "offer_dao_get_refreshed_offers", move |conn| {
+            let mut query = active_market_offers(expiry_validation_ts)
+                .filter(offer::subscribed_expiration_ts.is_not_null())
+                .filter(offer::private.eq(false));
+
+            if let Some(ids) = node_ids {
+                query = query.filter(offer::node_id.eq_any(ids));
+            };
+
+            Ok(query.load(conn)?)
+        })
+        .await
+    }
+
     /// Deletes single Offer.
     /// Returns `true` on success.
     pub async fn delete(&self, id: &SubscriptionId) -> DbResult<bool> {
@@ -310,6 +385,8 @@ impl<'c> OfferDao<'c> {
         let num_deleted = do_with_transaction(self.pool, "offer_dao_clean", move |conn| {
             let nd = diesel::delete(market_offer.filter(offer::expiration_ts.lt(sql_now)))
                 .execute(conn)?;
+            diesel::delete(market_offer_origin.filter(origin::expiration_ts.lt(sql_now)))
+                .execute(conn)?;
             Result::<usize, DbError>::Ok(nd)
         })
         .await?;
@@ -0,0 +1,51 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, PoolType};
+
+use crate::db::model::OfferFilterEntry;
+use crate::db::schema::market_offer_filter::dsl as filter;
+use crate::db::schema::market_offer_filter::dsl::market_offer_filter;
+use crate::db::{AsMixedDao, DbResult};
+
+pub struct OfferFilterDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'a> AsMixedDao<'a> for OfferFilterDao<'a> {
+    fn as_dao(disk_pool: &'a PoolType, _ram_pool: &'a PoolType) -> Self {
+        Self { pool: disk_pool }
+    }
+}
+
+impl<'c> OfferFilterDao<'c> {
+    pub async fn list(&self) -> DbResult<Vec<OfferFilterEntry>> {
+        readonly_transaction(self.pool, "offer_filter_dao_list", move |conn| {
+            Ok(market_offer_filter.load(conn)?)
+        })
+        .await
+    }
+
+    pub async fn insert(&self, entry: OfferFilterEntry) -> DbResult<()> {
+        do_with_transaction(self.pool, "offer_filter_dao_insert", move |conn| {
+            diesel::insert_or_ignore_into(market_offer_filter)
+                .values(entry)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Returns false, if there was no such entry.
+    pub async fn remove(&self, entry: OfferFilterEntry) -> DbResult<bool> {
+        do_with_transaction(self.pool, "offer_filter_dao_remove", move |conn| {
+            let removed = diesel::delete(
+                market_offer_filter
+                    .filter(filter::kind.eq(entry.kind))
+                    .filter(filter::value.eq(entry.value)),
+            )
+            .execute(conn)?;
+            Ok(removed > 0)
+        })
+        .await
+    }
+}
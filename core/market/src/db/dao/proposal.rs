@@ -1,10 +1,15 @@
+use chrono::NaiveDateTime;
 use diesel::expression::dsl::now as sql_now;
 use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
 use serde::{Deserialize, Serialize};
 
 use ya_persistence::executor::{do_with_transaction, readonly_transaction, ConnType, PoolType};
 
-use crate::db::model::{DbProposal, Negotiation, Proposal, ProposalId, ProposalState};
+use ya_client::model::NodeId;
+
+use crate::db::model::{
+    DbProposal, Issuer, Negotiation, Proposal, ProposalId, ProposalState, SubscriptionId,
+};
 use crate::db::schema::market_negotiation::dsl as dsl_negotiation;
 use crate::db::schema::market_proposal::dsl;
 use crate::db::{AsMixedDao, DbError, DbResult};
@@ -19,6 +24,8 @@ pub enum SaveProposalError {
     DbInternal(DbError),
     #[error("Proposal [{0}] has no previous proposal. This should not happened when calling save_proposal.")]
     NoPrevious(ProposalId),
+    #[error("Proposal [{0}] expired. Can't counter it.")]
+    Expired(ProposalId),
 }
 
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
@@ -80,6 +87,10 @@ impl<'c> ProposalDao<'c> {
                 .optional()?
                 .ok_or_else(|| SaveProposalError::NoPrevious(proposal.id.clone()))?;
 
+            if prev_proposal.state == ProposalState::Expired {
+                return Err(SaveProposalError::Expired(prev_proposal_id));
+            }
+
             // If previous Proposal was rejected, we must change it's state back.
             if prev_proposal.state == ProposalState::Rejected {
                 // If rejected Proposal doesn't have prev_proposal_id, it was Initial Proposal.
@@ -139,6 +150,176 @@ impl<'c> ProposalDao<'c> {
         .await
     }
 
+    /// Draft Proposals from other side, which are the newest in their negotiations
+    /// on `subscription_id` and didn't expire. Negotiations with `excluded_node` are skipped.
+    /// Returns newest Proposals first.
+    pub async fn list_draft_proposals(
+        &self,
+        subscription_id: &SubscriptionId,
+        excluded_node: NodeId,
+    ) -> DbResult<Vec<Proposal>> {
+        let subscription_id = subscription_id.clone();
+        readonly_transaction(
+            self.pool,
+            "proposal_dao_list_draft_proposals",
+            move |conn| {
+                let negotiations: Vec<Negotiation> = dsl_negotiation::market_negotiation
+                    .filter(dsl_negotiation::subscription_id.eq(&subscription_id))
+                    .filter(dsl_negotiation::provider_id.ne(excluded_node))
+                    .filter(dsl_negotiation::requestor_id.ne(excluded_node))
+                    .load(conn)?;
+
+                let now = chrono::Utc::now().naive_utc();
+                let mut proposals = Vec::new();
+                for negotiation in negotiations {
+                    // Proposal was countered already, if it isn't the last one in negotiation.
+                    let last: Option<DbProposal> = dsl::market_proposal
+                        .filter(dsl::negotiation_id.eq(&negotiation.id))
+                        .order_by(dsl::creation_ts.desc())
+                        .first(conn)
+                        .optional()?;
+
+                    match last {
+                        Some(body)
+                            if body.issuer == Issuer::Them
+                                && body.state == ProposalState::Draft
+                                && body.expiration_ts > now =>
+                        {
+                            proposals.push(Proposal { negotiation, body })
+                        }
+                        _ => (),
+                    }
+                }
+
+                proposals.sort_by(|a, b| b.body.creation_ts.cmp(&a.body.creation_ts));
+                Ok(proposals)
+            },
+        )
+        .await
+    }
+
+    /// Changes state of Draft Proposals created before `created_before`, which weren't
+    /// countered yet, to Expired. Returns expired Proposals.
+    pub async fn expire_drafts(&self, created_before: NaiveDateTime) -> DbResult<Vec<Proposal>> {
+        do_with_transaction(self.pool, "proposal_dao_expire_drafts", move |conn| {
+            use diesel::sql_types::{Text, Timestamp};
+            #[derive(QueryableByName)]
+            struct ExpiredRecord {
+                #[sql_type = "Text"]
+                id: ProposalId,
+            }
+
+            let expired: Vec<ExpiredRecord> = diesel::sql_query(
+                r#"
+            UPDATE market_proposal SET state = ?
+            WHERE state = ? AND creation_ts < ?
+                AND NOT EXISTS (
+                    SELECT 1 FROM market_proposal counter
+                    WHERE counter.prev_proposal_id = market_proposal.id
+                )
+            RETURNING id
+            "#,
+            )
+            .bind::<Text, _>(ProposalState::Expired)
+            .bind::<Text, _>(ProposalState::Draft)
+            .bind::<Timestamp, _>(created_before)
+            .load(conn)?;
+            if expired.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let expired = dsl::market_proposal
+                .inner_join(dsl_negotiation::market_negotiation)
+                .filter(dsl::id.eq_any(expired.into_iter().map(|record| record.id)))
+                .load::<(DbProposal, Negotiation)>(conn)?
+                .into_iter()
+                .map(|(body, negotiation)| Proposal { negotiation, body })
+                .collect();
+            Ok(expired)
+        })
+        .await
+    }
+
+    /// All stored Proposals from negotiations on `subscription_id`.
+    pub async fn list_by_subscription(
+        &self,
+        subscription_id: &SubscriptionId,
+    ) -> DbResult<Vec<Proposal>> {
+        let subscription_id = subscription_id.clone();
+        readonly_transaction(
+            self.pool,
+            "proposal_dao_list_by_subscription",
+            move |conn| {
+                let negotiations: Vec<Negotiation> = dsl_negotiation::market_negotiation
+                    .filter(dsl_negotiation::subscription_id.eq(&subscription_id))
+                    .load(conn)?;
+
+                let bodies: Vec<DbProposal> = dsl::market_proposal
+                    .filter(
+                        dsl::negotiation_id
+                            .eq_any(negotiations.iter().map(|negotiation| &negotiation.id)),
+                    )
+                    .order_by(dsl::creation_ts.asc())
+                    .load(conn)?;
+
+                Ok(bodies
+                    .into_iter()
+                    .filter_map(|body| {
+                        negotiations
+                            .iter()
+                            .find(|negotiation| negotiation.id == body.negotiation_id)
+                            .map(|negotiation| Proposal {
+                                negotiation: negotiation.clone(),
+                                body,
+                            })
+                    })
+                    .collect())
+            },
+        )
+        .await
+    }
+
+    /// Proposals, which led to `proposal_id` by countering each other, starting from
+    /// the Initial Proposal. Traversal stops at the first Proposal removed by cleaner,
+    /// so chains of old negotiations can be incomplete.
+    pub async fn list_proposal_chain(&self, proposal_id: &ProposalId) -> DbResult<Vec<Proposal>> {
+        let proposal_id = proposal_id.to_string();
+        readonly_transaction(self.pool, "proposal_dao_list_proposal_chain", move |conn| {
+            let mut bodies: Vec<DbProposal> = Vec::new();
+            let mut next_id = Some(proposal_id);
+            while let Some(id) = next_id {
+                let body: Option<DbProposal> = dsl::market_proposal
+                    .filter(dsl::id.eq(&id))
+                    .first(conn)
+                    .optional()?;
+                match body {
+                    Some(body) => {
+                        next_id = body.prev_proposal_id.as_ref().map(|id| id.to_string());
+                        bodies.push(body);
+                    }
+                    None => break,
+                }
+            }
+
+            let negotiation: Negotiation = match bodies.first() {
+                Some(body) => dsl_negotiation::market_negotiation
+                    .filter(dsl_negotiation::id.eq(&body.negotiation_id))
+                    .first(conn)?,
+                None => return Ok(vec![]),
+            };
+
+            Ok(bodies
+                .into_iter()
+                .rev()
+                .map(|body| Proposal {
+                    negotiation: negotiation.clone(),
+                    body,
+                })
+                .collect())
+        })
+        .await
+    }
+
     pub async fn clean(&self) -> DbResult<()> {
         log::debug!("Clean market proposals: start");
         loop {
@@ -0,0 +1,238 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use ya_client::model::NodeId;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, PoolType};
+
+use crate::config::DbConfig;
+use crate::db::dao::sql_functions::datetime;
+use crate::db::model::{
+    DeliveryStatus, NewWebhookDelivery, SubscriptionId, Webhook, WebhookDelivery,
+};
+use crate::db::schema::market_webhook::dsl as webhook;
+use crate::db::schema::market_webhook::dsl::market_webhook;
+use crate::db::schema::market_webhook_delivery::dsl as delivery;
+use crate::db::schema::market_webhook_delivery::dsl::market_webhook_delivery;
+use crate::db::{AsMixedDao, DbError, DbResult};
+
+pub struct WebhookDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'a> AsMixedDao<'a> for WebhookDao<'a> {
+    fn as_dao(disk_pool: &'a PoolType, _ram_pool: &'a PoolType) -> Self {
+        Self { pool: disk_pool }
+    }
+}
+
+impl<'c> WebhookDao<'c> {
+    pub async fn insert(&self, entry: Webhook) -> DbResult<()> {
+        do_with_transaction(self.pool, "webhook_dao_insert", move |conn| {
+            diesel::insert_into(market_webhook)
+                .values(&entry)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn get(&self, webhook_id: &str, owner_id: NodeId) -> DbResult<Option<Webhook>> {
+        let webhook_id = webhook_id.to_string();
+        readonly_transaction(self.pool, "webhook_dao_get", move |conn| {
+            Ok(market_webhook
+                .filter(webhook::id.eq(webhook_id))
+                .filter(webhook::owner_id.eq(owner_id))
+                .first(conn)
+                .optional()?)
+        })
+        .await
+    }
+
+    pub async fn list(
+        &self,
+        subscription_id: &SubscriptionId,
+        owner_id: NodeId,
+    ) -> DbResult<Vec<Webhook>> {
+        let subscription_id = subscription_id.clone();
+        readonly_transaction(self.pool, "webhook_dao_list", move |conn| {
+            Ok(market_webhook
+                .filter(webhook::subscription_id.eq(subscription_id))
+                .filter(webhook::owner_id.eq(owner_id))
+                .order_by(webhook::created_ts.asc())
+                .load(conn)?)
+        })
+        .await
+    }
+
+    /// Removes webhook together with its deliveries.
+    /// Returns false, if webhook didn't exist.
+    pub async fn remove(&self, webhook_id: &str, owner_id: NodeId) -> DbResult<bool> {
+        let webhook_id = webhook_id.to_string();
+        do_with_transaction(self.pool, "webhook_dao_remove", move |conn| {
+            let owned = market_webhook
+                .filter(webhook::id.eq(&webhook_id))
+                .filter(webhook::owner_id.eq(owner_id));
+            if diesel::select(diesel::dsl::exists(owned)).get_result::<bool>(conn)? {
+                diesel::delete(
+                    market_webhook_delivery.filter(delivery::webhook_id.eq(&webhook_id)),
+                )
+                .execute(conn)?;
+                diesel::delete(market_webhook.filter(webhook::id.eq(&webhook_id))).execute(conn)?;
+                return Ok(true);
+            }
+            Ok(false)
+        })
+        .await
+    }
+
+    /// Creates pending deliveries of the event for all webhooks of subscription,
+    /// which accept it. Returns deliveries with urls of their webhooks.
+    pub async fn enqueue(
+        &self,
+        subscription_id: &SubscriptionId,
+        event_type: &str,
+        payload: String,
+    ) -> DbResult<Vec<(WebhookDelivery, String)>> {
+        let subscription_id = subscription_id.clone();
+        let event_type = event_type.to_string();
+        do_with_transaction(self.pool, "webhook_dao_enqueue", move |conn| {
+            let webhooks = market_webhook
+                .filter(webhook::subscription_id.eq(subscription_id))
+                .load::<Webhook>(conn)?;
+
+            webhooks
+                .into_iter()
+                .filter(|hook| hook.accepts(&event_type))
+                .map(|hook| {
+                    let entry = NewWebhookDelivery {
+                        id: uuid::Uuid::new_v4().to_simple().to_string(),
+                        webhook_id: hook.id,
+                        event_type: event_type.clone(),
+                        payload: payload.clone(),
+                        status: DeliveryStatus::Pending,
+                        attempts: 0,
+                        created_ts: Utc::now().naive_utc(),
+                    };
+                    diesel::insert_into(market_webhook_delivery)
+                        .values(&entry)
+                        .execute(conn)?;
+                    let created = market_webhook_delivery
+                        .filter(delivery::id.eq(&entry.id))
+                        .first::<WebhookDelivery>(conn)?;
+                    Ok((created, hook.url))
+                })
+                .collect::<Result<Vec<_>, DbError>>()
+        })
+        .await
+    }
+
+    /// Pending deliveries of all webhooks with their urls, oldest first.
+    pub async fn pending(&self) -> DbResult<Vec<(WebhookDelivery, String)>> {
+        readonly_transaction(self.pool, "webhook_dao_pending", move |conn| {
+            Ok(market_webhook_delivery
+                .inner_join(market_webhook)
+                .filter(delivery::status.eq(DeliveryStatus::Pending))
+                .order_by(delivery::created_ts.asc())
+                .select((
+                    crate::db::schema::market_webhook_delivery::all_columns,
+                    webhook::url,
+                ))
+                .load(conn)?)
+        })
+        .await
+    }
+
+    pub async fn deliveries(&self, webhook_id: &str) -> DbResult<Vec<WebhookDelivery>> {
+        let webhook_id = webhook_id.to_string();
+        readonly_transaction(self.pool, "webhook_dao_deliveries", move |conn| {
+            Ok(market_webhook_delivery
+                .filter(delivery::webhook_id.eq(webhook_id))
+                .order_by(delivery::created_ts.desc())
+                .load(conn)?)
+        })
+        .await
+    }
+
+    pub async fn delivery(
+        &self,
+        webhook_id: &str,
+        delivery_id: &str,
+    ) -> DbResult<Option<WebhookDelivery>> {
+        let webhook_id = webhook_id.to_string();
+        let delivery_id = delivery_id.to_string();
+        readonly_transaction(self.pool, "webhook_dao_delivery", move |conn| {
+            Ok(market_webhook_delivery
+                .filter(delivery::id.eq(delivery_id))
+                .filter(delivery::webhook_id.eq(webhook_id))
+                .first(conn)
+                .optional()?)
+        })
+        .await
+    }
+
+    /// Records result of delivery attempt.
+    pub async fn record_attempt(
+        &self,
+        delivery_id: &str,
+        result: Result<NaiveDateTime, String>,
+        give_up: bool,
+    ) -> DbResult<()> {
+        let delivery_id = delivery_id.to_string();
+        do_with_transaction(self.pool, "webhook_dao_record_attempt", move |conn| {
+            let entry = market_webhook_delivery.filter(delivery::id.eq(delivery_id));
+            let attempts = delivery::attempts + 1;
+            match result {
+                Ok(delivered_ts) => diesel::update(entry)
+                    .set((
+                        delivery::status.eq(DeliveryStatus::Delivered),
+                        delivery::attempts.eq(attempts),
+                        delivery::last_error.eq(None::<String>),
+                        delivery::delivered_ts.eq(delivered_ts),
+                    ))
+                    .execute(conn)?,
+                Err(error) => diesel::update(entry)
+                    .set((
+                        delivery::status.eq(match give_up {
+                            true => DeliveryStatus::Failed,
+                            false => DeliveryStatus::Pending,
+                        }),
+                        delivery::attempts.eq(attempts),
+                        delivery::last_error.eq(error),
+                    ))
+                    .execute(conn)?,
+            };
+            Ok(())
+        })
+        .await
+    }
+
+    /// Marks delivery as pending again, so it can be replayed.
+    pub async fn reset(&self, delivery_id: &str) -> DbResult<()> {
+        let delivery_id = delivery_id.to_string();
+        do_with_transaction(self.pool, "webhook_dao_reset", move |conn| {
+            diesel::update(market_webhook_delivery.filter(delivery::id.eq(delivery_id)))
+                .set(delivery::status.eq(DeliveryStatus::Pending))
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn clean(&self, db_config: &DbConfig) -> DbResult<()> {
+        log::trace!("Clean market webhook deliveries: start");
+        let interval_days = db_config.event_store_days;
+        let num_deleted = do_with_transaction(self.pool, "webhook_dao_clean", move |conn| {
+            let nd = diesel::delete(market_webhook_delivery.filter(
+                delivery::created_ts.lt(datetime("NOW", format!("-{} days", interval_days))),
+            ))
+            .execute(conn)?;
+            Result::<usize, DbError>::Ok(nd)
+        })
+        .await?;
+        if num_deleted > 0 {
+            log::info!("Cleaned {} market webhook deliveries", num_deleted);
+        }
+        log::trace!("Clean market webhook deliveries: done");
+        Ok(())
+    }
+}
@@ -1,20 +1,30 @@
 mod agreement;
 mod agreement_events;
+mod agreement_label;
+mod app_session;
 mod demand;
 mod negotiation_events;
 mod offer;
+mod offer_filter;
 mod proposal;
 mod proposal_id;
 mod subscription_id;
+mod webhook;
 
 pub use agreement::{check_transition, Agreement, AgreementId, AgreementState, AppSessionId};
 pub use agreement_events::{AgreementEvent, AgreementEventType, NewAgreementEvent};
+pub use agreement_label::{
+    validate_labels, AgreementLabel, AgreementLabels, LabelError, LabelFilter, LabelSelector,
+};
+pub use app_session::{AppSession, SessionSubscription};
 pub use demand::Demand;
 pub use negotiation_events::{EventError, EventType, MarketEvent};
-pub use offer::{Offer, OfferUnsubscribed};
+pub use offer::{Offer, OfferOrigin, OfferUnsubscribed};
+pub use offer_filter::OfferFilterEntry;
 pub use proposal::{DbProposal, Issuer, Negotiation, Proposal, ProposalState};
 
 pub use proposal_id::{Owner, ProposalId, ProposalIdParseError, ProposalIdValidationError};
 pub use subscription_id::{
     generate_random_id, SubscriptionId, SubscriptionParseError, SubscriptionValidationError,
 };
+pub use webhook::{DeliveryStatus, NewWebhookDelivery, Webhook, WebhookDelivery};
@@ -0,0 +1,154 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::db::model::AgreementId;
+use crate::db::schema::market_agreement_label;
+
+pub const MAX_LABELS: usize = 64;
+pub const MAX_LABEL_KEY_LEN: usize = 63;
+pub const MAX_LABEL_VALUE_LEN: usize = 255;
+
+/// Arbitrary key-value tags attached to Agreement by its owner.
+pub type AgreementLabels = BTreeMap<String, String>;
+
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[table_name = "market_agreement_label"]
+pub struct AgreementLabel {
+    pub agreement_id: AgreementId,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum LabelError {
+    #[error("Too many labels: {0}, at most {1} allowed.")]
+    TooMany(usize, usize),
+    #[error("Invalid label key '{0}'. Expected 1 to {1} characters from [A-Za-z0-9._/-].")]
+    InvalidKey(String, usize),
+    #[error("Value of label '{0}' exceeds {1} characters.")]
+    ValueTooLong(String, usize),
+    #[error("Empty label selector.")]
+    EmptySelector,
+}
+
+/// Single condition of `LabelSelector`. Matches Agreements having label `key`,
+/// optionally with exactly this `value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelFilter {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// Comma separated list of label conditions in form `key` or `key=value`.
+/// Agreement matches selector, if it matches all conditions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LabelSelector(pub Vec<LabelFilter>);
+
+pub fn validate_labels(labels: &AgreementLabels) -> Result<(), LabelError> {
+    if labels.len() > MAX_LABELS {
+        return Err(LabelError::TooMany(labels.len(), MAX_LABELS));
+    }
+
+    for (key, value) in labels {
+        validate_key(key)?;
+        if value.chars().count() > MAX_LABEL_VALUE_LEN {
+            return Err(LabelError::ValueTooLong(key.clone(), MAX_LABEL_VALUE_LEN));
+        }
+    }
+    Ok(())
+}
+
+fn validate_key(key: &str) -> Result<(), LabelError> {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '/' | '-');
+    if key.is_empty() || key.len() > MAX_LABEL_KEY_LEN || !key.chars().all(valid_char) {
+        return Err(LabelError::InvalidKey(key.to_string(), MAX_LABEL_KEY_LEN));
+    }
+    Ok(())
+}
+
+impl LabelSelector {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromStr for LabelFilter {
+    type Err = LabelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = match s.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (s.trim(), None),
+        };
+
+        validate_key(key)?;
+        Ok(LabelFilter {
+            key: key.to_string(),
+            value,
+        })
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = LabelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(LabelError::EmptySelector);
+        }
+
+        Ok(LabelSelector(
+            s.split(',')
+                .map(LabelFilter::from_str)
+                .collect::<Result<Vec<_>, _>>()?,
+        ))
+    }
+}
+
+impl TryFrom<String> for LabelSelector {
+    type Error = LabelError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_label_selector() {
+        let selector = LabelSelector::from_str("job-id=1234, team").unwrap();
+        assert_eq!(
+            selector.0,
+            vec![
+                LabelFilter {
+                    key: "job-id".to_string(),
+                    value: Some("1234".to_string()),
+                },
+                LabelFilter {
+                    key: "team".to_string(),
+                    value: None,
+                },
+            ]
+        );
+
+        assert!(LabelSelector::from_str("").is_err());
+        assert!(LabelSelector::from_str("job id=1").is_err());
+        assert!(LabelSelector::from_str("job=1,").is_err());
+    }
+
+    #[test]
+    fn test_validate_labels() {
+        let mut labels = AgreementLabels::new();
+        labels.insert("golem.io/job".to_string(), "1".to_string());
+        assert!(validate_labels(&labels).is_ok());
+
+        labels.insert("job=".to_string(), "1".to_string());
+        assert!(validate_labels(&labels).is_err());
+    }
+}
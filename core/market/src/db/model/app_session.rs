@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+
+use ya_client::model::NodeId;
+
+use crate::db::model::SubscriptionId;
+use crate::db::schema::{market_app_session, market_session_subscription};
+
+/// Session of application using market (`appSessionId`). Sessions are created
+/// on first use and removed after period of inactivity.
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[table_name = "market_app_session"]
+pub struct AppSession {
+    pub id: String,
+    pub node_id: NodeId,
+
+    pub created_ts: NaiveDateTime,
+    pub last_active_ts: NaiveDateTime,
+}
+
+/// Subscription created in scope of session. Its negotiation events are
+/// visible only in this session.
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[table_name = "market_session_subscription"]
+pub struct SessionSubscription {
+    pub subscription_id: SubscriptionId,
+    pub node_id: NodeId,
+    pub session_id: String,
+}
@@ -6,7 +6,7 @@ use ya_service_api_web::middleware::Identity;
 
 use super::SubscriptionId;
 use crate::db::model::subscription_id::SubscriptionValidationError;
-use crate::db::schema::{market_offer, market_offer_unsubscribed};
+use crate::db::schema::{market_offer, market_offer_origin, market_offer_unsubscribed};
 use ya_client::model::market::NewOffer;
 
 #[derive(Clone, Debug, Identifiable, Insertable, Queryable, Deserialize, Serialize)]
@@ -21,8 +21,16 @@ pub struct Offer {
     pub creation_ts: NaiveDateTime,
     /// Timestamp of adding this Offer to database.
     pub insertion_ts: Option<NaiveDateTime>,
-    /// Time when Offer expires; set by Provider.
+    /// Time when Offer expires; set by Provider. Can be extended by refreshing Offer.
     pub expiration_ts: NaiveDateTime,
+    /// Expiration set at subscription, which Offer id hash was computed from.
+    /// Set only for refreshed Offers. Not sent to other nodes.
+    #[serde(skip)]
+    pub subscribed_expiration_ts: Option<NaiveDateTime>,
+    /// Private Offer isn't broadcasted nor matched with Demands. Requestor
+    /// can negotiate it only directly, knowing its subscription id.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub private: bool,
 }
 
 /// Keeps track of Offers, that were already unsubscribed.
@@ -42,6 +50,19 @@ pub struct OfferUnsubscribed {
     pub expiration_ts: NaiveDateTime,
 }
 
+/// Node, from which foreign Offer was retrieved. Kept until Offer expiration
+/// to be able to diagnose Offers propagation.
+#[derive(Clone, Debug, Identifiable, Insertable, Queryable)]
+#[table_name = "market_offer_origin"]
+pub struct OfferOrigin {
+    pub id: SubscriptionId,
+    pub received_from: NodeId,
+
+    /// Timestamp of adding this entry to database.
+    pub received_ts: Option<NaiveDateTime>,
+    pub expiration_ts: NaiveDateTime,
+}
+
 impl Offer {
     /// Creates new model offer. If ClientOffer has id already assigned,
     /// it will be ignored and regenerated.
@@ -75,6 +96,8 @@ impl Offer {
             creation_ts,
             insertion_ts: None, // Database will insert this timestamp.
             expiration_ts,
+            subscribed_expiration_ts: None,
+            private: false,
         })
     }
 
@@ -93,6 +116,15 @@ impl Offer {
         })
     }
 
+    pub fn origin(&self, received_from: NodeId) -> OfferOrigin {
+        OfferOrigin {
+            id: self.id.clone(),
+            received_from,
+            received_ts: None,
+            expiration_ts: self.expiration_ts,
+        }
+    }
+
     pub fn into_unsubscribe(self) -> OfferUnsubscribed {
         OfferUnsubscribed {
             id: self.id,
@@ -110,9 +142,25 @@ impl Offer {
             &self.constraints,
             &self.node_id,
             &self.creation_ts,
-            &self.expiration_ts,
+            &self.subscribed_expiration_ts.unwrap_or(self.expiration_ts),
         )
     }
+
+    /// Extends Offer expiration, keeping the one covered by Offer id hash.
+    pub fn refresh(&mut self, expiration_ts: NaiveDateTime) {
+        self.subscribed_expiration_ts
+            .get_or_insert(self.expiration_ts);
+        self.expiration_ts = expiration_ts;
+    }
+
+    /// Offer in the form it was subscribed, which other nodes can validate.
+    /// Refreshed expiration is propagated separately.
+    pub fn into_subscribed(mut self) -> Offer {
+        if let Some(expiration_ts) = self.subscribed_expiration_ts.take() {
+            self.expiration_ts = expiration_ts;
+        }
+        self
+    }
 }
 
 /// PartialEq implementation that ignores insertion_ts.
@@ -154,6 +202,8 @@ mod tests {
                 NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
                 NaiveTime::from_hms_opt(15, 1, 1).unwrap(),
             ),
+            subscribed_expiration_ts: None,
+            private: false,
         };
         assert!(offer.validate().is_err());
     }
@@ -177,6 +227,8 @@ mod tests {
                 NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
                 NaiveTime::from_hms_opt(15, 1, 1).unwrap(),
             ),
+            subscribed_expiration_ts: None,
+            private: false,
         };
         let id = SubscriptionId::generate_id(
             &offer.properties,
@@ -190,5 +242,38 @@ mod tests {
         offer.validate().unwrap();
     }
 
+    #[test]
+    fn test_refreshed_offer_validation() {
+        let offer_id = "c76161077d0343ab85ac986eb5f6ea38-85fdde1924371f4a3a412748f61e5b941c500ea69a55a5135b886a2bffcb8e55";
+        let node_id = "0xbabe000000000000000000000000000000000000";
+        let expiration_ts = NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+            NaiveTime::from_hms_opt(15, 1, 1).unwrap(),
+        );
+
+        let mut offer = Offer {
+            id: SubscriptionId::from_str(offer_id).unwrap(),
+            properties: "{}".to_string(),
+            constraints: "()".to_string(),
+            node_id: NodeId::from_str(node_id).unwrap(),
+            creation_ts: NaiveDateTime::new(
+                NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                NaiveTime::from_hms_opt(0, 1, 1).unwrap(),
+            ),
+            insertion_ts: None,
+            expiration_ts,
+            subscribed_expiration_ts: None,
+            private: false,
+        };
+        offer.refresh(expiration_ts + chrono::Duration::hours(1));
+        offer.refresh(expiration_ts + chrono::Duration::hours(2));
+        assert_eq!(offer.subscribed_expiration_ts, Some(expiration_ts));
+        offer.validate().unwrap();
+
+        let offer = offer.into_subscribed();
+        assert_eq!(offer.expiration_ts, expiration_ts);
+        offer.validate().unwrap();
+    }
+
     // TODO: test from_new
 }
@@ -0,0 +1,46 @@
+use std::convert::TryFrom;
+
+use ya_core_model::market::OfferFilterRule;
+
+use crate::db::schema::market_offer_filter;
+
+const IDENTITY: &str = "identity";
+const SUBNET: &str = "subnet";
+
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[table_name = "market_offer_filter"]
+pub struct OfferFilterEntry {
+    pub kind: String,
+    pub value: String,
+}
+
+impl From<OfferFilterRule> for OfferFilterEntry {
+    fn from(rule: OfferFilterRule) -> Self {
+        match rule {
+            OfferFilterRule::Identity(node_id) => OfferFilterEntry {
+                kind: IDENTITY.to_string(),
+                value: node_id.to_string(),
+            },
+            OfferFilterRule::Subnet(subnet) => OfferFilterEntry {
+                kind: SUBNET.to_string(),
+                value: subnet,
+            },
+        }
+    }
+}
+
+impl TryFrom<OfferFilterEntry> for OfferFilterRule {
+    type Error = String;
+
+    fn try_from(entry: OfferFilterEntry) -> Result<Self, Self::Error> {
+        match entry.kind.as_str() {
+            IDENTITY => entry
+                .value
+                .parse()
+                .map(OfferFilterRule::Identity)
+                .map_err(|e| format!("Invalid filtered identity {}: {}", entry.value, e)),
+            SUBNET => Ok(OfferFilterRule::Subnet(entry.value)),
+            kind => Err(format!("Unknown Offer filter kind: {}", kind)),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::NaiveDateTime;
+use diesel::sql_types::Text;
+use serde::Serialize;
+
+use ya_client::model::NodeId;
+use ya_diesel_utils::DbTextField;
+
+use crate::db::model::SubscriptionId;
+use crate::db::schema::{market_webhook, market_webhook_delivery};
+
+/// Callback URL, to which events of subscription are POSTed.
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[table_name = "market_webhook"]
+pub struct Webhook {
+    pub id: String,
+    pub subscription_id: SubscriptionId,
+    pub owner_id: NodeId,
+    pub url: String,
+    /// Comma separated event types. Empty means all events.
+    pub event_types: String,
+
+    pub created_ts: NaiveDateTime,
+}
+
+impl Webhook {
+    pub fn accepts(&self, event_type: &str) -> bool {
+        self.event_types.is_empty() || self.event_types.split(',').any(|t| t == event_type)
+    }
+}
+
+#[derive(
+    DbTextField,
+    strum_macros::EnumString,
+    strum_macros::Display,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    PartialEq,
+    Eq,
+    Debug,
+    Clone,
+    Copy,
+)]
+#[sql_type = "Text"]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// Single event sent to webhook.
+#[derive(Clone, Debug, Queryable)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event_type: String,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+
+    pub created_ts: NaiveDateTime,
+    pub delivered_ts: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[table_name = "market_webhook_delivery"]
+pub struct NewWebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event_type: String,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+
+    pub created_ts: NaiveDateTime,
+}
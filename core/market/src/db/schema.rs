@@ -21,6 +21,25 @@ table! {
         creation_ts -> Timestamp,
         insertion_ts -> Nullable<Timestamp>,
         expiration_ts -> Timestamp,
+        subscribed_expiration_ts -> Nullable<Timestamp>,
+        private -> Bool,
+    }
+}
+
+table! {
+    market_offer_filter (kind, value) {
+        kind -> Text,
+        value -> Text,
+    }
+}
+
+table! {
+    market_offer_origin (id) {
+        id -> Text,
+        received_from -> Text,
+
+        received_ts -> Nullable<Timestamp>,
+        expiration_ts -> Timestamp,
     }
 }
 
@@ -121,11 +140,78 @@ table! {
     }
 }
 
-allow_tables_to_appear_in_same_query!(market_demand, market_offer, market_offer_unsubscribed);
+table! {
+    market_agreement_label (agreement_id, key) {
+        agreement_id -> Text,
+        key -> Text,
+        value -> Text,
+    }
+}
+
+table! {
+    market_app_session (node_id, id) {
+        id -> Text,
+        node_id -> Text,
+
+        created_ts -> Timestamp,
+        last_active_ts -> Timestamp,
+    }
+}
+
+table! {
+    market_session_subscription (subscription_id) {
+        subscription_id -> Text,
+        node_id -> Text,
+        session_id -> Text,
+    }
+}
+
+table! {
+    market_webhook (id) {
+        id -> Text,
+        subscription_id -> Text,
+        owner_id -> Text,
+        url -> Text,
+        event_types -> Text,
+
+        created_ts -> Timestamp,
+    }
+}
+
+table! {
+    market_webhook_delivery (id) {
+        id -> Text,
+        webhook_id -> Text,
+        event_type -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Integer,
+        last_error -> Nullable<Text>,
+
+        created_ts -> Timestamp,
+        delivered_ts -> Nullable<Timestamp>,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(
+    market_demand,
+    market_offer,
+    market_offer_origin,
+    market_offer_unsubscribed
+);
 allow_tables_to_appear_in_same_query!(market_proposal, market_negotiation);
-allow_tables_to_appear_in_same_query!(market_agreement, market_agreement_event);
+allow_tables_to_appear_in_same_query!(market_webhook, market_webhook_delivery);
+allow_tables_to_appear_in_same_query!(
+    market_agreement,
+    market_agreement_event,
+    market_agreement_label,
+    market_app_session,
+    market_session_subscription
+);
 
 joinable!(market_agreement_event -> market_agreement (agreement_id));
+joinable!(market_agreement_label -> market_agreement (agreement_id));
 joinable!(market_negotiation -> market_agreement (agreement_id));
 joinable!(market_offer -> market_offer_unsubscribed (id));
 joinable!(market_proposal -> market_negotiation (negotiation_id));
+joinable!(market_webhook_delivery -> market_webhook (webhook_id));
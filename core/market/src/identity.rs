@@ -16,6 +16,8 @@ pub enum IdentityError {
     NoDefaultId,
     #[error("Can't list identities. Error: {0}.")]
     ListError(String),
+    #[error("Can't get metadata of identity. Error: {0}.")]
+    MetadataError(String),
 }
 
 /// Wraps calls to identity module. It is necessary to mock identity in tests.
@@ -23,6 +25,8 @@ pub enum IdentityError {
 pub trait IdentityApi: Send + Sync {
     async fn default_identity(&self) -> Result<NodeId, IdentityError>;
     async fn list(&self) -> Result<Vec<NodeId>, IdentityError>;
+    /// Signed metadata document of the identity, if its operator set one.
+    async fn metadata(&self, node_id: NodeId) -> Result<Option<String>, IdentityError>;
 }
 
 pub struct IdentityGSB;
@@ -31,7 +35,7 @@ pub struct IdentityGSB;
 impl IdentityApi for IdentityGSB {
     async fn default_identity(&self) -> Result<NodeId, IdentityError> {
         Ok(bus::service(identity::BUS_ID)
-            .send(identity::Get::ByDefault)
+            .send(identity::Get::ByModule(identity::Module::Market))
             .await
             .map_err(|e| IdentityError::GsbError(e.to_string()))?
             .map_err(|e| IdentityError::GetDefaultIdError(e.to_string()))?
@@ -49,6 +53,14 @@ impl IdentityApi for IdentityGSB {
             .map(|identity_info| identity_info.node_id)
             .collect::<Vec<NodeId>>())
     }
+
+    async fn metadata(&self, node_id: NodeId) -> Result<Option<String>, IdentityError> {
+        bus::service(identity::BUS_ID)
+            .send(identity::GetMetadata(node_id))
+            .await
+            .map_err(|e| IdentityError::GsbError(e.to_string()))?
+            .map_err(|e| IdentityError::MetadataError(e.to_string()))
+    }
 }
 
 #[allow(clippy::new_ret_no_self)]
@@ -15,10 +15,12 @@ use ya_service_api_interfaces::{Provider, Service};
 use ya_service_api_web::middleware::Identity;
 use ya_service_api_web::scope::ExtendableScope;
 
-use super::db::model::AgreementState;
 use crate::config::Config;
-use crate::db::dao::AgreementDao;
-use crate::db::model::{AgreementId, AppSessionId, Owner, SubscriptionId};
+use crate::db::dao::{AgreementDao, AgreementFilter, AgreementLabelsDao};
+use crate::db::model::{
+    validate_labels, AgreementId, AgreementLabels, AppSessionId, LabelSelector, Owner,
+    SubscriptionId,
+};
 use crate::db::DbMixedExecutor;
 use crate::identity::{IdentityApi, IdentityGSB};
 use crate::matcher::error::{
@@ -29,10 +31,20 @@ use crate::matcher::{store::SubscriptionStore, Matcher};
 use crate::negotiation::error::{
     AgreementError, AgreementEventsError, NegotiationError, NegotiationInitError,
 };
-use crate::negotiation::{EventNotifier, ProviderBroker, RequestorBroker, ScannerSet};
+use crate::negotiation::webhook::WebhookDispatcher;
+use crate::negotiation::{
+    expire_proposals_forever, EventNotifier, ProviderBroker, RequestorBroker, ScannerSet,
+};
 use crate::rest_api;
 
 pub mod agreement;
+pub mod blacklist;
+pub mod direct;
+pub mod inspect;
+pub mod metadata;
+pub mod session;
+pub mod stats;
+pub mod webhook;
 
 #[derive(Error, Debug)]
 pub enum MarketError {
@@ -48,6 +60,14 @@ pub enum MarketError {
     DemandError(#[from] DemandError),
     #[error(transparent)]
     Negotiation(#[from] NegotiationError),
+    #[error(transparent)]
+    Session(#[from] session::SessionError),
+    #[error(transparent)]
+    Webhook(#[from] webhook::WebhookError),
+    #[error(transparent)]
+    Blacklist(#[from] blacklist::BlacklistError),
+    #[error(transparent)]
+    DirectNegotiation(#[from] direct::DirectNegotiationError),
 }
 
 #[derive(Error, Debug)]
@@ -113,6 +133,15 @@ impl MarketService {
             agreement_notifier,
             config.clone(),
         )?;
+        if let Some(ttl) = config.negotiation.proposal_ttl {
+            tokio::spawn(expire_proposals_forever(
+                provider_engine.common.clone(),
+                requestor_engine.common.clone(),
+                ttl,
+                config.negotiation.proposal_expiry_interval,
+            ));
+        }
+
         let cleaner_db = db.clone();
         tokio::spawn(async move {
             crate::db::dao::cleaner::clean_forever(cleaner_db, config.db.clone()).await;
@@ -140,6 +169,10 @@ impl MarketService {
             .bind_gsb(public_prefix, local_prefix)
             .await?;
         agreement::bind_gsb(self.db.clone(), public_prefix, local_prefix).await;
+        stats::bind_gsb(self.db.clone(), public_prefix, local_prefix).await;
+        WebhookDispatcher::new(self.db.clone())
+            .resume_pending()
+            .await;
         Ok(())
     }
 
@@ -200,6 +233,19 @@ impl MarketService {
         Ok(offer.id)
     }
 
+    /// Subscribes Offer, which Requestors can negotiate only directly.
+    pub async fn subscribe_private_offer(
+        &self,
+        offer: &NewOffer,
+        id: &Identity,
+    ) -> Result<SubscriptionId, MarketError> {
+        let offer = self.matcher.subscribe_private_offer(offer, id).await?;
+        self.provider_engine.subscribe_offer(&offer).await?;
+
+        counter!("market.offers.subscribed.private", 1);
+        Ok(offer.id)
+    }
+
     pub async fn unsubscribe_offer(
         &self,
         offer_id: &SubscriptionId,
@@ -213,6 +259,21 @@ impl MarketService {
         Ok(())
     }
 
+    /// Extends Offer expiration without changing its subscription id.
+    pub async fn refresh_offer(
+        &self,
+        offer_id: &SubscriptionId,
+        expiration: DateTime<Utc>,
+        id: &Identity,
+    ) -> Result<(), MarketError> {
+        self.matcher
+            .refresh_offer(offer_id, expiration.naive_utc(), id)
+            .await?;
+
+        counter!("market.offers.refreshed", 1);
+        Ok(())
+    }
+
     pub async fn subscribe_demand(
         &self,
         demand: &NewDemand,
@@ -240,18 +301,26 @@ impl MarketService {
         Ok(())
     }
 
+    /// Page of caller's Agreements together with number of all Agreements
+    /// matching the filter.
     pub async fn list_agreements(
         &self,
         id: &Identity,
-        state: Option<AgreementState>,
-        before: Option<DateTime<Utc>>,
-        after: Option<DateTime<Utc>>,
-        app_sesssion_id: Option<String>,
-    ) -> Result<Vec<AgreementListEntry>, AgreementError> {
-        let agreements = self
-            .db
-            .as_dao::<AgreementDao>()
-            .list(Some(id.identity), state, before, after, app_sesssion_id)
+        filter: AgreementFilter,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<(Vec<AgreementListEntry>, i64), AgreementError> {
+        let filter = AgreementFilter {
+            node_id: Some(id.identity),
+            ..filter
+        };
+        let dao = self.db.as_dao::<AgreementDao>();
+        let total = dao
+            .count(filter.clone())
+            .await
+            .map_err(|e| AgreementError::Internal(e.to_string()))?;
+        let agreements = dao
+            .list(filter, offset, limit)
             .await
             .map_err(|e| AgreementError::Internal(e.to_string()))?;
 
@@ -272,7 +341,7 @@ impl MarketService {
             });
         }
 
-        Ok(result)
+        Ok((result, total))
     }
 
     pub async fn get_agreement(
@@ -294,6 +363,35 @@ impl MarketService {
         }
     }
 
+    pub async fn get_agreement_labels(
+        &self,
+        agreement_id: &AgreementId,
+        id: &Identity,
+    ) -> Result<AgreementLabels, AgreementError> {
+        // Checks if Agreement exists and belongs to caller.
+        self.get_agreement(agreement_id, id).await?;
+        self.db
+            .as_dao::<AgreementLabelsDao>()
+            .get(agreement_id)
+            .await
+            .map_err(|e| AgreementError::Internal(e.to_string()))
+    }
+
+    pub async fn set_agreement_labels(
+        &self,
+        agreement_id: &AgreementId,
+        labels: AgreementLabels,
+        id: &Identity,
+    ) -> Result<(), AgreementError> {
+        validate_labels(&labels)?;
+        self.get_agreement(agreement_id, id).await?;
+        self.db
+            .as_dao::<AgreementLabelsDao>()
+            .set(agreement_id, labels)
+            .await
+            .map_err(|e| AgreementError::Internal(e.to_string()))
+    }
+
     pub async fn query_agreement_events(
         &self,
         session_id: &AppSessionId,
@@ -301,11 +399,33 @@ impl MarketService {
         max_events: Option<i32>,
         after_timestamp: DateTime<Utc>,
         id: &Identity,
+    ) -> Result<Vec<ClientAgreementEvent>, AgreementEventsError> {
+        self.query_labeled_agreement_events(
+            session_id,
+            timeout,
+            max_events,
+            after_timestamp,
+            &LabelSelector::default(),
+            id,
+        )
+        .await
+    }
+
+    /// Same as `query_agreement_events`, but returns only events of Agreements
+    /// matching label selector.
+    pub async fn query_labeled_agreement_events(
+        &self,
+        session_id: &AppSessionId,
+        timeout: f32,
+        max_events: Option<i32>,
+        after_timestamp: DateTime<Utc>,
+        labels: &LabelSelector,
+        id: &Identity,
     ) -> Result<Vec<ClientAgreementEvent>, AgreementEventsError> {
         Ok(self
             .requestor_engine
             .common
-            .query_agreement_events(session_id, timeout, max_events, after_timestamp, id)
+            .query_agreement_events(session_id, timeout, max_events, after_timestamp, labels, id)
             .await?
             .into_iter()
             .map(|event| event.into_client())
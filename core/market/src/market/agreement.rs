@@ -3,8 +3,8 @@ use ya_client::model::market::{Agreement as ClientAgreement, AgreementListEntry,
 use ya_core_model::market::{GetAgreement, ListAgreements, RpcMessageError};
 use ya_service_bus::typed::ServiceBinder;
 
-use crate::db::dao::AgreementDao;
-use crate::db::model::{AgreementId, Owner};
+use crate::db::dao::{AgreementDao, AgreementFilter};
+use crate::db::model::{AgreementId, LabelSelector, Owner};
 use crate::db::DbMixedExecutor;
 
 pub async fn bind_gsb(db: DbMixedExecutor, public_prefix: &str, _local_prefix: &str) {
@@ -20,16 +20,24 @@ async fn list_agreements(
     _sender_id: String,
     msg: ListAgreements,
 ) -> Result<Vec<AgreementListEntry>, RpcMessageError> {
-    let dao = db.as_dao::<AgreementDao>();
+    let labels = match msg.label {
+        Some(label) => label
+            .parse::<LabelSelector>()
+            .map_err(|e| RpcMessageError::BadRequest(e.to_string()))?,
+        None => LabelSelector::default(),
+    };
 
+    let filter = AgreementFilter {
+        state: msg.state.map(Into::into),
+        before: msg.before_date,
+        after: msg.after_date,
+        app_session_id: msg.app_session_id,
+        labels,
+        ..Default::default()
+    };
+    let dao = db.as_dao::<AgreementDao>();
     let agreements = dao
-        .list(
-            None,
-            msg.state.map(Into::into),
-            msg.before_date,
-            msg.after_date,
-            msg.app_session_id,
-        )
+        .list(filter, None, None)
         .await
         .map_err(|e| RpcMessageError::Market(e.to_string()))?;
 
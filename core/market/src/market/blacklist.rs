@@ -0,0 +1,62 @@
+//! Requestor's view of Provider failures and automatic blacklist.
+use ya_client::model::NodeId;
+
+use crate::market::MarketService;
+use crate::negotiation::blacklist::{BlacklistSettings, FailureReport, ProviderFailureStats};
+
+#[derive(thiserror::Error, Debug)]
+pub enum BlacklistError {
+    #[error("Provider [{0}] is not blacklisted.")]
+    NotBlacklisted(NodeId),
+    #[error("Invalid blacklist settings: {0}")]
+    InvalidSettings(String),
+}
+
+impl MarketService {
+    pub fn report_provider_failure(
+        &self,
+        provider_id: NodeId,
+        report: FailureReport,
+    ) -> ProviderFailureStats {
+        self.requestor_engine
+            .common
+            .blacklist
+            .report(provider_id, report)
+    }
+
+    pub fn list_provider_failures(&self, only_blacklisted: bool) -> Vec<ProviderFailureStats> {
+        self.requestor_engine
+            .common
+            .blacklist
+            .list(only_blacklisted)
+    }
+
+    /// Lets Requestor negotiate with the Provider again before its cooldown ends.
+    pub fn unblacklist_provider(&self, provider_id: NodeId) -> Result<(), BlacklistError> {
+        match self.requestor_engine.common.blacklist.remove(&provider_id) {
+            true => Ok(()),
+            false => Err(BlacklistError::NotBlacklisted(provider_id)),
+        }
+    }
+
+    pub fn blacklist_settings(&self) -> BlacklistSettings {
+        self.requestor_engine.common.blacklist.settings()
+    }
+
+    pub fn set_blacklist_settings(
+        &self,
+        settings: BlacklistSettings,
+    ) -> Result<BlacklistSettings, BlacklistError> {
+        if settings.window_sec == 0 || settings.cooldown_sec == 0 {
+            return Err(BlacklistError::InvalidSettings(
+                "window and cooldown have to be positive".to_string(),
+            ));
+        }
+        log::info!("Provider blacklist settings changed to {:?}", settings);
+        self.requestor_engine
+            .common
+            .blacklist
+            .set_settings(settings.clone());
+        Ok(settings)
+    }
+}
@@ -0,0 +1,131 @@
+//! Reserved agreements negotiated directly with a chosen Provider.
+//!
+//! Provider subscribes private Offer, which isn't propagated through the market,
+//! and shares its subscription id with the Requestor out of band. Requestor
+//! retrieves the Offer from the Provider and negotiates it with single Demand.
+use chrono::Utc;
+use std::time::Duration;
+
+use ya_client::model::NodeId;
+use ya_service_api_web::middleware::Identity;
+
+use crate::db::dao::SaveProposalError;
+use crate::db::model::{Offer, SubscriptionId};
+use crate::market::MarketService;
+use crate::matcher::error::{DemandError, SaveOfferError};
+use crate::matcher::resolver::matches;
+
+const RETRIEVE_OFFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(thiserror::Error, Debug)]
+pub enum DirectNegotiationError {
+    #[error("Offer [{0}] not found on Provider [{1}].")]
+    OfferNotFound(SubscriptionId, NodeId),
+    #[error("Failed to retrieve Offer [{0}] from Provider [{1}]. Error: {2}")]
+    Retrieve(SubscriptionId, NodeId, String),
+    #[error("Offer [{0}] doesn't match Demand [{1}].")]
+    NoMatch(SubscriptionId, SubscriptionId),
+    #[error("Provider [{0}] is blacklisted.")]
+    Blacklisted(NodeId),
+    #[error(transparent)]
+    Demand(#[from] DemandError),
+    #[error(transparent)]
+    SaveOffer(#[from] SaveOfferError),
+    #[error(transparent)]
+    SaveProposal(#[from] SaveProposalError),
+}
+
+impl MarketService {
+    /// Negotiates Offer shared by the Provider without waiting for it to
+    /// propagate. Initial Proposal is emitted as usual Demand event.
+    pub async fn negotiate_direct(
+        &self,
+        demand_id: &SubscriptionId,
+        provider_id: NodeId,
+        offer_id: &SubscriptionId,
+        id: &Identity,
+    ) -> Result<(), DirectNegotiationError> {
+        let demand = self.matcher.store.get_demand(demand_id).await?;
+        if demand.node_id != id.identity {
+            return Err(DemandError::NotFound(demand_id.clone()).into());
+        }
+
+        if self
+            .requestor_engine
+            .common
+            .blacklist
+            .is_blacklisted(&provider_id)
+        {
+            return Err(DirectNegotiationError::Blacklisted(provider_id));
+        }
+
+        let offer = self.retrieve_direct_offer(provider_id, offer_id).await?;
+        if !matches(&self.matcher.resolver.compiled, &offer, &demand) {
+            return Err(DirectNegotiationError::NoMatch(
+                offer.id.clone(),
+                demand.id.clone(),
+            ));
+        }
+
+        log::info!(
+            "Negotiating directly Offer [{}] of Provider [{}] with Demand [{}].",
+            offer.id,
+            provider_id,
+            demand.id
+        );
+        Ok(self
+            .requestor_engine
+            .negotiate_direct(offer, demand)
+            .await?)
+    }
+
+    /// Takes Offer from local database, if we already know it. Otherwise asks
+    /// the Provider, which serves its private Offers only to nodes knowing their ids.
+    async fn retrieve_direct_offer(
+        &self,
+        provider_id: NodeId,
+        offer_id: &SubscriptionId,
+    ) -> Result<Offer, DirectNegotiationError> {
+        let not_found = || DirectNegotiationError::OfferNotFound(offer_id.clone(), provider_id);
+        let store = &self.matcher.store;
+
+        if let Ok(offer) = store.get_offer(offer_id).await {
+            return match offer.node_id == provider_id {
+                true => Ok(offer),
+                false => Err(not_found()),
+            };
+        }
+
+        let retrieve_err = |e: &dyn std::fmt::Display| {
+            DirectNegotiationError::Retrieve(offer_id.clone(), provider_id, e.to_string())
+        };
+        let discovery = &self.matcher.discovery;
+        let mut offer = discovery
+            .get_remote_offers(
+                provider_id.to_string(),
+                vec![offer_id.clone()],
+                RETRIEVE_OFFER_TIMEOUT,
+            )
+            .await
+            .map_err(|e| retrieve_err(&e))?
+            .into_iter()
+            .find(|offer| &offer.id == offer_id && offer.node_id == provider_id)
+            .ok_or_else(not_found)?;
+
+        // Offer returned with expiration set at subscription could have been refreshed since.
+        if offer.expiration_ts < Utc::now().naive_utc() {
+            let refreshes = discovery
+                .get_remote_expirations(provider_id, vec![offer_id.clone()], RETRIEVE_OFFER_TIMEOUT)
+                .await
+                .map_err(|e| retrieve_err(&e))?;
+            if let Some(refresh) = refreshes.iter().find(|r| &r.offer_id == offer_id) {
+                offer.refresh(refresh.expiration_ts);
+            }
+        }
+
+        // Offer isn't passed to Resolver, so it won't be matched with our other Demands.
+        let offer = store.save_offer(offer).await?;
+        store.save_offer_origin(&offer, provider_id).await;
+        Ok(offer)
+    }
+}
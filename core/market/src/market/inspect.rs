@@ -0,0 +1,417 @@
+//! Raw view of stored Offers, Demands and Proposals for diagnosing negotiations.
+//!
+//! Returns content exactly as stored in market database (flat properties, constraints,
+//! timestamps) together with information, where the subscription came from.
+//! Demand can be also explained against an Offer to see, which constraints don't match.
+//! Negotiation of an Agreement can be exported as transcript of all Proposals exchanged.
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use ya_client::model::NodeId;
+use ya_market_resolver::{explain_demand_offer, MatchError, MatchExplanation};
+use ya_service_api_web::middleware::Identity;
+
+use crate::db::dao::{
+    AgreementDao, AgreementDaoError, DemandDao, DemandState, OfferDao, OfferState, ProposalDao,
+};
+use crate::db::model::{
+    AgreementState, Demand, Offer, Owner, Proposal, ProposalId, ProposalIdParseError,
+    SubscriptionId,
+};
+use crate::db::DbError;
+use crate::market::MarketService;
+
+#[derive(thiserror::Error, Debug)]
+pub enum InspectError {
+    #[error("Subscription [{0}] not found.")]
+    SubscriptionNotFound(SubscriptionId),
+    #[error("Proposal [{0}] not found.")]
+    ProposalNotFound(String),
+    #[error("Agreement [{0}] not found.")]
+    AgreementNotFound(String),
+    #[error("Failed to get Agreement. {0}")]
+    Agreement(#[from] AgreementDaoError),
+    #[error("Invalid Proposal id. {0}")]
+    InvalidProposalId(#[from] ProposalIdParseError),
+    #[error("Failed to inspect market database. Error: {0}")]
+    Db(#[from] DbError),
+    #[error("Failed to resolve constraints. {0}")]
+    Match(#[from] MatchError),
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionKind {
+    Offer,
+    Demand,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawSubscription {
+    pub kind: SubscriptionKind,
+    pub subscription_id: SubscriptionId,
+    /// `Active`, `Unsubscribed` or `Expired`.
+    pub state: String,
+    pub node_id: NodeId,
+    /// Flat properties as stored.
+    pub properties: Value,
+    pub constraints: String,
+    pub creation_ts: DateTime<Utc>,
+    /// Time of adding subscription to local database.
+    pub insertion_ts: Option<DateTime<Utc>>,
+    pub expiration_ts: DateTime<Utc>,
+    /// Known nodes, through which subscription reached us, starting from its owner.
+    pub propagation_path: Vec<NodeId>,
+    /// Time of retrieving foreign Offer from the last node on propagation path.
+    pub received_ts: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawProposal {
+    pub proposal_id: String,
+    pub prev_proposal_id: Option<String>,
+    /// `Us` or `Them`.
+    pub issuer: String,
+    pub state: String,
+    pub properties: Value,
+    pub constraints: String,
+    pub creation_ts: DateTime<Utc>,
+    pub expiration_ts: DateTime<Utc>,
+    pub negotiation_id: String,
+    pub agreement_id: Option<String>,
+    pub requestor_id: NodeId,
+    pub provider_id: NodeId,
+    pub offer: Option<RawSubscription>,
+    pub demand: Option<RawSubscription>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgreementTranscript {
+    pub agreement_id: String,
+    pub state: AgreementState,
+    pub offer_id: SubscriptionId,
+    pub demand_id: SubscriptionId,
+    pub requestor_id: NodeId,
+    pub provider_id: NodeId,
+    pub creation_ts: DateTime<Utc>,
+    pub approved_ts: Option<DateTime<Utc>>,
+    /// False, if older Proposals of the negotiation were already removed from database.
+    pub complete: bool,
+    /// Proposals in order of exchanging them, starting from the Initial Proposal.
+    pub proposals: Vec<TranscriptProposal>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptProposal {
+    pub proposal_id: String,
+    pub prev_proposal_id: Option<String>,
+    /// `Us` or `Them`.
+    pub issuer: String,
+    pub state: String,
+    pub properties: Value,
+    pub constraints: String,
+    pub creation_ts: DateTime<Utc>,
+    pub expiration_ts: DateTime<Utc>,
+    /// Changes against the previous Proposal in transcript.
+    pub changes: Option<ProposalChanges>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalChanges {
+    pub added: Map<String, Value>,
+    pub removed: Vec<String>,
+    pub changed: BTreeMap<String, ValueChange>,
+    pub constraints_changed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ValueChange {
+    pub from: Value,
+    pub to: Value,
+}
+
+impl MarketService {
+    /// Offers are public, so any stored Offer can be inspected. Demands are visible
+    /// only to their owners.
+    pub async fn inspect_subscription(
+        &self,
+        subscription_id: &SubscriptionId,
+        id: &Identity,
+    ) -> Result<RawSubscription, InspectError> {
+        if let Some(offer) = self.raw_offer(subscription_id).await? {
+            return Ok(offer);
+        }
+        match self.raw_demand(subscription_id).await? {
+            Some(demand) if demand.node_id == id.identity => Ok(demand),
+            _ => Err(InspectError::SubscriptionNotFound(subscription_id.clone())),
+        }
+    }
+
+    /// Proposal with Offer and Demand it was negotiated from. Only Proposals
+    /// from negotiations of calling identity can be inspected.
+    pub async fn inspect_proposal(
+        &self,
+        proposal_id: &str,
+        id: &Identity,
+    ) -> Result<RawProposal, InspectError> {
+        for owner in [Owner::Requestor, Owner::Provider] {
+            let proposal_id = ProposalId::from_client(proposal_id, owner)?;
+            let proposal = match self
+                .db
+                .as_dao::<ProposalDao>()
+                .get_proposal(&proposal_id)
+                .await?
+            {
+                Some(proposal) => proposal,
+                None => continue,
+            };
+
+            let our_id = match owner {
+                Owner::Requestor => proposal.negotiation.requestor_id,
+                Owner::Provider => proposal.negotiation.provider_id,
+            };
+            if our_id != id.identity {
+                continue;
+            }
+            return self.raw_proposal(proposal).await;
+        }
+        Err(InspectError::ProposalNotFound(proposal_id.to_string()))
+    }
+
+    /// All Proposals exchanged in negotiation of the Agreement, with property changes
+    /// made by each of them. Only Agreements of calling identity can be exported.
+    pub async fn agreement_transcript(
+        &self,
+        agreement_id: &str,
+        id: &Identity,
+    ) -> Result<AgreementTranscript, InspectError> {
+        let agreement = self
+            .db
+            .as_dao::<AgreementDao>()
+            .select_by_node(agreement_id, id.identity, Utc::now().naive_utc())
+            .await?
+            .ok_or_else(|| InspectError::AgreementNotFound(agreement_id.to_string()))?;
+
+        // Agreement is always created from the Proposal sent by Provider, which ends
+        // negotiation.
+        let chain = self
+            .db
+            .as_dao::<ProposalDao>()
+            .list_proposal_chain(&agreement.offer_proposal_id)
+            .await?;
+
+        let complete = chain
+            .first()
+            .map(|proposal| proposal.body.prev_proposal_id.is_none())
+            .unwrap_or(false);
+        let mut prev: Option<&Proposal> = None;
+        let mut proposals = Vec::new();
+        for proposal in &chain {
+            let body = &proposal.body;
+            proposals.push(TranscriptProposal {
+                proposal_id: body.id.into_client(),
+                prev_proposal_id: body.prev_proposal_id.as_ref().map(|id| id.into_client()),
+                issuer: body.issuer.to_string(),
+                state: body.state.to_string(),
+                properties: parse_properties(&body.properties),
+                constraints: body.constraints.clone(),
+                creation_ts: naive_to_utc(body.creation_ts),
+                expiration_ts: naive_to_utc(body.expiration_ts),
+                changes: prev.map(|prev| ProposalChanges::between(prev, proposal)),
+            });
+            prev = Some(proposal);
+        }
+
+        Ok(AgreementTranscript {
+            agreement_id: agreement.id.into_client(),
+            state: agreement.state,
+            offer_id: agreement.offer_id,
+            demand_id: agreement.demand_id,
+            requestor_id: agreement.requestor_id,
+            provider_id: agreement.provider_id,
+            creation_ts: naive_to_utc(agreement.creation_ts),
+            approved_ts: agreement.approved_ts.map(naive_to_utc),
+            complete,
+            proposals,
+        })
+    }
+
+    /// Trace of resolving constraints of caller's Demand against the Offer.
+    pub async fn explain_match(
+        &self,
+        demand_id: &SubscriptionId,
+        offer_id: &SubscriptionId,
+        id: &Identity,
+    ) -> Result<MatchExplanation, InspectError> {
+        let demand = match self.raw_demand(demand_id).await? {
+            Some(demand) if demand.node_id == id.identity => demand,
+            _ => return Err(InspectError::SubscriptionNotFound(demand_id.clone())),
+        };
+        let offer = self
+            .raw_offer(offer_id)
+            .await?
+            .ok_or_else(|| InspectError::SubscriptionNotFound(offer_id.clone()))?;
+
+        Ok(explain_demand_offer(
+            &demand.properties.to_string(),
+            &demand.constraints,
+            &offer.properties.to_string(),
+            &offer.constraints,
+        )?)
+    }
+
+    async fn raw_proposal(&self, proposal: Proposal) -> Result<RawProposal, InspectError> {
+        let Proposal { negotiation, body } = proposal;
+        Ok(RawProposal {
+            proposal_id: body.id.into_client(),
+            prev_proposal_id: body.prev_proposal_id.map(|id| id.into_client()),
+            issuer: body.issuer.to_string(),
+            state: body.state.to_string(),
+            properties: parse_properties(&body.properties),
+            constraints: body.constraints,
+            creation_ts: naive_to_utc(body.creation_ts),
+            expiration_ts: naive_to_utc(body.expiration_ts),
+            negotiation_id: negotiation.id,
+            agreement_id: negotiation.agreement_id.map(|id| id.into_client()),
+            requestor_id: negotiation.requestor_id,
+            provider_id: negotiation.provider_id,
+            offer: self.raw_offer(&negotiation.offer_id).await?,
+            demand: self.raw_demand(&negotiation.demand_id).await?,
+        })
+    }
+
+    async fn raw_offer(
+        &self,
+        subscription_id: &SubscriptionId,
+    ) -> Result<Option<RawSubscription>, InspectError> {
+        let dao = self.db.as_dao::<OfferDao>();
+        let (offer, state) = match dao
+            .get_state(subscription_id, Utc::now().naive_utc())
+            .await?
+        {
+            OfferState::Active(offer) => (offer, "Active"),
+            OfferState::Unsubscribed(Some(offer)) => (offer, "Unsubscribed"),
+            OfferState::Expired(Some(offer)) => (offer, "Expired"),
+            _ => return Ok(None),
+        };
+
+        let origin = dao.get_origin(subscription_id).await?;
+        let mut raw = RawSubscription::from_offer(offer, state);
+        if let Some(origin) = origin {
+            if origin.received_from != raw.node_id {
+                raw.propagation_path.push(origin.received_from);
+            }
+            raw.received_ts = origin.received_ts.map(naive_to_utc);
+        }
+        Ok(Some(raw))
+    }
+
+    async fn raw_demand(
+        &self,
+        subscription_id: &SubscriptionId,
+    ) -> Result<Option<RawSubscription>, InspectError> {
+        Ok(
+            match self
+                .db
+                .as_dao::<DemandDao>()
+                .demand_state(subscription_id)
+                .await?
+            {
+                DemandState::Active(demand) => Some(RawSubscription::from_demand(demand, "Active")),
+                DemandState::Expired(Some(demand)) => {
+                    Some(RawSubscription::from_demand(demand, "Expired"))
+                }
+                _ => None,
+            },
+        )
+    }
+}
+
+impl RawSubscription {
+    fn from_offer(offer: Offer, state: &str) -> Self {
+        RawSubscription {
+            kind: SubscriptionKind::Offer,
+            subscription_id: offer.id,
+            state: state.to_string(),
+            node_id: offer.node_id,
+            properties: parse_properties(&offer.properties),
+            constraints: offer.constraints,
+            creation_ts: naive_to_utc(offer.creation_ts),
+            insertion_ts: offer.insertion_ts.map(naive_to_utc),
+            expiration_ts: naive_to_utc(offer.expiration_ts),
+            propagation_path: vec![offer.node_id],
+            received_ts: None,
+        }
+    }
+
+    fn from_demand(demand: Demand, state: &str) -> Self {
+        RawSubscription {
+            kind: SubscriptionKind::Demand,
+            subscription_id: demand.id,
+            state: state.to_string(),
+            node_id: demand.node_id,
+            properties: parse_properties(&demand.properties),
+            constraints: demand.constraints,
+            creation_ts: naive_to_utc(demand.creation_ts),
+            insertion_ts: demand.insertion_ts.map(naive_to_utc),
+            expiration_ts: naive_to_utc(demand.expiration_ts),
+            propagation_path: vec![demand.node_id],
+            received_ts: None,
+        }
+    }
+}
+
+impl ProposalChanges {
+    fn between(prev: &Proposal, next: &Proposal) -> Self {
+        let as_map = |proposal: &Proposal| match parse_properties(&proposal.body.properties) {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        let prev_props = as_map(prev);
+        let next_props = as_map(next);
+
+        let mut changes = ProposalChanges {
+            constraints_changed: prev.body.constraints != next.body.constraints,
+            ..Default::default()
+        };
+        for (name, value) in &next_props {
+            match prev_props.get(name) {
+                None => {
+                    changes.added.insert(name.clone(), value.clone());
+                }
+                Some(prev_value) if prev_value != value => {
+                    changes.changed.insert(
+                        name.clone(),
+                        ValueChange {
+                            from: prev_value.clone(),
+                            to: value.clone(),
+                        },
+                    );
+                }
+                _ => (),
+            }
+        }
+        changes.removed = prev_props
+            .keys()
+            .filter(|name| !next_props.contains_key(*name))
+            .cloned()
+            .collect();
+        changes
+    }
+}
+
+/// Stored properties are returned as raw string, if they aren't valid json.
+fn parse_properties(properties: &str) -> Value {
+    serde_json::from_str(properties).unwrap_or_else(|_| Value::String(properties.to_string()))
+}
+
+fn naive_to_utc(ts: NaiveDateTime) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&ts)
+}
@@ -0,0 +1,67 @@
+//! Signed operator metadata of Providers, which market attaches to their Offers.
+//!
+//! Requestor can't trust the property by itself, because any Provider can copy it,
+//! so the document is verified to be signed by the node, which subscribed the Offer.
+use chrono::Utc;
+use serde_json::{Map, Value};
+
+use ya_client::model::NodeId;
+use ya_core_model::identity::{self, SignedMetadata};
+use ya_service_bus::{typed as bus, RpcEndpoint};
+
+use crate::db::dao::OfferDao;
+use crate::db::DbError;
+use crate::market::MarketService;
+use crate::matcher::NODE_METADATA_PROPERTY;
+
+#[derive(thiserror::Error, Debug)]
+pub enum MetadataError {
+    #[error("No Offer of Provider [{0}] with node metadata.")]
+    NotFound(NodeId),
+    #[error("Invalid metadata of Provider [{0}]: {1}")]
+    Invalid(NodeId, String),
+    #[error("Failed to verify metadata: {0}")]
+    Gsb(String),
+    #[error("Failed to get Offers. Error: {0}")]
+    Db(#[from] DbError),
+}
+
+impl MarketService {
+    /// Verified metadata from the newest active Offer of the Provider.
+    pub async fn provider_metadata(
+        &self,
+        provider_id: NodeId,
+    ) -> Result<SignedMetadata, MetadataError> {
+        let offers = self
+            .db
+            .as_dao::<OfferDao>()
+            .get_offers(None, Some(vec![provider_id]), None, Utc::now().naive_utc())
+            .await?;
+
+        let document = offers
+            .iter()
+            .rev()
+            .find_map(|offer| {
+                serde_json::from_str::<Map<String, Value>>(&offer.properties)
+                    .ok()?
+                    .get(NODE_METADATA_PROPERTY)?
+                    .as_str()
+                    .map(ToString::to_string)
+            })
+            .ok_or(MetadataError::NotFound(provider_id))?;
+
+        let metadata = bus::service(identity::BUS_ID)
+            .send(identity::VerifyMetadata { document })
+            .await
+            .map_err(|e| MetadataError::Gsb(e.to_string()))?
+            .map_err(|e| MetadataError::Invalid(provider_id, e.to_string()))?;
+
+        if metadata.node_id != provider_id {
+            return Err(MetadataError::Invalid(
+                provider_id,
+                format!("signed by other node [{}]", metadata.node_id),
+            ));
+        }
+        Ok(metadata)
+    }
+}
@@ -0,0 +1,170 @@
+//! Registry of application sessions (`appSessionId`).
+//!
+//! Sessions are registered on first use. Subscriptions created in scope of a session
+//! are bound to it, so their negotiation events can't be collected from other sessions,
+//! the same way as Agreement events are filtered by session. Inactive sessions expire
+//! after `MARKET_SESSION_TTL`.
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+
+use ya_service_api_web::middleware::Identity;
+
+use crate::db::dao::{
+    AppSessionDao, DemandDao, DemandState, OfferDao, OfferState, TakeEventsError,
+};
+use crate::db::model::{AppSessionId, SubscriptionId};
+use crate::db::DbError;
+use crate::market::MarketService;
+use crate::negotiation::error::QueryEventsError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SessionError {
+    #[error("App session [{0}] not found.")]
+    NotFound(String),
+    #[error("App session registry error: {0}")]
+    Db(#[from] DbError),
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSessionInfo {
+    pub app_session_id: String,
+    pub created_ts: DateTime<Utc>,
+    pub last_active_ts: DateTime<Utc>,
+    /// Offers and Demands subscribed in this session.
+    pub subscriptions: Vec<SubscriptionId>,
+    pub agreements: i64,
+}
+
+impl MarketService {
+    /// Marks session as active. Registry is only bookkeeping, so failures
+    /// don't break operations done in session.
+    pub async fn touch_session(&self, session_id: &AppSessionId, id: &Identity) {
+        if let Some(session_id) = session_id {
+            if let Err(e) = self
+                .db
+                .as_dao::<AppSessionDao>()
+                .touch(session_id, id.identity)
+                .await
+            {
+                log::warn!("Failed to register app session [{}]: {}", session_id, e);
+            }
+        }
+    }
+
+    /// Binds newly created subscription to session, if there is any.
+    pub async fn bind_session(
+        &self,
+        session_id: &AppSessionId,
+        subscription_id: &SubscriptionId,
+        id: &Identity,
+    ) -> Result<(), SessionError> {
+        if let Some(session_id) = session_id {
+            self.db
+                .as_dao::<AppSessionDao>()
+                .bind(session_id, id.identity, subscription_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Subscriptions bound to other session than queried are reported as not found.
+    /// Queries without session and subscriptions created without session aren't limited.
+    pub async fn check_session(
+        &self,
+        session_id: &AppSessionId,
+        subscription_id: &SubscriptionId,
+        id: &Identity,
+    ) -> Result<(), QueryEventsError> {
+        let session_id = match session_id {
+            Some(session_id) => session_id,
+            None => return Ok(()),
+        };
+
+        let dao = self.db.as_dao::<AppSessionDao>();
+        let bound = dao
+            .session_of(subscription_id)
+            .await
+            .map_err(|e| QueryEventsError::Internal(e.to_string()))?;
+        match bound {
+            Some(bound) if &bound != session_id => {
+                return Err(TakeEventsError::NotFound(subscription_id.clone()).into())
+            }
+            _ => (),
+        }
+
+        self.touch_session(&Some(session_id.clone()), id).await;
+        Ok(())
+    }
+
+    pub async fn list_sessions(&self, id: &Identity) -> Result<Vec<AppSessionInfo>, SessionError> {
+        Ok(self
+            .db
+            .as_dao::<AppSessionDao>()
+            .list(id.identity)
+            .await?
+            .into_iter()
+            .map(|(session, subscriptions, agreements)| AppSessionInfo {
+                app_session_id: session.id,
+                created_ts: naive_to_utc(session.created_ts),
+                last_active_ts: naive_to_utc(session.last_active_ts),
+                subscriptions,
+                agreements,
+            })
+            .collect())
+    }
+
+    /// Removes session and unsubscribes Offers and Demands created in its scope.
+    /// Agreements keep their session id.
+    pub async fn remove_session(
+        &self,
+        session_id: &str,
+        id: &Identity,
+    ) -> Result<(), SessionError> {
+        let subscriptions = self
+            .db
+            .as_dao::<AppSessionDao>()
+            .remove(session_id, id.identity)
+            .await?
+            .ok_or_else(|| SessionError::NotFound(session_id.to_string()))?;
+
+        for subscription_id in subscriptions {
+            let demand = self
+                .db
+                .as_dao::<DemandDao>()
+                .demand_state(&subscription_id)
+                .await?;
+            let result = match demand {
+                DemandState::Active(_) => self
+                    .unsubscribe_demand(&subscription_id, id)
+                    .await
+                    .map_err(|e| e.to_string()),
+                _ => match self
+                    .db
+                    .as_dao::<OfferDao>()
+                    .get_state(&subscription_id, Utc::now().naive_utc())
+                    .await?
+                {
+                    OfferState::Active(_) => self
+                        .unsubscribe_offer(&subscription_id, id)
+                        .await
+                        .map_err(|e| e.to_string()),
+                    _ => Ok(()),
+                },
+            };
+            if let Err(e) = result {
+                log::warn!(
+                    "Failed to unsubscribe [{}] of removed app session [{}]: {}",
+                    subscription_id,
+                    session_id,
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn naive_to_utc(ts: NaiveDateTime) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&ts)
+}
@@ -0,0 +1,90 @@
+//! Negotiation statistics of subscriptions, helping to find constraints generating
+//! lots of negotiations, which never end with an Agreement.
+use std::collections::HashMap;
+
+use ya_core_model::market::{GetSubscriptionStats, RpcMessageError, SubscriptionStats};
+use ya_service_bus::typed::ServiceBinder;
+
+use crate::db::dao::{AgreementDao, AgreementFilter, ProposalDao};
+use crate::db::model::{Issuer, ProposalState, SubscriptionId};
+use crate::db::DbMixedExecutor;
+
+pub async fn bind_gsb(db: DbMixedExecutor, _public_prefix: &str, local_prefix: &str) {
+    log::trace!("Binding market stats local service to service bus");
+    ServiceBinder::new(local_prefix, &db, ()).bind(get_subscription_stats);
+    log::debug!("Successfully bound market stats local service to service bus");
+}
+
+async fn get_subscription_stats(
+    db: DbMixedExecutor,
+    _sender_id: String,
+    msg: GetSubscriptionStats,
+) -> Result<SubscriptionStats, RpcMessageError> {
+    let subscription_id = msg
+        .subscription_id
+        .parse::<SubscriptionId>()
+        .map_err(|e| RpcMessageError::BadRequest(e.to_string()))?;
+    subscription_stats(&db, &subscription_id)
+        .await
+        .map_err(|e| RpcMessageError::Market(e.to_string()))
+}
+
+pub async fn subscription_stats(
+    db: &DbMixedExecutor,
+    subscription_id: &SubscriptionId,
+) -> anyhow::Result<SubscriptionStats> {
+    let proposals = db
+        .as_dao::<ProposalDao>()
+        .list_by_subscription(subscription_id)
+        .await?;
+    let filter = AgreementFilter {
+        subscription_id: Some(subscription_id.clone()),
+        ..Default::default()
+    };
+    let agreements = db.as_dao::<AgreementDao>().list(filter, None, None).await?;
+
+    let mut stats = SubscriptionStats {
+        subscription_id: subscription_id.to_string(),
+        ..Default::default()
+    };
+
+    // Proposals are ordered by creation time, so the first one is the Initial Proposal.
+    let mut negotiation_start = HashMap::new();
+    for proposal in &proposals {
+        let body = &proposal.body;
+        match body.issuer {
+            Issuer::Us => stats.proposals_sent += 1,
+            Issuer::Them => stats.proposals_received += 1,
+        }
+        if body.state == ProposalState::Rejected {
+            stats.proposals_rejected += 1;
+        }
+        negotiation_start
+            .entry(body.negotiation_id.clone())
+            .or_insert(body.creation_ts);
+    }
+    stats.negotiations = negotiation_start.len() as u64;
+    stats.agreements = agreements.len() as u64;
+
+    let latencies = agreements
+        .iter()
+        .filter_map(|agreement| {
+            let proposal = proposals.iter().find(|proposal| {
+                proposal.body.id == agreement.offer_proposal_id
+                    || proposal.body.id == agreement.demand_proposal_id
+            })?;
+            let start = negotiation_start.get(&proposal.body.negotiation_id)?;
+            Some((agreement.creation_ts - *start).num_milliseconds() as f64 / 1000.0)
+        })
+        .collect::<Vec<_>>();
+    if !latencies.is_empty() {
+        stats.avg_negotiation_latency =
+            Some(latencies.iter().sum::<f64>() / latencies.len() as f64);
+    }
+    // Agreements outlive Proposals in the database, so only Agreements from stored
+    // negotiations are taken into account.
+    if stats.negotiations > 0 {
+        stats.conversion_rate = latencies.len() as f64 / stats.negotiations as f64;
+    }
+    Ok(stats)
+}
@@ -0,0 +1,233 @@
+//! Registration of webhooks for subscriptions and tracking of their deliveries.
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use ya_service_api_web::middleware::Identity;
+
+use crate::db::dao::WebhookDao;
+use crate::db::model::{DeliveryStatus, SubscriptionId, Webhook, WebhookDelivery};
+use crate::db::DbError;
+use crate::market::MarketService;
+use crate::negotiation::webhook::{WebhookDispatcher, WebhookEventType};
+
+#[derive(thiserror::Error, Debug)]
+pub enum WebhookError {
+    #[error("Subscription [{0}] not found.")]
+    SubscriptionNotFound(SubscriptionId),
+    #[error("Webhook [{0}] not found.")]
+    NotFound(String),
+    #[error("Webhook delivery [{0}] not found.")]
+    DeliveryNotFound(String),
+    #[error("Invalid webhook url [{0}]. Only http and https urls are supported.")]
+    InvalidUrl(String),
+    #[error("Unknown webhook event type [{0}].")]
+    InvalidEventType(String),
+    #[error("Webhook registry error: {0}")]
+    Db(#[from] DbError),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWebhook {
+    pub url: String,
+    /// All events are sent if empty.
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookInfo {
+    pub webhook_id: String,
+    pub subscription_id: SubscriptionId,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub created_ts: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryInfo {
+    pub delivery_id: String,
+    pub event_type: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub payload: serde_json::Value,
+    pub created_ts: DateTime<Utc>,
+    pub delivered_ts: Option<DateTime<Utc>>,
+}
+
+impl MarketService {
+    /// Registers webhook for caller's Offer or Demand.
+    pub async fn register_webhook(
+        &self,
+        subscription_id: &SubscriptionId,
+        webhook: NewWebhook,
+        id: &Identity,
+    ) -> Result<WebhookInfo, WebhookError> {
+        if !(webhook.url.starts_with("http://") || webhook.url.starts_with("https://")) {
+            return Err(WebhookError::InvalidUrl(webhook.url));
+        }
+        for event_type in &webhook.event_types {
+            WebhookEventType::from_str(event_type)
+                .map_err(|_| WebhookError::InvalidEventType(event_type.clone()))?;
+        }
+        self.check_subscription_owner(subscription_id, id).await?;
+
+        let entry = Webhook {
+            id: uuid::Uuid::new_v4().to_simple().to_string(),
+            subscription_id: subscription_id.clone(),
+            owner_id: id.identity,
+            url: webhook.url,
+            event_types: webhook.event_types.join(","),
+            created_ts: Utc::now().naive_utc(),
+        };
+        self.db.as_dao::<WebhookDao>().insert(entry.clone()).await?;
+
+        log::info!(
+            "Registered webhook [{}] for subscription [{}].",
+            entry.id,
+            subscription_id
+        );
+        Ok(entry.into())
+    }
+
+    pub async fn list_webhooks(
+        &self,
+        subscription_id: &SubscriptionId,
+        id: &Identity,
+    ) -> Result<Vec<WebhookInfo>, WebhookError> {
+        Ok(self
+            .db
+            .as_dao::<WebhookDao>()
+            .list(subscription_id, id.identity)
+            .await?
+            .into_iter()
+            .map(WebhookInfo::from)
+            .collect())
+    }
+
+    pub async fn remove_webhook(
+        &self,
+        webhook_id: &str,
+        id: &Identity,
+    ) -> Result<(), WebhookError> {
+        match self
+            .db
+            .as_dao::<WebhookDao>()
+            .remove(webhook_id, id.identity)
+            .await?
+        {
+            true => Ok(()),
+            false => Err(WebhookError::NotFound(webhook_id.to_string())),
+        }
+    }
+
+    /// Deliveries of webhook events, the most recent first.
+    pub async fn list_webhook_deliveries(
+        &self,
+        webhook_id: &str,
+        id: &Identity,
+    ) -> Result<Vec<DeliveryInfo>, WebhookError> {
+        self.get_webhook(webhook_id, id).await?;
+        Ok(self
+            .db
+            .as_dao::<WebhookDao>()
+            .deliveries(webhook_id)
+            .await?
+            .into_iter()
+            .map(DeliveryInfo::from)
+            .collect())
+    }
+
+    /// Sends event again, regardless of the previous delivery status.
+    pub async fn replay_webhook_delivery(
+        &self,
+        webhook_id: &str,
+        delivery_id: &str,
+        id: &Identity,
+    ) -> Result<(), WebhookError> {
+        let webhook = self.get_webhook(webhook_id, id).await?;
+        let dao = self.db.as_dao::<WebhookDao>();
+        let delivery = dao
+            .delivery(webhook_id, delivery_id)
+            .await?
+            .ok_or_else(|| WebhookError::DeliveryNotFound(delivery_id.to_string()))?;
+        dao.reset(delivery_id).await?;
+
+        log::debug!(
+            "Replaying webhook delivery [{}] to {}.",
+            delivery_id,
+            webhook.url
+        );
+        WebhookDispatcher::new(self.db.clone()).spawn_delivery(delivery, webhook.url);
+        Ok(())
+    }
+
+    async fn get_webhook(&self, webhook_id: &str, id: &Identity) -> Result<Webhook, WebhookError> {
+        self.db
+            .as_dao::<WebhookDao>()
+            .get(webhook_id, id.identity)
+            .await?
+            .ok_or_else(|| WebhookError::NotFound(webhook_id.to_string()))
+    }
+
+    async fn check_subscription_owner(
+        &self,
+        subscription_id: &SubscriptionId,
+        id: &Identity,
+    ) -> Result<(), WebhookError> {
+        let store = &self.matcher.store;
+        let owner = match store.get_demand(subscription_id).await {
+            Ok(demand) => Some(demand.node_id),
+            Err(_) => store
+                .get_offer(subscription_id)
+                .await
+                .ok()
+                .map(|offer| offer.node_id),
+        };
+        match owner {
+            Some(owner) if owner == id.identity => Ok(()),
+            _ => Err(WebhookError::SubscriptionNotFound(subscription_id.clone())),
+        }
+    }
+}
+
+impl From<Webhook> for WebhookInfo {
+    fn from(webhook: Webhook) -> Self {
+        WebhookInfo {
+            webhook_id: webhook.id,
+            subscription_id: webhook.subscription_id,
+            url: webhook.url,
+            event_types: webhook
+                .event_types
+                .split(',')
+                .filter(|t| !t.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            created_ts: naive_to_utc(webhook.created_ts),
+        }
+    }
+}
+
+impl From<WebhookDelivery> for DeliveryInfo {
+    fn from(delivery: WebhookDelivery) -> Self {
+        DeliveryInfo {
+            payload: serde_json::from_str(&delivery.payload)
+                .unwrap_or(serde_json::Value::String(delivery.payload)),
+            delivery_id: delivery.id,
+            event_type: delivery.event_type,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            last_error: delivery.last_error,
+            created_ts: naive_to_utc(delivery.created_ts),
+            delivered_ts: delivery.delivered_ts.map(naive_to_utc),
+        }
+    }
+}
+
+fn naive_to_utc(ts: NaiveDateTime) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&ts)
+}
@@ -1,5 +1,5 @@
 use actix::prelude::*;
-use chrono::{TimeZone, Utc};
+use chrono::{NaiveDateTime, TimeZone, Utc};
 use metrics::counter;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -14,25 +14,40 @@ use ya_utils_actix::deadline_checker::{
 use crate::config::Config;
 use crate::db::model::{Demand, Offer, SubscriptionId};
 use crate::identity::IdentityApi;
+use crate::protocol::discovery::message::OfferRefresh;
 use crate::protocol::discovery::{builder::DiscoveryBuilder, Discovery};
 
+pub(crate) mod cache;
+pub(crate) mod compiled;
 pub(crate) mod cyclic;
 pub mod error;
+pub(crate) mod filter;
 pub(crate) mod handlers;
+pub(crate) mod index;
 pub(crate) mod resolver;
 pub(crate) mod store;
+pub mod validation;
 
 use crate::db::dao::{DemandDao, DemandState};
-use error::{MatcherError, MatcherInitError, QueryOfferError, QueryOffersError};
+use error::{
+    DemandError, MatcherError, MatcherInitError, ModifyOfferError, QueryOfferError,
+    QueryOffersError,
+};
 use futures::FutureExt;
 use log::debug;
 use resolver::Resolver;
 use store::SubscriptionStore;
 use tracing::Level;
+use validation::{ValidationReport, Validators};
+use ya_core_model::market::{AddOfferFilter, ListOfferFilters, RemoveOfferFilter, RpcMessageError};
 use ya_core_model::net::local::{
     BindBroadcastError, BroadcastMessage, NewNeighbour, SendBroadcastMessage,
 };
 use ya_net::bind_broadcast_with_caller;
+use ya_service_bus::typed as bus;
+
+/// Offer property with signed metadata document of Provider's identity.
+pub const NODE_METADATA_PROPERTY: &str = "golem.node.metadata";
 
 /// Stores proposal generated from resolver.
 #[derive(Debug)]
@@ -51,6 +66,7 @@ pub struct EventsListeners {
 pub struct Matcher {
     pub store: SubscriptionStore,
     pub resolver: Resolver,
+    pub validators: Validators,
     pub(crate) discovery: Discovery,
     identity: Arc<dyn IdentityApi>,
     config: Arc<Config>,
@@ -64,7 +80,7 @@ impl Matcher {
         config: Arc<Config>,
     ) -> Result<(Matcher, EventsListeners), MatcherInitError> {
         let (proposal_sender, proposal_receiver) = unbounded_channel::<RawProposal>();
-        let resolver = Resolver::new(store.clone(), proposal_sender);
+        let resolver = Resolver::new(store.clone(), proposal_sender, &config.subscription);
 
         let discovery = DiscoveryBuilder::default()
             .add_data(identity_api.clone())
@@ -75,10 +91,14 @@ impl Matcher {
             .add_data_handler(handlers::get_local_offers)
             .add_data_handler(handlers::receive_remote_offer_unsubscribes)
             .add_data_handler(handlers::query_offers)
+            .add_data_handler(handlers::filter_offer_refreshes)
+            .add_data_handler(handlers::receive_offer_refreshes)
+            .add_data_handler(handlers::get_local_offers_expiration)
             .with_config(config.discovery.clone())
             .build();
 
         let matcher = Matcher {
+            validators: Validators::new(config.subscription.property_validation),
             store,
             resolver,
             discovery,
@@ -92,6 +112,7 @@ impl Matcher {
         // Initialize counters to 0 value. Otherwise they won't appear on metrics endpoint
         // until first change to value will be made.
         counter!("market.offers.incoming", 0);
+        counter!("market.offers.filtered", 0);
         counter!("market.offers.broadcasts", 0);
         counter!("market.offers.broadcasts.skip", 0);
         counter!("market.offers.broadcasts.net", 0);
@@ -100,6 +121,15 @@ impl Matcher {
         counter!("market.offers.unsubscribes.broadcasts", 0);
         counter!("market.offers.unsubscribes.broadcasts.net", 0);
         counter!("market.offers.unsubscribes.broadcasts.net_errors", 0);
+        counter!("market.offers.refreshes.incoming", 0);
+        counter!("market.offers.refreshes.broadcasts.net", 0);
+        counter!("market.offers.refreshes.broadcasts.net_errors", 0);
+        counter!("market.cache.offers.hits", 0);
+        counter!("market.cache.offers.misses", 0);
+        counter!("market.cache.offers.evictions", 0);
+        counter!("market.cache.demands.hits", 0);
+        counter!("market.cache.demands.misses", 0);
+        counter!("market.cache.demands.evictions", 0);
 
         Ok((matcher, listeners))
     }
@@ -109,6 +139,10 @@ impl Matcher {
         public_prefix: &str,
         local_prefix: &str,
     ) -> Result<(), MatcherInitError> {
+        if let Err(e) = self.store.filter.load(&self.store.db).await {
+            log::warn!("Failed to load Offer filter rules: {}", e);
+        }
+        self.bind_filter_gsb(local_prefix);
         self.discovery.bind_gsb(public_prefix, local_prefix).await?;
 
         // We can't spawn broadcasts, before gsb is bound.
@@ -142,6 +176,45 @@ impl Matcher {
         .await
     }
 
+    fn bind_filter_gsb(&self, local_prefix: &str) {
+        let store = self.store.clone();
+        let _ = bus::bind(local_prefix, move |msg: AddOfferFilter| {
+            let store = store.clone();
+            async move {
+                store
+                    .filter
+                    .add(&store.db, msg.0.clone())
+                    .await
+                    .map_err(|e| RpcMessageError::Service(e.to_string()))?;
+                log::info!("Added Offer filter rule: {}", msg.0);
+                Ok(())
+            }
+        });
+
+        let store = self.store.clone();
+        let _ = bus::bind(local_prefix, move |msg: RemoveOfferFilter| {
+            let store = store.clone();
+            async move {
+                let removed = store
+                    .filter
+                    .remove(&store.db, msg.0.clone())
+                    .await
+                    .map_err(|e| RpcMessageError::Service(e.to_string()))?;
+                if !removed {
+                    return Err(RpcMessageError::NotFound(msg.0.to_string()));
+                }
+                log::info!("Removed Offer filter rule: {}", msg.0);
+                Ok(())
+            }
+        });
+
+        let store = self.store.clone();
+        let _ = bus::bind(local_prefix, move |_: ListOfferFilters| {
+            let rules = store.filter.list();
+            async move { Ok(rules) }
+        });
+    }
+
     pub async fn bind_expiration_tracker(&self) -> anyhow::Result<()> {
         let store = self.store.clone();
         bind_deadline_reaction(self.expiration_tracker.clone(), move |msg| {
@@ -181,11 +254,53 @@ impl Matcher {
         offer: &NewOffer,
         id: &Identity,
     ) -> Result<Offer, MatcherError> {
-        let offer = self.store.create_offer(id, offer).await?;
-        self.resolver.receive(&offer);
+        self.subscribe(offer, id, false).await
+    }
+
+    /// Subscribes Offer, which won't be broadcasted nor matched with Demands.
+    /// Requestors can negotiate it only directly, knowing its subscription id.
+    pub async fn subscribe_private_offer(
+        &self,
+        offer: &NewOffer,
+        id: &Identity,
+    ) -> Result<Offer, MatcherError> {
+        self.subscribe(offer, id, true).await
+    }
+
+    /// Attaches signed metadata of the identity to Offer. Offer is subscribed
+    /// without it, if the metadata can't be read.
+    async fn with_node_metadata(&self, offer: &NewOffer, id: &Identity) -> NewOffer {
+        let mut offer = offer.clone();
+        match self.identity.metadata(id.identity).await {
+            Ok(Some(document)) => {
+                if let Some(properties) = offer.properties.as_object_mut() {
+                    properties.insert(NODE_METADATA_PROPERTY.to_string(), document.into());
+                }
+            }
+            Ok(None) => (),
+            Err(e) => log::warn!("Subscribing Offer without node metadata. {}", e),
+        }
+        offer
+    }
+
+    async fn subscribe(
+        &self,
+        offer: &NewOffer,
+        id: &Identity,
+        private: bool,
+    ) -> Result<Offer, MatcherError> {
+        self.validators
+            .check("Offer", &offer.properties, &offer.constraints)?;
+
+        let offer = self.with_node_metadata(offer, id).await;
+        let offer = self.store.create_offer(id, &offer, private).await?;
+        if !private {
+            self.resolver.receive(&offer);
+        }
 
         log::info!(
-            "Subscribed new Offer: [{}] using identity: {} [{}]",
+            "Subscribed new {} Offer: [{}] using identity: {} [{}]",
+            if private { "private" } else { "public" },
             &offer.id,
             id.name,
             id.identity
@@ -200,6 +315,10 @@ impl Matcher {
             .await
             .ok();
 
+        if private {
+            return Ok(offer);
+        }
+
         // Ignore error and don't retry to broadcast Offer. It will be broadcasted
         // anyway during random broadcast, so nothing bad happens here in case of error.
         let _ = self
@@ -212,6 +331,18 @@ impl Matcher {
         Ok(offer)
     }
 
+    /// Validates Offer content without subscribing it.
+    pub fn validate_offer(&self, offer: &NewOffer) -> ValidationReport {
+        self.validators
+            .validate(&offer.properties, &offer.constraints)
+    }
+
+    /// Validates Demand content without subscribing it.
+    pub fn validate_demand(&self, demand: &NewDemand) -> ValidationReport {
+        self.validators
+            .validate(&demand.properties, &demand.constraints)
+    }
+
     pub async fn unsubscribe_offer(
         &self,
         offer_id: &SubscriptionId,
@@ -254,6 +385,61 @@ impl Matcher {
         Ok(())
     }
 
+    /// Extends expiration of our Offer and propagates it to other nodes.
+    /// Subscription id doesn't change, so negotiations in progress are not affected.
+    pub async fn refresh_offer(
+        &self,
+        offer_id: &SubscriptionId,
+        expiration_ts: NaiveDateTime,
+        id: &Identity,
+    ) -> Result<Offer, MatcherError> {
+        let offer = self.store.get_offer(offer_id).await?;
+        if offer.node_id != id.identity {
+            return Err(ModifyOfferError::NotFound(offer_id.clone()).into());
+        }
+
+        let offer = self.store.refresh_offer(offer_id, expiration_ts).await?;
+
+        log::info!(
+            "Refreshed Offer: [{}] until {} using identity: {} [{}]",
+            &offer.id,
+            &offer.expiration_ts,
+            id.name,
+            id.identity
+        );
+
+        // Deadline set at subscription will be ignored, since Offer won't be expired.
+        self.expiration_tracker
+            .send(TrackDeadline {
+                category: "Offer".to_string(),
+                deadline: Utc.from_utc_datetime(&offer.expiration_ts),
+                id: offer.id.to_string(),
+            })
+            .await
+            .ok();
+
+        if offer.private {
+            return Ok(offer);
+        }
+
+        // Refreshes of our Offers are broadcasted cyclically, so we ignore errors here.
+        let _ = self
+            .discovery
+            .bcast_refreshes(vec![OfferRefresh {
+                offer_id: offer.id.clone(),
+                expiration_ts: offer.expiration_ts,
+            }])
+            .await
+            .map_err(|e| {
+                log::warn!(
+                    "Failed to bcast refresh of Offer [{}]. Error: {}.",
+                    offer.id,
+                    e
+                );
+            });
+        Ok(offer)
+    }
+
     pub async fn subscribe_demand(
         &self,
         demand: &NewDemand,
@@ -268,6 +454,10 @@ impl Matcher {
                 |_| (),
             );
         }
+        self.validators
+            .check("Demand", &demand.properties, &demand.constraints)?;
+        self.authorize_demand_owner(id).await?;
+
         let demand = self.store.create_demand(id, demand).await?;
         self.resolver.receive(&demand);
 
@@ -301,11 +491,32 @@ impl Matcher {
         Ok(())
     }
 
+    /// Delegated identity can act on behalf of the owner, but Agreements negotiated
+    /// for the Demand are signed with owner key, so owner must be our identity.
+    async fn authorize_demand_owner(&self, id: &Identity) -> Result<(), DemandError> {
+        if let Some(delegate) = id.delegated_by {
+            if !self.identity.list().await?.contains(&id.identity) {
+                return Err(DemandError::ForeignOwner(id.identity));
+            }
+            log::info!(
+                "[{}] subscribes Demand on behalf of [{}].",
+                delegate,
+                id.identity
+            );
+        }
+        Ok(())
+    }
+
     pub async fn get_our_active_offer_ids(&self) -> Result<Vec<SubscriptionId>, QueryOffersError> {
         let our_node_ids = self.identity.list().await?;
         self.store.get_active_offer_ids(Some(our_node_ids)).await
     }
 
+    pub async fn get_our_offer_refreshes(&self) -> Result<Vec<OfferRefresh>, QueryOffersError> {
+        let our_node_ids = self.identity.list().await?;
+        self.store.get_offer_refreshes(Some(our_node_ids)).await
+    }
+
     pub async fn get_our_unsubscribed_offer_ids(
         &self,
     ) -> Result<Vec<SubscriptionId>, QueryOffersError> {
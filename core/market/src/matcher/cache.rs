@@ -0,0 +1,232 @@
+//! In-memory LRU of recently used Offers and Demands.
+//!
+//! Negotiation looks up the same Offer and Demand for every Proposal, so under
+//! high throughput most of the queries hit sqlite for data, which didn't change.
+//! Cache is write-through: `SubscriptionStore` always writes to database first
+//! and only then updates the cache, so database stays the source of truth.
+//! Cached entries must come from database, since resolver depends on `insertion_ts`.
+//!
+//! The same LRU keeps compiled representations of subscriptions used by resolver,
+//! keyed by content hash instead of subscription id.
+use chrono::NaiveDateTime;
+use metrics::counter;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::db::model::{Demand, Offer, SubscriptionId};
+
+/// Approximate number of bytes, that fixed size fields of subscription occupy.
+pub(crate) const ENTRY_OVERHEAD: usize = 256;
+
+pub trait Cacheable: Clone {
+    type Key: Clone + Eq + Hash;
+
+    const HITS_METRIC: &'static str;
+    const MISSES_METRIC: &'static str;
+    const EVICTIONS_METRIC: &'static str;
+
+    fn id(&self) -> &Self::Key;
+    fn expiration_ts(&self) -> NaiveDateTime;
+    fn weight(&self) -> usize;
+}
+
+impl Cacheable for Offer {
+    type Key = SubscriptionId;
+
+    const HITS_METRIC: &'static str = "market.cache.offers.hits";
+    const MISSES_METRIC: &'static str = "market.cache.offers.misses";
+    const EVICTIONS_METRIC: &'static str = "market.cache.offers.evictions";
+
+    fn id(&self) -> &SubscriptionId {
+        &self.id
+    }
+
+    fn expiration_ts(&self) -> NaiveDateTime {
+        self.expiration_ts
+    }
+
+    fn weight(&self) -> usize {
+        ENTRY_OVERHEAD + self.properties.len() + self.constraints.len()
+    }
+}
+
+impl Cacheable for Demand {
+    type Key = SubscriptionId;
+
+    const HITS_METRIC: &'static str = "market.cache.demands.hits";
+    const MISSES_METRIC: &'static str = "market.cache.demands.misses";
+    const EVICTIONS_METRIC: &'static str = "market.cache.demands.evictions";
+
+    fn id(&self) -> &SubscriptionId {
+        &self.id
+    }
+
+    fn expiration_ts(&self) -> NaiveDateTime {
+        self.expiration_ts
+    }
+
+    fn weight(&self) -> usize {
+        ENTRY_OVERHEAD + self.properties.len() + self.constraints.len()
+    }
+}
+
+#[derive(Clone)]
+pub struct SubscriptionCache<T: Cacheable> {
+    inner: Arc<Mutex<Lru<T>>>,
+}
+
+struct Lru<T: Cacheable> {
+    entries: HashMap<T::Key, (T, u64)>,
+    /// Entries ordered by last usage. Oldest first.
+    usage: BTreeMap<u64, T::Key>,
+    tick: u64,
+    bytes: usize,
+    max_entries: usize,
+    max_bytes: usize,
+}
+
+impl<T: Cacheable> SubscriptionCache<T> {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        SubscriptionCache {
+            inner: Arc::new(Mutex::new(Lru {
+                entries: HashMap::new(),
+                usage: BTreeMap::new(),
+                tick: 0,
+                bytes: 0,
+                max_entries,
+                max_bytes,
+            })),
+        }
+    }
+
+    /// Returns not expired subscription. Expired entries are dropped.
+    pub fn get(&self, id: &T::Key, now: NaiveDateTime) -> Option<T> {
+        let mut lru = self.inner.lock();
+        let found = match lru.entries.get(id) {
+            Some((item, _)) if item.expiration_ts() >= now => Some(item.clone()),
+            Some(_) => {
+                lru.remove(id);
+                None
+            }
+            None => None,
+        };
+
+        match found {
+            Some(item) => {
+                lru.touch(id);
+                counter!(T::HITS_METRIC, 1);
+                Some(item)
+            }
+            None => {
+                counter!(T::MISSES_METRIC, 1);
+                None
+            }
+        }
+    }
+
+    pub fn put(&self, item: T) {
+        let mut lru = self.inner.lock();
+        if lru.max_entries == 0 || item.weight() > lru.max_bytes {
+            return;
+        }
+
+        let id = item.id().clone();
+        lru.remove(&id);
+        lru.tick += 1;
+        let tick = lru.tick;
+        lru.bytes += item.weight();
+        lru.usage.insert(tick, id.clone());
+        lru.entries.insert(id, (item, tick));
+
+        while lru.entries.len() > lru.max_entries || lru.bytes > lru.max_bytes {
+            let oldest = match lru.usage.values().next() {
+                Some(id) => id.clone(),
+                None => break,
+            };
+            lru.remove(&oldest);
+            counter!(T::EVICTIONS_METRIC, 1);
+        }
+    }
+
+    pub fn invalidate(&self, id: &T::Key) {
+        self.inner.lock().remove(id);
+    }
+}
+
+impl<T: Cacheable> Lru<T> {
+    fn touch(&mut self, id: &T::Key) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, used)) = self.entries.get_mut(id) {
+            self.usage.remove(used);
+            *used = tick;
+            self.usage.insert(tick, id.clone());
+        }
+    }
+
+    fn remove(&mut self, id: &T::Key) {
+        if let Some((item, used)) = self.entries.remove(id) {
+            self.usage.remove(&used);
+            self.bytes -= item.weight();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_offer::sample_offer_with_expiration as offer;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let now = Utc::now().naive_utc();
+        let cache = SubscriptionCache::<Offer>::new(2, usize::MAX);
+        let offers = (1..=3)
+            .map(|i| offer(now + Duration::minutes(i)))
+            .collect::<Vec<_>>();
+
+        cache.put(offers[0].clone());
+        cache.put(offers[1].clone());
+        assert!(cache.get(&offers[0].id, now).is_some());
+
+        cache.put(offers[2].clone());
+        assert!(cache.get(&offers[0].id, now).is_some());
+        assert!(cache.get(&offers[1].id, now).is_none());
+        assert!(cache.get(&offers[2].id, now).is_some());
+    }
+
+    #[test]
+    fn respects_memory_bound() {
+        let now = Utc::now().naive_utc();
+        let first = offer(now + Duration::minutes(1));
+        let second = offer(now + Duration::minutes(2));
+        let cache = SubscriptionCache::<Offer>::new(10, first.weight() + second.weight() - 1);
+
+        cache.put(first.clone());
+        cache.put(second.clone());
+        assert!(cache.get(&first.id, now).is_none());
+        assert!(cache.get(&second.id, now).is_some());
+    }
+
+    #[test]
+    fn drops_expired_and_invalidated() {
+        let now = Utc::now().naive_utc();
+        let cache = SubscriptionCache::<Offer>::new(10, usize::MAX);
+        let expiring = offer(now + Duration::minutes(1));
+        let invalidated = offer(now + Duration::minutes(2));
+
+        cache.put(expiring.clone());
+        cache.put(invalidated.clone());
+        cache.invalidate(&invalidated.id);
+
+        assert!(cache.get(&expiring.id, now).is_some());
+        assert!(cache
+            .get(&expiring.id, now + Duration::minutes(5))
+            .is_none());
+        assert!(cache.get(&invalidated.id, now).is_none());
+        assert_eq!(cache.inner.lock().bytes, 0);
+    }
+}
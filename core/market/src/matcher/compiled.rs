@@ -0,0 +1,147 @@
+//! Compiled Offers and Demands reused by resolver.
+//!
+//! Each subscription is matched against every counterparty, so parsing its properties
+//! and constraints again for each pair dominated matcher CPU usage. Compiled form depends
+//! only on the content, so it is keyed by hash of properties and constraints and shared
+//! by subscriptions with the same content.
+use chrono::{NaiveDateTime, Utc};
+use digest::Digest;
+use sha3::Sha3_256;
+use std::sync::Arc;
+
+use ya_market_resolver::{match_compiled, CompiledSubscription, Match, MatchError};
+
+use crate::config::SubscriptionConfig;
+use crate::db::model::{Demand, Offer};
+use crate::matcher::cache::{Cacheable, SubscriptionCache, ENTRY_OVERHEAD};
+
+#[derive(Clone)]
+pub struct Compiled {
+    hash: String,
+    /// Entry isn't needed after subscription, which it was compiled for, expires.
+    expiration_ts: NaiveDateTime,
+    weight: usize,
+    subscription: Arc<CompiledSubscription>,
+}
+
+impl Cacheable for Compiled {
+    type Key = String;
+
+    const HITS_METRIC: &'static str = "market.cache.compiled.hits";
+    const MISSES_METRIC: &'static str = "market.cache.compiled.misses";
+    const EVICTIONS_METRIC: &'static str = "market.cache.compiled.evictions";
+
+    fn id(&self) -> &String {
+        &self.hash
+    }
+
+    fn expiration_ts(&self) -> NaiveDateTime {
+        self.expiration_ts
+    }
+
+    fn weight(&self) -> usize {
+        self.weight
+    }
+}
+
+#[derive(Clone)]
+pub struct CompilationCache {
+    cache: SubscriptionCache<Compiled>,
+}
+
+impl CompilationCache {
+    pub fn new(config: &SubscriptionConfig) -> Self {
+        CompilationCache {
+            cache: SubscriptionCache::new(
+                config.compiled_cache_max_entries,
+                config.cache_max_bytes,
+            ),
+        }
+    }
+
+    pub fn match_demand_offer(&self, demand: &Demand, offer: &Offer) -> Result<Match, MatchError> {
+        let demand = self.compile(
+            &demand.properties,
+            &demand.constraints,
+            demand.expiration_ts,
+        )?;
+        let offer = self.compile(&offer.properties, &offer.constraints, offer.expiration_ts)?;
+        match_compiled(&demand, &offer)
+    }
+
+    fn compile(
+        &self,
+        properties: &str,
+        constraints: &str,
+        expiration_ts: NaiveDateTime,
+    ) -> Result<Arc<CompiledSubscription>, MatchError> {
+        let hash = content_hash(properties, constraints);
+        if let Some(compiled) = self.cache.get(&hash, Utc::now().naive_utc()) {
+            return Ok(compiled.subscription);
+        }
+
+        let subscription = Arc::new(CompiledSubscription::compile(properties, constraints)?);
+        self.cache.put(Compiled {
+            hash,
+            expiration_ts,
+            // Flattened properties and parsed expression take roughly twice the source size.
+            weight: ENTRY_OVERHEAD + 2 * (properties.len() + constraints.len()),
+            subscription: subscription.clone(),
+        });
+        Ok(subscription)
+    }
+}
+
+fn content_hash(properties: &str, constraints: &str) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.input(properties);
+    // Separator, so that content can't be shifted between properties and constraints.
+    hasher.input([0u8]);
+    hasher.input(constraints);
+    format!("{:x}", hasher.result())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_offer::{sample_demand, sample_offer};
+
+    fn cache(max_entries: usize) -> CompilationCache {
+        CompilationCache {
+            cache: SubscriptionCache::new(max_entries, usize::MAX),
+        }
+    }
+
+    #[test]
+    fn reuses_compiled_subscriptions() {
+        let cache = cache(10);
+        let (offer, demand) = (sample_offer(), sample_demand());
+
+        assert_eq!(
+            cache.match_demand_offer(&demand, &offer).unwrap(),
+            Match::Yes
+        );
+        let compiled = cache
+            .compile(&offer.properties, &offer.constraints, offer.expiration_ts)
+            .unwrap();
+        let again = cache
+            .compile(&offer.properties, &offer.constraints, offer.expiration_ts)
+            .unwrap();
+        assert!(Arc::ptr_eq(&compiled, &again));
+    }
+
+    #[test]
+    fn compiles_without_cache() {
+        let cache = cache(0);
+        let offer = sample_offer();
+
+        let compiled = cache
+            .compile(&offer.properties, &offer.constraints, offer.expiration_ts)
+            .unwrap();
+        let again = cache
+            .compile(&offer.properties, &offer.constraints, offer.expiration_ts)
+            .unwrap();
+        assert!(!Arc::ptr_eq(&compiled, &again));
+        assert_eq!(compiled, again);
+    }
+}
@@ -49,6 +49,10 @@ pub async fn bcast_offers_once(matcher: Matcher) {
 
         matcher.discovery.bcast_offers(offers_to_broadcast).await?;
 
+        // Nodes, which missed refresh broadcast, would keep old expiration otherwise.
+        let our_refreshes = matcher.get_our_offer_refreshes().await?;
+        matcher.discovery.bcast_refreshes(our_refreshes).await?;
+
         let end = Instant::now();
         counter!("market.offers.broadcasts", 1);
         timing!("market.offers.broadcasts.time", start, end);
@@ -1,6 +1,9 @@
+use ya_client::model::NodeId;
+
 use crate::db::model::{SubscriptionId, SubscriptionValidationError};
 use crate::db::DbError;
 use crate::identity::IdentityError;
+use crate::matcher::validation::ValidationError;
 use crate::protocol::discovery::error::DiscoveryInitError;
 
 #[derive(thiserror::Error, Debug)]
@@ -15,6 +18,10 @@ pub enum DemandError {
     Remove(DbError, SubscriptionId),
     #[error("Demand [{0}] not found.")]
     NotFound(SubscriptionId),
+    #[error("Delegated Demand owner [{0}] isn't identity of this node.")]
+    ForeignOwner(NodeId),
+    #[error("Failed to check Demand owner. Error: {0}.")]
+    Identity(#[from] IdentityError),
     #[error(transparent)]
     JsonObjectExpected(#[from] serde_json::error::Error),
 }
@@ -83,6 +90,10 @@ pub enum ModifyOfferError {
     Remove(DbError, SubscriptionId),
     #[error("Offer [{0}] marked as unsubscribed, but not removed")]
     UnsubscribedNotRemoved(SubscriptionId),
+    #[error("Offer [{0}] expiration can only be extended.")]
+    NotExtended(SubscriptionId),
+    #[error("Failed to refresh Offer [{1}]. Error: {0}")]
+    Refresh(DbError, SubscriptionId),
 }
 
 impl From<QueryOfferError> for ModifyOfferError {
@@ -109,6 +120,8 @@ pub enum MatcherError {
     SaveOffer(#[from] SaveOfferError),
     #[error(transparent)]
     ModifyOffer(#[from] ModifyOfferError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -0,0 +1,132 @@
+//! Filtering of propagated Offers by their source.
+//!
+//! Some nodes gossip garbage Offers, which bloat Offer store. Offers created by,
+//! or received from filtered nodes, as well as Offers from filtered subnets,
+//! are dropped before they reach database.
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::sync::{Arc, RwLock};
+
+use ya_client::model::NodeId;
+use ya_core_model::market::OfferFilterRule;
+
+use crate::db::dao::OfferFilterDao;
+use crate::db::model::Offer;
+use crate::db::{DbMixedExecutor, DbResult};
+
+const SUBNET_PROPERTY: &str = "golem.node.debug.subnet";
+
+#[derive(Clone, Default)]
+pub struct OfferFilter {
+    rules: Arc<RwLock<HashSet<OfferFilterRule>>>,
+}
+
+impl OfferFilter {
+    pub async fn load(&self, db: &DbMixedExecutor) -> DbResult<()> {
+        let rules = db
+            .as_dao::<OfferFilterDao>()
+            .list()
+            .await?
+            .into_iter()
+            .filter_map(|entry| {
+                OfferFilterRule::try_from(entry)
+                    .map_err(|e| log::warn!("Skipping Offer filter rule: {}", e))
+                    .ok()
+            })
+            .collect::<HashSet<_>>();
+        log::debug!("Loaded {} Offer filter rules.", rules.len());
+        *self.rules.write().unwrap() = rules;
+        Ok(())
+    }
+
+    pub async fn add(&self, db: &DbMixedExecutor, rule: OfferFilterRule) -> DbResult<()> {
+        db.as_dao::<OfferFilterDao>()
+            .insert(rule.clone().into())
+            .await?;
+        self.rules.write().unwrap().insert(rule);
+        Ok(())
+    }
+
+    /// Returns false, if rule didn't exist.
+    pub async fn remove(&self, db: &DbMixedExecutor, rule: OfferFilterRule) -> DbResult<bool> {
+        let removed = db
+            .as_dao::<OfferFilterDao>()
+            .remove(rule.clone().into())
+            .await?;
+        self.rules.write().unwrap().remove(&rule);
+        Ok(removed)
+    }
+
+    pub fn list(&self) -> Vec<OfferFilterRule> {
+        self.rules.read().unwrap().iter().cloned().collect()
+    }
+
+    pub fn blocks_node(&self, node_id: NodeId) -> bool {
+        self.rules
+            .read()
+            .unwrap()
+            .contains(&OfferFilterRule::Identity(node_id))
+    }
+
+    /// Returns rule, which the Offer violates.
+    pub fn check(&self, offer: &Offer, received_from: Option<NodeId>) -> Option<OfferFilterRule> {
+        let rules = self.rules.read().unwrap();
+        if rules.is_empty() {
+            return None;
+        }
+
+        received_from
+            .into_iter()
+            .chain(Some(offer.node_id))
+            .map(OfferFilterRule::Identity)
+            .chain(subnet(offer).map(OfferFilterRule::Subnet))
+            .find(|rule| rules.contains(rule))
+    }
+}
+
+fn subnet(offer: &Offer) -> Option<String> {
+    let properties: serde_json::Value = serde_json::from_str(&offer.properties).ok()?;
+    properties
+        .get(SUBNET_PROPERTY)?
+        .as_str()
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_offer::sample_offer;
+
+    fn filter(rules: Vec<OfferFilterRule>) -> OfferFilter {
+        OfferFilter {
+            rules: Arc::new(RwLock::new(rules.into_iter().collect())),
+        }
+    }
+
+    #[test]
+    fn offer_filter_rules() {
+        let offer = sample_offer();
+        let gossip: NodeId = "0xbabe000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+
+        assert_eq!(filter(vec![]).check(&offer, Some(gossip)), None);
+        assert_eq!(
+            filter(vec![OfferFilterRule::Subnet("other".to_string())]).check(&offer, Some(gossip)),
+            None
+        );
+
+        let rule = OfferFilterRule::Subnet("blaa".to_string());
+        assert_eq!(filter(vec![rule.clone()]).check(&offer, None), Some(rule));
+
+        let rule = OfferFilterRule::Identity(offer.node_id);
+        assert_eq!(filter(vec![rule.clone()]).check(&offer, None), Some(rule));
+
+        let rule = OfferFilterRule::Identity(gossip);
+        assert_eq!(
+            filter(vec![rule.clone()]).check(&offer, Some(gossip)),
+            Some(rule)
+        );
+        assert!(filter(vec![rule]).blocks_node(gossip));
+    }
+}
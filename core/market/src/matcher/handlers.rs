@@ -1,10 +1,16 @@
 //! Discovery protocol messages handlers
 use futures::prelude::*;
 use metrics::{counter, value};
+use std::collections::HashMap;
+
+use ya_client::model::NodeId;
 
 use crate::db::model::{Offer, SubscriptionId};
 use crate::matcher::error::ModifyOfferError;
-use crate::protocol::discovery::message::{QueryOffers, QueryOffersResult};
+use crate::protocol::discovery::message::{
+    OfferRefresh, OffersRefreshed, QueryOffers, QueryOffersExpiration, QueryOffersResult,
+    RefreshedOffersBcast,
+};
 use crate::protocol::discovery::{
     error::DiscoveryRemoteError,
     message::{OffersBcast, OffersRetrieved, RetrieveOffers, UnsubscribedOffersBcast},
@@ -15,9 +21,17 @@ use super::{resolver::Resolver, store::SubscriptionStore};
 /// Returns only those of input offers ids, that were not yet known.
 pub(super) async fn filter_out_known_offer_ids(
     store: SubscriptionStore,
-    _caller: String,
+    caller: String,
     msg: OffersBcast,
 ) -> Result<Vec<SubscriptionId>, ()> {
+    // Don't even retrieve Offers gossiped by filtered nodes.
+    if let Ok(caller) = caller.parse() {
+        if store.filter.blocks_node(caller) {
+            counter!("market.offers.filtered", msg.offer_ids.len() as u64);
+            return Ok(vec![]);
+        }
+    }
+
     // We shouldn't propagate Offer, if we already have it in our database.
     // Note that when we broadcast our Offer, it will reach us too, so it concerns
     // not only Offers from other nodes.
@@ -34,20 +48,35 @@ pub(super) async fn receive_remote_offers(
     caller: String,
     msg: OffersRetrieved,
 ) -> Result<Vec<SubscriptionId>, ()> {
+    let origin = caller.parse().ok();
+    let refreshes = msg
+        .refreshes
+        .into_iter()
+        .map(|refresh| (refresh.offer_id, refresh.expiration_ts))
+        .collect::<HashMap<_, _>>();
     let added_offers_ids = futures::stream::iter(msg.offers.into_iter())
-        .filter_map(|offer| {
+        .filter_map(|mut offer| {
             let resolver = resolver.clone();
+            if let Some(expiration_ts) = refreshes.get(&offer.id) {
+                offer.refresh(*expiration_ts);
+            }
             async move {
-                resolver
+                if let Some(rule) = resolver.store.filter.check(&offer, origin) {
+                    log::trace!("Dropping Offer [{}] matching filter: {}", offer.id, rule);
+                    counter!("market.offers.filtered", 1);
+                    return None;
+                }
+                let offer = resolver
                     .store
                     .save_offer(offer)
                     .await
-                    .map(|offer| {
-                        resolver.receive(&offer);
-                        offer.id
-                    })
                     .map_err(|e| log::info!("Skipping foreign Offer: {}", e))
-                    .ok()
+                    .ok()?;
+                if let Some(origin) = origin {
+                    resolver.store.save_offer_origin(&offer, origin).await;
+                }
+                resolver.receive(&offer);
+                Some(offer.id)
             }
         })
         .collect::<Vec<SubscriptionId>>()
@@ -76,8 +105,9 @@ pub(super) async fn get_local_offers(
         msg.offer_ids.len() as u64
     );
 
+    // Other nodes can validate Offers only with expiration set at subscription.
     match store.get_offers(msg.offer_ids).await {
-        Ok(offers) => Ok(offers),
+        Ok(offers) => Ok(offers.into_iter().map(Offer::into_subscribed).collect()),
         Err(e) => {
             log::error!("Failed to get batch offers. Error: {}", e);
             Err(DiscoveryRemoteError::InternalError(
@@ -152,3 +182,85 @@ pub(super) async fn receive_remote_offer_unsubscribes(
     }
     Ok(new_unsubscribes)
 }
+
+/// Returns only refreshes extending expiration of known active Offers, together
+/// with Offer owners, which can confirm them.
+pub(super) async fn filter_offer_refreshes(
+    store: SubscriptionStore,
+    caller: String,
+    msg: RefreshedOffersBcast,
+) -> Result<Vec<(NodeId, OfferRefresh)>, ()> {
+    if let Ok(caller) = caller.parse() {
+        if store.filter.blocks_node(caller) {
+            return Ok(vec![]);
+        }
+    }
+
+    Ok(futures::stream::iter(msg.refreshes.into_iter())
+        .filter_map(|refresh| {
+            let store = store.clone();
+            async move {
+                let offer = store.get_offer(&refresh.offer_id).await.ok()?;
+                match refresh.expiration_ts > offer.expiration_ts {
+                    true => Some((offer.node_id, refresh)),
+                    false => None,
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .await)
+}
+
+/// Returns only those of input refreshes, that were applied locally.
+pub(super) async fn receive_offer_refreshes(
+    store: SubscriptionStore,
+    caller: String,
+    msg: OffersRefreshed,
+) -> Result<Vec<OfferRefresh>, ()> {
+    let applied = futures::stream::iter(msg.refreshes.into_iter())
+        .filter_map(|refresh| {
+            let store = store.clone();
+            async move {
+                store
+                    .refresh_offer(&refresh.offer_id, refresh.expiration_ts)
+                    .await
+                    .map_err(|e| log::trace!("Offer refresh not applied: {}", e))
+                    .ok()?;
+                Some(refresh)
+            }
+        })
+        .collect::<Vec<OfferRefresh>>()
+        .await;
+
+    if !applied.is_empty() {
+        counter!("market.offers.refreshes.incoming", applied.len() as u64);
+        log::trace!(
+            "Received {} new Offer refreshes from [{}]",
+            applied.len(),
+            caller
+        );
+    }
+    Ok(applied)
+}
+
+pub(super) async fn get_local_offers_expiration(
+    store: SubscriptionStore,
+    _caller: String,
+    msg: QueryOffersExpiration,
+) -> Result<Vec<OfferRefresh>, DiscoveryRemoteError> {
+    match store.get_offers(msg.offer_ids).await {
+        Ok(offers) => Ok(offers
+            .into_iter()
+            .map(|offer| OfferRefresh {
+                offer_id: offer.id,
+                expiration_ts: offer.expiration_ts,
+            })
+            .collect()),
+        Err(e) => {
+            log::error!("Failed to get offers expiration. Error: {}", e);
+            Err(DiscoveryRemoteError::InternalError(
+                "Failed to get offers from db.".to_string(),
+            ))
+        }
+    }
+}
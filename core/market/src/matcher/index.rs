@@ -0,0 +1,103 @@
+//! Offers indexed for matching incoming Demands.
+//!
+//! Offers seen by resolver are kept in typed property columns, so matching a Demand
+//! doesn't parse properties of every stored Offer again. Rows of unsubscribed and
+//! expired Offers are skipped and dropped, when the index is rebuilt.
+use std::collections::HashMap;
+
+use ya_market_resolver::{Demand as IndexedDemand, MatchError, Offer as IndexedOffer, OfferIndex};
+
+use crate::db::model::{Demand, Offer, SubscriptionId};
+
+/// Index isn't rebuilt below this size, even if most of its Offers are gone.
+const MIN_REBUILD_ROWS: usize = 1000;
+
+#[derive(Default)]
+pub struct OfferMatcher {
+    index: OfferIndex,
+    rows: HashMap<SubscriptionId, usize>,
+}
+
+impl OfferMatcher {
+    /// Offers from `offers`, which match `demand` (equivalent of `Match::Yes`).
+    pub fn matching(
+        &mut self,
+        offers: Vec<Offer>,
+        demand: &Demand,
+    ) -> Result<Vec<Offer>, MatchError> {
+        if self.index.len() > MIN_REBUILD_ROWS && self.index.len() > 2 * offers.len() {
+            log::debug!("Rebuilding Offer index with {} rows", self.index.len());
+            *self = Default::default();
+        }
+
+        let mut active = HashMap::with_capacity(offers.len());
+        for offer in offers {
+            let row = match self.rows.get(&offer.id) {
+                Some(row) => *row,
+                None => match IndexedOffer::from(&offer.properties, &offer.constraints)
+                    .and_then(|indexed| self.index.insert(indexed))
+                {
+                    Ok(row) => {
+                        self.rows.insert(offer.id.clone(), row);
+                        row
+                    }
+                    Err(e) => {
+                        log::warn!("Indexing Offer [{}] error: {}", offer.id, e);
+                        continue;
+                    }
+                },
+            };
+            active.insert(row, offer);
+        }
+
+        let demand = IndexedDemand::from(&demand.properties, &demand.constraints)?;
+        Ok(self
+            .index
+            .match_demand(&demand)?
+            .into_iter()
+            .filter_map(|row| active.remove(&row))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    use crate::testing::mock_offer::{generate_demand, generate_offer};
+
+    const OFFER_ID: &str = "c76161077d0343ab85ac986eb5f6ea38-edb0016d9f8bafb54540da34f05a8d510de8114488f23916276bdead05509a5";
+
+    fn offer(idx: u8, mem_gib: u32) -> Offer {
+        let mut offer = generate_offer(&format!("{OFFER_ID}{idx}"), future());
+        offer.properties = format!(r#"{{"golem": {{"inf": {{"mem": {{"gib": {mem_gib}}}}}}}}}"#);
+        offer
+    }
+
+    fn future() -> chrono::NaiveDateTime {
+        Utc::now().naive_utc() + Duration::hours(1)
+    }
+
+    #[test]
+    fn matches_only_active_offers() {
+        let mut demand = generate_demand(&format!("{OFFER_ID}9"), future());
+        demand.constraints = "(golem.inf.mem.gib>=2)".to_string();
+
+        let mut matcher = OfferMatcher::default();
+        let offers = vec![offer(1, 1), offer(2, 4), offer(3, 8)];
+        let matched = matcher.matching(offers.clone(), &demand).unwrap();
+        assert_eq!(
+            matched.iter().map(|o| o.id.clone()).collect::<Vec<_>>(),
+            vec![offers[1].id.clone(), offers[2].id.clone()]
+        );
+
+        // Offer 3 was unsubscribed, but is still in the index.
+        let matched = matcher.matching(offers[..2].to_vec(), &demand).unwrap();
+        assert_eq!(
+            matched.iter().map(|o| o.id.clone()).collect::<Vec<_>>(),
+            vec![offers[1].id.clone()]
+        );
+        assert_eq!(matcher.index.len(), 3);
+    }
+}
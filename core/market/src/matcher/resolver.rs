@@ -1,8 +1,12 @@
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
-use ya_market_resolver::{match_demand_offer, Match};
+use ya_market_resolver::Match;
 
+use super::compiled::CompilationCache;
+use super::index::OfferMatcher;
 use super::{error::ResolverError, RawProposal, SubscriptionStore};
+use crate::config::SubscriptionConfig;
 use crate::db::model::{Demand, Offer, SubscriptionId};
 
 #[derive(Clone, Debug, derive_more::Display)]
@@ -29,16 +33,24 @@ impl From<&Demand> for Subscription {
 #[derive(Clone)]
 pub struct Resolver {
     pub(crate) store: SubscriptionStore,
+    pub(crate) compiled: CompilationCache,
+    offers: Arc<Mutex<OfferMatcher>>,
     subscription_tx: UnboundedSender<Subscription>,
     proposal_tx: UnboundedSender<RawProposal>,
 }
 
 impl Resolver {
-    pub fn new(store: SubscriptionStore, proposal_tx: UnboundedSender<RawProposal>) -> Self {
+    pub fn new(
+        store: SubscriptionStore,
+        proposal_tx: UnboundedSender<RawProposal>,
+        config: &SubscriptionConfig,
+    ) -> Self {
         let (subscription_tx, subscription_rx) = unbounded_channel::<Subscription>();
 
         let myself = Resolver {
             store,
+            compiled: CompilationCache::new(config),
+            offers: Default::default(),
             subscription_tx,
             proposal_tx,
         };
@@ -79,17 +91,26 @@ impl Resolver {
                     .get_demands_before(offer.insertion_ts.unwrap())
                     .await?
                     .into_iter()
-                    .filter(|demand| matches(&offer, demand))
+                    .filter(|demand| matches(&self.compiled, &offer, demand))
                     .for_each(|demand| self.emit_proposal(offer.clone(), demand));
             }
             Subscription::Demand(id) => {
                 let demand = self.store.get_demand(id).await?;
-                self.store
+                let offers = self
+                    .store
                     .get_offers_before(demand.insertion_ts.unwrap())
                     .await?
                     .into_iter()
-                    .filter(|offer| matches(offer, &demand))
-                    .for_each(|offer| self.emit_proposal(offer, demand.clone()));
+                    // Private Offers are negotiated only directly.
+                    .filter(|offer| !offer.private && offer.node_id != demand.node_id)
+                    .collect();
+                let matching = self.offers.lock().unwrap().matching(offers, &demand);
+                match matching {
+                    Ok(offers) => offers
+                        .into_iter()
+                        .for_each(|offer| self.emit_proposal(offer, demand.clone())),
+                    Err(e) => log::warn!("Matching [{:?}] error: {}", demand, e),
+                }
             }
         }
         Ok(())
@@ -109,7 +130,7 @@ impl Resolver {
     }
 }
 
-fn matches(offer: &Offer, demand: &Demand) -> bool {
+pub(crate) fn matches(compiled: &CompilationCache, offer: &Offer, demand: &Demand) -> bool {
     if offer.node_id == demand.node_id {
         log::info!(
             "Rejecting Demand Offer pair from single identity. node_id: {}",
@@ -118,12 +139,7 @@ fn matches(offer: &Offer, demand: &Demand) -> bool {
         return false;
     }
 
-    match match_demand_offer(
-        &demand.properties,
-        &demand.constraints,
-        &offer.properties,
-        &offer.constraints,
-    ) {
+    match compiled.match_demand_offer(demand, offer) {
         Ok(Match::Yes) => true,
         Err(e) => {
             log::warn!("Matching [{:?}] vs [{:?}] error: {}", offer, demand, e);
@@ -135,11 +151,14 @@ fn matches(offer: &Offer, demand: &Demand) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use crate::config::Config;
+    use crate::matcher::compiled::CompilationCache;
     use crate::matcher::resolver::matches;
     use crate::testing::mock_offer::{sample_demand, sample_offer};
 
     #[test]
     fn matches_empty() {
-        assert!(matches(&sample_offer(), &sample_demand()))
+        let compiled = CompilationCache::new(&Config::from_env().unwrap().subscription);
+        assert!(matches(&compiled, &sample_offer(), &sample_demand()))
     }
 }
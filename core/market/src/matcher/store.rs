@@ -11,24 +11,33 @@ use crate::config::Config;
 use crate::db::dao::*;
 use crate::db::model::{Demand, Offer, SubscriptionId};
 use crate::db::DbMixedExecutor;
+use crate::matcher::cache::SubscriptionCache;
 use crate::matcher::error::{
     DemandError, ModifyOfferError, QueryDemandsError, QueryOfferError, QueryOffersError,
     SaveOfferError,
 };
+use crate::matcher::filter::OfferFilter;
 use crate::negotiation::ScannerSet;
-use crate::protocol::discovery::message::{QueryOffers, QueryOffersResult};
+use crate::protocol::discovery::message::{OfferRefresh, QueryOffers, QueryOffersResult};
 
 #[derive(Clone)]
 pub struct SubscriptionStore {
     pub(crate) db: DbMixedExecutor,
+    pub(crate) filter: OfferFilter,
+    offers: SubscriptionCache<Offer>,
+    demands: SubscriptionCache<Demand>,
     config: Arc<Config>,
     scan_set: Data<ScannerSet>,
 }
 
 impl SubscriptionStore {
     pub fn new(db: DbMixedExecutor, scan_set: Data<ScannerSet>, config: Arc<Config>) -> Self {
+        let cache = &config.subscription;
         SubscriptionStore {
             db,
+            filter: OfferFilter::default(),
+            offers: SubscriptionCache::new(cache.cache_max_entries, cache.cache_max_bytes),
+            demands: SubscriptionCache::new(cache.cache_max_entries, cache.cache_max_bytes),
             config,
             scan_set,
         }
@@ -39,11 +48,13 @@ impl SubscriptionStore {
         &self,
         id: &Identity,
         offer: &NewOffer,
+        private: bool,
     ) -> Result<Offer, SaveOfferError> {
         let creation_ts = Utc::now().naive_utc();
         // TODO: provider agent should set expiration.
         let expiration_ts = creation_ts + self.config.subscription.default_ttl;
-        let offer = Offer::from_new(offer, id, creation_ts, expiration_ts)?;
+        let mut offer = Offer::from_new(offer, id, creation_ts, expiration_ts)?;
+        offer.private = private;
         let r = self.insert_offer(offer).await;
         if r.is_ok() {
             self.scan_set.notify();
@@ -57,6 +68,19 @@ impl SubscriptionStore {
         self.insert_offer(offer).await
     }
 
+    /// Remembers node, from which foreign Offer was retrieved. Best effort,
+    /// used only for diagnostics.
+    pub async fn save_offer_origin(&self, offer: &Offer, received_from: NodeId) {
+        if let Err(e) = self
+            .db
+            .as_dao::<OfferDao>()
+            .put_origin(offer.origin(received_from))
+            .await
+        {
+            log::debug!("Failed to save origin of Offer [{}]: {}", offer.id, e);
+        }
+    }
+
     async fn insert_offer(&self, mut offer: Offer) -> Result<Offer, SaveOfferError> {
         // Insertions timestamp should always reference our local time
         // of adding it to database, so we must reset it here.
@@ -69,7 +93,10 @@ impl SubscriptionStore {
             .put(offer, Utc::now().naive_utc())
             .await
         {
-            Ok((true, OfferState::Active(offer))) => Ok(offer),
+            Ok((true, OfferState::Active(offer))) => {
+                self.offers.put(offer.clone());
+                Ok(offer)
+            }
             Ok((false, OfferState::Active(_))) => Err(SaveOfferError::Exists(id)),
             Ok((false, OfferState::Unsubscribed(_))) => Err(SaveOfferError::Unsubscribed(id)),
             Ok((_, OfferState::Expired(_))) => Err(SaveOfferError::Expired(id)),
@@ -209,9 +236,16 @@ impl SubscriptionStore {
 
     pub async fn get_offer(&self, id: &SubscriptionId) -> Result<Offer, QueryOfferError> {
         let now = Utc::now().naive_utc();
+        if let Some(offer) = self.offers.get(id, now) {
+            return Ok(offer);
+        }
+
         match self.db.as_dao::<OfferDao>().get_state(id, now).await {
             Err(e) => Err(QueryOfferError::Get(e, id.clone())),
-            Ok(OfferState::Active(offer)) => Ok(offer),
+            Ok(OfferState::Active(offer)) => {
+                self.offers.put(offer.clone());
+                Ok(offer)
+            }
             Ok(OfferState::Unsubscribed(_)) => Err(QueryOfferError::Unsubscribed(id.clone())),
             Ok(OfferState::Expired(_)) => Err(QueryOfferError::Expired(id.clone())),
             Ok(OfferState::NotFound) => Err(QueryOfferError::NotFound(id.clone())),
@@ -219,10 +253,14 @@ impl SubscriptionStore {
     }
 
     async fn mark_offer_unsubscribed(&self, id: &SubscriptionId) -> Result<(), ModifyOfferError> {
-        self.db
+        let result = self
+            .db
             .as_dao::<OfferDao>()
             .unsubscribe(id, Utc::now().naive_utc())
-            .await
+            .await;
+        // Invalidated after commit, so concurrent lookup can't cache the old state again.
+        self.offers.invalidate(id);
+        result
             .map_err(|e| ModifyOfferError::Unsubscribe(e, id.clone()))
             .and_then(|state| match state {
                 OfferState::Active(_) => Ok(()),
@@ -234,6 +272,53 @@ impl SubscriptionStore {
             })
     }
 
+    /// Extends Offer expiration. Subscription id stays the same.
+    pub async fn refresh_offer(
+        &self,
+        offer_id: &SubscriptionId,
+        expiration_ts: NaiveDateTime,
+    ) -> Result<Offer, ModifyOfferError> {
+        let result = self
+            .db
+            .as_dao::<OfferDao>()
+            .refresh(offer_id, expiration_ts, Utc::now().naive_utc())
+            .await;
+        self.offers.invalidate(offer_id);
+        match result {
+            Ok((true, OfferState::Active(offer))) => {
+                self.offers.put(offer.clone());
+                Ok(offer)
+            }
+            Ok((false, OfferState::Active(_))) => {
+                Err(ModifyOfferError::NotExtended(offer_id.clone()))
+            }
+            Ok((_, OfferState::Unsubscribed(_))) => {
+                Err(ModifyOfferError::AlreadyUnsubscribed(offer_id.clone()))
+            }
+            Ok((_, OfferState::Expired(_))) => Err(ModifyOfferError::Expired(offer_id.clone())),
+            Ok((_, OfferState::NotFound)) => Err(ModifyOfferError::NotFound(offer_id.clone())),
+            Err(e) => Err(ModifyOfferError::Refresh(e, offer_id.clone())),
+        }
+    }
+
+    /// Returns current expiration of active refreshed Offers.
+    pub async fn get_offer_refreshes(
+        &self,
+        node_ids: Option<Vec<NodeId>>,
+    ) -> Result<Vec<OfferRefresh>, QueryOffersError> {
+        Ok(self
+            .db
+            .as_dao::<OfferDao>()
+            .get_refreshed_offers(node_ids, Utc::now().naive_utc())
+            .await?
+            .into_iter()
+            .map(|offer| OfferRefresh {
+                offer_id: offer.id,
+                expiration_ts: offer.expiration_ts,
+            })
+            .collect())
+    }
+
     /// Local Offers are kept after unsubscribe. Offers from other nodes are removed.
     pub async fn unsubscribe_offer(
         &self,
@@ -288,9 +373,16 @@ impl SubscriptionStore {
     }
 
     pub async fn get_demand(&self, id: &SubscriptionId) -> Result<Demand, DemandError> {
+        if let Some(demand) = self.demands.get(id, Utc::now().naive_utc()) {
+            return Ok(demand);
+        }
+
         match self.db.as_dao::<DemandDao>().select(id).await {
             Err(e) => Err(DemandError::GetSingle(e, id.clone())),
-            Ok(Some(demand)) => Ok(demand),
+            Ok(Some(demand)) => {
+                self.demands.put(demand.clone());
+                Ok(demand)
+            }
             Ok(None) => Err(DemandError::NotFound(id.clone())),
         }
     }
@@ -337,13 +429,9 @@ impl SubscriptionStore {
             return Err(DemandError::NotFound(demand_id.clone()));
         }
 
-        match self
-            .db
-            .as_dao::<DemandDao>()
-            .delete(demand_id)
-            .await
-            .map_err(|e| DemandError::Remove(e, demand_id.clone()))?
-        {
+        let result = self.db.as_dao::<DemandDao>().delete(demand_id).await;
+        self.demands.invalidate(demand_id);
+        match result.map_err(|e| DemandError::Remove(e, demand_id.clone()))? {
             true => Ok(()),
             false => Err(DemandError::NotFound(demand_id.clone())),
         }
@@ -0,0 +1,424 @@
+//! Validation of Offer and Demand content executed at subscription time.
+//!
+//! Each `SubscriptionValidator` plugin checks part of the properties namespace
+//! and reports issues. Errors reject subscription (unless validation mode is relaxed),
+//! warnings are only logged and returned by validate-only endpoints.
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+use strum_macros::{Display, EnumString, EnumVariantNames};
+
+use ya_agreement_utils::agreement::flatten;
+use ya_market_resolver::resolver::ldap_parser;
+use ya_market_resolver::resolver::lint::{check_constraints, SyntaxError};
+use ya_property_schema::{Registry, SchemaIssue};
+
+/// Properties namespaces defined by Golem standards.
+const KNOWN_NAMESPACES: &[&str] = &[
+    "golem.activity",
+    "golem.com",
+    "golem.inf",
+    "golem.node",
+    "golem.runtime",
+    "golem.srv",
+    "golem.!exp",
+];
+
+const PAYMENT_PLATFORM_PREFIX: &str = "golem.com.payment.platform.";
+
+/// Unknown `golem` properties within this edit distance from a property
+/// in the schema registry are reported as probably misspelled.
+const MAX_MISSPELLING_DISTANCE: usize = 2;
+
+lazy_static! {
+    static ref PAYMENT_PLATFORM: Regex = Regex::new(r"^[a-z0-9]+(-[a-z0-9]+){1,2}$").unwrap();
+    static ref ETH_ADDRESS: Regex = Regex::new(r"^0x[0-9a-fA-F]{40}$").unwrap();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum ValidationMode {
+    /// Subscriptions with errors are rejected.
+    Reject,
+    /// Errors are only logged.
+    Warn,
+    /// Validation is skipped.
+    Off,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidationIssue {
+    pub property: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.property, self.message)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn error(&mut self, property: impl ToString, message: impl ToString) {
+        self.push(property, Severity::Error, message);
+    }
+
+    pub fn warning(&mut self, property: impl ToString, message: impl ToString) {
+        self.push(property, Severity::Warning, message);
+    }
+
+    fn push(&mut self, property: impl ToString, severity: Severity, message: impl ToString) {
+        self.issues.push(ValidationIssue {
+            property: property.to_string(),
+            severity,
+            message: message.to_string(),
+        });
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid {kind}: {}.", issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct ValidationError {
+    pub kind: &'static str,
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// Validation plugin checking Offer or Demand content.
+pub trait SubscriptionValidator: Send + Sync {
+    fn validate(
+        &self,
+        properties: &Map<String, Value>,
+        constraints: &str,
+        report: &mut ValidationReport,
+    );
+}
+
+/// Set of validation plugins run on every subscribed Offer and Demand.
+#[derive(Clone)]
+pub struct Validators {
+    mode: ValidationMode,
+    plugins: Vec<Arc<dyn SubscriptionValidator>>,
+}
+
+impl Validators {
+    /// Validators checking standard Golem namespaces.
+    pub fn new(mode: ValidationMode) -> Self {
+        Validators {
+            mode,
+            plugins: vec![
+                Arc::new(NamespaceValidator),
+                Arc::new(SchemaValidator),
+                Arc::new(PaymentPlatformValidator),
+                Arc::new(ConstraintsValidator),
+            ],
+        }
+    }
+
+    pub fn register(&mut self, plugin: Arc<dyn SubscriptionValidator>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Runs all plugins regardless of validation mode.
+    pub fn validate(&self, properties: &Value, constraints: &str) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        match properties {
+            Value::Object(_) => {
+                let properties = flatten(properties.clone());
+                for plugin in &self.plugins {
+                    plugin.validate(&properties, constraints, &mut report);
+                }
+            }
+            _ => report.error("properties", "JSON object expected"),
+        }
+        report.valid = report.errors().next().is_none();
+        report
+    }
+
+    /// Validates subscription content according to validation mode.
+    pub fn check(
+        &self,
+        kind: &'static str,
+        properties: &Value,
+        constraints: &str,
+    ) -> Result<(), ValidationError> {
+        if self.mode == ValidationMode::Off {
+            return Ok(());
+        }
+
+        let report = self.validate(properties, constraints);
+        for issue in &report.issues {
+            log::warn!("Invalid {} content. {}", kind, issue);
+        }
+
+        if report.valid || self.mode == ValidationMode::Warn {
+            return Ok(());
+        }
+        Err(ValidationError {
+            kind,
+            issues: report.errors().cloned().collect(),
+        })
+    }
+}
+
+/// Warns about properties in `golem` namespaces not defined by standards.
+struct NamespaceValidator;
+
+impl SubscriptionValidator for NamespaceValidator {
+    fn validate(&self, properties: &Map<String, Value>, _: &str, report: &mut ValidationReport) {
+        for name in properties.keys().filter(|name| name.starts_with("golem.")) {
+            let known = KNOWN_NAMESPACES
+                .iter()
+                .any(|ns| name.starts_with(ns) && name[ns.len()..].starts_with('.'));
+            if !known {
+                report.warning(name, "unknown namespace");
+            }
+        }
+    }
+}
+
+/// Checks types of properties defined in the schema registry and warns about deprecated ones.
+struct SchemaValidator;
+
+impl SubscriptionValidator for SchemaValidator {
+    fn validate(&self, properties: &Map<String, Value>, _: &str, report: &mut ValidationReport) {
+        let registry = Registry::golem();
+        for (name, value) in properties {
+            for issue in registry.check(name, value) {
+                match issue {
+                    SchemaIssue::TypeMismatch(_) => report.error(name, issue),
+                    SchemaIssue::Deprecated(_) => report.warning(name, issue),
+                }
+            }
+        }
+    }
+}
+
+/// Checks `golem.com.payment.platform.<driver>-<network>-<token>.address` syntax.
+/// Network is optional, as in `dummy-glm`.
+struct PaymentPlatformValidator;
+
+impl SubscriptionValidator for PaymentPlatformValidator {
+    fn validate(&self, properties: &Map<String, Value>, _: &str, report: &mut ValidationReport) {
+        for (name, value) in properties {
+            let platform_property = match name.strip_prefix(PAYMENT_PLATFORM_PREFIX) {
+                Some(property) => property,
+                None => continue,
+            };
+            let platform = match platform_property.strip_suffix(".address") {
+                Some(platform) => platform,
+                None => continue,
+            };
+
+            if !PAYMENT_PLATFORM.is_match(platform) {
+                report.error(
+                    name,
+                    format!("invalid payment platform `{platform}`, `<driver>[-<network>]-<token>` expected"),
+                );
+            }
+            if !value
+                .as_str()
+                .map(|a| ETH_ADDRESS.is_match(a))
+                .unwrap_or(false)
+            {
+                report.error(name, "invalid address, 0x prefixed hex address expected");
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Misspelling {
+    pub property: String,
+    pub suggestion: String,
+}
+
+/// Result of constraints linting.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConstraintsLint {
+    pub valid: bool,
+    pub error: Option<SyntaxError>,
+    /// Referenced properties in order of appearance.
+    pub properties: Vec<String>,
+    pub namespaces: BTreeSet<String>,
+    pub misspelled: Vec<Misspelling>,
+}
+
+/// Checks syntax of constraints and flags referenced `golem` properties,
+/// which are probably misspelled names of properties from the schema registry.
+pub fn lint_constraints(constraints: &str) -> ConstraintsLint {
+    let properties = match check_constraints(constraints) {
+        Ok(properties) => properties,
+        Err(error) => {
+            return ConstraintsLint {
+                error: Some(error),
+                ..Default::default()
+            }
+        }
+    };
+
+    let registry = Registry::golem();
+    let namespaces = properties.iter().map(|name| namespace(name)).collect();
+    let misspelled = properties
+        .iter()
+        .filter(|name| name.starts_with("golem.") && registry.get(name).is_none())
+        .filter_map(|name| {
+            registry
+                .definitions()
+                .map(|definition| (edit_distance(name, definition.name), definition.name))
+                .filter(|(distance, _)| *distance <= MAX_MISSPELLING_DISTANCE)
+                .min()
+                .map(|(_, suggestion)| Misspelling {
+                    property: name.clone(),
+                    suggestion: suggestion.to_string(),
+                })
+        })
+        .collect();
+
+    ConstraintsLint {
+        valid: true,
+        error: None,
+        properties,
+        namespaces,
+        misspelled,
+    }
+}
+
+/// Two leading segments of `golem` properties (e.g. `golem.inf`), first segment of others.
+fn namespace(property: &str) -> String {
+    let segments = match property.starts_with("golem.") {
+        true => 2,
+        false => 1,
+    };
+    property
+        .split('.')
+        .take(segments)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Levenshtein distance.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Checks if constraints can be parsed.
+struct ConstraintsValidator;
+
+impl SubscriptionValidator for ConstraintsValidator {
+    fn validate(&self, _: &Map<String, Value>, constraints: &str, report: &mut ValidationReport) {
+        if constraints.trim().is_empty() {
+            return;
+        }
+        if let Err(e) = ldap_parser::parse(constraints) {
+            report.error("constraints", format!("parse error: {e}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_malformed_properties() {
+        let validators = Validators::new(ValidationMode::Reject);
+        let properties = json!({
+            "golem.inf.cpu.threads": "four",
+            "golem.inf.mem.gib": 0.5,
+            "golem.com.payment.platform.erc20-holesky-tglm.address": "0x95369fc6fd02afeca110b9c32a21fb8ad899ee0a",
+            "golem.com.payment.platform.erc20.address": "0x95369fc6fd02afeca110b9c32a21fb8ad899ee0a",
+            "golem.com.payment.platform.dummy-glm.address": "0x95369fc6fd02afeca110b9c32a21fb8ad899ee0a",
+            "golem.unknown.prop": 1,
+            "golem.com.scheme.payu.interval_sec": 120,
+        });
+
+        let report = validators.validate(&properties, "(golem.inf.mem.gib>=0.5)");
+        let mut errors = report
+            .errors()
+            .map(|e| e.property.as_str())
+            .collect::<Vec<_>>();
+        errors.sort();
+        assert!(!report.valid);
+        assert_eq!(
+            errors,
+            vec![
+                "golem.com.payment.platform.erc20.address",
+                "golem.inf.cpu.threads"
+            ]
+        );
+        assert_eq!(report.issues.len(), 4);
+
+        assert!(validators.check("Offer", &properties, "").is_err());
+        assert!(Validators::new(ValidationMode::Warn)
+            .check("Offer", &properties, "")
+            .is_ok());
+    }
+
+    #[test]
+    fn lints_constraints() {
+        let lint =
+            lint_constraints("(&(golem.inf.mem.gb>=0.5)(golem.inf.cpu.threads>=2)(foo=bar))");
+        assert!(lint.valid);
+        assert_eq!(
+            lint.properties,
+            vec!["golem.inf.mem.gb", "golem.inf.cpu.threads", "foo"]
+        );
+        assert_eq!(
+            lint.namespaces.into_iter().collect::<Vec<_>>(),
+            vec!["foo", "golem.inf"]
+        );
+        assert_eq!(lint.misspelled.len(), 1);
+        assert_eq!(lint.misspelled[0].property, "golem.inf.mem.gb");
+        assert_eq!(lint.misspelled[0].suggestion, "golem.inf.mem.gib");
+
+        let lint = lint_constraints("(&(golem.inf.mem.gib>=0.5)");
+        assert!(!lint.valid);
+        assert_eq!(lint.error.unwrap().position, 26);
+    }
+
+    #[test]
+    fn computes_edit_distance() {
+        assert_eq!(edit_distance("golem.inf.mem.gib", "golem.inf.mem.gib"), 0);
+        assert_eq!(edit_distance("golem.inf.mem.gb", "golem.inf.mem.gib"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
@@ -1,10 +1,14 @@
+pub(crate) mod blacklist;
 mod common;
 pub mod error;
+mod expiry;
 mod notifier;
 mod provider;
 mod requestor;
 mod scan;
+pub(crate) mod webhook;
 
+pub(crate) use expiry::expire_proposals_forever;
 pub use notifier::EventNotifier;
 pub use provider::{ApprovalResult, ProviderBroker};
 pub use requestor::{ApprovalStatus, RequestorBroker};
@@ -0,0 +1,278 @@
+//! Requestor side statistics of Provider failures.
+//!
+//! Requestor reports failures of Providers (activity crashes, timeouts, invalid results),
+//! Agreement approval timeouts are recorded automatically. Provider, which fails too often
+//! within the tracking window, is blacklisted for a cooldown period and Proposals from its
+//! Offers aren't generated, until the cooldown ends.
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+use ya_client::model::NodeId;
+
+use crate::config::BlacklistConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureKind {
+    ActivityCrash,
+    Timeout,
+    InvalidResult,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureReport {
+    pub kind: FailureKind,
+    pub agreement_id: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Thresholds of automatic blacklisting. Disabled if `max_failures` is 0.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlacklistSettings {
+    pub max_failures: u32,
+    pub window_sec: u64,
+    pub cooldown_sec: u64,
+}
+
+impl From<&BlacklistConfig> for BlacklistSettings {
+    fn from(config: &BlacklistConfig) -> Self {
+        BlacklistSettings {
+            max_failures: config.max_failures,
+            window_sec: config.window.as_secs(),
+            cooldown_sec: config.cooldown.as_secs(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderFailureStats {
+    pub provider_id: NodeId,
+    pub activity_crashes: u32,
+    pub timeouts: u32,
+    pub invalid_results: u32,
+    /// Failures within the tracking window.
+    pub recent_failures: u32,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_message: Option<String>,
+    pub blacklisted_until: Option<DateTime<Utc>>,
+}
+
+struct ProviderRecord {
+    stats: ProviderFailureStats,
+    recent: VecDeque<DateTime<Utc>>,
+}
+
+impl ProviderRecord {
+    fn new(provider_id: NodeId) -> Self {
+        ProviderRecord {
+            stats: ProviderFailureStats {
+                provider_id,
+                activity_crashes: 0,
+                timeouts: 0,
+                invalid_results: 0,
+                recent_failures: 0,
+                last_failure: None,
+                last_message: None,
+                blacklisted_until: None,
+            },
+            recent: VecDeque::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    providers: HashMap<NodeId, ProviderRecord>,
+}
+
+#[derive(Clone)]
+pub struct ProviderBlacklist {
+    settings: Arc<RwLock<BlacklistSettings>>,
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl ProviderBlacklist {
+    pub fn new(settings: BlacklistSettings) -> Self {
+        ProviderBlacklist {
+            settings: Arc::new(RwLock::new(settings)),
+            inner: Default::default(),
+        }
+    }
+
+    pub fn settings(&self) -> BlacklistSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// New settings apply to failures reported from now on.
+    pub fn set_settings(&self, settings: BlacklistSettings) {
+        *self.settings.write().unwrap() = settings;
+    }
+
+    /// Returns updated statistics of the Provider.
+    pub fn report(&self, provider_id: NodeId, report: FailureReport) -> ProviderFailureStats {
+        self.report_at(provider_id, report, Utc::now())
+    }
+
+    fn report_at(
+        &self,
+        provider_id: NodeId,
+        report: FailureReport,
+        now: DateTime<Utc>,
+    ) -> ProviderFailureStats {
+        log::debug!(
+            "Provider [{}] failure {:?} reported (Agreement [{}]): {}",
+            provider_id,
+            report.kind,
+            report.agreement_id.as_deref().unwrap_or("-"),
+            report.message.as_deref().unwrap_or_default()
+        );
+        let settings = self.settings();
+        let mut inner = self.inner.write().unwrap();
+        let record = inner
+            .providers
+            .entry(provider_id)
+            .or_insert_with(|| ProviderRecord::new(provider_id));
+        let stats = &mut record.stats;
+
+        match report.kind {
+            FailureKind::ActivityCrash => stats.activity_crashes += 1,
+            FailureKind::Timeout => stats.timeouts += 1,
+            FailureKind::InvalidResult => stats.invalid_results += 1,
+        }
+        stats.last_failure = Some(now);
+        stats.last_message = report.message;
+
+        let window_start = now - Duration::seconds(settings.window_sec as i64);
+        record.recent.push_back(now);
+        while matches!(record.recent.front(), Some(ts) if *ts <= window_start) {
+            record.recent.pop_front();
+        }
+        stats.recent_failures = record.recent.len() as u32;
+
+        if settings.max_failures > 0 && stats.recent_failures >= settings.max_failures {
+            let until = now + Duration::seconds(settings.cooldown_sec as i64);
+            log::info!(
+                "Provider [{}] blacklisted until {} after {} failures.",
+                provider_id,
+                until,
+                stats.recent_failures
+            );
+            stats.blacklisted_until = Some(until);
+            // Next blacklisting requires new failures after the cooldown.
+            record.recent.clear();
+        }
+        stats.clone()
+    }
+
+    pub fn is_blacklisted(&self, provider_id: &NodeId) -> bool {
+        self.is_blacklisted_at(provider_id, Utc::now())
+    }
+
+    fn is_blacklisted_at(&self, provider_id: &NodeId, now: DateTime<Utc>) -> bool {
+        self.inner
+            .read()
+            .unwrap()
+            .providers
+            .get(provider_id)
+            .and_then(|record| record.stats.blacklisted_until)
+            .map(|until| until > now)
+            .unwrap_or(false)
+    }
+
+    /// Ends cooldown of the Provider and forgets its recent failures.
+    /// Returns false, if Provider wasn't blacklisted.
+    pub fn remove(&self, provider_id: &NodeId) -> bool {
+        let blacklisted = self.is_blacklisted(provider_id);
+        if let Some(record) = self.inner.write().unwrap().providers.get_mut(provider_id) {
+            record.stats.blacklisted_until = None;
+            record.stats.recent_failures = 0;
+            record.recent.clear();
+        }
+        blacklisted
+    }
+
+    pub fn list(&self, only_blacklisted: bool) -> Vec<ProviderFailureStats> {
+        let now = Utc::now();
+        let mut stats = self
+            .inner
+            .read()
+            .unwrap()
+            .providers
+            .values()
+            .map(|record| record.stats.clone())
+            .filter(|stats| {
+                !only_blacklisted || stats.blacklisted_until.map(|t| t > now).unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| b.last_failure.cmp(&a.last_failure));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(kind: FailureKind) -> FailureReport {
+        FailureReport {
+            kind,
+            agreement_id: None,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn blacklist_after_repeated_failures() {
+        let blacklist = ProviderBlacklist::new(BlacklistSettings {
+            max_failures: 2,
+            window_sec: 60,
+            cooldown_sec: 600,
+        });
+        let provider: NodeId = "0xbabe000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+        let start = Utc::now();
+
+        blacklist.report_at(provider, failure(FailureKind::Timeout), start);
+        // First failure is out of the window already.
+        let stats = blacklist.report_at(
+            provider,
+            failure(FailureKind::ActivityCrash),
+            start + Duration::seconds(61),
+        );
+        assert_eq!(stats.recent_failures, 1);
+        assert!(!blacklist.is_blacklisted_at(&provider, start + Duration::seconds(61)));
+
+        let now = start + Duration::seconds(62);
+        let stats = blacklist.report_at(provider, failure(FailureKind::InvalidResult), now);
+        assert_eq!((stats.timeouts, stats.activity_crashes), (1, 1));
+        assert_eq!(stats.invalid_results, 1);
+        assert_eq!(stats.blacklisted_until, Some(now + Duration::seconds(600)));
+        assert!(blacklist.is_blacklisted_at(&provider, now));
+        assert!(!blacklist.is_blacklisted_at(&provider, now + Duration::seconds(601)));
+    }
+
+    #[test]
+    fn blacklisting_disabled() {
+        let blacklist = ProviderBlacklist::new(BlacklistSettings {
+            max_failures: 0,
+            window_sec: 60,
+            cooldown_sec: 600,
+        });
+        let provider: NodeId = "0xcafe000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+        for _ in 0..10 {
+            blacklist.report(provider, failure(FailureKind::Timeout));
+        }
+        assert!(!blacklist.is_blacklisted(&provider));
+        assert!(!blacklist.remove(&provider));
+        assert_eq!(blacklist.list(false)[0].timeouts, 10);
+        assert!(blacklist.list(true).is_empty());
+    }
+}
@@ -6,6 +6,8 @@ use std::time::{Duration, Instant};
 
 use ya_client::model::market::{proposal::Proposal as ClientProposal, reason::Reason, NewProposal};
 use ya_client::model::NodeId;
+use ya_core_model::events::EventPayload;
+use ya_market_resolver::resolver::diff::{diff_proposals, ProposalDiff};
 use ya_market_resolver::{match_demand_offer, Match};
 use ya_service_api_web::middleware::Identity;
 
@@ -17,8 +19,8 @@ use crate::db::{
         TakeEventsError,
     },
     model::{
-        Agreement, AgreementEvent, AgreementId, AgreementState, AppSessionId, MarketEvent, Owner,
-        Proposal, ProposalId, ProposalState, SubscriptionId,
+        Agreement, AgreementEvent, AgreementId, AgreementState, AppSessionId, LabelSelector,
+        MarketEvent, Owner, Proposal, ProposalId, ProposalState, SubscriptionId,
     },
     DbMixedExecutor,
 };
@@ -30,11 +32,13 @@ use crate::matcher::{
 use crate::negotiation::error::RegenerateProposalError;
 use crate::negotiation::error::{NegotiationError, ProposalValidationError};
 use crate::negotiation::{
+    blacklist::ProviderBlacklist,
     error::{
         AgreementError, AgreementEventsError, GetProposalError, MatchValidationError,
         ProposalError, QueryEventsError,
     },
     notifier::NotifierError,
+    webhook::{WebhookDispatcher, WebhookEvent},
     EventNotifier,
 };
 use crate::protocol::negotiation::error::{CallerParseError, RejectProposalError};
@@ -59,8 +63,10 @@ pub struct CommonBroker {
     pub(super) negotiation_notifier: EventNotifier<SubscriptionId>,
     pub(super) session_notifier: EventNotifier<AppSessionId>,
     pub(super) agreement_notifier: EventNotifier<AgreementId>,
+    pub(super) webhooks: WebhookDispatcher,
     pub(super) config: Arc<Config>,
     pub(super) agreement_lock: AgreementLock,
+    pub(crate) blacklist: ProviderBlacklist,
 }
 
 impl CommonBroker {
@@ -72,6 +78,8 @@ impl CommonBroker {
     ) -> CommonBroker {
         CommonBroker {
             store,
+            webhooks: WebhookDispatcher::new(db.clone()),
+            blacklist: ProviderBlacklist::new((&config.blacklist).into()),
             db,
             negotiation_notifier: EventNotifier::default(),
             session_notifier,
@@ -229,6 +237,7 @@ impl CommonBroker {
         timeout: f32,
         max_events: Option<i32>,
         after_timestamp: DateTime<Utc>,
+        labels: &LabelSelector,
         id: &Identity,
     ) -> Result<Vec<AgreementEvent>, AgreementEventsError> {
         let mut timeout = Duration::from_secs_f32(timeout.max(0.0));
@@ -252,6 +261,7 @@ impl CommonBroker {
                     session_id,
                     max_events,
                     after_timestamp.naive_utc(),
+                    labels,
                 )
                 .await
                 .map_err(|e| AgreementEventsError::Internal(e.to_string()))?;
@@ -334,6 +344,28 @@ impl CommonBroker {
             })
     }
 
+    /// Changes of the Proposal relative to the previous one in the negotiation.
+    /// `None` for initial Proposals.
+    pub async fn proposal_diff(&self, proposal: &ClientProposal) -> Option<ProposalDiff> {
+        let prev_id = ProposalId::from_str(proposal.prev_proposal_id.as_ref()?).ok()?;
+        match self.get_client_proposal(None, &prev_id).await {
+            Ok(prev) => Some(diff_proposals(
+                &prev.properties,
+                &prev.constraints,
+                &proposal.properties,
+                &proposal.constraints,
+            )),
+            Err(e) => {
+                log::warn!(
+                    "Can't compute diff of Proposal [{}]: {}",
+                    proposal.proposal_id,
+                    e
+                );
+                None
+            }
+        }
+    }
+
     // Called locally via REST
     pub async fn terminate_agreement(
         &self,
@@ -590,6 +622,7 @@ impl CommonBroker {
                 SaveProposalError::AlreadyCountered(id) => {
                     RemoteProposalError::AlreadyCountered(id)
                 }
+                SaveProposalError::Expired(id) => RemoteProposalError::Expired(id),
                 _ => {
                     // TODO: Don't leak our database error, but send meaningful message as response.
                     let msg = format!("Failed saving Proposal [{}]: {}", proposal.body.id, e);
@@ -615,6 +648,13 @@ impl CommonBroker {
 
         // Send channel message to wake all query_events waiting for proposals.
         self.negotiation_notifier.notify(&subscription_id).await;
+        self.webhooks.emit(
+            &subscription_id,
+            WebhookEvent::ProposalReceived {
+                subscription_id: subscription_id.to_string(),
+                proposal_id: proposal.body.id.to_string(),
+            },
+        );
 
         match caller_role {
             Owner::Requestor => counter!("market.proposals.requestor.received", 1),
@@ -756,12 +796,35 @@ impl CommonBroker {
 
         // This notifies wait_for_agreement endpoint.
         self.agreement_notifier.notify(&agreement.id).await;
+
+        let node_id = match agreement.id.owner() {
+            Owner::Requestor => agreement.requestor_id,
+            Owner::Provider => agreement.provider_id,
+        };
+        ya_events::publish(
+            Some(node_id),
+            EventPayload::AgreementStateChanged {
+                agreement_id: agreement.id.into_client(),
+                state: agreement.state.to_string(),
+            },
+        );
+        self.webhooks.emit_agreement(agreement);
     }
 
     pub async fn generate_proposal(&self, proposal: RawProposal) -> Result<(), SaveProposalError> {
         let db = self.db.clone();
         let notifier = self.negotiation_notifier.clone();
 
+        if self.blacklist.is_blacklisted(&proposal.offer.node_id) {
+            log::debug!(
+                "Skipping Offer [{}] of blacklisted Provider [{}].",
+                proposal.offer.id,
+                proposal.offer.node_id
+            );
+            counter!("market.proposals.requestor.blacklisted", 1);
+            return Ok(());
+        }
+
         // Add proposal to database together with Negotiation record.
         let proposal = Proposal::new_requestor(proposal.demand, proposal.offer);
         let proposal = db
@@ -785,6 +848,13 @@ impl CommonBroker {
         // Send channel message to wake all query_events waiting for proposals.
         counter!("market.proposals.requestor.generated", 1);
         notifier.notify(&subscription_id).await;
+        self.webhooks.emit(
+            &subscription_id,
+            WebhookEvent::ProposalReceived {
+                subscription_id: subscription_id.to_string(),
+                proposal_id: proposal.body.id.to_string(),
+            },
+        );
         Ok(())
     }
 
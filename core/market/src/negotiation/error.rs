@@ -9,7 +9,8 @@ use ya_client::model::{ErrorMessage, NodeId};
 
 use crate::db::dao::AgreementDaoError;
 use crate::db::model::{
-    AgreementId, ProposalId, ProposalIdParseError, SubscriptionId, SubscriptionParseError,
+    AgreementId, LabelError, ProposalId, ProposalIdParseError, SubscriptionId,
+    SubscriptionParseError,
 };
 use crate::db::{
     dao::TakeEventsError,
@@ -93,6 +94,8 @@ pub enum AgreementError {
     Internal(String),
     #[error("Agreement [{0}] not terminated yet.")]
     NotTerminated(AgreementId),
+    #[error("Invalid Agreement labels. {0}")]
+    InvalidLabels(#[from] LabelError),
 }
 
 #[derive(Error, Debug)]
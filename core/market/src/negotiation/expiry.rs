@@ -0,0 +1,90 @@
+use chrono::Utc;
+use metrics::counter;
+use std::time::Duration;
+
+use ya_client::model::market::Reason;
+
+use crate::db::dao::{NegotiationEventsDao, ProposalDao};
+use crate::db::model::{Owner, Proposal};
+use crate::negotiation::common::CommonBroker;
+
+/// Periodically expires Draft Proposals, which weren't answered within `ttl`.
+///
+/// Both negotiating nodes store their copy of each Proposal with the same creation
+/// timestamp, so each side expires it independently and emits Proposal rejected
+/// event with expiration reason to its own subscription.
+pub(crate) async fn expire_proposals_forever(
+    provider: CommonBroker,
+    requestor: CommonBroker,
+    ttl: Duration,
+    interval: Duration,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        if let Err(e) = expire_proposals(&provider, &requestor, ttl).await {
+            log::warn!("Failed to expire stale Proposals: {e}");
+        }
+    }
+}
+
+async fn expire_proposals(
+    provider: &CommonBroker,
+    requestor: &CommonBroker,
+    ttl: Duration,
+) -> anyhow::Result<()> {
+    let created_before = Utc::now().naive_utc() - chrono::Duration::from_std(ttl)?;
+    let expired = provider
+        .db
+        .as_dao::<ProposalDao>()
+        .expire_drafts(created_before)
+        .await?;
+
+    for proposal in expired {
+        let broker = match proposal.body.id.owner() {
+            Owner::Provider => provider,
+            Owner::Requestor => requestor,
+        };
+        if let Err(e) = notify_expired(broker, &proposal, ttl).await {
+            log::warn!(
+                "Failed to add expiration event of Proposal [{}]: {e}",
+                proposal.body.id
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn notify_expired(
+    broker: &CommonBroker,
+    proposal: &Proposal,
+    ttl: Duration,
+) -> anyhow::Result<()> {
+    log::info!(
+        "Proposal [{}] wasn't answered within {}. Expiring.",
+        proposal.body.id,
+        humantime::format_duration(ttl)
+    );
+
+    let mut reason = Reason::new(format!(
+        "Proposal not answered within {}.",
+        humantime::format_duration(ttl)
+    ));
+    reason.extra = serde_json::json!({ "golem.market.code": "ProposalExpired" });
+
+    broker
+        .db
+        .as_dao::<NegotiationEventsDao>()
+        .add_proposal_rejected_event(proposal, Some(reason))
+        .await?;
+    broker
+        .negotiation_notifier
+        .notify(&proposal.negotiation.subscription_id)
+        .await;
+
+    match proposal.body.id.owner() {
+        Owner::Provider => counter!("market.proposals.provider.expired", 1),
+        Owner::Requestor => counter!("market.proposals.requestor.expired", 1),
+    };
+    Ok(())
+}
@@ -16,6 +16,7 @@ use crate::db::{
     DbMixedExecutor,
 };
 use crate::matcher::store::SubscriptionStore;
+use crate::protocol::negotiation::interest::InterestChallenge;
 use crate::protocol::negotiation::{error::*, messages::*, provider::NegotiationApi};
 
 use super::common::CommonBroker;
@@ -103,6 +104,7 @@ impl ProviderBroker {
         counter!("market.events.provider.queried", 0);
         counter!("market.events.provider.query", 0);
         counter!("market.proposals.provider.countered", 0);
+        counter!("market.proposals.provider.expired", 0);
         counter!("market.proposals.provider.init-negotiation", 0);
         counter!("market.proposals.provider.received", 0);
         counter!("market.proposals.provider.rejected.initial", 0);
@@ -494,14 +496,36 @@ async fn initial_proposal(
 
     // Check subscription.
     let offer = store.get_offer(&msg.offer_id).await?;
+    let caller_id = CommonBroker::parse_caller(&caller)?;
+
+    let difficulty = broker.config.negotiation.required_proof_of_interest;
+    if difficulty > 0 {
+        let challenge = InterestChallenge {
+            requestor_id: caller_id,
+            provider_id: offer.node_id,
+            offer_id: msg.offer_id.clone(),
+            demand_id: msg.demand_id.clone(),
+        };
+        let valid = msg
+            .proof
+            .as_ref()
+            .map(|proof| challenge.verify(proof, difficulty))
+            .unwrap_or(false);
+        if !valid {
+            counter!(
+                "market.proposals.provider.init-negotiation.rejected-proof",
+                1
+            );
+            log::debug!("Initial Proposal from [{caller_id}] without valid proof of interest.");
+            return Err(RemoteProposalError::ProofRequired(difficulty));
+        }
+    }
 
     // In this step we add Proposal, that was generated on Requestor by market.
     // This way we have the same state on Provider as on Requestor and we can use
     // the same function to handle this, as in normal counter_proposal flow.
     // TODO: Initial proposal id will differ on Requestor and Provider!! It isn't problem as long
     //  we don't log ids somewhere and try to compare between nodes.
-    let caller_id = CommonBroker::parse_caller(&caller)?;
-
     let proposal = Proposal::new_provider(&msg.demand_id, caller_id, offer);
     let proposal_id = proposal.body.id.clone();
     let proposal = db
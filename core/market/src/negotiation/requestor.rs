@@ -12,14 +12,16 @@ use ya_service_api_web::middleware::Identity;
 use ya_std_utils::LogErr;
 
 use crate::db::{
-    dao::{AgreementDao, AgreementDaoError, SaveAgreementError},
+    dao::{AgreementDao, AgreementDaoError, ProposalDao, SaveAgreementError, SaveProposalError},
     model::{Agreement, AgreementId, AgreementState, AppSessionId},
-    model::{Demand, Issuer, Owner, ProposalId, SubscriptionId},
+    model::{Demand, Issuer, Offer, Owner, Proposal, ProposalId, SubscriptionId},
     DbMixedExecutor,
 };
 use crate::matcher::{store::SubscriptionStore, RawProposal};
+use crate::protocol::negotiation::interest::{InterestChallenge, ProofOfInterest};
 use crate::protocol::negotiation::{error::*, messages::*, requestor::NegotiationApi};
 
+use super::blacklist::{FailureKind, FailureReport};
 use super::{common::*, error::*, notifier::NotifierError, EventNotifier};
 use crate::config::Config;
 use crate::db::dao::AgreementEventsDao;
@@ -91,6 +93,7 @@ impl RequestorBroker {
         // until first change to value will be made.
         counter!("market.agreements.events.queried", 0);
         counter!("market.agreements.requestor.approved", 0);
+        counter!("market.agreements.requestor.approval-timeout", 0);
         counter!("market.agreements.requestor.cancelled", 0);
         counter!("market.agreements.requestor.confirmed", 0);
         counter!("market.agreements.requestor.created", 0);
@@ -101,6 +104,7 @@ impl RequestorBroker {
         counter!("market.agreements.requestor.committing", 0);
         counter!("market.events.requestor.queried", 0);
         counter!("market.proposals.requestor.countered", 0);
+        counter!("market.proposals.requestor.expired", 0);
         counter!("market.proposals.requestor.generated", 0);
         counter!("market.proposals.requestor.received", 0);
         counter!("market.proposals.requestor.rejected.initial", 0);
@@ -130,6 +134,19 @@ impl RequestorBroker {
         self.common.unsubscribe(id).await
     }
 
+    /// Entry point of direct negotiation. Offer shared by the Provider out of band
+    /// isn't matched by Resolver, but becomes initial Proposal right away.
+    pub async fn negotiate_direct(
+        &self,
+        offer: Offer,
+        demand: Demand,
+    ) -> Result<(), SaveProposalError> {
+        counter!("market.proposals.requestor.direct", 1);
+        self.common
+            .generate_proposal(RawProposal { offer, demand })
+            .await
+    }
+
     pub async fn counter_proposal(
         &self,
         demand_id: &SubscriptionId,
@@ -154,7 +171,10 @@ impl RequestorBroker {
         // of these cases.
         log::debug!("proposal_id={proposal_id}, is_first={is_first}");
         match is_first {
-            true => self.api.initial_proposal(new_proposal).await,
+            true => {
+                let proof = self.proof_of_interest(&new_proposal).await;
+                self.api.initial_proposal(new_proposal, proof).await
+            }
             false => self.api.counter_proposal(new_proposal).await,
         }
         .map_err(|e| ProposalError::Send(prev_proposal_id.clone(), e))?;
@@ -174,6 +194,32 @@ impl RequestorBroker {
         Ok(proposal_id)
     }
 
+    /// Proof of interest attached to the first Proposal sent to Provider.
+    async fn proof_of_interest(&self, proposal: &Proposal) -> Option<ProofOfInterest> {
+        let difficulty = self.common.config.negotiation.proof_of_interest_difficulty;
+        if difficulty == 0 {
+            return None;
+        }
+
+        let challenge = InterestChallenge {
+            requestor_id: proposal.negotiation.requestor_id,
+            provider_id: proposal.negotiation.provider_id,
+            offer_id: proposal.negotiation.offer_id.clone(),
+            demand_id: proposal.negotiation.demand_id.clone(),
+        };
+        match tokio::task::spawn_blocking(move || challenge.solve(difficulty)).await {
+            Ok(proof) => Some(proof),
+            Err(e) => {
+                log::warn!(
+                    "Failed to compute proof of interest for Proposal [{}]: {}",
+                    proposal.body.id,
+                    e
+                );
+                None
+            }
+        }
+    }
+
     pub async fn reject_proposal(
         &self,
         demand_id: &SubscriptionId,
@@ -468,6 +514,15 @@ impl RequestorBroker {
                 .map_err(|e| AgreementError::UpdateState(agreement_id.clone(), e))?;
         }
 
+        if let Some(timeout) = self.common.config.negotiation.approval_timeout {
+            tokio::spawn(watch_approval_timeout(
+                self.common.clone(),
+                self.api.clone(),
+                agreement_id.clone(),
+                timeout,
+            ));
+        }
+
         counter!("market.agreements.requestor.confirmed", 1);
         tracing::event!(
             Level::INFO,
@@ -500,6 +555,167 @@ impl RequestorBroker {
     }
 }
 
+async fn watch_approval_timeout(
+    broker: CommonBroker,
+    api: NegotiationApi,
+    agreement_id: AgreementId,
+    timeout: Duration,
+) {
+    tokio::time::sleep(timeout).await;
+    if let Err(e) = approval_timeout(broker, api, &agreement_id, timeout).await {
+        log::warn!("Failed to handle approval timeout of Agreement [{agreement_id}]: {e}");
+    }
+}
+
+/// Cancels Agreement, which Provider didn't approve within `timeout`. Timed out Proposal
+/// is reverted to Draft and the newest Draft Proposal from other Provider negotiating
+/// on the same Demand is countered, so negotiations don't have to start from scratch.
+async fn approval_timeout(
+    broker: CommonBroker,
+    api: NegotiationApi,
+    agreement_id: &AgreementId,
+    timeout: Duration,
+) -> Result<(), AgreementError> {
+    let dao = broker.db.as_dao::<AgreementDao>();
+    let agreement = {
+        let _hold = broker.agreement_lock.lock(agreement_id).await;
+
+        let agreement = match dao
+            .select(agreement_id, None, Utc::now().naive_utc())
+            .await
+            .map_err(|e| AgreementError::Get(agreement_id.to_string(), e))?
+        {
+            Some(agreement) if agreement.state == AgreementState::Pending => agreement,
+            _ => return Ok(()),
+        };
+
+        log::info!(
+            "Provider [{}] didn't approve Agreement [{}] within {}.",
+            agreement.provider_id,
+            agreement.id,
+            humantime::format_duration(timeout)
+        );
+        broker.blacklist.report(
+            agreement.provider_id,
+            FailureReport {
+                kind: FailureKind::Timeout,
+                agreement_id: Some(agreement.id.to_string()),
+                message: Some("Agreement approval timeout".to_string()),
+            },
+        );
+
+        let countered = counter_next_proposal(&broker, &api, &agreement)
+            .await
+            .map_err(|e| {
+                log::info!(
+                    "Failed to counter next Proposal after approval timeout of Agreement [{}]: {}",
+                    agreement.id,
+                    e
+                )
+            })
+            .ok()
+            .flatten();
+
+        let mut reason = Reason::new(format!(
+            "Agreement not approved within {}.",
+            humantime::format_duration(timeout)
+        ));
+        reason.extra = serde_json::json!({
+            "golem.requestor.code": "ApprovalTimeout",
+            "golem.requestor.counterProposalId": countered,
+        });
+
+        // Provider is probably unreachable, so we cancel Agreement regardless
+        // of him receiving our message.
+        let timestamp = Utc::now().naive_utc();
+        api.cancel_agreement(&agreement, Some(reason.clone()), timestamp)
+            .await
+            .map_err(|e| {
+                log::debug!(
+                    "Failed to send cancellation of Agreement [{}] to Provider [{}]: {}",
+                    agreement.id,
+                    agreement.provider_id,
+                    e
+                )
+            })
+            .ok();
+
+        let agreement = dao
+            .cancel(&agreement.id, Some(reason.clone()), &timestamp)
+            .await
+            .map_err(|e| AgreementError::UpdateState(agreement.id.clone(), e))?;
+
+        broker
+            .db
+            .as_dao::<ProposalDao>()
+            .change_proposal_state(&agreement.offer_proposal_id, ProposalState::Draft)
+            .await
+            .map_err(|e| AgreementError::Internal(e.to_string()))?;
+
+        agreement
+    };
+
+    broker.notify_agreement(&agreement).await;
+
+    counter!("market.agreements.requestor.approval-timeout", 1);
+    tracing::event!(
+        Level::INFO,
+        entity = "agreement",
+        action = "approval-timeout",
+        agreement_id = display(&agreement.id),
+        provider_id = display(&agreement.provider_id),
+        "Requestor cancelled agreement not approved in time"
+    );
+    Ok(())
+}
+
+/// Counters the newest Draft Proposal negotiated on Agreement's Demand with other Provider,
+/// using the same content as the timed out Agreement. Returns countering Proposal id.
+async fn counter_next_proposal(
+    broker: &CommonBroker,
+    api: &NegotiationApi,
+    agreement: &Agreement,
+) -> anyhow::Result<Option<ProposalId>> {
+    let candidates = broker
+        .db
+        .as_dao::<ProposalDao>()
+        .list_draft_proposals(&agreement.demand_id, agreement.provider_id)
+        .await?;
+    let next = match candidates.into_iter().next() {
+        Some(proposal) => proposal,
+        None => return Ok(None),
+    };
+
+    let content = NewProposal {
+        properties: serde_json::from_str(&agreement.demand_properties)?,
+        constraints: agreement.demand_constraints.clone(),
+    };
+    let (new_proposal, _) = broker
+        .counter_proposal(
+            &agreement.demand_id,
+            &next.body.id,
+            &content,
+            &agreement.requestor_id,
+            Owner::Requestor,
+        )
+        .await?;
+
+    let proposal_id = new_proposal.body.id.clone();
+    api.counter_proposal(new_proposal).await?;
+
+    counter!("market.proposals.requestor.countered", 1);
+    tracing::event!(
+        Level::INFO,
+        entity = "proposal",
+        action = "counter",
+        proposal_id = display(&next.body.id),
+        demand_id = display(&agreement.demand_id),
+        new_proposal_id = display(&proposal_id),
+        "requestor countered proposal after approval timeout"
+    );
+    Ok(Some(proposal_id))
+}
+
 async fn on_agreement_approved(
     broker: CommonBroker,
     caller: String,
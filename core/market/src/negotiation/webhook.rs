@@ -0,0 +1,203 @@
+//! Webhooks for negotiation and Agreement events.
+//!
+//! Applications, which can't hold long-poll connections (e.g. serverless Requestors),
+//! register callback URLs for their subscriptions. Each event is stored as delivery
+//! before sending, so its status can be tracked and failed deliveries replayed.
+//! Deliveries still pending on shutdown are resumed, when market starts again.
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+use ya_events::webhook::{self, RetryPolicy, WebhookRequest};
+
+use crate::db::dao::WebhookDao;
+use crate::db::model::{Agreement, AgreementState, Owner, SubscriptionId, WebhookDelivery};
+use crate::db::DbMixedExecutor;
+
+const RETRY: RetryPolicy = RetryPolicy {
+    max_retries: 4,
+    retry_delay: Duration::from_secs(2),
+};
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, strum_macros::Display, strum_macros::EnumString,
+)]
+pub enum WebhookEventType {
+    ProposalReceived,
+    AgreementApproved,
+    AgreementTerminated,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "eventType")]
+pub enum WebhookEvent {
+    #[serde(rename_all = "camelCase")]
+    ProposalReceived {
+        subscription_id: String,
+        proposal_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    AgreementApproved { agreement_id: String },
+    #[serde(rename_all = "camelCase")]
+    AgreementTerminated { agreement_id: String },
+}
+
+impl WebhookEvent {
+    pub fn event_type(&self) -> WebhookEventType {
+        match self {
+            WebhookEvent::ProposalReceived { .. } => WebhookEventType::ProposalReceived,
+            WebhookEvent::AgreementApproved { .. } => WebhookEventType::AgreementApproved,
+            WebhookEvent::AgreementTerminated { .. } => WebhookEventType::AgreementTerminated,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookPayload {
+    event_date: DateTime<Utc>,
+    #[serde(flatten)]
+    event: WebhookEvent,
+}
+
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    db: DbMixedExecutor,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: DbMixedExecutor) -> Self {
+        WebhookDispatcher { db }
+    }
+
+    /// Sends event to all webhooks of subscription. Deliveries are enqueued in background,
+    /// so events neither slow down nor break negotiations.
+    pub fn emit(&self, subscription_id: &SubscriptionId, event: WebhookEvent) {
+        let event_type = event.event_type().to_string();
+        let payload = WebhookPayload {
+            event_date: Utc::now(),
+            event,
+        };
+        let payload = match serde_json::to_string(&payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::warn!("Failed to serialize webhook event {}: {}", event_type, e);
+                return;
+            }
+        };
+
+        let dispatcher = self.clone();
+        let subscription_id = subscription_id.clone();
+        tokio::task::spawn_local(async move {
+            match dispatcher
+                .db
+                .as_dao::<WebhookDao>()
+                .enqueue(&subscription_id, &event_type, payload)
+                .await
+            {
+                Ok(deliveries) => {
+                    for (delivery, url) in deliveries {
+                        dispatcher.spawn_delivery(delivery, url);
+                    }
+                }
+                Err(e) => log::warn!(
+                    "Failed to enqueue webhook event {} of subscription [{}]: {}",
+                    event_type,
+                    subscription_id,
+                    e
+                ),
+            }
+        });
+    }
+
+    /// Emits Agreement events to webhooks of our subscription, which the Agreement
+    /// was created from.
+    pub fn emit_agreement(&self, agreement: &Agreement) {
+        let agreement_id = agreement.id.into_client();
+        let event = match agreement.state {
+            AgreementState::Approved => WebhookEvent::AgreementApproved { agreement_id },
+            AgreementState::Terminated => WebhookEvent::AgreementTerminated { agreement_id },
+            _ => return,
+        };
+        let subscription_id = match agreement.id.owner() {
+            Owner::Requestor => &agreement.demand_id,
+            Owner::Provider => &agreement.offer_id,
+        };
+        self.emit(subscription_id, event)
+    }
+
+    /// Resumes deliveries, which were pending, when market was stopped.
+    pub async fn resume_pending(&self) {
+        match self.db.as_dao::<WebhookDao>().pending().await {
+            Ok(deliveries) => {
+                if !deliveries.is_empty() {
+                    log::info!("Resuming {} pending webhook deliveries.", deliveries.len());
+                }
+                for (delivery, url) in deliveries {
+                    self.spawn_delivery(delivery, url);
+                }
+            }
+            Err(e) => log::warn!("Failed to load pending webhook deliveries: {}", e),
+        }
+    }
+
+    pub fn spawn_delivery(&self, delivery: WebhookDelivery, url: String) {
+        let db = self.db.clone();
+        tokio::task::spawn_local(async move { deliver(db, delivery, url).await });
+    }
+}
+
+async fn deliver(db: DbMixedExecutor, delivery: WebhookDelivery, url: String) {
+    let request = WebhookRequest::new(
+        url,
+        delivery.event_type.clone(),
+        delivery.payload.clone().into_bytes(),
+        None,
+    );
+    webhook::deliver(request, RETRY, |_, result, last| {
+        let db = db.clone();
+        let delivery_id = delivery.id.clone();
+        async move {
+            let result = result
+                .map(|_| Utc::now().naive_utc())
+                .map_err(|e| e.to_string());
+            if let Err(e) = db
+                .as_dao::<WebhookDao>()
+                .record_attempt(&delivery_id, result, last)
+                .await
+            {
+                log::warn!(
+                    "Failed to record webhook delivery [{}] attempt: {}",
+                    delivery_id,
+                    e
+                );
+            }
+        }
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn webhook_payload() {
+        let payload = WebhookPayload {
+            event_date: Utc::now(),
+            event: WebhookEvent::AgreementApproved {
+                agreement_id: "agreement".to_string(),
+            },
+        };
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["eventType"], "AgreementApproved");
+        assert_eq!(json["agreementId"], "agreement");
+        assert!(json["eventDate"].is_string());
+
+        assert_eq!(
+            WebhookEventType::from_str(&payload.event.event_type().to_string()).unwrap(),
+            WebhookEventType::AgreementApproved
+        );
+    }
+}
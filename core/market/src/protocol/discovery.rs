@@ -1,7 +1,8 @@
 //! Discovery protocol interface
+use chrono::Utc;
 use futures::TryFutureExt;
 use metrics::{counter, timing, value};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
@@ -45,6 +46,9 @@ pub(super) struct OfferHandlers {
     get_local_offers_handler: HandlerSlot<RetrieveOffers>,
     offer_unsubscribe_handler: HandlerSlot<UnsubscribedOffersBcast>,
     query_offers: HandlerSlot<QueryOffers>,
+    filter_offer_refreshes: HandlerSlot<RefreshedOffersBcast>,
+    receive_offer_refreshes: HandlerSlot<OffersRefreshed>,
+    query_offers_expiration: HandlerSlot<QueryOffersExpiration>,
 }
 
 pub struct DiscoveryImpl {
@@ -53,6 +57,7 @@ pub struct DiscoveryImpl {
     /// Sending queues.
     offer_sending_queue: Mutex<Vec<SubscriptionId>>,
     unsub_sending_queue: Mutex<Vec<SubscriptionId>>,
+    refresh_sending_queue: Mutex<Vec<OfferRefresh>>,
     lazy_binder_prefix: Mutex<Option<String>>,
 
     /// Receiving queue.
@@ -206,17 +211,45 @@ impl Discovery {
         let target_node = NodeId::from_str(&target_node_id)
             .map_err(|e| DiscoveryError::InternalError(e.to_string()))?;
 
-        Ok(net::from(self.default_identity().await?)
+        let endpoint = net::from(self.default_identity().await?)
             .to(target_node)
-            .service(&get_offers_addr(BUS_ID))
-            .send(RetrieveOffers { offer_ids })
+            .service(&get_offers_addr(BUS_ID));
+        Ok(
+            net::hedge::send_hedged(&endpoint, RetrieveOffers { offer_ids })
+                .timeout(Some(timeout))
+                .map_err(|_| {
+                    DiscoveryError::GsbError(
+                        BusError::Timeout(format!(
+                            "{}/{}",
+                            get_offers_addr(BUS_ID),
+                            RetrieveOffers::ID
+                        ))
+                        .to_string(),
+                    )
+                })
+                .await???,
+        )
+    }
+
+    /// Ask remote Node for current expiration of specified Offers.
+    pub async fn get_remote_expirations(
+        &self,
+        target_node: NodeId,
+        offer_ids: Vec<SubscriptionId>,
+        timeout: impl IntoDuration,
+    ) -> Result<Vec<OfferRefresh>, DiscoveryError> {
+        let endpoint = net::from(self.default_identity().await?)
+            .to(target_node)
+            .service(&get_offers_addr(BUS_ID));
+        Ok(endpoint
+            .send(QueryOffersExpiration { offer_ids })
             .timeout(Some(timeout))
             .map_err(|_| {
                 DiscoveryError::GsbError(
                     BusError::Timeout(format!(
                         "{}/{}",
                         get_offers_addr(BUS_ID),
-                        RetrieveOffers::ID
+                        QueryOffersExpiration::ID
                     ))
                     .to_string(),
                 )
@@ -224,6 +257,40 @@ impl Discovery {
             .await???)
     }
 
+    /// Returns refreshes confirmed by Offer owners. Refreshes sent by the owner
+    /// are confirmed already, others are checked with the owner. Owner's
+    /// expiration is used, if it differs from the one received from `caller`.
+    async fn confirm_refreshes(
+        &self,
+        caller: NodeId,
+        refreshes: Vec<(NodeId, OfferRefresh)>,
+    ) -> Vec<OfferRefresh> {
+        let mut confirmed = vec![];
+        let mut to_confirm = HashMap::<NodeId, Vec<SubscriptionId>>::new();
+        for (owner, refresh) in refreshes {
+            match owner == caller {
+                true => confirmed.push(refresh),
+                false => to_confirm.entry(owner).or_default().push(refresh.offer_id),
+            }
+        }
+
+        for (owner, offer_ids) in to_confirm {
+            match self
+                .get_remote_expirations(owner, offer_ids.clone(), 3)
+                .await
+            {
+                // Owner can confirm only Offers we asked about.
+                Ok(refreshes) => confirmed.extend(
+                    refreshes
+                        .into_iter()
+                        .filter(|refresh| offer_ids.contains(&refresh.offer_id)),
+                ),
+                Err(e) => log::debug!("Can't confirm Offers refresh with [{owner}]. Error: {e}"),
+            }
+        }
+        confirmed
+    }
+
     pub async fn bcast_unsubscribes(
         &self,
         offer_ids: Vec<SubscriptionId>,
@@ -299,6 +366,69 @@ impl Discovery {
         }
     }
 
+    pub async fn bcast_refreshes(
+        &self,
+        refreshes: Vec<OfferRefresh>,
+    ) -> Result<(), DiscoveryError> {
+        if refreshes.is_empty() {
+            return Ok(());
+        }
+
+        // When there are 0 items in the queue we should schedule a send job.
+        let must_schedule = {
+            let mut queue = self.inner.refresh_sending_queue.lock().await;
+            let result = queue.len() == 0;
+
+            queue.append(&mut refreshes.clone());
+            result
+        };
+
+        if must_schedule {
+            let myself = self.clone();
+            tokio::task::spawn_local(async move {
+                // Sleep to collect multiple refreshes to send
+                sleep(myself.inner.config.offer_broadcast_delay).await;
+                myself.send_bcast_refreshes().await;
+            });
+        }
+        Ok(())
+    }
+
+    async fn send_bcast_refreshes(&self) {
+        // `...refresh_queue` MUST be empty to trigger the sending again
+        let refreshes: Vec<OfferRefresh> =
+            std::mem::take(&mut *self.inner.refresh_sending_queue.lock().await);
+
+        // Should never happen, but just to be certain.
+        if refreshes.is_empty() {
+            return;
+        }
+        let default_id = match self.default_identity().await {
+            Ok(id) => id,
+            Err(e) => {
+                log::error!(
+                    "Error getting default identity, not sending bcast. error={:?}",
+                    e
+                );
+                return;
+            }
+        };
+
+        let size = refreshes.len();
+        log::debug!("Broadcasting Offer refreshes. count={}", size);
+        counter!("market.offers.refreshes.broadcasts.net", 1);
+
+        if self.is_hybrid_net() {
+            let mut iter = refreshes.into_iter().peekable();
+            while iter.peek().is_some() {
+                let chunk = iter.by_ref().take(MAX_OFFER_IDS_PER_BROADCAST).collect();
+                broadcast_refreshes(default_id, chunk).await;
+            }
+        } else {
+            broadcast_refreshes(default_id, refreshes).await;
+        }
+    }
+
     pub async fn bind_gsb(
         &self,
         public_prefix: &str,
@@ -323,6 +453,20 @@ impl Discovery {
                 });
         }
 
+        {
+            let me = self.clone();
+            let _ = ya_service_bus::typed::bind_with_caller(
+                &addr,
+                move |caller, msg: QueryOffersExpiration| {
+                    let inner = me.inner.clone();
+                    async move {
+                        let handler = inner.offer_handlers.query_offers_expiration.clone();
+                        handler.call(caller, msg).await
+                    }
+                },
+            );
+        }
+
         // Subscribe to offer broadcasts.
         {
             let mut prefix_guard = self.inner.lazy_binder_prefix.lock().await;
@@ -378,6 +522,19 @@ impl Discovery {
         )
         .await
         .map_err(|e| DiscoveryInitError::from_pair(bcast_address, e))?;
+
+        let myself = self.clone();
+        // /local/market/market-protocol-mk1-offer-refresh
+        let bcast_address = format!("{}/{}", local_prefix, RefreshedOffersBcast::TOPIC);
+        ya_net::bind_broadcast_with_caller(
+            &bcast_address,
+            move |caller, msg: SendBroadcastMessage<RefreshedOffersBcast>| {
+                let myself = myself.clone();
+                myself.on_bcast_refreshes(caller, msg.body().to_owned())
+            },
+        )
+        .await
+        .map_err(|e| DiscoveryInitError::from_pair(bcast_address, e))?;
         Ok(())
     }
 
@@ -425,10 +582,33 @@ impl Discovery {
                 end_remote
             );
 
+            // Refreshed Offers are sent with expiration set at subscription, which could
+            // already pass. We need their current expiration to be able to add them.
+            let now = Utc::now().naive_utc();
+            let expired = offers
+                .iter()
+                .filter(|offer| offer.expiration_ts <= now)
+                .map(|offer| (offer.id.clone(), offer.node_id))
+                .collect::<HashMap<_, _>>();
+            let refreshes = if !expired.is_empty() {
+                let ids = expired.keys().cloned().collect();
+                let refreshes = self
+                    .get_remote_expirations(caller, ids, 3)
+                    .await
+                    .map_err(|e| log::debug!("Can't get Offers expiration from [{caller}]: {e}"))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|refresh| Some((*expired.get(&refresh.offer_id)?, refresh)))
+                    .collect();
+                self.confirm_refreshes(caller, refreshes).await
+            } else {
+                vec![]
+            };
+
             // We still could fail to add some Offers to database. If we fail to add them, we don't
             // want to propagate subscription further.
             receive_remote_offers
-                .call(caller.to_string(), OffersRetrieved { offers })
+                .call(caller.to_string(), OffersRetrieved { offers, refreshes })
                 .await?
         } else {
             vec![]
@@ -513,6 +693,45 @@ impl Discovery {
         Ok(())
     }
 
+    async fn on_bcast_refreshes(self, caller: String, msg: RefreshedOffersBcast) -> Result<(), ()> {
+        let num_received = msg.refreshes.len();
+        log::trace!("Received {num_received} Offer refreshes from [{caller}].");
+        if msg.refreshes.is_empty() {
+            return Ok(());
+        }
+
+        let node_id: NodeId = caller.parse().map_err(|_| ())?;
+        if self.inner.ban_cache.is_banned_node(&node_id) {
+            log::trace!("banned node: {node_id}");
+            return Ok(());
+        }
+
+        let filter_offer_refreshes = self.inner.offer_handlers.filter_offer_refreshes.clone();
+        let receive_offer_refreshes = self.inner.offer_handlers.receive_offer_refreshes.clone();
+
+        let refreshes = filter_offer_refreshes.call(caller.clone(), msg).await?;
+        if refreshes.is_empty() {
+            return Ok(());
+        }
+        let refreshes = self.confirm_refreshes(node_id, refreshes).await;
+        let applied = receive_offer_refreshes
+            .call(caller.clone(), OffersRefreshed { refreshes })
+            .await?;
+
+        if self.re_broadcast_enabled() && !applied.is_empty() {
+            log::trace!(
+                "Propagating {}/{num_received} Offer refreshes received from [{caller}].",
+                applied.len(),
+            );
+
+            // No need to retry broadcasting, since Offer owners send cyclic broadcasts.
+            if let Err(error) = self.bcast_refreshes(applied).await {
+                log::error!("Error propagating Offer refreshes further: {error}");
+            }
+        }
+        Ok(())
+    }
+
     async fn default_identity(&self) -> Result<NodeId, IdentityError> {
         self.inner.identity.default_identity().await
     }
@@ -531,3 +750,10 @@ async fn broadcast_unsubscribed(node_id: NodeId, offer_ids: Vec<SubscriptionId>)
         counter!("market.offers.unsubscribes.broadcasts.net_errors", 1);
     };
 }
+
+async fn broadcast_refreshes(node_id: NodeId, refreshes: Vec<OfferRefresh>) {
+    if let Err(e) = net::broadcast(node_id, RefreshedOffersBcast { refreshes }).await {
+        log::error!("Error broadcasting Offer refreshes: {e}");
+        counter!("market.offers.refreshes.broadcasts.net_errors", 1);
+    };
+}
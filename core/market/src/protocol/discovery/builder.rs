@@ -71,6 +71,9 @@ impl DiscoveryBuilder {
             get_local_offers_handler: self.get_handler(),
             offer_unsubscribe_handler: self.get_handler(),
             query_offers: self.get_handler(),
+            filter_offer_refreshes: self.get_handler(),
+            receive_offer_refreshes: self.get_handler(),
+            query_offers_expiration: self.get_handler(),
         };
 
         let (sender, receiver) =
@@ -82,6 +85,7 @@ impl DiscoveryBuilder {
                 offer_handlers,
                 offer_sending_queue: Mutex::new(vec![]),
                 unsub_sending_queue: Mutex::new(vec![]),
+                refresh_sending_queue: Mutex::new(vec![]),
                 lazy_binder_prefix: Mutex::new(None),
                 config: self.config.clone().unwrap(),
                 net_type: net::Config::from_env().unwrap().net_type,
@@ -165,6 +169,9 @@ mod test {
             .add_handler(|_, _: OffersBcast| async { Ok(vec![]) })
             .add_handler(|_, _: RetrieveOffers| async { Ok(vec![]) })
             .add_handler(|_, _: QueryOffers| async { Ok(QueryOffersResult::default()) })
+            .add_handler(|_, _: RefreshedOffersBcast| async { Ok(vec![]) })
+            .add_handler(|_, _: OffersRefreshed| async { Ok(vec![]) })
+            .add_handler(|_, _: QueryOffersExpiration| async { Ok(vec![]) })
             .with_config(Config::from_env().unwrap().discovery)
             .build();
     }
@@ -179,6 +186,9 @@ mod test {
             .add_handler(|_, _: OffersBcast| async { Ok(vec![]) })
             .add_handler(|_, _: QueryOffers| async { Ok(QueryOffersResult::default()) })
             .add_data_handler(|_: &str, _, _: RetrieveOffers| async { Ok(vec![]) })
+            .add_handler(|_, _: RefreshedOffersBcast| async { Ok(vec![]) })
+            .add_handler(|_, _: OffersRefreshed| async { Ok(vec![]) })
+            .add_data_handler(|_: &str, _, _: QueryOffersExpiration| async { Ok(vec![]) })
             .with_config(Config::from_env().unwrap().discovery)
             .build();
     }
@@ -206,6 +216,9 @@ mod test {
             })
             .add_handler(|_, _: OffersBcast| async { Ok(vec![]) })
             .add_handler(|_, _: QueryOffers| async { Ok(QueryOffersResult::default()) })
+            .add_handler(|_, _: RefreshedOffersBcast| async { Ok(vec![]) })
+            .add_handler(|_, _: OffersRefreshed| async { Ok(vec![]) })
+            .add_handler(|_, _: QueryOffersExpiration| async { Ok(vec![]) })
             .with_config(Config::from_env().unwrap().discovery)
             .build();
 
@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 
@@ -74,6 +75,8 @@ impl RpcMessage for QueryOffers {
 #[serde(rename_all = "camelCase")]
 pub struct OffersRetrieved {
     pub offers: Vec<ModelOffer>,
+    /// Confirmed refreshes of retrieved Offers, which would be expired otherwise.
+    pub refreshes: Vec<OfferRefresh>,
 }
 
 /// Local handler will return only ids of offers, that was successfully saved.
@@ -103,3 +106,59 @@ impl BroadcastMessage for UnsubscribedOffersBcast {
         "-offers-unsubscribe"
     );
 }
+
+/// Expiration of Offer extended by its owner.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferRefresh {
+    pub offer_id: SubscriptionId,
+    pub expiration_ts: NaiveDateTime,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshedOffersBcast {
+    pub refreshes: Vec<OfferRefresh>,
+}
+
+/// Local handler will return only refreshes extending known Offers, together with
+/// their owners. Refreshes not sent by the owner will be confirmed with the owner first.
+impl CallbackMessage for RefreshedOffersBcast {
+    type Ok = Vec<(NodeId, OfferRefresh)>;
+    type Error = ();
+}
+
+impl BroadcastMessage for RefreshedOffersBcast {
+    const TOPIC: &'static str = concat!(
+        "market-protocol-discovery-",
+        PROTOCOL_VERSION!(),
+        "-offers-refresh"
+    );
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OffersRefreshed {
+    pub refreshes: Vec<OfferRefresh>,
+}
+
+/// Local handler will return only refreshes, that were applied.
+/// Those will be bcasted further to the network.
+impl CallbackMessage for OffersRefreshed {
+    type Ok = Vec<OfferRefresh>;
+    type Error = ();
+}
+
+/// Asks node for current expiration of specified Offers. Used to confirm
+/// refreshes with Offer owner.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryOffersExpiration {
+    pub offer_ids: Vec<SubscriptionId>,
+}
+
+impl RpcMessage for QueryOffersExpiration {
+    const ID: &'static str = "QueryExpiration";
+    type Item = Vec<OfferRefresh>;
+    type Error = DiscoveryRemoteError;
+}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 pub mod error;
+pub mod interest;
 pub mod messages;
 pub mod provider;
 pub mod requestor;
@@ -63,6 +63,10 @@ pub enum RemoteProposalError {
     InvalidId(#[from] ProposalIdValidationError),
     #[error(transparent)]
     CallerParse(#[from] CallerParseError),
+    #[error("First Proposal requires proof of interest with difficulty {0}.")]
+    ProofRequired(u32),
+    #[error("Proposal [{0}] expired.")]
+    Expired(ProposalId),
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
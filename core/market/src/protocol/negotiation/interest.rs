@@ -0,0 +1,105 @@
+//! Hashcash-style proof of interest attached by Requestor to the first Proposal
+//! sent to a Provider.
+//!
+//! Proof is bound to both Nodes and to Offer and Demand, so it can't be reused
+//! in other negotiations. Finding it costs Requestor about `2^difficulty` hashes,
+//! while Provider verifies it with a single one.
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+use sha3::Sha3_256;
+
+use ya_client::model::NodeId;
+
+use crate::db::model::SubscriptionId;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofOfInterest {
+    pub nonce: u64,
+}
+
+/// Negotiation, which proof is computed for.
+#[derive(Clone, Debug)]
+pub struct InterestChallenge {
+    pub requestor_id: NodeId,
+    pub provider_id: NodeId,
+    pub offer_id: SubscriptionId,
+    pub demand_id: SubscriptionId,
+}
+
+impl InterestChallenge {
+    pub fn solve(&self, difficulty: u32) -> ProofOfInterest {
+        let prefix = self.prefix();
+        let nonce = (0..u64::MAX)
+            .find(|nonce| leading_zero_bits(&hash(&prefix, *nonce)) >= difficulty)
+            .unwrap_or_default();
+        ProofOfInterest { nonce }
+    }
+
+    pub fn verify(&self, proof: &ProofOfInterest, difficulty: u32) -> bool {
+        leading_zero_bits(&hash(&self.prefix(), proof.nonce)) >= difficulty
+    }
+
+    fn prefix(&self) -> Sha3_256 {
+        let mut hasher = Sha3_256::new();
+        hasher.input(self.requestor_id.to_string());
+        hasher.input(self.provider_id.to_string());
+        hasher.input(self.offer_id.to_string());
+        hasher.input(self.demand_id.to_string());
+        hasher
+    }
+}
+
+fn hash(prefix: &Sha3_256, nonce: u64) -> Vec<u8> {
+    let mut hasher = prefix.clone();
+    hasher.input(nonce.to_le_bytes());
+    hasher.result().to_vec()
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn challenge(provider_id: &str) -> InterestChallenge {
+        let offer_id = SubscriptionId::from_str(
+            "c76161077d0343ab85ac986eb5f6ea38-edb0016d9f8bafb54540da34f05a8d510de8114488f23916276bdead05509a53",
+        )
+        .unwrap();
+        InterestChallenge {
+            requestor_id: NodeId::from_str("0xbabe000000000000000000000000000000000000").unwrap(),
+            provider_id: NodeId::from_str(provider_id).unwrap(),
+            offer_id: offer_id.clone(),
+            demand_id: offer_id,
+        }
+    }
+
+    #[test]
+    fn proof_is_bound_to_negotiation() {
+        let challenge = challenge("0xcafe000000000000000000000000000000000000");
+        let proof = challenge.solve(12);
+        assert!(challenge.verify(&proof, 12));
+        assert!(challenge.verify(&proof, 0));
+
+        let other = self::challenge("0xbeef000000000000000000000000000000000000");
+        assert!(!other.verify(&proof, 12));
+    }
+
+    #[test]
+    fn counts_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0, 0, 0x10, 0xff]), 19);
+        assert_eq!(leading_zero_bits(&[0x80]), 0);
+        assert_eq!(leading_zero_bits(&[0, 0]), 16);
+    }
+}
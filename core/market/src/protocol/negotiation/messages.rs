@@ -11,6 +11,7 @@ use crate::protocol::negotiation::error::{
 
 use super::super::callback::CallbackMessage;
 use super::error::{AgreementProtocolError, CounterProposalError, TerminateAgreementError};
+use super::interest::ProofOfInterest;
 
 pub mod provider {
     pub fn proposal_addr(prefix: &str) -> String {
@@ -78,6 +79,10 @@ pub struct InitialProposalReceived {
 
     pub offer_id: SubscriptionId,
     pub demand_id: SubscriptionId,
+
+    /// Required by Providers, which configured minimal proof difficulty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<ProofOfInterest>,
 }
 
 impl RpcMessage for InitialProposalReceived {
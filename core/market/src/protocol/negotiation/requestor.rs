@@ -14,6 +14,7 @@ use super::error::{
     AgreementProtocolError, CounterProposalError, GsbAgreementError, GsbProposalError,
     NegotiationApiInitError, TerminateAgreementError,
 };
+use super::interest::ProofOfInterest;
 use super::messages::{
     provider, requestor, AgreementApproved, AgreementCancelled, AgreementReceived,
     AgreementRejected, AgreementTerminated, InitialProposalReceived, ProposalContent,
@@ -66,7 +67,11 @@ impl NegotiationApi {
 
     /// Sent to provider, when Requestor counters initial proposal
     /// generated by market.
-    pub async fn initial_proposal(&self, proposal: Proposal) -> Result<(), CounterProposalError> {
+    pub async fn initial_proposal(
+        &self,
+        proposal: Proposal,
+        proof: Option<ProofOfInterest>,
+    ) -> Result<(), CounterProposalError> {
         let proposal_id = proposal.body.id.clone();
         log::debug!(
             "Sending initial proposal [{}] to [{}].",
@@ -79,6 +84,7 @@ impl NegotiationApi {
             proposal: content,
             offer_id: proposal.negotiation.offer_id,
             demand_id: proposal.negotiation.demand_id,
+            proof,
         };
         let provider_id = proposal.negotiation.provider_id;
         spawn_local(
@@ -7,13 +7,15 @@
 use actix_web::web::JsonConfig;
 use actix_web::{error::InternalError, http::StatusCode, web::PathConfig, HttpResponse};
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use ya_client::model::{market::agreement::State, ErrorMessage};
 use ya_core_model::NodeId;
+use ya_market_resolver::resolver::diff::ProposalDiff;
 
 use crate::db::model::{
-    AgreementId, AppSessionId, Owner, ProposalId, ProposalIdParseError, SubscriptionId,
+    AgreementId, AppSessionId, LabelSelector, Owner, ProposalId, ProposalIdParseError,
+    SubscriptionId,
 };
 
 pub(crate) mod common;
@@ -23,6 +25,8 @@ pub(crate) mod requestor;
 
 const DEFAULT_EVENT_TIMEOUT: f32 = 5.0; // seconds
 const DEFAULT_QUERY_TIMEOUT: f32 = 5.0;
+/// Number of all items matching the query, when only a page of them is returned.
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
 
 pub fn path_config() -> PathConfig {
     PathConfig::default().error_handler(|err, _req| {
@@ -46,17 +50,41 @@ pub struct PathAgreement {
     pub agreement_id: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathProvider {
+    pub provider_id: NodeId,
+}
+
 #[derive(Deserialize)]
 pub struct PathSubscription {
     pub subscription_id: SubscriptionId,
 }
 
+#[derive(Deserialize)]
+pub struct PathExplainMatch {
+    pub demand_id: SubscriptionId,
+    pub offer_id: SubscriptionId,
+}
+
+#[derive(Deserialize)]
+pub struct LintConstraints {
+    pub constraints: String,
+}
+
 #[derive(Deserialize)]
 pub struct PathSubscriptionProposal {
     pub subscription_id: SubscriptionId,
     pub proposal_id: ProposalId,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct QueryProviderFailures {
+    /// List only Providers in cooldown.
+    #[serde(default)]
+    pub blacklisted: bool,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryAgreementList {
@@ -64,6 +92,16 @@ pub struct QueryAgreementList {
     pub before_date: Option<DateTime<Utc>>,
     pub after_date: Option<DateTime<Utc>>,
     pub app_session_id: Option<String>,
+    #[serde(default)]
+    pub label: LabelSelector,
+    /// Only Agreements concluded with this node.
+    pub counterparty: Option<NodeId>,
+    /// Only Agreements, in which caller has this role.
+    pub role: Option<Owner>,
+    /// Number of Agreements skipped, before the page starts.
+    pub offset: Option<u32>,
+    /// Maximal page size.
+    pub limit: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -72,6 +110,16 @@ pub struct QueryAppSessionId {
     pub app_session_id: AppSessionId,
 }
 
+#[derive(Deserialize)]
+pub struct QuerySubscribeOffer {
+    #[serde(rename = "appSessionId")]
+    pub app_session_id: AppSessionId,
+    /// Private Offer isn't propagated through the market. Requestors can
+    /// negotiate it only directly, knowing its subscription id.
+    #[serde(default)]
+    pub private: bool,
+}
+
 #[derive(Deserialize)]
 pub struct QueryTimeoutAppSessionId {
     #[serde(rename = "appSessionId")]
@@ -103,6 +151,24 @@ pub struct QueryTimeoutMaxEvents {
     /// maximum count of events to return
     #[serde(rename = "maxEvents")]
     pub max_events: Option<i32>,
+    /// events of subscriptions created in other sessions are not returned
+    #[serde(rename = "appSessionId")]
+    pub app_session_id: AppSessionId,
+    /// ProposalEvents include `diff` relative to the previous Proposal
+    #[serde(rename = "includeDiff", default)]
+    pub include_diff: bool,
+}
+
+/// Serializes the event with `diff` field, if there is one.
+pub(crate) fn with_diff(event: impl Serialize, diff: Option<ProposalDiff>) -> serde_json::Value {
+    let mut value = serde_json::to_value(event).unwrap_or_default();
+    if let (Some(diff), Some(object)) = (diff, value.as_object_mut()) {
+        object.insert(
+            "diff".to_string(),
+            serde_json::to_value(diff).unwrap_or_default(),
+        );
+    }
+    value
 }
 
 #[derive(Deserialize, Debug)]
@@ -117,6 +183,9 @@ pub struct QueryAgreementEvents {
     pub app_session_id: AppSessionId,
     #[serde(rename = "afterTimestamp")]
     pub after_timestamp: Option<DateTime<Utc>>,
+    /// comma separated label conditions: `key` or `key=value`
+    #[serde(rename = "label", default)]
+    pub label: LabelSelector,
 }
 
 #[derive(Deserialize, Debug)]
@@ -137,6 +206,21 @@ pub struct QueryTerminateAgreement {
     pub reason: Option<String>,
 }
 
+/// New expiration of refreshed Offer.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferExpiration {
+    pub expiration_ts: DateTime<Utc>,
+}
+
+/// Offer shared by the Provider out of band for direct negotiation.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectOffer {
+    pub provider_id: NodeId,
+    pub offer_id: SubscriptionId,
+}
+
 #[inline(always)]
 pub(crate) fn default_query_timeout() -> f32 {
     DEFAULT_QUERY_TIMEOUT
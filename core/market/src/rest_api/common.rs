@@ -12,12 +12,15 @@ use ya_service_api_web::middleware::Identity;
 use ya_service_bus::timeout::IntoTimeoutFuture;
 use ya_std_utils::LogErr;
 
-use super::{PathAgreement, QueryScanEvents};
+use super::{LintConstraints, PathAgreement, PathExplainMatch, PathSubscription, QueryScanEvents};
+use crate::db::dao::AgreementFilter;
 use crate::db::model::Owner;
+use crate::market::webhook::NewWebhook;
 use crate::market::MarketService;
+use crate::matcher::validation::lint_constraints;
 use crate::negotiation::error::{AgreementError, ScanError};
 use crate::negotiation::{ScanId, ScannerSet};
-use crate::rest_api::{QueryAgreementEvents, QueryAgreementList};
+use crate::rest_api::{QueryAgreementEvents, QueryAgreementList, TOTAL_COUNT_HEADER};
 use futures::prelude::*;
 use tracing::Level;
 
@@ -31,6 +34,18 @@ pub fn register_endpoints(scope: Scope) -> Scope {
         .service(scan_begin)
         .service(scan_collect)
         .service(scan_end)
+        .service(inspect_subscription)
+        .service(inspect_proposal)
+        .service(agreement_transcript)
+        .service(explain_match)
+        .service(validate_constraints)
+        .service(list_sessions)
+        .service(remove_session)
+        .service(register_webhook)
+        .service(list_webhooks)
+        .service(remove_webhook)
+        .service(list_webhook_deliveries)
+        .service(replay_webhook_delivery)
 }
 
 #[actix_web::get("/agreements")]
@@ -40,17 +55,30 @@ async fn list_agreements(
     id: Identity,
 ) -> impl Responder {
     let query = query.into_inner();
+    let filter = AgreementFilter {
+        counterparty: query.counterparty,
+        role: query.role,
+        state: query.state.map(Into::into),
+        before: query.before_date,
+        after: query.after_date,
+        app_session_id: query.app_session_id,
+        labels: query.label,
+        ..Default::default()
+    };
 
     market
         .list_agreements(
             &id,
-            query.state.map(Into::into),
-            query.before_date,
-            query.after_date,
-            query.app_session_id,
+            filter,
+            query.offset.map(i64::from),
+            query.limit.map(i64::from),
         )
         .await
-        .map(|list| HttpResponse::Ok().json(list))
+        .map(|(list, total)| {
+            HttpResponse::Ok()
+                .insert_header((TOTAL_COUNT_HEADER, total.to_string()))
+                .json(list)
+        })
 }
 
 #[actix_web::get("/agreements/{agreement_id}")]
@@ -91,13 +119,15 @@ async fn collect_agreement_events(
     let after_timestamp = query
         .after_timestamp
         .unwrap_or_else(|| Utc.with_ymd_and_hms(2016, 11, 11, 15, 12, 0).unwrap());
+    market.touch_session(&query.app_session_id, &id).await;
 
     market
-        .query_agreement_events(
+        .query_labeled_agreement_events(
             &query.app_session_id,
             timeout,
             query.max_events,
             after_timestamp,
+            &query.label,
             &id,
         )
         .await
@@ -246,3 +276,157 @@ async fn scan_end(
     scan_set.end(id.identity, scan_id).await?;
     Ok(HttpResponse::NoContent().finish())
 }
+
+/// Raw Offer or Demand as stored in database, with its propagation path.
+#[actix_web::get("/debug/subscriptions/{subscription_id}")]
+async fn inspect_subscription(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathSubscription>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .inspect_subscription(&path.into_inner().subscription_id, &id)
+        .await
+        .map(|raw| HttpResponse::Ok().json(raw))
+}
+
+/// Raw Proposal together with Offer and Demand it was negotiated from.
+#[actix_web::get("/debug/proposals/{proposal_id}")]
+async fn inspect_proposal(
+    market: Data<Arc<MarketService>>,
+    path: Path<(String,)>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .inspect_proposal(&path.into_inner().0, &id)
+        .await
+        .map(|raw| HttpResponse::Ok().json(raw))
+}
+
+/// All Proposals exchanged in negotiation of the Agreement with changes made by each of them.
+#[actix_web::get("/debug/agreements/{agreement_id}/transcript")]
+async fn agreement_transcript(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathAgreement>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .agreement_transcript(&path.into_inner().agreement_id, &id)
+        .await
+        .map(|transcript| HttpResponse::Ok().json(transcript))
+}
+
+/// Explains, which constraints of caller's Demand and the Offer match each other.
+#[actix_web::get("/debug/demands/{demand_id}/match/{offer_id}")]
+async fn explain_match(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathExplainMatch>,
+    id: Identity,
+) -> impl Responder {
+    let path = path.into_inner();
+    market
+        .explain_match(&path.demand_id, &path.offer_id, &id)
+        .await
+        .map(|explanation| HttpResponse::Ok().json(explanation))
+}
+
+/// Lints Offer or Demand constraints: reports syntax error position, referenced
+/// namespaces and probably misspelled `golem` properties.
+#[actix_web::post("/validate")]
+async fn validate_constraints(body: Json<LintConstraints>, _id: Identity) -> impl Responder {
+    HttpResponse::Ok().json(lint_constraints(&body.into_inner().constraints))
+}
+
+/// App sessions of caller with subscriptions created in their scope.
+#[actix_web::get("/sessions")]
+async fn list_sessions(market: Data<Arc<MarketService>>, id: Identity) -> impl Responder {
+    market
+        .list_sessions(&id)
+        .await
+        .log_err()
+        .map(|sessions| HttpResponse::Ok().json(sessions))
+}
+
+/// Removes app session and unsubscribes its Offers and Demands.
+#[actix_web::delete("/sessions/{app_session_id}")]
+async fn remove_session(
+    market: Data<Arc<MarketService>>,
+    path: Path<(String,)>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .remove_session(&path.into_inner().0, &id)
+        .await
+        .log_err()
+        .map(|_| HttpResponse::NoContent().finish())
+}
+
+/// Registers callback URL for events of caller's Offer or Demand.
+#[actix_web::post("/subscriptions/{subscription_id}/webhooks")]
+async fn register_webhook(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathSubscription>,
+    body: Json<NewWebhook>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .register_webhook(&path.into_inner().subscription_id, body.into_inner(), &id)
+        .await
+        .log_err()
+        .map(|webhook| HttpResponse::Created().json(webhook))
+}
+
+#[actix_web::get("/subscriptions/{subscription_id}/webhooks")]
+async fn list_webhooks(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathSubscription>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .list_webhooks(&path.into_inner().subscription_id, &id)
+        .await
+        .log_err()
+        .map(|webhooks| HttpResponse::Ok().json(webhooks))
+}
+
+#[actix_web::delete("/webhooks/{webhook_id}")]
+async fn remove_webhook(
+    market: Data<Arc<MarketService>>,
+    path: Path<(String,)>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .remove_webhook(&path.into_inner().0, &id)
+        .await
+        .log_err()
+        .map(|_| HttpResponse::NoContent().finish())
+}
+
+/// Delivery status of webhook events, the most recent first.
+#[actix_web::get("/webhooks/{webhook_id}/deliveries")]
+async fn list_webhook_deliveries(
+    market: Data<Arc<MarketService>>,
+    path: Path<(String,)>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .list_webhook_deliveries(&path.into_inner().0, &id)
+        .await
+        .log_err()
+        .map(|deliveries| HttpResponse::Ok().json(deliveries))
+}
+
+/// Sends event again. Delivery status can be tracked on deliveries endpoint.
+#[actix_web::post("/webhooks/{webhook_id}/deliveries/{delivery_id}/replay")]
+async fn replay_webhook_delivery(
+    market: Data<Arc<MarketService>>,
+    path: Path<(String, String)>,
+    id: Identity,
+) -> impl Responder {
+    let (webhook_id, delivery_id) = path.into_inner();
+    market
+        .replay_webhook_delivery(&webhook_id, &delivery_id, &id)
+        .await
+        .log_err()
+        .map(|_| HttpResponse::Accepted().finish())
+}
@@ -4,6 +4,12 @@ use ya_client::model::ErrorMessage;
 
 use crate::db::dao::{AgreementDaoError, SaveProposalError};
 use crate::db::model::AgreementState;
+use crate::market::blacklist::BlacklistError;
+use crate::market::direct::DirectNegotiationError;
+use crate::market::inspect::InspectError;
+use crate::market::metadata::MetadataError;
+use crate::market::session::SessionError;
+use crate::market::webhook::WebhookError;
 use crate::negotiation::error::{AgreementEventsError, ProposalValidationError};
 use crate::protocol::negotiation::error::RejectProposalError;
 use crate::{
@@ -34,6 +40,10 @@ impl ResponseError for MarketError {
             MarketError::QueryOffersError(e) => e.error_response(),
             MarketError::DemandError(e) => e.error_response(),
             MarketError::Negotiation(e) => e.error_response(),
+            MarketError::Session(e) => e.error_response(),
+            MarketError::Webhook(e) => e.error_response(),
+            MarketError::Blacklist(e) => e.error_response(),
+            MarketError::DirectNegotiation(e) => e.error_response(),
         }
     }
 }
@@ -46,6 +56,9 @@ impl ResponseError for MatcherError {
             MatcherError::QueryOffer(e) => e.error_response(),
             MatcherError::SaveOffer(e) => e.error_response(),
             MatcherError::ModifyOffer(e) => e.error_response(),
+            MatcherError::Validation(e) => {
+                HttpResponse::BadRequest().json(ErrorMessage::new(e.to_string()))
+            }
         }
     }
 }
@@ -64,6 +77,9 @@ impl ResponseError for DemandError {
             DemandError::NotFound(_) => {
                 HttpResponse::NotFound().json(ErrorMessage::new(self.to_string()))
             }
+            DemandError::ForeignOwner(_) => {
+                HttpResponse::Forbidden().json(ErrorMessage::new(self.to_string()))
+            }
             _ => HttpResponse::InternalServerError().json(ErrorMessage::new(self.to_string())),
         }
     }
@@ -111,6 +127,7 @@ impl ResponseError for ModifyOfferError {
             ModifyOfferError::AlreadyUnsubscribed(_) | ModifyOfferError::Expired(_) => {
                 HttpResponse::Gone().json(msg)
             }
+            ModifyOfferError::NotExtended(_) => HttpResponse::BadRequest().json(msg),
             _ => HttpResponse::InternalServerError().json(msg),
         }
     }
@@ -137,9 +154,8 @@ impl ResponseError for ProposalError {
         let msg = ErrorMessage::new(self.to_string());
         match self {
             ProposalError::Validation(e) => e.error_response(),
-            ProposalError::Save(SaveProposalError::AlreadyCountered(..)) => {
-                HttpResponse::Gone().json(msg)
-            }
+            ProposalError::Save(SaveProposalError::AlreadyCountered(..))
+            | ProposalError::Save(SaveProposalError::Expired(..)) => HttpResponse::Gone().json(msg),
             ProposalError::Get(e) => e.error_response(),
             ProposalError::Reject(e) => e.error_response(),
             // TODO: get rid of those `_` patterns as they do not break when error is extended
@@ -176,6 +192,89 @@ impl ResponseError for ProposalValidationError {
     }
 }
 
+impl ResponseError for InspectError {
+    fn error_response(&self) -> HttpResponse {
+        let msg = ErrorMessage::new(self.to_string());
+        match self {
+            InspectError::SubscriptionNotFound(_)
+            | InspectError::ProposalNotFound(_)
+            | InspectError::AgreementNotFound(_) => HttpResponse::NotFound().json(msg),
+            InspectError::InvalidProposalId(_)
+            | InspectError::Agreement(AgreementDaoError::InvalidId(_)) => {
+                HttpResponse::BadRequest().json(msg)
+            }
+            InspectError::Db(_) | InspectError::Match(_) | InspectError::Agreement(_) => {
+                HttpResponse::InternalServerError().json(msg)
+            }
+        }
+    }
+}
+
+impl ResponseError for MetadataError {
+    fn error_response(&self) -> HttpResponse {
+        let msg = ErrorMessage::new(self.to_string());
+        match self {
+            MetadataError::NotFound(_) => HttpResponse::NotFound().json(msg),
+            MetadataError::Invalid(..) => HttpResponse::UnprocessableEntity().json(msg),
+            MetadataError::Gsb(_) | MetadataError::Db(_) => {
+                HttpResponse::InternalServerError().json(msg)
+            }
+        }
+    }
+}
+
+impl ResponseError for SessionError {
+    fn error_response(&self) -> HttpResponse {
+        let msg = ErrorMessage::new(self.to_string());
+        match self {
+            SessionError::NotFound(_) => HttpResponse::NotFound().json(msg),
+            SessionError::Db(_) => HttpResponse::InternalServerError().json(msg),
+        }
+    }
+}
+
+impl ResponseError for WebhookError {
+    fn error_response(&self) -> HttpResponse {
+        let msg = ErrorMessage::new(self.to_string());
+        match self {
+            WebhookError::SubscriptionNotFound(_)
+            | WebhookError::NotFound(_)
+            | WebhookError::DeliveryNotFound(_) => HttpResponse::NotFound().json(msg),
+            WebhookError::InvalidUrl(_) | WebhookError::InvalidEventType(_) => {
+                HttpResponse::BadRequest().json(msg)
+            }
+            WebhookError::Db(_) => HttpResponse::InternalServerError().json(msg),
+        }
+    }
+}
+
+impl ResponseError for BlacklistError {
+    fn error_response(&self) -> HttpResponse {
+        let msg = ErrorMessage::new(self.to_string());
+        match self {
+            BlacklistError::NotBlacklisted(_) => HttpResponse::NotFound().json(msg),
+            BlacklistError::InvalidSettings(_) => HttpResponse::BadRequest().json(msg),
+        }
+    }
+}
+
+impl ResponseError for DirectNegotiationError {
+    fn error_response(&self) -> HttpResponse {
+        let msg = ErrorMessage::new(self.to_string());
+        match self {
+            DirectNegotiationError::OfferNotFound(..) => HttpResponse::NotFound().json(msg),
+            DirectNegotiationError::Retrieve(..) => HttpResponse::BadGateway().json(msg),
+            DirectNegotiationError::NoMatch(..) => HttpResponse::BadRequest().json(msg),
+            DirectNegotiationError::Blacklisted(_) => HttpResponse::Forbidden().json(msg),
+            DirectNegotiationError::Demand(e) => e.error_response(),
+            DirectNegotiationError::SaveOffer(e) => e.error_response(),
+            DirectNegotiationError::SaveProposal(_) => {
+                HttpResponse::InternalServerError().json(msg)
+            }
+        }
+    }
+}
+
 impl ResponseError for GetProposalError {
     fn error_response(&self) -> HttpResponse {
         let msg = ErrorMessage::new(self.to_string());
@@ -202,7 +301,8 @@ impl ResponseError for AgreementError {
             | AgreementError::InvalidDate(..)
             | AgreementError::InvalidAgreementState(..)
             | AgreementError::InvalidId(..)
-            | AgreementError::NotTerminated(..) => HttpResponse::BadRequest().json(msg),
+            | AgreementError::NotTerminated(..)
+            | AgreementError::InvalidLabels(..) => HttpResponse::BadRequest().json(msg),
             AgreementError::GetProposal(..)
             | AgreementError::Save(..)
             | AgreementError::Get(..)
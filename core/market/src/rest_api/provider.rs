@@ -1,24 +1,31 @@
 use actix_web::web::{Data, Json, Path, Query};
 use actix_web::{HttpResponse, Responder, Scope};
+use futures::StreamExt;
 use std::sync::Arc;
 
-use ya_client::model::market::{NewOffer, NewProposal, Reason};
+use ya_client::model::market::{NewOffer, NewProposal, ProviderEvent, Reason};
 use ya_service_api_web::middleware::Identity;
 use ya_std_utils::LogErr;
 
 use crate::db::model::Owner;
-use crate::market::MarketService;
+use crate::market::{MarketError, MarketService};
 
-use super::{PathAgreement, PathSubscription, PathSubscriptionProposal, QueryTimeoutMaxEvents};
+use super::{
+    with_diff, OfferExpiration, PathAgreement, PathSubscription, PathSubscriptionProposal,
+    QueryTimeoutMaxEvents,
+};
+use crate::negotiation::error::QueryEventsError;
 use crate::negotiation::ApprovalResult;
-use crate::rest_api::QueryTimeoutAppSessionId;
+use crate::rest_api::{QuerySubscribeOffer, QueryTimeoutAppSessionId};
 use ya_client::model::ErrorMessage;
 
 pub fn register_endpoints(scope: Scope) -> Scope {
     scope
+        .service(validate)
         .service(subscribe)
         .service(get_offers)
         .service(unsubscribe)
+        .service(refresh)
         .service(collect)
         .service(counter_proposal)
         .service(get_proposal)
@@ -31,13 +38,46 @@ pub fn register_endpoints(scope: Scope) -> Scope {
 async fn subscribe(
     market: Data<Arc<MarketService>>,
     body: Json<NewOffer>,
+    query: Query<QuerySubscribeOffer>,
     id: Identity,
 ) -> impl Responder {
-    market
-        .subscribe_offer(&body.into_inner(), &id)
+    let QuerySubscribeOffer {
+        app_session_id: session,
+        private,
+    } = query.into_inner();
+    let subscription_id = match private {
+        true => {
+            market
+                .subscribe_private_offer(&body.into_inner(), &id)
+                .await
+        }
+        false => market.subscribe_offer(&body.into_inner(), &id).await,
+    }
+    .log_err()?;
+    if let Err(e) = market
+        .bind_session(&session, &subscription_id, &id)
         .await
         .log_err()
-        .map(|id| HttpResponse::Created().json(id))
+    {
+        // Offer outside of its session couldn't be managed by the application.
+        market
+            .unsubscribe_offer(&subscription_id, &id)
+            .await
+            .log_err()
+            .ok();
+        return Err(MarketError::from(e));
+    }
+    Ok::<_, MarketError>(HttpResponse::Created().json(subscription_id))
+}
+
+/// Validate-only check of Offer content, which would be done on subscription.
+#[actix_web::post("/offers/validate")]
+async fn validate(
+    market: Data<Arc<MarketService>>,
+    body: Json<NewOffer>,
+    _id: Identity,
+) -> impl Responder {
+    HttpResponse::Ok().json(market.matcher.validate_offer(&body.into_inner()))
 }
 
 #[actix_web::get("/offers")]
@@ -62,22 +102,58 @@ async fn unsubscribe(
         .map(|_| HttpResponse::NoContent())
 }
 
+/// Extends Offer expiration. Subscription id and negotiations in progress are kept.
+#[actix_web::post("/offers/{subscription_id}/refresh")]
+async fn refresh(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathSubscription>,
+    body: Json<OfferExpiration>,
+    id: Identity,
+) -> impl Responder {
+    market
+        .refresh_offer(&path.into_inner().subscription_id, body.expiration_ts, &id)
+        .await
+        .log_err()
+        .map(|_| HttpResponse::NoContent())
+}
+
 #[actix_web::get("/offers/{subscription_id}/events")]
 async fn collect(
     market: Data<Arc<MarketService>>,
     path: Path<PathSubscription>,
     query: Query<QueryTimeoutMaxEvents>,
-    _id: Identity,
+    id: Identity,
 ) -> impl Responder {
     let subscription_id = path.into_inner().subscription_id;
     let timeout = query.timeout;
     let max_events = query.max_events;
     market
+        .check_session(&query.app_session_id, &subscription_id, &id)
+        .await
+        .log_err()?;
+    let events = market
         .provider_engine
         .query_events(&subscription_id, timeout, max_events)
         .await
-        .log_err()
-        .map(|events| HttpResponse::Ok().json(events))
+        .log_err()?;
+    if !query.include_diff {
+        return Ok(HttpResponse::Ok().json(events));
+    }
+
+    let common = &market.provider_engine.common;
+    let events = futures::stream::iter(events)
+        .then(|event| async move {
+            let diff = match &event {
+                ProviderEvent::ProposalEvent { proposal, .. } => {
+                    common.proposal_diff(proposal).await
+                }
+                _ => None,
+            };
+            with_diff(event, diff)
+        })
+        .collect::<Vec<_>>()
+        .await;
+    Ok::<_, QueryEventsError>(HttpResponse::Ok().json(events))
 }
 
 #[actix_web::post("/offers/{subscription_id}/proposals/{proposal_id}")]
@@ -150,6 +226,7 @@ async fn approve_agreement(
     let agreement_id = path.into_inner().to_id(Owner::Provider)?;
     let timeout = query.timeout;
     let session = query.into_inner().app_session_id;
+    market.touch_session(&session, &id).await;
     market
         .provider_engine
         .approve_agreement(id, &agreement_id, session, timeout)
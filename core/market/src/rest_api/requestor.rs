@@ -1,29 +1,35 @@
 use actix_web::web::{Data, Json, Path, Query};
 use actix_web::{HttpResponse, Responder, Scope};
+use futures::StreamExt;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use ya_client::model::market::{AgreementProposal, NewDemand, NewProposal, Reason};
+use ya_client::model::market::{AgreementProposal, NewDemand, NewProposal, Reason, RequestorEvent};
 use ya_client::model::ErrorMessage;
 use ya_service_api_web::middleware::Identity;
 use ya_service_bus::timeout::IntoTimeoutFuture;
 use ya_std_utils::LogErr;
 
-use crate::db::model::Owner;
-use crate::market::MarketService;
+use crate::db::model::{AgreementLabels, Owner};
+use crate::market::{MarketError, MarketService};
+use crate::negotiation::blacklist::{BlacklistSettings, FailureReport};
 
 use super::{
-    PathAgreement, PathSubscription, PathSubscriptionProposal, ProposalId, QueryTimeout,
+    with_diff, DirectOffer, PathAgreement, PathProvider, PathSubscription,
+    PathSubscriptionProposal, ProposalId, QueryProviderFailures, QueryTimeout,
     QueryTimeoutMaxEvents,
 };
+use crate::negotiation::error::QueryEventsError;
 use crate::negotiation::ApprovalStatus;
 use crate::rest_api::QueryAppSessionId;
 
 pub fn register_endpoints(scope: Scope) -> Scope {
     scope
+        .service(validate)
         .service(subscribe)
         .service(get_demands)
         .service(unsubscribe)
+        .service(negotiate_direct)
         .service(collect)
         .service(counter_proposal)
         .service(get_proposal)
@@ -32,19 +38,52 @@ pub fn register_endpoints(scope: Scope) -> Scope {
         .service(confirm_agreement)
         .service(wait_for_approval)
         .service(cancel_agreement)
+        .service(get_agreement_labels)
+        .service(set_agreement_labels)
+        .service(list_provider_failures)
+        .service(report_provider_failure)
+        .service(get_provider_metadata)
+        .service(unblacklist_provider)
+        .service(get_blacklist_settings)
+        .service(set_blacklist_settings)
 }
 
 #[actix_web::post("/demands")]
 async fn subscribe(
     market: Data<Arc<MarketService>>,
     body: Json<NewDemand>,
+    query: Query<QueryAppSessionId>,
     id: Identity,
 ) -> impl Responder {
-    market
+    let session = query.into_inner().app_session_id;
+    let subscription_id = market
         .subscribe_demand(&body.into_inner(), &id)
         .await
+        .log_err()?;
+    if let Err(e) = market
+        .bind_session(&session, &subscription_id, &id)
+        .await
         .log_err()
-        .map(|id| HttpResponse::Created().json(id))
+    {
+        // Demand outside of its session couldn't be managed by the application.
+        market
+            .unsubscribe_demand(&subscription_id, &id)
+            .await
+            .log_err()
+            .ok();
+        return Err(MarketError::from(e));
+    }
+    Ok::<_, MarketError>(HttpResponse::Created().json(subscription_id))
+}
+
+/// Validate-only check of Demand content, which would be done on subscription.
+#[actix_web::post("/demands/validate")]
+async fn validate(
+    market: Data<Arc<MarketService>>,
+    body: Json<NewDemand>,
+    _id: Identity,
+) -> impl Responder {
+    HttpResponse::Ok().json(market.matcher.validate_demand(&body.into_inner()))
 }
 
 #[actix_web::get("/demands")]
@@ -70,22 +109,64 @@ async fn unsubscribe(
         .map(|_| HttpResponse::NoContent())
 }
 
+/// Negotiates private Offer shared by the Provider. Initial Proposal
+/// is returned as Demand event, like for Offers matched on the market.
+#[actix_web::post("/demands/{subscription_id}/direct-offers")]
+async fn negotiate_direct(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathSubscription>,
+    body: Json<DirectOffer>,
+    id: Identity,
+) -> impl Responder {
+    let subscription_id = path.into_inner().subscription_id;
+    let DirectOffer {
+        provider_id,
+        offer_id,
+    } = body.into_inner();
+    market
+        .negotiate_direct(&subscription_id, provider_id, &offer_id, &id)
+        .await
+        .log_err()
+        .map(|_| HttpResponse::NoContent())
+}
+
 #[actix_web::get("/demands/{subscription_id}/events")]
 async fn collect(
     market: Data<Arc<MarketService>>,
     path: Path<PathSubscription>,
     query: Query<QueryTimeoutMaxEvents>,
-    _id: Identity, // TODO: use it
+    id: Identity,
 ) -> impl Responder {
     let subscription_id = path.into_inner().subscription_id;
     let timeout = query.timeout;
     let max_events = query.max_events;
     market
+        .check_session(&query.app_session_id, &subscription_id, &id)
+        .await
+        .log_err()?;
+    let events = market
         .requestor_engine
         .query_events(&subscription_id, timeout, max_events)
         .await
-        .log_err()
-        .map(|events| HttpResponse::Ok().json(events))
+        .log_err()?;
+    if !query.include_diff {
+        return Ok(HttpResponse::Ok().json(events));
+    }
+
+    let common = &market.requestor_engine.common;
+    let events = futures::stream::iter(events)
+        .then(|event| async move {
+            let diff = match &event {
+                RequestorEvent::ProposalEvent { proposal, .. } => {
+                    common.proposal_diff(proposal).await
+                }
+                _ => None,
+            };
+            with_diff(event, diff)
+        })
+        .collect::<Vec<_>>()
+        .await;
+    Ok::<_, QueryEventsError>(HttpResponse::Ok().json(events))
 }
 
 #[actix_web::post("/demands/{subscription_id}/proposals/{proposal_id}")]
@@ -181,9 +262,11 @@ async fn confirm_agreement(
     id: Identity,
 ) -> impl Responder {
     let agreement_id = path.into_inner().to_id(Owner::Requestor)?;
+    let session = query.into_inner().app_session_id;
+    market.touch_session(&session, &id).await;
     market
         .requestor_engine
-        .confirm_agreement(id, &agreement_id, query.into_inner().app_session_id)
+        .confirm_agreement(id, &agreement_id, session)
         .await
         .log_err()
         .map(|_| HttpResponse::NoContent().finish())
@@ -224,3 +307,97 @@ async fn cancel_agreement(
         .log_err()
         .map(|_| HttpResponse::Ok().finish())
 }
+
+#[actix_web::get("/agreements/{agreement_id}/labels")]
+async fn get_agreement_labels(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathAgreement>,
+    id: Identity,
+) -> impl Responder {
+    let agreement_id = path.into_inner().to_id(Owner::Requestor)?;
+    market
+        .get_agreement_labels(&agreement_id, &id)
+        .await
+        .log_err()
+        .map(|labels| HttpResponse::Ok().json(labels))
+}
+
+#[actix_web::put("/agreements/{agreement_id}/labels")]
+async fn set_agreement_labels(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathAgreement>,
+    id: Identity,
+    body: Json<AgreementLabels>,
+) -> impl Responder {
+    let agreement_id = path.into_inner().to_id(Owner::Requestor)?;
+    market
+        .set_agreement_labels(&agreement_id, body.into_inner(), &id)
+        .await
+        .log_err()
+        .map(|_| HttpResponse::NoContent().finish())
+}
+
+/// Failure statistics of Providers, which Requestor negotiated with.
+#[actix_web::get("/providers/failures")]
+async fn list_provider_failures(
+    market: Data<Arc<MarketService>>,
+    query: Query<QueryProviderFailures>,
+    _id: Identity,
+) -> impl Responder {
+    HttpResponse::Ok().json(market.list_provider_failures(query.blacklisted))
+}
+
+#[actix_web::post("/providers/{providerId}/failures")]
+async fn report_provider_failure(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathProvider>,
+    body: Json<FailureReport>,
+    _id: Identity,
+) -> impl Responder {
+    let stats = market.report_provider_failure(path.provider_id, body.into_inner());
+    HttpResponse::Created().json(stats)
+}
+
+/// Operator metadata signed by the Provider and attached to its Offers.
+#[actix_web::get("/providers/{providerId}/metadata")]
+async fn get_provider_metadata(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathProvider>,
+    _id: Identity,
+) -> impl Responder {
+    market
+        .provider_metadata(path.provider_id)
+        .await
+        .log_err()
+        .map(|metadata| HttpResponse::Ok().json(metadata))
+}
+
+#[actix_web::delete("/providers/{providerId}/blacklist")]
+async fn unblacklist_provider(
+    market: Data<Arc<MarketService>>,
+    path: Path<PathProvider>,
+    _id: Identity,
+) -> impl Responder {
+    market
+        .unblacklist_provider(path.provider_id)
+        .log_err()
+        .map_err(MarketError::from)
+        .map(|_| HttpResponse::NoContent().finish())
+}
+
+#[actix_web::get("/providers/blacklist/settings")]
+async fn get_blacklist_settings(market: Data<Arc<MarketService>>, _id: Identity) -> impl Responder {
+    HttpResponse::Ok().json(market.blacklist_settings())
+}
+
+#[actix_web::put("/providers/blacklist/settings")]
+async fn set_blacklist_settings(
+    market: Data<Arc<MarketService>>,
+    body: Json<BlacklistSettings>,
+    _id: Identity,
+) -> impl Responder {
+    market
+        .set_blacklist_settings(body.into_inner())
+        .map_err(MarketError::from)
+        .map(|settings| HttpResponse::Ok().json(settings))
+}
@@ -30,6 +30,10 @@ impl IdentityApi for MockIdentity {
             .map(|id| id.identity)
             .collect())
     }
+
+    async fn metadata(&self, _node_id: NodeId) -> Result<Option<String>, IdentityError> {
+        Ok(None)
+    }
 }
 
 impl MockIdentity {
@@ -79,6 +83,7 @@ pub fn generate_identity(name: &str) -> Identity {
     Identity {
         name: name.to_string(),
         role: "manager".to_string(),
+        delegated_by: None,
         identity: NodeId::from(random_node_id.as_bytes()),
     }
 }
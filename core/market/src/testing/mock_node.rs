@@ -238,6 +238,9 @@ impl MarketsNetwork {
             .add_handler(empty_on_offer_unsubscribed_bcast)
             .add_handler(empty_on_retrieve_offers)
             .add_handler(empty_query_offers_handler)
+            .add_handler(empty_on_refreshed_offers_bcast)
+            .add_handler(empty_on_offers_refreshed)
+            .add_handler(empty_query_offers_expiration_handler)
     }
 
     pub async fn add_provider_negotiation_api(
@@ -601,6 +604,7 @@ pub mod default {
         AgreementProtocolError, CommitAgreementError, CounterProposalError, ProposeAgreementError,
         RejectProposalError, TerminateAgreementError,
     };
+    use ya_client::model::NodeId;
 
     pub async fn empty_on_offers_retrieved(
         _caller: String,
@@ -637,6 +641,27 @@ pub mod default {
         Ok(vec![])
     }
 
+    pub async fn empty_on_refreshed_offers_bcast(
+        _caller: String,
+        _msg: RefreshedOffersBcast,
+    ) -> Result<Vec<(NodeId, OfferRefresh)>, ()> {
+        Ok(vec![])
+    }
+
+    pub async fn empty_on_offers_refreshed(
+        _caller: String,
+        _msg: OffersRefreshed,
+    ) -> Result<Vec<OfferRefresh>, ()> {
+        Ok(vec![])
+    }
+
+    pub async fn empty_query_offers_expiration_handler(
+        _caller: String,
+        _msg: QueryOffersExpiration,
+    ) -> Result<Vec<OfferRefresh>, DiscoveryRemoteError> {
+        Ok(vec![])
+    }
+
     pub async fn empty_on_initial_proposal(
         _caller: String,
         _msg: InitialProposalReceived,
@@ -54,6 +54,8 @@ pub fn generate_offer(id: &str, expiration_ts: NaiveDateTime) -> Offer {
         creation_ts: Utc::now().naive_utc(),
         insertion_ts: None,
         expiration_ts,
+        subscribed_expiration_ts: None,
+        private: false,
     }
 }
 
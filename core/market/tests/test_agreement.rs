@@ -1,5 +1,6 @@
 use actix_web::{http::StatusCode, web::Bytes};
 use chrono::{Duration, Utc};
+use std::collections::BTreeMap;
 
 use ya_client::model::market::Role;
 use ya_core_model::market;
@@ -12,8 +13,8 @@ use ya_market::testing::{
     mock_agreement::generate_agreement,
     mock_node::MarketServiceExt,
     proposal_util::{exchange_draft_proposals, NegotiationHelper},
-    AgreementDao, AgreementDaoError, AgreementError, AgreementState, ApprovalStatus,
-    MarketsNetwork, Owner, ProposalState, WaitForApprovalError,
+    AgreementDao, AgreementDaoError, AgreementError, AgreementFilter, AgreementState,
+    ApprovalStatus, MarketsNetwork, Owner, ProposalState, WaitForApprovalError,
 };
 use ya_service_bus::{typed as bus, RpcEndpoint};
 
@@ -114,6 +115,179 @@ async fn test_gsb_list_agreements() {
     assert_eq!(agreements[0].role, Role::Requestor);
 }
 
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_gsb_subscription_stats() {
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance(REQ_NAME)
+        .await
+        .add_market_instance(PROV_NAME)
+        .await;
+
+    let negotiation = exchange_draft_proposals(&network, REQ_NAME, PROV_NAME)
+        .await
+        .unwrap();
+    let req_market = network.get_market(REQ_NAME);
+    let req_id = network.get_default_id(REQ_NAME);
+
+    req_market
+        .requestor_engine
+        .create_agreement(
+            req_id.clone(),
+            &negotiation.proposal_id,
+            Utc::now() + Duration::hours(1),
+        )
+        .await
+        .unwrap();
+
+    let stats = bus::service(network.market_gsb_prefixes(REQ_NAME).1)
+        .send(market::GetSubscriptionStats {
+            subscription_id: negotiation.demand_id.to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Initial and countered Offer Proposals were received, Demand Proposal was sent.
+    assert_eq!(stats.proposals_received, 2);
+    assert_eq!(stats.proposals_sent, 1);
+    assert_eq!(stats.proposals_rejected, 0);
+    assert_eq!(stats.negotiations, 1);
+    assert_eq!(stats.agreements, 1);
+    assert_eq!(stats.conversion_rate, 1.0);
+    assert!(stats.avg_negotiation_latency.unwrap() >= 0.0);
+}
+
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_agreement_transcript() {
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance(REQ_NAME)
+        .await
+        .add_market_instance(PROV_NAME)
+        .await;
+
+    let proposal_id = exchange_draft_proposals(&network, REQ_NAME, PROV_NAME)
+        .await
+        .unwrap()
+        .proposal_id;
+    let req_market = network.get_market(REQ_NAME);
+    let req_id = network.get_default_id(REQ_NAME);
+
+    let agreement_id = req_market
+        .requestor_engine
+        .create_agreement(
+            req_id.clone(),
+            &proposal_id,
+            Utc::now() + Duration::hours(1),
+        )
+        .await
+        .unwrap();
+
+    let transcript = req_market
+        .agreement_transcript(&agreement_id.into_client(), &req_id)
+        .await
+        .unwrap();
+
+    // Initial Offer Proposal, Demand counter Proposal and Offer counter Proposal.
+    assert!(transcript.complete);
+    assert_eq!(transcript.proposals.len(), 3);
+    assert!(transcript.proposals[0].prev_proposal_id.is_none());
+    assert!(transcript.proposals[0].changes.is_none());
+    assert!(transcript.proposals[1..]
+        .iter()
+        .all(|proposal| proposal.changes.is_some()));
+    assert_eq!(
+        transcript.proposals[2].proposal_id,
+        proposal_id.into_client()
+    );
+
+    // Other nodes can't export the transcript.
+    let prov_id = network.get_default_id(PROV_NAME);
+    assert!(req_market
+        .agreement_transcript(&transcript.agreement_id, &prov_id)
+        .await
+        .is_err());
+}
+
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_agreement_labels() {
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance(REQ_NAME)
+        .await
+        .add_market_instance(PROV_NAME)
+        .await;
+
+    let proposal_id = exchange_draft_proposals(&network, REQ_NAME, PROV_NAME)
+        .await
+        .unwrap()
+        .proposal_id;
+    let req_market = network.get_market(REQ_NAME);
+    let req_engine = &req_market.requestor_engine;
+    let req_id = network.get_default_id(REQ_NAME);
+    let prov_id = network.get_default_id(PROV_NAME);
+
+    let agreement_id = req_engine
+        .create_agreement(
+            req_id.clone(),
+            &proposal_id,
+            Utc::now() + Duration::hours(1),
+        )
+        .await
+        .unwrap();
+
+    let mut labels = BTreeMap::new();
+    labels.insert("job".to_string(), "1234".to_string());
+    labels.insert("team".to_string(), "render".to_string());
+    req_market
+        .set_agreement_labels(&agreement_id, labels.clone(), &req_id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        req_market
+            .get_agreement_labels(&agreement_id, &req_id)
+            .await
+            .unwrap(),
+        labels
+    );
+
+    // Other node can't tag our Agreement.
+    assert!(req_market
+        .set_agreement_labels(&agreement_id, labels.clone(), &prov_id)
+        .await
+        .is_err());
+
+    let mut invalid = BTreeMap::new();
+    invalid.insert("job id".to_string(), "1234".to_string());
+    assert!(matches!(
+        req_market
+            .set_agreement_labels(&agreement_id, invalid, &req_id)
+            .await,
+        Err(AgreementError::InvalidLabels(..))
+    ));
+
+    let list = |label: &str| market::ListAgreements {
+        label: Some(label.to_string()),
+        ..Default::default()
+    };
+    let gsb = bus::service(network.market_gsb_prefixes(REQ_NAME).0);
+
+    let agreements = gsb.send(list("job=1234,team")).await.unwrap().unwrap();
+    assert_eq!(agreements.len(), 1);
+    assert_eq!(agreements[0].id, agreement_id.into_client());
+
+    let agreements = gsb.send(list("job=4321")).await.unwrap().unwrap();
+    assert!(agreements.is_empty());
+
+    let agreements = gsb.send(list("owner")).await.unwrap().unwrap();
+    assert!(agreements.is_empty());
+}
+
 #[cfg_attr(not(feature = "test-suite"), ignore)]
 #[serial_test::serial]
 async fn test_get_agreement() {
@@ -147,6 +321,73 @@ async fn test_get_agreement() {
     assert_eq!(agreement.offer.provider_id, prov_id.identity);
 }
 
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_list_agreements_filters_and_pagination() {
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance(REQ_NAME)
+        .await
+        .add_market_instance(PROV_NAME)
+        .await;
+
+    let req_market = network.get_market(REQ_NAME);
+    let req_id = network.get_default_id(REQ_NAME);
+    let prov_id = network.get_default_id(PROV_NAME);
+
+    let first = negotiate_agreement(&network, REQ_NAME, PROV_NAME, "negotiation", "r-s", "p-s")
+        .await
+        .unwrap()
+        .r_agreement;
+    let second = negotiate_agreement(&network, REQ_NAME, PROV_NAME, "negotiation", "r-s", "p-s")
+        .await
+        .unwrap()
+        .r_agreement;
+
+    let by_counterparty = AgreementFilter {
+        counterparty: Some(prov_id.identity),
+        ..Default::default()
+    };
+    let (page, total) = req_market
+        .list_agreements(&req_id, by_counterparty.clone(), Some(0), Some(1))
+        .await
+        .unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].id, first.into_client());
+
+    let (page, total) = req_market
+        .list_agreements(&req_id, by_counterparty, Some(1), Some(1))
+        .await
+        .unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].id, second.into_client());
+
+    // Requestor has no Agreements with the Provider role.
+    let as_provider = AgreementFilter {
+        role: Some(Owner::Provider),
+        ..Default::default()
+    };
+    let (page, total) = req_market
+        .list_agreements(&req_id, as_provider, None, None)
+        .await
+        .unwrap();
+    assert_eq!(total, 0);
+    assert!(page.is_empty());
+
+    // Unknown counterparty.
+    let unknown = AgreementFilter {
+        counterparty: Some(req_id.identity),
+        ..Default::default()
+    };
+    let (_, total) = req_market
+        .list_agreements(&req_id, unknown, None, None)
+        .await
+        .unwrap();
+    assert_eq!(total, 0);
+}
+
 #[cfg_attr(not(feature = "test-suite"), ignore)]
 #[serial_test::serial]
 async fn test_rest_get_not_existing_agreement() {
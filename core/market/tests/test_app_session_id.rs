@@ -2,6 +2,7 @@ use all_asserts::*;
 use chrono::{Duration, Utc};
 
 use ya_market::testing::agreement_utils::{negotiate_agreement, negotiate_agreement_with_ids};
+use ya_market::testing::client;
 use ya_market::testing::proposal_util::exchange_proposals_exclusive;
 use ya_market::testing::MarketsNetwork;
 use ya_market::testing::Owner;
@@ -618,3 +619,62 @@ async fn test_common_event_flow() {
 
     assert_eq!(events.len(), 0);
 }
+
+/// Negotiation events of subscription created in session can't be collected
+/// from other sessions. Removing session unsubscribes its Demands.
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_session_subscriptions_isolation() {
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance(REQ_NAME)
+        .await;
+
+    let market = network.get_market(REQ_NAME);
+    let id = network.get_default_id(REQ_NAME);
+    let session = Some("session-1".to_string());
+
+    let demand_id = market
+        .subscribe_demand(&client::sample_demand(), &id)
+        .await
+        .unwrap();
+    market
+        .bind_session(&session, &demand_id, &id)
+        .await
+        .unwrap();
+
+    assert!(market
+        .check_session(&session, &demand_id, &id)
+        .await
+        .is_ok());
+    assert!(market.check_session(&None, &demand_id, &id).await.is_ok());
+    assert!(market
+        .check_session(&Some("session-2".to_string()), &demand_id, &id)
+        .await
+        .is_err());
+
+    // Subscriptions created without session are visible in all sessions.
+    let unbound_id = market
+        .subscribe_demand(&client::sample_demand(), &id)
+        .await
+        .unwrap();
+    assert!(market
+        .check_session(&Some("session-2".to_string()), &unbound_id, &id)
+        .await
+        .is_ok());
+    market.unsubscribe_demand(&unbound_id, &id).await.unwrap();
+
+    let sessions = market.list_sessions(&id).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].app_session_id, "session-1");
+    assert_eq!(sessions[0].subscriptions, vec![demand_id.clone()]);
+
+    market.remove_session("session-1", &id).await.unwrap();
+    assert!(market.list_sessions(&id).await.unwrap().is_empty());
+    assert!(market
+        .get_demands(Some(id.clone()))
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(market.remove_session("session-1", &id).await.is_err());
+}
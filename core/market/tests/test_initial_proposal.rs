@@ -670,3 +670,61 @@ async fn test_respawn_proposal_after_terminate_by_provider() -> anyhow::Result<(
     assert_eq!(proposal.state, State::Initial);
     Ok(())
 }
+
+/// Private Offer isn't matched with Demands on the market, but Requestor
+/// can negotiate it directly, knowing Provider and Offer id.
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_direct_negotiation_of_private_offer() {
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance(REQ_NAME)
+        .await
+        .add_market_instance(PROV_NAME)
+        .await;
+
+    let req_mkt = network.get_market(REQ_NAME);
+    let req_id = network.get_default_id(REQ_NAME);
+    let prov_mkt = network.get_market(PROV_NAME);
+    let prov_id = network.get_default_id(PROV_NAME);
+
+    let demand_id = req_mkt
+        .subscribe_demand(&sample_demand(), &req_id)
+        .await
+        .unwrap();
+    let offer_id = prov_mkt
+        .subscribe_private_offer(&sample_offer(), &prov_id)
+        .await
+        .unwrap();
+
+    // Private Offer shouldn't be broadcasted nor matched.
+    let events = req_mkt
+        .query_events(&demand_id, 1.0, Some(5))
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 0);
+    assert!(req_mkt.get_offer(&offer_id).await.is_err());
+
+    req_mkt
+        .negotiate_direct(&demand_id, prov_id.identity, &offer_id, &req_id)
+        .await
+        .unwrap();
+
+    let events = req_mkt
+        .query_events(&demand_id, 5.0, Some(5))
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    let proposal = match events[0].clone() {
+        RequestorEvent::ProposalEvent { proposal, .. } => proposal,
+        e => panic!("Invalid event Type. ProposalEvent expected, got: {:?}", e),
+    };
+    assert_eq!(proposal.state, State::Initial);
+    assert_eq!(proposal.issuer_id, prov_id.identity);
+
+    // Only Demand owner can negotiate on its behalf.
+    assert!(prov_mkt
+        .negotiate_direct(&demand_id, prov_id.identity, &offer_id, &prov_id)
+        .await
+        .is_err());
+}
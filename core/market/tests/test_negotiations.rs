@@ -1,10 +1,11 @@
 use chrono::{Duration, Utc};
-use ya_client::model::market::{proposal::State, RequestorEvent};
+use std::sync::Arc;
+use ya_client::model::market::{proposal::State, ProviderEvent, RequestorEvent};
 use ya_framework_mocks::net::MockNet;
 use ya_market::testing::{
     agreement_utils::gen_reason,
     events_helper::{provider, requestor, ClientProposalHelper},
-    mock_node::assert_offers_broadcasted,
+    mock_node::{assert_offers_broadcasted, create_market_config_for_test},
     mock_offer::client::{not_matching_demand, not_matching_offer, sample_demand, sample_offer},
     mock_offer::flatten_json,
     negotiation::error::{CounterProposalError, RemoteProposalError},
@@ -1314,3 +1315,91 @@ async fn test_reject_initial_proposal() {
         ProposalState::Initial
     );
 }
+
+/// Draft Proposals not answered within `proposal_ttl` expire on both sides.
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_unanswered_proposal_expires() {
+    let mut config = create_market_config_for_test();
+    config.negotiation.proposal_ttl = Some(std::time::Duration::from_secs(2));
+    config.negotiation.proposal_expiry_interval = std::time::Duration::from_millis(100);
+
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .with_config(Arc::new(config))
+        .add_market_instance("Requestor1")
+        .await
+        .add_market_instance("Provider1")
+        .await;
+
+    let req_market = network.get_market("Requestor1");
+    let prov_market = network.get_market("Provider1");
+    let req_id = network.get_default_id("Requestor1");
+
+    let negotiation = exchange_draft_proposals(&network, "Requestor1", "Provider1")
+        .await
+        .unwrap();
+    let prov_proposal_id = negotiation.proposal_id.clone().translate(Owner::Provider);
+
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+
+    assert_eq!(
+        req_market
+            .get_proposal(&negotiation.proposal_id)
+            .await
+            .unwrap()
+            .body
+            .state,
+        ProposalState::Expired
+    );
+    assert_eq!(
+        prov_market
+            .get_proposal(&prov_proposal_id)
+            .await
+            .unwrap()
+            .body
+            .state,
+        ProposalState::Expired
+    );
+
+    // Both sides get rejection event.
+    let events = req_market
+        .requestor_engine
+        .query_events(&negotiation.demand_id, 1.0, Some(5))
+        .await
+        .unwrap();
+    match &events[..] {
+        [RequestorEvent::ProposalRejectedEvent { proposal_id, .. }] => {
+            assert_eq!(proposal_id, &negotiation.proposal_id.to_string())
+        }
+        events => panic!("Expected ProposalRejectedEvent, got: {:?}", events),
+    }
+
+    let events = prov_market
+        .provider_engine
+        .query_events(&negotiation.offer_id, 1.0, Some(5))
+        .await
+        .unwrap();
+    match &events[..] {
+        [ProviderEvent::ProposalRejectedEvent { proposal_id, .. }] => {
+            assert_eq!(proposal_id, &prov_proposal_id.to_string())
+        }
+        events => panic!("Expected ProposalRejectedEvent, got: {:?}", events),
+    }
+
+    match req_market
+        .requestor_engine
+        .counter_proposal(
+            &negotiation.demand_id,
+            &negotiation.proposal_id,
+            &sample_demand(),
+            &req_id,
+        )
+        .await
+    {
+        Err(ProposalError::Save(SaveProposalError::Expired(id))) => {
+            assert_eq!(id, negotiation.proposal_id)
+        }
+        e => panic!("Expected SaveProposalError::Expired, got: {:?}", e),
+    }
+}
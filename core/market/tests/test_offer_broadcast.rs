@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{TimeZone, Utc};
 use futures::{channel::mpsc, prelude::*};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -64,6 +64,108 @@ async fn test_broadcast_offer() {
     assert_unsunbscribes_broadcasted(&[&mkt2, &mkt3], &[offer_id]).await;
 }
 
+/// Refreshed Offer keeps its subscription id and new expiration
+/// should be propagated to nodes, which already know the Offer.
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_broadcast_offer_refresh() {
+    let _ = env_logger::builder().try_init();
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance("Node-1")
+        .await
+        .add_market_instance("Node-2")
+        .await;
+    // make node is subscribed to broadcasts
+    let mkt2 = network.get_market("Node-2");
+    let id2 = network.get_default_id("Node-2");
+    mkt2.subscribe_demand(&client::sample_demand(), &id2)
+        .await
+        .unwrap();
+
+    let mkt1 = network.get_market("Node-1");
+    let id1 = network.get_default_id("Node-1");
+    let offer_id = mkt1
+        .subscribe_offer(&client::sample_offer(), &id1)
+        .await
+        .unwrap();
+    assert_offers_broadcasted(&[&mkt2], &[offer_id.clone()]).await;
+
+    // Only extending expiration is allowed.
+    let offer = mkt1.get_offer(&offer_id).await.unwrap();
+    let earlier = offer.expiration_ts - chrono::Duration::minutes(1);
+    assert!(mkt1
+        .refresh_offer(&offer_id, Utc.from_utc_datetime(&earlier), &id1)
+        .await
+        .is_err());
+    // Only Offer owner can refresh it.
+    assert!(mkt2
+        .refresh_offer(&offer_id, Utc::now() + chrono::Duration::hours(2), &id2)
+        .await
+        .is_err());
+
+    let expiration = Utc::now() + chrono::Duration::hours(2);
+    mkt1.refresh_offer(&offer_id, expiration, &id1)
+        .await
+        .unwrap();
+    let refreshed = mkt1.get_offer(&offer_id).await.unwrap();
+    assert_eq!(refreshed.id, offer_id);
+    assert!(refreshed.expiration_ts > offer.expiration_ts);
+    refreshed.validate().unwrap();
+
+    let mut propagated = false;
+    for _ in 0..20 {
+        if mkt2.get_offer(&offer_id).await.unwrap().expiration_ts > offer.expiration_ts {
+            propagated = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    assert!(propagated, "Offer refresh was not propagated");
+}
+
+/// Raw view of broadcasted Offer should point to the node, from which it was received.
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_inspect_broadcasted_offer() {
+    let _ = env_logger::builder().try_init();
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance("Node-1")
+        .await
+        .add_market_instance("Node-2")
+        .await;
+
+    let mkt1 = network.get_market("Node-1");
+    let id1 = network.get_default_id("Node-1");
+    let mkt2 = network.get_market("Node-2");
+    let id2 = network.get_default_id("Node-2");
+
+    let offer_id = mkt1
+        .subscribe_offer(&client::sample_offer(), &id1)
+        .await
+        .unwrap();
+    assert_offers_broadcasted(&[&mkt2], &[offer_id.clone()]).await;
+
+    let local = mkt1.inspect_subscription(&offer_id, &id1).await.unwrap();
+    assert_eq!(local.propagation_path, vec![id1.identity]);
+    assert!(local.received_ts.is_none());
+
+    let remote = mkt2.inspect_subscription(&offer_id, &id2).await.unwrap();
+    assert_eq!(remote.propagation_path, vec![id1.identity]);
+    assert!(remote.received_ts.is_some());
+    assert_eq!(remote.constraints, local.constraints);
+    assert_eq!(remote.properties, local.properties);
+
+    // Demands are visible only to their owners.
+    let demand_id = mkt2
+        .subscribe_demand(&client::sample_demand(), &id2)
+        .await
+        .unwrap();
+    assert!(mkt2.inspect_subscription(&demand_id, &id2).await.is_ok());
+    assert!(mkt2.inspect_subscription(&demand_id, &id1).await.is_err());
+}
+
 /// This test checks, if Discovery interface calls expected sequence of callbacks.
 /// In result Offer should be available on Node, that received broadcast.
 /// Note: We don't need this test to check, if broadcasting works. test_broadcast_offer
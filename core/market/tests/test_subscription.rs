@@ -1,6 +1,7 @@
 use ya_framework_mocks::net::MockNet;
 use ya_market::assert_err_eq;
 use ya_market::testing::client::{sample_demand, sample_offer};
+use ya_market::testing::mock_identity::generate_identity;
 use ya_market::testing::mock_offer::flatten_json;
 use ya_market::testing::{DemandError, QueryOfferError};
 use ya_market::testing::{MarketServiceExt, MarketsNetwork};
@@ -89,3 +90,33 @@ async fn test_subscribe_demand() {
         market1.get_demand(&subscription_id).await
     );
 }
+
+/// Delegated identity subscribes Demand on behalf of the owner,
+/// which must be identity of the node.
+#[cfg_attr(not(feature = "test-suite"), ignore)]
+#[serial_test::serial]
+async fn test_subscribe_delegated_demand() {
+    let network = MarketsNetwork::new(None, MockNet::new())
+        .await
+        .add_market_instance("Node-1")
+        .await;
+
+    let market1 = network.get_market("Node-1");
+    let manager = network.create_identity("Node-1", "Manager");
+    let mut owner = network.get_default_id("Node-1");
+    owner.delegated_by = Some(manager.identity);
+
+    let subscription_id = market1
+        .subscribe_demand(&sample_demand(), &owner)
+        .await
+        .unwrap();
+    let demand = market1.get_demand(&subscription_id).await.unwrap();
+    assert_eq!(demand.node_id, owner.identity);
+
+    let mut foreign = generate_identity("Foreign");
+    foreign.delegated_by = Some(manager.identity);
+    assert_err_eq!(
+        DemandError::ForeignOwner(foreign.identity),
+        market1.subscribe_demand(&sample_demand(), &foreign).await
+    );
+}
@@ -3,7 +3,7 @@
 //! Top level objects constitutes public activity API.
 //! Local and Exeunit are in dedicated submodules.
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use ya_client_model::activity::{
     ActivityState, ActivityUsage, ExeScriptCommand, ExeScriptCommandResult, ExeScriptCommandState,
@@ -218,6 +218,16 @@ pub struct Exec {
     pub batch_id: String,
     pub exe_script: Vec<ExeScriptCommand>,
     pub timeout: Option<f32>,
+    /// Indices of `Run` commands, which accept stdin streamed with [`WriteStdin`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interactive: Vec<usize>,
+    /// OTLP/HTTP endpoint, which usage and runtime metrics are pushed to. Replaces
+    /// the endpoint set in the Agreement. Must be whitelisted in the manifest outbound access.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_push_url: Option<String>,
+    /// Process options of `Run` commands by their index, see [`exe_script::parse`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub run_options: BTreeMap<usize, RunOptions>,
 }
 
 impl RpcMessage for Exec {
@@ -226,6 +236,287 @@ impl RpcMessage for Exec {
     type Error = RpcMessageError;
 }
 
+/// Options of the process started by `Run` command, which `ExeScriptCommand::Run`
+/// has no place for. Enforcing them is up to the runtime.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunOptions {
+    /// Environment variables added to the environment set with `Deploy`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Working directory of the process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// User, which the process is run as.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl RunOptions {
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty() && self.cwd.is_none() && self.user.is_none()
+    }
+}
+
+/// Parsing of ExeScript sent by Requestors.
+pub mod exe_script {
+    use serde::de::Error as _;
+    use serde_json::Value;
+
+    use ya_client_model::activity::exe_script_command::Capture;
+
+    use super::*;
+
+    /// Structured form of `run` command. Arguments are passed as they are,
+    /// without being split or quoted by a shell.
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct StructuredRun {
+        argv: Vec<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+        #[serde(default)]
+        cwd: Option<String>,
+        #[serde(default)]
+        user: Option<String>,
+        #[serde(default)]
+        capture: Option<Capture>,
+    }
+
+    impl StructuredRun {
+        fn split(self) -> Result<(ExeScriptCommand, RunOptions), serde_json::Error> {
+            let mut argv = self.argv.into_iter();
+            let entry_point = argv
+                .next()
+                .filter(|entry_point| !entry_point.is_empty())
+                .ok_or_else(|| serde_json::Error::custom("run: argv must not be empty"))?;
+            if let Some(name) = self
+                .env
+                .keys()
+                .find(|name| name.is_empty() || name.contains('='))
+            {
+                return Err(serde_json::Error::custom(format!(
+                    "run: invalid environment variable name '{}'",
+                    name
+                )));
+            }
+            if matches!(&self.user, Some(user) if user.is_empty()) {
+                return Err(serde_json::Error::custom("run: user must not be empty"));
+            }
+
+            let command = ExeScriptCommand::Run {
+                entry_point,
+                args: argv.collect(),
+                capture: self.capture,
+            };
+            let options = RunOptions {
+                env: self.env,
+                cwd: self.cwd,
+                user: self.user,
+            };
+            Ok((command, options))
+        }
+    }
+
+    /// Parses ExeScript in JSON. Besides commands known to [`ExeScriptCommand`],
+    /// accepts `run` with `argv` array and optional `env`, `cwd` and `user`.
+    /// Such commands are converted to plain `Run` and their options are returned
+    /// by command index.
+    pub fn parse(
+        text: &str,
+    ) -> Result<(Vec<ExeScriptCommand>, BTreeMap<usize, RunOptions>), serde_json::Error> {
+        let values: Vec<Value> = serde_json::from_str(text)?;
+        let mut commands = Vec::with_capacity(values.len());
+        let mut run_options = BTreeMap::new();
+
+        for (idx, value) in values.into_iter().enumerate() {
+            match value.get("run").and_then(|run| run.get("argv")) {
+                Some(_) => {
+                    let run = value.get("run").cloned().unwrap_or_default();
+                    let (command, options) =
+                        serde_json::from_value::<StructuredRun>(run)?.split()?;
+                    if !options.is_empty() {
+                        run_options.insert(idx, options);
+                    }
+                    commands.push(command);
+                }
+                None => commands.push(serde_json::from_value(value)?),
+            }
+        }
+        Ok((commands, run_options))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parse_structured_run() {
+            let (commands, options) = parse(
+                r#"[
+                {"run": {"entry_point": "/bin/sh", "args": ["-c", "echo a b"]}},
+                {"run": {
+                    "argv": ["/bin/echo", "a b", "'c'"],
+                    "env": {"NAME": "x y"},
+                    "cwd": "/work",
+                    "user": "golem"
+                }},
+                {"run": {"argv": ["/bin/true"]}}
+            ]"#,
+            )
+            .unwrap();
+
+            assert_eq!(commands.len(), 3);
+            match &commands[1] {
+                ExeScriptCommand::Run {
+                    entry_point, args, ..
+                } => {
+                    assert_eq!(entry_point, "/bin/echo");
+                    assert_eq!(args, &vec!["a b".to_string(), "'c'".to_string()]);
+                }
+                _ => panic!("expected run command"),
+            }
+            assert!(matches!(&commands[2], ExeScriptCommand::Run { args, .. } if args.is_empty()));
+
+            assert_eq!(options.keys().collect::<Vec<_>>(), vec![&1]);
+            assert_eq!(options[&1].env["NAME"], "x y");
+            assert_eq!(options[&1].cwd.as_deref(), Some("/work"));
+            assert_eq!(options[&1].user.as_deref(), Some("golem"));
+        }
+
+        #[test]
+        fn reject_invalid_structured_run() {
+            assert!(parse(r#"[{"run": {"argv": []}}]"#).is_err());
+            assert!(parse(r#"[{"run": {"argv": ["/bin/true"], "env": {"A=B": "c"}}}]"#).is_err());
+            assert!(parse(r#"[{"run": {"argv": ["/bin/true"], "user": ""}}]"#).is_err());
+            assert!(parse(r#"[{"run": {"argv": ["/bin/true"], "shell": true}}]"#).is_err());
+        }
+    }
+}
+
+/// Maximum size of data sent in a single [`WriteStdin`] call.
+pub const STDIN_CHUNK_MAX_SIZE: usize = 256 * 1024;
+
+/// Write data to stdin of a running interactive command. Returns number of bytes written.
+///
+/// Input is buffered in a bounded queue and the call resolves once data is queued,
+/// so callers are slowed down when the command doesn't keep up. Setting `close`
+/// closes stdin (after writing `data`), which is observed by the command as EOF.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteStdin {
+    pub activity_id: String,
+    pub batch_id: String,
+    pub idx: usize,
+    pub data: Vec<u8>,
+    pub close: bool,
+}
+
+impl RpcMessage for WriteStdin {
+    const ID: &'static str = "WriteStdin";
+    type Item = usize;
+    type Error = RpcMessageError;
+}
+
+/// Size of a terminal window in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Default for TerminalSize {
+    fn default() -> Self {
+        TerminalSize { cols: 80, rows: 24 }
+    }
+}
+
+/// Open a pseudo-terminal session within a started activity. Returns `terminal_id`.
+///
+/// The entry point is executed the same way as a `Run` command, with a shell
+/// (`/bin/sh`) by default. Output is read with [`StreamTerminal`] and input is written
+/// with [`WriteTerminal`]. The session ends when the process exits or when it is closed
+/// with [`CloseTerminal`].
+///
+/// Terminals are available only for runtimes in process per command mode on unix hosts.
+/// Activities of service mode runtimes reject this message.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenTerminal {
+    pub activity_id: String,
+    pub entry_point: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub size: TerminalSize,
+}
+
+impl RpcMessage for OpenTerminal {
+    const ID: &'static str = "OpenTerminal";
+    type Item = String;
+    type Error = RpcMessageError;
+}
+
+/// Change window size of a terminal session.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResizeTerminal {
+    pub activity_id: String,
+    pub terminal_id: String,
+    pub size: TerminalSize,
+}
+
+impl RpcMessage for ResizeTerminal {
+    const ID: &'static str = "ResizeTerminal";
+    type Item = ();
+    type Error = RpcMessageError;
+}
+
+/// Write input to a terminal session. Returns number of bytes written.
+///
+/// Chunks are limited to [`STDIN_CHUNK_MAX_SIZE`] and buffered the same way as in [`WriteStdin`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteTerminal {
+    pub activity_id: String,
+    pub terminal_id: String,
+    pub data: Vec<u8>,
+}
+
+impl RpcMessage for WriteTerminal {
+    const ID: &'static str = "WriteTerminal";
+    type Item = usize;
+    type Error = RpcMessageError;
+}
+
+/// Stream output of a terminal session. Output can be streamed only once.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamTerminal {
+    pub activity_id: String,
+    pub terminal_id: String,
+}
+
+impl RpcStreamMessage for StreamTerminal {
+    const ID: &'static str = "StreamTerminal";
+    type Item = Vec<u8>;
+    type Error = RpcMessageError;
+}
+
+/// Close a terminal session, terminating its processes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseTerminal {
+    pub activity_id: String,
+    pub terminal_id: String,
+}
+
+impl RpcMessage for CloseTerminal {
+    const ID: &'static str = "CloseTerminal";
+    type Item = ();
+    type Error = RpcMessageError;
+}
+
 /// Get script execution results.
 ///
 /// Returns vector of results: one for every **already executed** script command.
@@ -272,6 +563,56 @@ impl RpcMessage for GetRunningCommand {
     type Error = RpcMessageError;
 }
 
+/// Get state of all script commands at once: their status, timing and tails of outputs.
+///
+/// Unlike `GetExecBatchResults` doesn't wait for commands to finish.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetExecBatchState {
+    pub activity_id: String,
+    pub batch_id: String,
+    /// Maximal number of trailing characters of each command output.
+    pub output_limit: Option<usize>,
+}
+
+impl RpcMessage for GetExecBatchState {
+    const ID: &'static str = "GetExecBatchState";
+    type Item = ExecBatchState;
+    type Error = RpcMessageError;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecBatchState {
+    pub batch_id: String,
+    /// One entry for every script command, in script order.
+    pub commands: Vec<ExecCommandState>,
+    pub is_batch_finished: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExecCommandStatus {
+    Pending,
+    Running,
+    Ok,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecCommandState {
+    pub index: u32,
+    pub status: ExecCommandStatus,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Time elapsed since start until finish or, for running command, until now.
+    pub duration_ms: Option<u64>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub message: Option<String>,
+}
+
 /// Local activity bus API (used by ExeUnit).
 ///
 /// Should be accessible only from local service bus (not via net ie. from remote hosts).
@@ -314,6 +655,9 @@ pub mod local {
         pub timeout: Option<f32>,
         #[serde(default)]
         pub credentials: Option<Credentials>,
+        /// Security profile confining the runtime, reported once initialized.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub security_profile: Option<String>,
     }
 
     impl SetState {
@@ -327,8 +671,14 @@ pub mod local {
                 state,
                 timeout: Default::default(),
                 credentials,
+                security_profile: None,
             }
         }
+
+        pub fn with_security_profile(mut self, security_profile: String) -> Self {
+            self.security_profile = Some(security_profile);
+            self
+        }
     }
 
     impl RpcMessage for SetState {
@@ -105,6 +105,31 @@ pub struct AppKey {
     pub identity: NodeId,
     pub created_date: NaiveDateTime,
     pub allow_origins: Vec<String>,
+    /// Number of REST API requests authorized with this app-key.
+    #[serde(default)]
+    pub request_count: u64,
+    #[serde(default)]
+    pub last_used_date: Option<NaiveDateTime>,
+    /// Address, from which the last request was made.
+    #[serde(default)]
+    pub last_source_ip: Option<String>,
+}
+
+/// Usage of app-key since the previous report.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppKeyUsage {
+    pub name: String,
+    pub request_count: u64,
+    pub last_used_date: NaiveDateTime,
+    pub last_source_ip: Option<String>,
+}
+
+/// Reported periodically by REST API authorization middleware.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordUsage {
+    pub usage: Vec<AppKeyUsage>,
 }
 
 impl RpcMessage for Create {
@@ -137,6 +162,12 @@ impl RpcMessage for Remove {
     type Error = Error;
 }
 
+impl RpcMessage for RecordUsage {
+    const ID: &'static str = "RecordUsage";
+    type Item = ();
+    type Error = Error;
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Subscribe {
@@ -306,6 +306,8 @@ pub struct SchedulePayment {
     platform: String,
     deposit_id: Option<Deposit>,
     due_date: DateTime<Utc>,
+    #[serde(default)]
+    gasless: bool,
 }
 
 impl SchedulePayment {
@@ -324,9 +326,17 @@ impl SchedulePayment {
             platform,
             deposit_id,
             due_date,
+            gasless: false,
         }
     }
 
+    /// Allows the driver to relay the payment, if the sender can't pay for gas.
+    /// Set only when the Provider accepted gasless payments on the platform.
+    pub fn with_gasless(mut self, gasless: bool) -> Self {
+        self.gasless = gasless;
+        self
+    }
+
     pub fn amount(&self) -> BigDecimal {
         self.amount.clone()
     }
@@ -350,6 +360,10 @@ impl SchedulePayment {
     pub fn due_date(&self) -> DateTime<Utc> {
         self.due_date
     }
+
+    pub fn gasless(&self) -> bool {
+        self.gasless
+    }
 }
 
 impl RpcMessage for SchedulePayment {
@@ -514,6 +528,66 @@ impl RpcMessage for Exit {
     type Error = GenericError;
 }
 
+// ************************** WITHDRAW **************************
+
+/// Estimates the cost of withdrawing token funds to an external address.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EstimateWithdraw {
+    pub sender: String,
+    pub to: String,
+    /// Defaults to the whole token balance
+    pub amount: Option<BigDecimal>,
+    pub network: String,
+}
+
+impl RpcMessage for EstimateWithdraw {
+    const ID: &'static str = "EstimateWithdraw";
+    type Item = WithdrawEstimate;
+    type Error = GenericError;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawEstimate {
+    pub network: String,
+    pub token: String,
+    pub sender: String,
+    pub to: String,
+    pub amount: BigDecimal,
+    pub token_balance: BigDecimal,
+    pub gas_details: Option<GasDetails>,
+    /// Upper bound of the fee paid in the native currency of the network
+    pub max_fee: BigDecimal,
+    pub sufficient_gas: bool,
+}
+
+/// Withdraws token funds to an external address.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Withdraw {
+    pub sender: String,
+    pub to: String,
+    /// Defaults to the whole token balance
+    pub amount: Option<BigDecimal>,
+    pub network: String,
+}
+
+impl From<Withdraw> for EstimateWithdraw {
+    fn from(msg: Withdraw) -> Self {
+        EstimateWithdraw {
+            sender: msg.sender,
+            to: msg.to,
+            amount: msg.amount,
+            network: msg.network,
+        }
+    }
+}
+
+impl RpcMessage for Withdraw {
+    const ID: &'static str = "Withdraw";
+    type Item = String; // Transaction Identifier
+    type Error = GenericError;
+}
+
 // ************************** TRANSFER **************************
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -562,6 +636,74 @@ impl RpcMessage for Transfer {
     type Error = GenericError;
 }
 
+// ********************** PENDING TRANSACTIONS **********************
+
+/// Lists transactions sent by the driver, which are not confirmed yet,
+/// and detects gaps between on-chain and pending nonces.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GetPendingTransactions {
+    pub network: Option<String>,
+    pub sender: Option<String>,
+}
+
+impl RpcMessage for GetPendingTransactions {
+    const ID: &'static str = "GetPendingTransactions";
+    type Item = PendingTransactions;
+    type Error = GenericError;
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingTransactions {
+    pub transactions: Vec<PendingTransaction>,
+    pub nonce_gaps: Vec<NonceGap>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingTransaction {
+    pub id: i64,
+    pub network: String,
+    pub sender: String,
+    pub nonce: Option<u64>,
+    pub tx_hash: Option<String>,
+    /// In Gwei
+    pub max_fee_per_gas: Option<BigDecimal>,
+    /// In Gwei
+    pub priority_fee: Option<BigDecimal>,
+    pub created: DateTime<Utc>,
+    pub broadcast: Option<DateTime<Utc>>,
+    pub stuck_since: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// Pending transactions of the sender start above the next on-chain nonce,
+/// so none of them can be mined until the missing nonces are used.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceGap {
+    pub network: String,
+    pub sender: String,
+    pub next_nonce: u64,
+    pub first_pending_nonce: u64,
+}
+
+// ************************ BUMP TRANSACTION ************************
+
+/// Re-broadcasts a pending transaction with the same nonce and increased gas price.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BumpTransaction {
+    pub id: i64,
+    /// Gas price increase in percent. Nodes accept replacements raising price by at least 10%.
+    pub gas_increase: u32,
+}
+
+impl RpcMessage for BumpTransaction {
+    const ID: &'static str = "BumpTransaction";
+    type Item = PendingTransaction;
+    type Error = GenericError;
+}
+
 // ************************ SIGN PAYMENT ************************
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -676,4 +818,10 @@ pub struct GasDetails {
     pub currency_short_name: String,
     pub currency_long_name: String,
     pub balance: BigDecimal,
+    /// Where to get gas for free, on test networks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faucet_url: Option<String>,
+    /// Where to bridge gas from Ethereum Mainnet, on L2 networks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridge_url: Option<String>,
 }
@@ -0,0 +1,276 @@
+//! Local event bus API.
+//!
+//! Market, Activity and Payment services publish lifecycle events to a single
+//! endpoint, so local applications can subscribe once instead of polling
+//! event endpoints of three REST APIs. Events are numbered and persisted, so
+//! subscribers can replay the ones they missed, also across daemon restarts.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use ya_client_model::{ErrorMessage, NodeId};
+use ya_service_bus::RpcMessage;
+
+pub const BUS_ID: &str = "/local/events";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum EventSource {
+    Market,
+    Activity,
+    Payment,
+}
+
+/// Event content tagged with its type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "eventType")]
+pub enum EventPayload {
+    #[serde(rename_all = "camelCase")]
+    AgreementStateChanged { agreement_id: String, state: String },
+    #[serde(rename_all = "camelCase")]
+    ActivityCreated {
+        activity_id: String,
+        agreement_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ActivityStateChanged { activity_id: String, state: String },
+    #[serde(rename_all = "camelCase")]
+    DebitNoteReceived {
+        debit_note_id: String,
+        activity_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    DebitNoteAccepted { debit_note_id: String },
+    #[serde(rename_all = "camelCase")]
+    InvoiceReceived {
+        invoice_id: String,
+        agreement_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    InvoiceAccepted {
+        invoice_id: String,
+        agreement_id: String,
+    },
+    /// Received invoice hasn't been accepted and its acceptance deadline is approaching.
+    #[serde(rename_all = "camelCase")]
+    InvoiceAcceptanceDue {
+        invoice_id: String,
+        agreement_id: String,
+        deadline: DateTime<Utc>,
+    },
+    #[serde(rename_all = "camelCase")]
+    PaymentSent {
+        payment_id: String,
+        platform: String,
+        amount: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    PaymentReceived {
+        payment_id: String,
+        platform: String,
+        amount: String,
+    },
+}
+
+impl EventPayload {
+    pub fn source(&self) -> EventSource {
+        match self {
+            EventPayload::AgreementStateChanged { .. } => EventSource::Market,
+            EventPayload::ActivityCreated { .. } | EventPayload::ActivityStateChanged { .. } => {
+                EventSource::Activity
+            }
+            EventPayload::DebitNoteReceived { .. }
+            | EventPayload::DebitNoteAccepted { .. }
+            | EventPayload::InvoiceReceived { .. }
+            | EventPayload::InvoiceAccepted { .. }
+            | EventPayload::InvoiceAcceptanceDue { .. }
+            | EventPayload::PaymentSent { .. }
+            | EventPayload::PaymentReceived { .. } => EventSource::Payment,
+        }
+    }
+
+    /// Value of `eventType` tag.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            EventPayload::AgreementStateChanged { .. } => "AgreementStateChanged",
+            EventPayload::ActivityCreated { .. } => "ActivityCreated",
+            EventPayload::ActivityStateChanged { .. } => "ActivityStateChanged",
+            EventPayload::DebitNoteReceived { .. } => "DebitNoteReceived",
+            EventPayload::DebitNoteAccepted { .. } => "DebitNoteAccepted",
+            EventPayload::InvoiceReceived { .. } => "InvoiceReceived",
+            EventPayload::InvoiceAccepted { .. } => "InvoiceAccepted",
+            EventPayload::InvoiceAcceptanceDue { .. } => "InvoiceAcceptanceDue",
+            EventPayload::PaymentSent { .. } => "PaymentSent",
+            EventPayload::PaymentReceived { .. } => "PaymentReceived",
+        }
+    }
+}
+
+/// Delivered to endpoints of subscribers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifecycleEvent {
+    /// Assigned by the events service, when event is published. Increases with every
+    /// event and keeps increasing across restarts.
+    #[serde(default)]
+    pub sequence: u64,
+    pub event_date: DateTime<Utc>,
+    pub source: EventSource,
+    /// Local identity, which the event concerns, if known.
+    pub node_id: Option<NodeId>,
+    #[serde(flatten)]
+    pub payload: EventPayload,
+}
+
+impl LifecycleEvent {
+    pub fn new(node_id: Option<NodeId>, payload: EventPayload) -> Self {
+        LifecycleEvent {
+            sequence: 0,
+            event_date: Utc::now(),
+            source: payload.source(),
+            node_id,
+            payload,
+        }
+    }
+}
+
+impl RpcMessage for LifecycleEvent {
+    const ID: &'static str = "LifecycleEvent";
+    type Item = ();
+    type Error = ErrorMessage;
+}
+
+/// Empty lists match everything. Events with unknown identity match any `node_id`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilter {
+    #[serde(default)]
+    pub sources: Vec<EventSource>,
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub node_id: Option<NodeId>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &LifecycleEvent) -> bool {
+        (self.sources.is_empty() || self.sources.contains(&event.source))
+            && (self.event_types.is_empty()
+                || self
+                    .event_types
+                    .iter()
+                    .any(|t| t == event.payload.event_type()))
+            && match (self.node_id, event.node_id) {
+                (Some(filter), Some(node_id)) => filter == node_id,
+                _ => true,
+            }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Publish(pub LifecycleEvent);
+
+impl RpcMessage for Publish {
+    const ID: &'static str = "Publish";
+    type Item = ();
+    type Error = ErrorMessage;
+}
+
+/// Registers GSB endpoint, which will receive `LifecycleEvent`s matching the filter
+/// in order of their sequence. Events with sequence greater than `since` are replayed
+/// first, as long as they are still retained. Subscribing again with the same endpoint
+/// replaces the subscription. Returns sequence of the latest event (0 if none).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscribe {
+    pub endpoint: String,
+    #[serde(default)]
+    pub filter: EventFilter,
+    #[serde(default)]
+    pub since: Option<u64>,
+}
+
+impl RpcMessage for Subscribe {
+    const ID: &'static str = "Subscribe";
+    type Item = u64;
+    type Error = ErrorMessage;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Unsubscribe {
+    pub endpoint: String,
+}
+
+impl RpcMessage for Unsubscribe {
+    const ID: &'static str = "Unsubscribe";
+    type Item = ();
+    type Error = ErrorMessage;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_is_type_tagged() {
+        let event = LifecycleEvent::new(
+            None,
+            EventPayload::InvoiceReceived {
+                invoice_id: "inv".to_string(),
+                agreement_id: "agr".to_string(),
+            },
+        );
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["eventType"], "InvoiceReceived");
+        assert_eq!(json["source"], "payment");
+        assert_eq!(json["invoiceId"], "inv");
+        assert_eq!(
+            serde_json::from_value::<LifecycleEvent>(json).unwrap(),
+            event
+        );
+    }
+
+    #[test]
+    fn filter() {
+        let node_id: NodeId = "0xbabe000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+        let other: NodeId = "0xcafe000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+        let event = LifecycleEvent::new(
+            Some(node_id),
+            EventPayload::ActivityStateChanged {
+                activity_id: "act".to_string(),
+                state: "Ready".to_string(),
+            },
+        );
+
+        assert!(EventFilter::default().matches(&event));
+        assert!(EventFilter {
+            sources: vec![EventSource::Activity],
+            event_types: vec!["ActivityStateChanged".to_string()],
+            node_id: Some(node_id),
+        }
+        .matches(&event));
+        assert!(!EventFilter {
+            sources: vec![EventSource::Market, EventSource::Payment],
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!EventFilter {
+            event_types: vec!["ActivityCreated".to_string()],
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!EventFilter {
+            node_id: Some(other),
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+}
@@ -57,6 +57,45 @@ impl RpcMessage for GetChunk {
     type Error = Error;
 }
 
+/// Gets integrity manifest of file published through gftp.
+/// Returns GftpManifest structure.
+/// Publishers which don't support manifests respond with GSB error,
+/// in which case downloaders should fall back to `GetMetadata`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetManifest;
+
+impl RpcMessage for GetManifest {
+    const ID: &'static str = "GetManifest";
+    type Item = GftpManifest;
+    type Error = Error;
+}
+
+/// File split into consecutive blocks of `block_size` bytes (the last one can be smaller),
+/// which can be downloaded and verified independently.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GftpManifest {
+    pub file_size: u64,
+    pub block_size: u64,
+    /// Hex encoded SHA3-256 hashes of consecutive blocks.
+    pub block_hashes: Vec<String>,
+    /// Hex encoded SHA3-256 hash of the whole file.
+    pub hash: String,
+}
+
+impl GftpManifest {
+    pub fn num_blocks(&self) -> u64 {
+        self.block_hashes.len() as u64
+    }
+
+    /// Returns offset and size of block with given index.
+    pub fn block_range(&self, index: u64) -> (u64, u64) {
+        let offset = index * self.block_size;
+        (offset, self.block_size.min(self.file_size - offset))
+    }
+}
+
 // =========================================== //
 // Upload messages
 // =========================================== //
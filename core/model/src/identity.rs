@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString, EnumVariantNames};
 use thiserror::Error;
 
 use ya_client_model::NodeId;
@@ -33,6 +35,10 @@ pub enum Error {
     BadKeyStoreFormat(String),
     #[error("invalid password")]
     InvalidPassword,
+    #[error("invalid delegation: {0}")]
+    InvalidDelegation(String),
+    #[error("invalid metadata: {0}")]
+    InvalidMetadata(String),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Error)]
@@ -103,6 +109,8 @@ pub enum Get {
     ByNodeId(NodeId),
     ByAlias(String),
     ByDefault,
+    /// Default identity of the module. Falls back to the global default.
+    ByModule(Module),
 }
 
 impl RpcMessage for Get {
@@ -111,6 +119,58 @@ impl RpcMessage for Get {
     type Error = Error;
 }
 
+/// Modules, which can act on behalf of an identity other than the global default.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    Display,
+    EnumString,
+    EnumVariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Module {
+    Market,
+    Payment,
+    Activity,
+}
+
+/// Sets default identity of the module. `None` restores the global default.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetModuleDefault {
+    pub module: Module,
+    pub node_id: Option<NodeId>,
+}
+
+impl RpcMessage for SetModuleDefault {
+    const ID: &'static str = "SetModuleDefault";
+    type Item = Option<IdentityInfo>;
+    type Error = Error;
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ListModuleDefaults {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleDefault {
+    pub module: Module,
+    pub node_id: NodeId,
+}
+
+impl RpcMessage for ListModuleDefaults {
+    const ID: &'static str = "ListModuleDefaults";
+    type Item = Vec<ModuleDefault>;
+    type Error = Error;
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[non_exhaustive]
@@ -270,6 +330,109 @@ impl RpcMessage for GetKeyFile {
     type Error = Error;
 }
 
+/// Permission for `delegate` identity to act on behalf of `owner` identity
+/// in selected REST APIs (e.g. `market`) until `valid_to`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delegation {
+    pub owner: NodeId,
+    pub delegate: NodeId,
+    pub scopes: Vec<String>,
+    pub valid_to: DateTime<Utc>,
+}
+
+impl Delegation {
+    pub fn allows(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.valid_to < Utc::now()
+    }
+}
+
+/// Signs delegation with `owner` identity key.
+/// Returns token, which is passed by delegate in `X-Yagna-Delegation` header.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDelegation(pub Delegation);
+
+impl RpcMessage for CreateDelegation {
+    const ID: &'static str = "CreateDelegation";
+    type Item = String;
+    type Error = Error;
+}
+
+/// Checks, that token was signed by delegation owner and didn't expire.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyDelegation {
+    pub token: String,
+}
+
+impl RpcMessage for VerifyDelegation {
+    const ID: &'static str = "VerifyDelegation";
+    type Item = Delegation;
+    type Error = Error;
+}
+
+/// Operator information, which node publishes together with its Offers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeMetadata {
+    pub contact_email: Option<String>,
+    pub homepage: Option<String>,
+    pub sla_url: Option<String>,
+}
+
+/// Metadata signed by `node_id` identity.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedMetadata {
+    pub node_id: NodeId,
+    pub metadata: NodeMetadata,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Signs metadata with identity key and stores it, replacing the previous one.
+/// Returns signed document.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetMetadata {
+    pub node_id: NodeId,
+    pub metadata: NodeMetadata,
+}
+
+impl RpcMessage for SetMetadata {
+    const ID: &'static str = "SetMetadata";
+    type Item = String;
+    type Error = Error;
+}
+
+/// Signed metadata document of identity, if it was set.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetMetadata(pub NodeId);
+
+impl RpcMessage for GetMetadata {
+    const ID: &'static str = "GetMetadata";
+    type Item = Option<String>;
+    type Error = Error;
+}
+
+/// Checks, that metadata document was signed by identity it describes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyMetadata {
+    pub document: String,
+}
+
+impl RpcMessage for VerifyMetadata {
+    const ID: &'static str = "VerifyMetadata";
+    type Item = SignedMetadata;
+    type Error = Error;
+}
+
 pub mod event {
     use super::Error;
     use serde::{Deserialize, Serialize};
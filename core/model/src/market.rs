@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use ya_client_model::market::{agreement::State, Role};
 pub use ya_client_model::market::{Agreement, AgreementListEntry};
+use ya_client_model::NodeId;
 use ya_service_bus::RpcMessage;
 
 /// Public Market bus address.
@@ -50,6 +51,9 @@ pub struct ListAgreements {
     pub before_date: Option<DateTime<Utc>>,
     pub after_date: Option<DateTime<Utc>>,
     pub app_session_id: Option<String>,
+    /// Comma separated label conditions: `key` or `key=value`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 impl RpcMessage for ListAgreements {
@@ -58,6 +62,89 @@ impl RpcMessage for ListAgreements {
     type Error = RpcMessageError;
 }
 
+/// Source of propagated Offers, which are dropped by the matcher before they are stored.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "value")]
+pub enum OfferFilterRule {
+    /// Offers created by the node or received from it.
+    Identity(NodeId),
+    /// Offers with `golem.node.debug.subnet` property set to the value.
+    Subnet(String),
+}
+
+impl std::fmt::Display for OfferFilterRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OfferFilterRule::Identity(node_id) => write!(f, "identity {}", node_id),
+            OfferFilterRule::Subnet(subnet) => write!(f, "subnet {}", subnet),
+        }
+    }
+}
+
+/// Adds market filter rule. Bound on local Market bus address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddOfferFilter(pub OfferFilterRule);
+
+impl RpcMessage for AddOfferFilter {
+    const ID: &'static str = "AddOfferFilter";
+    type Item = ();
+    type Error = RpcMessageError;
+}
+
+/// Removes market filter rule. Bound on local Market bus address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoveOfferFilter(pub OfferFilterRule);
+
+impl RpcMessage for RemoveOfferFilter {
+    const ID: &'static str = "RemoveOfferFilter";
+    type Item = ();
+    type Error = RpcMessageError;
+}
+
+/// Lists market filter rules. Bound on local Market bus address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListOfferFilters {}
+
+impl RpcMessage for ListOfferFilters {
+    const ID: &'static str = "ListOfferFilters";
+    type Item = Vec<OfferFilterRule>;
+    type Error = RpcMessageError;
+}
+
+/// Returns negotiation statistics of the Offer or Demand. Bound on local Market bus address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSubscriptionStats {
+    pub subscription_id: String,
+}
+
+impl RpcMessage for GetSubscriptionStats {
+    const ID: &'static str = "GetSubscriptionStats";
+    type Item = SubscriptionStats;
+    type Error = RpcMessageError;
+}
+
+/// Statistics are computed from Proposals and Agreements stored in the market database,
+/// so negotiations removed by the cleaner aren't counted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionStats {
+    pub subscription_id: String,
+    /// Proposals issued by the other side.
+    pub proposals_received: u64,
+    /// Proposals issued by the subscription owner.
+    pub proposals_sent: u64,
+    /// Proposals rejected by any side.
+    pub proposals_rejected: u64,
+    pub negotiations: u64,
+    pub agreements: u64,
+    /// Average time in seconds from the Initial Proposal to creating the Agreement.
+    /// `None` if no Agreement was negotiated.
+    pub avg_negotiation_latency: Option<f64>,
+    /// Fraction of negotiations, which ended with an Agreement.
+    pub conversion_rate: f64,
+}
+
 /// Error message for market service bus API.
 #[derive(thiserror::Error, Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -207,6 +207,27 @@ pub mod local {
         pub metrics: StatusMetrics,
     }
 
+    #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Relays {}
+
+    impl RpcMessage for Relays {
+        const ID: &'static str = "Relays";
+        type Item = Vec<RelayResponse>;
+        type Error = StatusError;
+    }
+
+    /// Relay server candidate with the last measured round trip time.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RelayResponse {
+        pub host: String,
+        pub address: SocketAddr,
+        /// `None` if relay didn't respond to the last probe.
+        pub ping: Option<Duration>,
+        pub current: bool,
+    }
+
     #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
     #[serde(rename_all = "camelCase")]
     pub struct FindNode {
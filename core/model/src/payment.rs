@@ -151,6 +151,28 @@ pub mod local {
     pub struct RegisterDriver {
         pub driver_name: String,
         pub details: DriverDetails,
+        #[serde(default)]
+        pub capabilities: DriverCapabilities,
+    }
+
+    /// Optional features of the driver, so that clients can detect them
+    /// instead of relying on driver names.
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DriverCapabilities {
+        /// Payments are accumulated and sent in batched transactions.
+        pub batching: bool,
+        /// Allocations can be backed by deposit contracts.
+        pub deposits: bool,
+        /// Platforms, on which payments can be relayed without paying for gas. Relayed
+        /// payment is scheduled only if the Provider accepts it, see `SchedulePayment::gasless`.
+        #[serde(default)]
+        pub gasless_platforms: Vec<String>,
+        /// Driver reports status flags, see `PaymentDriverStatus`.
+        pub status_flags: bool,
+        /// Block confirmations required before a payment is final, by network.
+        #[serde(default)]
+        pub confirmations: HashMap<String, u64>,
     }
 
     #[derive(Clone, Debug, Serialize, Deserialize, thiserror::Error)]
@@ -242,6 +264,10 @@ pub mod local {
         pub recipient: String,
         pub order_ids: Vec<String>,
         pub confirmation: PaymentConfirmation,
+        /// Fee paid for the whole transaction in the network gas currency.
+        /// Transaction can carry payments of other orders too.
+        #[serde(default)]
+        pub gas_cost: Option<BigDecimal>,
     }
 
     impl RpcMessage for NotifyPayment {
@@ -277,6 +303,247 @@ pub mod local {
         pub gas: Option<GasDetails>,
         pub block_number: u64,
         pub block_datetime: DateTime<Utc>,
+        /// Token exchange rate, if fiat annotation is enabled.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub fiat: Option<FiatRate>,
+    }
+
+    /// Exchange rate of payment token to fiat currency at given time.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct FiatRate {
+        pub currency: String,
+        pub rate: BigDecimal,
+        pub timestamp: DateTime<Utc>,
+    }
+
+    /// Lists fiat values of invoices and payments stamped at the time they were
+    /// issued, received or sent. Used for accounting export.
+    #[derive(Clone, Debug, Serialize, Deserialize, Default)]
+    pub struct GetFiatAnnotations {
+        pub owner_id: Option<NodeId>,
+        pub since: Option<DateTime<Utc>>,
+    }
+
+    impl RpcMessage for GetFiatAnnotations {
+        const ID: &'static str = "GetFiatAnnotations";
+        type Item = Vec<FiatAnnotation>;
+        type Error = GenericError;
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct FiatAnnotation {
+        pub owner_id: NodeId,
+        /// `invoice` or `payment`.
+        pub entity_type: String,
+        pub entity_id: String,
+        pub platform: String,
+        pub amount: BigDecimal,
+        pub fiat_amount: BigDecimal,
+        #[serde(flatten)]
+        pub rate: FiatRate,
+    }
+
+    /// Lists amounts paid for Agreements, including their share of transaction fees.
+    /// Fee of the transaction paying many Agreements is split proportionally to amounts.
+    #[derive(Clone, Debug, Serialize, Deserialize, Default)]
+    pub struct GetAgreementCosts {
+        pub owner_id: Option<NodeId>,
+        /// Empty means all Agreements.
+        pub agreement_ids: Vec<String>,
+        pub since: Option<DateTime<Utc>>,
+    }
+
+    impl RpcMessage for GetAgreementCosts {
+        const ID: &'static str = "GetAgreementCosts";
+        type Item = Vec<AgreementCost>;
+        type Error = GenericError;
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AgreementCost {
+        pub owner_id: NodeId,
+        pub agreement_id: String,
+        pub platform: String,
+        /// Amount of tokens paid.
+        pub amount: BigDecimal,
+        /// Attributed transaction fees in the network gas currency.
+        pub gas_cost: BigDecimal,
+    }
+
+    /// Runs auto-withdrawal of earnings immediately, regardless of schedule.
+    /// With `dry_run` withdrawals are only estimated and not recorded.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct RunAutoWithdrawal {
+        pub dry_run: bool,
+    }
+
+    impl RpcMessage for RunAutoWithdrawal {
+        const ID: &'static str = "RunAutoWithdrawal";
+        type Item = Vec<Withdrawal>;
+        type Error = GenericError;
+    }
+
+    /// Lists recorded auto-withdrawals, the newest first.
+    #[derive(Clone, Debug, Serialize, Deserialize, Default)]
+    pub struct ListWithdrawals {
+        pub limit: Option<u32>,
+    }
+
+    impl RpcMessage for ListWithdrawals {
+        const ID: &'static str = "ListWithdrawals";
+        type Item = Vec<Withdrawal>;
+        type Error = GenericError;
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
+    #[serde(rename_all = "camelCase")]
+    #[strum(serialize_all = "camelCase")]
+    pub enum WithdrawalStatus {
+        Scheduled,
+        DryRun,
+        BelowThreshold,
+        GasFeeTooHigh,
+        InsufficientGas,
+        Failed,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Withdrawal {
+        pub sender: String,
+        pub to: String,
+        pub driver: String,
+        pub network: String,
+        pub token: String,
+        pub amount: BigDecimal,
+        /// Upper bound of the fee in the native currency of the network.
+        pub max_fee: BigDecimal,
+        pub status: WithdrawalStatus,
+        pub tx_id: Option<String>,
+        pub error: Option<String>,
+        pub timestamp: DateTime<Utc>,
+    }
+
+    /// Lists marketplace fees charged on invoice payments, the newest first.
+    #[derive(Clone, Debug, Serialize, Deserialize, Default)]
+    pub struct ListFees {
+        pub limit: Option<u32>,
+    }
+
+    impl RpcMessage for ListFees {
+        const ID: &'static str = "ListFees";
+        type Item = Vec<Fee>;
+        type Error = GenericError;
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
+    #[serde(rename_all = "camelCase")]
+    #[strum(serialize_all = "camelCase")]
+    pub enum FeeStatus {
+        /// Waiting for confirmation of the invoice payment.
+        Pending,
+        /// Invoice is paid, fee payment is to be scheduled. Retried until it succeeds.
+        Due,
+        Scheduled,
+        Paid,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Fee {
+        pub owner_id: NodeId,
+        pub invoice_id: String,
+        pub agreement_id: String,
+        pub payment_platform: String,
+        pub payer_addr: String,
+        pub recipient: String,
+        pub amount: BigDecimal,
+        pub status: FeeStatus,
+        pub tx_id: Option<String>,
+        pub error: Option<String>,
+        pub timestamp: DateTime<Utc>,
+    }
+
+    /// Rebuilds payment state of the node, which lost its database, from summaries
+    /// requested from recent counterparties with [`public::PaymentSyncSummaryRequest`].
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct ColdSync {
+        pub node_id: NodeId,
+        pub peers: Vec<NodeId>,
+        pub since: DateTime<Utc>,
+    }
+
+    impl RpcMessage for ColdSync {
+        const ID: &'static str = "ColdSync";
+        type Item = ColdSyncResult;
+        type Error = GenericError;
+    }
+
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ColdSyncResult {
+        pub synced_peers: Vec<NodeId>,
+        pub failed_peers: Vec<NodeId>,
+        pub agreements: usize,
+        pub invoices: usize,
+    }
+
+    /// Lists funds reserved by scheduled payments, which are not confirmed yet.
+    #[derive(Clone, Debug, Serialize, Deserialize, Default)]
+    pub struct GetReservations {
+        pub platform: Option<String>,
+        pub address: Option<String>,
+    }
+
+    impl RpcMessage for GetReservations {
+        const ID: &'static str = "GetReservations";
+        type Item = Vec<Reservation>;
+        type Error = GenericError;
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Reservation {
+        /// Payment order id, `None` until the order is accepted by the driver.
+        pub order_id: Option<String>,
+        pub platform: String,
+        pub payer_addr: String,
+        pub payee_addr: String,
+        pub allocation_id: String,
+        pub amount: BigDecimal,
+        pub timestamp: DateTime<Utc>,
+    }
+
+    /// Reports Provider's tolerance of underpaid invoices and discrepancies of invoices
+    /// settled within it since the service start.
+    #[derive(Clone, Debug, Serialize, Deserialize, Default)]
+    pub struct GetSettlementStats {}
+
+    impl RpcMessage for GetSettlementStats {
+        const ID: &'static str = "GetSettlementStats";
+        type Item = SettlementStats;
+        type Error = GenericError;
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SettlementStats {
+        /// Maximum amount missing from invoice, which is still settled automatically.
+        pub tolerance: BigDecimal,
+        pub platforms: Vec<PlatformDiscrepancy>,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PlatformDiscrepancy {
+        pub platform: String,
+        /// Number of invoices settled within tolerance.
+        pub settled: u64,
+        /// Total amount missing from these invoices.
+        pub discrepancy: BigDecimal,
     }
 
     #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -362,18 +629,46 @@ pub mod local {
         }
     }
 
+    /// Lists payment accounts registered by drivers. When `owner` is set,
+    /// only accounts belonging to that identity are returned.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    pub struct GetAccounts {
+        #[serde(default)]
+        pub owner: Option<NodeId>,
+    }
+
+    impl GetAccounts {
+        pub fn all() -> Self {
+            Self::default()
+        }
+
+        pub fn owned_by(owner: NodeId) -> Self {
+            Self { owner: Some(owner) }
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, Deserialize)]
-    pub struct GetAccounts {}
+    #[serde(rename_all = "camelCase")]
+    pub struct OwnedAccount {
+        #[serde(flatten)]
+        pub account: Account,
+        /// Local identity holding the account keys, `None` for foreign addresses
+        pub owner: Option<NodeId>,
+        pub alias: Option<String>,
+        pub is_default: bool,
+    }
 
     #[derive(Clone, Debug, Serialize, Deserialize, thiserror::Error)]
     pub enum GetAccountsError {
         #[error("Internal timeout")]
         InternalTimeout,
+        #[error("Unable to list identities: {0}")]
+        Identity(String),
     }
 
     impl RpcMessage for GetAccounts {
         const ID: &'static str = "GetAccounts";
-        type Item = Vec<Account>;
+        type Item = Vec<OwnedAccount>;
         type Error = GetAccountsError;
     }
 
@@ -384,6 +679,9 @@ pub mod local {
         pub requestor: bool,
         pub provider: bool,
         pub since: DateTime<Utc>,
+        /// When set, stats are additionally split into buckets of this length.
+        #[serde(default)]
+        pub interval: Option<StatsInterval>,
     }
 
     impl GetInvoiceStats {
@@ -393,8 +691,14 @@ pub mod local {
                 requestor: true,
                 provider: true,
                 since,
+                interval: None,
             }
         }
+
+        pub fn with_interval(mut self, interval: StatsInterval) -> Self {
+            self.interval = Some(interval);
+            self
+        }
     }
 
     impl RpcMessage for GetInvoiceStats {
@@ -419,6 +723,61 @@ pub mod local {
     pub struct InvoiceStats {
         pub requestor: InvoiceStatusNotes,
         pub provider: InvoiceStatusNotes,
+        /// Stats split into buckets, oldest first. Empty unless `interval` was requested.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub series: Vec<InvoiceStatsBucket>,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Default)]
+    pub struct InvoiceStatsBucket {
+        /// Start of the bucket, inclusive.
+        pub start: DateTime<Utc>,
+        pub requestor: InvoiceStatusNotes,
+        pub provider: InvoiceStatusNotes,
+    }
+
+    #[derive(
+        EnumString,
+        EnumVariantNames,
+        IntoStaticStr,
+        Display,
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        Serialize,
+        Deserialize,
+    )]
+    #[strum(serialize_all = "lowercase")]
+    #[serde(rename_all = "lowercase")]
+    pub enum StatsInterval {
+        Daily,
+        Weekly,
+    }
+
+    impl StatsInterval {
+        /// Start of the bucket containing `timestamp`. Days start at midnight UTC,
+        /// weeks on Monday.
+        pub fn bucket_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+            use chrono::{Datelike, TimeZone};
+
+            let date = timestamp.date_naive();
+            let date = match self {
+                StatsInterval::Daily => date,
+                StatsInterval::Weekly => {
+                    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+                }
+            };
+            Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        pub fn duration(&self) -> chrono::Duration {
+            match self {
+                StatsInterval::Daily => chrono::Duration::days(1),
+                StatsInterval::Weekly => chrono::Duration::weeks(1),
+            }
+        }
     }
 
     #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -483,6 +842,16 @@ pub mod local {
         type Error = GetDriversError;
     }
 
+    /// Capabilities of registered drivers by driver name.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct GetDriversCapabilities {}
+
+    impl RpcMessage for GetDriversCapabilities {
+        const ID: &'static str = "GetDriversCapabilities";
+        type Item = HashMap<String, DriverCapabilities>;
+        type Error = GetDriversError;
+    }
+
     // ********************* STATUS ********************************
     #[derive(Clone, Debug, Serialize, Deserialize, thiserror::Error)]
     pub enum PaymentDriverStatusError {
@@ -665,6 +1034,7 @@ pub mod local {
 pub mod public {
     use super::*;
     use crate::signable::Signable;
+    use chrono::{DateTime, Utc};
     use ya_client_model::NodeId;
 
     pub const BUS_ID: &str = "/public/payment";
@@ -840,17 +1210,27 @@ pub mod public {
         #[serde(flatten)]
         pub payment: Payment,
         pub signature: Vec<u8>,
+        /// Allows the receiver to recognize redelivered messages. Older nodes don't send it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub idempotency_key: Option<String>,
     }
 
     impl SendPayment {
         pub fn new(payment: Payment, signature: Vec<u8>) -> Self {
             Self {
+                idempotency_key: Some(idempotency_key(&payment)),
                 payment: payment.remove_private_info(),
                 signature,
             }
         }
     }
 
+    /// Key identifying delivery of a single payment. It has to be stable across retries
+    /// and payment sync, so it is derived from the payment rather than generated randomly.
+    pub fn idempotency_key(payment: &Payment) -> String {
+        format!("{}:{}", payment.payer_id, payment.payment_id)
+    }
+
     impl RpcMessage for SendPayment {
         const ID: &'static str = "SendPayment";
         type Item = Ack;
@@ -865,6 +1245,9 @@ pub mod public {
         pub signature: Vec<u8>,
         #[serde(with = "serde_bytes")]
         pub signed_bytes: Vec<u8>,
+        /// Allows the receiver to recognize redelivered messages. Older nodes don't send it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub idempotency_key: Option<String>,
     }
 
     impl SendSignedPayment {
@@ -872,6 +1255,7 @@ pub mod public {
             // Unwrap won't happen, because serialization is always possible.
             let signed_bytes = payment.canonicalize().unwrap_or_default();
             Self {
+                idempotency_key: Some(idempotency_key(&payment)),
                 payment: payment.remove_private_info(),
                 signature,
                 signed_bytes,
@@ -885,10 +1269,58 @@ pub mod public {
         type Error = SendError;
     }
 
+    // *************************** RECEIPT ****************************
+
+    /// Proof of payment issued by the payer. Signature covers canonical representation
+    /// of the whole receipt, so it can be verified by third parties knowing only payer ID.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PaymentReceipt {
+        pub payment: Payment,
+        /// Hash of the transaction realizing the payment.
+        pub tx_hash: String,
+        pub issued_at: DateTime<Utc>,
+    }
+
+    impl Signable for PaymentReceipt {
+        fn remove_private_info(mut self) -> Self {
+            self.payment = self.payment.remove_private_info();
+            self
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SendPaymentReceipt {
+        pub receipt: PaymentReceipt,
+        #[serde(with = "serde_bytes")]
+        pub signature: Vec<u8>,
+        #[serde(with = "serde_bytes")]
+        pub signed_bytes: Vec<u8>,
+    }
+
+    impl SendPaymentReceipt {
+        pub fn new(receipt: PaymentReceipt, signature: Vec<u8>) -> Self {
+            // Unwrap won't happen, because serialization is always possible.
+            let signed_bytes = receipt.canonicalize().unwrap_or_default();
+            Self {
+                receipt: receipt.remove_private_info(),
+                signature,
+                signed_bytes,
+            }
+        }
+    }
+
+    impl RpcMessage for SendPaymentReceipt {
+        const ID: &'static str = "SendPaymentReceipt";
+        type Item = Ack;
+        type Error = SendError;
+    }
+
     // **************************** SYNC *****************************
 
     /// Push unsynchronized state
-    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
     pub struct PaymentSync {
         /// Payment confirmations.
         pub payments: Vec<SendPayment>,
@@ -902,7 +1334,7 @@ pub mod public {
         pub debit_note_accepts: Vec<AcceptDebitNote>,
     }
 
-    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
     pub struct PaymentSyncWithBytes {
         /// Payment confirmations.
         pub payments: Vec<SendSignedPayment>,
@@ -962,4 +1394,34 @@ pub mod public {
         type Item = Ack;
         type Error = SendError;
     }
+
+    /// Requests state of payments shared with the sender. Sent by a node, which lost
+    /// its database (e.g. reinstalled with the same identity), to recent counterparties,
+    /// so it can continue settling outstanding invoices.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct PaymentSyncSummaryRequest {
+        /// Only documents issued after this time are included.
+        pub since: DateTime<Utc>,
+    }
+
+    impl RpcMessage for PaymentSyncSummaryRequest {
+        const ID: &'static str = "PaymentSyncSummary";
+        type Item = PaymentSyncSummary;
+        type Error = SendError;
+    }
+
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    pub struct PaymentSyncSummary {
+        /// Agreements, which invoices below were issued for.
+        pub agreements: Vec<AgreementPaymentSummary>,
+        /// Invoices, which are neither settled nor cancelled.
+        pub invoices: Vec<Invoice>,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct AgreementPaymentSummary {
+        pub agreement: ya_client_model::market::Agreement,
+        pub total_amount_accepted: bigdecimal::BigDecimal,
+        pub total_amount_paid: bigdecimal::BigDecimal,
+    }
 }
@@ -0,0 +1,2 @@
+DROP INDEX net_spool_remote_id_idx;
+DROP TABLE net_spool;
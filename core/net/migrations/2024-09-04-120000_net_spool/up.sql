@@ -0,0 +1,11 @@
+CREATE TABLE net_spool(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    caller_id VARCHAR(50) NOT NULL,
+    remote_id VARCHAR(50) NOT NULL,
+    address VARCHAR(255) NOT NULL,
+    msg BLOB NOT NULL,
+    created_ts DATETIME NOT NULL,
+    expires_ts DATETIME NOT NULL
+);
+
+CREATE INDEX net_spool_remote_id_idx ON net_spool(remote_id);
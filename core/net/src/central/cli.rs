@@ -18,6 +18,10 @@ pub(crate) fn bind_service() {
         futures::future::err(err.clone())
     });
     let err = error.clone();
+    let _ = bus::bind(model::BUS_ID, move |_: model::Relays| {
+        futures::future::err(err.clone())
+    });
+    let err = error.clone();
     let _ = bus::bind(model::BUS_ID, move |_: model::GsbPing| {
         futures::future::err(err.clone())
     });
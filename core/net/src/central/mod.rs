@@ -8,6 +8,8 @@ pub use api::*;
 pub use rest_api::web_scope;
 pub use service::{bind_remote, Net};
 
+pub(crate) use service::HubAddr;
+
 use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
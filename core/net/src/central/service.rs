@@ -25,6 +25,9 @@ use crate::config::Config;
 
 const CENTRAL_ADDR_ENV_VAR: &str = "CENTRAL_NET_HOST";
 
+/// Address of the hub and hash of its certificate, if TLS is used.
+pub(crate) type HubAddr = (SocketAddr, Option<ya_service_bus::connection::CertHash>);
+
 fn parse_host_link(host_link: &str) -> Option<(&str, u16, Option<&str>)> {
     let re = regex::Regex::new(r"^(([0-9a-z]{56})@)?([^:]*)(:[0-9]{1,4})?$").unwrap();
 
@@ -41,8 +44,7 @@ fn parse_host_link(host_link: &str) -> Option<(&str, u16, Option<&str>)> {
     }
 }
 
-async fn central_net_addr(
-) -> std::io::Result<(SocketAddr, Option<ya_service_bus::connection::CertHash>)> {
+async fn central_net_addr() -> std::io::Result<HubAddr> {
     let urls = match std::env::var(CENTRAL_ADDR_ENV_VAR) {
         Ok(v) => vec![v],
         Err(_) => resolver::resolve_href_record("_net._tcp.dev.golem.network").await?,
@@ -67,7 +69,17 @@ pub async fn bind_remote(
     default_node_id: NodeId,
     nodes: Vec<NodeId>,
 ) -> std::io::Result<oneshot::Receiver<()>> {
-    let (hub_addr, cert) = central_net_addr().await?;
+    let hub = central_net_addr().await?;
+    bind_remote_at(client_info, default_node_id, nodes, hub).await
+}
+
+/// Initialize net module on the hub with given address.
+pub(crate) async fn bind_remote_at(
+    client_info: ClientInfo,
+    default_node_id: NodeId,
+    nodes: Vec<NodeId>,
+    (hub_addr, cert): HubAddr,
+) -> std::io::Result<oneshot::Receiver<()>> {
     log::info!("connecting to {:?}, cert={:?}", hub_addr, cert);
     if let Some(cert) = cert {
         bind_remote_for(
@@ -508,6 +520,16 @@ pub struct Net;
 
 impl Net {
     pub async fn gsb<Context>(_: Context, _config: Config) -> anyhow::Result<()> {
+        Self::gsb_with_hub(central_net_addr).await
+    }
+
+    /// Connects to the hub returned by `hub`. Hub address is resolved again
+    /// on each reconnection.
+    pub(crate) async fn gsb_with_hub<H, F>(hub: H) -> anyhow::Result<()>
+    where
+        H: Fn() -> F + 'static,
+        F: Future<Output = std::io::Result<HubAddr>> + 'static,
+    {
         let (default_id, ids) = crate::service::identities().await?;
         log::info!(
             "CENTRAL_NET - Using default identity as network id: {:?}",
@@ -516,12 +538,16 @@ impl Net {
 
         let client_info = ClientInfo::new("sb-client-net");
         let ids_clone = ids.clone();
+        let hub = Rc::new(hub);
 
         let bind = move || {
             let client_info = client_info.clone();
             let ids = ids.clone();
+            let hub = hub.clone();
             async move {
-                let rx = bind_remote(client_info.clone(), default_id, ids.clone()).await?;
+                let hub_addr = hub().await?;
+                let rx =
+                    bind_remote_at(client_info.clone(), default_id, ids.clone(), hub_addr).await?;
                 resubscribe().await;
                 Ok(rx)
             }
@@ -23,6 +23,8 @@ pub enum NetCommand {
     Sessions {},
     /// List virtual sockets
     Sockets {},
+    /// List relay server candidates and the one currently used
+    Relays {},
     /// Find node
     Find {
         /// Node information to query for
@@ -48,7 +50,7 @@ pub enum NetCommand {
 
 impl NetCommand {
     pub async fn run_command(self, ctx: &CliCtx) -> anyhow::Result<CommandOutput> {
-        let is_json = ctx.json_output;
+        let is_json = ctx.structured_output();
 
         match self {
             NetCommand::Status {} => {
@@ -148,6 +150,34 @@ impl NetCommand {
                 }
                 .into())
             }
+            NetCommand::Relays {} => {
+                let relays: Vec<model::RelayResponse> = bus::service(model::BUS_ID)
+                    .send(model::Relays {})
+                    .await
+                    .map_err(anyhow::Error::msg)??;
+
+                Ok(ResponseTable {
+                    columns: vec![
+                        "host".into(),
+                        "address".into(),
+                        "ping".into(),
+                        "current".into(),
+                    ],
+                    values: relays
+                        .into_iter()
+                        .map(|r| {
+                            let ping = r.ping.map(|p| p.as_secs_f64() * 1000.0);
+                            serde_json::json! {[
+                                r.host,
+                                r.address.to_string(),
+                                to_ms(ping, is_json),
+                                r.current,
+                            ]}
+                        })
+                        .collect(),
+                }
+                .into())
+            }
             NetCommand::Find { node_id } => {
                 let node: model::FindNodeResponse = bus::service(model::BUS_ID)
                     .send(model::FindNode { node_id })
@@ -27,6 +27,32 @@ pub enum NetType {
     Central,
     #[cfg_attr(not(feature = "central-net"), default)]
     Hybrid,
+    /// Nodes discover each other with mDNS in the local network. Doesn't require
+    /// relay server nor internet connectivity.
+    Lan,
+}
+
+/// Role of the node in LAN network.
+#[derive(EnumString, EnumVariantNames, IntoStaticStr, Copy, Clone, Eq, PartialEq, Debug)]
+#[strum(serialize_all = "kebab-case")]
+pub enum LanRole {
+    /// Connect to the hub discovered in the network or become the hub, if none was found.
+    Auto,
+    /// Always host the hub.
+    Hub,
+    /// Only connect to the hub discovered in the network.
+    Client,
+}
+
+/// Transport used to reach the relay server.
+#[derive(EnumString, EnumVariantNames, IntoStaticStr, Copy, Clone, Eq, PartialEq, Debug)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RelayTransport {
+    /// Native UDP protocol. WebSocket is used, only if relay server isn't reachable over UDP.
+    Auto,
+    Udp,
+    /// Relay protocol tunnelled over WebSocket, for networks which block UDP traffic.
+    Websocket,
 }
 
 #[derive(StructOpt, Clone)]
@@ -34,6 +60,8 @@ pub enum NetType {
 pub struct Config {
     #[structopt(env = "YA_NET_TYPE", possible_values = NetType::VARIANTS, default_value = NetType::default().into())]
     pub net_type: NetType,
+    /// Comma separated relay servers (host:port). The one with the lowest latency is used.
+    /// If unspecified, relays are taken from `_net_relay._udp` SRV records.
     #[structopt(env = "YA_NET_RELAY_HOST")]
     pub host: Option<String>,
     #[structopt(env = "YA_NET_BIND_URL", default_value = "udp://0.0.0.0:11500")]
@@ -46,6 +74,51 @@ pub struct Config {
     pub session_expiration: Duration,
     #[structopt(env = "YA_NET_SESSION_REQUEST_TIMEOUT", parse(try_from_str = humantime::parse_duration), default_value = "3s")]
     pub session_request_timeout: Duration,
+    #[structopt(env = "YA_NET_RELAY_PROBE_TIMEOUT", parse(try_from_str = humantime::parse_duration), default_value = "5s")]
+    pub relay_probe_timeout: Duration,
+    #[structopt(env = "YA_NET_RELAY_CHECK_INTERVAL", parse(try_from_str = humantime::parse_duration), default_value = "60s")]
+    pub relay_check_interval: Duration,
+    /// Relay is considered degraded, when its ping exceeds this value.
+    #[structopt(env = "YA_NET_RELAY_MAX_PING", parse(try_from_str = humantime::parse_duration), default_value = "1s")]
+    pub relay_max_ping: Duration,
+    #[structopt(env = "YA_NET_RELAY_TRANSPORT", possible_values = RelayTransport::VARIANTS, default_value = "auto")]
+    pub relay_transport: RelayTransport,
+    /// WebSocket endpoint of the relay server. Defaults to `wss://<relay host>:443/relay`.
+    /// When set, all relay servers are reached through this endpoint.
+    #[structopt(env = "YA_NET_RELAY_WS_URL")]
+    pub relay_ws_url: Option<Url>,
+    /// Interval of WebSocket pings, which keep the connection open through proxies.
+    #[structopt(env = "YA_NET_RELAY_WS_PING_INTERVAL", parse(try_from_str = humantime::parse_duration), default_value = "20s")]
+    pub relay_ws_ping_interval: Duration,
+    /// Comma separated GSB methods (`service/MessageId`) spooled while the remote peer
+    /// is unreachable and delivered when it reconnects. Handlers must be idempotent.
+    #[structopt(
+        env = "YA_NET_SPOOL_METHODS",
+        default_value = "payment/AcceptInvoice,payment/AcceptDebitNote,payment/PaymentSync,payment/PaymentSyncWithBytes"
+    )]
+    pub spool_methods: String,
+    /// Comma separated idempotent read RPCs (`MessageId=delay`), which are sent again
+    /// to the same peer, if reply doesn't arrive within the delay. First reply is used.
+    #[structopt(env = "YA_NET_HEDGED_RPCS", default_value = "")]
+    pub hedged_rpcs: String,
+    /// Spooled messages are dropped after this time. Zero disables spooling.
+    #[structopt(env = "YA_NET_SPOOL_TTL", parse(try_from_str = humantime::parse_duration), default_value = "1d")]
+    pub spool_ttl: Duration,
+    #[structopt(env = "YA_NET_SPOOL_RETRY_INTERVAL", parse(try_from_str = humantime::parse_duration), default_value = "1min")]
+    pub spool_retry_interval: Duration,
+    #[structopt(env = "YA_NET_SPOOL_MAX_PER_PEER", default_value = "1000")]
+    pub spool_max_per_peer: usize,
+    /// Interval of exporting session counts, RTT and traffic to metrics. Zero disables export.
+    #[structopt(env = "YA_NET_SESSION_METRICS_INTERVAL", parse(try_from_str = humantime::parse_duration), default_value = "30s")]
+    pub session_metrics_interval: Duration,
+    #[structopt(env = "YA_NET_LAN_ROLE", possible_values = LanRole::VARIANTS, default_value = "auto")]
+    pub lan_role: LanRole,
+    /// Address on which the LAN hub accepts connections from other nodes.
+    #[structopt(env = "YA_NET_LAN_HUB_URL", default_value = "tcp://0.0.0.0:7465")]
+    pub lan_hub_url: Url,
+    /// How long mDNS responses are awaited, before the node becomes the hub.
+    #[structopt(env = "YA_NET_LAN_DISCOVERY_TIMEOUT", parse(try_from_str = humantime::parse_duration), default_value = "3s")]
+    pub lan_discovery_timeout: Duration,
 }
 
 impl Config {
@@ -0,0 +1,168 @@
+use chrono::Utc;
+use diesel::prelude::*;
+
+use ya_client_model::NodeId;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+use crate::db::model::{NewSpooledMessage, SpooledMessage};
+use crate::db::schema::net_spool::dsl;
+
+pub struct SpoolDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'a> AsDao<'a> for SpoolDao<'a> {
+    fn as_dao(pool: &'a PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> SpoolDao<'c> {
+    /// Spools message, unless the same one is already spooled. The oldest messages
+    /// to the same peer are dropped above `max_per_peer`.
+    pub async fn push(
+        &self,
+        message: NewSpooledMessage,
+        max_per_peer: usize,
+    ) -> anyhow::Result<bool> {
+        do_with_transaction(self.pool, "spool_dao_push", move |conn| {
+            let remote_id = message.remote_id;
+            let duplicates: i64 = dsl::net_spool
+                .filter(dsl::remote_id.eq(remote_id))
+                .filter(dsl::caller_id.eq(message.caller_id))
+                .filter(dsl::address.eq(&message.address))
+                .filter(dsl::msg.eq(&message.msg))
+                .count()
+                .get_result(conn)?;
+            if duplicates > 0 {
+                return Ok(false);
+            }
+
+            diesel::insert_into(dsl::net_spool)
+                .values(&message)
+                .execute(conn)?;
+
+            let overflow: Vec<i32> = dsl::net_spool
+                .select(dsl::id)
+                .filter(dsl::remote_id.eq(remote_id))
+                .order(dsl::id.desc())
+                // SQLite doesn't accept OFFSET without LIMIT.
+                .limit(-1)
+                .offset(max_per_peer as i64)
+                .load(conn)?;
+            if !overflow.is_empty() {
+                log::warn!(
+                    "Spool for [{remote_id}] is full, dropping {} oldest message(s)",
+                    overflow.len()
+                );
+                diesel::delete(dsl::net_spool.filter(dsl::id.eq_any(overflow))).execute(conn)?;
+            }
+            Ok(true)
+        })
+        .await
+    }
+
+    /// Drops spooled copies of a message, which was delivered directly.
+    pub async fn discard(
+        &self,
+        caller_id: NodeId,
+        remote_id: NodeId,
+        address: String,
+        msg: Vec<u8>,
+    ) -> anyhow::Result<usize> {
+        do_with_transaction(self.pool, "spool_dao_discard", move |conn| {
+            Ok(diesel::delete(
+                dsl::net_spool
+                    .filter(dsl::remote_id.eq(remote_id))
+                    .filter(dsl::caller_id.eq(caller_id))
+                    .filter(dsl::address.eq(address))
+                    .filter(dsl::msg.eq(msg)),
+            )
+            .execute(conn)?)
+        })
+        .await
+    }
+
+    /// Not expired messages to `remote_id`, in the order they were spooled.
+    pub async fn list(&self, remote_id: NodeId) -> anyhow::Result<Vec<SpooledMessage>> {
+        readonly_transaction(self.pool, "spool_dao_list", move |conn| {
+            Ok(dsl::net_spool
+                .filter(dsl::remote_id.eq(remote_id))
+                .filter(dsl::expires_ts.gt(Utc::now().naive_utc()))
+                .order(dsl::id.asc())
+                .load(conn)?)
+        })
+        .await
+    }
+
+    /// Peers having not expired messages spooled.
+    pub async fn peers(&self) -> anyhow::Result<Vec<NodeId>> {
+        readonly_transaction(self.pool, "spool_dao_peers", move |conn| {
+            Ok(dsl::net_spool
+                .select(dsl::remote_id)
+                .filter(dsl::expires_ts.gt(Utc::now().naive_utc()))
+                .distinct()
+                .load(conn)?)
+        })
+        .await
+    }
+
+    pub async fn remove(&self, id: i32) -> anyhow::Result<()> {
+        do_with_transaction(self.pool, "spool_dao_remove", move |conn| {
+            diesel::delete(dsl::net_spool.find(id)).execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn clean_expired(&self) -> anyhow::Result<usize> {
+        do_with_transaction(self.pool, "spool_dao_clean_expired", move |conn| {
+            Ok(
+                diesel::delete(dsl::net_spool.filter(dsl::expires_ts.le(Utc::now().naive_utc())))
+                    .execute(conn)?,
+            )
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use ya_persistence::executor::DbExecutor;
+
+    fn message(msg: &[u8]) -> NewSpooledMessage {
+        NewSpooledMessage {
+            caller_id: NodeId::from([1; 20].as_ref()),
+            remote_id: NodeId::from([2; 20].as_ref()),
+            address: "/public/payment/AcceptInvoice".to_string(),
+            msg: msg.to_vec(),
+            created_ts: Utc::now().naive_utc(),
+            expires_ts: NaiveDateTime::MAX,
+        }
+    }
+
+    #[tokio::test]
+    async fn spooled_once_and_discarded_after_retry() {
+        let db = DbExecutor::in_memory("net_spool_dedup").unwrap();
+        db.apply_migration(crate::db::migrations::run_with_output)
+            .unwrap();
+        let dao = db.as_dao::<SpoolDao>();
+
+        assert!(dao.push(message(b"first"), 10).await.unwrap());
+        assert!(!dao.push(message(b"first"), 10).await.unwrap());
+        assert!(dao.push(message(b"second"), 10).await.unwrap());
+
+        let first = message(b"first");
+        let discarded = dao
+            .discard(first.caller_id, first.remote_id, first.address, first.msg)
+            .await
+            .unwrap();
+        assert_eq!(discarded, 1);
+
+        let remaining = dao.list(NodeId::from([2; 20].as_ref())).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].msg, b"second".to_vec());
+    }
+}
@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+
+use ya_client_model::NodeId;
+
+use crate::db::schema::net_spool;
+
+#[derive(Clone, Debug, Insertable)]
+#[table_name = "net_spool"]
+pub struct NewSpooledMessage {
+    pub caller_id: NodeId,
+    pub remote_id: NodeId,
+    pub address: String,
+    pub msg: Vec<u8>,
+    pub created_ts: NaiveDateTime,
+    pub expires_ts: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Queryable)]
+pub struct SpooledMessage {
+    pub id: i32,
+    pub caller_id: NodeId,
+    pub remote_id: NodeId,
+    pub address: String,
+    pub msg: Vec<u8>,
+    pub created_ts: NaiveDateTime,
+    pub expires_ts: NaiveDateTime,
+}
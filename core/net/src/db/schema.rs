@@ -0,0 +1,11 @@
+table! {
+    net_spool (id) {
+        id -> Integer,
+        caller_id -> Text,
+        remote_id -> Text,
+        address -> Text,
+        msg -> Binary,
+        created_ts -> Timestamp,
+        expires_ts -> Timestamp,
+    }
+}
@@ -0,0 +1,98 @@
+//! Hedged requests for idempotent read RPCs.
+//!
+//! If the reply doesn't arrive within the delay configured for the message type, the same
+//! request is sent once more to the same peer and the first reply wins. This cuts tail
+//! latency caused by relay hiccups. Only messages, which can be safely handled twice,
+//! should be hedged. Hedging is configured with `YA_NET_HEDGED_RPCS`.
+use futures::future::{select, Either};
+use futures::pin_mut;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use ya_service_bus::{Error as BusError, RpcEndpoint, RpcMessage};
+
+lazy_static::lazy_static! {
+    static ref HEDGE_DELAYS: RwLock<HashMap<String, Duration>> = Default::default();
+}
+
+/// Parses comma separated `MessageId=delay` pairs, e.g. `RetrieveOffers=500ms`.
+pub(crate) fn configure(hedged_rpcs: &str) -> anyhow::Result<()> {
+    let delays = parse(hedged_rpcs)?;
+    if !delays.is_empty() {
+        log::info!("Hedging RPCs: {:?}", delays);
+    }
+    *HEDGE_DELAYS.write().unwrap() = delays;
+    Ok(())
+}
+
+fn parse(hedged_rpcs: &str) -> anyhow::Result<HashMap<String, Duration>> {
+    hedged_rpcs
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (msg_id, delay) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected `MessageId=delay`, got `{}`", entry))?;
+            let delay = humantime::parse_duration(delay.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid hedging delay of {}: {}", msg_id, e))?;
+            Ok((msg_id.trim().to_string(), delay))
+        })
+        .collect()
+}
+
+/// Delay after which request of given type is hedged. `None` if hedging is disabled.
+pub fn hedge_delay(msg_id: &str) -> Option<Duration> {
+    HEDGE_DELAYS.read().unwrap().get(msg_id).copied()
+}
+
+/// Sends `msg` to `endpoint`, hedging it when configured for the message type.
+/// Behaves like plain `send` otherwise.
+pub async fn send_hedged<M, E>(endpoint: &E, msg: M) -> Result<Result<M::Item, M::Error>, BusError>
+where
+    M: RpcMessage + Clone,
+    E: RpcEndpoint<M>,
+{
+    let delay = match hedge_delay(M::ID) {
+        Some(delay) => delay,
+        None => return endpoint.send(msg).await,
+    };
+
+    let first = endpoint.send(msg.clone());
+    let timer = tokio::time::sleep(delay);
+    pin_mut!(first, timer);
+    let first = match select(first, timer).await {
+        Either::Left((result, _)) => return result,
+        Either::Right((_, first)) => first,
+    };
+
+    log::debug!("No reply to {} within {:?}. Hedging request.", M::ID, delay);
+    metrics::counter!("net.hedged-requests", 1);
+
+    let second = endpoint.send(msg);
+    pin_mut!(second);
+    // Transport error of one request doesn't matter, as long as the other one succeeds.
+    match select(first, second).await {
+        Either::Left((Ok(reply), _)) | Either::Right((Ok(reply), _)) => Ok(reply),
+        Either::Left((Err(e), other)) | Either::Right((Err(e), other)) => {
+            log::debug!("Hedged request {} failed: {}", M::ID, e);
+            other.await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let delays = parse(" RetrieveOffers=500ms, DriverStatus=2s,").unwrap();
+        assert_eq!(delays["RetrieveOffers"], Duration::from_millis(500));
+        assert_eq!(delays["DriverStatus"], Duration::from_secs(2));
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("RetrieveOffers").is_err());
+        assert!(parse("RetrieveOffers=soon").is_err());
+    }
+}
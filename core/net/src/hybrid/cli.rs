@@ -1,6 +1,7 @@
 use anyhow::anyhow;
 use futures::future::join_all;
 use futures::TryFutureExt;
+use metrics::gauge;
 use std::convert::TryFrom;
 use std::net::{IpAddr, SocketAddr};
 use std::time::{Duration, Instant};
@@ -18,6 +19,8 @@ use ya_service_bus::timeout::IntoTimeoutFuture;
 use ya_service_bus::typed::ServiceBinder;
 use ya_service_bus::{typed as bus, RpcEndpoint};
 
+use crate::hybrid::relay::RELAYS;
+
 pub(crate) fn bind_service(base_client: Client) {
     let client = base_client.clone();
     let _ = bus::bind(model::BUS_ID, move |ping: model::GsbPing| {
@@ -125,6 +128,10 @@ pub(crate) fn bind_service(base_client: Client) {
         .map_err(status_err)
     });
 
+    let _ = bus::bind(model::BUS_ID, move |_: model::Relays| async move {
+        Ok(RELAYS.read().await.to_response())
+    });
+
     let find_node_client = base_client.clone();
     let _ = bus::bind(model::BUS_ID, move |find: model::FindNode| {
         let client = find_node_client.clone();
@@ -161,6 +168,40 @@ pub(crate) fn bind_service(base_client: Client) {
     });
 }
 
+/// Periodically exports aggregated session statistics to metrics. Sessions are
+/// queried through local bus, so statistics come from the client currently in use,
+/// also after migrating to another relay.
+pub(crate) async fn report_session_metrics(interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+
+        let sessions = match bus::service(model::BUS_ID).send(model::Sessions {}).await {
+            Ok(Ok(sessions)) => sessions,
+            Ok(Err(e)) => {
+                log::debug!("Can't export net session metrics: {}", e);
+                continue;
+            }
+            Err(e) => {
+                log::debug!("Can't export net session metrics: {}", e);
+                continue;
+            }
+        };
+
+        let count = |kind: &str| sessions.iter().filter(|s| s.session_type == kind).count();
+        let peers = sessions.iter().filter(|s| s.node_id.is_some());
+        let rtt_max = peers.clone().map(|s| s.ping).max().unwrap_or_default();
+        let rx_total: usize = sessions.iter().map(|s| s.metrics.rx_total).sum();
+        let tx_total: usize = sessions.iter().map(|s| s.metrics.tx_total).sum();
+
+        gauge!("net.sessions.p2p", count("p2p") as i64);
+        gauge!("net.sessions.relay", count("relay") as i64);
+        gauge!("net.sessions.rtt-max-ms", rtt_max.as_millis() as i64);
+        gauge!("net.sessions.in-bytes", rx_total as i64);
+        gauge!("net.sessions.out-bytes", tx_total as i64);
+    }
+}
+
 fn to_status_metrics(metrics: &mut ChannelMetrics) -> model::StatusMetrics {
     let time = Instant::now();
     model::StatusMetrics {
@@ -2,8 +2,11 @@ mod api;
 pub(crate) mod cli;
 mod codec;
 mod crypto;
+mod relay;
 mod rest_api;
 mod service;
+mod spool;
+mod tunnel;
 
 pub use api::*;
 pub use rest_api::web_scope;
@@ -0,0 +1,344 @@
+//! Client side selection of the relay server, preferring the one with the lowest latency.
+use std::future::Future;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use futures::future::join_all;
+use tokio::sync::RwLock;
+use url::Url;
+
+use ya_core_model::net::local as model;
+use ya_relay_client::{Client, ClientBuilder, FailFast};
+use ya_service_bus::timeout::IntoTimeoutFuture;
+use ya_utils_networking::resolver;
+
+use crate::config::Config;
+use crate::hybrid::tunnel;
+
+/// Number of consecutive failed checks, after which current relay is replaced.
+const DEGRADED_CHECKS: usize = 3;
+
+lazy_static::lazy_static! {
+    pub(crate) static ref RELAYS: Arc<RwLock<Relays>> = Default::default();
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RelayCandidate {
+    pub host: String,
+    pub addr: SocketAddr,
+    pub ping: Option<Duration>,
+    /// Reachable only through WebSocket tunnel.
+    pub tunnel: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Relays {
+    candidates: Vec<RelayCandidate>,
+    current: Option<SocketAddr>,
+}
+
+impl Relays {
+    fn current(&self) -> Option<RelayCandidate> {
+        let current = self.current?;
+        self.candidates.iter().find(|c| c.addr == current).cloned()
+    }
+
+    fn update_ping(&mut self, addr: SocketAddr, ping: Option<Duration>) {
+        if let Some(candidate) = self.candidates.iter_mut().find(|c| c.addr == addr) {
+            candidate.ping = ping;
+        }
+    }
+
+    pub fn to_response(&self) -> Vec<model::RelayResponse> {
+        self.candidates
+            .iter()
+            .map(|c| model::RelayResponse {
+                host: c.host.clone(),
+                address: c.addr,
+                ping: c.ping,
+                current: Some(c.addr) == self.current,
+            })
+            .collect()
+    }
+}
+
+/// Resolves relay candidates, measures their latency and returns the best one.
+/// Falls back to the first candidate, if none of them responded.
+pub(crate) async fn select_relay(config: &Config) -> anyhow::Result<RelayCandidate> {
+    let mut candidates = candidates(config).await?;
+    if candidates.len() > 1 {
+        candidates = probe_all(config, candidates).await;
+    }
+
+    let relay = best(&candidates)
+        .or_else(|| candidates.first())
+        .cloned()
+        .ok_or_else(|| anyhow!("No relay server candidates"))?;
+
+    log::info!(
+        "Hybrid NET relay server selected: udp://{} ({}), ping: {:?}",
+        relay.host,
+        relay.addr,
+        relay.ping
+    );
+
+    let mut relays = RELAYS.write().await;
+    relays.candidates = candidates;
+    relays.current = Some(relay.addr);
+    Ok(relay)
+}
+
+/// Periodically pings current relay server. When it stays unreachable or slower
+/// than `relay_max_ping`, the client is migrated to a faster candidate using `migrate`.
+/// `server` is the address, which the client talks to: the relay server itself or the
+/// local end of its WebSocket tunnel. `migrate` returns the new client with its address.
+pub(crate) async fn monitor_relay<F, Fut>(
+    config: Arc<Config>,
+    mut client: Client,
+    mut server: SocketAddr,
+    migrate: F,
+) where
+    F: Fn(Client, RelayCandidate) -> Fut,
+    Fut: Future<Output = anyhow::Result<(Client, SocketAddr)>>,
+{
+    let mut failed_checks = 0;
+
+    loop {
+        tokio::time::sleep(config.relay_check_interval).await;
+
+        let current = RELAYS.read().await.current();
+        let current = match current {
+            Some(current) => current,
+            None => continue,
+        };
+
+        client.ping_sessions().await;
+        let ping = server_ping(&client, server).await;
+        RELAYS.write().await.update_ping(current.addr, ping);
+
+        if matches!(ping, Some(ping) if ping <= config.relay_max_ping) {
+            failed_checks = 0;
+            continue;
+        }
+
+        failed_checks += 1;
+        log::warn!(
+            "Hybrid NET relay server {} degraded ({}/{}), ping: {:?}",
+            current.host,
+            failed_checks,
+            DEGRADED_CHECKS,
+            ping
+        );
+        if failed_checks < DEGRADED_CHECKS {
+            continue;
+        }
+        failed_checks = 0;
+
+        let others = {
+            let relays = RELAYS.read().await;
+            relays
+                .candidates
+                .iter()
+                .filter(|c| c.addr != current.addr)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let others = probe_all(&config, others).await;
+        {
+            let mut relays = RELAYS.write().await;
+            for candidate in &others {
+                relays.update_ping(candidate.addr, candidate.ping);
+            }
+        }
+
+        let relay = match best(&others) {
+            Some(relay) if ping.map(|ping| relay.ping < Some(ping)).unwrap_or(true) => {
+                relay.clone()
+            }
+            _ => {
+                log::info!("No better relay server than {} available", current.host);
+                continue;
+            }
+        };
+
+        log::info!(
+            "Migrating from relay server {} to {} ({}), ping: {:?}",
+            current.host,
+            relay.host,
+            relay.addr,
+            relay.ping
+        );
+        match migrate(client.clone(), relay.clone()).await {
+            Ok((new_client, new_server)) => {
+                client = new_client;
+                server = new_server;
+                RELAYS.write().await.current = Some(relay.addr);
+            }
+            Err(e) => log::warn!("Migration to relay server {} failed: {}", relay.host, e),
+        }
+    }
+}
+
+fn best(candidates: &[RelayCandidate]) -> Option<&RelayCandidate> {
+    candidates
+        .iter()
+        .filter(|c| c.ping.is_some())
+        .min_by_key(|c| c.ping)
+}
+
+async fn server_ping(client: &Client, addr: SocketAddr) -> Option<Duration> {
+    client
+        .sessions()
+        .await
+        .into_iter()
+        .find(|session| session.remote == addr)
+        .map(|session| session.last_ping)
+}
+
+async fn probe_all(config: &Config, candidates: Vec<RelayCandidate>) -> Vec<RelayCandidate> {
+    join_all(candidates.into_iter().map(|mut candidate| async move {
+        candidate.ping = tunnel::probe(config, &mut candidate)
+            .await
+            .map_err(|e| log::debug!("Probing relay server {} failed: {}", candidate.host, e))
+            .ok();
+        candidate
+    }))
+    .await
+}
+
+/// Measures round trip time to relay server using temporary client with ephemeral identity.
+pub(crate) async fn probe(addr: SocketAddr, timeout: Duration) -> anyhow::Result<Duration> {
+    let probe = async move {
+        let mut client = ClientBuilder::from_url(Url::parse(&format!("udp://{addr}"))?)
+            .listen(Url::parse("udp://0.0.0.0:0")?)
+            .connect(FailFast::Yes)
+            .build()
+            .await?;
+
+        client.ping_sessions().await;
+        let ping = server_ping(&client, addr).await;
+        let _ = client.shutdown().await;
+
+        ping.ok_or_else(|| anyhow!("no session with relay server"))
+    };
+
+    probe
+        .timeout(Some(timeout))
+        .await
+        .map_err(|_| anyhow!("timeout"))?
+}
+
+async fn candidates(config: &Config) -> anyhow::Result<Vec<RelayCandidate>> {
+    let hosts = match &config.host {
+        Some(hosts) => hosts
+            .split(',')
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+            .collect(),
+        None => {
+            resolve_srv_records_with_retries(
+                "_net_relay._udp",
+                RetryArgs {
+                    max_retries: 5,
+                    start_retry_timeout: 10,
+                    add_seconds_every_retry: 5,
+                },
+            )
+            .await?
+        }
+    };
+
+    let mut candidates = Vec::new();
+    for host in hosts {
+        match resolve_relay_addr(&host).await {
+            Ok(addr) => candidates.push(RelayCandidate {
+                host,
+                addr,
+                ping: None,
+                tunnel: false,
+            }),
+            Err(e) => log::warn!("Invalid relay server {}: {}", host, e),
+        }
+    }
+
+    if candidates.is_empty() {
+        anyhow::bail!("No valid relay server configured");
+    }
+    Ok(candidates)
+}
+
+struct RetryArgs {
+    max_retries: u64,
+    start_retry_timeout: u64,
+    add_seconds_every_retry: u64,
+}
+
+async fn resolve_srv_records_with_retries(
+    prefix: &str,
+    args: RetryArgs,
+) -> anyhow::Result<Vec<String>> {
+    let mut retries = 0;
+    let mut timeout_s = args.start_retry_timeout;
+    log::info!("Resolving {prefix} SRV record...");
+    loop {
+        match resolver::resolve_yagna_srv_records(prefix).await {
+            Ok(addrs) => {
+                log::info!("SRV record {prefix} resolved to: {addrs:?}");
+                break Ok(addrs);
+            }
+            Err(err) => {
+                if retries >= args.max_retries {
+                    return Err(anyhow!(
+                        "Failed to resolve {prefix} SRV record: {err} after {retries} retries"
+                    ));
+                }
+                log::warn!(
+                    "Failed to resolve {prefix} SRV record: {err}. Trying again in {timeout_s} seconds",
+                );
+                tokio::time::sleep(std::time::Duration::from_secs(timeout_s)).await;
+                retries += 1;
+                timeout_s += args.add_seconds_every_retry;
+                log::info!("Retrying ({retries}) to resolve {prefix} SRV record...");
+            }
+        }
+    }
+}
+
+async fn resolve_relay_addr(host_port: &str) -> anyhow::Result<SocketAddr> {
+    let (host, port) = host_port
+        .split_once(':')
+        .context("Please use host:port format")?;
+    let ip = resolver::try_resolve_dns_record(host).await;
+    let socket = format!("{}:{}", ip, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("Invalid relay address: {ip}:{port}"))?;
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(port: u16, ping_ms: Option<u64>) -> RelayCandidate {
+        RelayCandidate {
+            host: format!("relay:{port}"),
+            addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            ping: ping_ms.map(Duration::from_millis),
+            tunnel: false,
+        }
+    }
+
+    #[test]
+    fn best_relay_has_lowest_ping() {
+        let candidates = vec![
+            candidate(7477, None),
+            candidate(7478, Some(120)),
+            candidate(7479, Some(30)),
+        ];
+        assert_eq!(best(&candidates).unwrap().addr.port(), 7479);
+        assert!(best(&candidates[..1]).is_none());
+    }
+}
@@ -1,7 +1,7 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::net::SocketAddr;
 use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::atomic::AtomicBool;
@@ -21,9 +21,11 @@ use url::Url;
 use ya_core_model::identity::event::IdentityEvent;
 use ya_core_model::net::local::{
     BindBroadcastError, BroadcastMessage, NewNeighbour, SendBroadcastMessage, SendBroadcastStub,
+    ToEndpoint,
 };
 use ya_core_model::net::GenericNetError;
 use ya_core_model::{identity, net, NodeId};
+use ya_persistence::executor::DbExecutor;
 use ya_relay_client::channels::{ForwardReceiver, ForwardSender, PrefixedStream};
 use ya_relay_client::crypto::CryptoProvider;
 use ya_relay_client::model::{Payload, TransportType};
@@ -36,13 +38,15 @@ use ya_service_bus::untyped::{Fn4HandlerExt, Fn4StreamHandlerExt};
 use ya_service_bus::{
     serialization, typed, untyped as local_bus, Error, ResponseChunk, RpcEndpoint, RpcMessage,
 };
-use ya_utils_networking::resolver;
 
 use crate::bcast::BCastService;
 use crate::config::Config;
 use crate::hybrid::codec;
 use crate::hybrid::codec::encode_message;
 use crate::hybrid::crypto::IdentityCryptoProvider;
+use crate::hybrid::relay;
+use crate::hybrid::spool::Spool;
+use crate::hybrid::tunnel;
 use crate::service::NET_TYPE;
 use crate::{broadcast, NetType};
 
@@ -60,7 +64,11 @@ lazy_static::lazy_static! {
 pub struct Net;
 
 impl Net {
-    pub async fn gsb<Context>(_: Context, config: Config) -> anyhow::Result<()> {
+    pub async fn gsb<Context>(
+        _: Context,
+        config: Config,
+        db: Option<DbExecutor>,
+    ) -> anyhow::Result<()> {
         ya_service_bus::serialization::CONFIG.set_compress(true);
 
         let (default_id, ids) = crate::service::identities().await?;
@@ -77,7 +85,7 @@ impl Net {
             system.block_on(async move {
                 SHUTDOWN_TX.write().await.replace(shutdown_tx);
 
-                let result = start_network(Arc::new(config), default_id, ids).await;
+                let result = start_network(Arc::new(config), default_id, ids, db).await;
                 started_tx.send(result).expect("Unable to start network");
                 let _ = shutdown_rx.await;
             });
@@ -109,6 +117,7 @@ pub async fn start_network(
     config: Arc<Config>,
     default_id: NodeId,
     ids: Vec<NodeId>,
+    db: Option<DbExecutor>,
 ) -> anyhow::Result<()> {
     counter!("net.connections.p2p", 0);
     counter!("net.connections.relay", 0);
@@ -117,18 +126,106 @@ pub async fn start_network(
 
     let broadcast_size = (config.broadcast_size, config.pub_broadcast_size);
     let crypto = IdentityCryptoProvider::new(default_id);
-    let client = build_client(config, crypto.clone()).await?;
-
-    super::cli::bind_service(client.clone());
+    let relay = relay::select_relay(&config)
+        .await
+        .map_err(|e| anyhow!("Resolving hybrid NET relay server failed. Error: {}", e))?;
+    let endpoint = tunnel::relay_endpoint(&config, &relay).await?;
+    let client = build_client(&config, endpoint.addr(), crypto.clone(), FailFast::No).await?;
 
-    let receiver = client.clone().forward_receiver().await.unwrap();
     let mut services: HashSet<_> = Default::default();
     ids.iter().for_each(|id| {
         services.insert(net::net_service_udp(id));
         services.insert(net::net_service(id));
         services.insert(net::net_transfer_service(id));
     });
-    let state = State::new(ids, services);
+    let spool = match db {
+        Some(db) => {
+            db.apply_migration(crate::db::migrations::run_with_output)?;
+            Spool::new(db, &config).await?
+        }
+        None => None,
+    };
+    let state = State::new(ids, services, spool.clone());
+
+    bind_client(
+        client.clone(),
+        state.clone(),
+        default_id,
+        broadcast_size,
+        crypto.clone(),
+    )
+    .await;
+    subscribe_identity_events().await;
+    subscribe_neighbourhood_bcast().await?;
+
+    if let Some(address) = client.public_addr().await {
+        log::info!("Public address: {}", address);
+        counter!("net.public-addresses", 1);
+    } else {
+        counter!("net.public-addresses", 0);
+    }
+
+    if let Some(spool) = spool {
+        tokio::task::spawn_local(spool.run(config.spool_retry_interval));
+    }
+    if !config.session_metrics_interval.is_zero() {
+        tokio::task::spawn_local(super::cli::report_session_metrics(
+            config.session_metrics_interval,
+        ));
+    }
+
+    let server = endpoint.addr();
+    // Keeps the tunnel to the current relay server open.
+    let endpoint = Rc::new(RefCell::new(endpoint));
+    tokio::task::spawn_local(relay::monitor_relay(
+        config.clone(),
+        client,
+        server,
+        move |old_client, relay| {
+            let config = config.clone();
+            let state = state.clone();
+            let crypto = crypto.clone();
+            let current_endpoint = endpoint.clone();
+            async move {
+                let endpoint = tunnel::relay_endpoint(&config, &relay).await?;
+                let client =
+                    build_client(&config, endpoint.addr(), crypto.clone(), FailFast::Yes).await?;
+
+                // Routes are bound to sessions of the previous client.
+                state.inner.borrow_mut().routes.clear();
+                bind_client(client.clone(), state, default_id, broadcast_size, crypto).await;
+
+                let mut old_client = old_client;
+                if let Err(e) = old_client.shutdown().await {
+                    log::debug!("Shutting down previous relay client failed: {}", e);
+                }
+                let server = endpoint.addr();
+                // Tunnel to the previous relay server is closed, when replaced.
+                *current_endpoint.borrow_mut() = endpoint;
+                Ok((client, server))
+            }
+        },
+    ));
+
+    Ok(())
+}
+
+/// Binds local bus handlers using `client`. Called again, when client is replaced
+/// after migrating to another relay server.
+async fn bind_client(
+    client: Client,
+    state: State,
+    default_id: NodeId,
+    broadcast_size: (u32, u32),
+    crypto: IdentityCryptoProvider,
+) {
+    super::cli::bind_service(client.clone());
+
+    if let Some(spool) = &state.spool {
+        spool.set_client(client.clone());
+    }
+
+    let receiver = client.clone().forward_receiver().await.unwrap();
 
     // outbound traffic
     let net_handler = || {
@@ -210,103 +307,31 @@ pub async fn start_network(
         from_handler(),
     );
 
-    tokio::task::spawn_local(forward_handler(client.clone(), receiver, state.clone()));
+    tokio::task::spawn_local(forward_handler(client.clone(), receiver, state));
 
     bind_broadcast_handlers(client.clone(), broadcast_size);
-    bind_identity_event_handler(client.clone(), crypto).await;
-    bind_neighbourhood_bcast(client.clone()).await?;
-
-    if let Some(address) = client.public_addr().await {
-        log::info!("Public address: {}", address);
-        counter!("net.public-addresses", 1);
-    } else {
-        counter!("net.public-addresses", 0);
-    }
-
-    Ok(())
+    bind_identity_event_handler(client.clone(), crypto);
+    bind_neighbourhood_bcast(client);
 }
 
 async fn build_client(
-    config: Arc<Config>,
+    config: &Config,
+    relay_addr: SocketAddr,
     crypto: impl CryptoProvider + 'static,
+    fail_fast: FailFast,
 ) -> anyhow::Result<Client> {
-    let addr = resolve_relay_addr(&config)
-        .await
-        .map_err(|e| anyhow!("Resolving hybrid NET relay server failed. Error: {}", e))?;
-    let url = Url::parse(&format!("udp://{addr}"))?;
+    let url = Url::parse(&format!("udp://{relay_addr}"))?;
 
     ClientBuilder::from_url(url)
         .crypto(crypto)
         .listen(config.bind_url.clone())
         .expire_session_after(config.session_expiration)
         .session_request_timeout(config.session_request_timeout)
-        .connect(FailFast::No)
+        .connect(fail_fast)
         .build()
         .await
 }
 
-struct RetryArgs {
-    max_retries: u64,
-    start_retry_timeout: u64,
-    add_seconds_every_retry: u64,
-}
-async fn resolve_srv_record_with_retries(prefix: &str, args: RetryArgs) -> anyhow::Result<String> {
-    let mut retries = 0;
-    let mut timeout_s = args.start_retry_timeout;
-    log::info!("Resolving {prefix} SRV record...");
-    loop {
-        match resolver::resolve_yagna_srv_record(prefix).await {
-            Ok(addr) => {
-                log::info!("SRV record {prefix} resolved to: {addr}");
-                break Ok(addr);
-            }
-            Err(err) => {
-                if retries >= args.max_retries {
-                    return Err(anyhow!(
-                        "Failed to resolve {prefix} SRV record: {err} after {retries} retries"
-                    ));
-                }
-                log::warn!(
-                    "Failed to resolve {prefix} SRV record: {err}. Trying again in {timeout_s} seconds",
-                );
-                tokio::time::sleep(std::time::Duration::from_secs(timeout_s)).await;
-                retries += 1;
-                timeout_s += args.add_seconds_every_retry;
-                log::info!("Retrying ({retries}) to resolve {prefix} SRV record...");
-            }
-        }
-    }
-}
-
-async fn resolve_relay_addr(config: &Config) -> anyhow::Result<SocketAddr> {
-    let host_port = match &config.host {
-        Some(val) => val.to_string(),
-        None => {
-            resolve_srv_record_with_retries(
-                "_net_relay._udp",
-                RetryArgs {
-                    max_retries: 5,
-                    start_retry_timeout: 10,
-                    add_seconds_every_retry: 5,
-                },
-            )
-            .await?
-        }
-    };
-
-    log::info!("Hybrid NET relay server configured on url: udp://{host_port}");
-
-    let (host, port) = &host_port
-        .split_once(':')
-        .context("Please use host:port format")?;
-    let ip = resolver::try_resolve_dns_record(host).await;
-    let socket = format!("{}:{}", ip, port)
-        .to_socket_addrs()?
-        .next()
-        .ok_or_else(|| anyhow!("Invalid relay address: {ip}:{port}"))?;
-    Ok(socket)
-}
-
 fn bind_local_bus<F>(
     base_client: Client,
     address: &'static str,
@@ -477,10 +502,8 @@ fn bind_local_bus<F>(
 }
 
 /// Handle identity changes
-async fn bind_identity_event_handler(client: Client, crypto: IdentityCryptoProvider) {
-    let endpoint = format!("{}/id", net::BUS_ID);
-
-    typed::bind(endpoint.as_str(), move |event: IdentityEvent| {
+fn bind_identity_event_handler(client: Client, crypto: IdentityCryptoProvider) {
+    typed::bind(&identity_events_endpoint(), move |event: IdentityEvent| {
         log::debug!("Identity event received: {:?}", event);
 
         crypto.reset_alias_cache();
@@ -495,7 +518,10 @@ async fn bind_identity_event_handler(client: Client, crypto: IdentityCryptoProvi
             Ok(())
         }
     });
+}
 
+async fn subscribe_identity_events() {
+    let endpoint = identity_events_endpoint();
     match typed::service(identity::BUS_ID)
         .send(identity::Subscribe { endpoint })
         .await
@@ -506,6 +532,10 @@ async fn bind_identity_event_handler(client: Client, crypto: IdentityCryptoProvi
     }
 }
 
+fn identity_events_endpoint() -> String {
+    format!("{}/id", net::BUS_ID)
+}
+
 /// Forward requests from and to the local bus
 fn forward_bus_to_local(caller: NodeId, addr: &str, data: &[u8], state: &State, tx: BusSender) {
     let caller = caller.to_string();
@@ -565,6 +595,8 @@ fn forward_bus_to_net(
     });
 
     let (tx, rx) = mpsc::channel(1);
+    let spool = state.spool_for(&address, transport);
+    let spooled = spool.as_ref().map(|_| msg.to_vec());
     let msg = match codec::encode_request(
         caller_id,
         address.clone(),
@@ -597,18 +629,24 @@ fn forward_bus_to_net(
             msg.len()
         );
 
-        match state.forward_sink(client, remote_id, transport).await {
-            Ok(mut sink) => {
-                let _ = sink.send(msg.into()).await.map_err(|_| {
-                    let err = "Net: error sending message: session closed".to_string();
-                    handler_reply_service_err(request_id, err, tx);
-                });
-            }
-            Err(error) => {
-                let err = format!("Net: error forwarding message: {:?}", error);
-                handler_reply_service_err(request_id, err, tx);
-            }
+        let mut err = match state.forward_sink(client, remote_id, transport).await {
+            Ok(mut sink) => match sink.send(msg.into()).await {
+                Ok(_) => {
+                    if let (Some(spool), Some(spooled)) = (spool, spooled) {
+                        spool.discard(caller_id, remote_id, address, spooled).await;
+                    }
+                    return;
+                }
+                Err(_) => "Net: error sending message: session closed".to_string(),
+            },
+            Err(error) => format!("Net: error forwarding message: {:?}", error),
         };
+
+        if let (Some(spool), Some(spooled)) = (spool, spooled) {
+            spool.store(caller_id, remote_id, address, spooled).await;
+            err.push_str(" (spooled for delivery)");
+        }
+        handler_reply_service_err(request_id, err, tx);
     });
 
     rx
@@ -631,6 +669,8 @@ fn push_bus_to_net(
         ya_packet_trace::try_extract_from_ip_frame(msg)
     });
 
+    let spool = state.spool_for(&address, transport);
+    let spooled = spool.as_ref().map(|_| msg.to_vec());
     let msg = match codec::encode_request(
         caller_id,
         address.clone(),
@@ -655,15 +695,23 @@ fn push_bus_to_net(
             .forward_sink(client.clone(), remote_id, transport)
             .await
         {
-            Ok(mut sink) => {
-                let _ = sink.send(msg.into()).await.map_err(|_| {
-                    log::debug!("Net: error sending message: session closed");
-                });
-            }
+            Ok(mut sink) => match sink.send(msg.into()).await {
+                Ok(_) => {
+                    if let (Some(spool), Some(spooled)) = (spool, spooled) {
+                        spool.discard(caller_id, remote_id, address, spooled).await;
+                    }
+                    return;
+                }
+                Err(_) => log::debug!("Net: error sending message: session closed"),
+            },
             Err(error) => {
                 log::debug!("Net: error forwarding message: {}", error);
             }
         };
+
+        if let (Some(spool), Some(spooled)) = (spool, spooled) {
+            spool.store(caller_id, remote_id, address, spooled).await;
+        }
     });
 }
 
@@ -784,6 +832,14 @@ fn forward_handler(
                     fwd.node_id
                 );
 
+                // Peer is reachable again, deliver messages spooled while it was offline.
+                if let Some(spool) = &state.spool {
+                    if spool.has_pending(&fwd.node_id) {
+                        let spool = spool.clone();
+                        tokio::task::spawn_local(async move { spool.flush(fwd.node_id).await });
+                    }
+                }
+
                 tokio::task::spawn_local(async move {
                     if tx.send(fwd.payload).await.is_err() {
                         log::debug!("Net routing error: channel closed for [{}]", fwd.node_id);
@@ -1096,6 +1152,7 @@ fn handle_broadcast(
 #[derive(Clone)]
 struct State {
     inner: Rc<RefCell<StateInner>>,
+    spool: Option<Spool>,
 }
 
 #[derive(Default)]
@@ -1107,13 +1164,28 @@ struct StateInner {
 }
 
 impl State {
-    fn new(ids: impl IntoIterator<Item = NodeId>, services: HashSet<String>) -> Self {
+    fn new(
+        ids: impl IntoIterator<Item = NodeId>,
+        services: HashSet<String>,
+        spool: Option<Spool>,
+    ) -> Self {
         Self {
             inner: Rc::new(RefCell::new(StateInner {
                 ids: ids.into_iter().collect(),
                 services,
                 ..Default::default()
             })),
+            spool,
+        }
+    }
+
+    /// Spool for messages to `address`, which should survive peer being offline.
+    fn spool_for(&self, address: &str, transport: TransportType) -> Option<Spool> {
+        match &self.spool {
+            Some(spool) if transport == TransportType::Reliable && spool.accepts(address) => {
+                Some(spool.clone())
+            }
+            _ => None,
         }
     }
 
@@ -1228,16 +1300,15 @@ pub fn parse_from_to_addr(addr: &str) -> anyhow::Result<(NodeId, NodeId, String)
     Ok((from_id, to_id, format!("{}{}", prefix, addr)))
 }
 
-fn gen_id() -> u64 {
+pub(crate) fn gen_id() -> u64 {
     use rand::Rng;
     let mut rng = rand::thread_rng();
     rng.gen::<u64>() & 0x001f_ffff_ffff_ffff_u64
 }
 
-async fn bind_neighbourhood_bcast(client: Client) -> anyhow::Result<(), BindBroadcastError> {
-    let bcast_address = format!("{}/{}", net::local::BUS_ID, NewNeighbour::TOPIC);
-    crate::hybrid::bind_broadcast_with_caller(
-        &bcast_address,
+fn bind_neighbourhood_bcast(client: Client) {
+    typed::bind_with_caller(
+        &neighbourhood_bcast_address(),
         move |caller, _msg: SendBroadcastMessage<NewNeighbour>| {
             let client = client.clone();
             async move {
@@ -1248,8 +1319,19 @@ async fn bind_neighbourhood_bcast(client: Client) -> anyhow::Result<(), BindBroa
                 Ok(())
             }
         },
-    )
-    .await
+    );
+}
+
+async fn subscribe_neighbourhood_bcast() -> anyhow::Result<(), BindBroadcastError> {
+    let subscription = NewNeighbour::into_subscribe_msg(neighbourhood_bcast_address());
+    typed::service(net::local::BUS_ID)
+        .send(subscription)
+        .await??;
+    Ok(())
+}
+
+fn neighbourhood_bcast_address() -> String {
+    format!("{}/{}", net::local::BUS_ID, NewNeighbour::TOPIC)
 }
 
 pub async fn send_bcast_new_neighbour() {
@@ -0,0 +1,206 @@
+//! Store-and-forward of designated GSB messages to temporarily unreachable peers.
+//!
+//! Messages, which couldn't be sent, are persisted with TTL and delivered as pushes
+//! when the peer reconnects, or on periodic retry. Callers of requests still get
+//! the error and may retry: the same message is spooled only once and spooled copy
+//! is dropped, when the retry gets through. Spooled methods must be idempotent.
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::{NaiveDateTime, Utc};
+use futures::SinkExt;
+
+use ya_core_model::NodeId;
+use ya_persistence::executor::DbExecutor;
+use ya_relay_client::Client;
+
+use crate::config::Config;
+use crate::db::dao::SpoolDao;
+use crate::db::model::NewSpooledMessage;
+use crate::hybrid::codec;
+use crate::hybrid::service::gen_id;
+
+#[derive(Clone)]
+pub(crate) struct Spool {
+    db: DbExecutor,
+    methods: Rc<Vec<String>>,
+    ttl: Duration,
+    max_per_peer: usize,
+    client: Rc<RefCell<Option<Client>>>,
+    /// Peers with spooled messages.
+    pending: Rc<RefCell<HashSet<NodeId>>>,
+    /// Peers, which spooled messages are being delivered to.
+    delivering: Rc<RefCell<HashSet<NodeId>>>,
+}
+
+impl Spool {
+    /// Returns `None` if spooling is disabled in `config`.
+    pub async fn new(db: DbExecutor, config: &Config) -> anyhow::Result<Option<Self>> {
+        let methods = config
+            .spool_methods
+            .split(',')
+            .map(|method| method.trim().trim_start_matches('/'))
+            .filter(|method| !method.is_empty())
+            .map(|method| format!("/{method}"))
+            .collect::<Vec<_>>();
+        if methods.is_empty() || config.spool_ttl.is_zero() {
+            return Ok(None);
+        }
+
+        let pending = db.as_dao::<SpoolDao>().peers().await?;
+        if !pending.is_empty() {
+            log::info!("Spooled messages waiting for {} peer(s)", pending.len());
+        }
+
+        Ok(Some(Spool {
+            db,
+            methods: Rc::new(methods),
+            ttl: config.spool_ttl,
+            max_per_peer: config.spool_max_per_peer,
+            client: Default::default(),
+            pending: Rc::new(RefCell::new(pending.into_iter().collect())),
+            delivering: Default::default(),
+        }))
+    }
+
+    /// Client used for delivery. Replaced after migrating to another relay server.
+    pub fn set_client(&self, client: Client) {
+        self.client.replace(Some(client));
+    }
+
+    pub fn accepts(&self, address: &str) -> bool {
+        self.methods
+            .iter()
+            .any(|method| address.ends_with(method.as_str()))
+    }
+
+    pub fn has_pending(&self, remote_id: &NodeId) -> bool {
+        self.pending.borrow().contains(remote_id)
+    }
+
+    pub async fn store(&self, caller_id: NodeId, remote_id: NodeId, address: String, msg: Vec<u8>) {
+        let now = Utc::now().naive_utc();
+        let expires_ts = chrono::Duration::from_std(self.ttl)
+            .ok()
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .unwrap_or(NaiveDateTime::MAX);
+        let message = NewSpooledMessage {
+            caller_id,
+            remote_id,
+            address: address.clone(),
+            msg,
+            created_ts: now,
+            expires_ts,
+        };
+
+        match self
+            .db
+            .as_dao::<SpoolDao>()
+            .push(message, self.max_per_peer)
+            .await
+        {
+            Ok(true) => {
+                log::info!("Spooled message {address} to unreachable peer [{remote_id}]");
+                self.pending.borrow_mut().insert(remote_id);
+            }
+            Ok(false) => log::debug!("Message {address} to [{remote_id}] is already spooled"),
+            Err(e) => log::warn!("Failed to spool message {address} to [{remote_id}]: {e}"),
+        }
+    }
+
+    /// Drops spooled copies of a message, which was just delivered directly.
+    pub async fn discard(
+        &self,
+        caller_id: NodeId,
+        remote_id: NodeId,
+        address: String,
+        msg: Vec<u8>,
+    ) {
+        if !self.has_pending(&remote_id) {
+            return;
+        }
+        let dao = self.db.as_dao::<SpoolDao>();
+        match dao
+            .discard(caller_id, remote_id, address.clone(), msg)
+            .await
+        {
+            Ok(0) => (),
+            Ok(_) => log::debug!("Dropped spooled message {address} delivered to [{remote_id}]"),
+            Err(e) => log::warn!("Failed to drop spooled message {address} to [{remote_id}]: {e}"),
+        }
+    }
+
+    /// Delivers messages spooled for `remote_id` in the order they were spooled.
+    pub async fn flush(&self, remote_id: NodeId) {
+        let client = match self.client.borrow().clone() {
+            Some(client) => client,
+            None => return,
+        };
+        if !self.delivering.borrow_mut().insert(remote_id) {
+            return;
+        }
+
+        let result = self.deliver(client, remote_id).await;
+        self.delivering.borrow_mut().remove(&remote_id);
+
+        match result {
+            Ok(0) => (),
+            Ok(delivered) => {
+                log::info!("Delivered {delivered} spooled message(s) to [{remote_id}]")
+            }
+            Err(e) => log::debug!("Delivering spooled messages to [{remote_id}] failed: {e}"),
+        }
+    }
+
+    async fn deliver(&self, client: Client, remote_id: NodeId) -> anyhow::Result<usize> {
+        let dao = self.db.as_dao::<SpoolDao>();
+        let mut delivered = 0;
+
+        // Messages could be spooled while delivering previous batch.
+        loop {
+            let messages = dao.list(remote_id).await?;
+            if messages.is_empty() {
+                self.pending.borrow_mut().remove(&remote_id);
+                return Ok(delivered);
+            }
+
+            let mut sink = client.forward_reliable(remote_id).await?.framed();
+            for message in messages {
+                let request = codec::encode_request(
+                    message.caller_id,
+                    message.address,
+                    gen_id().to_string(),
+                    message.msg,
+                    true,
+                )?;
+                sink.send(request.into())
+                    .await
+                    .map_err(|_| anyhow!("session closed"))?;
+
+                dao.remove(message.id).await?;
+                delivered += 1;
+            }
+        }
+    }
+
+    /// Periodically retries delivery to peers with spooled messages and drops expired ones.
+    pub async fn run(self, retry_interval: Duration) {
+        loop {
+            tokio::time::sleep(retry_interval).await;
+
+            match self.db.as_dao::<SpoolDao>().clean_expired().await {
+                Ok(0) => (),
+                Ok(expired) => log::info!("Dropped {expired} expired spooled message(s)"),
+                Err(e) => log::warn!("Failed to drop expired spooled messages: {e}"),
+            }
+
+            let peers = self.pending.borrow().iter().cloned().collect::<Vec<_>>();
+            for remote_id in peers {
+                self.flush(remote_id).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,508 @@
+//! WebSocket fallback transport for networks, which block UDP traffic to the relay server.
+//!
+//! Relay protocol packets are carried over `wss://` (port 443 by default, which corporate
+//! proxies and firewalls let through), one packet per binary WebSocket message. The tunnel
+//! listens on a local UDP socket, so the relay client talks to it as it would to the relay
+//! server itself. Broken WebSocket connection is re-established in the background.
+//!
+//! When `HTTPS_PROXY` (`HTTP_PROXY` for `ws://`) is set, the connection is opened with
+//! `CONNECT` through the proxy, unless the relay host is listed in `NO_PROXY`. Each relay
+//! server is reached through its own tunnel, so relay migration works the same way as over
+//! UDP: candidates are probed through temporary tunnels.
+use std::cell::Cell;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Duration;
+
+use actix_codec::Framed;
+use anyhow::{anyhow, bail, Context};
+use awc::ws::{Codec, Frame, Message, ProtocolError};
+use base64::Engine;
+use bytes::Bytes;
+use futures::future::Either;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::task::JoinHandle;
+use url::Url;
+
+use crate::config::{Config, RelayTransport};
+use crate::hybrid::relay::{self, RelayCandidate};
+
+/// Path of the WebSocket endpoint on relay servers.
+const WS_PATH: &str = "/relay";
+const MAX_PACKET_SIZE: usize = 65535;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Limit of HTTP response head read from proxies and WebSocket endpoints.
+const MAX_HEAD_SIZE: usize = 8192;
+
+/// Local end of a WebSocket tunnel to the relay server. Forwarding stops, when dropped.
+pub(crate) struct Tunnel {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Address the relay client connects to.
+#[derive(Clone)]
+pub(crate) enum Endpoint {
+    Direct(SocketAddr),
+    Tunnel(Rc<Tunnel>),
+}
+
+impl Endpoint {
+    pub fn addr(&self) -> SocketAddr {
+        match self {
+            Endpoint::Direct(addr) => *addr,
+            Endpoint::Tunnel(tunnel) => tunnel.addr,
+        }
+    }
+}
+
+/// Chooses transport to the relay server according to `YA_NET_RELAY_TRANSPORT`. In auto
+/// mode the tunnel is started, only if the relay doesn't respond over UDP, but its
+/// WebSocket endpoint is reachable.
+pub(crate) async fn relay_endpoint(
+    config: &Config,
+    relay: &RelayCandidate,
+) -> anyhow::Result<Endpoint> {
+    let tunnel = match config.relay_transport {
+        RelayTransport::Udp => false,
+        RelayTransport::Websocket => true,
+        RelayTransport::Auto if relay.ping.is_some() => relay.tunnel,
+        RelayTransport::Auto => relay::probe(relay.addr, config.relay_probe_timeout)
+            .await
+            .map_err(|e| log::info!("Relay server {} unreachable over UDP: {}", relay.host, e))
+            .is_err(),
+    };
+    if !tunnel {
+        return Ok(Endpoint::Direct(relay.addr));
+    }
+
+    let url = ws_url(config.relay_ws_url.as_ref(), &relay.host)?;
+    match start(url.clone(), config.relay_ws_ping_interval).await {
+        Ok(tunnel) => {
+            log::info!("Hybrid NET relay server reached through WebSocket tunnel: {url}");
+            Ok(Endpoint::Tunnel(Rc::new(tunnel)))
+        }
+        Err(e) if config.relay_transport == RelayTransport::Auto => {
+            log::warn!("WebSocket fallback unavailable, staying with UDP. {e}");
+            Ok(Endpoint::Direct(relay.addr))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Measures round trip time to the relay server over the transport, which it would be
+/// reached with. Marks candidates reachable only through the tunnel.
+pub(crate) async fn probe(config: &Config, relay: &mut RelayCandidate) -> anyhow::Result<Duration> {
+    if config.relay_transport != RelayTransport::Websocket {
+        match relay::probe(relay.addr, config.relay_probe_timeout).await {
+            Ok(ping) => {
+                relay.tunnel = false;
+                return Ok(ping);
+            }
+            Err(e) if config.relay_transport == RelayTransport::Udp => return Err(e),
+            Err(e) => log::debug!("Relay server {} unreachable over UDP: {}", relay.host, e),
+        }
+    }
+
+    let url = ws_url(config.relay_ws_url.as_ref(), &relay.host)?;
+    let tunnel = tokio::time::timeout(
+        config.relay_probe_timeout,
+        start(url, config.relay_ws_ping_interval),
+    )
+    .await
+    .map_err(|_| anyhow!("timeout"))??;
+    let ping = relay::probe(tunnel.addr, config.relay_probe_timeout).await?;
+    relay.tunnel = true;
+    Ok(ping)
+}
+
+/// WebSocket endpoint of the relay server given as `host:port`, unless configured.
+fn ws_url(configured: Option<&Url>, relay_host: &str) -> anyhow::Result<Url> {
+    if let Some(url) = configured {
+        return Ok(url.clone());
+    }
+    let host = relay_host
+        .rsplit_once(':')
+        .map(|(host, _)| host)
+        .unwrap_or(relay_host);
+    Ok(Url::parse(&format!("wss://{host}:443{WS_PATH}"))?)
+}
+
+/// Connects to `url` and starts forwarding packets.
+async fn start(url: Url, ping_interval: Duration) -> anyhow::Result<Tunnel> {
+    let socket = Rc::new(UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await?);
+    let addr = socket.local_addr()?;
+    // Fail early, so the caller can fall back to UDP.
+    let connection = connect(&url).await?;
+
+    let task = tokio::task::spawn_local(async move {
+        let peer = Cell::new(None);
+        let mut connection = connection;
+        loop {
+            if let Err(e) = forward(&socket, &peer, connection, ping_interval).await {
+                log::warn!("WebSocket tunnel to {url} broken: {e}");
+            }
+            connection = loop {
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                match connect(&url).await {
+                    Ok(connection) => break connection,
+                    Err(e) => log::debug!("{e}"),
+                }
+            };
+            log::info!("WebSocket tunnel to {url} reconnected");
+        }
+    });
+    Ok(Tunnel { addr, task })
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Io for T {}
+
+async fn connect(
+    url: &Url,
+) -> anyhow::Result<
+    impl Sink<Message, Error = ProtocolError> + Stream<Item = Result<Frame, ProtocolError>> + Unpin,
+> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("Missing host in {url}"))?;
+    let proxy = match proxy_for(url.scheme(), host, |name| std::env::var(name).ok()) {
+        Some(proxy) => proxy,
+        None => {
+            let (_, connection) = awc::Client::new()
+                .ws(url.as_str())
+                .max_frame_size(MAX_PACKET_SIZE)
+                .connect()
+                .await
+                .map_err(|e| anyhow!("WebSocket connection to {url} failed: {e}"))?;
+            return Ok(Either::Left(connection));
+        }
+    };
+
+    let connection = async {
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| anyhow!("Missing port"))?;
+        let stream = proxy_connect(&proxy, host, port).await?;
+        let mut stream: Box<dyn Io> = match url.scheme() {
+            "wss" => Box::new(tls_connect(stream, host).await?),
+            _ => Box::new(stream),
+        };
+        ws_handshake(&mut stream, url).await?;
+        anyhow::Ok(Framed::new(
+            stream,
+            Codec::new().max_size(MAX_PACKET_SIZE).client_mode(),
+        ))
+    }
+    .await
+    .with_context(|| format!("WebSocket connection to {url} through proxy {proxy} failed"))?;
+    Ok(Either::Right(connection))
+}
+
+/// Proxy for connections to `host` taken from the environment, unless the host is excluded
+/// by `NO_PROXY`.
+fn proxy_for(scheme: &str, host: &str, env: impl Fn(&str) -> Option<String>) -> Option<Url> {
+    let var = |name: &str| {
+        env(name)
+            .or_else(|| env(&name.to_lowercase()))
+            .filter(|value| !value.trim().is_empty())
+    };
+    let proxy = match scheme {
+        "wss" => var("HTTPS_PROXY"),
+        _ => var("HTTP_PROXY"),
+    }?;
+
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let excluded = var("NO_PROXY").map_or(false, |no_proxy| {
+        no_proxy
+            .split(',')
+            .map(|entry| entry.trim().trim_start_matches('.'))
+            .filter(|entry| !entry.is_empty())
+            .any(|entry| entry == "*" || host == entry || host.ends_with(&format!(".{entry}")))
+    });
+    if excluded {
+        return None;
+    }
+
+    // Proxies are commonly given without a scheme.
+    let proxy = match proxy.contains("://") {
+        true => proxy,
+        false => format!("http://{proxy}"),
+    };
+    Url::parse(&proxy)
+        .map_err(|e| log::warn!("Invalid proxy {}: {}", proxy, e))
+        .ok()
+}
+
+/// Opens TCP tunnel to `host:port` with HTTP `CONNECT` through the proxy.
+async fn proxy_connect(proxy: &Url, host: &str, port: u16) -> anyhow::Result<TcpStream> {
+    let proxy_host = proxy
+        .host_str()
+        .ok_or_else(|| anyhow!("Missing proxy host"))?;
+    let proxy_port = proxy.port_or_known_default().unwrap_or(80);
+    let mut stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if !proxy.username().is_empty() {
+        let credentials = format!(
+            "{}:{}",
+            percent_decode(proxy.username()),
+            percent_decode(proxy.password().unwrap_or_default())
+        );
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        ));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let head = read_head(&mut stream).await?;
+    match status(&head) {
+        Some(200) => Ok(stream),
+        _ => bail!(
+            "proxy refused to connect: {}",
+            head.lines().next().unwrap_or_default()
+        ),
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    percent_encoding::percent_decode_str(value)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+async fn tls_connect(
+    stream: TcpStream,
+    host: &str,
+) -> anyhow::Result<tokio_openssl::SslStream<TcpStream>> {
+    use openssl::ssl::{SslConnector, SslMethod};
+
+    let ssl = SslConnector::builder(SslMethod::tls())?
+        .build()
+        .configure()?
+        .into_ssl(host)?;
+    let mut stream = tokio_openssl::SslStream::new(ssl, stream)?;
+    Pin::new(&mut stream).connect().await?;
+    Ok(stream)
+}
+
+/// Upgrades connection to WebSocket.
+async fn ws_handshake<S: Io + ?Sized>(stream: &mut S, url: &Url) -> anyhow::Result<()> {
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    let key = base64::engine::general_purpose::STANDARD.encode(rand::random::<[u8; 16]>());
+    let request = format!(
+        "GET {path} HTTP/1.1\r\nHost: {host}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let head = read_head(stream).await?;
+    match status(&head) {
+        Some(101) => Ok(()),
+        _ => bail!(
+            "upgrade to WebSocket refused: {}",
+            head.lines().next().unwrap_or_default()
+        ),
+    }
+}
+
+/// Reads HTTP response head. Reads byte by byte, so nothing after the head is consumed.
+async fn read_head<S: AsyncRead + Unpin + ?Sized>(stream: &mut S) -> anyhow::Result<String> {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEAD_SIZE {
+            bail!("response head too long");
+        }
+        match stream.read_u8().await {
+            Ok(byte) => head.push(byte),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => bail!("connection closed"),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Status code of HTTP response head.
+fn status(head: &str) -> Option<u16> {
+    let mut status_line = head.lines().next()?.split_whitespace();
+    match status_line.next()? {
+        version if version.starts_with("HTTP/1.") => status_line.next()?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Forwards packets between the local socket and WebSocket, until the connection breaks.
+/// Packets from WebSocket are sent to the address, which the last local packet came from.
+async fn forward<C>(
+    socket: &UdpSocket,
+    peer: &Cell<Option<SocketAddr>>,
+    connection: C,
+    ping_interval: Duration,
+) -> anyhow::Result<()>
+where
+    C: Sink<Message, Error = ProtocolError> + Stream<Item = Result<Frame, ProtocolError>> + Unpin,
+{
+    let (mut sink, mut stream) = connection.split();
+    let mut buf = vec![0u8; MAX_PACKET_SIZE];
+    let mut ping = tokio::time::interval(ping_interval);
+
+    loop {
+        tokio::select! {
+            received = socket.recv_from(&mut buf) => {
+                let (size, from) = received?;
+                peer.set(Some(from));
+                sink.send(Message::Binary(Bytes::copy_from_slice(&buf[..size])))
+                    .await?;
+            }
+            frame = stream.next() => match frame {
+                Some(Ok(Frame::Binary(packet))) => {
+                    if let Some(peer) = peer.get() {
+                        socket.send_to(&packet, peer).await?;
+                    }
+                }
+                Some(Ok(Frame::Ping(data))) => sink.send(Message::Pong(data)).await?,
+                Some(Ok(Frame::Close(reason))) => bail!("closed by relay server: {reason:?}"),
+                Some(Ok(_)) => (),
+                Some(Err(e)) => return Err(e.into()),
+                None => bail!("connection closed"),
+            },
+            _ = ping.tick() => sink.send(Message::Ping(Bytes::new())).await?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn default_ws_url_uses_https_port() {
+        assert_eq!(
+            ws_url(None, "relay.golem.network:7477").unwrap().as_str(),
+            "wss://relay.golem.network/relay"
+        );
+        assert_eq!(
+            ws_url(None, "127.0.0.1:7464").unwrap().as_str(),
+            "wss://127.0.0.1/relay"
+        );
+        let configured = Url::parse("wss://tunnel.example.com/relay").unwrap();
+        assert_eq!(
+            ws_url(Some(&configured), "127.0.0.1:7464").unwrap(),
+            configured
+        );
+    }
+
+    #[test]
+    fn proxy_from_environment() {
+        let env: HashMap<&str, &str> = [
+            ("HTTPS_PROXY", "proxy.corp:3128"),
+            ("http_proxy", "http://plain.corp:8080"),
+            ("NO_PROXY", "localhost, .internal.corp"),
+        ]
+        .iter()
+        .copied()
+        .collect();
+        let proxy = |scheme: &str, host: &str| {
+            proxy_for(scheme, host, |name| env.get(name).map(|v| v.to_string()))
+                .map(|url| url.to_string())
+        };
+
+        assert_eq!(
+            proxy("wss", "relay.golem.network"),
+            Some("http://proxy.corp:3128/".to_string())
+        );
+        assert_eq!(
+            proxy("ws", "relay.golem.network"),
+            Some("http://plain.corp:8080/".to_string())
+        );
+        assert_eq!(proxy("wss", "localhost"), None);
+        assert_eq!(proxy("wss", "relay.internal.corp"), None);
+        assert_eq!(proxy("wss", "internal.corp"), None);
+    }
+
+    #[test]
+    fn response_status() {
+        assert_eq!(
+            status("HTTP/1.1 200 Connection established\r\n\r\n"),
+            Some(200)
+        );
+        assert_eq!(
+            status("HTTP/1.0 407 Proxy Authentication Required\r\n"),
+            Some(407)
+        );
+        assert_eq!(status("SSH-2.0-OpenSSH\r\n"), None);
+    }
+
+    #[tokio::test]
+    async fn connect_through_proxy() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let proxy = Url::parse(&format!(
+            "http://user:p%40ss@{}",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let request = read_head(&mut stream).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nrelay")
+                .await
+                .unwrap();
+            request
+        });
+
+        let mut stream = proxy_connect(&proxy, "relay.golem.network", 443)
+            .await
+            .unwrap();
+        let mut tunnelled = [0u8; 5];
+        stream.read_exact(&mut tunnelled).await.unwrap();
+        assert_eq!(&tunnelled, b"relay");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("CONNECT relay.golem.network:443 HTTP/1.1\r\n"));
+        // base64("user:p@ss")
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwQHNz\r\n"));
+    }
+
+    #[tokio::test]
+    async fn proxy_refusal_is_reported() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let proxy = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_head(&mut stream).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 403 Forbidden\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let error = proxy_connect(&proxy, "relay.golem.network", 443)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("403 Forbidden"));
+    }
+}
@@ -0,0 +1,209 @@
+//! LAN-only network.
+//!
+//! Nodes discover each other with mDNS, without relay server or internet connectivity,
+//! which is useful for air-gapped clusters and CI environments. One of the nodes hosts
+//! GSB router (the hub) for the whole LAN and announces it as `_yagna-hub._tcp` service.
+//! Other nodes connect to the hub using Central NET protocol, so Offers reach all nodes
+//! through hub broadcasts. When the hub disappears, nodes discover it again and one
+//! of them takes over.
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rand::Rng;
+use url::Url;
+
+use ya_core_model::NodeId;
+
+use crate::central::HubAddr;
+use crate::config::{Config, LanRole};
+
+const SERVICE_TYPE: &str = "_yagna-hub._tcp.local.";
+const NODE_ID_PROPERTY: &str = "node_id";
+
+lazy_static::lazy_static! {
+    static ref MDNS: Mutex<Option<ServiceDaemon>> = Default::default();
+    /// Address of the hub hosted by this node.
+    static ref OWN_HUB: Mutex<Option<SocketAddr>> = Default::default();
+}
+
+pub struct Net;
+
+impl Net {
+    pub async fn gsb<Context>(_: Context, config: Config) -> anyhow::Result<()> {
+        let (default_id, _) = crate::service::identities().await?;
+        let daemon = ServiceDaemon::new()?;
+        MDNS.lock().unwrap().replace(daemon.clone());
+
+        log::info!(
+            "LAN NET - Discovering hub with mDNS, role: {}",
+            <&str>::from(config.lan_role)
+        );
+
+        let config = Rc::new(config);
+        crate::central::Net::gsb_with_hub(move || {
+            resolve_hub(daemon.clone(), config.clone(), default_id)
+        })
+        .await
+    }
+
+    pub async fn shutdown() -> anyhow::Result<()> {
+        let daemon = MDNS.lock().unwrap().take();
+        if let Some(daemon) = daemon {
+            if let Err(e) = daemon.shutdown() {
+                log::debug!("Shutting down mDNS daemon failed: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_io(e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+async fn resolve_hub(
+    daemon: ServiceDaemon,
+    config: Rc<Config>,
+    node_id: NodeId,
+) -> io::Result<HubAddr> {
+    let own_hub = *OWN_HUB.lock().unwrap();
+    if let Some(addr) = own_hub {
+        return Ok((addr, None));
+    }
+
+    if config.lan_role != LanRole::Hub {
+        // Random delay lowers the chance, that nodes started together all become hubs.
+        let jitter = config
+            .lan_discovery_timeout
+            .mul_f64(rand::thread_rng().gen_range(0.0, 1.0));
+        let timeout = config.lan_discovery_timeout + jitter;
+
+        if let Some(hub) = discover_hub(&daemon, timeout).await.map_err(to_io)? {
+            return Ok((hub, None));
+        }
+        if config.lan_role == LanRole::Client {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "LAN hub not found"));
+        }
+    }
+
+    let addr = start_hub(&daemon, &config.lan_hub_url, node_id).await?;
+    Ok((addr, None))
+}
+
+/// Browses hubs announced in the network for `timeout`.
+async fn discover_hub(
+    daemon: &ServiceDaemon,
+    timeout: Duration,
+) -> Result<Option<SocketAddr>, mdns_sd::Error> {
+    let receiver = daemon.browse(SERVICE_TYPE)?;
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut hubs = Vec::new();
+
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, receiver.recv_async()).await {
+        if let ServiceEvent::ServiceResolved(info) = event {
+            let node_id = info
+                .get_property_val_str(NODE_ID_PROPERTY)
+                .and_then(|id| id.parse::<NodeId>().ok());
+            let addr = info
+                .get_addresses()
+                .iter()
+                .next()
+                .map(|ip| SocketAddr::new((*ip).into(), info.get_port()));
+
+            if let (Some(node_id), Some(addr)) = (node_id, addr) {
+                log::debug!("LAN NET - Discovered hub [{}] at {}", node_id, addr);
+                hubs.push((node_id, addr));
+            }
+        }
+    }
+    daemon.stop_browse(SERVICE_TYPE).ok();
+
+    Ok(choose_hub(hubs))
+}
+
+/// Hub of the node with the lowest id is chosen, so all nodes connect
+/// to the same one, when more hubs are announced.
+fn choose_hub(hubs: Vec<(NodeId, SocketAddr)>) -> Option<SocketAddr> {
+    hubs.into_iter()
+        .min_by_key(|(node_id, _)| node_id.to_string())
+        .map(|(_, addr)| addr)
+}
+
+async fn start_hub(daemon: &ServiceDaemon, url: &Url, node_id: NodeId) -> io::Result<SocketAddr> {
+    let addr = hub_connect_addr(url)?;
+    ya_sb_router::bind_gsb_router(Some(url.clone()))
+        .await
+        .map_err(to_io)?;
+
+    let node_id = node_id.to_string();
+    let host_name = format!("{}.local.", node_id);
+    let properties = [(NODE_ID_PROPERTY, node_id.as_str())];
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &node_id,
+        &host_name,
+        "",
+        addr.port(),
+        &properties[..],
+    )
+    .map_err(to_io)?
+    .enable_addr_auto();
+    daemon.register(info).map_err(to_io)?;
+
+    log::info!("LAN NET - Hosting hub at {}", url);
+    OWN_HUB.lock().unwrap().replace(addr);
+    Ok(addr)
+}
+
+/// Address, which this node uses to connect to the hub it hosts.
+fn hub_connect_addr(url: &Url) -> io::Result<SocketAddr> {
+    let port = url
+        .port()
+        .ok_or_else(|| to_io(format!("LAN hub url {} without port", url)))?;
+    let ip = match url.host_str().map(|host| host.parse::<IpAddr>()) {
+        Some(Ok(ip)) if !ip.is_unspecified() => ip,
+        Some(Ok(_)) | None => Ipv4Addr::LOCALHOST.into(),
+        Some(Err(e)) => return Err(to_io(format!("invalid LAN hub url {}: {}", url, e))),
+    };
+    Ok(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hub_with_lowest_node_id_is_chosen() {
+        let node = |id: &str| id.parse::<NodeId>().unwrap();
+        let hubs = vec![
+            (
+                node("0xe93ab94a2095729ad0b7cfa5bfd7d33e1b44d6df"),
+                "192.168.1.2:7465".parse().unwrap(),
+            ),
+            (
+                node("0x99402605903da83901151b0871ebeae9296ef66b"),
+                "192.168.1.3:7465".parse().unwrap(),
+            ),
+        ];
+        assert_eq!(choose_hub(hubs), Some("192.168.1.3:7465".parse().unwrap()));
+        assert_eq!(choose_hub(vec![]), None);
+    }
+
+    #[test]
+    fn own_hub_is_connected_locally() {
+        let url = |url: &str| Url::parse(url).unwrap();
+        assert_eq!(
+            hub_connect_addr(&url("tcp://0.0.0.0:7465")).unwrap(),
+            "127.0.0.1:7465".parse().unwrap()
+        );
+        assert_eq!(
+            hub_connect_addr(&url("tcp://192.168.1.2:7465")).unwrap(),
+            "192.168.1.2:7465".parse().unwrap()
+        );
+        assert!(hub_connect_addr(&url("tcp://localhost")).is_err());
+    }
+}
@@ -1,13 +1,21 @@
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
+
 pub use ya_core_model::net::{
     from, NetApiError, NetDst, NetSrc, RemoteEndpoint, TryRemoteEndpoint,
 };
 
-pub use config::{Config, NetType};
+pub use config::{Config, LanRole, NetType};
 pub use service::{bind_broadcast_with_caller, broadcast, Net};
 
 mod bcast;
 pub mod central;
+mod db;
+pub mod hedge;
 pub mod hybrid;
+mod lan;
 mod service;
 
 mod cli;
@@ -2,6 +2,7 @@ use std::sync::{Arc, RwLock};
 
 use ya_core_model::net::local::{BindBroadcastError, BroadcastMessage, SendBroadcastMessage};
 use ya_core_model::{identity, NodeId};
+use ya_persistence::executor::DbExecutor;
 use ya_service_api_interfaces::{Provider, Service};
 use ya_service_bus::{Error, RpcEndpoint, RpcMessage};
 
@@ -41,26 +42,34 @@ impl Service for Net {
 }
 
 impl Net {
-    pub async fn gsb<Context>(ctx: Context) -> anyhow::Result<()> {
+    pub async fn gsb<Context: Provider<Self, DbExecutor>>(ctx: &Context) -> anyhow::Result<()> {
         let config = Config::from_env()?;
 
         {
             (*NET_TYPE.write().unwrap()) = config.net_type;
         }
+        crate::hedge::configure(&config.hedged_rpcs)?;
 
         match &config.net_type {
             NetType::Central => {
                 crate::central::cli::bind_service();
                 crate::central::Net::gsb(ctx, config).await
             }
-            NetType::Hybrid => crate::hybrid::Net::gsb(ctx, config).await,
+            NetType::Hybrid => {
+                let db: DbExecutor = ctx.component();
+                crate::hybrid::Net::gsb(ctx, config, Some(db)).await
+            }
+            NetType::Lan => {
+                crate::central::cli::bind_service();
+                crate::lan::Net::gsb(ctx, config).await
+            }
         }
     }
 
     pub fn rest<CONTEXT: Provider<Self, ()>>(_: &CONTEXT) -> actix_web::Scope {
         let net_type = { *NET_TYPE.read().unwrap() };
         match net_type {
-            NetType::Central => crate::central::web_scope(),
+            NetType::Central | NetType::Lan => crate::central::web_scope(),
             NetType::Hybrid => crate::hybrid::web_scope(),
         }
     }
@@ -75,6 +84,7 @@ impl Net {
         match &config.net_type {
             NetType::Central => Ok(()),
             NetType::Hybrid => crate::hybrid::Net::shutdown().await,
+            NetType::Lan => crate::lan::Net::shutdown().await,
         }
     }
 }
@@ -97,7 +107,7 @@ where
 {
     let net_type = { *NET_TYPE.read().unwrap() };
     match net_type {
-        NetType::Central => crate::central::broadcast(caller, message).await,
+        NetType::Central | NetType::Lan => crate::central::broadcast(caller, message).await,
         NetType::Hybrid => crate::hybrid::broadcast(caller, message).await,
     }
 }
@@ -120,7 +130,7 @@ where
 {
     let net_type = { *NET_TYPE.read().unwrap() };
     match net_type {
-        NetType::Central => {
+        NetType::Central | NetType::Lan => {
             crate::central::bind_broadcast_with_caller(broadcast_address, handler).await
         }
         NetType::Hybrid => {
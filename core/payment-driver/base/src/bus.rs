@@ -5,6 +5,7 @@
 */
 
 // External crates
+use bigdecimal::BigDecimal;
 use std::sync::Arc;
 
 use ya_client_model::payment::DriverStatusProperty;
@@ -47,6 +48,18 @@ pub async fn bind_service<Driver: PaymentDriver + 'static>(
         .bind_with_processor(
             move |_, dr, c, m| async move { dr.exit( c, m).await }
         )
+        .bind_with_processor(
+            move |_, dr, c, m| async move { dr.estimate_withdraw( c, m).await }
+        )
+        .bind_with_processor(
+            move |_, dr, c, m| async move { dr.withdraw( c, m).await }
+        )
+        .bind_with_processor(
+            move |_, dr, c, m| async move { dr.get_pending_transactions( c, m).await }
+        )
+        .bind_with_processor(
+            move |_, dr, c, m| async move { dr.bump_transaction( c, m).await }
+        )
         .bind_with_processor(
             move |_, dr, c, m| async move { dr.fund( c, m).await }
         )
@@ -105,6 +118,7 @@ pub async fn bind_service<Driver: PaymentDriver + 'static>(
             networks: driver.get_networks(),
             recv_init_required: driver.recv_init_required(),
         },
+        capabilities: driver.get_capabilities(),
     };
     service(payment_srv::BUS_ID).send(message).await?.unwrap(); // Unwrap on purpose because it's NoError
     log::debug!("Successfully registered driver in payment service.");
@@ -179,6 +193,7 @@ pub async fn notify_payment(
     order_ids: Vec<String>,
     details: &PaymentDetails,
     confirmation: Vec<u8>,
+    gas_cost: Option<BigDecimal>,
 ) -> Result<(), GenericError> {
     let msg = payment_srv::NotifyPayment {
         driver: driver_name.to_string(),
@@ -188,6 +203,7 @@ pub async fn notify_payment(
         recipient: details.recipient.clone(),
         order_ids,
         confirmation: PaymentConfirmation { confirmation },
+        gas_cost,
     };
     service(payment_srv::BUS_ID)
         .send(msg)
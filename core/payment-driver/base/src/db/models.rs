@@ -171,14 +171,9 @@ where
     DB: Backend,
 {
     fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
-        Ok(match i32::from_sql(bytes)? {
-            1 => Network::Mainnet,
-            4 => Network::Rinkeby,
-            5 => Network::Goerli,
-            137 => Network::Polygon,
-            17000 => Network::Holesky,
-            80001 => Network::Mumbai,
-            _ => return Err(anyhow::anyhow!("invalid value").into()),
-        })
+        // Stored as chain id
+        let chain_id = i32::from_sql(bytes)?;
+        Network::from_i32(chain_id)
+            .ok_or_else(|| anyhow::anyhow!("invalid chain id: {chain_id}").into())
     }
 }
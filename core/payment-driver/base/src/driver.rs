@@ -22,6 +22,7 @@ pub use ya_client_model::payment::network::Network;
 pub use ya_client_model::NodeId;
 pub use ya_core_model::identity::event::IdentityEvent;
 pub use ya_core_model::identity::Error as IdentityError;
+pub use ya_core_model::payment::local::DriverCapabilities;
 use ya_core_model::signable::{prepare_signature_hash, Signable};
 
 #[async_trait(?Send)]
@@ -45,11 +46,45 @@ pub trait PaymentDriver {
 
     async fn exit(&self, caller: String, msg: Exit) -> Result<String, GenericError>;
 
+    async fn estimate_withdraw(
+        &self,
+        caller: String,
+        msg: EstimateWithdraw,
+    ) -> Result<WithdrawEstimate, GenericError>;
+
+    async fn withdraw(&self, caller: String, msg: Withdraw) -> Result<String, GenericError>;
+
+    async fn get_pending_transactions(
+        &self,
+        _caller: String,
+        _msg: GetPendingTransactions,
+    ) -> Result<PendingTransactions, GenericError> {
+        Err(GenericError::new(format!(
+            "Driver {} doesn't manage transactions",
+            self.get_name()
+        )))
+    }
+
+    async fn bump_transaction(
+        &self,
+        _caller: String,
+        _msg: BumpTransaction,
+    ) -> Result<PendingTransaction, GenericError> {
+        Err(GenericError::new(format!(
+            "Driver {} doesn't manage transactions",
+            self.get_name()
+        )))
+    }
+
     // used by bus to bind service
     fn get_name(&self) -> String;
     fn get_default_network(&self) -> String;
     fn get_networks(&self) -> HashMap<String, Network>;
     fn recv_init_required(&self) -> bool;
+    /// Optional features advertised to clients. None by default.
+    fn get_capabilities(&self) -> DriverCapabilities {
+        DriverCapabilities::default()
+    }
 
     /// There is no guarentee that this method will be called only once
     /// AccountMode in Init message should be incremental i.e. :
@@ -43,6 +43,7 @@ pub async fn register_in_payment_service() -> anyhow::Result<()> {
     let message = payment_srv::RegisterDriver {
         driver_name: DRIVER_NAME.to_string(),
         details,
+        capabilities: Default::default(),
     };
     service(payment_srv::BUS_ID).send(message).await?.unwrap(); // Unwrap on purpose because it's NoError
     log::debug!("Successfully registered driver in payment service.");
@@ -106,6 +107,7 @@ async fn schedule_payment(
         recipient: details.recipient,
         order_ids: vec![order_id.clone()],
         confirmation: PaymentConfirmation { confirmation },
+        gas_cost: None,
     };
 
     // Spawned because calling payment service while handling a call from payment service
@@ -15,8 +15,6 @@ use ya_payment_driver::{
     utils,
 };
 
-use crate::network::platform_to_network_token;
-
 pub struct Erc20Dao {
     db: DbExecutor,
 }
@@ -60,11 +58,11 @@ impl Erc20Dao {
         &self,
         order_id: &str,
         msg: &SchedulePayment,
+        network: Network,
     ) -> Result<(), GenericError> {
         let recipient = msg.recipient().to_owned();
         let glm_amount = utils::big_dec_to_u256(&msg.amount());
         let gas_amount = Default::default();
-        let (network, _token) = platform_to_network_token(msg.platform())?;
 
         let payment = PaymentEntity {
             amount: utils::u256_to_big_endian_hex(glm_amount),
@@ -25,42 +25,90 @@ use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::mpsc::Receiver;
 use uuid::Uuid;
-use web3::types::{Address, H256};
+use web3::types::{Address, H256, U64};
 use ya_client_model::payment::allocation::Deposit;
 use ya_client_model::payment::DriverStatusProperty;
-use ya_payment_driver::db::models::Network;
 use ya_payment_driver::driver::IdentityError;
 
 use ya_payment_driver::{
     bus,
-    driver::{async_trait, BigDecimal, IdentityEvent, Network as NetworkConfig, PaymentDriver},
+    driver::{
+        async_trait, BigDecimal, DriverCapabilities, IdentityEvent, Network as NetworkConfig,
+        PaymentDriver,
+    },
     model::*,
 };
 
 // Local uses
-use crate::erc20::utils;
+use crate::erc20::gasless::RelayError;
 use crate::erc20::utils::{big_dec_to_u256, u256_to_big_dec};
-use crate::network::platform_to_currency;
+use crate::erc20::{ethereum, gasless, utils};
+use crate::network::{NetworkDefinition, Networks};
 use crate::signer::IdentitySigner;
-use crate::{driver::PaymentDetails, network, HOLESKY_NETWORK};
-use crate::{network::SUPPORTED_NETWORKS, DRIVER_NAME};
+use crate::{driver::PaymentDetails, DRIVER_NAME};
+use relayed::GaslessOrder;
 
 mod cli;
+pub(crate) mod relayed;
+mod transactions;
+
+/// Gas limit of a token transfer to an account that doesn't hold any tokens yet
+const WITHDRAW_GAS_LIMIT: u64 = 60_000;
+
+/// Interval of checking, whether pending transactions aren't blocked by a nonce gap
+const NONCE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Interval of confirming, or submitting again, orders handed over to gasless relayer
+const GASLESS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Upper bound of the withdrawal fee in the native currency, for gas priced at
+/// `max_fee_per_gas` wei.
+fn withdraw_max_fee(max_fee_per_gas: U256) -> Result<BigDecimal, GenericError> {
+    u256_to_big_dec(max_fee_per_gas * U256::from(WITHDRAW_GAS_LIMIT))
+}
+
+/// Unknown gas balance is treated as insufficient.
+fn has_sufficient_gas(gas_details: Option<&GasDetails>, max_fee: &BigDecimal) -> bool {
+    gas_details
+        .map(|gas| &gas.balance >= max_fee)
+        .unwrap_or(false)
+}
 
 pub struct Erc20Driver {
     payment_runtime: PaymentRuntime,
+    networks: Networks,
+    /// Serializes allocation of meta-transaction nonces.
+    gasless_lock: tokio::sync::Mutex<()>,
 }
 
 impl Erc20Driver {
-    pub fn new(payment_runtime: PaymentRuntime, recv: Receiver<DriverEvent>) -> Arc<Self> {
-        let this = Arc::new(Self { payment_runtime });
+    pub fn new(
+        payment_runtime: PaymentRuntime,
+        networks: Networks,
+        recv: Receiver<DriverEvent>,
+    ) -> Arc<Self> {
+        let this = Arc::new(Self {
+            payment_runtime,
+            networks,
+            gasless_lock: Default::default(),
+        });
 
         let this_ = Arc::clone(&this);
         tokio::task::spawn_local(Self::payment_confirm_job(this_, recv));
 
+        let this_ = Arc::clone(&this);
+        tokio::task::spawn_local(Self::nonce_check_job(this_));
+
+        let this_ = Arc::clone(&this);
+        tokio::task::spawn_local(Self::gasless_check_job(this_));
+
         this
     }
 
+    pub fn networks(&self) -> &Networks {
+        &self.networks
+    }
+
     pub async fn load_active_accounts(&self) {
         log::debug!("load_active_accounts");
         let unlocked_accounts = bus::list_unlocked_identities().await.unwrap();
@@ -124,6 +172,31 @@ impl Erc20Driver {
         deadline: Option<DateTime<Utc>>,
         deposit_id: Option<Deposit>,
     ) -> Result<String, GenericError> {
+        let payment_id = Uuid::new_v4().to_simple().to_string();
+        self.insert_transfer(
+            payment_id.clone(),
+            sender,
+            to,
+            amount,
+            network,
+            deadline,
+            deposit_id,
+        )
+        .await?;
+        Ok(payment_id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_transfer(
+        &self,
+        payment_id: String,
+        sender: &str,
+        to: &str,
+        amount: &BigDecimal,
+        network: &str,
+        deadline: Option<DateTime<Utc>>,
+        deposit_id: Option<Deposit>,
+    ) -> Result<(), GenericError> {
         self.is_account_active(sender).await?;
         let sender = H160::from_str(sender)
             .map_err(|err| GenericError::new(format!("Error when parsing sender {err:?}")))?;
@@ -131,8 +204,6 @@ impl Erc20Driver {
             .map_err(|err| GenericError::new(format!("Error when parsing receiver {err:?}")))?;
         let amount = big_dec_to_u256(amount)?;
 
-        let payment_id = Uuid::new_v4().to_simple().to_string();
-
         let deposit_id = if let Some(deposit) = deposit_id {
             Some(DepositId {
                 deposit_id: U256::from_str(&deposit.id).map_err(|err| {
@@ -155,19 +226,321 @@ impl Erc20Driver {
                 receiver,
                 tx_type: TransferType::Token,
                 amount,
-                payment_id: payment_id.clone(),
+                payment_id,
                 deadline,
                 deposit_id,
             })
             .await
             .map_err(|err| GenericError::new(format!("Error when inserting transfer {err:?}")))?;
 
-        Ok(payment_id)
+        Ok(())
+    }
+
+    /// Transfer handed over to the relayer, which pays for gas. Order is stored before
+    /// the relayer is called and confirmed by `gasless_check_job` once mined. Fails only
+    /// if nothing was relayed, so the payment can be sent regularly instead.
+    async fn do_gasless_transfer(
+        &self,
+        sender: &str,
+        to: &str,
+        amount: &BigDecimal,
+        network: &NetworkDefinition,
+        deadline: Option<DateTime<Utc>>,
+    ) -> Result<String, GenericError> {
+        let settings = network.gasless.as_ref().ok_or_else(|| {
+            GenericError::new(format!(
+                "Network {} doesn't support gasless transfers",
+                network.name
+            ))
+        })?;
+        self.is_account_active(sender).await?;
+        let sender_addr = H160::from_str(sender)
+            .map_err(|err| GenericError::new(format!("Error when parsing sender {err:?}")))?;
+        let receiver = H160::from_str(to)
+            .map_err(|err| GenericError::new(format!("Error when parsing receiver {err:?}")))?;
+        let db_network = network.db_network()?;
+        let conn = &self.payment_runtime.conn;
+        let payment_id = Uuid::new_v4().to_simple().to_string();
+
+        let order = {
+            let _lock = self.gasless_lock.lock().await;
+            let sender = format!("{sender_addr:#x}");
+            let on_chain = ethereum::get_nonce_from_contract(sender_addr, db_network)
+                .await?
+                .as_u64() as i64;
+            let nonce = match relayed::next_nonce(conn, network.chain_id, &sender).await? {
+                Some(pending) => pending.max(on_chain),
+                None => on_chain,
+            };
+            let request = gasless::sign_transfer(
+                settings,
+                db_network,
+                sender_addr,
+                receiver,
+                big_dec_to_u256(amount)?,
+                U256::from(nonce),
+            )
+            .await?;
+            let order = GaslessOrder {
+                payment_id: payment_id.clone(),
+                chain_id: network.chain_id,
+                sender,
+                recipient: format!("{receiver:#x}"),
+                amount: amount.clone(),
+                deadline,
+                nonce,
+                next_nonce: nonce + request.transactions() as i64,
+                request,
+                tx_hash: None,
+                created_date: Utc::now(),
+            };
+            relayed::insert(conn, &order).await?;
+            order
+        };
+
+        match gasless::submit(&settings.relayer_url, &order.request).await {
+            Ok(tx_hash) => {
+                log::info!("Gasless payment {payment_id} relayed in transaction {tx_hash:#x}");
+                if let Err(e) = relayed::relayed(conn, &payment_id, tx_hash).await {
+                    log::warn!("Failed to store transaction of gasless payment {payment_id}: {e}");
+                }
+                Ok(payment_id)
+            }
+            Err(RelayError::Rejected(e)) => match relayed::fail(conn, &payment_id, &e).await {
+                Ok(()) => Err(GenericError::new(RelayError::Rejected(e))),
+                // Left to `gasless_check_job`, so the payment isn't sent twice.
+                Err(db_err) => {
+                    log::warn!(
+                        "Failed to store rejection of gasless payment {payment_id}: {db_err}"
+                    );
+                    Ok(payment_id)
+                }
+            },
+            Err(e) => {
+                log::warn!("Gasless payment {payment_id} will be submitted again: {e}");
+                Ok(payment_id)
+            }
+        }
+    }
+
+    async fn gasless_check_job(this: Arc<Self>) {
+        let mut interval = tokio::time::interval(GASLESS_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let orders = match relayed::pending(&this.payment_runtime.conn).await {
+                Ok(orders) => orders,
+                Err(e) => {
+                    log::warn!("Failed to check gasless payments: {e}");
+                    continue;
+                }
+            };
+            for order in orders {
+                if let Err(e) = this.check_gasless_order(&order).await {
+                    log::warn!("Gasless payment {}: {e}", order.payment_id);
+                }
+            }
+        }
+    }
+
+    /// Confirms relayed payment once mined with required confirmations, otherwise
+    /// submits it again. Payment, which the relayer won't execute and nobody else did,
+    /// is sent regularly under the same payment id.
+    async fn check_gasless_order(&self, order: &GaslessOrder) -> Result<(), GenericError> {
+        let network = self.networks.by_chain_id(order.chain_id)?;
+        let db_network = network.db_network()?;
+        let conn = &self.payment_runtime.conn;
+
+        let mut reverted = None;
+        if let Some(tx_hash) = order.tx_hash {
+            if let Some(receipt) = ethereum::get_tx_receipt(tx_hash, db_network).await? {
+                if receipt.status != Some(U64::from(1)) {
+                    reverted = Some(tx_hash);
+                } else {
+                    let mined = receipt.block_number.unwrap_or_default();
+                    let confirmations = ethereum::block_number(db_network)
+                        .await?
+                        .saturating_sub(mined)
+                        .as_u64()
+                        + 1;
+                    if confirmations < network.confirmations {
+                        return Ok(());
+                    }
+                    return self.confirm_gasless(order, network, tx_hash).await;
+                }
+            }
+        }
+
+        let settings = network.gasless.as_ref().ok_or_else(|| {
+            GenericError::new(format!(
+                "No gasless relayer configured for {}",
+                network.name
+            ))
+        })?;
+        let reason = match gasless::submit(&settings.relayer_url, &order.request).await {
+            Ok(tx_hash) if Some(tx_hash) != reverted => {
+                if order.tx_hash != Some(tx_hash) {
+                    log::info!(
+                        "Gasless payment {} relayed in transaction {tx_hash:#x}",
+                        order.payment_id
+                    );
+                }
+                return relayed::relayed(conn, &order.payment_id, tx_hash).await;
+            }
+            Ok(tx_hash) => format!("relayed transaction {tx_hash:#x} reverted"),
+            Err(RelayError::Rejected(e)) => e,
+            Err(e) => return Err(GenericError::new(e)),
+        };
+
+        // Meta-transaction nonce still unused means the transfer wasn't executed.
+        let sender = H160::from_str(&order.sender).map_err(GenericError::new)?;
+        let next_nonce = ethereum::get_nonce_from_contract(sender, db_network).await?;
+        // Marked failed first, so the payment can't be sent twice.
+        relayed::fail(conn, &order.payment_id, &reason).await?;
+        if next_nonce > U256::from(order.nonce) {
+            log::error!(
+                "Gasless payment {} was executed in an unknown transaction, check payment to {} manually: {reason}",
+                order.payment_id,
+                order.recipient
+            );
+            return Ok(());
+        }
+
+        log::warn!(
+            "Gasless payment {} not relayed, sending it regularly: {reason}",
+            order.payment_id
+        );
+        if let Err(e) = self
+            .insert_transfer(
+                order.payment_id.clone(),
+                &order.sender,
+                &order.recipient,
+                &order.amount,
+                &network.name,
+                order.deadline,
+                None,
+            )
+            .await
+        {
+            log::error!("Gasless payment {} won't be sent: {e}", order.payment_id);
+        }
+        Ok(())
+    }
+
+    async fn confirm_gasless(
+        &self,
+        order: &GaslessOrder,
+        network: &NetworkDefinition,
+        tx_hash: H256,
+    ) -> Result<(), GenericError> {
+        let details = PaymentDetails {
+            recipient: order.recipient.clone(),
+            sender: order.sender.clone(),
+            amount: order.amount.clone(),
+            date: Some(order.created_date),
+        };
+        // Gas is paid by the relayer.
+        bus::notify_payment(
+            &self.get_name(),
+            &network.platform,
+            vec![order.payment_id.clone()],
+            &details,
+            tx_hash.as_bytes().to_vec(),
+            None,
+        )
+        .await?;
+        relayed::confirm(&self.payment_runtime.conn, &order.payment_id).await?;
+        log::info!(
+            "Gasless payment confirmed: {}, after {} block confirmations",
+            order.payment_id,
+            network.confirmations
+        );
+        Ok(())
+    }
+
+    /// Scheduled payments go through the relayer only if the sender can't pay for gas.
+    async fn prefers_gasless(&self, network: &NetworkDefinition, sender: &str) -> bool {
+        let Some(sender) = network
+            .gasless
+            .as_ref()
+            .and_then(|_| H160::from_str(sender).ok())
+        else {
+            return false;
+        };
+        match self
+            .payment_runtime
+            .get_token_balance(network.name.clone(), sender, None)
+            .await
+        {
+            Ok(balance) => balance.gas_balance.is_some_and(|gas| gas.is_zero()),
+            Err(e) => {
+                log::warn!("Unable to check gas balance of {sender:#x}: {e}");
+                false
+            }
+        }
+    }
+
+    async fn do_estimate_withdraw(
+        &self,
+        msg: EstimateWithdraw,
+    ) -> Result<WithdrawEstimate, GenericError> {
+        let network = self.networks.get(&msg.network)?;
+        let token = network.token.clone();
+        let platform = network.platform.clone();
+
+        let balance = self
+            .get_account_balance(
+                String::new(),
+                GetAccountBalance::new(msg.sender.clone(), platform),
+            )
+            .await?;
+
+        let amount = msg.amount.unwrap_or_else(|| balance.token_balance.clone());
+        if amount > balance.token_balance {
+            return Err(GenericError::new(format!(
+                "Insufficient funds: requested {} {}, available {} {}",
+                amount, token, balance.token_balance, token
+            )));
+        }
+
+        let chain_id = network.chain_id;
+        let max_fee_per_gas = self
+            .payment_runtime
+            .setup
+            .chain_setup
+            .get(&chain_id)
+            .map(|chain_cfg| chain_cfg.max_fee_per_gas)
+            .ok_or_else(|| {
+                GenericError::new(format!("Missing chain config for network {}", msg.network))
+            })?;
+        let max_fee = withdraw_max_fee(max_fee_per_gas)?;
+        let sufficient_gas = has_sufficient_gas(balance.gas_details.as_ref(), &max_fee);
+
+        Ok(WithdrawEstimate {
+            network: msg.network,
+            token,
+            sender: msg.sender,
+            to: msg.to,
+            amount,
+            token_balance: balance.token_balance,
+            gas_details: balance.gas_details,
+            max_fee,
+            sufficient_gas,
+        })
+    }
+
+    async fn nonce_check_job(this: Arc<Self>) {
+        let mut interval = tokio::time::interval(NONCE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            transactions::report_nonce_gaps(&this.payment_runtime).await;
+        }
     }
 
     async fn payment_confirm_job(this: Arc<Self>, mut events: Receiver<DriverEvent>) {
         while let Some(event) = events.recv().await {
             match &event.content {
+                // Emitted once the transaction is buried under the network's required
+                // confirmations, so the payment moves from sent to confirmed here.
                 DriverEventContent::TransferFinished(transfer_finished) => {
                     match this
                         .confirm_payments(
@@ -177,11 +550,16 @@ impl Erc20Driver {
                         .await
                     {
                         Ok(_) => log::info!(
-                            "Payment confirmed: {}",
+                            "Payment confirmed: {}, after {} block confirmations",
                             transfer_finished
                                 .token_transfer_dao
                                 .payment_id
                                 .clone()
+                                .unwrap_or_default(),
+                            this.payment_runtime
+                                .network_name(transfer_finished.token_transfer_dao.chain_id)
+                                .and_then(|name| this.networks.get(name).ok())
+                                .map(|network| network.confirmations)
                                 .unwrap_or_default()
                         ),
                         Err(e) => log::error!(
@@ -309,18 +687,7 @@ impl Erc20Driver {
             )))?
             .to_string();
 
-        let networks = self.get_networks();
-        let network = networks.get(network_name).ok_or(GenericError::new(format!(
-            "Network {network_name} not supported by Erc20Driver"
-        )))?;
-        let platform = network
-            .tokens
-            .get(&network.default_token)
-            .ok_or(GenericError::new(format!(
-                "Network {} doesn't specify platform for default token {}",
-                network_name, network.default_token
-            )))?
-            .as_str();
+        let platform = self.networks.get(network_name)?.platform.as_str();
 
         let Ok(tx_token_amount) = U256::from_dec_str(&token_transfer.token_amount) else {
             return Err(GenericError::new(format!(
@@ -364,12 +731,20 @@ impl Erc20Driver {
         let Some(payment_id) = &token_transfer.payment_id else {
             return Err(GenericError::new("token_transfer.payment_id is null"));
         };
+        // Fee of the whole, possibly batched, transaction. Payment service splits it
+        // between orders paid by the transaction.
+        let gas_cost = tx
+            .fee_paid
+            .as_ref()
+            .and_then(|fee| U256::from_dec_str(fee).ok())
+            .and_then(|fee| u256_to_big_dec(fee).ok());
         bus::notify_payment(
             &self.get_name(),
             platform,
             vec![payment_id.clone()],
             &payment_details,
             transaction_hash,
+            gas_cost,
         )
         .await
     }
@@ -591,7 +966,7 @@ impl Erc20Driver {
     async fn fund_eth_faucet(
         &self,
         faucet_setup: &FaucetSetup,
-        network: Network,
+        network: &str,
         starting_eth_balance: U256,
         address: H160,
     ) -> Result<U256, GenericError> {
@@ -746,6 +1121,44 @@ impl PaymentDriver for Erc20Driver {
         Ok("NOT_IMPLEMENTED".to_string())
     }
 
+    async fn estimate_withdraw(
+        &self,
+        _caller: String,
+        msg: EstimateWithdraw,
+    ) -> Result<WithdrawEstimate, GenericError> {
+        log::debug!("estimate_withdraw: {:?}", msg);
+        self.do_estimate_withdraw(msg).await
+    }
+
+    async fn withdraw(&self, _caller: String, msg: Withdraw) -> Result<String, GenericError> {
+        log::debug!("withdraw: {:?}", msg);
+        let estimate = self.do_estimate_withdraw(msg.into()).await?;
+        if !estimate.sufficient_gas {
+            return Err(GenericError::new(format!(
+                "Insufficient gas on network {}: up to {} required",
+                estimate.network, estimate.max_fee
+            )));
+        }
+
+        log::info!(
+            "Withdrawing {} {} from {} to {} on network {}",
+            estimate.amount,
+            estimate.token,
+            estimate.sender,
+            estimate.to,
+            estimate.network
+        );
+        self.do_transfer(
+            &estimate.sender,
+            &estimate.to,
+            &estimate.amount,
+            &estimate.network,
+            Some(Utc::now()),
+            None,
+        )
+        .await
+    }
+
     async fn get_rpc_endpoints(
         &self,
         _caller: String,
@@ -827,12 +1240,20 @@ impl PaymentDriver for Erc20Driver {
         let token_balance = u256_to_big_dec(token_balance).map_err(|e| {
             GenericError::new(format!("Error converting token balance to big int: {}", e))
         })?;
-        let (currency_short_name, currency_long_name) = platform_to_currency(platform)?;
+        let NetworkDefinition {
+            currency_short,
+            currency_long,
+            faucet_url,
+            bridge_url,
+            ..
+        } = self.networks.by_platform(&platform)?.clone();
         Ok(GetAccountBalanceResult {
             gas_details: Some(GasDetails {
-                currency_short_name,
-                currency_long_name,
+                currency_short_name: currency_short,
+                currency_long_name: currency_long,
                 balance: gas_balance,
+                faucet_url,
+                bridge_url,
             }),
             token_balance,
             block_number: balance.block_number,
@@ -840,22 +1261,49 @@ impl PaymentDriver for Erc20Driver {
         })
     }
 
+    async fn get_pending_transactions(
+        &self,
+        _caller: String,
+        msg: GetPendingTransactions,
+    ) -> Result<PendingTransactions, GenericError> {
+        transactions::pending(&self.payment_runtime, msg).await
+    }
+
+    async fn bump_transaction(
+        &self,
+        _caller: String,
+        msg: BumpTransaction,
+    ) -> Result<PendingTransaction, GenericError> {
+        log::debug!("bump_transaction: {:?}", msg);
+        transactions::bump(&self.payment_runtime, msg).await
+    }
+
     fn get_name(&self) -> String {
         DRIVER_NAME.to_string()
     }
 
     fn get_default_network(&self) -> String {
-        HOLESKY_NETWORK.to_string()
+        self.networks.default_network().name.clone()
     }
 
     fn get_networks(&self) -> HashMap<String, NetworkConfig> {
-        SUPPORTED_NETWORKS.clone()
+        self.networks.supported()
     }
 
     fn recv_init_required(&self) -> bool {
         false
     }
 
+    fn get_capabilities(&self) -> DriverCapabilities {
+        DriverCapabilities {
+            batching: true,
+            deposits: true,
+            gasless_platforms: self.networks.gasless(),
+            status_flags: true,
+            confirmations: self.networks.confirmations(),
+        }
+    }
+
     async fn init(&self, _caller: String, msg: Init) -> Result<Ack, GenericError> {
         cli::init(self, msg).await?;
         Ok(Ack {})
@@ -864,7 +1312,9 @@ impl PaymentDriver for Erc20Driver {
     async fn fund(&self, _caller: String, msg: Fund) -> Result<String, GenericError> {
         log::debug!("fund: {:?}", msg);
         let address = msg.address();
-        let network = network::network_like_to_network(msg.network());
+        let network = self.networks.network_like(msg.network());
+        let chain_id = network.chain_id;
+        let network = network.name.as_str();
         let result = {
             let address = utils::str_to_addr(&address)?;
             log::info!(
@@ -876,7 +1326,7 @@ impl PaymentDriver for Erc20Driver {
                 .payment_runtime
                 .setup
                 .chain_setup
-                .get(&(network as i64))
+                .get(&chain_id)
                 .ok_or(GenericError::new(format!(
                     "Missing chain config for network {}",
                     network
@@ -1078,6 +1528,13 @@ impl PaymentDriver for Erc20Driver {
             .network
             .ok_or(GenericError::new("Network not specified".to_string()))?;
 
+        if msg.gasless {
+            let network = self.networks.get(&network)?;
+            return self
+                .do_gasless_transfer(&msg.sender, &msg.to, &msg.amount, network, Some(Utc::now()))
+                .await;
+        }
+
         self.do_transfer(
             &msg.sender,
             &msg.to,
@@ -1104,6 +1561,30 @@ impl PaymentDriver for Erc20Driver {
 
         let transfer_margin = Duration::minutes(2);
 
+        // Deposit payments are executed by the lock contract.
+        let definition = self.networks.get(network)?;
+        if msg.gasless()
+            && msg.deposit_id().is_none()
+            && self.prefers_gasless(definition, &msg.sender()).await
+        {
+            match self
+                .do_gasless_transfer(
+                    &msg.sender(),
+                    &msg.recipient(),
+                    &msg.amount(),
+                    definition,
+                    Some(msg.due_date() - transfer_margin),
+                )
+                .await
+            {
+                Ok(payment_id) => return Ok(payment_id),
+                // Nothing was relayed. Regular transfer will wait until the sender gets gas.
+                Err(e) => {
+                    log::warn!("Gasless payment not relayed, scheduling regular transfer: {e}")
+                }
+            }
+        }
+
         self.do_transfer(
             &msg.sender(),
             &msg.recipient(),
@@ -1121,13 +1602,17 @@ impl PaymentDriver for Erc20Driver {
         msg: VerifyPayment,
     ) -> Result<PaymentDetails, GenericError> {
         log::debug!("verify_payment: {:?}", msg);
-        let (network, _) = network::platform_to_network_token(msg.platform())?;
+        let network = self.networks.by_platform(&msg.platform())?;
         let tx_hash = format!("0x{}", hex::encode(msg.confirmation().confirmation));
-        log::info!("Verifying transaction: {} on network {}", tx_hash, network);
+        log::info!(
+            "Verifying transaction: {} on network {}",
+            tx_hash,
+            network.name
+        );
         let verify_res = self
             .payment_runtime
             .verify_transaction(
-                network as i64,
+                network.chain_id,
                 H256::from_str(&tx_hash)
                     .map_err(|_| GenericError::new("Hash cannot be converted to string"))?,
                 H160::from_str(&msg.details.payer_addr)
@@ -1225,3 +1710,28 @@ impl PaymentDriver for Erc20Driver {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gas(balance: &str) -> GasDetails {
+        GasDetails {
+            balance: BigDecimal::from_str(balance).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn withdraw_fee_estimate() {
+        // 30 Gwei * 60 000 gas
+        let max_fee = withdraw_max_fee(U256::from(30_000_000_000u64)).unwrap();
+        assert_eq!(max_fee, BigDecimal::from_str("0.0018").unwrap());
+        assert_eq!(withdraw_max_fee(U256::zero()).unwrap(), BigDecimal::from(0));
+
+        assert!(has_sufficient_gas(Some(&gas("0.0018")), &max_fee));
+        assert!(has_sufficient_gas(Some(&gas("1")), &max_fee));
+        assert!(!has_sufficient_gas(Some(&gas("0.0017")), &max_fee));
+        assert!(!has_sufficient_gas(None, &max_fee));
+    }
+}
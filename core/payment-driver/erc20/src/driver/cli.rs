@@ -12,7 +12,7 @@ use ya_payment_driver::{
 };
 
 // Local uses
-use crate::{driver::Erc20Driver, network, DRIVER_NAME};
+use crate::{driver::Erc20Driver, DRIVER_NAME};
 
 pub async fn init(driver: &Erc20Driver, msg: Init) -> Result<(), GenericError> {
     log::debug!("init: {:?}", msg);
@@ -24,9 +24,10 @@ pub async fn init(driver: &Erc20Driver, msg: Init) -> Result<(), GenericError> {
         driver.is_account_active(&address).await?
     }
 
-    let network = network::network_like_to_network(msg.network());
-    let token = network::get_network_token(network, msg.token());
-    bus::register_account(driver, &msg.address(), &network.to_string(), &token, mode).await?;
+    let network = driver.networks().network_like(msg.network());
+    let token = network.token_or_default(msg.token());
+    let network = network.name.as_str();
+    bus::register_account(driver, &msg.address(), network, &token, mode).await?;
 
     log::info!(
         "Initialised payment account. mode={:?}, address={}, driver={}, network={}, token={}",
@@ -0,0 +1,259 @@
+/*
+    Gasless orders handed over to the relayer.
+
+    Orders are kept in `gasless_order` table of the payment runtime database. They are
+    stored before the relayer is called, so a transfer with unknown outcome is confirmed,
+    submitted again or paid regularly even after a restart.
+
+    Meta-transaction nonces are allocated here, so orders of the same sender, which
+    aren't executed yet, don't sign the same nonce.
+*/
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row, SqlitePool};
+use std::str::FromStr;
+use web3::types::H256;
+
+use ya_payment_driver::driver::BigDecimal;
+use ya_payment_driver::model::GenericError;
+
+use crate::erc20::gasless::RelayRequest;
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS gasless_order (\
+    payment_id TEXT NOT NULL PRIMARY KEY, \
+    chain_id INTEGER NOT NULL, \
+    sender TEXT NOT NULL, \
+    recipient TEXT NOT NULL, \
+    amount TEXT NOT NULL, \
+    deadline DATETIME NULL, \
+    nonce INTEGER NOT NULL, \
+    next_nonce INTEGER NOT NULL, \
+    request TEXT NOT NULL, \
+    tx_hash TEXT NULL, \
+    created_date DATETIME NOT NULL, \
+    confirm_date DATETIME NULL, \
+    error TEXT NULL)";
+
+const INSERT_ORDER: &str = "INSERT INTO gasless_order \
+    (payment_id, chain_id, sender, recipient, amount, deadline, nonce, next_nonce, request, \
+    created_date) \
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)";
+
+const PENDING_ORDERS: &str = "SELECT * FROM gasless_order \
+    WHERE confirm_date IS NULL AND error IS NULL \
+    ORDER BY created_date";
+
+const NEXT_NONCE: &str = "SELECT MAX(next_nonce) FROM gasless_order \
+    WHERE chain_id = $1 AND sender = $2 AND confirm_date IS NULL AND error IS NULL";
+
+const SET_TX_HASH: &str = "UPDATE gasless_order SET tx_hash = $2 \
+    WHERE payment_id = $1 AND confirm_date IS NULL";
+
+const CONFIRM_ORDER: &str = "UPDATE gasless_order SET confirm_date = $2 \
+    WHERE payment_id = $1";
+
+const FAIL_ORDER: &str = "UPDATE gasless_order SET error = $2 \
+    WHERE payment_id = $1 AND confirm_date IS NULL";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaslessOrder {
+    pub payment_id: String,
+    pub chain_id: i64,
+    pub sender: String,
+    pub recipient: String,
+    pub amount: BigDecimal,
+    /// Deadline of the regular transfer, if the order falls back to one.
+    pub deadline: Option<DateTime<Utc>>,
+    /// Meta-transaction nonce of the transfer.
+    pub nonce: i64,
+    /// First nonce not used by the order, its relayer fee included.
+    pub next_nonce: i64,
+    pub request: RelayRequest,
+    /// Latest transaction reported by the relayer.
+    pub tx_hash: Option<H256>,
+    pub created_date: DateTime<Utc>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for GaslessOrder {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let decode = |column: &str, e: String| sqlx::Error::ColumnDecode {
+            index: column.to_string(),
+            source: e.into(),
+        };
+        Ok(GaslessOrder {
+            payment_id: row.try_get("payment_id")?,
+            chain_id: row.try_get("chain_id")?,
+            sender: row.try_get("sender")?,
+            recipient: row.try_get("recipient")?,
+            amount: BigDecimal::from_str(&row.try_get::<String, _>("amount")?)
+                .map_err(|e| decode("amount", e.to_string()))?,
+            deadline: row.try_get("deadline")?,
+            nonce: row.try_get("nonce")?,
+            next_nonce: row.try_get("next_nonce")?,
+            request: serde_json::from_str(&row.try_get::<String, _>("request")?)
+                .map_err(|e| decode("request", e.to_string()))?,
+            tx_hash: row
+                .try_get::<Option<String>, _>("tx_hash")?
+                .map(|tx_hash| H256::from_str(&tx_hash))
+                .transpose()
+                .map_err(|e| decode("tx_hash", e.to_string()))?,
+            created_date: row.try_get("created_date")?,
+        })
+    }
+}
+
+pub async fn init(conn: &SqlitePool) -> Result<(), GenericError> {
+    sqlx::query(CREATE_TABLE)
+        .execute(conn)
+        .await
+        .map_err(GenericError::new)?;
+    Ok(())
+}
+
+pub async fn insert(conn: &SqlitePool, order: &GaslessOrder) -> Result<(), GenericError> {
+    sqlx::query(INSERT_ORDER)
+        .bind(&order.payment_id)
+        .bind(order.chain_id)
+        .bind(&order.sender)
+        .bind(&order.recipient)
+        .bind(order.amount.to_string())
+        .bind(order.deadline)
+        .bind(order.nonce)
+        .bind(order.next_nonce)
+        .bind(serde_json::to_string(&order.request).map_err(GenericError::new)?)
+        .bind(order.created_date)
+        .execute(conn)
+        .await
+        .map_err(GenericError::new)?;
+    Ok(())
+}
+
+/// Orders neither confirmed nor failed, oldest first.
+pub async fn pending(conn: &SqlitePool) -> Result<Vec<GaslessOrder>, GenericError> {
+    sqlx::query_as::<_, GaslessOrder>(PENDING_ORDERS)
+        .fetch_all(conn)
+        .await
+        .map_err(GenericError::new)
+}
+
+/// First nonce not used by pending orders of `sender`.
+pub async fn next_nonce(
+    conn: &SqlitePool,
+    chain_id: i64,
+    sender: &str,
+) -> Result<Option<i64>, GenericError> {
+    sqlx::query_scalar::<_, Option<i64>>(NEXT_NONCE)
+        .bind(chain_id)
+        .bind(sender)
+        .fetch_one(conn)
+        .await
+        .map_err(GenericError::new)
+}
+
+pub async fn relayed(
+    conn: &SqlitePool,
+    payment_id: &str,
+    tx_hash: H256,
+) -> Result<(), GenericError> {
+    sqlx::query(SET_TX_HASH)
+        .bind(payment_id)
+        .bind(format!("{tx_hash:#x}"))
+        .execute(conn)
+        .await
+        .map_err(GenericError::new)?;
+    Ok(())
+}
+
+pub async fn confirm(conn: &SqlitePool, payment_id: &str) -> Result<(), GenericError> {
+    sqlx::query(CONFIRM_ORDER)
+        .bind(payment_id)
+        .bind(Utc::now())
+        .execute(conn)
+        .await
+        .map_err(GenericError::new)?;
+    Ok(())
+}
+
+/// Order won't be relayed anymore.
+pub async fn fail(conn: &SqlitePool, payment_id: &str, error: &str) -> Result<(), GenericError> {
+    sqlx::query(FAIL_ORDER)
+        .bind(payment_id)
+        .bind(error)
+        .execute(conn)
+        .await
+        .map_err(GenericError::new)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn db() -> SqlitePool {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        init(&conn).await.unwrap();
+        conn
+    }
+
+    fn order(payment_id: &str, nonce: i64) -> GaslessOrder {
+        let request = serde_json::from_value(serde_json::json!({
+            "chainId": 137,
+            "sender": "0x0000000000000000000000000000000000000001",
+            "metaTransactions": [],
+        }))
+        .unwrap();
+        GaslessOrder {
+            payment_id: payment_id.to_string(),
+            chain_id: 137,
+            sender: "0x0000000000000000000000000000000000000001".to_string(),
+            recipient: "0x0000000000000000000000000000000000000002".to_string(),
+            amount: BigDecimal::from_str("1.5").unwrap(),
+            deadline: None,
+            nonce,
+            next_nonce: nonce + 2,
+            request,
+            tx_hash: None,
+            created_date: Utc::now(),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn pending_orders_reserve_nonces() {
+        let conn = db().await;
+        let sender = "0x0000000000000000000000000000000000000001";
+        assert_eq!(next_nonce(&conn, 137, sender).await.unwrap(), None);
+
+        insert(&conn, &order("first", 3)).await.unwrap();
+        insert(&conn, &order("second", 5)).await.unwrap();
+        assert_eq!(next_nonce(&conn, 137, sender).await.unwrap(), Some(7));
+        assert_eq!(next_nonce(&conn, 1, sender).await.unwrap(), None);
+
+        fail(&conn, "second", "rejected").await.unwrap();
+        assert_eq!(next_nonce(&conn, 137, sender).await.unwrap(), Some(5));
+    }
+
+    #[actix_rt::test]
+    async fn orders_survive_until_confirmed() {
+        let conn = db().await;
+        let first = order("first", 0);
+        insert(&conn, &first).await.unwrap();
+        insert(&conn, &order("second", 2)).await.unwrap();
+
+        let tx_hash = H256::repeat_byte(1);
+        relayed(&conn, "first", tx_hash).await.unwrap();
+        let orders = pending(&conn).await.unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].tx_hash, Some(tx_hash));
+        assert_eq!(orders[0].amount, first.amount);
+        assert_eq!(orders[0].request, first.request);
+
+        confirm(&conn, "first").await.unwrap();
+        fail(&conn, "second", "rejected").await.unwrap();
+        assert!(pending(&conn).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+/*
+    Inspection and rescue of transactions sent by erc20_payment_lib.
+
+    Transactions are kept in `tx` table of the payment runtime database. Bumped transaction
+    is reset to unsigned state, so the runtime signs it again with the same nonce and
+    broadcasts it as a replacement of the stuck one.
+
+    The runtime updates the same rows, so reset only succeeds if the transaction is still
+    in the state it was inspected in. Transactions, which the runtime is resending, are
+    left alone.
+*/
+use erc20_payment_lib::model::TxDbObj;
+use erc20_payment_lib::runtime::PaymentRuntime;
+use ethereum_types::{H160, U256};
+use num_bigint::BigInt;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use ya_payment_driver::driver::BigDecimal;
+use ya_payment_driver::model::{
+    BumpTransaction, GenericError, GetPendingTransactions, NonceGap, PendingTransaction,
+    PendingTransactions,
+};
+
+use crate::erc20::ethereum;
+use crate::network::db_network;
+
+/// Nodes reject replacement transactions raising gas price less than 10%.
+pub const MIN_GAS_INCREASE: u32 = 10;
+
+const PENDING_TRANSACTIONS: &str = "SELECT * FROM tx \
+    WHERE confirm_date IS NULL AND processing > 0 \
+    ORDER BY chain_id, from_addr, nonce";
+
+const GET_TRANSACTION: &str = "SELECT * FROM tx WHERE id = $1";
+
+const RESET_TRANSACTION: &str = "UPDATE tx SET \
+    max_fee_per_gas = $2, priority_fee = $3, \
+    signed_raw_data = NULL, signed_date = NULL, tx_hash = NULL, \
+    broadcast_date = NULL, broadcast_count = 0, first_stuck_date = NULL, \
+    engine_message = $4 \
+    WHERE id = $1 AND confirm_date IS NULL AND processing > 0 \
+    AND tx_hash = $5 AND broadcast_date = $6 AND broadcast_count = $7";
+
+pub async fn pending(
+    runtime: &PaymentRuntime,
+    msg: GetPendingTransactions,
+) -> Result<PendingTransactions, GenericError> {
+    let transactions = sqlx::query_as::<_, TxDbObj>(PENDING_TRANSACTIONS)
+        .fetch_all(&runtime.conn)
+        .await
+        .map_err(GenericError::new)?
+        .iter()
+        .filter_map(|tx| Some((tx.chain_id, to_pending(runtime, tx)?)))
+        .filter(|(_, tx)| {
+            msg.network.as_ref().map_or(true, |n| n == &tx.network)
+                && msg
+                    .sender
+                    .as_ref()
+                    .map_or(true, |s| s.eq_ignore_ascii_case(&tx.sender))
+        })
+        .collect::<Vec<_>>();
+    let nonce_gaps = nonce_gaps(&transactions).await;
+
+    Ok(PendingTransactions {
+        transactions: transactions.into_iter().map(|(_, tx)| tx).collect(),
+        nonce_gaps,
+    })
+}
+
+pub async fn bump(
+    runtime: &PaymentRuntime,
+    msg: BumpTransaction,
+) -> Result<PendingTransaction, GenericError> {
+    if msg.gas_increase < MIN_GAS_INCREASE {
+        return Err(GenericError::new(format!(
+            "Gas price must be increased by at least {}%",
+            MIN_GAS_INCREASE
+        )));
+    }
+
+    let tx = get(runtime, msg.id).await?;
+    if tx.confirm_date.is_some() || tx.processing == 0 {
+        return Err(GenericError::new(format!(
+            "Transaction {} is already processed",
+            tx.id
+        )));
+    }
+    let (tx_hash, broadcast_date) = match (tx.nonce, &tx.tx_hash, tx.broadcast_date) {
+        (Some(_), Some(tx_hash), Some(broadcast_date)) => (tx_hash.clone(), broadcast_date),
+        _ => {
+            return Err(GenericError::new(format!(
+                "Transaction {} wasn't broadcast yet",
+                tx.id
+            )))
+        }
+    };
+    // Signed after the last broadcast means the runtime is sending it again right now.
+    if matches!(tx.signed_date, Some(signed_date) if signed_date > broadcast_date) {
+        return Err(GenericError::new(format!(
+            "Transaction {} is being resent, try again later",
+            tx.id
+        )));
+    }
+
+    let max_fee_per_gas = increase(tx.max_fee_per_gas.as_deref(), msg.gas_increase)?;
+    let priority_fee = increase(tx.priority_fee.as_deref(), msg.gas_increase)?;
+    let message = format!(
+        "Replacing {} with gas price increased by {}%",
+        tx_hash, msg.gas_increase
+    );
+
+    let reset = sqlx::query(RESET_TRANSACTION)
+        .bind(tx.id)
+        .bind(max_fee_per_gas.to_string())
+        .bind(priority_fee.to_string())
+        .bind(&message)
+        .bind(&tx_hash)
+        .bind(broadcast_date)
+        .bind(tx.broadcast_count)
+        .execute(&runtime.conn)
+        .await
+        .map_err(GenericError::new)?;
+    if reset.rows_affected() == 0 {
+        return Err(GenericError::new(format!(
+            "Transaction {} was updated by the payment runtime, try again",
+            tx.id
+        )));
+    }
+    log::info!("Transaction {}: {}", tx.id, message);
+
+    let tx = get(runtime, msg.id).await?;
+    to_pending(runtime, &tx).ok_or_else(|| {
+        GenericError::new(format!(
+            "Missing configuration for chain_id {}",
+            tx.chain_id
+        ))
+    })
+}
+
+async fn get(runtime: &PaymentRuntime, id: i64) -> Result<TxDbObj, GenericError> {
+    sqlx::query_as::<_, TxDbObj>(GET_TRANSACTION)
+        .bind(id)
+        .fetch_optional(&runtime.conn)
+        .await
+        .map_err(GenericError::new)?
+        .ok_or_else(|| GenericError::new(format!("Transaction {} not found", id)))
+}
+
+fn to_pending(runtime: &PaymentRuntime, tx: &TxDbObj) -> Option<PendingTransaction> {
+    let network = runtime.network_name(tx.chain_id)?.to_string();
+    Some(PendingTransaction {
+        id: tx.id,
+        network,
+        sender: format!("{:#x}", tx.from_addr),
+        nonce: tx.nonce.map(|nonce| nonce as u64),
+        tx_hash: tx.tx_hash.clone(),
+        max_fee_per_gas: tx.max_fee_per_gas.as_deref().and_then(wei_to_gwei),
+        priority_fee: tx.priority_fee.as_deref().and_then(wei_to_gwei),
+        created: tx.created_date,
+        broadcast: tx.broadcast_date,
+        stuck_since: tx.first_stuck_date,
+        error: tx.error.clone(),
+    })
+}
+
+/// Senders, which first pending nonce is above the next on-chain nonce.
+/// Transactions are paired with chain id of the network they are sent on.
+async fn nonce_gaps(transactions: &[(i64, PendingTransaction)]) -> Vec<NonceGap> {
+    let mut first_pending = BTreeMap::<(i64, String, String), u64>::new();
+    for (chain_id, tx) in transactions {
+        if let Some(nonce) = tx.nonce {
+            first_pending
+                .entry((*chain_id, tx.network.clone(), tx.sender.clone()))
+                .and_modify(|first| *first = (*first).min(nonce))
+                .or_insert(nonce);
+        }
+    }
+
+    let mut gaps = Vec::new();
+    for ((chain_id, network, sender), first_pending_nonce) in first_pending {
+        let next_nonce = match next_nonce(chain_id, &sender).await {
+            Ok(nonce) => nonce,
+            Err(e) => {
+                log::warn!("Can't get nonce of {} on {}: {}", sender, network, e);
+                continue;
+            }
+        };
+        if first_pending_nonce > next_nonce {
+            gaps.push(NonceGap {
+                network,
+                sender,
+                next_nonce,
+                first_pending_nonce,
+            });
+        }
+    }
+    gaps
+}
+
+async fn next_nonce(chain_id: i64, sender: &str) -> Result<u64, GenericError> {
+    let network = db_network(chain_id)?;
+    let address = H160::from_str(sender).map_err(GenericError::new)?;
+    Ok(ethereum::get_next_nonce_latest(address, network)
+        .await?
+        .as_u64())
+}
+
+fn wei_to_gwei(wei: &str) -> Option<BigDecimal> {
+    Some(BigDecimal::new(BigInt::from_str(wei).ok()?, 9))
+}
+
+fn increase(wei: Option<&str>, percent: u32) -> Result<U256, GenericError> {
+    let wei = wei.ok_or_else(|| GenericError::new("Transaction has no gas price set"))?;
+    let wei = U256::from_dec_str(wei).map_err(|e| GenericError::new(format!("{:?}", e)))?;
+    Ok(wei * U256::from(100 + percent) / U256::from(100))
+}
+
+/// Logs warning for every detected nonce gap.
+pub async fn report_nonce_gaps(runtime: &PaymentRuntime) {
+    match pending(runtime, Default::default()).await {
+        Ok(pending) => {
+            for gap in pending.nonce_gaps {
+                log::warn!(
+                    "Nonce gap on {} for {}: next on-chain nonce is {}, but first pending \
+                    transaction has nonce {}. Pending transactions can't be mined.",
+                    gap.network,
+                    gap.sender,
+                    gap.next_nonce,
+                    gap.first_pending_nonce
+                );
+            }
+        }
+        Err(e) => log::warn!("Failed to check pending transactions: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gas_increase() {
+        assert_eq!(
+            increase(Some("30000000000"), 20).unwrap(),
+            U256::from(36_000_000_000u64)
+        );
+        assert!(increase(None, 20).is_err());
+        assert_eq!(
+            wei_to_gwei("1500000000"),
+            Some(BigDecimal::from_str("1.5").unwrap())
+        );
+    }
+}
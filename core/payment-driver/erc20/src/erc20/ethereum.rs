@@ -196,6 +196,25 @@ async fn get_next_nonce_pending_with(
         .map_err(Into::into)
 }
 
+/// Nonce of the next transaction, counting only mined transactions.
+pub async fn get_next_nonce_latest(address: H160, network: Network) -> Result<U256, GenericError> {
+    with_clients(network, |client| {
+        get_next_nonce_latest_with(client, address)
+    })
+    .await
+}
+
+async fn get_next_nonce_latest_with(
+    client: Web3<Http>,
+    address: H160,
+) -> Result<U256, ClientError> {
+    client
+        .eth()
+        .transaction_count(address, Some(web3::types::BlockNumber::Latest))
+        .await
+        .map_err(Into::into)
+}
+
 pub async fn with_clients<T, F, R>(network: Network, mut f: F) -> Result<T, GenericError>
 where
     F: FnMut(Web3<Http>) -> R,
@@ -0,0 +1,223 @@
+/*
+    Gasless transfers using meta-transactions.
+
+    Payer signs EIP-712 messages calling token's `transfer`, relayer submits them with
+    token's `executeMetaTransaction` and pays for gas. Relayer is compensated in tokens by
+    a second meta-transaction, which uses the next nonce, so it can be executed only
+    after the payment.
+
+    Relayer has to be idempotent: meta-transactions, which it already relayed, are
+    answered with hashes of their transactions. So a transfer with unknown outcome is
+    submitted again, until it's either relayed or rejected.
+*/
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+use web3::types::{H160, H256, U256};
+
+use ya_payment_driver::db::models::Network;
+use ya_payment_driver::model::GenericError;
+
+use crate::erc20::eth_utils::keccak256_hash;
+use crate::erc20::ethereum;
+use crate::erc20::utils::big_dec_to_u256;
+use crate::network::GaslessSettings;
+
+const RELAYER_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetaTransaction {
+    /// Encoded call of token's `transfer` function.
+    function_call: String,
+    /// Payer's signature of the EIP-712 message.
+    signature: String,
+}
+
+/// Signed meta-transactions of a single transfer. Stored with the order, so the very
+/// same request can be submitted again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayRequest {
+    chain_id: u64,
+    sender: String,
+    /// Have to be executed in order.
+    meta_transactions: Vec<MetaTransaction>,
+}
+
+impl RelayRequest {
+    /// Number of meta-transaction nonces used by the request.
+    pub fn transactions(&self) -> u64 {
+        self.meta_transactions.len() as u64
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RelayResponse {
+    tx_hashes: Vec<H256>,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum RelayError {
+    /// Relayer refused the request, so it won't execute the meta-transactions.
+    #[error("Gasless relayer rejected transfer: {0}")]
+    Rejected(String),
+    /// Meta-transactions may still be executed, e.g. when the response was lost.
+    #[error("Gasless relayer result unknown: {0}")]
+    Unknown(String),
+}
+
+async fn sign_meta_transfer(
+    sender: H160,
+    recipient: H160,
+    amount: U256,
+    nonce: U256,
+    network: Network,
+) -> Result<MetaTransaction, GenericError> {
+    let function_abi = ethereum::encode_transfer_abi(recipient, amount, network).await?;
+    let message = ethereum::encode_meta_transaction_to_eip712(
+        sender,
+        recipient,
+        amount,
+        nonce,
+        &function_abi,
+        network,
+    )
+    .await?;
+    let signature = ethereum::sign_hash_of_data(sender, keccak256_hash(&message)).await?;
+
+    Ok(MetaTransaction {
+        function_call: format!("0x{}", hex::encode(function_abi)),
+        signature: format!("0x{}", hex::encode(signature)),
+    })
+}
+
+/// Signs transfer to `recipient` with meta-transaction `nonce` and the relayer fee
+/// with the next one.
+pub async fn sign_transfer(
+    settings: &GaslessSettings,
+    network: Network,
+    sender: H160,
+    recipient: H160,
+    amount: U256,
+    nonce: U256,
+) -> Result<RelayRequest, GenericError> {
+    let fee = BigDecimal::from_str(&settings.relayer_fee.to_string()).map_err(GenericError::new)?;
+    let fee = big_dec_to_u256(&fee)?;
+
+    let mut meta_transactions =
+        vec![sign_meta_transfer(sender, recipient, amount, nonce, network).await?];
+    if !fee.is_zero() {
+        meta_transactions.push(
+            sign_meta_transfer(sender, settings.relayer_address, fee, nonce + 1, network).await?,
+        );
+    }
+
+    Ok(RelayRequest {
+        chain_id: network as u64,
+        sender: format!("{sender:#x}"),
+        meta_transactions,
+    })
+}
+
+/// Hands signed transfer over to the relayer. Returns hash of the transaction paying
+/// the recipient. Only client errors of the relayer are taken as rejection.
+pub async fn submit(relayer_url: &str, request: &RelayRequest) -> Result<H256, RelayError> {
+    log::debug!(
+        "Relaying transfer from {} via {relayer_url}",
+        request.sender
+    );
+
+    let client = awc::Client::builder().timeout(RELAYER_TIMEOUT).finish();
+    let mut response = client
+        .post(relayer_url)
+        .send_json(request)
+        .await
+        .map_err(|e| RelayError::Unknown(format!("relayer unreachable: {e}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.body().await.unwrap_or_default();
+        let message = format!("{} {}", status, String::from_utf8_lossy(&body));
+        return Err(match status.is_client_error() {
+            true => RelayError::Rejected(message),
+            false => RelayError::Unknown(message),
+        });
+    }
+    let response: RelayResponse = response
+        .json()
+        .await
+        .map_err(|e| RelayError::Unknown(format!("invalid relayer response: {e}")))?;
+
+    response
+        .tx_hashes
+        .first()
+        .copied()
+        .ok_or_else(|| RelayError::Unknown("relayer returned no transaction".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+
+    fn request() -> RelayRequest {
+        RelayRequest {
+            chain_id: 137,
+            sender: "0x0000000000000000000000000000000000000001".to_string(),
+            meta_transactions: vec![MetaTransaction {
+                function_call: "0x".to_string(),
+                signature: "0x".to_string(),
+            }],
+        }
+    }
+
+    /// Relayer answering every request with `status`. Returns its URL.
+    fn relayer(status: u16) -> String {
+        let server = HttpServer::new(move || {
+            App::new().route(
+                "/relay",
+                web::post().to(move |body: web::Json<RelayRequest>| async move {
+                    assert_eq!(body.into_inner(), request());
+                    match status {
+                        200 => HttpResponse::Ok().json(serde_json::json!({
+                            "txHashes": [H256::repeat_byte(1)]
+                        })),
+                        status => HttpResponse::build(
+                            actix_web::http::StatusCode::from_u16(status).unwrap(),
+                        )
+                        .body("nonce already used"),
+                    }
+                }),
+            )
+        })
+        .workers(1)
+        .bind("127.0.0.1:0")
+        .unwrap();
+        let addr = server.addrs()[0];
+        actix_rt::spawn(server.run());
+        format!("http://{addr}/relay")
+    }
+
+    #[actix_rt::test]
+    async fn relayed_transfer_returns_payment_hash() {
+        let tx_hash = submit(&relayer(200), &request()).await.unwrap();
+        assert_eq!(tx_hash, H256::repeat_byte(1));
+    }
+
+    #[actix_rt::test]
+    async fn only_client_errors_are_rejections() {
+        let err = submit(&relayer(409), &request()).await.unwrap_err();
+        assert!(matches!(err, RelayError::Rejected(msg) if msg.contains("nonce already used")));
+
+        let err = submit(&relayer(502), &request()).await.unwrap_err();
+        assert!(matches!(err, RelayError::Unknown(_)));
+
+        // Connection errors aren't told apart from lost responses.
+        let err = submit("http://127.0.0.1:9/relay", &request())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RelayError::Unknown(_)));
+    }
+}
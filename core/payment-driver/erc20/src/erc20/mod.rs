@@ -4,6 +4,7 @@
 
 pub mod ethereum;
 pub mod faucet;
+pub mod gasless;
 pub mod utils;
 pub mod wallet;
 
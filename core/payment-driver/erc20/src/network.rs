@@ -1,152 +1,464 @@
-use maplit::hashmap;
-use std::collections::HashMap;
-use std::str::FromStr;
+/*
+    Networks supported by the driver.
+
+    Networks are data-driven: chain parameters (chain id, token and multicall contracts,
+    gas token) come from `config-payments.toml`, yagna specific ones (currency name, faucet
+    and bridge links, confirmation policy) from `config-networks.toml`. Both files are
+    validated against each other on driver start, so a new EVM chain is onboarded by
+    adding a `[chain.<name>]` and a `[network.<name>]` section, without code changes.
+*/
+use ethereum_types::H160;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+// External crates
+use erc20_payment_lib::config::Config as PaymentConfig;
+use num_traits::FromPrimitive;
 
 // Workspace uses
 use ya_payment_driver::{db::models::Network as DbNetwork, driver::Network, model::GenericError};
 
 // Local uses
-use crate::{
-    AMOY_CURRENCY_LONG, AMOY_CURRENCY_SHORT, AMOY_NETWORK, AMOY_PLATFORM, AMOY_TOKEN,
-    GOERLI_CURRENCY_LONG, GOERLI_CURRENCY_SHORT, GOERLI_NETWORK, GOERLI_PLATFORM, GOERLI_TOKEN,
-    HOLESKY_CURRENCY_LONG, HOLESKY_CURRENCY_SHORT, HOLESKY_NETWORK, HOLESKY_PLATFORM,
-    HOLESKY_TOKEN, MAINNET_CURRENCY_LONG, MAINNET_CURRENCY_SHORT, MAINNET_NETWORK,
-    MAINNET_PLATFORM, MAINNET_TOKEN, MUMBAI_CURRENCY_LONG, MUMBAI_CURRENCY_SHORT, MUMBAI_NETWORK,
-    MUMBAI_PLATFORM, MUMBAI_TOKEN, POLYGON_MAINNET_CURRENCY_LONG, POLYGON_MAINNET_CURRENCY_SHORT,
-    POLYGON_MAINNET_NETWORK, POLYGON_MAINNET_PLATFORM, POLYGON_MAINNET_TOKEN,
-    RINKEBY_CURRENCY_LONG, RINKEBY_CURRENCY_SHORT, RINKEBY_NETWORK, RINKEBY_PLATFORM,
-    RINKEBY_TOKEN, SEPOLIA_CURRENCY_LONG, SEPOLIA_CURRENCY_SHORT, SEPOLIA_NETWORK,
-    SEPOLIA_PLATFORM, SEPOLIA_TOKEN,
-};
-
-lazy_static::lazy_static! {
-    pub static ref SUPPORTED_NETWORKS: HashMap<String, Network> = hashmap! {
-        RINKEBY_NETWORK.to_string() => Network {
-            default_token: RINKEBY_TOKEN.to_string(),
-            tokens: hashmap! {
-                RINKEBY_TOKEN.to_string() => RINKEBY_PLATFORM.to_string()
+use crate::DRIVER_NAME;
+
+pub const NETWORKS_CONFIG_FILE: &str = "config-networks.toml";
+
+/// Contents of `config-networks.toml`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworksConfig {
+    pub default_network: Option<String>,
+    #[serde(default)]
+    pub network: BTreeMap<String, NetworkSettings>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkSettings {
+    /// Full name of the gas token, e.g. `Holesky Ether`.
+    pub currency_long: String,
+    /// Faucet suggested to users running out of gas.
+    pub faucet_url: Option<String>,
+    /// Bridge suggested to users running out of gas.
+    pub bridge_url: Option<String>,
+    /// Relayer of gasless transfers. Network supports them only if it's set.
+    pub gasless: Option<GaslessSettings>,
+    /// Layer of the chain, which determines the default confirmation depth.
+    #[serde(default)]
+    pub layer: Layer,
+    /// Blocks mined on top of a payment transaction before it's considered final.
+    /// Overrides the default of the network's layer.
+    pub confirmations: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Layer {
+    #[default]
+    L1,
+    L2,
+}
+
+impl Layer {
+    /// L1 blocks can be reorganized until the chain finalizes, so a few blocks are awaited.
+    /// L2 inherits finality from the settlement chain, so one block is enough.
+    pub fn default_confirmations(&self) -> u64 {
+        match self {
+            Layer::L1 => 6,
+            Layer::L2 => 1,
+        }
+    }
+}
+
+impl NetworkSettings {
+    pub fn required_confirmations(&self) -> u64 {
+        self.confirmations
+            .unwrap_or_else(|| self.layer.default_confirmations())
+    }
+}
+
+/// Relayer submitting meta-transactions signed by accounts, which hold no gas token.
+/// Token contract has to support `executeMetaTransaction`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct GaslessSettings {
+    /// Endpoint accepting signed meta-transactions.
+    pub relayer_url: String,
+    /// Account of the relayer, which receives its fee.
+    pub relayer_address: H160,
+    /// Tokens paid to the relayer for every relayed transfer.
+    pub relayer_fee: Decimal,
+}
+
+impl NetworksConfig {
+    pub fn load_from_str(content: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub async fn load(path: &Path) -> anyhow::Result<Self> {
+        Self::load_from_str(&tokio::fs::read_to_string(path).await?)
+    }
+
+    /// Networks from `other` are added to, or replace the ones from `self`.
+    pub fn merge(&mut self, other: NetworksConfig) {
+        if other.default_network.is_some() {
+            self.default_network = other.default_network;
+        }
+        self.network.extend(other.network);
+    }
+
+    /// Confirmations set through `ERC20_<NETWORK>_REQUIRED_CONFIRMATIONS` take precedence
+    /// over the configured policy.
+    pub fn apply_env_confirmations(&mut self) {
+        for (name, settings) in &mut self.network {
+            let env_name = format!("ERC20_{}_REQUIRED_CONFIRMATIONS", name.to_ascii_uppercase());
+            if let Ok(confirmations) = std::env::var(&env_name) {
+                match confirmations.parse::<u64>() {
+                    Ok(parsed) => {
+                        log::info!("{name} required confirmations set to {parsed}");
+                        settings.confirmations = Some(parsed);
+                    }
+                    Err(e) => {
+                        log::warn!("Value {confirmations} for {env_name} is not valid u64: {e}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Complete definition of a supported network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkDefinition {
+    pub name: String,
+    pub chain_id: i64,
+    pub token: String,
+    pub platform: String,
+    pub currency_short: String,
+    pub currency_long: String,
+    pub faucet_url: Option<String>,
+    pub bridge_url: Option<String>,
+    pub gasless: Option<GaslessSettings>,
+    pub layer: Layer,
+    /// Confirmations awaited before a sent payment is reported as confirmed.
+    pub confirmations: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Networks {
+    default_network: String,
+    networks: BTreeMap<String, NetworkDefinition>,
+}
+
+impl Networks {
+    /// Combines network settings with chain settings of the payment engine.
+    /// Fails on the first inconsistency, so misconfigured networks are never exposed.
+    /// Confirmation policy of every network is applied to its chain.
+    pub fn new(config: &NetworksConfig, payments: &mut PaymentConfig) -> anyhow::Result<Self> {
+        let mut networks = BTreeMap::new();
+        let mut chain_ids = HashSet::new();
+
+        for (name, settings) in &config.network {
+            let chain = payments.chain.get_mut(name).ok_or_else(|| {
+                anyhow::anyhow!("Network {name} has no [chain.{name}] section in payment config")
+            })?;
+            if name.contains('-') || name.to_lowercase() != *name {
+                anyhow::bail!("Network name {name} has to be lowercase and can't contain '-'");
             }
-        },
-        GOERLI_NETWORK.to_string() => Network {
-            default_token: GOERLI_TOKEN.to_string(),
-            tokens: hashmap! {
-                GOERLI_TOKEN.to_string() => GOERLI_PLATFORM.to_string()
+            if !chain_ids.insert(chain.chain_id) {
+                anyhow::bail!("Network {name}: chain id {} is not unique", chain.chain_id);
             }
-        },
-        HOLESKY_NETWORK.to_string() => Network {
-            default_token: HOLESKY_TOKEN.to_string(),
-            tokens: hashmap! {
-                HOLESKY_TOKEN.to_string() => HOLESKY_PLATFORM.to_string()
+            if chain.token.symbol.is_empty() || chain.token.address.is_zero() {
+                anyhow::bail!("Network {name}: token symbol and address are required");
             }
-        },
-        SEPOLIA_NETWORK.to_string() => Network {
-            default_token: SEPOLIA_TOKEN.to_string(),
-            tokens: hashmap! {SEPOLIA_TOKEN.to_string() => SEPOLIA_PLATFORM.to_string()}
-        },
-        MAINNET_NETWORK.to_string() => Network {
-            default_token: MAINNET_TOKEN.to_string(),
-            tokens: hashmap! {
-                MAINNET_TOKEN.to_string() => MAINNET_PLATFORM.to_string()
+            if chain.currency_symbol.is_empty() {
+                anyhow::bail!("Network {name}: gas token symbol is required");
             }
-        },
-        MUMBAI_NETWORK.to_string() => Network {
-            default_token: MUMBAI_TOKEN.to_string(),
-            tokens: hashmap! {
-                MUMBAI_TOKEN.to_string() => MUMBAI_PLATFORM.to_string()
+            if chain.rpc_endpoints.is_empty() {
+                anyhow::bail!("Network {name}: no RPC endpoints configured");
             }
-        },
-        AMOY_NETWORK.to_string() => Network {
-            default_token: AMOY_TOKEN.to_string(),
-            tokens: hashmap! {
-                AMOY_TOKEN.to_string() => AMOY_PLATFORM.to_string()
+            if let Some(gasless) = &settings.gasless {
+                if gasless.relayer_fee.is_sign_negative() {
+                    anyhow::bail!("Network {name}: gasless relayer fee can't be negative");
+                }
+                // Meta-transactions are signed and tracked by the legacy clients.
+                if let Err(e) = db_network(chain.chain_id) {
+                    anyhow::bail!("Network {name}: gasless transfers unavailable: {e}");
+                }
+                log::info!(
+                    "Network {name}: gasless transfers relayed by {}",
+                    gasless.relayer_url
+                );
             }
-        },
-        POLYGON_MAINNET_NETWORK.to_string() => Network {
-            default_token: POLYGON_MAINNET_TOKEN.to_string(),
-            tokens: hashmap! {
-                POLYGON_MAINNET_TOKEN.to_string() => POLYGON_MAINNET_PLATFORM.to_string()
+            let confirmations = settings.required_confirmations();
+            if chain.confirmation_blocks != confirmations {
+                log::debug!(
+                    "Network {name}: confirmation blocks set to {confirmations}, was {}",
+                    chain.confirmation_blocks
+                );
+                chain.confirmation_blocks = confirmations;
             }
+            if chain.multi_contract.is_none() {
+                log::warn!(
+                    "Network {name} has no multi payment contract, transfers won't be batched"
+                );
+            }
+            log::debug!(
+                "Network {name}: chain id {}, token {} at {:#x}, {} confirmation blocks",
+                chain.chain_id,
+                chain.token.symbol,
+                chain.token.address,
+                chain.confirmation_blocks
+            );
+
+            networks.insert(
+                name.clone(),
+                NetworkDefinition {
+                    name: name.clone(),
+                    chain_id: chain.chain_id,
+                    token: chain.token.symbol.clone(),
+                    platform: format!("{DRIVER_NAME}-{name}-{}", chain.token.symbol.to_lowercase()),
+                    currency_short: chain.currency_symbol.clone(),
+                    currency_long: settings.currency_long.clone(),
+                    faucet_url: settings.faucet_url.clone(),
+                    bridge_url: settings.bridge_url.clone(),
+                    gasless: settings.gasless.clone(),
+                    layer: settings.layer,
+                    confirmations,
+                },
+            );
         }
-    };
-    pub static ref RINKEBY_DB_NETWORK: DbNetwork = DbNetwork::from_str(RINKEBY_NETWORK).unwrap();
-    pub static ref GOERLI_DB_NETWORK: DbNetwork = DbNetwork::from_str(GOERLI_NETWORK).unwrap();
-    pub static ref HOLESKY_DB_NETWORK: DbNetwork = DbNetwork::from_str(HOLESKY_NETWORK).unwrap();
-    pub static ref MAINNET_DB_NETWORK: DbNetwork = DbNetwork::from_str(MAINNET_NETWORK).unwrap();
-    pub static ref MUMBAI_DB_NETWORK: DbNetwork = DbNetwork::from_str(MUMBAI_NETWORK).unwrap();
-    pub static ref AMOY_DB_NETWORK: DbNetwork = DbNetwork::from_str(AMOY_NETWORK).unwrap();
-    pub static ref POLYGON_MAINNET_DB_NETWORK: DbNetwork = DbNetwork::from_str(POLYGON_MAINNET_NETWORK).unwrap();
-}
 
-pub fn platform_to_network_token(platform: String) -> Result<(DbNetwork, String), GenericError> {
-    match platform.as_str() {
-        RINKEBY_PLATFORM => Ok((*RINKEBY_DB_NETWORK, RINKEBY_TOKEN.to_owned())),
-        GOERLI_PLATFORM => Ok((*GOERLI_DB_NETWORK, GOERLI_TOKEN.to_owned())),
-        HOLESKY_PLATFORM => Ok((*HOLESKY_DB_NETWORK, HOLESKY_TOKEN.to_owned())),
-        MAINNET_PLATFORM => Ok((*MAINNET_DB_NETWORK, MAINNET_TOKEN.to_owned())),
-        MUMBAI_PLATFORM => Ok((*MUMBAI_DB_NETWORK, MUMBAI_TOKEN.to_owned())),
-        AMOY_PLATFORM => Ok((*AMOY_DB_NETWORK, AMOY_TOKEN.to_owned())),
-        POLYGON_MAINNET_PLATFORM => Ok((
-            *POLYGON_MAINNET_DB_NETWORK,
-            POLYGON_MAINNET_TOKEN.to_owned(),
-        )),
-        other => Err(GenericError::new(format!(
-            "Unable to find network for platform: {}",
-            other
-        ))),
+        for name in payments.chain.keys() {
+            if !networks.contains_key(name) {
+                log::warn!("Chain {name} from payment config has no network settings, skipping");
+            }
+        }
+
+        let default_network = config
+            .default_network
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Default network not set"))?;
+        if !networks.contains_key(&default_network) {
+            anyhow::bail!("Default network {default_network} is not defined");
+        }
+
+        Ok(Networks {
+            default_network,
+            networks,
+        })
+    }
+
+    pub fn default_network(&self) -> &NetworkDefinition {
+        &self.networks[&self.default_network]
+    }
+
+    pub fn get(&self, name: &str) -> Result<&NetworkDefinition, GenericError> {
+        self.networks
+            .get(name)
+            .ok_or_else(|| GenericError::new(format!("Unsupported network: {name}")))
+    }
+
+    pub fn by_platform(&self, platform: &str) -> Result<&NetworkDefinition, GenericError> {
+        self.networks
+            .values()
+            .find(|network| network.platform == platform)
+            .ok_or_else(|| {
+                GenericError::new(format!("Unable to find network for platform: {platform}"))
+            })
     }
-}
 
-pub fn platform_to_currency(platform: String) -> Result<(String, String), GenericError> {
-    match platform.as_str() {
-        RINKEBY_PLATFORM => Ok((
-            RINKEBY_CURRENCY_SHORT.to_owned(),
-            RINKEBY_CURRENCY_LONG.to_owned(),
-        )),
-        GOERLI_PLATFORM => Ok((
-            GOERLI_CURRENCY_SHORT.to_owned(),
-            GOERLI_CURRENCY_LONG.to_owned(),
-        )),
-        HOLESKY_PLATFORM => Ok((
-            HOLESKY_CURRENCY_SHORT.to_owned(),
-            HOLESKY_CURRENCY_LONG.to_owned(),
-        )),
-        SEPOLIA_PLATFORM => Ok((
-            SEPOLIA_CURRENCY_SHORT.to_owned(),
-            SEPOLIA_CURRENCY_LONG.to_string(),
-        )),
-        MAINNET_PLATFORM => Ok((
-            MAINNET_CURRENCY_SHORT.to_owned(),
-            MAINNET_CURRENCY_LONG.to_owned(),
-        )),
-        MUMBAI_PLATFORM => Ok((
-            MUMBAI_CURRENCY_SHORT.to_owned(),
-            MUMBAI_CURRENCY_LONG.to_owned(),
-        )),
-        AMOY_PLATFORM => Ok((
-            AMOY_CURRENCY_SHORT.to_owned(),
-            AMOY_CURRENCY_LONG.to_owned(),
-        )),
-        POLYGON_MAINNET_PLATFORM => Ok((
-            POLYGON_MAINNET_CURRENCY_SHORT.to_owned(),
-            POLYGON_MAINNET_CURRENCY_LONG.to_owned(),
-        )),
-        other => Err(GenericError::new(format!(
-            "Unable to find network currency for platform: {}",
-            other
-        ))),
+    pub fn by_chain_id(&self, chain_id: i64) -> Result<&NetworkDefinition, GenericError> {
+        self.networks
+            .values()
+            .find(|network| network.chain_id == chain_id)
+            .ok_or_else(|| GenericError::new(format!("Unsupported chain id: {chain_id}")))
+    }
+
+    /// Unknown or missing network names resolve to the default network.
+    pub fn network_like(&self, network_like: Option<String>) -> &NetworkDefinition {
+        network_like
+            .and_then(|name| self.networks.get(&name))
+            .unwrap_or_else(|| self.default_network())
+    }
+
+    /// Platforms of networks with gasless relayer configured, sorted.
+    pub fn gasless(&self) -> Vec<String> {
+        let mut platforms = self
+            .networks
+            .values()
+            .filter(|network| network.gasless.is_some())
+            .map(|network| network.platform.clone())
+            .collect::<Vec<_>>();
+        platforms.sort();
+        platforms
+    }
+
+    /// Required confirmations by network name.
+    pub fn confirmations(&self) -> HashMap<String, u64> {
+        self.networks
+            .values()
+            .map(|network| (network.name.clone(), network.confirmations))
+            .collect()
+    }
+
+    pub fn supported(&self) -> HashMap<String, Network> {
+        self.networks
+            .values()
+            .map(|network| {
+                (
+                    network.name.clone(),
+                    Network {
+                        default_token: network.token.clone(),
+                        tokens: HashMap::from([(network.token.clone(), network.platform.clone())]),
+                    },
+                )
+            })
+            .collect()
     }
 }
 
-pub fn get_network_token(network: DbNetwork, token: Option<String>) -> String {
-    // Fetch network config, safe as long as all DbNetwork entries are in SUPPORTED_NETWORKS
-    let network_config = (*SUPPORTED_NETWORKS).get(&(network.to_string())).unwrap();
+impl NetworkDefinition {
     // TODO: Check if token in network.tokens
-    token.unwrap_or_else(|| network_config.default_token.clone())
+    pub fn token_or_default(&self, token: Option<String>) -> String {
+        token.unwrap_or_else(|| self.token.clone())
+    }
+
+    /// Network of the legacy database models and RPC clients.
+    pub fn db_network(&self) -> Result<DbNetwork, GenericError> {
+        db_network(self.chain_id)
+    }
+}
+
+/// Legacy database models and RPC clients are keyed on chain id and know only the
+/// built-in chains.
+pub fn db_network(chain_id: i64) -> Result<DbNetwork, GenericError> {
+    DbNetwork::from_i64(chain_id).ok_or_else(|| {
+        GenericError::new(format!(
+            "Chain id {chain_id} is not supported by legacy erc20 clients"
+        ))
+    })
 }
 
-pub fn network_like_to_network(network_like: Option<String>) -> DbNetwork {
-    match network_like {
-        Some(n) => DbNetwork::from_str(&n).unwrap_or(*HOLESKY_DB_NETWORK),
-        None => *HOLESKY_DB_NETWORK,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(networks: &str) -> anyhow::Result<Networks> {
+        let mut payments =
+            PaymentConfig::load_from_str(include_str!("../config-payments.toml")).unwrap();
+        Networks::new(&NetworksConfig::load_from_str(networks)?, &mut payments)
+    }
+
+    #[test]
+    fn default_networks_are_valid() {
+        let networks = load(include_str!("../config-networks.toml")).unwrap();
+
+        assert_eq!(networks.default_network().name, "holesky");
+        let polygon = networks.by_platform("erc20-polygon-glm").unwrap();
+        assert_eq!(polygon.chain_id, 137);
+        assert_eq!(polygon.currency_short, "POL");
+        assert!(polygon.bridge_url.is_some());
+        assert_eq!(polygon.confirmations, 1);
+        assert_eq!(networks.get("mainnet").unwrap().confirmations, 6);
+        assert_eq!(
+            networks.get("holesky").unwrap().platform,
+            "erc20-holesky-tglm"
+        );
+        assert_eq!(
+            networks.network_like(Some("unknown".into())).name,
+            "holesky"
+        );
+    }
+
+    #[test]
+    fn rinkeby_is_not_supported() {
+        let networks = load(include_str!("../config-networks.toml")).unwrap();
+        assert!(networks.get("rinkeby").is_err());
+        assert!(networks.by_platform("erc20-rinkeby-tglm").is_err());
+        assert!(!networks.supported().contains_key("rinkeby"));
+    }
+
+    #[test]
+    fn network_without_chain_is_rejected() {
+        let err = load(
+            r#"
+            default-network = "holesky"
+            [network.holesky]
+            currency-long = "Holesky Ether"
+            [network.arbitrum]
+            currency-long = "Arbitrum Ether"
+        "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("[chain.arbitrum]"));
+    }
+
+    #[test]
+    fn default_network_has_to_be_defined() {
+        let err = load(
+            r#"
+            default-network = "sepolia"
+            [network.holesky]
+            currency-long = "Holesky Ether"
+        "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("sepolia"));
+    }
+
+    #[test]
+    fn gasless_relayer_is_optional() {
+        let networks = load(
+            r#"
+            default-network = "holesky"
+            [network.holesky]
+            currency-long = "Holesky Ether"
+            [network.polygon]
+            currency-long = "Polygon"
+            [network.polygon.gasless]
+            relayer-url = "http://127.0.0.1:8080/relay"
+            relayer-address = "0x0000000000000000000000000000000000000001"
+            relayer-fee = "0.1"
+        "#,
+        )
+        .unwrap();
+        assert!(networks.get("holesky").unwrap().gasless.is_none());
+        let gasless = networks.get("polygon").unwrap().gasless.clone().unwrap();
+        assert_eq!(gasless.relayer_fee, Decimal::new(1, 1));
+        assert_eq!(networks.gasless(), vec!["erc20-polygon-glm".to_string()]);
+    }
+
+    #[test]
+    fn confirmations_follow_layer_unless_set() {
+        let networks = load(
+            r#"
+            default-network = "holesky"
+            [network.mainnet]
+            currency-long = "Ether"
+            [network.polygon]
+            currency-long = "Polygon"
+            layer = "l2"
+            [network.holesky]
+            currency-long = "Holesky Ether"
+            confirmations = 0
+        "#,
+        )
+        .unwrap();
+        assert_eq!(networks.get("mainnet").unwrap().confirmations, 6);
+        assert_eq!(networks.get("polygon").unwrap().confirmations, 1);
+        assert_eq!(networks.get("holesky").unwrap().confirmations, 0);
+    }
+
+    #[test]
+    fn db_network_is_keyed_on_chain_id() {
+        let networks = load(include_str!("../config-networks.toml")).unwrap();
+        for network in ["sepolia", "amoy", "polygon"] {
+            let network = networks.get(network).unwrap();
+            assert_eq!(network.db_network().unwrap() as i64, network.chain_id);
+        }
+        assert!(db_network(42161).is_err());
     }
 }
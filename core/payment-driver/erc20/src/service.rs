@@ -17,6 +17,8 @@ use ethereum_types::H160;
 use ya_payment_driver::bus;
 
 // Local uses
+use crate::driver::relayed;
+use crate::network::{Networks, NetworksConfig, NETWORKS_CONFIG_FILE};
 use crate::{driver::Erc20Driver, signer::IdentitySigner};
 
 pub struct Erc20Service;
@@ -95,7 +97,6 @@ impl Erc20Service {
                 let wrapper_contract_env = format!("{prefix}_WRAPPER_CONTRACT_ADDRESS");
                 let multi_payment_addr_env = format!("{prefix}_MULTI_PAYMENT_CONTRACT_ADDRESS");
                 let lock_payment_addr_env = format!("{prefix}_LOCK_PAYMENT_CONTRACT_ADDRESS");
-                let confirmations_env = format!("ERC20_{prefix}_REQUIRED_CONFIRMATIONS");
 
                 if let Ok(addr) = env::var(&rpc_env) {
                     chain.rpc_endpoints = addr
@@ -155,19 +156,6 @@ impl Erc20Service {
                         }
                     };
                 }
-                if let Ok(confirmations) = env::var(&confirmations_env) {
-                    match confirmations.parse::<u64>() {
-                        Ok(parsed) => {
-                            log::info!("{network} required confirmations set to {parsed}");
-                            chain.confirmation_blocks = parsed;
-                        }
-                        Err(e) => {
-                            log::warn!(
-                                "Value {confirmations} for {confirmations} is not valid u64: {e}"
-                            );
-                        }
-                    };
-                }
                 if let Ok(multi_payment_addr) = env::var(&multi_payment_addr_env) {
                     match H160::from_str(&multi_payment_addr) {
                         Ok(parsed) => {
@@ -226,6 +214,25 @@ impl Erc20Service {
                 }
             }
 
+            let mut networks_config =
+                NetworksConfig::load_from_str(include_str!("../config-networks.toml"))
+                    .expect("Default erc20 networks config doesn't parse");
+
+            // Networks from file are added to the default ones, so a new chain can be onboarded
+            // without copying the whole file
+            let networks_path = path.join(NETWORKS_CONFIG_FILE);
+            if tokio::fs::try_exists(&networks_path).await.unwrap_or(false) {
+                log::info!("Networks config file found in {}", networks_path.display());
+                networks_config.merge(
+                    NetworksConfig::load(&networks_path)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Invalid {}: {e}", networks_path.display()))?,
+                );
+            }
+            networks_config.apply_env_confirmations();
+            let networks = Networks::new(&networks_config, &mut config)
+                .map_err(|e| anyhow::anyhow!("Invalid erc20 network configuration: {e}"))?;
+
             log::debug!("Starting payment engine: {:#?}", config);
             let signer = IdentitySigner;
 
@@ -251,7 +258,8 @@ impl Erc20Service {
             //    .await?;
 
             log::debug!("Bind erc20 driver");
-            let driver = Erc20Driver::new(pr, recv);
+            relayed::init(&pr.conn).await?;
+            let driver = Erc20Driver::new(pr, networks, recv);
             driver.load_active_accounts().await;
             bus::bind_service(driver).await?;
 
@@ -8,11 +8,11 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
 
     let account_list = bus::service(pay::BUS_ID)
-        .call(pay::GetAccounts {})
+        .call(pay::GetAccounts::all())
         .await??;
     log::debug!("account_list: {:?}", account_list);
 
-    for account in account_list.into_iter() {
+    for account in account_list.into_iter().map(|owned| owned.account) {
         log::info!("Address: {:?}", account.address);
 
         let payer_status = bus::service(pay::BUS_ID)
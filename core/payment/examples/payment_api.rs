@@ -350,6 +350,7 @@ async fn main() -> anyhow::Result<()> {
         Arc::new(NetConfig::from_env()?),
         provider_id,
         vec![provider_id, requestor_id],
+        None,
     )
     .await?;
 
@@ -357,24 +358,29 @@ async fn main() -> anyhow::Result<()> {
     let rest_addr = rest_api_addr();
     log::info!("Starting http server on port {}", rest_addr);
 
+    let agreement_lock = ya_payment::AgreementLock::arc();
     HttpServer::new(move || {
         let provider_identity = Identity {
             identity: provider_id,
             name: "".to_string(),
             role: "".to_string(),
+            delegated_by: None,
         };
         let requestor_identity = Identity {
             identity: requestor_id,
             name: "".to_string(),
             role: "".to_string(),
+            delegated_by: None,
         };
 
         let provider_api_scope = Scope::new(&format!("provider{}", PAYMENT_API_PATH))
             .app_data(Data::new(db.clone()))
+            .app_data(Data::new(agreement_lock.clone()))
             .extend(ya_payment::api::api_scope)
             .wrap(DummyAuth::new(provider_identity));
         let requestor_api_scope = Scope::new(&format!("requestor{}", PAYMENT_API_PATH))
             .app_data(Data::new(db.clone()))
+            .app_data(Data::new(agreement_lock.clone()))
             .extend(ya_payment::api::api_scope)
             .wrap(DummyAuth::new(requestor_identity));
         App::new()
@@ -0,0 +1,2 @@
+DROP INDEX pay_idempotency_key_created_ts_idx;
+DROP TABLE pay_idempotency_key;
@@ -0,0 +1,10 @@
+CREATE TABLE pay_idempotency_key(
+    key VARCHAR(100) NOT NULL,
+    owner_id VARCHAR(50) NOT NULL,
+    peer_id VARCHAR(50) NOT NULL,
+    payment_id VARCHAR(50) NOT NULL,
+    created_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW')),
+    PRIMARY KEY(owner_id, peer_id, key)
+);
+
+CREATE INDEX pay_idempotency_key_created_ts_idx ON pay_idempotency_key (created_ts);
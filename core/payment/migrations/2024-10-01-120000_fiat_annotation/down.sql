@@ -0,0 +1,2 @@
+DROP INDEX pay_fiat_annotation_timestamp_idx;
+DROP TABLE pay_fiat_annotation;
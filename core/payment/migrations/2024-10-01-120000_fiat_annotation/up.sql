@@ -0,0 +1,13 @@
+CREATE TABLE pay_fiat_annotation(
+    owner_id VARCHAR(50) NOT NULL,
+    entity_type VARCHAR(16) NOT NULL,
+    entity_id VARCHAR(50) NOT NULL,
+    platform VARCHAR(50) NOT NULL,
+    amount VARCHAR(32) NOT NULL,
+    currency VARCHAR(8) NOT NULL,
+    rate VARCHAR(32) NOT NULL,
+    timestamp DATETIME NOT NULL,
+    PRIMARY KEY(owner_id, entity_type, entity_id)
+);
+
+CREATE INDEX pay_fiat_annotation_timestamp_idx ON pay_fiat_annotation (timestamp);
@@ -0,0 +1 @@
+DROP TABLE pay_payment_receipt;
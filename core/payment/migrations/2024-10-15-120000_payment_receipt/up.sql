@@ -0,0 +1,9 @@
+CREATE TABLE pay_payment_receipt(
+    payment_id VARCHAR(50) NOT NULL,
+    owner_id VARCHAR(50) NOT NULL,
+    tx_hash VARCHAR(128) NOT NULL,
+    issued_at DATETIME NOT NULL,
+    signature BLOB NOT NULL,
+    signed_bytes BLOB NOT NULL,
+    PRIMARY KEY(payment_id, owner_id)
+);
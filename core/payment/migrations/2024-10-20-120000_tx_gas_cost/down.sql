@@ -0,0 +1 @@
+DROP TABLE pay_tx_gas_cost;
@@ -0,0 +1,7 @@
+CREATE TABLE pay_tx_gas_cost(
+    owner_id VARCHAR(50) NOT NULL,
+    details BLOB NOT NULL,
+    platform VARCHAR(50) NOT NULL,
+    gas_cost VARCHAR(32) NOT NULL,
+    PRIMARY KEY(owner_id, details)
+);
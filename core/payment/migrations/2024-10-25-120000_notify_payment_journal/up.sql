@@ -0,0 +1,11 @@
+CREATE TABLE pay_notify_payment(
+    driver VARCHAR(50) NOT NULL,
+    details BLOB NOT NULL,
+    order_ids TEXT NOT NULL,
+    notification TEXT NOT NULL,
+    payment_id VARCHAR(50),
+    created_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW')),
+    PRIMARY KEY(driver, details, order_ids)
+);
+
+CREATE INDEX pay_notify_payment_pending_idx ON pay_notify_payment (payment_id) WHERE payment_id IS NULL;
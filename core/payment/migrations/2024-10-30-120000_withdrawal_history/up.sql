@@ -0,0 +1,14 @@
+CREATE TABLE pay_withdrawal(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    sender VARCHAR(50) NOT NULL,
+    recipient VARCHAR(50) NOT NULL,
+    driver VARCHAR(50) NOT NULL,
+    network VARCHAR(50) NOT NULL,
+    token VARCHAR(50) NOT NULL,
+    amount VARCHAR(32) NOT NULL,
+    max_fee VARCHAR(32) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    tx_id VARCHAR(50),
+    error TEXT,
+    timestamp DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW'))
+);
@@ -0,0 +1,18 @@
+CREATE TABLE pay_fee(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    owner_id VARCHAR(50) NOT NULL,
+    invoice_id VARCHAR(50) NOT NULL,
+    agreement_id VARCHAR(50) NOT NULL,
+    payment_platform VARCHAR(50) NOT NULL,
+    payer_addr VARCHAR(50) NOT NULL,
+    recipient VARCHAR(50) NOT NULL,
+    amount VARCHAR(32) NOT NULL,
+    driver VARCHAR(50) NOT NULL,
+    order_id VARCHAR(50),
+    status VARCHAR(50) NOT NULL,
+    tx_id VARCHAR(128),
+    error TEXT,
+    timestamp DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW'))
+);
+
+CREATE INDEX pay_fee_order_idx ON pay_fee(order_id, driver);
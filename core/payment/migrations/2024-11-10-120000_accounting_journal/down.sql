@@ -0,0 +1,2 @@
+DROP TABLE pay_journal_entry;
+DROP TABLE pay_journal_period;
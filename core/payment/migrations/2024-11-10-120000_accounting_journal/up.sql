@@ -0,0 +1,51 @@
+CREATE TABLE pay_journal_period(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    owner_id VARCHAR(50) NOT NULL,
+    closed_until DATETIME NOT NULL,
+    closed_ts DATETIME NOT NULL DEFAULT(STRFTIME('%Y-%m-%d %H:%M:%f', 'NOW'))
+);
+
+CREATE INDEX pay_journal_period_owner_idx ON pay_journal_period(owner_id, closed_until);
+
+CREATE TABLE pay_journal_entry(
+    owner_id VARCHAR(50) NOT NULL,
+    source VARCHAR(200) NOT NULL,
+    timestamp DATETIME NOT NULL,
+    debit_account VARCHAR(50) NOT NULL,
+    credit_account VARCHAR(50) NOT NULL,
+    currency VARCHAR(50) NOT NULL,
+    amount VARCHAR(32) NOT NULL,
+    memo TEXT NULL,
+    period_id INTEGER NULL,
+    PRIMARY KEY(owner_id, source),
+    FOREIGN KEY(period_id) REFERENCES pay_journal_period (id)
+);
+
+CREATE INDEX pay_journal_entry_period_idx ON pay_journal_entry(owner_id, period_id, timestamp);
+
+-- Journal is posted together with payment events. Events recorded before it existed are
+-- posted here, with the same sources, so they are never posted twice.
+INSERT OR IGNORE INTO pay_journal_entry(owner_id, source, timestamp, debit_account, credit_account, currency, amount, memo)
+SELECT i.owner_id, 'invoice:' || i.id, e.timestamp,
+    CASE i.role WHEN 'P' THEN 'receivables' ELSE 'compute-expense' END,
+    CASE i.role WHEN 'P' THEN 'revenue' ELSE 'payables' END,
+    a.payment_platform, i.amount, 'Invoice accepted, agreement ' || i.agreement_id
+FROM pay_invoice i
+JOIN pay_agreement a ON a.owner_id = i.owner_id AND a.id = i.agreement_id
+JOIN pay_invoice_event e ON e.owner_id = i.owner_id AND e.invoice_id = i.id AND e.event_type = 'ACCEPTED'
+WHERE i.status IN ('ACCEPTED', 'SETTLED') AND CAST(i.amount AS REAL) > 0;
+
+INSERT OR IGNORE INTO pay_journal_entry(owner_id, source, timestamp, debit_account, credit_account, currency, amount, memo)
+SELECT owner_id, 'payment:' || id, timestamp,
+    CASE role WHEN 'P' THEN 'wallet' ELSE 'payables' END,
+    CASE role WHEN 'P' THEN 'receivables' ELSE 'wallet' END,
+    payment_platform, amount,
+    CASE role WHEN 'P' THEN 'Payment from ' ELSE 'Payment to ' END || peer_id
+FROM pay_payment;
+
+INSERT OR IGNORE INTO pay_journal_entry(owner_id, source, timestamp, debit_account, credit_account, currency, amount, memo)
+SELECT g.owner_id, 'gas:' || LOWER(HEX(g.details)), MIN(p.timestamp), 'gas-expense', 'wallet',
+    g.platform || ':gas', g.gas_cost, 'Transaction fee'
+FROM pay_tx_gas_cost g
+JOIN pay_payment p ON p.owner_id = g.owner_id AND p.details = g.details
+GROUP BY g.owner_id, g.details;
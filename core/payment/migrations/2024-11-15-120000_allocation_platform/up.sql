@@ -0,0 +1,12 @@
+-- Fallback payment platforms of multi-currency allocations, in order of preference.
+-- Primary platform stays in pay_allocation.payment_platform.
+-- Amount is the part of the remaining amount reserved on the fallback platform,
+-- the rest is reserved on the primary one.
+CREATE TABLE pay_allocation_platform(
+    allocation_id VARCHAR(50) NOT NULL,
+    platform VARCHAR(50) NOT NULL,
+    priority INTEGER NOT NULL,
+    amount VARCHAR(32) NOT NULL,
+    PRIMARY KEY(allocation_id, platform),
+    FOREIGN KEY(allocation_id) REFERENCES pay_allocation (id)
+);
@@ -0,0 +1,7 @@
+DROP INDEX pay_fee_status_idx;
+DROP INDEX pay_fee_payment_order_idx;
+
+UPDATE pay_fee SET status = 'failed' WHERE status IN ('pending', 'due');
+
+ALTER TABLE pay_fee DROP COLUMN allocation_id;
+ALTER TABLE pay_fee DROP COLUMN payment_order_id;
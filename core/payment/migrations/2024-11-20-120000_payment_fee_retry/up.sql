@@ -0,0 +1,8 @@
+ALTER TABLE pay_fee ADD COLUMN payment_order_id VARCHAR(50);
+ALTER TABLE pay_fee ADD COLUMN allocation_id VARCHAR(50);
+
+-- Fees, which failed to be scheduled, are retried.
+UPDATE pay_fee SET status = 'due' WHERE status = 'failed';
+
+CREATE INDEX pay_fee_payment_order_idx ON pay_fee(payment_order_id, driver);
+CREATE INDEX pay_fee_status_idx ON pay_fee(status);
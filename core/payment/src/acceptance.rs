@@ -0,0 +1,226 @@
+//! Tracking of invoices awaiting acceptance on the Requestor side.
+//!
+//! Providers expect invoices to be accepted in time, otherwise they may consider the
+//! agreement broken. Received invoices are checked periodically: when enabled, invoices of
+//! terminated agreements, which match accepted debit notes within tolerance, are accepted
+//! automatically. For the rest an `InvoiceAcceptanceDue` event is published (once per invoice)
+//! before the deadline, so they aren't forgotten.
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use ya_client_model::market::agreement::State as AgreementState;
+use ya_client_model::market::Role as MarketRole;
+use ya_client_model::payment::params::DEFAULT_ACK_TIMEOUT;
+use ya_client_model::payment::{Acceptance, DocumentStatus, Invoice};
+use ya_core_model::events::EventPayload;
+use ya_persistence::executor::DbExecutor;
+use ya_persistence::types::Role;
+
+use crate::api::guard::AgreementLock;
+use crate::api::invoices::accept;
+use crate::config::InvoiceAcceptanceConfig;
+use crate::dao::{AgreementDao, InvoiceDao, OrderDao};
+use crate::utils::get_agreement;
+
+pub struct InvoiceAcceptance {
+    db: DbExecutor,
+    config: InvoiceAcceptanceConfig,
+    agreement_lock: Arc<AgreementLock>,
+    /// Invoices, which the due event was published for.
+    notified: HashSet<String>,
+}
+
+/// Starts periodic checks of received invoices.
+pub fn start(
+    db: &DbExecutor,
+    agreement_lock: Arc<AgreementLock>,
+    config: &InvoiceAcceptanceConfig,
+) {
+    let mut acceptance = InvoiceAcceptance::new(db.clone(), agreement_lock, config.clone());
+    let period = config.invoice_accept_check_interval;
+    tokio::task::spawn_local(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = acceptance.check().await {
+                log::warn!("Invoice acceptance check failed: {}", e);
+            }
+        }
+    });
+}
+
+impl InvoiceAcceptance {
+    pub fn new(
+        db: DbExecutor,
+        agreement_lock: Arc<AgreementLock>,
+        config: InvoiceAcceptanceConfig,
+    ) -> Self {
+        InvoiceAcceptance {
+            db,
+            config,
+            agreement_lock,
+            notified: HashSet::new(),
+        }
+    }
+
+    pub async fn check(&mut self) -> anyhow::Result<()> {
+        let invoices = self
+            .db
+            .as_dao::<InvoiceDao>()
+            .list(Some(Role::Requestor), Some(DocumentStatus::Received))
+            .await?;
+        self.notified
+            .retain(|id| invoices.iter().any(|invoice| &invoice.invoice_id == id));
+
+        let deadline = chrono::Duration::from_std(self.config.invoice_accept_deadline)?;
+        let warning = chrono::Duration::from_std(self.config.invoice_accept_warning)?;
+        for invoice in invoices {
+            if self.config.invoice_auto_accept {
+                match self.auto_accept(&invoice).await {
+                    Ok(true) => continue,
+                    Ok(false) => (),
+                    Err(e) => log::warn!(
+                        "Failed to accept Invoice [{}] automatically: {}",
+                        invoice.invoice_id,
+                        e
+                    ),
+                }
+            }
+
+            let deadline = match acceptance_due(invoice.timestamp, Utc::now(), deadline, warning) {
+                Some(deadline) => deadline,
+                None => continue,
+            };
+            if self.notified.insert(invoice.invoice_id.clone()) {
+                log::warn!(
+                    "Invoice [{}] for Agreement [{}] awaits acceptance. Deadline: {}",
+                    invoice.invoice_id,
+                    invoice.agreement_id,
+                    deadline
+                );
+                ya_events::publish(
+                    Some(invoice.recipient_id),
+                    EventPayload::InvoiceAcceptanceDue {
+                        invoice_id: invoice.invoice_id,
+                        agreement_id: invoice.agreement_id,
+                        deadline,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Accepts the invoice, if it meets the criteria. Returns whether it was accepted.
+    async fn auto_accept(&self, invoice: &Invoice) -> anyhow::Result<bool> {
+        let node_id = invoice.recipient_id;
+        match get_agreement(invoice.agreement_id.clone(), MarketRole::Requestor).await? {
+            Some(agreement) if agreement.state == AgreementState::Terminated => (),
+            _ => return Ok(false),
+        }
+
+        let accepted = match self
+            .db
+            .as_dao::<AgreementDao>()
+            .get(invoice.agreement_id.clone(), node_id)
+            .await?
+        {
+            Some(agreement) => agreement.total_amount_accepted.0,
+            None => return Ok(false),
+        };
+        if !within_tolerance(
+            &invoice.amount,
+            &accepted,
+            &self.config.invoice_auto_accept_tolerance,
+        ) {
+            return Ok(false);
+        }
+
+        let allocation_id = match self
+            .db
+            .as_dao::<OrderDao>()
+            .last_agreement_allocation(invoice.agreement_id.clone(), node_id)
+            .await?
+        {
+            Some(allocation_id) => allocation_id,
+            None => return Ok(false),
+        };
+
+        let acceptance = Acceptance {
+            total_amount_accepted: invoice.amount.clone(),
+            allocation_id,
+        };
+        accept(
+            &self.db,
+            &self.agreement_lock,
+            invoice.invoice_id.clone(),
+            node_id,
+            acceptance,
+            DEFAULT_ACK_TIMEOUT,
+        )
+        .await?;
+
+        log::info!(
+            "Invoice [{}] for Agreement [{}] accepted automatically.",
+            invoice.invoice_id,
+            invoice.agreement_id
+        );
+        Ok(true)
+    }
+}
+
+/// Acceptance deadline of an invoice issued at `timestamp`, if it's time to warn about it.
+fn acceptance_due(
+    timestamp: DateTime<Utc>,
+    now: DateTime<Utc>,
+    deadline: chrono::Duration,
+    warning: chrono::Duration,
+) -> Option<DateTime<Utc>> {
+    let deadline = timestamp + deadline;
+    (now >= deadline - warning).then_some(deadline)
+}
+
+fn within_tolerance(amount: &BigDecimal, accepted: &BigDecimal, tolerance: &BigDecimal) -> bool {
+    (amount - accepted).abs() <= *tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_acceptance_due() {
+        let issued = Utc::now();
+        let deadline = chrono::Duration::hours(24);
+        let warning = chrono::Duration::hours(2);
+
+        let due = |hours| {
+            acceptance_due(
+                issued,
+                issued + chrono::Duration::hours(hours),
+                deadline,
+                warning,
+            )
+        };
+        assert_eq!(due(1), None);
+        assert_eq!(due(22), Some(issued + deadline));
+        assert_eq!(due(25), Some(issued + deadline));
+    }
+
+    #[test]
+    fn test_within_tolerance() {
+        let amount = BigDecimal::from_str("10.001").unwrap();
+        let accepted = BigDecimal::from(10);
+
+        assert!(within_tolerance(
+            &amount,
+            &accepted,
+            &BigDecimal::from_str("0.01").unwrap()
+        ));
+        assert!(!within_tolerance(&amount, &accepted, &BigDecimal::from(0)));
+        assert!(within_tolerance(&accepted, &accepted, &BigDecimal::from(0)));
+    }
+}
@@ -0,0 +1,227 @@
+//! Double-entry accounting journal.
+//!
+//! Payment events are posted to the journal in the same transaction, which records them,
+//! each as a balanced entry debiting one account and crediting another:
+//! - accepted Invoice: receivables / revenue for Provider, compute expense / payables for Requestor,
+//! - received payment: wallet / receivables, sent payment: payables / wallet,
+//! - transaction fee: gas expense / wallet, in the gas token of the network.
+//!
+//! Entries are never modified, so the journal can be fed into ERP systems incrementally.
+//! Closing a period assigns to it all entries up to its end, which aren't part of earlier
+//! periods, so entries recorded late land in the next period instead of changing closed ones.
+//! Amounts paid for Debit Notes before the Invoice is accepted temporarily show up as
+//! negative receivables (Provider) or payables (Requestor), like prepayments.
+//! Deposits to and withdrawals from wallet aren't journaled, so wallet shows net flow only.
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use strum::{Display, EnumString};
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display, EnumString, Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "camelCase")]
+pub enum Account {
+    /// Tokens held on payment addresses.
+    Wallet,
+    /// Invoiced amounts, which Requestors didn't pay yet.
+    Receivables,
+    /// Accepted Invoices, which weren't paid to Providers yet.
+    Payables,
+    Revenue,
+    ComputeExpense,
+    /// Transaction fees paid in the gas token of the network.
+    GasExpense,
+}
+
+impl Account {
+    /// Balance of debit-normal accounts grows with debits, others with credits.
+    fn debit_normal(&self) -> bool {
+        matches!(
+            self,
+            Account::Wallet | Account::Receivables | Account::ComputeExpense | Account::GasExpense
+        )
+    }
+}
+
+/// Gas token is a separate currency, so transaction fees don't mix with payment tokens.
+pub fn gas_currency(payment_platform: &str) -> String {
+    format!("{}:gas", payment_platform)
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    /// Payment event, which the entry was posted for, e.g. `payment:<paymentId>`.
+    pub source: String,
+    pub timestamp: DateTime<Utc>,
+    pub debit: Account,
+    pub credit: Account,
+    /// Payment platform, or its gas token for transaction fees.
+    pub currency: String,
+    pub amount: BigDecimal,
+    pub memo: Option<String>,
+    /// Closed period, which the entry belongs to.
+    pub period_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Period {
+    pub id: i32,
+    /// Period contains entries up to this time, which aren't part of earlier periods.
+    pub closed_until: DateTime<Utc>,
+    pub closed_ts: DateTime<Utc>,
+    pub entries: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBalance {
+    pub account: Account,
+    pub currency: String,
+    pub debit: BigDecimal,
+    pub credit: BigDecimal,
+    /// Debit minus credit for debit-normal accounts (wallet, receivables, expenses),
+    /// credit minus debit for the others.
+    pub balance: BigDecimal,
+}
+
+/// Totals of all accounts per currency. Debits equal credits in every currency.
+pub fn trial_balance(entries: &[JournalEntry]) -> Vec<AccountBalance> {
+    let mut totals = BTreeMap::<(String, Account), (BigDecimal, BigDecimal)>::new();
+    for entry in entries {
+        totals
+            .entry((entry.currency.clone(), entry.debit))
+            .or_insert_with(|| (BigDecimal::zero(), BigDecimal::zero()))
+            .0 += &entry.amount;
+        totals
+            .entry((entry.currency.clone(), entry.credit))
+            .or_insert_with(|| (BigDecimal::zero(), BigDecimal::zero()))
+            .1 += &entry.amount;
+    }
+    totals
+        .into_iter()
+        .map(|((currency, account), (debit, credit))| AccountBalance {
+            balance: match account.debit_normal() {
+                true => &debit - &credit,
+                false => &credit - &debit,
+            },
+            account,
+            currency,
+            debit,
+            credit,
+        })
+        .collect()
+}
+
+/// Journal in CSV format, one entry per line.
+pub fn to_csv(entries: &[JournalEntry]) -> String {
+    let mut csv = "timestamp,source,debit,credit,currency,amount,memo,period\n".to_string();
+    for entry in entries {
+        let line = [
+            entry.timestamp.to_rfc3339(),
+            entry.source.clone(),
+            entry.debit.to_string(),
+            entry.credit.to_string(),
+            entry.currency.clone(),
+            entry.amount.to_string(),
+            entry.memo.clone().unwrap_or_default(),
+            entry.period_id.map(|id| id.to_string()).unwrap_or_default(),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",");
+        csv.push_str(&line);
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn entry(debit: Account, credit: Account, currency: &str, amount: &str) -> JournalEntry {
+        JournalEntry {
+            source: format!("test:{}-{}", debit, credit),
+            timestamp: Utc::now(),
+            debit,
+            credit,
+            currency: currency.to_string(),
+            amount: BigDecimal::from_str(amount).unwrap(),
+            memo: None,
+            period_id: None,
+        }
+    }
+
+    #[test]
+    fn trial_balance_is_balanced() {
+        let platform = "erc20-holesky-tglm";
+        let gas = gas_currency(platform);
+        let entries = vec![
+            entry(Account::ComputeExpense, Account::Payables, platform, "10"),
+            entry(Account::Payables, Account::Wallet, platform, "7.5"),
+            entry(Account::GasExpense, Account::Wallet, &gas, "0.001"),
+        ];
+        let balances = trial_balance(&entries);
+
+        let balance = |account, currency: &str| {
+            balances
+                .iter()
+                .find(|b| b.account == account && b.currency == currency)
+                .map(|b| b.balance.to_string())
+        };
+        assert_eq!(balance(Account::Payables, platform).unwrap(), "2.5");
+        assert_eq!(balance(Account::Wallet, platform).unwrap(), "-7.5");
+        assert_eq!(balance(Account::ComputeExpense, platform).unwrap(), "10");
+        assert_eq!(balance(Account::GasExpense, &gas).unwrap(), "0.001");
+        assert_eq!(balance(Account::Wallet, &gas).unwrap(), "-0.001");
+        assert!(balance(Account::Revenue, platform).is_none());
+
+        for currency in [platform, gas.as_str()] {
+            let (debit, credit) = balances
+                .iter()
+                .filter(|b| b.currency == currency)
+                .fold((BigDecimal::zero(), BigDecimal::zero()), |(d, c), b| {
+                    (d + &b.debit, c + &b.credit)
+                });
+            assert_eq!(debit, credit);
+        }
+    }
+
+    #[test]
+    fn accounts_are_stored_in_kebab_case() {
+        assert_eq!(Account::ComputeExpense.to_string(), "compute-expense");
+        assert_eq!(
+            Account::from_str("gas-expense").unwrap(),
+            Account::GasExpense
+        );
+    }
+
+    #[test]
+    fn csv_escaping() {
+        let mut payment = entry(
+            Account::Wallet,
+            Account::Receivables,
+            "erc20-polygon-glm",
+            "1",
+        );
+        payment.memo = Some("Payment from \"x\", y".to_string());
+        let csv = to_csv(&[payment]);
+        let line = csv.lines().nth(1).unwrap();
+        assert!(line
+            .ends_with(",wallet,receivables,erc20-polygon-glm,1,\"Payment from \"\"x\"\", y\","));
+    }
+}
@@ -1,30 +1,45 @@
 use actix_web::web::{self, Data};
 use actix_web::Scope;
+use std::sync::Arc;
 use ya_client_model::payment::PAYMENT_API_PATH;
 use ya_persistence::executor::DbExecutor;
 use ya_service_api_web::scope::ExtendableScope;
 
+use crate::fiat::FiatAnnotator;
+use crate::Config;
+
+mod accounting;
 mod accounts;
 pub mod allocations;
 mod debit_notes;
-mod invoices;
+mod income;
+pub(crate) mod invoices;
 mod payments;
 
-mod guard;
+pub(crate) mod guard;
+
+pub use guard::AgreementLock;
 
+/// Payment API endpoints. Expects `DbExecutor` and shared `Arc<AgreementLock>` in app data.
 pub fn api_scope(scope: Scope) -> Scope {
+    let fiat = Config::from_env()
+        .map(|config| FiatAnnotator::new(&config.fiat))
+        .unwrap_or_default();
     scope
-        .app_data(web::Data::new(guard::AgreementLock::arc()))
+        .app_data(web::Data::new(fiat))
+        .extend(accounting::register_endpoints)
         .extend(accounts::register_endpoints)
         .extend(allocations::register_endpoints)
         .extend(debit_notes::register_endpoints)
+        .extend(income::register_endpoints)
         .extend(invoices::register_endpoints)
         .extend(payments::register_endpoints)
 }
 
-pub fn web_scope(db: &DbExecutor) -> Scope {
+pub fn web_scope(db: &DbExecutor, agreement_lock: Arc<AgreementLock>) -> Scope {
     Scope::new(PAYMENT_API_PATH)
         .app_data(Data::new(db.clone()))
+        .app_data(Data::new(agreement_lock))
         .service(api_scope(Scope::new("")))
     // TODO: TEST
     // Scope::new(PAYMENT_API_PATH).extend(api_scope).app_data(Data::new(db.clone()))
@@ -0,0 +1,157 @@
+// External crates
+use actix_web::web::{get, post, Data, Json, Path, Query};
+use actix_web::{HttpResponse, Scope};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+// Workspace uses
+use ya_persistence::executor::DbExecutor;
+use ya_service_api_web::middleware::Identity;
+
+// Local uses
+use crate::accounting::{to_csv, trial_balance};
+use crate::dao::*;
+use crate::error::DbError;
+use crate::utils::*;
+
+pub fn register_endpoints(scope: Scope) -> Scope {
+    scope
+        .route("/accounting/journal", get().to(get_journal))
+        .route("/accounting/balances", get().to(get_balances))
+        .route("/accounting/periods", get().to(get_periods))
+        .route("/accounting/periods", post().to(close_period))
+        .route(
+            "/accounting/periods/{period_id}/export",
+            get().to(export_period),
+        )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalParams {
+    period_id: Option<i32>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceParams {
+    /// Balances at the end of closed period. Current balances, if not given.
+    period_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClosePeriod {
+    /// End of the period. Now, if not given.
+    until: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct PeriodId {
+    period_id: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportParams {
+    /// `csv` (default) or `json`.
+    format: Option<String>,
+}
+
+async fn get_journal(
+    db: Data<DbExecutor>,
+    query: Query<JournalParams>,
+    id: Identity,
+) -> HttpResponse {
+    let dao: JournalDao = db.as_dao();
+    match dao
+        .list(
+            id.identity,
+            query.period_id,
+            query.since.map(|t| t.naive_utc()),
+            query.until.map(|t| t.naive_utc()),
+        )
+        .await
+    {
+        Ok(entries) => response::ok(entries),
+        Err(e) => response::server_error(&e),
+    }
+}
+
+async fn get_balances(
+    db: Data<DbExecutor>,
+    query: Query<BalanceParams>,
+    id: Identity,
+) -> HttpResponse {
+    let dao: JournalDao = db.as_dao();
+    if let Some(period_id) = query.period_id {
+        match dao.get_period(id.identity, period_id).await {
+            Ok(Some(_)) => (),
+            Ok(None) => return response::not_found(),
+            Err(e) => return response::server_error(&e),
+        }
+    }
+    match dao.up_to_period(id.identity, query.period_id).await {
+        Ok(entries) => response::ok(trial_balance(&entries)),
+        Err(e) => response::server_error(&e),
+    }
+}
+
+async fn get_periods(db: Data<DbExecutor>, id: Identity) -> HttpResponse {
+    let dao: JournalDao = db.as_dao();
+    match dao.periods(id.identity).await {
+        Ok(periods) => response::ok(periods),
+        Err(e) => response::server_error(&e),
+    }
+}
+
+async fn close_period(db: Data<DbExecutor>, body: Json<ClosePeriod>, id: Identity) -> HttpResponse {
+    let now = Utc::now();
+    let until = body.until.unwrap_or(now);
+    // Entries up to now may still be posted, so future can't be closed.
+    if until > now {
+        return response::bad_request(&"Period can't end in the future");
+    }
+
+    let dao: JournalDao = db.as_dao();
+    match dao.close_period(id.identity, until.naive_utc()).await {
+        Ok(period) => response::created(period),
+        Err(DbError::Integrity(e)) => response::bad_request(&e),
+        Err(e) => response::server_error(&e),
+    }
+}
+
+async fn export_period(
+    db: Data<DbExecutor>,
+    path: Path<PeriodId>,
+    query: Query<ExportParams>,
+    id: Identity,
+) -> HttpResponse {
+    let dao: JournalDao = db.as_dao();
+    match dao.get_period(id.identity, path.period_id).await {
+        Ok(Some(_)) => (),
+        Ok(None) => return response::not_found(),
+        Err(e) => return response::server_error(&e),
+    }
+    let entries = match dao
+        .list(id.identity, Some(path.period_id), None, None)
+        .await
+    {
+        Ok(entries) => entries,
+        Err(e) => return response::server_error(&e),
+    };
+
+    match query.format.as_deref().unwrap_or("csv") {
+        "csv" => HttpResponse::Ok()
+            .content_type("text/csv")
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"journal-{}.csv\"", path.period_id),
+            ))
+            .body(to_csv(&entries)),
+        "json" => response::ok(entries),
+        format => response::bad_request(&format!("Unsupported export format: {}", format)),
+    }
+}
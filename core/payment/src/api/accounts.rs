@@ -10,9 +10,6 @@ use ya_service_bus::{typed as bus, RpcEndpoint};
 // Local uses
 use crate::utils::*;
 
-use actix_web::web::Data;
-use ya_persistence::executor::DbExecutor;
-
 pub fn register_endpoints(scope: Scope) -> Scope {
     scope
         .service(get_provider_accounts)
@@ -21,31 +18,29 @@ pub fn register_endpoints(scope: Scope) -> Scope {
 
 #[actix_web::get("/providerAccounts")]
 async fn get_provider_accounts(id: Identity) -> HttpResponse {
-    let node_id = id.identity.to_string();
-    let all_accounts = match bus::service(LOCAL_SERVICE).send(GetAccounts {}).await {
-        Ok(Ok(accounts)) => accounts,
-        Ok(Err(e)) => return response::server_error(&e),
-        Err(e) => return response::server_error(&e),
+    let recv_accounts: Vec<Account> = match owned_accounts(&id).await {
+        Ok(accounts) => accounts.into_iter().filter(|a| a.receive).collect(),
+        Err(response) => return response,
     };
-    let recv_accounts: Vec<Account> = all_accounts
-        .into_iter()
-        .filter(|account| account.receive)
-        //.filter(|account| account.address == node_id) // TODO: Implement proper account permission system
-        .collect();
     response::ok(recv_accounts)
 }
 
 #[actix_web::get("/requestorAccounts")]
-async fn get_requestor_accounts(db: Data<DbExecutor>, id: Identity) -> HttpResponse {
-    let node_id = id.identity.to_string();
-    let all_accounts = match bus::service(LOCAL_SERVICE).send(GetAccounts {}).await {
-        Ok(Ok(accounts)) => accounts,
-        Ok(Err(e)) => return response::server_error(&e),
-        Err(e) => return response::server_error(&e),
-    };
-    let recv_accounts: Vec<Account> = all_accounts
-        .into_iter()
-        .filter(|account| account.address == node_id) // TODO: Implement proper account permission system
-        .collect();
-    response::ok(recv_accounts)
+async fn get_requestor_accounts(id: Identity) -> HttpResponse {
+    match owned_accounts(&id).await {
+        Ok(accounts) => response::ok(accounts),
+        Err(response) => response,
+    }
+}
+
+/// Accounts are isolated per identity, so each caller only sees its own accounts.
+async fn owned_accounts(id: &Identity) -> Result<Vec<Account>, HttpResponse> {
+    match bus::service(LOCAL_SERVICE)
+        .send(GetAccounts::owned_by(id.identity))
+        .await
+    {
+        Ok(Ok(accounts)) => Ok(accounts.into_iter().map(|a| a.account).collect()),
+        Ok(Err(e)) => Err(response::server_error(&e)),
+        Err(e) => Err(response::server_error(&e)),
+    }
 }
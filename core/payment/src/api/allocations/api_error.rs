@@ -5,6 +5,7 @@ use http::Uri;
 use problem_details::ProblemDetails;
 use serde::Serialize;
 use serde_json::Value;
+use ya_client_model::NodeId;
 use ya_core_model::driver::ValidateAllocationResult;
 
 pub type PaymentProblemDetails = ProblemDetails<BTreeMap<String, Value>>;
@@ -269,6 +270,37 @@ pub fn account_not_registered(
         .json(details)
 }
 
+pub fn account_not_owned(
+    request_body: &impl Serialize,
+    address: String,
+    identity: NodeId,
+) -> HttpResponse {
+    let mut extensions = BTreeMap::new();
+
+    extensions.insert(
+        "requestBody".to_string(),
+        serde_json::to_value(request_body).unwrap_or(Value::String(
+            "[requestBody serialization failed]".to_string(),
+        )),
+    );
+
+    extensions.insert("address".to_string(), Value::String(address.clone()));
+    extensions.insert("identity".to_string(), Value::String(identity.to_string()));
+
+    let details = ProblemDetails::new()
+        .with_type(Uri::from_static(
+            "/payment-api/v1/allocations/account-not-owned",
+        ))
+        .with_detail(format!(
+            "Account {address} doesn't belong to identity {identity}"
+        ))
+        .with_extensions(extensions);
+
+    HttpResponse::Forbidden()
+        .insert_header(CONTENT_TYPE_PROBLEM_JSON)
+        .json(details)
+}
+
 pub fn bad_platform_parameter(
     request_body: &impl Serialize,
     error: &impl Serialize,
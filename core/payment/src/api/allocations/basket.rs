@@ -0,0 +1,107 @@
+//! Fallback platforms of multi-currency allocations, see [`crate::basket`].
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use ya_client_model::payment::allocation::Deposit;
+use ya_core_model::driver::ValidateAllocationResult;
+use ya_core_model::payment::local::{ValidateAllocation, BUS_ID as LOCAL_SERVICE};
+use ya_service_bus::{typed as bus, RpcEndpoint};
+
+use super::platform_triple::PaymentPlatformTriple;
+use crate::basket::same_token;
+use crate::error::Error;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BasketParams {
+    /// Comma separated payment platforms used after the primary one, in order of preference.
+    #[serde(default)]
+    pub fallback_platforms: Option<String>,
+}
+
+impl BasketParams {
+    pub fn parse(&self, primary: &PaymentPlatformTriple) -> anyhow::Result<Vec<String>> {
+        let primary = primary.to_string();
+        let mut platforms: Vec<String> = vec![];
+        for name in self
+            .fallback_platforms
+            .iter()
+            .flat_map(|platforms| platforms.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let platform = PaymentPlatformTriple::from_payment_platform_str(name)?.to_string();
+            if !same_token(&primary, &platform) {
+                anyhow::bail!(
+                    "Fallback platform {} has to use driver and token of {}",
+                    platform,
+                    primary
+                );
+            }
+            if platform == primary || platforms.contains(&platform) {
+                anyhow::bail!("Platform {} listed twice", platform);
+            }
+            platforms.push(platform);
+        }
+        Ok(platforms)
+    }
+}
+
+/// Validates `amount` against the platforms together. Funds missing on a platform are
+/// looked for on the next one. Amounts taken from fallback platforms (all but the first
+/// one) are returned along with the result, so they stay reserved for the allocation.
+/// The first platform covers the rest.
+pub async fn validate(
+    platforms: &[String],
+    address: &str,
+    amount: BigDecimal,
+    timeout: Option<DateTime<Utc>>,
+    deposit: Option<Deposit>,
+    new_allocation: bool,
+) -> Result<(ValidateAllocationResult, Vec<(String, BigDecimal)>), Error> {
+    let mut missing = amount.clone();
+    let mut reserved = BigDecimal::zero();
+    let mut shares = vec![];
+
+    for (index, platform) in platforms.iter().enumerate() {
+        let fallback = index > 0;
+        let msg = ValidateAllocation {
+            platform: platform.clone(),
+            address: address.to_string(),
+            amount: missing.clone(),
+            timeout,
+            deposit: deposit.clone(),
+            new_allocation,
+        };
+        match bus::service(LOCAL_SERVICE).send(msg).await?? {
+            ValidateAllocationResult::InsufficientAccountFunds {
+                available_funds,
+                reserved_funds,
+                ..
+            } => {
+                if available_funds > BigDecimal::zero() {
+                    missing -= &available_funds;
+                    if fallback {
+                        shares.push((platform.clone(), available_funds));
+                    }
+                }
+                reserved += reserved_funds;
+            }
+            ValidateAllocationResult::Valid => {
+                if fallback && !missing.is_zero() {
+                    shares.push((platform.clone(), missing));
+                }
+                return Ok((ValidateAllocationResult::Valid, shares));
+            }
+            result => return Ok((result, vec![])),
+        }
+    }
+
+    let result = ValidateAllocationResult::InsufficientAccountFunds {
+        available_funds: &amount - missing,
+        requested_funds: amount,
+        reserved_funds: reserved,
+    };
+    Ok((result, vec![]))
+}
@@ -1,8 +1,9 @@
+use std::collections::HashMap;
 use std::time::Duration;
 // External crates
 use actix_web::web::{delete, get, post, put, Data, Json, Path, Query};
 use actix_web::{HttpResponse, Scope};
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, Zero};
 use chrono::{DateTime, Utc};
 use serde_json::value::Value::Null;
 use ya_client_model::NodeId;
@@ -12,8 +13,7 @@ use ya_agreement_utils::{ClauseOperator, ConstraintKey, Constraints};
 use ya_client_model::payment::allocation::PaymentPlatformEnum;
 use ya_client_model::payment::*;
 use ya_core_model::payment::local::{
-    DriverName, NetworkName, ReleaseDeposit, ValidateAllocation, ValidateAllocationError,
-    BUS_ID as LOCAL_SERVICE,
+    DriverName, NetworkName, ReleaseDeposit, ValidateAllocationError, BUS_ID as LOCAL_SERVICE,
 };
 use ya_core_model::payment::RpcMessageError;
 use ya_persistence::executor::DbExecutor;
@@ -31,9 +31,11 @@ const DEFAULT_MAINNET_NETWORK: NetworkName = NetworkName::Polygon;
 const DEFAULT_PAYMENT_DRIVER: DriverName = DriverName::Erc20;
 
 mod api_error;
+mod basket;
 mod platform_triple;
 mod token_name;
 
+use basket::BasketParams;
 use platform_triple::PaymentPlatformTriple;
 
 pub fn register_endpoints(scope: Scope) -> Scope {
@@ -41,6 +43,10 @@ pub fn register_endpoints(scope: Scope) -> Scope {
         .route("/allocations", post().to(create_allocation))
         .route("/allocations", get().to(get_allocations))
         .route("/allocations/{allocation_id}", get().to(get_allocation))
+        .route(
+            "/allocations/{allocation_id}/platforms",
+            get().to(get_allocation_platforms),
+        )
         .route("/allocations/{allocation_id}", put().to(amend_allocation))
         .route(
             "/allocations/{allocation_id}",
@@ -52,6 +58,7 @@ pub fn register_endpoints(scope: Scope) -> Scope {
 async fn create_allocation(
     db: Data<DbExecutor>,
     body: Json<NewAllocation>,
+    basket_params: Query<BasketParams>,
     id: Identity,
 ) -> HttpResponse {
     let allocation = body.into_inner();
@@ -97,6 +104,29 @@ async fn create_allocation(
         .clone()
         .unwrap_or_else(|| node_id.to_string());
 
+    if address.parse::<NodeId>().ok() != Some(node_id) {
+        return api_error::account_not_owned(&allocation, address, node_id);
+    }
+
+    let fallback_platforms = match basket_params.parse(&payment_triple) {
+        Ok(platforms) if !platforms.is_empty() && allocation.deposit.is_some() => {
+            return api_error::bad_platform_parameter(
+                &allocation,
+                &"Allocation with deposit can't have fallback platforms",
+                &platforms,
+            );
+        }
+        Ok(platforms) => platforms,
+        Err(err) => {
+            log::error!("Fallback payment platforms parse failed: {err}");
+            return api_error::bad_platform_parameter(
+                &allocation,
+                &err.to_string(),
+                &basket_params.fallback_platforms,
+            );
+        }
+    };
+
     log::info!(
         "Creating allocation for payment platform: {}",
         payment_triple
@@ -114,18 +144,34 @@ async fn create_allocation(
     if let Err(err) = init_account(acc).await {
         return api_error::server_error(&allocation, &err.to_string());
     }
+    for platform in &fallback_platforms {
+        let acc = Account {
+            driver: payment_triple.driver().to_string(),
+            address: address.clone(),
+            network: platform.split('-').nth(1).map(ToOwned::to_owned),
+            token: None,
+            send: true,
+            receive: false,
+        };
+        if let Err(err) = init_account(acc).await {
+            return api_error::server_error(&allocation, &err.to_string());
+        }
+    }
 
-    let validate_msg = ValidateAllocation {
-        platform: payment_triple.to_string(),
-        address: address.clone(),
-        amount: allocation.total_amount.clone(),
-        timeout: allocation.timeout,
-        deposit: allocation.deposit.clone(),
-        new_allocation: true,
-    };
+    let platforms = std::iter::once(payment_triple.to_string())
+        .chain(fallback_platforms.iter().cloned())
+        .collect::<Vec<_>>();
+    let validation = basket::validate(
+        &platforms,
+        &address,
+        allocation.total_amount.clone(),
+        allocation.timeout,
+        allocation.deposit.clone(),
+        true,
+    );
 
-    match async move { Ok(bus::service(LOCAL_SERVICE).send(validate_msg).await??) }.await {
-        Ok(result) => {
+    let mut shares = match validation.await {
+        Ok((result, shares)) => {
             if let Some(error_response) = api_error::try_from_validation(
                 result,
                 &allocation,
@@ -134,6 +180,7 @@ async fn create_allocation(
             ) {
                 return error_response;
             }
+            shares.into_iter().collect::<HashMap<_, _>>()
         }
         Err(Error::Rpc(RpcMessageError::ValidateAllocation(
             ValidateAllocationError::AccountNotRegistered,
@@ -151,7 +198,14 @@ async fn create_allocation(
             );
         }
         Err(e) => return api_error::server_error(&allocation, &e.to_string()),
-    }
+    };
+    let fallback_platforms = fallback_platforms
+        .into_iter()
+        .map(|platform| {
+            let share = shares.remove(&platform).unwrap_or_else(BigDecimal::zero);
+            (platform, share)
+        })
+        .collect();
 
     let dao = db.as_dao::<AllocationDao>();
 
@@ -161,6 +215,7 @@ async fn create_allocation(
             node_id,
             payment_triple.to_string(),
             address,
+            fallback_platforms,
         )
         .await
     {
@@ -226,6 +281,41 @@ async fn get_allocation(
     }
 }
 
+/// Payment platforms of the allocation, starting with the primary one.
+async fn get_allocation_platforms(
+    db: Data<DbExecutor>,
+    path: Path<params::AllocationId>,
+    id: Identity,
+) -> HttpResponse {
+    let allocation_id = path.allocation_id.clone();
+    let node_id = id.identity;
+    let dao: AllocationDao = db.as_dao();
+
+    let allocation = match dao.get(allocation_id.clone(), node_id).await {
+        Ok(AllocationStatus::Active(allocation)) => allocation,
+        Ok(AllocationStatus::Gone) => {
+            return response::gone(&format!(
+                "Allocation {} has been already released",
+                allocation_id
+            ))
+        }
+        Ok(AllocationStatus::NotFound) => return response::not_found(),
+        Err(e) => return response::server_error(&e),
+    };
+
+    match dao
+        .get_fallback_platforms(vec![allocation_id.clone()])
+        .await
+    {
+        Ok(mut fallbacks) => response::ok(
+            std::iter::once(allocation.payment_platform)
+                .chain(fallbacks.remove(&allocation_id).unwrap_or_default())
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => response::server_error(&e),
+    }
+}
+
 fn amend_allocation_fields(
     old_allocation: Allocation,
     update: AllocationUpdate,
@@ -294,26 +384,35 @@ async fn amend_allocation(
         };
 
     let payment_triple = amended_allocation.payment_platform.clone();
+    let platforms = match dao
+        .get_fallback_platforms(vec![allocation_id.clone()])
+        .await
+    {
+        Ok(mut fallbacks) => std::iter::once(payment_triple.clone())
+            .chain(fallbacks.remove(&allocation_id).unwrap_or_default())
+            .collect::<Vec<_>>(),
+        Err(e) => return api_error::server_error(&allocation_update, &e.to_string()),
+    };
 
     // validation will take into account all existing allocation, including the one
     // being currently modified. This means we only need to validate the increase.
     let amount_to_validate =
         amended_allocation.total_amount.clone() - &current_allocation.total_amount;
 
-    let validate_msg = ValidateAllocation {
-        platform: amended_allocation.payment_platform.clone(),
-        address: amended_allocation.address.clone(),
-        amount: if amount_to_validate > BigDecimal::from(0) {
+    let validation = basket::validate(
+        &platforms,
+        &amended_allocation.address,
+        if amount_to_validate > BigDecimal::from(0) {
             amount_to_validate
         } else {
             0.into()
         },
-        timeout: amended_allocation.timeout,
-        deposit: amended_allocation.deposit.clone(),
-        new_allocation: false,
-    };
-    match async move { Ok(bus::service(LOCAL_SERVICE).send(validate_msg).await??) }.await {
-        Ok(result) => {
+        amended_allocation.timeout,
+        amended_allocation.deposit.clone(),
+        false,
+    );
+    let fallback_increase = match validation.await {
+        Ok((result, shares)) => {
             if let Some(error_response) = api_error::try_from_validation(
                 result,
                 &allocation_update,
@@ -322,6 +421,7 @@ async fn amend_allocation(
             ) {
                 return error_response;
             }
+            shares
         }
         Err(Error::Rpc(RpcMessageError::ValidateAllocation(
             ValidateAllocationError::AccountNotRegistered,
@@ -339,9 +439,12 @@ async fn amend_allocation(
             );
         }
         Err(e) => return api_error::server_error(&allocation_update, &e.to_string()),
-    }
+    };
 
-    match dao.replace(amended_allocation, node_id).await {
+    match dao
+        .replace(amended_allocation, node_id, fallback_increase)
+        .await
+    {
         Ok(true) => {}
         Ok(false) => {
             return api_error::server_error(
@@ -410,6 +513,8 @@ async fn get_demand_decorations(
     }
 
     // Populate payment platform properties / constraint.
+    // Agreements are made on primary platforms only. Fallback platforms are used
+    // at payment time, if the Provider's Offer accepts them.
     let mut properties: Vec<MarketProperty> = allocations
         .iter()
         .map(|allocation| MarketProperty {
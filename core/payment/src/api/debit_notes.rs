@@ -9,6 +9,7 @@ use std::time::Instant;
 // Workspace uses
 use metrics::{counter, timing};
 use ya_client_model::payment::*;
+use ya_core_model::events::EventPayload;
 use ya_core_model::payment::local::{SchedulePayment, BUS_ID as LOCAL_SERVICE};
 use ya_core_model::payment::public::{
     AcceptDebitNote, AcceptRejectError, SendDebitNote, SendError, BUS_ID as PUBLIC_SERVICE,
@@ -477,6 +478,12 @@ async fn accept_debit_note(
                     activity_id
                 );
                 counter!("payment.debit_notes.requestor.accepted", 1);
+                ya_events::publish(
+                    Some(node_id),
+                    EventPayload::DebitNoteAccepted {
+                        debit_note_id: path.debit_note_id.clone(),
+                    },
+                );
                 response::ok(Null)
             }
             Ok(Err(Error::Rpc(RpcMessageError::AcceptReject(AcceptRejectError::BadRequest(
@@ -5,7 +5,9 @@ use std::sync::Mutex as StdMutex;
 use tokio::sync::Mutex as TokioMutex;
 
 /// Registry of locks for agreements
-pub(super) struct AgreementLock {
+///
+/// Single instance is shared by REST API workers and automatic invoice acceptance.
+pub struct AgreementLock {
     locks: StdMutex<HashMap<String, Arc<TokioMutex<()>>>>,
 }
 
@@ -18,7 +20,7 @@ impl AgreementLock {
     /// Take a lock for a given agreement.
     ///
     /// The entry in the internal registry will be automatically cleaned up.
-    pub async fn lock(self: &Arc<Self>, agreement: String) -> AgreementLockGuard {
+    pub(crate) async fn lock(self: &Arc<Self>, agreement: String) -> AgreementLockGuard {
         let lock = Arc::clone(
             self.locks
                 .lock()
@@ -47,7 +49,7 @@ impl Default for AgreementLock {
 ///
 /// For use in REST API only. Motivated by a need to synchronize debit note and
 /// invoice acceptances.
-pub(super) struct AgreementLockGuard {
+pub(crate) struct AgreementLockGuard {
     guard: Option<tokio::sync::OwnedMutexGuard<()>>,
     lock_map: Arc<AgreementLock>,
 }
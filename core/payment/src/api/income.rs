@@ -0,0 +1,71 @@
+// External crates
+use actix_web::web::{get, Data, Query};
+use actix_web::{HttpResponse, Scope};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+// Workspace uses
+use ya_client_model::market::agreement::State as AgreementState;
+use ya_persistence::executor::DbExecutor;
+use ya_persistence::types::Role;
+use ya_service_api_web::middleware::Identity;
+
+// Local uses
+use crate::dao::*;
+use crate::income::IncomeDashboard;
+use crate::utils::*;
+
+const DEFAULT_EARNINGS_DAYS: u32 = 30;
+
+pub fn register_endpoints(scope: Scope) -> Scope {
+    scope.route("/income", get().to(get_income))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IncomeParams {
+    /// Number of days of earnings history.
+    days: Option<u32>,
+}
+
+async fn get_income(
+    db: Data<DbExecutor>,
+    query: Query<IncomeParams>,
+    id: Identity,
+) -> HttpResponse {
+    let node_id = id.identity;
+    let now = Utc::now().naive_utc();
+    let since = now - Duration::days(query.days.unwrap_or(DEFAULT_EARNINGS_DAYS).into());
+
+    // Terminated and expired Agreements won't be invoiced any more.
+    let approved = match list_agreement_ids(AgreementState::Approved).await {
+        Ok(agreement_ids) => agreement_ids,
+        Err(e) => return response::server_error(&e),
+    };
+    let agreements = match db
+        .as_dao::<AgreementDao>()
+        .uninvoiced(node_id, Role::Provider, approved)
+        .await
+    {
+        Ok(agreements) => agreements,
+        Err(e) => return response::server_error(&e),
+    };
+    let invoices = match db
+        .as_dao::<InvoiceDao>()
+        .unpaid(node_id, Role::Provider)
+        .await
+    {
+        Ok(invoices) => invoices,
+        Err(e) => return response::server_error(&e),
+    };
+    let payments = match db
+        .as_dao::<PaymentDao>()
+        .list_since(node_id, Role::Provider, since)
+        .await
+    {
+        Ok(payments) => payments,
+        Err(e) => return response::server_error(&e),
+    };
+
+    response::ok(IncomeDashboard::new(agreements, invoices, payments, now))
+}
@@ -9,6 +9,8 @@ use std::time::Instant;
 // Workspace uses
 use metrics::{counter, timing};
 use ya_client_model::payment::*;
+use ya_client_model::NodeId;
+use ya_core_model::events::EventPayload;
 use ya_core_model::payment::local::{SchedulePayment, BUS_ID as LOCAL_SERVICE};
 use ya_core_model::payment::public::{
     AcceptInvoice, AcceptRejectError, CancelError, CancelInvoice, RejectInvoiceV2, SendError,
@@ -26,6 +28,7 @@ use ya_service_bus::{typed as bus, RpcEndpoint};
 use super::guard::AgreementLock;
 use crate::dao::*;
 use crate::error::{DbError, Error};
+use crate::fiat::{FiatAnnotator, FiatEntity};
 use crate::payment_sync::SYNC_NOTIFS_NOTIFY;
 use crate::utils::provider::get_agreement_id;
 use crate::utils::*;
@@ -185,7 +188,12 @@ async fn get_invoice_events(
 
 // Provider
 
-async fn issue_invoice(db: Data<DbExecutor>, body: Json<NewInvoice>, id: Identity) -> HttpResponse {
+async fn issue_invoice(
+    db: Data<DbExecutor>,
+    fiat: Data<FiatAnnotator>,
+    body: Json<NewInvoice>,
+    id: Identity,
+) -> HttpResponse {
     let invoice = body.into_inner();
     let agreement_id = invoice.agreement_id.clone();
     let activity_ids = invoice.activity_ids.clone().unwrap_or_default();
@@ -235,6 +243,16 @@ async fn issue_invoice(db: Data<DbExecutor>, body: Json<NewInvoice>, id: Identit
         let dao: InvoiceDao = db.as_dao();
         let invoice_id = dao.create_new(invoice, node_id).await?;
         let invoice = dao.get(invoice_id.clone(), node_id).await?;
+        if let Some(invoice) = &invoice {
+            fiat.stamp(
+                db.get_ref().clone(),
+                node_id,
+                FiatEntity::Invoice,
+                invoice_id.clone(),
+                invoice.payment_platform.clone(),
+                invoice.amount.clone(),
+            );
+        }
 
         log::info!("Invoice [{invoice_id}] for Agreement [{agreement_id}] issued.");
         counter!("payment.invoices.provider.issued", 1);
@@ -407,39 +425,104 @@ async fn accept_invoice(
 ) -> HttpResponse {
     let start = Instant::now();
 
-    let invoice_id = path.invoice_id.clone();
-    let node_id = id.identity;
-    let acceptance = body.into_inner();
-    let allocation_id = acceptance.allocation_id.clone();
-
-    log::debug!("Requested accept invoice [{}]", invoice_id);
+    log::debug!("Requested accept invoice [{}]", path.invoice_id);
     counter!("payment.invoices.requestor.accepted.call", 1);
 
+    let timeout = query.timeout.unwrap_or(params::DEFAULT_ACK_TIMEOUT);
+    let result = match accept(
+        &db,
+        &agreement_lock,
+        path.invoice_id.clone(),
+        id.identity,
+        body.into_inner(),
+        timeout,
+    )
+    .await
+    {
+        Ok(()) => response::ok(Null),
+        Err(e) => e.response(),
+    };
+
+    timing!(
+        "payment.invoices.requestor.accepted.time",
+        start,
+        Instant::now()
+    );
+    result
+}
+
+/// Failure of invoice acceptance.
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum AcceptError {
+    #[error("Invoice not found")]
+    NotFound,
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Gone(String),
+    #[error("{0}")]
+    Server(String),
+    #[error("Timeout accepting Invoice on remote Node.")]
+    Timeout,
+}
+
+impl AcceptError {
+    fn server(e: impl ToString) -> Self {
+        AcceptError::Server(e.to_string())
+    }
+
+    fn response(&self) -> HttpResponse {
+        match self {
+            AcceptError::NotFound => response::not_found(),
+            AcceptError::BadRequest(e) => response::bad_request(e),
+            AcceptError::Gone(e) => response::gone(e),
+            AcceptError::Server(e) => response::server_error(e),
+            AcceptError::Timeout => response::timeout(self),
+        }
+    }
+}
+
+/// Accepts the received invoice and schedules its payment from the allocation.
+/// Invoices already accepted are left intact.
+pub(crate) async fn accept(
+    db: &DbExecutor,
+    agreement_lock: &Arc<AgreementLock>,
+    invoice_id: String,
+    node_id: NodeId,
+    acceptance: Acceptance,
+    timeout: f64,
+) -> Result<(), AcceptError> {
+    let allocation_id = acceptance.allocation_id.clone();
+
     let dao: InvoiceDao = db.as_dao();
     let sync_dao: SyncNotifsDao = db.as_dao();
 
     log::trace!("Querying DB for Invoice [{}]", invoice_id);
     let invoice = match dao.get(invoice_id.clone(), node_id).await {
         Ok(Some(invoice)) => invoice,
-        Ok(None) => return response::not_found(),
-        Err(e) => return response::server_error(&e),
+        Ok(None) => return Err(AcceptError::NotFound),
+        Err(e) => return Err(AcceptError::server(e)),
     };
 
     // Required to serialize complex DB access patterns related to debit note / invoice acceptances.
     let _agreement_lock = agreement_lock.lock(invoice.agreement_id.clone());
 
     if invoice.amount != acceptance.total_amount_accepted {
-        return response::bad_request(&"Invalid amount accepted");
+        return Err(AcceptError::BadRequest(
+            "Invalid amount accepted".to_string(),
+        ));
     }
 
     match invoice.status {
         DocumentStatus::Received => (),
         DocumentStatus::Rejected => (),
         DocumentStatus::Failed => (),
-        DocumentStatus::Accepted => return response::ok(Null),
-        DocumentStatus::Settled => return response::ok(Null),
-        DocumentStatus::Cancelled => return response::bad_request(&"Invoice cancelled"),
-        DocumentStatus::Issued => return response::server_error(&"Illegal status: issued"),
+        DocumentStatus::Accepted => return Ok(()),
+        DocumentStatus::Settled => return Ok(()),
+        DocumentStatus::Cancelled => {
+            return Err(AcceptError::BadRequest("Invoice cancelled".to_string()))
+        }
+        DocumentStatus::Issued => return Err(AcceptError::server("Illegal status: issued")),
     }
 
     let agreement_id = invoice.agreement_id.clone();
@@ -455,9 +538,12 @@ async fn accept_invoice(
     {
         Ok(Some(agreement)) => agreement,
         Ok(None) => {
-            return response::server_error(&format!("Agreement {} not found", agreement_id))
+            return Err(AcceptError::server(format!(
+                "Agreement {} not found",
+                agreement_id
+            )))
         }
-        Err(e) => return response::server_error(&e),
+        Err(e) => return Err(AcceptError::server(e)),
     };
     // OK when invoice.amount is greater than or equal to agreement.amount_accepted
     if invoice.amount < agreement.total_amount_accepted.0 {
@@ -467,7 +553,7 @@ async fn accept_invoice(
             &invoice_id, &invoice.amount, &agreement.total_amount_accepted
         );
         log::warn!("{}", msg);
-        return response::bad_request(&msg);
+        return Err(AcceptError::BadRequest(msg));
     }
     let amount_to_pay = &invoice.amount - &agreement.total_amount_scheduled.0;
 
@@ -483,15 +569,18 @@ async fn accept_invoice(
     {
         Ok(AllocationStatus::Active(allocation)) => allocation,
         Ok(AllocationStatus::Gone) => {
-            return response::gone(&format!(
+            return Err(AcceptError::Gone(format!(
                 "Allocation {} has been already released",
                 allocation_id
-            ))
+            )))
         }
         Ok(AllocationStatus::NotFound) => {
-            return response::bad_request(&format!("Allocation {} not found", allocation_id))
+            return Err(AcceptError::BadRequest(format!(
+                "Allocation {} not found",
+                allocation_id
+            )))
         }
-        Err(e) => return response::server_error(&e),
+        Err(e) => return Err(AcceptError::server(e)),
     };
     if amount_to_pay > allocation.remaining_amount {
         let msg = format!(
@@ -500,72 +589,69 @@ async fn accept_invoice(
         );
 
         counter!("payment.invoices.requestor.not-enough-funds", 1);
-        return response::bad_request(&msg);
+        return Err(AcceptError::BadRequest(msg));
     }
 
-    let timeout = query.timeout.unwrap_or(params::DEFAULT_ACK_TIMEOUT);
-    let result = async move {
-        let issuer_id = invoice.issuer_id;
-        let accept_msg = AcceptInvoice::new(invoice_id.clone(), acceptance, issuer_id);
-        let schedule_msg = SchedulePayment::from_invoice(invoice, allocation_id, amount_to_pay);
-        match async move {
-            // Schedule payment (will be none for amount=0, which is OK)
-            if let Some(msg) = schedule_msg {
-                log::trace!("Calling SchedulePayment [{}] locally", invoice_id);
-                bus::service(LOCAL_SERVICE).send(msg).await??;
-            }
-
-            // Mark the invoice as accepted in DB
-            log::trace!("Accepting Invoice [{}] in DB", invoice_id);
-            dao.accept(invoice_id.clone(), node_id).await?;
-            log::trace!("Invoice accepted successfully for [{}]", invoice_id);
-
-            log::debug!("Sending AcceptInvoice [{}] to [{}]", invoice_id, issuer_id);
-            let send_result = ya_net::from(node_id)
-                .to(issuer_id)
-                .service(PUBLIC_SERVICE)
-                .call(accept_msg)
-                .await;
+    let issuer_id = invoice.issuer_id;
+    let accept_msg = AcceptInvoice::new(invoice_id.clone(), acceptance, issuer_id);
+    let schedule_msg = SchedulePayment::from_invoice(invoice, allocation_id, amount_to_pay);
+    let accepted_id = invoice_id.clone();
+    match async move {
+        // Schedule payment (will be none for amount=0, which is OK)
+        if let Some(msg) = schedule_msg {
+            log::trace!("Calling SchedulePayment [{}] locally", invoice_id);
+            bus::service(LOCAL_SERVICE).send(msg).await??;
+        }
 
-            if let Ok(response) = send_result {
-                log::debug!("AcceptInvoice delivered for [{invoice_id}]");
-                dao.mark_accept_sent(invoice_id.clone(), node_id).await?;
-                response?;
-            } else {
-                log::debug!("AcceptInvoice not delivered for [{invoice_id}]");
-                sync_dao.upsert(issuer_id).await?;
-                SYNC_NOTIFS_NOTIFY.notify_one();
-            }
+        // Mark the invoice as accepted in DB
+        log::trace!("Accepting Invoice [{}] in DB", invoice_id);
+        dao.accept(invoice_id.clone(), node_id).await?;
+        log::trace!("Invoice accepted successfully for [{}]", invoice_id);
+
+        log::debug!("Sending AcceptInvoice [{}] to [{}]", invoice_id, issuer_id);
+        let send_result = ya_net::from(node_id)
+            .to(issuer_id)
+            .service(PUBLIC_SERVICE)
+            .call(accept_msg)
+            .await;
+
+        if let Ok(response) = send_result {
+            log::debug!("AcceptInvoice delivered for [{invoice_id}]");
+            dao.mark_accept_sent(invoice_id.clone(), node_id).await?;
+            response?;
+        } else {
+            log::debug!("AcceptInvoice not delivered for [{invoice_id}]");
+            sync_dao.upsert(issuer_id).await?;
+            SYNC_NOTIFS_NOTIFY.notify_one();
+        }
 
+        Ok(())
+    }
+    .timeout(Some(timeout))
+    .await
+    {
+        Ok(Ok(_)) => {
+            counter!("payment.invoices.requestor.accepted", 1);
+            log::info!(
+                "Invoice [{}] for Agreement [{}] accepted.",
+                accepted_id,
+                agreement_id
+            );
+            ya_events::publish(
+                Some(node_id),
+                EventPayload::InvoiceAccepted {
+                    invoice_id: accepted_id,
+                    agreement_id: agreement_id.to_string(),
+                },
+            );
             Ok(())
         }
-        .timeout(Some(timeout))
-        .await
-        {
-            Ok(Ok(_)) => {
-                counter!("payment.invoices.requestor.accepted", 1);
-                log::info!(
-                    "Invoice [{}] for Agreement [{}] accepted.",
-                    path.invoice_id,
-                    agreement_id
-                );
-                response::ok(Null)
-            }
-            Ok(Err(Error::Rpc(RpcMessageError::AcceptReject(AcceptRejectError::BadRequest(
-                e,
-            ))))) => response::bad_request(&e),
-            Ok(Err(e)) => response::server_error(&e),
-            Err(_) => response::timeout(&"Timeout accepting Invoice on remote Node."),
+        Ok(Err(Error::Rpc(RpcMessageError::AcceptReject(AcceptRejectError::BadRequest(e))))) => {
+            Err(AcceptError::BadRequest(e))
         }
+        Ok(Err(e)) => Err(AcceptError::server(e)),
+        Err(_) => Err(AcceptError::Timeout),
     }
-    .await;
-
-    timing!(
-        "payment.invoices.requestor.accepted.time",
-        start,
-        Instant::now()
-    );
-    result
 }
 
 async fn reject_invoice(
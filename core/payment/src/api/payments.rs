@@ -1,6 +1,8 @@
 // External crates
 use actix_web::web::{get, Data, Path, Query};
 use actix_web::{HttpResponse, Scope};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
 use std::str::FromStr;
 use ya_service_bus::typed::service;
 
@@ -22,6 +24,10 @@ pub fn register_endpoints(scope: Scope) -> Scope {
         .route("/payments", get().to(get_payments))
         .route("/payments/status", get().to(payment_status))
         .route("/payments/{payment_id}", get().to(get_payment))
+        .route(
+            "/payments/{payment_id}/receipt",
+            get().to(get_payment_receipt),
+        )
 }
 
 async fn get_payments(
@@ -87,6 +93,40 @@ async fn get_payment(
     }
 }
 
+/// Receipt in a form, which can be handed over to third parties.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReceiptResponse {
+    payment_id: String,
+    tx_hash: String,
+    issued_at: DateTime<Utc>,
+    /// Canonical JSON of the receipt covered by the signature.
+    signed_receipt: String,
+    /// Payer's signature of Sha3-256 hash of `signedReceipt`, hex encoded.
+    signature: String,
+}
+
+async fn get_payment_receipt(
+    db: Data<DbExecutor>,
+    path: Path<params::PaymentId>,
+    id: Identity,
+) -> HttpResponse {
+    let payment_id = path.payment_id.clone();
+    let node_id = id.identity;
+    let dao: PaymentReceiptDao = db.as_dao();
+    match dao.get(payment_id, node_id).await {
+        Ok(Some(receipt)) => response::ok(ReceiptResponse {
+            payment_id: receipt.payment_id,
+            tx_hash: receipt.tx_hash,
+            issued_at: Utc.from_utc_datetime(&receipt.issued_at),
+            signed_receipt: String::from_utf8_lossy(&receipt.signed_bytes).into_owned(),
+            signature: format!("0x{}", hex::encode(receipt.signature)),
+        }),
+        Ok(None) => response::not_found(),
+        Err(e) => response::server_error(&e),
+    }
+}
+
 async fn payment_status(
     db: Data<DbExecutor>,
     query: Query<params::DriverStatusParams>,
@@ -0,0 +1,155 @@
+//! Multi-currency allocations (token baskets).
+//!
+//! Allocation may list fallback payment platforms besides its primary one, e.g. prefer
+//! Polygon GLM and fall back to Ethereum mainnet GLM. Fallbacks use the same driver and
+//! token, so amounts are interchangeable. Allocation is valid, when the platforms
+//! together cover its amount. Share taken from a fallback platform stays reserved on it
+//! until paid out, the primary platform reserves the rest. Each payment is scheduled on the first platform of the
+//! basket, which has enough spendable funds and gas for the transaction fee, so
+//! platforms should be listed starting with the cheapest to transact on.
+//!
+//! Demands are decorated with primary platforms only, so Agreements are made on them.
+//! Payment leaves the platform of the Agreement only if the Provider accepts it: its
+//! Offer has to advertise the platform with the same address and support
+//! [`BASKET_PROTOCOL_VERSION`].
+use bigdecimal::{BigDecimal, Zero};
+use serde_json::Value;
+
+use ya_agreement_utils::agreement::{expand, TypedPointer};
+use ya_client_model::market::Agreement;
+
+/// Payment protocol version, since which Providers accept payments on any platform
+/// advertised in their Offer with the token of the Agreement.
+pub const BASKET_PROTOCOL_VERSION: u64 = 4;
+
+/// Driver and token of `driver-network-token` platform.
+fn driver_token(platform: &str) -> Option<(&str, &str)> {
+    let mut parts = platform.split('-');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(driver), Some(_network), Some(token), None) => Some((driver, token)),
+        _ => None,
+    }
+}
+
+/// Platforms differ only in network.
+pub fn same_token(platform: &str, other: &str) -> bool {
+    match (driver_token(platform), driver_token(other)) {
+        (Some(platform), Some(other)) => platform == other,
+        _ => false,
+    }
+}
+
+/// Provider's Offer accepts payments on `platform` to `payee_addr`.
+pub fn offer_accepts(agreement: &Agreement, platform: &str, payee_addr: &str) -> bool {
+    let offer = expand(agreement.offer.properties.clone());
+    let version = offer
+        .pointer("/golem/com/payment/protocol/version")
+        .as_typed(Value::as_u64)
+        .unwrap_or(0);
+    let address = offer
+        .pointer(&format!("/golem/com/payment/platform/{}/address", platform))
+        .as_typed(Value::as_str);
+
+    version >= BASKET_PROTOCOL_VERSION
+        && matches!(address, Ok(address) if address.eq_ignore_ascii_case(payee_addr))
+}
+
+/// Payer account on a platform of the basket.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub platform: String,
+    /// Token balance without funds reserved by scheduled payments.
+    pub spendable: BigDecimal,
+    /// Gas balance, if the driver reports it.
+    pub gas: Option<BigDecimal>,
+}
+
+impl Candidate {
+    /// Account has enough spendable funds and some gas to pay `amount`.
+    pub fn can_pay(&self, amount: &BigDecimal) -> bool {
+        &self.spendable >= amount && self.gas.as_ref().map(|gas| !gas.is_zero()).unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+    use std::str::FromStr;
+    use ya_client_model::market::agreement::State;
+    use ya_client_model::market::{Demand, Offer};
+
+    const PAYEE: &str = "0xd39a168f0480b8502c2531b2ffd8588c592d713a";
+
+    fn agreement(version: u32) -> Agreement {
+        let demand = Demand::new(
+            json!({}),
+            "()".to_string(),
+            "demand_id".to_string(),
+            Default::default(),
+            Default::default(),
+        );
+        let offer = Offer::new(
+            json!({
+                "golem.com.payment.protocol.version": version,
+                "golem.com.payment.platform.erc20-polygon-glm.address": PAYEE,
+                "golem.com.payment.platform.erc20-mainnet-glm.address": PAYEE,
+            }),
+            "()".to_string(),
+            "offer_id".to_string(),
+            Default::default(),
+            Default::default(),
+        );
+        Agreement::new(
+            "agreement_id".to_string(),
+            demand,
+            offer,
+            Utc::now(),
+            State::Approved,
+            Utc::now(),
+        )
+    }
+
+    fn candidate(platform: &str, spendable: &str, gas: Option<&str>) -> Candidate {
+        Candidate {
+            platform: platform.to_string(),
+            spendable: BigDecimal::from_str(spendable).unwrap(),
+            gas: gas.map(|gas| BigDecimal::from_str(gas).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_same_token() {
+        assert!(same_token("erc20-polygon-glm", "erc20-mainnet-glm"));
+        assert!(!same_token("erc20-polygon-glm", "erc20-holesky-tglm"));
+        assert!(!same_token("erc20-polygon-glm", "glm"));
+    }
+
+    #[test]
+    fn test_offer_accepts() {
+        let basket_agreement = agreement(4);
+        assert!(offer_accepts(&basket_agreement, "erc20-mainnet-glm", PAYEE));
+        assert!(!offer_accepts(
+            &basket_agreement,
+            "erc20-mainnet-glm",
+            "0x00"
+        ));
+        assert!(!offer_accepts(
+            &basket_agreement,
+            "erc20-arbitrum-glm",
+            PAYEE
+        ));
+        // Older Providers verify payments against platform of the Agreement only.
+        assert!(!offer_accepts(&agreement(3), "erc20-mainnet-glm", PAYEE));
+    }
+
+    #[test]
+    fn test_candidate_can_pay() {
+        let amount = BigDecimal::from(10);
+        assert!(!candidate("erc20-polygon-glm", "5", Some("1")).can_pay(&amount));
+        assert!(candidate("erc20-polygon-glm", "5", Some("1")).can_pay(&BigDecimal::from(1)));
+        assert!(!candidate("erc20-mainnet-glm", "20", Some("0")).can_pay(&amount));
+        assert!(candidate("erc20-arbitrum-glm", "20", None).can_pay(&amount));
+    }
+}
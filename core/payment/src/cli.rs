@@ -1,13 +1,15 @@
 mod rpc;
 
 // External crates
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, Zero};
 use chrono::{DateTime, Utc};
 use serde_json::to_value;
 use std::str::FromStr;
 use std::time::UNIX_EPOCH;
 use structopt::*;
+use strum::VariantNames;
 use ya_client_model::payment::DriverStatusProperty;
+use ya_client_model::NodeId;
 use ya_core_model::payment::local::NetworkName;
 
 // Workspace uses
@@ -23,7 +25,7 @@ use crate::wallet;
 /// Payment driver management.
 #[derive(StructOpt, Debug)]
 pub enum DriverSubcommand {
-    /// List registered drivers, networks, tokens and platforms
+    /// List registered drivers, networks, tokens, platforms and driver capabilities
     List,
 
     /// Display status of the payment driver
@@ -40,6 +42,23 @@ pub enum DriverSubcommand {
         #[structopt(flatten)]
         rpc_params: RpcCommandParams,
     },
+
+    /// List transactions sent by the driver, which are not confirmed yet
+    Transactions {
+        #[structopt(flatten)]
+        account: pay::AccountCli,
+    },
+
+    /// Re-broadcast a stuck transaction with the same nonce and increased gas price
+    Bump {
+        #[structopt(long, possible_values = pay::DriverName::VARIANTS, default_value = pay::DriverName::Erc20.into())]
+        driver: pay::DriverName,
+        /// Transaction id, as listed by `driver transactions`
+        id: i64,
+        /// Gas price increase in percent (at least 10)
+        #[structopt(long, default_value = "20")]
+        gas_increase: u32,
+    },
 }
 
 /// Payment management.
@@ -90,7 +109,7 @@ pub enum PaymentCli {
         amount: String,
     },
 
-    /// Exit layer 2 (withdraw funds to Ethereum)
+    /// Withdraw funds to an external address
     Exit {
         #[structopt(flatten)]
         account: pay::AccountCli,
@@ -99,8 +118,36 @@ pub enum PaymentCli {
             help = "Optional address to exit to [default: <DEFAULT_IDENTITY>]"
         )]
         to_address: Option<String>,
-        #[structopt(long, help = "Optional amount to exit [default: <ALL_FUNDS>]")]
+        #[structopt(
+            long,
+            help = "Optional amount to exit from each network [default: <ALL_FUNDS>]"
+        )]
         amount: Option<String>,
+        /// Withdraw from all networks of the driver instead of `--network` only
+        #[structopt(long)]
+        all_networks: bool,
+        /// Withdraw without asking for confirmation
+        #[structopt(long)]
+        yes: bool,
+    },
+
+    /// Withdraw earnings to the configured address now, if the withdrawal policy allows it
+    AutoWithdraw {
+        /// Show what would be withdrawn without sending any transactions
+        #[structopt(long)]
+        dry_run: bool,
+    },
+
+    /// List history of automatic withdrawals
+    Withdrawals {
+        #[structopt(long, default_value = "20")]
+        limit: u32,
+    },
+
+    /// List marketplace fees charged on invoice payments
+    Fees {
+        #[structopt(long, default_value = "20")]
+        limit: u32,
     },
 
     Transfer {
@@ -127,7 +174,7 @@ pub enum PaymentCli {
 
         #[structopt(
             long,
-            help = "Use gasless forwarder, no gas on account is required",
+            help = "Let network's relayer pay for gas in exchange for a token fee, no gas on account is required",
             conflicts_with_all(&["gas-limit", "max-gas-price", "gas-price"])
         )]
         gasless: bool,
@@ -138,6 +185,35 @@ pub enum PaymentCli {
         command: InvoiceCommand,
     },
 
+    /// Export fiat values of invoices and payments for accounting
+    FiatExport {
+        #[structopt(long, help = "Export documents from the given period of time")]
+        last: Option<humantime::Duration>,
+    },
+
+    /// Export amounts paid for agreements including their share of transaction fees
+    AgreementCosts {
+        #[structopt(long, help = "Export payments from the given period of time")]
+        last: Option<humantime::Duration>,
+        /// Id of the agreement. Can be repeated, all agreements are exported by default.
+        #[structopt(long = "agreement", number_of_values = 1)]
+        agreement_ids: Vec<String>,
+    },
+
+    /// Rebuild payment state lost with the database from recent counterparties
+    ColdSync {
+        address: Option<String>,
+        /// Node id of a counterparty. Can be repeated.
+        #[structopt(long = "peer", required = true, number_of_values = 1)]
+        peers: Vec<NodeId>,
+        #[structopt(
+            long,
+            help = "Sync documents from the given period of time",
+            default_value = "30days"
+        )]
+        last: humantime::Duration,
+    },
+
     /// Clear all existing allocations
     ReleaseAllocations,
 }
@@ -147,6 +223,9 @@ pub enum InvoiceCommand {
     Status {
         #[structopt(long, help = "Display invoice status from the given period of time")]
         last: Option<humantime::Duration>,
+        /// Split invoice status into daily or weekly buckets
+        #[structopt(long, possible_values = pay::StatsInterval::VARIANTS)]
+        interval: Option<pay::StatsInterval>,
     },
 }
 
@@ -249,7 +328,7 @@ Typically operation should take less than 1 minute.
                         after_timestamp: timestamp,
                     })
                     .await??;
-                if ctx.json_output {
+                if ctx.structured_output() {
                     return CommandOutput::object(status);
                 }
 
@@ -269,6 +348,15 @@ Typically operation should take less than 1 minute.
                 } else {
                     format!("{:.4} {}", status.amount, status.token)
                 };
+                let token_info = match &status.fiat {
+                    Some(fiat) => format!(
+                        "{} ({:.2} {})",
+                        token_info,
+                        &status.amount * &fiat.rate,
+                        fiat.currency.to_uppercase()
+                    ),
+                    None => token_info,
+                };
 
                 let driver_status_props = bus::service(pay::BUS_ID)
                     .call(pay::PaymentDriverStatus {
@@ -300,10 +388,29 @@ Typically operation should take less than 1 minute.
                             CantSign { address, .. } => {
                                 header.push_str(&format!("Outstanding payments for address {address} cannot be signed. Is the relevant identity locked?\n"));
                             }
-                            InsufficientGas { needed_gas_est, .. } => {
-                                header.push_str(&format!("Not enough gas to send any more transactions. To send out all scheduled transactions additionally {}{} is needed.\n", needed_gas_est,
-                                                         status.clone().gas.map(|g|g.currency_short_name.clone()).unwrap_or("ETH".to_string())
-                                ));
+                            InsufficientGas {
+                                address,
+                                needed_gas_est,
+                                ..
+                            } => {
+                                let gas_currency = status
+                                    .gas
+                                    .as_ref()
+                                    .map(|g| g.currency_short_name.clone())
+                                    .unwrap_or_else(|| "ETH".to_string());
+                                header.push_str(&format!("Not enough gas to send any more transactions. To send out all scheduled transactions additionally {needed_gas_est}{gas_currency} is needed.\n"));
+                                if let Some(faucet_url) =
+                                    status.gas.as_ref().and_then(|g| g.faucet_url.as_ref())
+                                {
+                                    header.push_str(&format!("  Get {gas_currency} for {address} from the faucet: {faucet_url}\n"));
+                                }
+                                if let Some(bridge_url) =
+                                    status.gas.as_ref().and_then(|g| g.bridge_url.as_ref())
+                                {
+                                    header.push_str(&format!(
+                                        "  Bridge {gas_currency} to {address}: {bridge_url}\n"
+                                    ));
+                                }
                             }
                             InsufficientToken {
                                 needed_token_est, ..
@@ -367,15 +474,16 @@ Typically operation should take less than 1 minute.
             }
             PaymentCli::Accounts => {
                 let accounts = bus::service(pay::BUS_ID)
-                    .call(pay::GetAccounts {})
+                    .call(pay::GetAccounts::all())
                     .await??;
-                if ctx.json_output {
+                if ctx.structured_output() {
                     return CommandOutput::object(accounts);
                 }
 
                 Ok(ResponseTable {
                     columns: vec![
                         "address".to_owned(),
+                        "identity".to_owned(),
                         "driver".to_owned(),
                         "network".to_owned(),
                         "token".to_owned(),
@@ -384,9 +492,16 @@ Typically operation should take less than 1 minute.
                     ],
                     values: accounts
                         .into_iter()
-                        .map(|account| {
+                        .map(|owned| {
+                            let identity = match (owned.owner, owned.is_default) {
+                                (None, _) => "-".to_string(),
+                                (Some(_), true) => "default".to_string(),
+                                (Some(_), false) => owned.alias.unwrap_or_default(),
+                            };
+                            let account = owned.account;
                             serde_json::json! {[
                                 account.address,
+                                identity,
                                 account.driver,
                                 account.network,
                                 account.token,
@@ -400,19 +515,113 @@ Typically operation should take less than 1 minute.
             }
             PaymentCli::Invoice {
                 address,
-                command: InvoiceCommand::Status { last },
+                command: InvoiceCommand::Status { last, interval },
             } => {
                 let seconds = last.map(|d| d.as_secs() as i64).unwrap_or(3600);
+                let address = resolve_address(address).await?;
+                let mut msg = pay::GetInvoiceStats::new(
+                    address.parse()?,
+                    Utc::now() + chrono::Duration::seconds(-seconds),
+                );
+                if let Some(interval) = interval {
+                    msg = msg.with_interval(interval);
+                }
+                CommandOutput::object(bus::service(pay::BUS_ID).call(msg).await??)
+            }
+            PaymentCli::ColdSync {
+                address,
+                peers,
+                last,
+            } => {
                 let address = resolve_address(address).await?;
                 CommandOutput::object(
                     bus::service(pay::BUS_ID)
-                        .call(pay::GetInvoiceStats::new(
-                            address.parse()?,
-                            Utc::now() + chrono::Duration::seconds(-seconds),
-                        ))
+                        .call(pay::ColdSync {
+                            node_id: address.parse()?,
+                            peers,
+                            since: Utc::now() - chrono::Duration::seconds(last.as_secs() as i64),
+                        })
                         .await??,
                 )
             }
+            PaymentCli::FiatExport { last } => {
+                let since =
+                    last.map(|d| Utc::now() - chrono::Duration::seconds(d.as_secs() as i64));
+                let annotations = bus::service(pay::BUS_ID)
+                    .call(pay::GetFiatAnnotations {
+                        owner_id: None,
+                        since,
+                    })
+                    .await??;
+                if ctx.structured_output() {
+                    return CommandOutput::object(annotations);
+                }
+
+                Ok(ResponseTable {
+                    columns: vec![
+                        "timestamp".to_owned(),
+                        "type".to_owned(),
+                        "id".to_owned(),
+                        "platform".to_owned(),
+                        "amount".to_owned(),
+                        "rate".to_owned(),
+                        "fiat amount".to_owned(),
+                    ],
+                    values: annotations
+                        .into_iter()
+                        .map(|a| {
+                            serde_json::json! {[
+                                a.rate.timestamp.to_rfc3339(),
+                                a.entity_type,
+                                a.entity_id,
+                                a.platform,
+                                a.amount.to_string(),
+                                a.rate.rate.to_string(),
+                                format!("{:.2} {}", a.fiat_amount, a.rate.currency.to_uppercase()),
+                            ]}
+                        })
+                        .collect(),
+                }
+                .into())
+            }
+            PaymentCli::AgreementCosts {
+                last,
+                agreement_ids,
+            } => {
+                let since =
+                    last.map(|d| Utc::now() - chrono::Duration::seconds(d.as_secs() as i64));
+                let costs = bus::service(pay::BUS_ID)
+                    .call(pay::GetAgreementCosts {
+                        owner_id: None,
+                        agreement_ids,
+                        since,
+                    })
+                    .await??;
+                if ctx.structured_output() {
+                    return CommandOutput::object(costs);
+                }
+
+                Ok(ResponseTable {
+                    columns: vec![
+                        "agreement".to_owned(),
+                        "platform".to_owned(),
+                        "amount".to_owned(),
+                        "gas cost".to_owned(),
+                    ],
+                    values: costs
+                        .into_iter()
+                        .map(|c| {
+                            serde_json::json! {[
+                                c.agreement_id,
+                                c.platform,
+                                c.amount.to_string(),
+                                c.gas_cost.to_string(),
+                            ]}
+                        })
+                        .collect(),
+                }
+                .into())
+            }
             PaymentCli::Enter { account, amount } => CommandOutput::object(
                 wallet::enter(
                     BigDecimal::from_str(&amount)?,
@@ -427,22 +636,49 @@ Typically operation should take less than 1 minute.
                 account,
                 to_address,
                 amount,
+                all_networks,
+                yes,
             } => {
+                let sender = resolve_address(account.address()).await?;
                 let amount = match amount {
                     None => None,
                     Some(a) => Some(BigDecimal::from_str(&a)?),
                 };
-                CommandOutput::object(
-                    wallet::exit(
-                        resolve_address(account.address()).await?,
-                        to_address,
+                let networks = if all_networks {
+                    wallet::driver_networks(&account.driver()).await?
+                } else {
+                    vec![account.network()]
+                };
+                exit(
+                    ExitParams {
+                        to: to_address.unwrap_or_else(|| sender.clone()),
+                        sender,
                         amount,
-                        account.driver(),
-                        Some(account.network()),
-                        None,
-                    )
-                    .await?,
+                        driver: account.driver(),
+                        networks,
+                        confirmed: yes,
+                    },
+                    ctx.structured_output(),
                 )
+                .await
+            }
+            PaymentCli::AutoWithdraw { dry_run } => {
+                let withdrawals = bus::service(pay::BUS_ID)
+                    .call(pay::RunAutoWithdrawal { dry_run })
+                    .await??;
+                withdrawals_output(withdrawals, ctx.structured_output())
+            }
+            PaymentCli::Withdrawals { limit } => {
+                let withdrawals = bus::service(pay::BUS_ID)
+                    .call(pay::ListWithdrawals { limit: Some(limit) })
+                    .await??;
+                withdrawals_output(withdrawals, ctx.structured_output())
+            }
+            PaymentCli::Fees { limit } => {
+                let fees = bus::service(pay::BUS_ID)
+                    .call(pay::ListFees { limit: Some(limit) })
+                    .await??;
+                fees_output(fees, ctx.structured_output())
             }
 
             PaymentCli::Transfer {
@@ -504,7 +740,7 @@ Typically operation should take less than 1 minute.
                         })
                         .await??;
 
-                    if ctx.json_output {
+                    if ctx.structured_output() {
                         return CommandOutput::object(driver_status_props);
                     }
 
@@ -552,42 +788,124 @@ Typically operation should take less than 1 minute.
                         ok_msg
                     )))
                 }
+                DriverSubcommand::Transactions { account } => {
+                    let pending = wallet::pending_transactions(
+                        account.driver(),
+                        Some(account.network()),
+                        account.address(),
+                    )
+                    .await?;
+                    if ctx.structured_output() {
+                        return CommandOutput::object(pending);
+                    }
+
+                    let gaps = pending
+                        .nonce_gaps
+                        .iter()
+                        .map(|gap| {
+                            format!(
+                                "\nNonce gap for {}: next on-chain nonce is {}, first pending is {}",
+                                gap.sender, gap.next_nonce, gap.first_pending_nonce
+                            )
+                        })
+                        .collect::<String>();
+
+                    Ok(ResponseTable {
+                        columns: vec![
+                            "id".to_owned(),
+                            "sender".to_owned(),
+                            "nonce".to_owned(),
+                            "tx hash".to_owned(),
+                            "max fee (gwei)".to_owned(),
+                            "broadcast".to_owned(),
+                            "stuck since".to_owned(),
+                            "error".to_owned(),
+                        ],
+                        values: pending
+                            .transactions
+                            .into_iter()
+                            .map(|tx| {
+                                serde_json::json! {[
+                                    tx.id,
+                                    tx.sender,
+                                    tx.nonce,
+                                    tx.tx_hash,
+                                    tx.max_fee_per_gas.map(|fee| fee.to_string()),
+                                    tx.broadcast.map(|ts| ts.to_rfc3339()),
+                                    tx.stuck_since.map(|ts| ts.to_rfc3339()),
+                                    tx.error,
+                                ]}
+                            })
+                            .collect(),
+                    }
+                    .with_header(format!(
+                        "Pending transactions of the {} payment driver on {}{}",
+                        account.driver(),
+                        account.network(),
+                        gaps
+                    )))
+                }
+                DriverSubcommand::Bump {
+                    driver,
+                    id,
+                    gas_increase,
+                } => {
+                    let tx = wallet::bump_transaction(driver.to_string(), id, gas_increase).await?;
+                    CommandOutput::object(tx)
+                }
                 DriverSubcommand::List => {
                     let drivers = bus::service(pay::BUS_ID).call(pay::GetDrivers {}).await??;
-                    if ctx.json_output {
-                        return CommandOutput::object(drivers);
+                    let capabilities = bus::service(pay::BUS_ID)
+                        .call(pay::GetDriversCapabilities {})
+                        .await??;
+                    if ctx.structured_output() {
+                        let mut output = serde_json::Map::new();
+                        for (driver, dd) in drivers {
+                            let mut value = to_value(dd)?;
+                            value["capabilities"] =
+                                to_value(capabilities.get(&driver).cloned().unwrap_or_default())?;
+                            output.insert(driver, value);
+                        }
+                        return CommandOutput::object(output);
+                    }
+
+                    let flag = |enabled: bool| if enabled { "X" } else { "" };
+                    let mut values = Vec::new();
+                    for (driver, dd) in drivers.iter() {
+                        let caps = capabilities.get(driver).cloned().unwrap_or_default();
+                        for (network, n) in dd.networks.iter() {
+                            for (token, platform) in n.tokens.iter() {
+                                values.push(serde_json::json! {[
+                                    driver,
+                                    network,
+                                    flag(&dd.default_network == network),
+                                    token,
+                                    platform,
+                                    flag(caps.batching),
+                                    flag(caps.deposits),
+                                    flag(caps.gasless_platforms.contains(platform)),
+                                    flag(caps.status_flags),
+                                    caps.confirmations.get(network),
+                                ]});
+                            }
+                        }
                     }
                     Ok(ResponseTable {
-                                columns: vec![
-                                    "driver".to_owned(),
-                                    "network".to_owned(),
-                                    "default?".to_owned(),
-                                    "token".to_owned(),
-                                    "platform".to_owned(),
-                                ],
-                                values: drivers
-                                    .iter()
-                                    .flat_map(|(driver, dd)| {
-                                        dd.networks
-                                            .iter()
-                                            .flat_map(|(network, n)| {
-                                                n.tokens
-                                                    .iter()
-                                                    .map(|(token, platform)|
-                                                        serde_json::json! {[
-                                                driver,
-                                                network,
-                                                if &dd.default_network == network { "X" } else { "" },
-                                                token,
-                                                platform,
-                                            ]}
-                                                    )
-                                                    .collect::<Vec<serde_json::Value>>()
-                                            })
-                                            .collect::<Vec<serde_json::Value>>()
-                                    })
-                                    .collect(),
-                            }.into())
+                        columns: vec![
+                            "driver".to_owned(),
+                            "network".to_owned(),
+                            "default?".to_owned(),
+                            "token".to_owned(),
+                            "platform".to_owned(),
+                            "batching".to_owned(),
+                            "deposits".to_owned(),
+                            "gasless".to_owned(),
+                            "status".to_owned(),
+                            "confirmations".to_owned(),
+                        ],
+                        values,
+                    }
+                    .into())
                 }
             },
             PaymentCli::ReleaseAllocations => {
@@ -600,13 +918,233 @@ Typically operation should take less than 1 minute.
     }
 }
 
+struct ExitParams {
+    sender: String,
+    to: String,
+    amount: Option<BigDecimal>,
+    driver: String,
+    networks: Vec<String>,
+    confirmed: bool,
+}
+
+/// Estimates withdrawals on every network holding funds and executes them after
+/// confirmation. In JSON mode nothing is withdrawn unless `--yes` was given.
+async fn exit(params: ExitParams, json_output: bool) -> anyhow::Result<CommandOutput> {
+    let mut estimates = Vec::new();
+    let mut results = Vec::new();
+
+    for network in &params.networks {
+        match wallet::estimate_withdraw(
+            params.sender.clone(),
+            params.to.clone(),
+            params.amount.clone(),
+            params.driver.clone(),
+            network.clone(),
+        )
+        .await
+        {
+            Ok(estimate) if estimate.amount.is_zero() => {
+                log::debug!("No funds to withdraw on network {}", network);
+            }
+            Ok(estimate) => estimates.push(estimate),
+            Err(e) => results.push(serde_json::json!({
+                "network": network,
+                "status": "error",
+                "error": e.to_string(),
+            })),
+        }
+    }
+
+    if !json_output {
+        let mut summary = format!(
+            "Funds of {} available for withdrawal to {}:\n",
+            params.sender, params.to
+        );
+        if estimates.is_empty() {
+            summary.push_str("  none\n");
+        }
+        for estimate in &estimates {
+            let gas_currency = estimate
+                .gas_details
+                .as_ref()
+                .map(|gas| gas.currency_short_name.as_str())
+                .unwrap_or_default();
+            summary.push_str(&format!(
+                "  {}: {} {} (max fee {} {}{})\n",
+                estimate.network,
+                estimate.amount,
+                estimate.token,
+                estimate.max_fee,
+                gas_currency,
+                if estimate.sufficient_gas {
+                    ""
+                } else {
+                    ", insufficient gas"
+                }
+            ));
+        }
+        eprintln!("{}", summary);
+    }
+
+    for estimate in estimates {
+        let approved = estimate.sufficient_gas
+            && (params.confirmed
+                || (!json_output
+                    && confirm(format!(
+                        "Withdraw {} {} on {}?",
+                        estimate.amount, estimate.token, estimate.network
+                    ))
+                    .await?));
+        let (status, details) = if !estimate.sufficient_gas {
+            ("insufficientGas", None)
+        } else if !approved {
+            ("skipped", None)
+        } else {
+            match wallet::withdraw(
+                estimate.sender.clone(),
+                estimate.to.clone(),
+                Some(estimate.amount.clone()),
+                params.driver.clone(),
+                estimate.network.clone(),
+            )
+            .await
+            {
+                Ok(tx_id) => ("scheduled", Some(serde_json::json!({ "txId": tx_id }))),
+                Err(e) => ("error", Some(serde_json::json!({ "error": e.to_string() }))),
+            }
+        };
+
+        let mut result = serde_json::json!({
+            "network": estimate.network,
+            "status": status,
+            "estimate": estimate,
+        });
+        if let (Some(result), Some(serde_json::Value::Object(details))) =
+            (result.as_object_mut(), details)
+        {
+            result.extend(details);
+        }
+        results.push(result);
+    }
+
+    if json_output {
+        return CommandOutput::object(results);
+    }
+
+    Ok(ResponseTable {
+        columns: vec![
+            "network".to_owned(),
+            "amount".to_owned(),
+            "token".to_owned(),
+            "status".to_owned(),
+            "details".to_owned(),
+        ],
+        values: results
+            .into_iter()
+            .map(|result| {
+                let details = result
+                    .get("txId")
+                    .or_else(|| result.get("error"))
+                    .cloned()
+                    .unwrap_or_default();
+                serde_json::json! {[
+                    result["network"],
+                    result["estimate"]["amount"],
+                    result["estimate"]["token"],
+                    result["status"],
+                    details,
+                ]}
+            })
+            .collect(),
+    }
+    .into())
+}
+
+fn withdrawals_output(
+    withdrawals: Vec<pay::Withdrawal>,
+    json_output: bool,
+) -> anyhow::Result<CommandOutput> {
+    if json_output {
+        return CommandOutput::object(withdrawals);
+    }
+
+    Ok(ResponseTable {
+        columns: vec![
+            "timestamp".to_owned(),
+            "network".to_owned(),
+            "amount".to_owned(),
+            "token".to_owned(),
+            "max fee".to_owned(),
+            "status".to_owned(),
+            "details".to_owned(),
+        ],
+        values: withdrawals
+            .into_iter()
+            .map(|w| {
+                serde_json::json! {[
+                    w.timestamp.to_rfc3339(),
+                    w.network,
+                    w.amount.to_string(),
+                    w.token,
+                    w.max_fee.to_string(),
+                    w.status.to_string(),
+                    w.tx_id.or(w.error).unwrap_or_default(),
+                ]}
+            })
+            .collect(),
+    }
+    .into())
+}
+
+fn fees_output(fees: Vec<pay::Fee>, json_output: bool) -> anyhow::Result<CommandOutput> {
+    if json_output {
+        return CommandOutput::object(fees);
+    }
+
+    Ok(ResponseTable {
+        columns: vec![
+            "timestamp".to_owned(),
+            "invoice".to_owned(),
+            "platform".to_owned(),
+            "amount".to_owned(),
+            "recipient".to_owned(),
+            "status".to_owned(),
+            "details".to_owned(),
+        ],
+        values: fees
+            .into_iter()
+            .map(|f| {
+                serde_json::json! {[
+                    f.timestamp.to_rfc3339(),
+                    f.invoice_id,
+                    f.payment_platform,
+                    f.amount.to_string(),
+                    f.recipient,
+                    f.status.to_string(),
+                    f.tx_id.or(f.error).unwrap_or_default(),
+                ]}
+            })
+            .collect(),
+    }
+    .into())
+}
+
+async fn confirm(question: String) -> anyhow::Result<bool> {
+    let answer = tokio::task::spawn_blocking(move || {
+        let r: bool = promptly::prompt_default(question, false)?;
+        Ok::<_, anyhow::Error>(r)
+    })
+    .await??;
+    Ok(answer)
+}
+
 async fn resolve_address(address: Option<String>) -> anyhow::Result<String> {
     if let Some(id) = address {
         return Ok(id);
     }
 
     let id = bus::service(id_api::BUS_ID)
-        .send(id_api::Get::ByDefault)
+        .send(id_api::Get::ByModule(id_api::Module::Payment))
         .await??;
 
     if let Some(id) = id {
@@ -615,3 +1153,169 @@ async fn resolve_address(address: Option<String>) -> anyhow::Result<String> {
 
     anyhow::bail!("Default identity not found")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use std::sync::{Arc, Mutex};
+    use ya_core_model::driver::{
+        driver_bus_id, EstimateWithdraw, GasDetails, GenericError, Withdraw, WithdrawEstimate,
+    };
+
+    const SENDER: &str = "0xd39a168f0480b8502c2531b2ffd8588c592d713a";
+    const TO: &str = "0x2d2d6cfd3e9a50e7b5d3fa2eff4d8b6e3cfc6e5a";
+
+    fn estimate(msg: EstimateWithdraw) -> Result<WithdrawEstimate, GenericError> {
+        let (balance, gas) = match msg.network.as_str() {
+            "funded" => (10, 1),
+            "empty" => (0, 1),
+            "no-gas" => (10, 0),
+            network => return Err(GenericError::new(format!("Unknown network {network}"))),
+        };
+        Ok(WithdrawEstimate {
+            network: msg.network,
+            token: "tGLM".to_string(),
+            sender: msg.sender,
+            to: msg.to,
+            amount: msg.amount.unwrap_or_else(|| BigDecimal::from(balance)),
+            token_balance: BigDecimal::from(balance),
+            gas_details: Some(GasDetails {
+                currency_short_name: "tETH".to_string(),
+                currency_long_name: "Test Ether".to_string(),
+                balance: BigDecimal::from(gas),
+                faucet_url: None,
+                bridge_url: None,
+            }),
+            max_fee: BigDecimal::from_str("0.001").unwrap(),
+            sufficient_gas: gas > 0,
+        })
+    }
+
+    /// Binds driver answering withdraw RPCs and returns withdrawals it received.
+    /// Each test needs its own driver, because GSB handlers are global.
+    fn bind_driver(driver: &str) -> Arc<Mutex<Vec<Withdraw>>> {
+        let withdrawals = Arc::new(Mutex::new(Vec::new()));
+        let bus_id = driver_bus_id(driver);
+        let _ = bus::bind(&bus_id, |msg: EstimateWithdraw| {
+            future::ready(estimate(msg))
+        });
+
+        let received = withdrawals.clone();
+        let _ = bus::bind(&bus_id, move |msg: Withdraw| {
+            received.lock().unwrap().push(msg.clone());
+            future::ready(estimate(msg.into()).map(|estimate| format!("tx-{}", estimate.network)))
+        });
+        withdrawals
+    }
+
+    fn params(driver: &str, networks: &[&str], confirmed: bool) -> ExitParams {
+        ExitParams {
+            sender: SENDER.to_string(),
+            to: TO.to_string(),
+            amount: None,
+            driver: driver.to_string(),
+            networks: networks.iter().map(ToString::to_string).collect(),
+            confirmed,
+        }
+    }
+
+    fn statuses(output: CommandOutput) -> Vec<(String, String)> {
+        match output {
+            CommandOutput::Object(serde_json::Value::Array(results)) => results
+                .iter()
+                .map(|result| {
+                    (
+                        result["network"].as_str().unwrap().to_string(),
+                        result["status"].as_str().unwrap().to_string(),
+                    )
+                })
+                .collect(),
+            output => panic!("Unexpected output: {:?}", output),
+        }
+    }
+
+    #[test]
+    fn test_exit_cli_defaults() {
+        let cli = PaymentCli::from_iter(&["payment", "exit"]);
+        match cli {
+            PaymentCli::Exit {
+                account,
+                to_address,
+                amount,
+                all_networks,
+                yes,
+            } => {
+                assert_eq!(account.driver(), "erc20");
+                assert_eq!(account.network(), "holesky");
+                assert_eq!(to_address, None);
+                assert_eq!(amount, None);
+                assert!(!all_networks);
+                assert!(!yes);
+            }
+            cli => panic!("Unexpected command: {:?}", cli),
+        }
+
+        let cli = PaymentCli::from_iter(&[
+            "payment",
+            "exit",
+            "--network",
+            "polygon",
+            "--to-address",
+            TO,
+            "--amount",
+            "1.5",
+        ]);
+        match cli {
+            PaymentCli::Exit {
+                account,
+                to_address,
+                amount,
+                ..
+            } => {
+                assert_eq!(account.network(), "polygon");
+                assert_eq!(to_address.as_deref(), Some(TO));
+                assert_eq!(amount.as_deref(), Some("1.5"));
+            }
+            cli => panic!("Unexpected command: {:?}", cli),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_exit_withdraws_confirmed() {
+        let driver = "exit-test-confirmed";
+        let withdrawals = bind_driver(driver);
+
+        let networks = ["funded", "empty", "no-gas", "broken"];
+        let output = exit(params(driver, &networks, true), true).await.unwrap();
+        assert_eq!(
+            statuses(output),
+            vec![
+                ("broken".to_string(), "error".to_string()),
+                ("funded".to_string(), "scheduled".to_string()),
+                ("no-gas".to_string(), "insufficientGas".to_string()),
+            ]
+        );
+
+        let withdrawals = withdrawals.lock().unwrap();
+        assert_eq!(withdrawals.len(), 1);
+        assert_eq!(withdrawals[0].network, "funded");
+        assert_eq!(withdrawals[0].to, TO);
+        assert_eq!(withdrawals[0].amount, Some(BigDecimal::from(10)));
+    }
+
+    #[actix_rt::test]
+    async fn test_exit_json_requires_confirmation() {
+        let driver = "exit-test-unconfirmed";
+        let withdrawals = bind_driver(driver);
+
+        let output = exit(params(driver, &["funded"], false), true)
+            .await
+            .unwrap();
+        assert_eq!(
+            statuses(output),
+            vec![("funded".to_string(), "skipped".to_string())]
+        );
+        assert!(withdrawals.lock().unwrap().is_empty());
+    }
+}
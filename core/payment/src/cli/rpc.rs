@@ -167,7 +167,7 @@ pub async fn run_command_rpc(
         serde_json::from_value(result.endpoints).unwrap();
     let sources: BTreeMap<String, Web3ExternalSources> =
         serde_json::from_value(result.sources).unwrap();
-    if ctx.json_output {
+    if ctx.structured_output() {
         return CommandOutput::object(json!({"endpoints": endpoints, "sources": sources}));
     }
 
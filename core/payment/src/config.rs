@@ -4,6 +4,178 @@ use structopt::*;
 pub struct Config {
     #[structopt(flatten)]
     pub sync_notif_backoff: SyncNotifBackoffConfig,
+
+    /// Maximum estimated size in bytes of a single PaymentSync message. Larger backlogs are
+    /// sent in several batches, the oldest and most valuable documents first.
+    #[structopt(long, env = "YA_PAYMENT_SYNC_BATCH_SIZE", default_value = "262144")]
+    pub sync_batch_size: usize,
+
+    /// How long idempotency keys of received payments are remembered.
+    /// Redelivered payment confirmations are acknowledged without processing within this window.
+    #[structopt(long, env = "YA_PAYMENT_IDEMPOTENCY_WINDOW", parse(try_from_str = humantime::parse_duration), default_value = "7d")]
+    pub idempotency_window: std::time::Duration,
+
+    /// Maximum amount, which may be missing from a paid invoice. Accepted invoices paid
+    /// less than invoiced within this tolerance (e.g. rounding or gas dust) are settled.
+    #[structopt(long, env = "YA_PAYMENT_SETTLE_TOLERANCE", default_value = "0")]
+    pub settle_tolerance: bigdecimal::BigDecimal,
+
+    /// Minimum debit note payout per network as comma separated `network=amount` pairs
+    /// (e.g. `mainnet=5,polygon=0.1`). Smaller debit note payments are accumulated until
+    /// their sum passes the threshold. Invoices are always paid in full.
+    #[structopt(long, env = "YA_PAYMENT_MIN_PAYOUT", default_value = "")]
+    pub min_payout: crate::payout::PayoutThresholds,
+
+    /// Deferred debit note payments are scheduled this long before their due date.
+    /// Debit notes due sooner aren't deferred.
+    #[structopt(long, env = "YA_PAYMENT_MIN_PAYOUT_DUE_MARGIN", parse(try_from_str = humantime::parse_duration), default_value = "10min")]
+    pub min_payout_due_margin: std::time::Duration,
+
+    #[structopt(flatten)]
+    pub fiat: FiatConfig,
+
+    #[structopt(flatten)]
+    pub webhook: WebhookConfig,
+
+    #[structopt(flatten)]
+    pub auto_withdrawal: AutoWithdrawalConfig,
+
+    #[structopt(flatten)]
+    pub invoice_acceptance: InvoiceAcceptanceConfig,
+
+    #[structopt(flatten)]
+    pub fee: FeeConfig,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct FeeConfig {
+    /// Address, to which a marketplace fee is paid with every invoice payment of the Requestor.
+    /// Fees are disabled if not set.
+    #[structopt(long, env = "YA_PAYMENT_FEE_ADDRESS")]
+    pub fee_address: Option<String>,
+
+    /// Fee as a percentage of the invoiced amount.
+    #[structopt(long, env = "YA_PAYMENT_FEE_PERCENT", default_value = "0")]
+    pub fee_percent: bigdecimal::BigDecimal,
+
+    /// Flat fee added to each invoice payment.
+    #[structopt(long, env = "YA_PAYMENT_FEE_FLAT", default_value = "0")]
+    pub fee_flat: bigdecimal::BigDecimal,
+
+    /// Comma separated payment platforms (e.g. `erc20-polygon-glm`), on which fees are charged.
+    /// All platforms if not set.
+    #[structopt(long, env = "YA_PAYMENT_FEE_PLATFORMS", default_value = "")]
+    pub fee_platforms: String,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct InvoiceAcceptanceConfig {
+    /// Time since an invoice was issued, within which the Provider expects it to be accepted.
+    #[structopt(long, env = "YA_PAYMENT_INVOICE_ACCEPT_DEADLINE", parse(try_from_str = humantime::parse_duration), default_value = "1d")]
+    pub invoice_accept_deadline: std::time::Duration,
+
+    /// How long before the deadline an `InvoiceAcceptanceDue` event is published
+    /// for invoices still awaiting acceptance.
+    #[structopt(long, env = "YA_PAYMENT_INVOICE_ACCEPT_WARNING", parse(try_from_str = humantime::parse_duration), default_value = "2h")]
+    pub invoice_accept_warning: std::time::Duration,
+
+    /// Accepts invoices of terminated agreements, which match the amount of accepted
+    /// debit notes. Payment is scheduled from the allocation used for the debit notes.
+    #[structopt(
+        long,
+        env = "YA_PAYMENT_INVOICE_AUTO_ACCEPT",
+        parse(try_from_str),
+        default_value = "false"
+    )]
+    pub invoice_auto_accept: bool,
+
+    /// Maximum difference between the invoiced amount and accepted debit notes,
+    /// which is still accepted automatically.
+    #[structopt(
+        long,
+        env = "YA_PAYMENT_INVOICE_AUTO_ACCEPT_TOLERANCE",
+        default_value = "0"
+    )]
+    pub invoice_auto_accept_tolerance: bigdecimal::BigDecimal,
+
+    #[structopt(long, env = "YA_PAYMENT_INVOICE_ACCEPT_CHECK_INTERVAL", parse(try_from_str = humantime::parse_duration), default_value = "1m")]
+    pub invoice_accept_check_interval: std::time::Duration,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct AutoWithdrawalConfig {
+    /// Address, to which earnings of the payment identity are periodically withdrawn.
+    /// Auto-withdrawal is disabled if not set.
+    #[structopt(long, env = "YA_PAYMENT_AUTO_WITHDRAW_TO")]
+    pub auto_withdraw_to: Option<String>,
+
+    /// Token balance, which has to be reached on the network to withdraw it.
+    #[structopt(long, env = "YA_PAYMENT_AUTO_WITHDRAW_THRESHOLD", default_value = "10")]
+    pub auto_withdraw_threshold: bigdecimal::BigDecimal,
+
+    #[structopt(long, env = "YA_PAYMENT_AUTO_WITHDRAW_INTERVAL", parse(try_from_str = humantime::parse_duration), default_value = "1d")]
+    pub auto_withdraw_interval: std::time::Duration,
+
+    /// Withdrawal is postponed while the upper bound of its gas fee, in the native currency
+    /// of the network, exceeds this amount.
+    #[structopt(long, env = "YA_PAYMENT_AUTO_WITHDRAW_MAX_GAS_FEE")]
+    pub auto_withdraw_max_gas_fee: Option<bigdecimal::BigDecimal>,
+
+    #[structopt(long, env = "YA_PAYMENT_AUTO_WITHDRAW_DRIVER", default_value = "erc20")]
+    pub auto_withdraw_driver: String,
+
+    /// Comma separated networks to withdraw from. All networks of the driver if not set.
+    #[structopt(long, env = "YA_PAYMENT_AUTO_WITHDRAW_NETWORKS", default_value = "")]
+    pub auto_withdraw_networks: String,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct WebhookConfig {
+    /// Comma separated URLs, to which invoice and payment events are POSTed.
+    /// Webhooks are disabled if not set.
+    #[structopt(long, env = "YA_PAYMENT_WEBHOOK_URLS", default_value = "")]
+    pub webhook_urls: String,
+
+    /// Secret for HMAC-SHA256 signature of request body sent in `X-Yagna-Signature` header.
+    /// Requests are not signed if not set.
+    #[structopt(long, env = "YA_PAYMENT_WEBHOOK_SECRET")]
+    pub webhook_secret: Option<String>,
+
+    #[structopt(long, env = "YA_PAYMENT_WEBHOOK_MAX_RETRIES", default_value = "5")]
+    pub webhook_max_retries: u32,
+
+    /// Delay before the first retry. Doubled with each next one.
+    #[structopt(long, env = "YA_PAYMENT_WEBHOOK_RETRY_DELAY", parse(try_from_str = humantime::parse_duration), default_value = "5s")]
+    pub webhook_retry_delay: std::time::Duration,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct FiatConfig {
+    /// Fiat currency (e.g. `usd`), which invoices, payments and account status are annotated with.
+    /// Annotation is disabled if not set.
+    #[structopt(long, env = "YA_PAYMENT_FIAT_CURRENCY")]
+    pub fiat_currency: Option<String>,
+
+    /// Coingecko compatible `simple/price` endpoint.
+    #[structopt(
+        long,
+        env = "YA_PAYMENT_PRICE_FEED_URL",
+        default_value = "https://api.coingecko.com/api/v3/simple/price"
+    )]
+    pub price_feed_url: String,
+
+    /// Price feed ids of payment tokens as comma separated `token=id` pairs.
+    /// Tokens without id (e.g. test tokens) are not annotated.
+    #[structopt(
+        long,
+        env = "YA_PAYMENT_PRICE_FEED_TOKENS",
+        default_value = "glm=golem"
+    )]
+    pub price_feed_tokens: String,
+
+    /// How long fetched rates are reused.
+    #[structopt(long, env = "YA_PAYMENT_PRICE_FEED_CACHE", parse(try_from_str = humantime::parse_duration), default_value = "5m")]
+    pub price_feed_cache: std::time::Duration,
 }
 
 #[derive(StructOpt, Clone)]
@@ -3,11 +3,19 @@ mod agreement;
 mod allocation;
 mod debit_note;
 mod debit_note_event;
+mod fee;
+mod fiat_annotation;
+mod gas_cost;
+mod idempotency_key;
 mod invoice;
 mod invoice_event;
+mod journal;
+mod notify_payment;
 mod order;
 mod payment;
+mod payment_receipt;
 mod sync_notifs;
+mod withdrawal;
 
 pub use self::activity::ActivityDao;
 pub use self::agreement::AgreementDao;
@@ -16,8 +24,16 @@ pub use self::allocation::AllocationReleaseStatus;
 pub use self::allocation::AllocationStatus;
 pub use self::debit_note::DebitNoteDao;
 pub use self::debit_note_event::DebitNoteEventDao;
+pub use self::fee::FeeDao;
+pub use self::fiat_annotation::FiatAnnotationDao;
+pub use self::gas_cost::GasCostDao;
+pub use self::idempotency_key::IdempotencyKeyDao;
 pub use self::invoice::InvoiceDao;
 pub use self::invoice_event::InvoiceEventDao;
+pub use self::journal::JournalDao;
+pub use self::notify_payment::NotifyPaymentDao;
 pub use self::order::OrderDao;
 pub use self::payment::PaymentDao;
+pub use self::payment_receipt::PaymentReceiptDao;
 pub use self::sync_notifs::SyncNotifsDao;
+pub use self::withdrawal::WithdrawalDao;
@@ -206,6 +206,116 @@ impl<'a> AgreementDao<'a> {
         .await
     }
 
+    /// Raises amounts accepted and paid to values reported by the peer, when rebuilding
+    /// payment state after data loss. Amounts are never lowered.
+    pub async fn restore_totals(
+        &self,
+        agreement_id: String,
+        owner_id: NodeId,
+        total_amount_accepted: BigDecimal,
+        total_amount_paid: BigDecimal,
+    ) -> DbResult<()> {
+        do_with_transaction(self.pool, "agreement_dao_restore_totals", move |conn| {
+            let agreement: ReadObj = dsl::pay_agreement
+                .find((&agreement_id, &owner_id))
+                .first(conn)?;
+            let accepted: BigDecimalField = agreement
+                .total_amount_accepted
+                .0
+                .clone()
+                .max(total_amount_accepted)
+                .into();
+            let paid: BigDecimalField = agreement
+                .total_amount_paid
+                .0
+                .clone()
+                .max(total_amount_paid)
+                .into();
+            diesel::update(&agreement)
+                .set((
+                    dsl::total_amount_accepted.eq(accepted),
+                    dsl::total_amount_paid.eq(paid),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Settles Provider's accepted invoice, which is paid less than invoiced by at most
+    /// `tolerance`. Returns id of the settled invoice and the missing amount.
+    pub async fn settle_within_tolerance(
+        &self,
+        agreement_id: String,
+        owner_id: NodeId,
+        tolerance: BigDecimal,
+    ) -> DbResult<Option<(String, BigDecimal)>> {
+        do_with_transaction(
+            self.pool,
+            "agreement_dao_settle_within_tolerance",
+            move |conn| {
+                let total_amount_paid: BigDecimalField = dsl::pay_agreement
+                    .find((&agreement_id, &owner_id))
+                    .select(dsl::total_amount_paid)
+                    .first(conn)?;
+
+                let invoice: Option<(String, BigDecimalField)> = invoice_dsl::pay_invoice
+                    .filter(invoice_dsl::agreement_id.eq(&agreement_id))
+                    .filter(invoice_dsl::owner_id.eq(&owner_id))
+                    .filter(invoice_dsl::role.eq(Role::Provider))
+                    .filter(invoice_dsl::status.eq(DocumentStatus::Accepted.to_string()))
+                    .select((invoice_dsl::id, invoice_dsl::amount))
+                    .first(conn)
+                    .optional()?;
+
+                let (invoice_id, amount) = match invoice {
+                    Some(invoice) => invoice,
+                    None => return Ok(None),
+                };
+                let discrepancy = &amount.0 - &total_amount_paid.0;
+                if discrepancy <= BigDecimal::from(0) || discrepancy > tolerance {
+                    return Ok(None);
+                }
+
+                invoice::update_status(&invoice_id, &owner_id, &DocumentStatus::Settled, conn)?;
+                invoice_event::create(
+                    invoice_id.clone(),
+                    owner_id,
+                    InvoiceEventType::InvoiceSettledEvent,
+                    conn,
+                )?;
+                Ok(Some((invoice_id, discrepancy)))
+            },
+        )
+        .await
+    }
+
+    /// Agreements of given role, which aren't invoiced yet. Only Agreements from
+    /// `agreement_ids` are considered, since their state is kept by market.
+    pub async fn uninvoiced(
+        &self,
+        owner_id: NodeId,
+        role: Role,
+        agreement_ids: Vec<String>,
+    ) -> DbResult<Vec<ReadObj>> {
+        readonly_transaction(self.pool, "agreement_dao_uninvoiced", move |conn| {
+            let agreements = dsl::pay_agreement
+                .filter(dsl::owner_id.eq(owner_id))
+                .filter(dsl::role.eq(role))
+                .filter(dsl::id.eq_any(agreement_ids))
+                .filter(diesel::dsl::not(diesel::dsl::exists(
+                    invoice_dsl::pay_invoice
+                        .filter(invoice_dsl::agreement_id.eq(dsl::id))
+                        .filter(invoice_dsl::owner_id.eq(dsl::owner_id))
+                        .select(invoice_dsl::id),
+                )))
+                .select(crate::schema::pay_agreement::all_columns)
+                .get_results(conn)?;
+            Ok(agreements)
+        })
+        .await
+    }
+
     pub async fn get_transaction_balance(
         &self,
         node_id: NodeId,
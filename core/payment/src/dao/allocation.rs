@@ -1,9 +1,13 @@
 use crate::error::{DbError, DbResult};
-use crate::models::allocation::{ReadObj, WriteObj};
+use crate::models::allocation::{PlatformObj, ReadObj, WriteObj};
 use crate::schema::pay_allocation::dsl;
-use bigdecimal::BigDecimal;
+use crate::schema::pay_allocation_platform::dsl as platform_dsl;
+use bigdecimal::{BigDecimal, Zero};
 use chrono::NaiveDateTime;
-use diesel::{self, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use diesel::{
+    self, BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+};
+use std::collections::HashMap;
 use ya_client_model::payment::allocation::Deposit;
 use ya_client_model::payment::{Allocation, NewAllocation};
 use ya_client_model::NodeId;
@@ -22,8 +26,10 @@ impl<'c> AsDao<'c> for AllocationDao<'c> {
     }
 }
 
+/// Payment on a fallback platform is taken from its share first.
 pub fn spend_from_allocation(
     allocation_id: &String,
+    platform: &str,
     amount: &BigDecimalField,
     conn: &ConnType,
 ) -> DbResult<()> {
@@ -42,38 +48,143 @@ pub fn spend_from_allocation(
             dsl::remaining_amount.eq(remaining_amount),
         ))
         .execute(conn)?;
+
+    let share: Option<PlatformObj> = platform_dsl::pay_allocation_platform
+        .find((allocation_id, platform))
+        .first(conn)
+        .optional()?;
+    if let Some(share) = share {
+        let amount = (&share.amount - amount).0.max(BigDecimal::zero());
+        diesel::update(platform_dsl::pay_allocation_platform)
+            .filter(platform_dsl::allocation_id.eq(allocation_id))
+            .filter(platform_dsl::platform.eq(platform))
+            .set(platform_dsl::amount.eq(BigDecimalField(amount)))
+            .execute(conn)?;
+    }
     Ok(())
 }
 
+/// Active allocations reserving funds of `address` on `platform` along with the reserved
+/// amount. Allocation reserves its share on a fallback platform and the rest of its
+/// remaining amount on the primary one.
+fn reserving_funds(
+    platform: &str,
+    address: &str,
+    conn: &ConnType,
+) -> DbResult<Vec<(ReadObj, BigDecimal)>> {
+    let fallback_shares: HashMap<String, BigDecimal> = platform_dsl::pay_allocation_platform
+        .filter(platform_dsl::platform.eq(platform))
+        .load::<PlatformObj>(conn)?
+        .into_iter()
+        .map(|share| (share.allocation_id, share.amount.0))
+        .collect();
+    let allocations: Vec<ReadObj> = dsl::pay_allocation
+        .filter(dsl::address.eq(address))
+        .filter(dsl::released.eq(false))
+        .filter(
+            dsl::payment_platform
+                .eq(platform)
+                .or(dsl::id.eq_any(fallback_shares.keys().cloned().collect::<Vec<_>>())),
+        )
+        .load(conn)?;
+
+    let primary_ids = allocations
+        .iter()
+        .filter(|allocation| allocation.payment_platform == platform)
+        .map(|allocation| allocation.id.clone())
+        .collect::<Vec<_>>();
+    let mut shared: HashMap<String, BigDecimal> = HashMap::new();
+    for share in platform_dsl::pay_allocation_platform
+        .filter(platform_dsl::allocation_id.eq_any(primary_ids))
+        .load::<PlatformObj>(conn)?
+    {
+        *shared.entry(share.allocation_id).or_default() += share.amount.0;
+    }
+
+    Ok(allocations
+        .into_iter()
+        .map(|allocation| {
+            let remaining = &allocation.remaining_amount.0;
+            let reserved = match fallback_shares.get(&allocation.id) {
+                Some(share) => share.min(remaining).clone(),
+                None => match shared.get(&allocation.id) {
+                    Some(shared) => (remaining - shared).max(BigDecimal::zero()),
+                    None => remaining.clone(),
+                },
+            };
+            (allocation, reserved)
+        })
+        .collect())
+}
+
 impl<'c> AllocationDao<'c> {
+    /// `fallback_platforms` are listed in order of preference along with their shares
+    /// of the allocation.
     pub async fn create(
         &self,
         allocation: NewAllocation,
         owner_id: NodeId,
         payment_platform: String,
         address: String,
+        fallback_platforms: Vec<(String, BigDecimal)>,
     ) -> DbResult<String> {
         let allocation = WriteObj::new(allocation, owner_id, payment_platform, address);
         let allocation_id = allocation.id.clone();
+        let platforms = fallback_platforms
+            .into_iter()
+            .enumerate()
+            .map(|(priority, (platform, amount))| PlatformObj {
+                allocation_id: allocation_id.clone(),
+                platform,
+                priority: priority as i32,
+                amount: amount.into(),
+            })
+            .collect::<Vec<_>>();
         do_with_transaction(self.pool, "allocation_dao_create", move |conn| {
             diesel::insert_into(dsl::pay_allocation)
                 .values(allocation)
                 .execute(conn)?;
+            if !platforms.is_empty() {
+                diesel::insert_into(platform_dsl::pay_allocation_platform)
+                    .values(platforms)
+                    .execute(conn)?;
+            }
             Ok(allocation_id)
         })
         .await
     }
 
-    pub async fn replace(&self, allocation: Allocation, owner_id: NodeId) -> DbResult<bool> {
+    /// Shares of fallback platforms are increased by `fallback_increase`.
+    pub async fn replace(
+        &self,
+        allocation: Allocation,
+        owner_id: NodeId,
+        fallback_increase: Vec<(String, BigDecimal)>,
+    ) -> DbResult<bool> {
         do_with_transaction(self.pool, "allocation_dao_replace", move |conn| {
+            let allocation_id = allocation.allocation_id.clone();
             let count = diesel::update(dsl::pay_allocation)
-                .filter(dsl::id.eq(allocation.allocation_id.clone()))
+                .filter(dsl::id.eq(allocation_id.clone()))
                 .filter(dsl::owner_id.eq(&owner_id))
                 .filter(dsl::released.eq(false))
                 .set(WriteObj::from_allocation(allocation, owner_id))
                 .execute(conn)?;
+            if count != 1 {
+                return Ok(false);
+            }
 
-            Ok(count == 1)
+            for (platform, increase) in fallback_increase {
+                let share = platform_dsl::pay_allocation_platform
+                    .find((allocation_id.clone(), platform.clone()))
+                    .select(platform_dsl::amount)
+                    .first::<BigDecimalField>(conn)?;
+                diesel::update(platform_dsl::pay_allocation_platform)
+                    .filter(platform_dsl::allocation_id.eq(&allocation_id))
+                    .filter(platform_dsl::platform.eq(platform))
+                    .set(platform_dsl::amount.eq(BigDecimalField(share.0 + increase)))
+                    .execute(conn)?;
+            }
+            Ok(true)
         })
         .await
     }
@@ -115,6 +226,34 @@ impl<'c> AllocationDao<'c> {
         .await
     }
 
+    /// Fallback payment platforms of given allocations, in order of preference.
+    /// Allocations without fallbacks are omitted.
+    pub async fn get_fallback_platforms(
+        &self,
+        allocation_ids: Vec<String>,
+    ) -> DbResult<HashMap<String, Vec<String>>> {
+        readonly_transaction(
+            self.pool,
+            "allocation_dao_get_fallback_platforms",
+            move |conn| {
+                let platforms: Vec<PlatformObj> = platform_dsl::pay_allocation_platform
+                    .filter(platform_dsl::allocation_id.eq_any(allocation_ids))
+                    .order_by(platform_dsl::priority.asc())
+                    .load(conn)?;
+
+                let mut fallbacks: HashMap<String, Vec<String>> = HashMap::new();
+                for platform in platforms {
+                    fallbacks
+                        .entry(platform.allocation_id)
+                        .or_default()
+                        .push(platform.platform);
+                }
+                Ok(fallbacks)
+            },
+        )
+        .await
+    }
+
     pub async fn get_for_owner(
         &self,
         owner_id: NodeId,
@@ -133,6 +272,32 @@ impl<'c> AllocationDao<'c> {
         .await
     }
 
+    /// Active allocations reserving funds of `address` on `platform`, including those
+    /// using it as fallback platform. Allocations are presented as if they were created
+    /// on `platform` with remaining amount limited to its share.
+    pub async fn get_reserving_funds(
+        &self,
+        platform: String,
+        address: String,
+    ) -> DbResult<Vec<Allocation>> {
+        readonly_transaction(
+            self.pool,
+            "allocation_dao_get_reserving_funds",
+            move |conn| {
+                let allocations = reserving_funds(&platform, &address, conn)?;
+                Ok(allocations
+                    .into_iter()
+                    .map(|(allocation, reserved)| Allocation {
+                        payment_platform: platform.clone(),
+                        remaining_amount: reserved,
+                        ..Allocation::from(allocation)
+                    })
+                    .collect())
+            },
+        )
+        .await
+    }
+
     pub async fn get_for_address(
         &self,
         payment_platform: String,
@@ -243,13 +408,11 @@ impl<'c> AllocationDao<'c> {
             self.pool,
             "allocation_dao_total_remaining_allocation",
             move |conn| {
-                let total_remaining_amount = dsl::pay_allocation
-                    .select(dsl::remaining_amount)
-                    .filter(dsl::payment_platform.eq(platform))
-                    .filter(dsl::address.eq(address))
-                    .filter(dsl::released.eq(false))
-                    .filter(dsl::timestamp.gt(after_timestamp))
-                    .get_results::<BigDecimalField>(conn)?
+                let total_remaining_amount = reserving_funds(&platform, &address, conn)?
+                    .into_iter()
+                    .filter(|(allocation, _)| allocation.timestamp > after_timestamp)
+                    .map(|(_, reserved)| reserved)
+                    .collect::<Vec<_>>()
                     .sum();
 
                 Ok(total_remaining_amount)
@@ -274,3 +437,99 @@ pub enum AllocationReleaseStatus {
         platform: String,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use ya_persistence::executor::DbExecutor;
+
+    const ADDRESS: &str = "0x0000000000000000000000000000000000000001";
+
+    fn new_allocation(amount: u32) -> NewAllocation {
+        NewAllocation {
+            address: Some(ADDRESS.to_string()),
+            payment_platform: None,
+            total_amount: BigDecimal::from(amount),
+            timeout: None,
+            make_deposit: false,
+            deposit: None,
+            extend_timeout: None,
+        }
+    }
+
+    fn reserved(allocations: Vec<Allocation>) -> Vec<(String, BigDecimal)> {
+        allocations
+            .into_iter()
+            .map(|allocation| (allocation.payment_platform, allocation.remaining_amount))
+            .collect()
+    }
+
+    #[actix_rt::test]
+    async fn test_fallback_share_is_reserved() {
+        let db = DbExecutor::in_memory("allocation_fallback_share").unwrap();
+        db.apply_migration(crate::migrations::run_with_output)
+            .unwrap();
+        let dao = db.as_dao::<AllocationDao>();
+        let owner_id = NodeId::default();
+        let reserving =
+            |platform: &str| dao.get_reserving_funds(platform.to_string(), ADDRESS.to_string());
+
+        dao.create(
+            new_allocation(10),
+            owner_id,
+            "erc20-polygon-glm".to_string(),
+            ADDRESS.to_string(),
+            vec![
+                ("erc20-mainnet-glm".to_string(), BigDecimal::from(4)),
+                ("erc20-arbitrum-glm".to_string(), BigDecimal::zero()),
+            ],
+        )
+        .await
+        .unwrap();
+        let allocation_id = dao
+            .create(
+                new_allocation(5),
+                owner_id,
+                "erc20-mainnet-glm".to_string(),
+                ADDRESS.to_string(),
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            reserved(reserving("erc20-polygon-glm").await.unwrap()),
+            vec![("erc20-polygon-glm".to_string(), BigDecimal::from(6))]
+        );
+        let mut mainnet = reserved(reserving("erc20-mainnet-glm").await.unwrap());
+        mainnet.sort();
+        assert_eq!(
+            mainnet,
+            vec![
+                ("erc20-mainnet-glm".to_string(), BigDecimal::from(4)),
+                ("erc20-mainnet-glm".to_string(), BigDecimal::from(5)),
+            ]
+        );
+        assert_eq!(
+            dao.total_remaining_allocation(
+                "erc20-mainnet-glm".to_string(),
+                ADDRESS.to_string(),
+                Utc::now().naive_utc() - chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap(),
+            BigDecimal::from(9)
+        );
+
+        dao.release(allocation_id, None).await.unwrap();
+        assert_eq!(
+            reserved(reserving("erc20-mainnet-glm").await.unwrap()),
+            vec![("erc20-mainnet-glm".to_string(), BigDecimal::from(4))]
+        );
+        assert_eq!(
+            reserved(reserving("erc20-arbitrum-glm").await.unwrap()),
+            vec![("erc20-arbitrum-glm".to_string(), BigDecimal::zero())]
+        );
+    }
+}
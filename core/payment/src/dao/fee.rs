@@ -0,0 +1,206 @@
+use crate::error::DbResult;
+use crate::models::fee::{ReadObj, WriteObj};
+use crate::schema::pay_fee::dsl;
+
+use diesel::{self, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use ya_core_model::payment::local::{Fee, FeeStatus};
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+pub struct FeeDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for FeeDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> FeeDao<'c> {
+    pub async fn insert(&self, fee: WriteObj) -> DbResult<()> {
+        do_with_transaction(self.pool, "fee_dao_insert", move |conn| {
+            diesel::insert_into(dsl::pay_fee)
+                .values(fee)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Marks fees charged on payments of given orders due, once they are confirmed.
+    pub async fn mark_due(&self, payment_order_ids: Vec<String>, driver: String) -> DbResult<()> {
+        do_with_transaction(self.pool, "fee_dao_mark_due", move |conn| {
+            diesel::update(
+                dsl::pay_fee
+                    .filter(dsl::payment_order_id.eq_any(payment_order_ids))
+                    .filter(dsl::driver.eq(driver))
+                    .filter(dsl::status.eq(FeeStatus::Pending.to_string())),
+            )
+            .set(dsl::status.eq(FeeStatus::Due.to_string()))
+            .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Fees, which are due, but not scheduled yet.
+    pub async fn due(&self) -> DbResult<Vec<ReadObj>> {
+        readonly_transaction(self.pool, "fee_dao_due", move |conn| {
+            Ok(dsl::pay_fee
+                .filter(dsl::status.eq(FeeStatus::Due.to_string()))
+                .order_by(dsl::id.asc())
+                .load(conn)?)
+        })
+        .await
+    }
+
+    pub async fn scheduled(&self, id: i32, order_id: String) -> DbResult<()> {
+        do_with_transaction(self.pool, "fee_dao_scheduled", move |conn| {
+            diesel::update(dsl::pay_fee.find(id))
+                .set((
+                    dsl::status.eq(FeeStatus::Scheduled.to_string()),
+                    dsl::order_id.eq(Some(order_id)),
+                    dsl::error.eq(None::<String>),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Records the reason, why the fee couldn't be scheduled. It stays due.
+    pub async fn failed(&self, id: i32, error: String) -> DbResult<()> {
+        do_with_transaction(self.pool, "fee_dao_failed", move |conn| {
+            diesel::update(dsl::pay_fee.find(id))
+                .set(dsl::error.eq(Some(error)))
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Marks fees paid by given orders. Returns `false` without changes, unless all orders
+    /// are fee orders.
+    pub async fn settle(
+        &self,
+        order_ids: Vec<String>,
+        driver: String,
+        tx_id: String,
+    ) -> DbResult<bool> {
+        do_with_transaction(self.pool, "fee_dao_settle", move |conn| {
+            let fees: i64 = dsl::pay_fee
+                .filter(dsl::order_id.eq_any(&order_ids))
+                .filter(dsl::driver.eq(&driver))
+                .count()
+                .get_result(conn)?;
+            if order_ids.is_empty() || fees as usize != order_ids.len() {
+                return Ok(false);
+            }
+
+            diesel::update(
+                dsl::pay_fee
+                    .filter(dsl::order_id.eq_any(&order_ids))
+                    .filter(dsl::driver.eq(&driver)),
+            )
+            .set((
+                dsl::status.eq(FeeStatus::Paid.to_string()),
+                dsl::tx_id.eq(Some(tx_id)),
+            ))
+            .execute(conn)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    /// The newest fees first.
+    pub async fn list(&self, limit: Option<u32>) -> DbResult<Vec<Fee>> {
+        readonly_transaction(self.pool, "fee_dao_list", move |conn| {
+            let mut query = dsl::pay_fee.order_by(dsl::id.desc()).into_boxed();
+            if let Some(limit) = limit {
+                query = query.limit(limit as i64);
+            }
+            let fees: Vec<ReadObj> = query.load(conn)?;
+            Ok(fees.into_iter().map(ReadObj::into_api).collect())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ya_client_model::NodeId;
+    use ya_persistence::executor::DbExecutor;
+
+    fn fee(payment_order_id: &str) -> WriteObj {
+        WriteObj {
+            owner_id: NodeId::default(),
+            invoice_id: format!("invoice-{payment_order_id}"),
+            agreement_id: "agreement".to_string(),
+            payment_platform: "erc20-holesky-tglm".to_string(),
+            payer_addr: "0xa".to_string(),
+            recipient: "0xfee".to_string(),
+            amount: bigdecimal::BigDecimal::from(1).into(),
+            driver: "erc20".to_string(),
+            order_id: None,
+            status: FeeStatus::Pending.to_string(),
+            error: None,
+            payment_order_id: Some(payment_order_id.to_string()),
+            allocation_id: Some("allocation".to_string()),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn fee_is_due_after_payment_confirmation() {
+        let db = DbExecutor::in_memory("fee_due").unwrap();
+        db.apply_migration(crate::migrations::run_with_output)
+            .unwrap();
+        let dao: FeeDao = db.as_dao();
+        dao.insert(fee("order-1")).await.unwrap();
+        dao.insert(fee("order-2")).await.unwrap();
+        assert!(dao.due().await.unwrap().is_empty());
+
+        dao.mark_due(vec!["order-1".to_string()], "erc20".to_string())
+            .await
+            .unwrap();
+        let due = dao.due().await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].invoice_id, "invoice-order-1");
+
+        // Failed attempts are retried.
+        dao.failed(due[0].id, "insufficient funds".to_string())
+            .await
+            .unwrap();
+        let due = dao.due().await.unwrap();
+        assert_eq!(due[0].error.as_deref(), Some("insufficient funds"));
+
+        dao.scheduled(due[0].id, "fee-order".to_string())
+            .await
+            .unwrap();
+        assert!(dao.due().await.unwrap().is_empty());
+        assert!(dao
+            .settle(
+                vec!["fee-order".to_string()],
+                "erc20".to_string(),
+                "0x01".to_string()
+            )
+            .await
+            .unwrap());
+
+        let statuses: Vec<_> = dao
+            .list(None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fee| (fee.invoice_id, fee.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("invoice-order-2".to_string(), FeeStatus::Pending),
+                ("invoice-order-1".to_string(), FeeStatus::Paid),
+            ]
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::DbResult;
+use crate::models::fiat_annotation::FiatAnnotation;
+use crate::schema::pay_fiat_annotation::dsl;
+
+use chrono::NaiveDateTime;
+use diesel::{self, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use ya_client_model::NodeId;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+pub struct FiatAnnotationDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for FiatAnnotationDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> FiatAnnotationDao<'c> {
+    /// Stores annotation. The first annotation of given entity is kept.
+    pub async fn insert(&self, annotation: FiatAnnotation) -> DbResult<()> {
+        do_with_transaction(self.pool, "fiat_annotation_dao_insert", move |conn| {
+            diesel::insert_or_ignore_into(dsl::pay_fiat_annotation)
+                .values(annotation)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn list(
+        &self,
+        owner_id: Option<NodeId>,
+        since: Option<NaiveDateTime>,
+    ) -> DbResult<Vec<FiatAnnotation>> {
+        readonly_transaction(self.pool, "fiat_annotation_dao_list", move |conn| {
+            let mut query = dsl::pay_fiat_annotation.into_boxed();
+            if let Some(owner_id) = owner_id {
+                query = query.filter(dsl::owner_id.eq(owner_id));
+            }
+            if let Some(since) = since {
+                query = query.filter(dsl::timestamp.ge(since));
+            }
+            let annotations = query.order_by(dsl::timestamp.asc()).load(conn)?;
+            Ok(annotations)
+        })
+        .await
+    }
+}
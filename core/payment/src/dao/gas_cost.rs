@@ -0,0 +1,136 @@
+use crate::dao::journal;
+use crate::error::DbResult;
+use crate::gas::PaidPart;
+use crate::models::gas_cost::TxGasCost;
+use crate::models::journal::WriteObj as JournalEntry;
+use crate::schema::pay_activity::dsl as activity_dsl;
+use crate::schema::pay_activity_payment::dsl as activity_pay_dsl;
+use crate::schema::pay_agreement_payment::dsl as agreement_pay_dsl;
+use crate::schema::pay_payment::dsl as payment_dsl;
+use crate::schema::pay_tx_gas_cost::dsl;
+
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use diesel::{self, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl, RunQueryDsl};
+use std::collections::HashMap;
+
+use ya_client_model::NodeId;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+use ya_persistence::types::{BigDecimalField, Role};
+
+type PartRow = (NodeId, Vec<u8>, String, String, BigDecimalField);
+
+pub struct GasCostDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for GasCostDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> GasCostDao<'c> {
+    /// Stores transaction fee. The same fee is reported for every order paid
+    /// by the transaction, so only the first report is kept.
+    pub async fn insert(&self, gas_cost: TxGasCost) -> DbResult<()> {
+        do_with_transaction(self.pool, "gas_cost_dao_insert", move |conn| {
+            let journal_entry = JournalEntry::gas_cost(&gas_cost);
+            diesel::insert_or_ignore_into(dsl::pay_tx_gas_cost)
+                .values(gas_cost)
+                .execute(conn)?;
+            journal::post(journal_entry, conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn fees(
+        &self,
+        owner_id: Option<NodeId>,
+    ) -> DbResult<HashMap<(NodeId, Vec<u8>), BigDecimal>> {
+        readonly_transaction(self.pool, "gas_cost_dao_fees", move |conn| {
+            let mut query = dsl::pay_tx_gas_cost.into_boxed();
+            if let Some(owner_id) = owner_id {
+                query = query.filter(dsl::owner_id.eq(owner_id));
+            }
+            let fees: Vec<TxGasCost> = query.load(conn)?;
+            Ok(fees
+                .into_iter()
+                .map(|fee| ((fee.owner_id, fee.details), fee.gas_cost.0))
+                .collect())
+        })
+        .await
+    }
+
+    /// Amounts paid for Agreements and their Activities by sent payments.
+    pub async fn paid_parts(
+        &self,
+        owner_id: Option<NodeId>,
+        since: Option<NaiveDateTime>,
+    ) -> DbResult<Vec<PaidPart>> {
+        readonly_transaction(self.pool, "gas_cost_dao_paid_parts", move |conn| {
+            let mut agreement_query = agreement_pay_dsl::pay_agreement_payment
+                .inner_join(
+                    payment_dsl::pay_payment.on(agreement_pay_dsl::owner_id
+                        .eq(payment_dsl::owner_id)
+                        .and(agreement_pay_dsl::payment_id.eq(payment_dsl::id))),
+                )
+                .filter(payment_dsl::role.eq(Role::Requestor))
+                .select((
+                    agreement_pay_dsl::owner_id,
+                    payment_dsl::details,
+                    agreement_pay_dsl::agreement_id,
+                    payment_dsl::payment_platform,
+                    agreement_pay_dsl::amount,
+                ))
+                .into_boxed();
+            let mut activity_query = activity_pay_dsl::pay_activity_payment
+                .inner_join(
+                    payment_dsl::pay_payment.on(activity_pay_dsl::owner_id
+                        .eq(payment_dsl::owner_id)
+                        .and(activity_pay_dsl::payment_id.eq(payment_dsl::id))),
+                )
+                .inner_join(
+                    activity_dsl::pay_activity.on(activity_pay_dsl::owner_id
+                        .eq(activity_dsl::owner_id)
+                        .and(activity_pay_dsl::activity_id.eq(activity_dsl::id))),
+                )
+                .filter(payment_dsl::role.eq(Role::Requestor))
+                .select((
+                    activity_pay_dsl::owner_id,
+                    payment_dsl::details,
+                    activity_dsl::agreement_id,
+                    payment_dsl::payment_platform,
+                    activity_pay_dsl::amount,
+                ))
+                .into_boxed();
+
+            if let Some(owner_id) = owner_id {
+                agreement_query = agreement_query.filter(payment_dsl::owner_id.eq(owner_id));
+                activity_query = activity_query.filter(payment_dsl::owner_id.eq(owner_id));
+            }
+            if let Some(since) = since {
+                agreement_query = agreement_query.filter(payment_dsl::timestamp.ge(since));
+                activity_query = activity_query.filter(payment_dsl::timestamp.ge(since));
+            }
+
+            let mut rows: Vec<PartRow> = agreement_query.load(conn)?;
+            rows.extend(activity_query.load::<PartRow>(conn)?);
+
+            Ok(rows
+                .into_iter()
+                .map(
+                    |(owner_id, details, agreement_id, platform, amount)| PaidPart {
+                        owner_id,
+                        details,
+                        agreement_id,
+                        platform,
+                        amount: amount.0,
+                    },
+                )
+                .collect())
+        })
+        .await
+    }
+}
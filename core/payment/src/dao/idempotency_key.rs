@@ -0,0 +1,67 @@
+use crate::error::DbResult;
+use crate::models::idempotency_key::{ReadObj, WriteObj};
+use crate::schema::pay_idempotency_key::dsl;
+
+use chrono::NaiveDateTime;
+use diesel::{self, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use ya_client_model::NodeId;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+pub struct IdempotencyKeyDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for IdempotencyKeyDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> IdempotencyKeyDao<'c> {
+    /// Returns the entry if a message with given key was already processed
+    pub async fn get(
+        &self,
+        key: String,
+        owner_id: NodeId,
+        peer_id: NodeId,
+    ) -> DbResult<Option<ReadObj>> {
+        readonly_transaction(self.pool, "idempotency_key_dao_get", move |conn| {
+            let entry = dsl::pay_idempotency_key
+                .find((owner_id, peer_id, key))
+                .first(conn)
+                .optional()?;
+            Ok(entry)
+        })
+        .await
+    }
+
+    /// Records a successfully processed message. Recording the same key twice is a no-op.
+    pub async fn insert(
+        &self,
+        key: String,
+        owner_id: NodeId,
+        peer_id: NodeId,
+        payment_id: String,
+    ) -> DbResult<()> {
+        let entry = WriteObj::new(key, owner_id, peer_id, payment_id);
+        do_with_transaction(self.pool, "idempotency_key_dao_insert", move |conn| {
+            diesel::insert_or_ignore_into(dsl::pay_idempotency_key)
+                .values(entry)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Removes entries older than `before`, returning the number of removed keys
+    pub async fn purge(&self, before: NaiveDateTime) -> DbResult<usize> {
+        do_with_transaction(self.pool, "idempotency_key_dao_purge", move |conn| {
+            let removed =
+                diesel::delete(dsl::pay_idempotency_key.filter(dsl::created_ts.lt(before)))
+                    .execute(conn)?;
+            Ok(removed)
+        })
+        .await
+    }
+}
@@ -1,6 +1,7 @@
-use crate::dao::{agreement, invoice_event};
+use crate::dao::{agreement, invoice_event, journal};
 use crate::error::{DbError, DbResult};
 use crate::models::invoice::{equivalent, InvoiceXActivity, ReadObj, WriteObj};
+use crate::models::journal::WriteObj as JournalEntry;
 use crate::schema::pay_agreement::dsl as agreement_dsl;
 use crate::schema::pay_invoice::dsl;
 use crate::schema::pay_invoice_x_activity::dsl as activity_dsl;
@@ -13,7 +14,7 @@ use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use ya_client_model::payment::{DocumentStatus, Invoice, InvoiceEventType, NewInvoice, Rejection};
 use ya_client_model::NodeId;
-use ya_core_model::payment::local::StatValue;
+use ya_core_model::payment::local::{StatValue, StatsInterval};
 use ya_persistence::executor::{
     do_with_transaction, readonly_transaction, AsDao, ConnType, PoolType,
 };
@@ -138,6 +139,17 @@ impl<'c> InvoiceDao<'c> {
         self.insert(invoice, activity_ids).await
     }
 
+    /// Restores invoice from the peer's copy. Invoices issued by the owner keep
+    /// their status, received ones have to be accepted again.
+    pub async fn insert_recovered(&self, invoice: Invoice, owner_id: NodeId) -> DbResult<()> {
+        let activity_ids = invoice.activity_ids.clone();
+        let invoice = match invoice.issuer_id == owner_id {
+            true => WriteObj::recovered_issued(invoice),
+            false => WriteObj::new_received(invoice),
+        };
+        self.insert(invoice, activity_ids).await
+    }
+
     pub async fn list(
         &self,
         role: Option<Role>,
@@ -272,29 +284,71 @@ impl<'c> InvoiceDao<'c> {
         node_id: NodeId,
         since: DateTime<Utc>,
     ) -> DbResult<BTreeMap<(Role, DocumentStatus), StatValue>> {
-        let results =
-            readonly_transaction(self.pool, "invoice_dao_last_invoice_stats", move |conn| {
-                let invoices: Vec<ReadObj> = query!()
-                    .filter(dsl::owner_id.eq(node_id))
-                    .filter(dsl::timestamp.gt(since.naive_utc()))
-                    .load(conn)?;
-                Ok::<_, DbError>(invoices)
-            })
-            .await?;
         let mut stats = BTreeMap::<(Role, DocumentStatus), StatValue>::new();
-        for invoice in results {
-            let key = (invoice.role, DocumentStatus::try_from(invoice.status)?);
-            let entry = stats.entry(key).or_default();
-            let total_amount = invoice.amount.0;
-            *entry = entry.clone()
-                + StatValue {
-                    total_amount,
-                    agreements_count: 1,
-                };
+        for invoice in self.invoices_since(node_id, since).await? {
+            add_to_stats(&mut stats, invoice)?;
         }
         Ok(stats)
     }
 
+    /// Invoice stats split into buckets of `interval`, keyed by bucket start.
+    /// Buckets without invoices are included, so the series has no gaps.
+    pub async fn invoice_stats_series(
+        &self,
+        node_id: NodeId,
+        since: DateTime<Utc>,
+        interval: StatsInterval,
+    ) -> DbResult<BTreeMap<DateTime<Utc>, BTreeMap<(Role, DocumentStatus), StatValue>>> {
+        let mut series = BTreeMap::<_, BTreeMap<_, _>>::new();
+
+        let mut start = interval.bucket_start(since);
+        let end = Utc::now();
+        while start <= end {
+            series.insert(start, Default::default());
+            start = start + interval.duration();
+        }
+
+        for invoice in self.invoices_since(node_id, since).await? {
+            let bucket =
+                interval.bucket_start(DateTime::from_naive_utc_and_offset(invoice.timestamp, Utc));
+            add_to_stats(series.entry(bucket).or_default(), invoice)?;
+        }
+        Ok(series)
+    }
+
+    async fn invoices_since(
+        &self,
+        node_id: NodeId,
+        since: DateTime<Utc>,
+    ) -> DbResult<Vec<ReadObj>> {
+        readonly_transaction(self.pool, "invoice_dao_invoices_since", move |conn| {
+            let invoices: Vec<ReadObj> = query!()
+                .filter(dsl::owner_id.eq(node_id))
+                .filter(dsl::timestamp.gt(since.naive_utc()))
+                .load(conn)?;
+            Ok::<_, DbError>(invoices)
+        })
+        .await
+    }
+
+    /// Invoices of given role, which are neither paid, nor rejected or cancelled.
+    pub async fn unpaid(&self, owner_id: NodeId, role: Role) -> DbResult<Vec<ReadObj>> {
+        readonly_transaction(self.pool, "invoice_dao_unpaid", move |conn| {
+            let invoices: Vec<ReadObj> = query!()
+                .filter(dsl::owner_id.eq(owner_id))
+                .filter(dsl::role.eq(role.to_string()))
+                .filter(dsl::status.eq_any(vec![
+                    DocumentStatus::Issued.to_string(),
+                    DocumentStatus::Received.to_string(),
+                    DocumentStatus::Accepted.to_string(),
+                ]))
+                .order_by(dsl::timestamp.asc())
+                .load(conn)?;
+            Ok::<_, DbError>(invoices)
+        })
+        .await
+    }
+
     pub async fn mark_received(&self, invoice_id: String, owner_id: NodeId) -> DbResult<()> {
         do_with_transaction(self.pool, "invoice_dao_mark_received", move |conn| {
             update_status(&invoice_id, &owner_id, &DocumentStatus::Received, conn)
@@ -334,6 +388,22 @@ impl<'c> InvoiceDao<'c> {
             update_status(&invoice_id, &owner_id, &status, conn)?;
             agreement::set_amount_accepted(&agreement_id, &owner_id, &amount, conn)?;
 
+            let payment_platform: String = agreement_dsl::pay_agreement
+                .find((&agreement_id, &owner_id))
+                .select(agreement_dsl::payment_platform)
+                .first(conn)?;
+            journal::post(
+                JournalEntry::invoice_accepted(
+                    &invoice_id,
+                    owner_id,
+                    &role,
+                    &agreement_id,
+                    payment_platform,
+                    amount,
+                ),
+                conn,
+            )?;
+
             for event in events {
                 invoice_event::create(invoice_id.clone(), owner_id, event, conn)?;
             }
@@ -394,6 +464,40 @@ impl<'c> InvoiceDao<'c> {
         .await
     }
 
+    /// Invoices exchanged with the peer since given time, which are neither settled
+    /// nor cancelled. Shared with the peer rebuilding its payment state.
+    pub async fn outstanding_with_peer(
+        &self,
+        peer_id: NodeId,
+        since: NaiveDateTime,
+    ) -> DbResult<Vec<Invoice>> {
+        readonly_transaction(
+            self.pool,
+            "invoice_dao_outstanding_with_peer",
+            move |conn| {
+                let invoices: Vec<ReadObj> = query!()
+                    .filter(agreement_dsl::peer_id.eq(peer_id))
+                    .filter(dsl::timestamp.ge(since))
+                    .filter(dsl::status.ne_all(vec![
+                        DocumentStatus::Settled.to_string(),
+                        DocumentStatus::Cancelled.to_string(),
+                    ]))
+                    .order_by(dsl::timestamp.asc())
+                    .load(conn)?;
+
+                let invoice_ids = invoices
+                    .iter()
+                    .map(|invoice| invoice.id.clone())
+                    .collect::<Vec<_>>();
+                let activities = activity_dsl::pay_invoice_x_activity
+                    .filter(activity_dsl::invoice_id.eq_any(invoice_ids))
+                    .load(conn)?;
+                join_invoices_with_activities(invoices, activities)
+            },
+        )
+        .await
+    }
+
     /// All invoices with status Issued or Accepted and provider role
     pub async fn dangling(&self, owner_id: NodeId) -> DbResult<Vec<Invoice>> {
         readonly_transaction(self.pool, "invoice_dao_dangling", move |conn| {
@@ -554,3 +658,15 @@ fn join_invoices_with_activities(
         })
         .collect()
 }
+
+fn add_to_stats(
+    stats: &mut BTreeMap<(Role, DocumentStatus), StatValue>,
+    invoice: ReadObj,
+) -> DbResult<()> {
+    let key = (invoice.role, DocumentStatus::try_from(invoice.status)?);
+    *stats.entry(key).or_default() += StatValue {
+        total_amount: invoice.amount.0,
+        agreements_count: 1,
+    };
+    Ok(())
+}
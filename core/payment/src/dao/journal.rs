@@ -0,0 +1,169 @@
+use crate::accounting::{JournalEntry, Period};
+use crate::error::{DbError, DbResult};
+use crate::models::journal::{PeriodReadObj, PeriodWriteObj, ReadObj, WriteObj};
+use crate::schema::pay_journal_entry::dsl;
+use crate::schema::pay_journal_period::dsl as period_dsl;
+
+use bigdecimal::Zero;
+use chrono::NaiveDateTime;
+use diesel::{self, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use std::collections::HashMap;
+
+use ya_client_model::NodeId;
+use ya_persistence::executor::{
+    do_with_transaction, readonly_transaction, AsDao, ConnType, PoolType,
+};
+use ya_persistence::types::AdaptTimestamp;
+
+/// Posts entry in the transaction recording the payment event. Entry of the same
+/// source is posted only once, so repeated processing of the event is harmless.
+pub fn post(entry: WriteObj, conn: &ConnType) -> DbResult<()> {
+    if entry.amount.0.is_zero() {
+        return Ok(());
+    }
+    diesel::insert_or_ignore_into(dsl::pay_journal_entry)
+        .values(entry)
+        .execute(conn)?;
+    Ok(())
+}
+
+pub struct JournalDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for JournalDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> JournalDao<'c> {
+    /// Entries in chronological order. `period_id` selects entries of closed period.
+    pub async fn list(
+        &self,
+        owner_id: NodeId,
+        period_id: Option<i32>,
+        since: Option<NaiveDateTime>,
+        until: Option<NaiveDateTime>,
+    ) -> DbResult<Vec<JournalEntry>> {
+        readonly_transaction(self.pool, "journal_dao_list", move |conn| {
+            let mut query = dsl::pay_journal_entry
+                .filter(dsl::owner_id.eq(owner_id))
+                .into_boxed();
+            if let Some(period_id) = period_id {
+                query = query.filter(dsl::period_id.eq(period_id));
+            }
+            if let Some(since) = since {
+                query = query.filter(dsl::timestamp.ge(since.adapt()));
+            }
+            if let Some(until) = until {
+                query = query.filter(dsl::timestamp.lt(until.adapt()));
+            }
+            let entries: Vec<ReadObj> = query
+                .order_by((dsl::timestamp.asc(), dsl::source.asc()))
+                .load(conn)?;
+            Ok(entries.into_iter().map(ReadObj::into_api).collect())
+        })
+        .await
+    }
+
+    /// Entries included in balances at the end of the period. All entries, when
+    /// `period_id` is not given.
+    pub async fn up_to_period(
+        &self,
+        owner_id: NodeId,
+        period_id: Option<i32>,
+    ) -> DbResult<Vec<JournalEntry>> {
+        readonly_transaction(self.pool, "journal_dao_up_to_period", move |conn| {
+            let mut query = dsl::pay_journal_entry
+                .filter(dsl::owner_id.eq(owner_id))
+                .into_boxed();
+            if let Some(period_id) = period_id {
+                query = query.filter(dsl::period_id.le(period_id));
+            }
+            let entries: Vec<ReadObj> = query.load(conn)?;
+            Ok(entries.into_iter().map(ReadObj::into_api).collect())
+        })
+        .await
+    }
+
+    /// Closed periods, the oldest first.
+    pub async fn periods(&self, owner_id: NodeId) -> DbResult<Vec<Period>> {
+        readonly_transaction(self.pool, "journal_dao_periods", move |conn| {
+            let periods: Vec<PeriodReadObj> = period_dsl::pay_journal_period
+                .filter(period_dsl::owner_id.eq(owner_id))
+                .order_by(period_dsl::id.asc())
+                .load(conn)?;
+            let assigned: Vec<Option<i32>> = dsl::pay_journal_entry
+                .filter(dsl::owner_id.eq(owner_id))
+                .filter(dsl::period_id.is_not_null())
+                .select(dsl::period_id)
+                .load(conn)?;
+
+            let mut counts = HashMap::<i32, u64>::new();
+            for period_id in assigned.into_iter().flatten() {
+                *counts.entry(period_id).or_default() += 1;
+            }
+            Ok(periods
+                .into_iter()
+                .map(|period| {
+                    let entries = counts.get(&period.id).copied().unwrap_or_default();
+                    period.into_api(entries)
+                })
+                .collect())
+        })
+        .await
+    }
+
+    pub async fn get_period(&self, owner_id: NodeId, period_id: i32) -> DbResult<Option<Period>> {
+        Ok(self
+            .periods(owner_id)
+            .await?
+            .into_iter()
+            .find(|period| period.id == period_id))
+    }
+
+    /// Closes period ending at `until`. All entries up to `until`, which aren't part
+    /// of earlier periods, are assigned to it. Entries of closed periods never change.
+    pub async fn close_period(&self, owner_id: NodeId, until: NaiveDateTime) -> DbResult<Period> {
+        do_with_transaction(self.pool, "journal_dao_close_period", move |conn| {
+            let last: Option<NaiveDateTime> = period_dsl::pay_journal_period
+                .filter(period_dsl::owner_id.eq(owner_id))
+                .select(period_dsl::closed_until)
+                .order_by(period_dsl::id.desc())
+                .first(conn)
+                .optional()?;
+            if let Some(last) = last {
+                if until <= last {
+                    return Err(DbError::Integrity(format!(
+                        "Period has to end after the last closed period ({})",
+                        last
+                    )));
+                }
+            }
+
+            diesel::insert_into(period_dsl::pay_journal_period)
+                .values(PeriodWriteObj {
+                    owner_id,
+                    closed_until: until.adapt(),
+                })
+                .execute(conn)?;
+            let period: PeriodReadObj = period_dsl::pay_journal_period
+                .filter(period_dsl::owner_id.eq(owner_id))
+                .order_by(period_dsl::id.desc())
+                .first(conn)?;
+
+            let entries = diesel::update(
+                dsl::pay_journal_entry
+                    .filter(dsl::owner_id.eq(owner_id))
+                    .filter(dsl::period_id.is_null())
+                    .filter(dsl::timestamp.le(until.adapt())),
+            )
+            .set(dsl::period_id.eq(period.id))
+            .execute(conn)?;
+
+            Ok(period.into_api(entries as u64))
+        })
+        .await
+    }
+}
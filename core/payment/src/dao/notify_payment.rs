@@ -0,0 +1,181 @@
+use crate::error::DbResult;
+use crate::models::notify_payment::WriteObj;
+use crate::schema::pay_notify_payment::dsl;
+
+use diesel::{self, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use ya_persistence::executor::{
+    do_with_transaction, readonly_transaction, AsDao, ConnType, PoolType,
+};
+
+pub struct NotifyPaymentDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for NotifyPaymentDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> NotifyPaymentDao<'c> {
+    /// Stores the notification unless it was already received. Returns id of the payment,
+    /// when the notification was already processed.
+    pub async fn record(&self, notification: WriteObj) -> DbResult<Option<String>> {
+        do_with_transaction(self.pool, "notify_payment_dao_record", move |conn| {
+            diesel::insert_or_ignore_into(dsl::pay_notify_payment)
+                .values(&notification)
+                .execute(conn)?;
+
+            let payment_id: Option<Option<String>> = dsl::pay_notify_payment
+                .find(notification.key())
+                .select(dsl::payment_id)
+                .first(conn)
+                .optional()?;
+            Ok(payment_id.flatten())
+        })
+        .await
+    }
+
+    /// Notifications of the driver, which were received, but not processed.
+    pub async fn pending(&self, driver: String) -> DbResult<Vec<WriteObj>> {
+        readonly_transaction(self.pool, "notify_payment_dao_pending", move |conn| {
+            let pending = dsl::pay_notify_payment
+                .filter(dsl::driver.eq(driver))
+                .filter(dsl::payment_id.is_null())
+                .order_by(dsl::created_ts.asc())
+                .select((
+                    dsl::driver,
+                    dsl::details,
+                    dsl::order_ids,
+                    dsl::notification,
+                    dsl::payment_id,
+                ))
+                .load(conn)?;
+            Ok(pending)
+        })
+        .await
+    }
+}
+
+pub fn mark_processed(
+    key: (String, Vec<u8>, String),
+    payment_id: &str,
+    conn: &ConnType,
+) -> DbResult<()> {
+    diesel::update(dsl::pay_notify_payment.find(key))
+        .set(dsl::payment_id.eq(payment_id))
+        .execute(conn)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::PaymentDao;
+    use bigdecimal::BigDecimal;
+    use ya_client_model::payment::ActivityPayment;
+    use ya_client_model::NodeId;
+    use ya_core_model::driver::PaymentConfirmation;
+    use ya_core_model::payment::local::NotifyPayment;
+    use ya_persistence::executor::DbExecutor;
+
+    fn db(name: &str) -> DbExecutor {
+        let db = DbExecutor::in_memory(name).unwrap();
+        db.apply_migration(crate::migrations::run_with_output)
+            .unwrap();
+        db
+    }
+
+    fn notification() -> NotifyPayment {
+        NotifyPayment {
+            driver: "erc20".to_string(),
+            platform: "erc20-holesky-tglm".to_string(),
+            amount: BigDecimal::from(1),
+            sender: "0xa".to_string(),
+            recipient: "0xb".to_string(),
+            order_ids: vec!["order-2".to_string(), "order-1".to_string()],
+            confirmation: PaymentConfirmation {
+                confirmation: vec![0xab; 32],
+            },
+            gas_cost: None,
+        }
+    }
+
+    async fn process(
+        db: &DbExecutor,
+        msg: &NotifyPayment,
+        activity_payments: Vec<ActivityPayment>,
+    ) -> DbResult<String> {
+        db.as_dao::<PaymentDao>()
+            .create_new(
+                NodeId::default(),
+                NodeId::default(),
+                msg.sender.clone(),
+                msg.recipient.clone(),
+                msg.platform.clone(),
+                msg.amount.clone(),
+                msg.confirmation.confirmation.clone(),
+                activity_payments,
+                vec![],
+                msg.order_ids.clone(),
+                WriteObj::new(msg).unwrap(),
+            )
+            .await
+    }
+
+    #[actix_rt::test]
+    async fn crash_before_accounting_is_recovered() {
+        let db = db("crash_before_accounting");
+        let dao: NotifyPaymentDao = db.as_dao();
+        let msg = notification();
+
+        // Driver confirmed the payment, but node crashed before accounting it.
+        assert_eq!(
+            dao.record(WriteObj::new(&msg).unwrap()).await.unwrap(),
+            None
+        );
+
+        let pending = dao.pending("erc20".to_string()).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].order_ids, "order-1,order-2");
+        let recovered = pending[0].notification().unwrap();
+        assert_eq!(recovered.order_ids, msg.order_ids);
+
+        // Recovery after restart repeats the notification.
+        assert_eq!(
+            dao.record(WriteObj::new(&recovered).unwrap())
+                .await
+                .unwrap(),
+            None
+        );
+        let payment_id = process(&db, &recovered, vec![]).await.unwrap();
+
+        // Late duplicate from the driver is recognized as processed.
+        assert_eq!(
+            dao.record(WriteObj::new(&msg).unwrap()).await.unwrap(),
+            Some(payment_id)
+        );
+        assert!(dao.pending("erc20".to_string()).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn failed_accounting_keeps_notification_pending() {
+        let db = db("failed_accounting");
+        let dao: NotifyPaymentDao = db.as_dao();
+        let msg = notification();
+        dao.record(WriteObj::new(&msg).unwrap()).await.unwrap();
+
+        // Unknown activity aborts the transaction after the payment row was inserted.
+        let activity_payment = ActivityPayment {
+            activity_id: "unknown".to_string(),
+            amount: BigDecimal::from(1),
+            allocation_id: None,
+        };
+        assert!(process(&db, &msg, vec![activity_payment]).await.is_err());
+
+        assert_eq!(dao.pending("erc20".to_string()).await.unwrap().len(), 1);
+        assert!(process(&db, &msg, vec![]).await.is_ok());
+        assert!(dao.pending("erc20".to_string()).await.unwrap().is_empty());
+    }
+}
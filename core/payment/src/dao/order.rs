@@ -1,17 +1,21 @@
 use crate::dao::{activity, agreement, allocation};
 use crate::error::DbResult;
 use crate::models::order::{ReadObj, WriteObj};
+use crate::schema::pay_activity::dsl as activity_dsl;
 use crate::schema::pay_debit_note::dsl as debit_note_dsl;
 use crate::schema::pay_invoice::dsl as invoice_dsl;
 use crate::schema::pay_order::dsl;
 use diesel::{
-    self, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, NullableExpressionMethods, QueryDsl,
-    RunQueryDsl,
+    self, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, NullableExpressionMethods,
+    OptionalExtension, QueryDsl, RunQueryDsl,
 };
+use ya_client_model::NodeId;
 use ya_core_model::payment::local::{
     DebitNotePayment, InvoicePayment, PaymentTitle, SchedulePayment,
 };
-use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+use ya_persistence::executor::{
+    do_with_transaction, readonly_transaction, AsDao, ConnType, PoolType,
+};
 
 pub struct OrderDao<'c> {
     pool: &'c PoolType,
@@ -45,7 +49,12 @@ impl<'c> OrderDao<'c> {
                 }
             };
             let order = WriteObj::new(msg, id, driver);
-            allocation::spend_from_allocation(&order.allocation_id, &order.amount, conn)?;
+            allocation::spend_from_allocation(
+                &order.allocation_id,
+                &order.payment_platform,
+                &order.amount,
+                conn,
+            )?;
             diesel::insert_into(dsl::pay_order)
                 .values(order)
                 .execute(conn)?;
@@ -90,4 +99,48 @@ impl<'c> OrderDao<'c> {
         })
         .await
     }
+
+    /// Allocation, from which the latest debit note of the agreement was paid.
+    pub async fn last_agreement_allocation(
+        &self,
+        agreement_id: String,
+        payer_id: NodeId,
+    ) -> DbResult<Option<String>> {
+        readonly_transaction(
+            self.pool,
+            "order_dao_last_agreement_allocation",
+            move |conn| {
+                let allocation_id = dsl::pay_order
+                    .inner_join(
+                        debit_note_dsl::pay_debit_note.on(dsl::debit_note_id
+                            .eq(debit_note_dsl::id.nullable())
+                            .and(dsl::payer_id.eq(debit_note_dsl::owner_id))),
+                    )
+                    .inner_join(
+                        activity_dsl::pay_activity.on(debit_note_dsl::activity_id
+                            .eq(activity_dsl::id)
+                            .and(debit_note_dsl::owner_id.eq(activity_dsl::owner_id))),
+                    )
+                    .filter(activity_dsl::agreement_id.eq(agreement_id))
+                    .filter(dsl::payer_id.eq(payer_id))
+                    .order_by(debit_note_dsl::timestamp.desc())
+                    .select(dsl::allocation_id)
+                    .first(conn)
+                    .optional()?;
+                Ok(allocation_id)
+            },
+        )
+        .await
+    }
+}
+
+pub fn mark_paid(ids: &[String], driver: &str, conn: &ConnType) -> DbResult<()> {
+    diesel::update(
+        dsl::pay_order
+            .filter(dsl::id.eq_any(ids))
+            .filter(dsl::driver.eq(driver)),
+    )
+    .set(dsl::is_paid.eq(true))
+    .execute(conn)?;
+    Ok(())
 }
@@ -1,5 +1,7 @@
-use crate::dao::{activity, agreement};
+use crate::dao::{activity, agreement, journal, notify_payment, order};
 use crate::error::DbResult;
+use crate::models::journal::WriteObj as JournalEntry;
+use crate::models::notify_payment::WriteObj as NotificationObj;
 use crate::models::payment::{
     ActivityPayment as DbActivityPayment, AgreementPayment as DbAgreementPayment, ReadObj, WriteObj,
 };
@@ -83,6 +85,27 @@ fn insert_agreement_payments(
     Ok(())
 }
 
+fn insert_payment(
+    payment: WriteObj,
+    activity_payments: Vec<ActivityPayment>,
+    agreement_payments: Vec<AgreementPayment>,
+    payment_id: &str,
+    owner_id: &NodeId,
+    conn: &ConnType,
+) -> DbResult<()> {
+    log::trace!("Inserting payment...");
+    let journal_entry = JournalEntry::payment(&payment);
+    diesel::insert_into(dsl::pay_payment)
+        .values(payment)
+        .execute(conn)?;
+    log::trace!("Payment inserted.");
+    journal::post(journal_entry, conn)?;
+
+    insert_activity_payments(activity_payments, payment_id, owner_id, conn)?;
+    insert_agreement_payments(agreement_payments, payment_id, owner_id, conn)?;
+    Ok(())
+}
+
 impl<'c> AsDao<'c> for PaymentDao<'c> {
     fn as_dao(pool: &'c PoolType) -> Self {
         Self { pool }
@@ -101,20 +124,21 @@ impl<'c> PaymentDao<'c> {
         let amount = payment.amount.clone();
 
         do_with_transaction(self.pool, "payment_dao_insert", move |conn| {
-            log::trace!("Inserting payment...");
-            diesel::insert_into(dsl::pay_payment)
-                .values(payment)
-                .execute(conn)?;
-            log::trace!("Payment inserted.");
-
-            insert_activity_payments(activity_payments, &payment_id, &owner_id, conn)?;
-            insert_agreement_payments(agreement_payments, &payment_id, &owner_id, conn)?;
-
-            Ok(())
+            insert_payment(
+                payment,
+                activity_payments,
+                agreement_payments,
+                &payment_id,
+                &owner_id,
+                conn,
+            )
         })
         .await
     }
 
+    /// Accounts payment confirmed by the driver. Orders are marked as paid and
+    /// the notification as processed in the same transaction, so processing
+    /// interrupted at any point can be safely repeated.
     #[allow(clippy::too_many_arguments)]
     pub async fn create_new(
         &self,
@@ -127,6 +151,8 @@ impl<'c> PaymentDao<'c> {
         details: Vec<u8>,
         activity_payments: Vec<ActivityPayment>,
         agreement_payments: Vec<AgreementPayment>,
+        order_ids: Vec<String>,
+        notification: NotificationObj,
     ) -> DbResult<String> {
         let payment = WriteObj::new_sent(
             payer_id,
@@ -140,9 +166,21 @@ impl<'c> PaymentDao<'c> {
             None,
         );
         let payment_id = payment.id.clone();
-        self.insert(payment, activity_payments, agreement_payments)
-            .await?;
-        Ok(payment_id)
+
+        do_with_transaction(self.pool, "payment_dao_create_new", move |conn| {
+            insert_payment(
+                payment,
+                activity_payments,
+                agreement_payments,
+                &payment_id,
+                &payer_id,
+                conn,
+            )?;
+            order::mark_paid(&order_ids, &notification.driver, conn)?;
+            notify_payment::mark_processed(notification.key(), &payment_id, conn)?;
+            Ok(payment_id)
+        })
+        .await
     }
 
     pub async fn insert_received(
@@ -222,6 +260,25 @@ impl<'c> PaymentDao<'c> {
         .await
     }
 
+    /// Payments of given role made after `since`, oldest first.
+    pub async fn list_since(
+        &self,
+        owner_id: NodeId,
+        role: Role,
+        since: NaiveDateTime,
+    ) -> DbResult<Vec<ReadObj>> {
+        readonly_transaction(self.pool, "payment_dao_list_since", move |conn| {
+            let payments = dsl::pay_payment
+                .filter(dsl::owner_id.eq(owner_id))
+                .filter(dsl::role.eq(role))
+                .filter(dsl::timestamp.gt(since))
+                .order_by(dsl::timestamp.asc())
+                .load(conn)?;
+            Ok(payments)
+        })
+        .await
+    }
+
     pub async fn get_for_confirmation(
         &self,
         details: Vec<u8>,
@@ -0,0 +1,46 @@
+use crate::error::DbResult;
+use crate::models::payment_receipt::PaymentReceipt;
+use crate::schema::pay_payment_receipt::dsl;
+
+use diesel::{self, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use ya_client_model::NodeId;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+pub struct PaymentReceiptDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for PaymentReceiptDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> PaymentReceiptDao<'c> {
+    /// Stores receipt. Redelivered receipts replace the stored one.
+    pub async fn upsert(&self, receipt: PaymentReceipt) -> DbResult<()> {
+        do_with_transaction(self.pool, "payment_receipt_dao_upsert", move |conn| {
+            diesel::replace_into(dsl::pay_payment_receipt)
+                .values(receipt)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn get(
+        &self,
+        payment_id: String,
+        owner_id: NodeId,
+    ) -> DbResult<Option<PaymentReceipt>> {
+        readonly_transaction(self.pool, "payment_receipt_dao_get", move |conn| {
+            let receipt = dsl::pay_payment_receipt
+                .find((payment_id, owner_id))
+                .first(conn)
+                .optional()?;
+            Ok(receipt)
+        })
+        .await
+    }
+}
@@ -0,0 +1,44 @@
+use crate::error::DbResult;
+use crate::models::withdrawal::{ReadObj, WriteObj};
+use crate::schema::pay_withdrawal::dsl;
+
+use diesel::{self, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use ya_core_model::payment::local::Withdrawal;
+use ya_persistence::executor::{do_with_transaction, readonly_transaction, AsDao, PoolType};
+
+pub struct WithdrawalDao<'c> {
+    pool: &'c PoolType,
+}
+
+impl<'c> AsDao<'c> for WithdrawalDao<'c> {
+    fn as_dao(pool: &'c PoolType) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'c> WithdrawalDao<'c> {
+    pub async fn insert(&self, withdrawal: Withdrawal) -> DbResult<()> {
+        let withdrawal = WriteObj::from(withdrawal);
+        do_with_transaction(self.pool, "withdrawal_dao_insert", move |conn| {
+            diesel::insert_into(dsl::pay_withdrawal)
+                .values(withdrawal)
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// The newest withdrawals first.
+    pub async fn list(&self, limit: Option<u32>) -> DbResult<Vec<Withdrawal>> {
+        readonly_transaction(self.pool, "withdrawal_dao_list", move |conn| {
+            let mut query = dsl::pay_withdrawal.order_by(dsl::id.desc()).into_boxed();
+            if let Some(limit) = limit {
+                query = query.limit(limit as i64);
+            }
+            let withdrawals: Vec<ReadObj> = query.load(conn)?;
+            Ok(withdrawals.into_iter().map(ReadObj::into_api).collect())
+        })
+        .await
+    }
+}
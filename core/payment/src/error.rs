@@ -112,6 +112,7 @@ pub mod processor {
     use bigdecimal::BigDecimal;
     use std::fmt::Display;
     use tokio::time::error::Elapsed;
+    use ya_client_model::NodeId;
     use ya_core_model::driver::AccountMode;
     use ya_core_model::payment::local::{
         GenericError, ValidateAllocationError as GsbValidateAllocationError,
@@ -153,6 +154,13 @@ pub mod processor {
         Database(#[from] DbError),
         #[error("Payment service is shutting down")]
         Shutdown,
+        #[error(
+            "Insufficient funds: {required} required, {available} available after reservations"
+        )]
+        InsufficientFunds {
+            required: BigDecimal,
+            available: BigDecimal,
+        },
         #[error("Internal timeout")]
         InternalTimeout(#[from] Elapsed),
     }
@@ -186,6 +194,13 @@ pub mod processor {
             )))
         }
 
+        pub fn payer_id(order: &Order, payer_id: &NodeId) -> Result<(), Self> {
+            Err(Self(format!(
+                "Invalid payer identity for payment order {}: {} != {}",
+                order.id, order.payer_id, payer_id
+            )))
+        }
+
         pub fn payee_addr(order: &Order, payee_addr: &str) -> Result<(), Self> {
             Err(Self(format!(
                 "Invalid payee address for payment order {}: {} != {}",
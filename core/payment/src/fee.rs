@@ -0,0 +1,205 @@
+//! Marketplace fees charged on top of invoice payments.
+//!
+//! Marketplaces built on yagna can route a commission to their own address: whenever
+//! a Requestor's invoice payment is scheduled, a fee is computed by a [`FeeHook`], by
+//! default [`ConfiguredFee`], and recorded in the fee ledger. Fee payment from the same
+//! account is scheduled, once the invoice payment is confirmed. Fees, which fail to be
+//! scheduled, are retried periodically, also after restart. Fee is marked paid, once its
+//! own payment is confirmed.
+use bigdecimal::{BigDecimal, Zero};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ya_core_model::payment::local::{
+    GenericError, ListFees, PaymentTitle, SchedulePayment, BUS_ID,
+};
+use ya_persistence::executor::DbExecutor;
+use ya_service_bus::typed as bus;
+
+use crate::config::FeeConfig;
+use crate::dao::FeeDao;
+use crate::processor::PaymentProcessor;
+
+/// Token precision, to which fees are rounded down.
+const FEE_SCALE: i64 = 18;
+const FEE_RETRY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Fee payment to be scheduled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fee {
+    pub recipient: String,
+    pub amount: BigDecimal,
+}
+
+/// Source of fees charged on invoice payments.
+pub trait FeeHook: Send + Sync {
+    /// Fee charged for the payment. `None`, if no fee applies.
+    fn fee(&self, payment: &SchedulePayment) -> Option<Fee>;
+}
+
+/// Percentage of the invoiced amount plus a flat fee, paid to a configured address.
+#[derive(Clone, Debug)]
+pub struct ConfiguredFee {
+    recipient: String,
+    percent: BigDecimal,
+    flat: BigDecimal,
+    platforms: Vec<String>,
+}
+
+impl ConfiguredFee {
+    /// `None`, if fees are disabled.
+    pub fn new(config: &FeeConfig) -> Option<Self> {
+        let recipient = match &config.fee_address {
+            Some(address) if !address.trim().is_empty() => address.trim().to_lowercase(),
+            _ => return None,
+        };
+        if config.fee_percent <= BigDecimal::zero() && config.fee_flat <= BigDecimal::zero() {
+            log::warn!("Fee address is set, but neither percentage nor flat fee is configured");
+            return None;
+        }
+
+        let platforms = config
+            .fee_platforms
+            .split(',')
+            .map(|platform| platform.trim().to_lowercase())
+            .filter(|platform| !platform.is_empty())
+            .collect();
+        Some(ConfiguredFee {
+            recipient,
+            percent: config.fee_percent.clone(),
+            flat: config.fee_flat.clone(),
+            platforms,
+        })
+    }
+
+    pub fn arc(config: &FeeConfig) -> Option<Arc<dyn FeeHook>> {
+        Self::new(config).map(|fee| Arc::new(fee) as Arc<dyn FeeHook>)
+    }
+}
+
+impl FeeHook for ConfiguredFee {
+    fn fee(&self, payment: &SchedulePayment) -> Option<Fee> {
+        if !matches!(payment.title, PaymentTitle::Invoice(_)) {
+            return None;
+        }
+        if !self.platforms.is_empty()
+            && !self
+                .platforms
+                .contains(&payment.payment_platform.to_lowercase())
+        {
+            return None;
+        }
+
+        let amount = (&payment.amount * &self.percent / BigDecimal::from(100) + &self.flat)
+            .with_scale(FEE_SCALE);
+        if amount <= BigDecimal::zero() {
+            return None;
+        }
+        Some(Fee {
+            recipient: self.recipient.clone(),
+            amount,
+        })
+    }
+}
+
+/// Schedules due fees periodically, starting with those left from the previous run.
+pub fn spawn_retry(processor: Arc<PaymentProcessor>) {
+    tokio::task::spawn_local(async move {
+        let mut interval = tokio::time::interval(FEE_RETRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            processor.schedule_due_fees().await;
+        }
+    });
+}
+
+/// Binds listing of the fee ledger.
+pub fn bind_service(db: &DbExecutor) {
+    let db = db.clone();
+    let _ = bus::bind(BUS_ID, move |msg: ListFees| {
+        let db = db.clone();
+        async move {
+            db.as_dao::<FeeDao>()
+                .list(msg.limit)
+                .await
+                .map_err(GenericError::new)
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::str::FromStr;
+    use ya_core_model::payment::local::{DebitNotePayment, InvoicePayment};
+
+    fn config(percent: &str, flat: &str, platforms: &str) -> FeeConfig {
+        FeeConfig {
+            fee_address: Some("0xFEE".to_string()),
+            fee_percent: BigDecimal::from_str(percent).unwrap(),
+            fee_flat: BigDecimal::from_str(flat).unwrap(),
+            fee_platforms: platforms.to_string(),
+        }
+    }
+
+    fn payment(title: PaymentTitle, platform: &str, amount: &str) -> SchedulePayment {
+        SchedulePayment {
+            title,
+            payer_id: Default::default(),
+            payee_id: Default::default(),
+            payer_addr: "0x".to_string(),
+            payee_addr: "0x".to_string(),
+            payment_platform: platform.to_string(),
+            allocation_id: "allocation".to_string(),
+            amount: BigDecimal::from_str(amount).unwrap(),
+            due_date: Utc::now(),
+        }
+    }
+
+    fn invoice() -> PaymentTitle {
+        PaymentTitle::Invoice(InvoicePayment {
+            invoice_id: "invoice".to_string(),
+            agreement_id: "agreement".to_string(),
+        })
+    }
+
+    #[test]
+    fn charges_invoices_on_enabled_platforms() {
+        let hook = ConfiguredFee::new(&config("2.5", "0.01", "erc20-polygon-glm")).unwrap();
+
+        assert_eq!(
+            hook.fee(&payment(invoice(), "erc20-polygon-glm", "10")),
+            Some(Fee {
+                recipient: "0xfee".to_string(),
+                amount: BigDecimal::from_str("0.26").unwrap(),
+            })
+        );
+        assert_eq!(
+            hook.fee(&payment(invoice(), "erc20-holesky-tglm", "10")),
+            None
+        );
+
+        let debit_note = PaymentTitle::DebitNote(DebitNotePayment {
+            debit_note_id: "debit-note".to_string(),
+            activity_id: "activity".to_string(),
+        });
+        assert_eq!(
+            hook.fee(&payment(debit_note, "erc20-polygon-glm", "10")),
+            None
+        );
+    }
+
+    #[test]
+    fn disabled_without_address_or_amount() {
+        let all_platforms = ConfiguredFee::new(&config("1", "0", "")).unwrap();
+        assert!(all_platforms
+            .fee(&payment(invoice(), "erc20-holesky-tglm", "1"))
+            .is_some());
+
+        assert!(ConfiguredFee::new(&config("0", "0", "")).is_none());
+        let mut no_address = config("1", "0", "");
+        no_address.fee_address = None;
+        assert!(ConfiguredFee::new(&no_address).is_none());
+    }
+}
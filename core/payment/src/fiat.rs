@@ -0,0 +1,317 @@
+//! Annotation of invoices, payments and account status with token value in fiat currency.
+//!
+//! Token rate is taken from pluggable `PriceFeed` when a document is issued, received
+//! or paid and stored for later accounting export. Annotation is best effort: rates
+//! unavailable at event time are never filled in.
+use anyhow::{anyhow, bail};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use ya_client_model::NodeId;
+use ya_core_model::payment::local::FiatRate;
+use ya_persistence::executor::DbExecutor;
+use ya_persistence::types::BigDecimalField;
+
+use crate::config::FiatConfig;
+use crate::dao::FiatAnnotationDao;
+use crate::models::fiat_annotation::FiatAnnotation;
+
+const FEED_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum FiatEntity {
+    Invoice,
+    Payment,
+}
+
+/// Source of token prices.
+pub trait PriceFeed: Send + Sync {
+    /// Price of token identified by feed specific `token_id` in `currency`.
+    fn price(
+        &self,
+        token_id: &str,
+        currency: &str,
+    ) -> LocalBoxFuture<'static, anyhow::Result<BigDecimal>>;
+}
+
+/// HTTP price feed compatible with Coingecko `simple/price` API.
+pub struct CoingeckoFeed {
+    url: String,
+}
+
+impl CoingeckoFeed {
+    pub fn new(url: impl ToString) -> Self {
+        CoingeckoFeed {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl PriceFeed for CoingeckoFeed {
+    fn price(
+        &self,
+        token_id: &str,
+        currency: &str,
+    ) -> LocalBoxFuture<'static, anyhow::Result<BigDecimal>> {
+        let url = self.url.clone();
+        let token_id = token_id.to_string();
+        let currency = currency.to_string();
+
+        async move {
+            let mut response = awc::Client::builder()
+                .timeout(FEED_TIMEOUT)
+                .finish()
+                .get(url)
+                .query(&[("ids", &token_id), ("vs_currencies", &currency)])?
+                .send()
+                .await
+                .map_err(|e| anyhow!("price feed request failed: {e}"))?;
+            if !response.status().is_success() {
+                bail!("price feed responded with {}", response.status());
+            }
+
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| anyhow!("invalid price feed response: {e}"))?;
+            match &body[&token_id][&currency] {
+                // Parsing number representation keeps precision of the response.
+                Value::Number(price) => Ok(BigDecimal::from_str(&price.to_string())?),
+                _ => bail!("no {token_id} price in {currency} in price feed response"),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+struct Inner {
+    feed: Box<dyn PriceFeed>,
+    currency: String,
+    /// Payment token symbol to price feed id.
+    tokens: HashMap<String, String>,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<String, FiatRate>>,
+    /// Token ids, which rates are being refreshed in the background.
+    refreshing: Mutex<HashSet<String>>,
+}
+
+impl Inner {
+    fn token_id(&self, platform: &str) -> Option<&String> {
+        let token = platform.rsplit('-').next()?.to_lowercase();
+        self.tokens.get(&token)
+    }
+}
+
+/// Stamps documents with fiat rates. Does nothing, if annotation is disabled.
+#[derive(Clone, Default)]
+pub struct FiatAnnotator {
+    inner: Option<Arc<Inner>>,
+}
+
+impl FiatAnnotator {
+    pub fn new(config: &FiatConfig) -> Self {
+        Self::with_feed(config, Box::new(CoingeckoFeed::new(&config.price_feed_url)))
+    }
+
+    pub fn with_feed(config: &FiatConfig, feed: Box<dyn PriceFeed>) -> Self {
+        let currency = match &config.fiat_currency {
+            Some(currency) if !currency.trim().is_empty() => currency.trim().to_lowercase(),
+            _ => return Self::default(),
+        };
+        let tokens = config
+            .price_feed_tokens
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(token, id)| (token.trim().to_lowercase(), id.trim().to_string()))
+            .collect();
+
+        FiatAnnotator {
+            inner: Some(Arc::new(Inner {
+                feed,
+                currency,
+                tokens,
+                cache_ttl: Duration::from_std(config.price_feed_cache)
+                    .unwrap_or_else(|_| Duration::zero()),
+                cache: Default::default(),
+                refreshing: Default::default(),
+            })),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Current rate of `platform` token. `None` if annotation is disabled,
+    /// token has no price feed id, or the feed is unavailable.
+    pub async fn rate(&self, platform: &str) -> Option<FiatRate> {
+        let inner = self.inner.as_ref()?;
+        let token_id = inner.token_id(platform)?;
+
+        let now = Utc::now();
+        if let Some(rate) = inner.cache.lock().unwrap().get(token_id) {
+            if now - rate.timestamp < inner.cache_ttl {
+                return Some(rate.clone());
+            }
+        }
+
+        match inner.feed.price(token_id, &inner.currency).await {
+            Ok(price) => {
+                let rate = FiatRate {
+                    currency: inner.currency.clone(),
+                    rate: price,
+                    timestamp: now,
+                };
+                inner
+                    .cache
+                    .lock()
+                    .unwrap()
+                    .insert(token_id.clone(), rate.clone());
+                Some(rate)
+            }
+            Err(e) => {
+                log::warn!("Failed to get {token_id} rate in {}: {e}", inner.currency);
+                None
+            }
+        }
+    }
+
+    /// Last known rate of `platform` token, without waiting for the price feed.
+    /// Missing or expired rate is refreshed in the background.
+    pub fn cached_rate(&self, platform: &str) -> Option<FiatRate> {
+        let inner = self.inner.as_ref()?;
+        let token_id = inner.token_id(platform)?.clone();
+
+        let cached = inner.cache.lock().unwrap().get(&token_id).cloned();
+        let expired = match &cached {
+            Some(rate) => Utc::now() - rate.timestamp >= inner.cache_ttl,
+            None => true,
+        };
+        if expired && inner.refreshing.lock().unwrap().insert(token_id.clone()) {
+            let annotator = self.clone();
+            let platform = platform.to_string();
+            tokio::task::spawn_local(async move {
+                annotator.rate(&platform).await;
+                if let Some(inner) = &annotator.inner {
+                    inner.refreshing.lock().unwrap().remove(&token_id);
+                }
+            });
+        }
+        cached
+    }
+
+    /// Stores current rate of `platform` token for the entity in the background.
+    pub fn stamp(
+        &self,
+        db: DbExecutor,
+        owner_id: NodeId,
+        entity: FiatEntity,
+        entity_id: String,
+        platform: String,
+        amount: BigDecimal,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let annotator = self.clone();
+        tokio::task::spawn_local(async move {
+            let rate = match annotator.rate(&platform).await {
+                Some(rate) => rate,
+                None => return,
+            };
+            let annotation = FiatAnnotation {
+                owner_id,
+                entity_type: entity.to_string(),
+                entity_id: entity_id.clone(),
+                platform,
+                amount: BigDecimalField(amount),
+                currency: rate.currency,
+                rate: BigDecimalField(rate.rate),
+                timestamp: rate.timestamp.naive_utc(),
+            };
+            if let Err(e) = db.as_dao::<FiatAnnotationDao>().insert(annotation).await {
+                log::warn!("Failed to store fiat rate of {entity} [{entity_id}]: {e}");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct FixedFeed(Arc<AtomicUsize>);
+
+    impl PriceFeed for FixedFeed {
+        fn price(
+            &self,
+            token_id: &str,
+            _: &str,
+        ) -> LocalBoxFuture<'static, anyhow::Result<BigDecimal>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let price = match token_id {
+                "golem" => Ok(BigDecimal::from_str("0.25").unwrap()),
+                _ => Err(anyhow!("unknown token")),
+            };
+            futures::future::ready(price).boxed_local()
+        }
+    }
+
+    fn config(currency: Option<&str>) -> FiatConfig {
+        FiatConfig {
+            fiat_currency: currency.map(ToString::to_string),
+            price_feed_url: Default::default(),
+            price_feed_tokens: "glm=golem, usdc=usd-coin".to_string(),
+            price_feed_cache: std::time::Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn rates_are_cached_per_token() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let annotator =
+            FiatAnnotator::with_feed(&config(Some("USD")), Box::new(FixedFeed(calls.clone())));
+
+        let rate = annotator.rate("erc20-polygon-glm").await.unwrap();
+        assert_eq!(rate.currency, "usd");
+        assert_eq!(rate.rate, BigDecimal::from_str("0.25").unwrap());
+        assert!(annotator.rate("erc20-polygon-glm").await.is_some());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert!(annotator.rate("erc20-holesky-tglm").await.is_none());
+        assert!(annotator.rate("erc20-polygon-usdc").await.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn cached_rate_is_refreshed_in_background() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let annotator =
+            FiatAnnotator::with_feed(&config(Some("USD")), Box::new(FixedFeed(calls.clone())));
+
+        assert!(annotator.cached_rate("erc20-polygon-glm").is_none());
+        assert!(annotator.cached_rate("erc20-polygon-glm").is_none());
+        tokio::task::yield_now().await;
+
+        let rate = annotator.cached_rate("erc20-polygon-glm").unwrap();
+        assert_eq!(rate.rate, BigDecimal::from_str("0.25").unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn disabled_without_currency() {
+        let annotator =
+            FiatAnnotator::with_feed(&config(None), Box::new(FixedFeed(Default::default())));
+        assert!(!annotator.is_enabled());
+        assert!(annotator.rate("erc20-polygon-glm").await.is_none());
+    }
+}
@@ -0,0 +1,108 @@
+//! Attribution of transaction fees to Agreements.
+//!
+//! Driver reports fee of the whole transaction, which can pay many orders when payments
+//! are batched. Fee is split between Agreements paid by the transaction proportionally
+//! to the paid amounts, so requestors can compare true cost of Agreements.
+use bigdecimal::{BigDecimal, Zero};
+use std::collections::HashMap;
+
+use ya_client_model::NodeId;
+use ya_core_model::payment::local::AgreementCost;
+
+/// Amount paid for the Agreement (directly or for its Activity) by a single payment.
+#[derive(Clone, Debug)]
+pub struct PaidPart {
+    pub owner_id: NodeId,
+    /// Payment confirmation identifying the transaction.
+    pub details: Vec<u8>,
+    pub agreement_id: String,
+    pub platform: String,
+    pub amount: BigDecimal,
+}
+
+/// Sums paid amounts and attributed fees per Agreement. `fees` are keyed by owner
+/// and payment confirmation. Parts of transactions without known fee cost no gas.
+pub fn agreement_costs(
+    parts: Vec<PaidPart>,
+    fees: &HashMap<(NodeId, Vec<u8>), BigDecimal>,
+) -> Vec<AgreementCost> {
+    let mut tx_totals: HashMap<(NodeId, &[u8]), BigDecimal> = HashMap::new();
+    for part in &parts {
+        *tx_totals
+            .entry((part.owner_id, part.details.as_slice()))
+            .or_insert_with(BigDecimal::zero) += &part.amount;
+    }
+
+    let mut costs: HashMap<(NodeId, String), AgreementCost> = HashMap::new();
+    for part in &parts {
+        let gas_cost = match (
+            fees.get(&(part.owner_id, part.details.clone())),
+            tx_totals.get(&(part.owner_id, part.details.as_slice())),
+        ) {
+            (Some(fee), Some(total)) if !total.is_zero() => fee * &part.amount / total,
+            _ => BigDecimal::zero(),
+        };
+
+        let cost = costs
+            .entry((part.owner_id, part.agreement_id.clone()))
+            .or_insert_with(|| AgreementCost {
+                owner_id: part.owner_id,
+                agreement_id: part.agreement_id.clone(),
+                platform: part.platform.clone(),
+                amount: BigDecimal::zero(),
+                gas_cost: BigDecimal::zero(),
+            });
+        cost.amount += &part.amount;
+        cost.gas_cost += gas_cost;
+    }
+
+    let mut costs: Vec<AgreementCost> = costs.into_values().collect();
+    costs.sort_by(|a, b| a.agreement_id.cmp(&b.agreement_id));
+    costs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn part(details: &[u8], agreement_id: &str, amount: &str) -> PaidPart {
+        PaidPart {
+            owner_id: NodeId::default(),
+            details: details.to_vec(),
+            agreement_id: agreement_id.to_string(),
+            platform: "erc20-polygon-glm".to_string(),
+            amount: BigDecimal::from_str(amount).unwrap(),
+        }
+    }
+
+    #[test]
+    fn fee_is_split_pro_rata() {
+        let parts = vec![
+            part(b"tx1", "a", "3"),
+            part(b"tx1", "b", "1"),
+            part(b"tx2", "a", "2"),
+            part(b"tx3", "b", "5"),
+        ];
+        let fees = HashMap::from([
+            (
+                (NodeId::default(), b"tx1".to_vec()),
+                BigDecimal::from_str("0.004").unwrap(),
+            ),
+            (
+                (NodeId::default(), b"tx2".to_vec()),
+                BigDecimal::from_str("0.001").unwrap(),
+            ),
+        ]);
+
+        let costs = agreement_costs(parts, &fees);
+        let dec = |v: &str| BigDecimal::from_str(v).unwrap();
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs[0].agreement_id, "a");
+        assert_eq!(costs[0].amount, dec("5"));
+        assert_eq!(costs[0].gas_cost, dec("0.004"));
+        assert_eq!(costs[1].agreement_id, "b");
+        assert_eq!(costs[1].amount, dec("6"));
+        assert_eq!(costs[1].gas_cost, dec("0.001"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Gasless payments.
+//!
+//! Drivers listing a platform in `DriverCapabilities::gasless_platforms` can hand payments
+//! of Requestors without gas token over to a relayer, which pays for gas in exchange for
+//! a token fee. Relayed transaction isn't sent by the payer, so it's used only if the
+//! Provider accepts it per platform in its Offer, e.g.
+//! `golem.com.payment.platform.erc20-polygon-glm.gasless = true`.
+use serde_json::Value;
+
+use ya_agreement_utils::agreement::{expand, TypedPointer};
+use ya_client_model::market::Agreement;
+
+/// Provider's Offer accepts relayed payments on `platform`.
+pub fn offer_accepts(agreement: &Agreement, platform: &str) -> bool {
+    let offer = expand(agreement.offer.properties.clone());
+    offer
+        .pointer(&format!("/golem/com/payment/platform/{}/gasless", platform))
+        .as_typed(Value::as_bool)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+    use ya_client_model::market::agreement::State;
+    use ya_client_model::market::{Demand, Offer};
+
+    fn agreement(offer: Value) -> Agreement {
+        let demand = Demand::new(
+            json!({}),
+            "()".to_string(),
+            "demand_id".to_string(),
+            Default::default(),
+            Default::default(),
+        );
+        let offer = Offer::new(
+            offer,
+            "()".to_string(),
+            "offer_id".to_string(),
+            Default::default(),
+            Default::default(),
+        );
+        Agreement::new(
+            "agreement_id".to_string(),
+            demand,
+            offer,
+            Utc::now(),
+            State::Approved,
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn test_offer_accepts_gasless() {
+        let agreement = agreement(json!({
+            "golem.com.payment.platform.erc20-polygon-glm.address": "0x00",
+            "golem.com.payment.platform.erc20-polygon-glm.gasless": true,
+            "golem.com.payment.platform.erc20-mainnet-glm.address": "0x00",
+        }));
+        assert!(offer_accepts(&agreement, "erc20-polygon-glm"));
+        assert!(!offer_accepts(&agreement, "erc20-mainnet-glm"));
+        assert!(!offer_accepts(&agreement, "erc20-holesky-tglm"));
+    }
+}
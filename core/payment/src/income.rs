@@ -0,0 +1,183 @@
+//! Provider income dashboard.
+//!
+//! Aggregates Provider's Agreements, which aren't invoiced yet, unpaid Invoices and
+//! received payments into a single report, so provider UIs don't have to page through
+//! all payment documents.
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use ya_client_model::NodeId;
+
+use crate::models::agreement::ReadObj as DbAgreement;
+use crate::models::invoice::ReadObj as DbInvoice;
+use crate::models::payment::ReadObj as DbPayment;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncomeDashboard {
+    pub active_agreements: Vec<ActiveAgreement>,
+    /// Amount due on active Agreements, which isn't paid yet, per platform.
+    pub projected_income: BTreeMap<String, BigDecimal>,
+    pub unpaid_invoices: Vec<InvoiceAging>,
+    pub earnings: Vec<DailyEarnings>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveAgreement {
+    pub agreement_id: String,
+    pub requestor_id: NodeId,
+    pub platform: String,
+    /// Sum of amounts due of all Activities, as reported by the last Debit Notes.
+    pub amount_due: BigDecimal,
+    pub amount_accepted: BigDecimal,
+    pub amount_paid: BigDecimal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvoiceAge {
+    UpTo1Day,
+    UpTo7Days,
+    UpTo30Days,
+    Over30Days,
+}
+
+impl InvoiceAge {
+    fn of(age: Duration) -> Self {
+        if age <= Duration::days(1) {
+            InvoiceAge::UpTo1Day
+        } else if age <= Duration::days(7) {
+            InvoiceAge::UpTo7Days
+        } else if age <= Duration::days(30) {
+            InvoiceAge::UpTo30Days
+        } else {
+            InvoiceAge::Over30Days
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceAging {
+    pub platform: String,
+    pub age: InvoiceAge,
+    pub count: u64,
+    pub amount: BigDecimal,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyEarnings {
+    pub date: NaiveDate,
+    pub network: String,
+    pub platform: String,
+    pub payments: u64,
+    pub amount: BigDecimal,
+}
+
+impl IncomeDashboard {
+    pub fn new(
+        agreements: Vec<DbAgreement>,
+        invoices: Vec<DbInvoice>,
+        payments: Vec<DbPayment>,
+        now: NaiveDateTime,
+    ) -> Self {
+        let active_agreements = agreements
+            .into_iter()
+            .map(|agreement| ActiveAgreement {
+                agreement_id: agreement.id,
+                requestor_id: agreement.peer_id,
+                platform: agreement.payment_platform,
+                amount_due: agreement.total_amount_due.0,
+                amount_accepted: agreement.total_amount_accepted.0,
+                amount_paid: agreement.total_amount_paid.0,
+            })
+            .collect::<Vec<_>>();
+
+        let mut projected_income = BTreeMap::<String, BigDecimal>::new();
+        for agreement in &active_agreements {
+            let unpaid = &agreement.amount_due - &agreement.amount_paid;
+            if unpaid > BigDecimal::zero() {
+                *projected_income
+                    .entry(agreement.platform.clone())
+                    .or_insert_with(BigDecimal::zero) += unpaid;
+            }
+        }
+
+        let mut aging = BTreeMap::<(String, InvoiceAge), (u64, BigDecimal)>::new();
+        for invoice in invoices {
+            let age = InvoiceAge::of(now - invoice.timestamp);
+            let (count, amount) = aging
+                .entry((invoice.payment_platform, age))
+                .or_insert_with(|| (0, BigDecimal::zero()));
+            *count += 1;
+            *amount += invoice.amount.0;
+        }
+        let unpaid_invoices = aging
+            .into_iter()
+            .map(|((platform, age), (count, amount))| InvoiceAging {
+                platform,
+                age,
+                count,
+                amount,
+            })
+            .collect();
+
+        let mut daily = BTreeMap::<(NaiveDate, String), (u64, BigDecimal)>::new();
+        for payment in payments {
+            let (count, amount) = daily
+                .entry((payment.timestamp.date(), payment.payment_platform))
+                .or_insert_with(|| (0, BigDecimal::zero()));
+            *count += 1;
+            *amount += payment.amount.0;
+        }
+        let earnings = daily
+            .into_iter()
+            .map(|((date, platform), (payments, amount))| DailyEarnings {
+                date,
+                network: platform.split('-').nth(1).unwrap_or_default().to_string(),
+                platform,
+                payments,
+                amount,
+            })
+            .collect();
+
+        IncomeDashboard {
+            active_agreements,
+            projected_income,
+            unpaid_invoices,
+            earnings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invoice_age() {
+        assert_eq!(InvoiceAge::of(Duration::hours(3)), InvoiceAge::UpTo1Day);
+        assert_eq!(InvoiceAge::of(Duration::days(1)), InvoiceAge::UpTo1Day);
+        assert_eq!(InvoiceAge::of(Duration::days(2)), InvoiceAge::UpTo7Days);
+        assert_eq!(InvoiceAge::of(Duration::days(30)), InvoiceAge::UpTo30Days);
+        assert_eq!(InvoiceAge::of(Duration::days(31)), InvoiceAge::Over30Days);
+    }
+
+    #[test]
+    fn empty_dashboard() {
+        let now = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let dashboard = IncomeDashboard::new(vec![], vec![], vec![], now);
+        let json = serde_json::to_value(&dashboard).unwrap();
+        assert_eq!(json["activeAgreements"], serde_json::json!([]));
+        assert_eq!(json["projectedIncome"], serde_json::json!({}));
+        assert_eq!(json["unpaidInvoices"], serde_json::json!([]));
+        assert_eq!(json["earnings"], serde_json::json!([]));
+    }
+}
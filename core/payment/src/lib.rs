@@ -1,7 +1,10 @@
 #![allow(dead_code)] // Crate under development
 #![allow(unused_variables)] // Crate under development
+pub use crate::api::AgreementLock;
 pub use crate::config::Config;
+use crate::fiat::FiatAnnotator;
 use crate::processor::PaymentProcessor;
+use crate::webhook::WebhookDispatcher;
 
 use futures::FutureExt;
 use std::{sync::Arc, time::Duration};
@@ -14,20 +17,34 @@ use ya_service_bus::typed as bus;
 #[macro_use]
 extern crate diesel;
 
+pub mod acceptance;
+pub mod accounting;
 pub mod accounts;
 pub mod api;
+pub mod basket;
 mod cli;
 pub mod config;
 pub mod dao;
 pub mod error;
+pub mod fee;
+pub mod fiat;
+pub mod gas;
+pub mod gasless;
+pub mod income;
 pub mod models;
 pub mod payment_sync;
+pub mod payout;
 pub mod processor;
+pub mod receipt;
+pub mod reservation;
 pub mod schema;
 pub mod service;
+pub mod settlement;
 pub mod timeout_lock;
 pub mod utils;
 mod wallet;
+pub mod webhook;
+pub mod withdrawal;
 
 pub mod migrations {
     #[derive(diesel_migrations::EmbedMigrations)]
@@ -52,13 +69,31 @@ impl Service for PaymentService {
 }
 
 impl PaymentService {
-    pub async fn gsb<Context: Provider<Self, DbExecutor>>(context: &Context) -> anyhow::Result<()> {
-        let db = context.component();
+    pub async fn gsb<Context>(context: &Context) -> anyhow::Result<()>
+    where
+        Context: Provider<Self, DbExecutor> + Provider<Self, Arc<AgreementLock>>,
+    {
+        let db: DbExecutor = context.component();
         db.apply_migration(migrations::run_with_output)?;
 
         let config = Arc::new(Config::from_env()?);
 
-        let processor = Arc::new(PaymentProcessor::new(db.clone()));
+        let processor = Arc::new(
+            PaymentProcessor::new(db.clone())
+                .with_fiat(FiatAnnotator::new(&config.fiat))
+                .with_settle_tolerance(config.settle_tolerance.clone())
+                .with_payout_thresholds(config.min_payout.clone(), config.min_payout_due_margin)
+                .with_fee_hook(fee::ConfiguredFee::arc(&config.fee)),
+        );
+        if let Some(dispatcher) = WebhookDispatcher::new(&config.webhook) {
+            if let Err(e) = dispatcher.bind().await {
+                log::warn!("Failed to enable payment webhooks: {}", e);
+            }
+        }
+        self::withdrawal::bind_service(&db, &config.auto_withdrawal);
+        self::fee::bind_service(&db);
+        self::fee::spawn_retry(processor.clone());
+        self::acceptance::start(&db, context.component(), &config.invoice_acceptance);
         self::service::bind_service(&db, processor.clone(), config);
 
         tokio::task::spawn(async move {
@@ -68,8 +103,11 @@ impl PaymentService {
         Ok(())
     }
 
-    pub fn rest<Context: Provider<Self, DbExecutor>>(ctx: &Context) -> actix_web::Scope {
-        api::web_scope(&ctx.component())
+    pub fn rest<Context>(ctx: &Context) -> actix_web::Scope
+    where
+        Context: Provider<Self, DbExecutor> + Provider<Self, Arc<AgreementLock>>,
+    {
+        api::web_scope(&ctx.component(), ctx.component())
     }
 
     pub async fn shut_down() {
@@ -3,8 +3,16 @@ pub mod agreement;
 pub mod allocation;
 pub mod debit_note;
 pub mod debit_note_event;
+pub mod fee;
+pub mod fiat_annotation;
+pub mod gas_cost;
+pub mod idempotency_key;
 pub mod invoice;
 pub mod invoice_event;
+pub mod journal;
+pub mod notify_payment;
 pub mod order;
 pub mod payment;
+pub mod payment_receipt;
 pub mod sync_notifs;
+pub mod withdrawal;
@@ -1,4 +1,4 @@
-use crate::schema::pay_allocation;
+use crate::schema::{pay_allocation, pay_allocation_platform};
 use chrono::{NaiveDateTime, TimeZone, Utc};
 use uuid::Uuid;
 use ya_client_model::payment::{Allocation, NewAllocation};
@@ -38,6 +38,17 @@ pub struct ReadObj {
     pub released: bool,
 }
 
+/// Fallback payment platform of an allocation. Lower `priority` is preferred.
+/// `amount` is the part of the remaining amount reserved on the platform.
+#[derive(Queryable, Debug, Insertable)]
+#[table_name = "pay_allocation_platform"]
+pub struct PlatformObj {
+    pub allocation_id: String,
+    pub platform: String,
+    pub priority: i32,
+    pub amount: BigDecimalField,
+}
+
 impl WriteObj {
     pub fn new(
         allocation: NewAllocation,
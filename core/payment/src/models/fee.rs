@@ -0,0 +1,64 @@
+use crate::schema::pay_fee;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use std::str::FromStr;
+
+use ya_client_model::NodeId;
+use ya_core_model::payment::local::{Fee, FeeStatus};
+use ya_persistence::types::BigDecimalField;
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "pay_fee"]
+pub struct WriteObj {
+    pub owner_id: NodeId,
+    pub invoice_id: String,
+    pub agreement_id: String,
+    pub payment_platform: String,
+    pub payer_addr: String,
+    pub recipient: String,
+    pub amount: BigDecimalField,
+    pub driver: String,
+    pub order_id: Option<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub payment_order_id: Option<String>,
+    pub allocation_id: Option<String>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ReadObj {
+    pub id: i32,
+    pub owner_id: NodeId,
+    pub invoice_id: String,
+    pub agreement_id: String,
+    pub payment_platform: String,
+    pub payer_addr: String,
+    pub recipient: String,
+    pub amount: BigDecimalField,
+    pub driver: String,
+    pub order_id: Option<String>,
+    pub status: String,
+    pub tx_id: Option<String>,
+    pub error: Option<String>,
+    pub timestamp: NaiveDateTime,
+    pub payment_order_id: Option<String>,
+    pub allocation_id: Option<String>,
+}
+
+impl ReadObj {
+    pub fn into_api(self) -> Fee {
+        Fee {
+            owner_id: self.owner_id,
+            invoice_id: self.invoice_id,
+            agreement_id: self.agreement_id,
+            payment_platform: self.payment_platform,
+            payer_addr: self.payer_addr,
+            recipient: self.recipient,
+            amount: self.amount.0,
+            // Only known statuses are stored.
+            status: FeeStatus::from_str(&self.status).unwrap_or(FeeStatus::Due),
+            tx_id: self.tx_id,
+            error: self.error,
+            timestamp: Utc.from_utc_datetime(&self.timestamp),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::schema::pay_fiat_annotation;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use ya_client_model::NodeId;
+use ya_core_model::payment::local as model;
+use ya_persistence::types::BigDecimalField;
+
+#[derive(Queryable, Debug, Insertable)]
+#[table_name = "pay_fiat_annotation"]
+pub struct FiatAnnotation {
+    pub owner_id: NodeId,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub platform: String,
+    pub amount: BigDecimalField,
+    pub currency: String,
+    pub rate: BigDecimalField,
+    pub timestamp: NaiveDateTime,
+}
+
+impl From<FiatAnnotation> for model::FiatAnnotation {
+    fn from(annotation: FiatAnnotation) -> Self {
+        let amount = annotation.amount.0;
+        let rate = annotation.rate.0;
+        model::FiatAnnotation {
+            owner_id: annotation.owner_id,
+            entity_type: annotation.entity_type,
+            entity_id: annotation.entity_id,
+            platform: annotation.platform,
+            fiat_amount: &amount * &rate,
+            amount,
+            rate: model::FiatRate {
+                currency: annotation.currency,
+                rate,
+                timestamp: Utc.from_utc_datetime(&annotation.timestamp),
+            },
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use crate::schema::pay_tx_gas_cost;
+use ya_client_model::NodeId;
+use ya_persistence::types::BigDecimalField;
+
+/// Fee of the transaction identified by payment confirmation `details`.
+#[derive(Queryable, Debug, Insertable)]
+#[table_name = "pay_tx_gas_cost"]
+pub struct TxGasCost {
+    pub owner_id: NodeId,
+    pub details: Vec<u8>,
+    pub platform: String,
+    pub gas_cost: BigDecimalField,
+}
@@ -0,0 +1,32 @@
+use crate::schema::pay_idempotency_key;
+use chrono::NaiveDateTime;
+use ya_client_model::NodeId;
+
+#[derive(Queryable, Debug, Insertable)]
+#[table_name = "pay_idempotency_key"]
+pub struct WriteObj {
+    pub key: String,
+    pub owner_id: NodeId,
+    pub peer_id: NodeId,
+    pub payment_id: String,
+}
+
+impl WriteObj {
+    pub fn new(key: String, owner_id: NodeId, peer_id: NodeId, payment_id: String) -> Self {
+        WriteObj {
+            key,
+            owner_id,
+            peer_id,
+            payment_id,
+        }
+    }
+}
+
+#[derive(Queryable, Debug)]
+pub struct ReadObj {
+    pub key: String,
+    pub owner_id: NodeId,
+    pub peer_id: NodeId,
+    pub payment_id: String,
+    pub created_ts: NaiveDateTime,
+}
@@ -43,6 +43,19 @@ impl WriteObj {
             payment_due_date: invoice.payment_due_date.naive_utc(),
         }
     }
+
+    /// Invoice issued by the owner, restored from the recipient's copy.
+    pub fn recovered_issued(invoice: Invoice) -> Self {
+        Self {
+            id: invoice.invoice_id,
+            owner_id: invoice.issuer_id,
+            role: Role::Provider,
+            agreement_id: invoice.agreement_id,
+            status: invoice.status.into(),
+            amount: invoice.amount.into(),
+            payment_due_date: invoice.payment_due_date.naive_utc(),
+        }
+    }
 }
 
 #[derive(Queryable, Debug, Identifiable)]
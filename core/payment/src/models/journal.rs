@@ -0,0 +1,154 @@
+use crate::accounting::{gas_currency, Account, JournalEntry, Period};
+use crate::models::gas_cost::TxGasCost;
+use crate::models::payment::WriteObj as PaymentWriteObj;
+use crate::schema::{pay_journal_entry, pay_journal_period};
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use std::str::FromStr;
+use ya_client_model::NodeId;
+use ya_persistence::types::{AdaptTimestamp, BigDecimalField, Role, TimestampAdapter};
+
+#[derive(Debug, Insertable)]
+#[table_name = "pay_journal_entry"]
+pub struct WriteObj {
+    pub owner_id: NodeId,
+    pub source: String,
+    pub timestamp: TimestampAdapter,
+    pub debit_account: String,
+    pub credit_account: String,
+    pub currency: String,
+    pub amount: BigDecimalField,
+    pub memo: Option<String>,
+}
+
+impl WriteObj {
+    fn new(
+        owner_id: NodeId,
+        source: String,
+        (debit, credit): (Account, Account),
+        currency: String,
+        amount: BigDecimalField,
+        memo: String,
+    ) -> Self {
+        Self {
+            owner_id,
+            source,
+            timestamp: Utc::now().naive_utc().adapt(),
+            debit_account: debit.to_string(),
+            credit_account: credit.to_string(),
+            currency,
+            amount,
+            memo: Some(memo),
+        }
+    }
+
+    /// Provider recognizes revenue, Requestor an expense owed to the Provider.
+    pub fn invoice_accepted(
+        invoice_id: &str,
+        owner_id: NodeId,
+        role: &Role,
+        agreement_id: &str,
+        payment_platform: String,
+        amount: BigDecimalField,
+    ) -> Self {
+        let accounts = match role {
+            Role::Provider => (Account::Receivables, Account::Revenue),
+            Role::Requestor => (Account::ComputeExpense, Account::Payables),
+        };
+        Self::new(
+            owner_id,
+            format!("invoice:{}", invoice_id),
+            accounts,
+            payment_platform,
+            amount,
+            format!("Invoice accepted, agreement {}", agreement_id),
+        )
+    }
+
+    pub fn payment(payment: &PaymentWriteObj) -> Self {
+        let (accounts, memo) = match payment.role {
+            Role::Provider => (
+                (Account::Wallet, Account::Receivables),
+                format!("Payment from {}", payment.peer_id),
+            ),
+            Role::Requestor => (
+                (Account::Payables, Account::Wallet),
+                format!("Payment to {}", payment.peer_id),
+            ),
+        };
+        Self::new(
+            payment.owner_id,
+            format!("payment:{}", payment.id),
+            accounts,
+            payment.payment_platform.clone(),
+            payment.amount.clone(),
+            memo,
+        )
+    }
+
+    pub fn gas_cost(gas_cost: &TxGasCost) -> Self {
+        Self::new(
+            gas_cost.owner_id,
+            format!("gas:{}", hex::encode(&gas_cost.details)),
+            (Account::GasExpense, Account::Wallet),
+            gas_currency(&gas_cost.platform),
+            gas_cost.gas_cost.clone(),
+            "Transaction fee".to_string(),
+        )
+    }
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ReadObj {
+    pub owner_id: NodeId,
+    pub source: String,
+    pub timestamp: NaiveDateTime,
+    pub debit_account: String,
+    pub credit_account: String,
+    pub currency: String,
+    pub amount: BigDecimalField,
+    pub memo: Option<String>,
+    pub period_id: Option<i32>,
+}
+
+impl ReadObj {
+    pub fn into_api(self) -> JournalEntry {
+        JournalEntry {
+            source: self.source,
+            timestamp: Utc.from_utc_datetime(&self.timestamp),
+            // Only known accounts are stored.
+            debit: Account::from_str(&self.debit_account).unwrap_or(Account::Wallet),
+            credit: Account::from_str(&self.credit_account).unwrap_or(Account::Wallet),
+            currency: self.currency,
+            amount: BigDecimal::from(self.amount),
+            memo: self.memo,
+            period_id: self.period_id,
+        }
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "pay_journal_period"]
+pub struct PeriodWriteObj {
+    pub owner_id: NodeId,
+    pub closed_until: TimestampAdapter,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct PeriodReadObj {
+    pub id: i32,
+    pub owner_id: NodeId,
+    pub closed_until: NaiveDateTime,
+    pub closed_ts: NaiveDateTime,
+}
+
+impl PeriodReadObj {
+    pub fn into_api(self, entries: u64) -> Period {
+        Period {
+            id: self.id,
+            closed_until: Utc.from_utc_datetime(&self.closed_until),
+            closed_ts: Utc.from_utc_datetime(&self.closed_ts),
+            entries,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::schema::pay_notify_payment;
+use ya_core_model::payment::local::NotifyPayment;
+
+/// Payment confirmation reported by the driver. It is stored before the payment is
+/// accounted, so notifications interrupted by a crash can be processed again.
+#[derive(Queryable, Debug, Clone, Insertable)]
+#[table_name = "pay_notify_payment"]
+pub struct WriteObj {
+    pub driver: String,
+    pub details: Vec<u8>,
+    pub order_ids: String,
+    pub notification: String,
+    pub payment_id: Option<String>,
+}
+
+impl WriteObj {
+    pub fn new(msg: &NotifyPayment) -> serde_json::Result<Self> {
+        let mut order_ids = msg.order_ids.clone();
+        order_ids.sort();
+
+        Ok(WriteObj {
+            driver: msg.driver.clone(),
+            details: msg.confirmation.confirmation.clone(),
+            order_ids: order_ids.join(","),
+            notification: serde_json::to_string(msg)?,
+            payment_id: None,
+        })
+    }
+
+    pub fn key(&self) -> (String, Vec<u8>, String) {
+        (
+            self.driver.clone(),
+            self.details.clone(),
+            self.order_ids.clone(),
+        )
+    }
+
+    pub fn notification(&self) -> serde_json::Result<NotifyPayment> {
+        serde_json::from_str(&self.notification)
+    }
+}
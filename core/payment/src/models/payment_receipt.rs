@@ -0,0 +1,28 @@
+use crate::schema::pay_payment_receipt;
+use chrono::NaiveDateTime;
+use ya_client_model::NodeId;
+use ya_core_model::payment::public::SendPaymentReceipt;
+
+#[derive(Queryable, Debug, Insertable)]
+#[table_name = "pay_payment_receipt"]
+pub struct PaymentReceipt {
+    pub payment_id: String,
+    pub owner_id: NodeId,
+    pub tx_hash: String,
+    pub issued_at: NaiveDateTime,
+    pub signature: Vec<u8>,
+    pub signed_bytes: Vec<u8>,
+}
+
+impl PaymentReceipt {
+    pub fn new(msg: SendPaymentReceipt, owner_id: NodeId) -> Self {
+        PaymentReceipt {
+            payment_id: msg.receipt.payment.payment_id,
+            owner_id,
+            tx_hash: msg.receipt.tx_hash,
+            issued_at: msg.receipt.issued_at.naive_utc(),
+            signature: msg.signature,
+            signed_bytes: msg.signed_bytes,
+        }
+    }
+}
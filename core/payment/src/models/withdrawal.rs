@@ -0,0 +1,73 @@
+use crate::schema::pay_withdrawal;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use std::str::FromStr;
+
+use ya_core_model::payment::local::{Withdrawal, WithdrawalStatus};
+use ya_persistence::types::BigDecimalField;
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "pay_withdrawal"]
+pub struct WriteObj {
+    pub sender: String,
+    pub recipient: String,
+    pub driver: String,
+    pub network: String,
+    pub token: String,
+    pub amount: BigDecimalField,
+    pub max_fee: BigDecimalField,
+    pub status: String,
+    pub tx_id: Option<String>,
+    pub error: Option<String>,
+}
+
+impl From<Withdrawal> for WriteObj {
+    fn from(withdrawal: Withdrawal) -> Self {
+        WriteObj {
+            sender: withdrawal.sender,
+            recipient: withdrawal.to,
+            driver: withdrawal.driver,
+            network: withdrawal.network,
+            token: withdrawal.token,
+            amount: withdrawal.amount.into(),
+            max_fee: withdrawal.max_fee.into(),
+            status: withdrawal.status.to_string(),
+            tx_id: withdrawal.tx_id,
+            error: withdrawal.error,
+        }
+    }
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ReadObj {
+    pub id: i32,
+    pub sender: String,
+    pub recipient: String,
+    pub driver: String,
+    pub network: String,
+    pub token: String,
+    pub amount: BigDecimalField,
+    pub max_fee: BigDecimalField,
+    pub status: String,
+    pub tx_id: Option<String>,
+    pub error: Option<String>,
+    pub timestamp: NaiveDateTime,
+}
+
+impl ReadObj {
+    pub fn into_api(self) -> Withdrawal {
+        Withdrawal {
+            sender: self.sender,
+            to: self.recipient,
+            driver: self.driver,
+            network: self.network,
+            token: self.token,
+            amount: self.amount.0,
+            max_fee: self.max_fee.0,
+            // Only known statuses are stored.
+            status: WithdrawalStatus::from_str(&self.status).unwrap_or(WithdrawalStatus::Failed),
+            tx_id: self.tx_id,
+            error: self.error,
+            timestamp: Utc.from_utc_datetime(&self.timestamp),
+        }
+    }
+}
@@ -1,6 +1,12 @@
+use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
+use std::cell::Cell;
+use std::future::Future;
 use std::sync::Arc;
-use std::{collections::HashSet, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 use tokio::sync::Notify;
 
 use ya_client_model::{
@@ -14,34 +20,112 @@ use ya_core_model::{
     identity::{self, IdentityInfo},
     payment::{
         self,
-        local::GenericError,
+        local::{ColdSyncResult, GenericError},
         public::{
-            AcceptDebitNote, AcceptInvoice, PaymentSync, PaymentSyncRequest, PaymentSyncWithBytes,
-            RejectInvoiceV2, SendPayment, SendSignedPayment,
+            AcceptDebitNote, AcceptInvoice, AgreementPaymentSummary, PaymentSync,
+            PaymentSyncRequest, PaymentSyncSummary, PaymentSyncSummaryRequest,
+            PaymentSyncWithBytes, RejectInvoiceV2, SendPayment, SendSignedPayment,
         },
     },
 };
 use ya_net::RemoteEndpoint;
 use ya_persistence::executor::DbExecutor;
+use ya_persistence::types::Role;
 use ya_service_bus::{timeout::IntoTimeoutFuture, typed, RpcEndpoint};
 
-use crate::dao::{DebitNoteDao, InvoiceDao, InvoiceEventDao, PaymentDao, SyncNotifsDao};
+use crate::dao::{
+    ActivityDao, AgreementDao, DebitNoteDao, IdempotencyKeyDao, InvoiceDao, InvoiceEventDao,
+    PaymentDao, SyncNotifsDao,
+};
+use crate::utils::get_agreement;
 use crate::Config;
 
 const REMOTE_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+const IDEMPOTENCY_KEYS_PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Single document of PaymentSync, which is delivered and marked as sent independently.
+#[derive(Clone, Debug)]
+enum SyncItem {
+    Payment(SendPayment, SendSignedPayment),
+    InvoiceAccept(AcceptInvoice),
+    InvoiceReject(RejectInvoiceV2),
+    DebitNoteAccept(AcceptDebitNote),
+}
+
+impl SyncItem {
+    /// Estimated size of the item in the message.
+    fn size(&self) -> usize {
+        fn json_size<T: serde::Serialize>(value: &T) -> usize {
+            serde_json::to_vec(value)
+                .map(|bytes| bytes.len())
+                .unwrap_or_default()
+        }
+
+        match self {
+            SyncItem::Payment(payment, signed) => json_size(payment).max(json_size(signed)),
+            SyncItem::InvoiceAccept(accept) => json_size(accept),
+            SyncItem::InvoiceReject(reject) => json_size(reject),
+            SyncItem::DebitNoteAccept(accept) => json_size(accept),
+        }
+    }
+}
+
+/// Sync item with its priority: older documents first, more valuable first among equally old.
+#[derive(Clone, Debug)]
+struct PrioritizedItem {
+    timestamp: DateTime<Utc>,
+    value: BigDecimal,
+    item: SyncItem,
+}
+
+/// PaymentSync and its variant with canonicalized payment signatures.
+#[derive(Clone, Debug, Default)]
+struct SyncBatch {
+    msg: PaymentSync,
+    msg_with_bytes: PaymentSyncWithBytes,
+}
+
+impl SyncBatch {
+    fn len(&self) -> usize {
+        self.msg.payments.len()
+            + self.msg.invoice_accepts.len()
+            + self.msg.invoice_rejects.len()
+            + self.msg.debit_note_accepts.len()
+    }
+
+    fn push(&mut self, item: SyncItem) {
+        match item {
+            SyncItem::Payment(payment, signed) => {
+                self.msg.payments.push(payment);
+                self.msg_with_bytes.payments.push(signed);
+            }
+            SyncItem::InvoiceAccept(accept) => {
+                self.msg.invoice_accepts.push(accept.clone());
+                self.msg_with_bytes.invoice_accepts.push(accept);
+            }
+            SyncItem::InvoiceReject(reject) => {
+                self.msg.invoice_rejects.push(reject.clone());
+                self.msg_with_bytes.invoice_rejects.push(reject);
+            }
+            SyncItem::DebitNoteAccept(accept) => {
+                self.msg.debit_note_accepts.push(accept.clone());
+                self.msg_with_bytes.debit_note_accepts.push(accept);
+            }
+        }
+    }
+}
 
 async fn payment_sync(
     db: &DbExecutor,
     owner: NodeId,
     peer_id: NodeId,
-) -> anyhow::Result<(PaymentSync, PaymentSyncWithBytes)> {
+) -> anyhow::Result<Vec<PrioritizedItem>> {
     let payment_dao: PaymentDao = db.as_dao();
     let invoice_dao: InvoiceDao = db.as_dao();
     let debit_note_dao: DebitNoteDao = db.as_dao();
     let invoice_event_dao: InvoiceEventDao = db.as_dao();
 
-    let mut payments = Vec::default();
-    let mut payments_canonicalized = Vec::default();
+    let mut items = Vec::default();
     for payment in payment_dao.list_unsent(owner, Some(peer_id)).await? {
         let platform_components = payment.payment_platform.split('-').collect::<Vec<_>>();
         let driver = &platform_components[0];
@@ -52,27 +136,35 @@ async fn payment_sync(
         let signature = typed::service(bus_id.clone())
             .send(SignPayment(payment.clone()))
             .await??;
-        payments.push(SendPayment::new(payment.clone(), signature));
-
         let signature_canonical = typed::service(bus_id.clone())
             .send(SignPaymentCanonicalized(payment.clone()))
             .await??;
-        payments_canonicalized.push(SendSignedPayment::new(payment.clone(), signature_canonical));
+
+        items.push(PrioritizedItem {
+            timestamp: payment.timestamp,
+            value: payment.amount.clone(),
+            item: SyncItem::Payment(
+                SendPayment::new(payment.clone(), signature),
+                SendSignedPayment::new(payment, signature_canonical),
+            ),
+        });
     }
 
-    let mut invoice_accepts = Vec::default();
     for invoice in invoice_dao.unsent_accepted(owner, peer_id).await? {
-        invoice_accepts.push(AcceptInvoice::new(
-            invoice.invoice_id,
-            Acceptance {
-                total_amount_accepted: invoice.amount,
-                allocation_id: String::new(),
-            },
-            peer_id,
-        ));
+        items.push(PrioritizedItem {
+            timestamp: invoice.timestamp,
+            value: invoice.amount.clone(),
+            item: SyncItem::InvoiceAccept(AcceptInvoice::new(
+                invoice.invoice_id,
+                Acceptance {
+                    total_amount_accepted: invoice.amount,
+                    allocation_id: String::new(),
+                },
+                peer_id,
+            )),
+        });
     }
 
-    let mut invoice_rejects = Vec::default();
     for invoice in invoice_dao.unsent_rejected(owner, peer_id).await? {
         let events = invoice_event_dao
             .get_for_invoice_id(
@@ -87,89 +179,144 @@ async fn payment_sync(
             .map_err(GenericError::new)?;
         if let Some(event) = events.into_iter().last() {
             if let InvoiceEventType::InvoiceRejectedEvent { rejection } = event.event_type {
-                invoice_rejects.push(RejectInvoiceV2 {
-                    invoice_id: invoice.invoice_id,
-                    rejection,
-                    issuer_id: peer_id,
+                items.push(PrioritizedItem {
+                    timestamp: invoice.timestamp,
+                    value: invoice.amount,
+                    item: SyncItem::InvoiceReject(RejectInvoiceV2 {
+                        invoice_id: invoice.invoice_id,
+                        rejection,
+                        issuer_id: peer_id,
+                    }),
                 });
             };
         };
     }
 
-    let mut debit_note_accepts = Vec::default();
     for debit_note in debit_note_dao.unsent_accepted(owner, peer_id).await? {
-        debit_note_accepts.push(AcceptDebitNote::new(
-            debit_note.debit_note_id,
-            Acceptance {
-                total_amount_accepted: debit_note.total_amount_due,
-                allocation_id: String::new(),
-            },
-            peer_id,
-        ));
+        items.push(PrioritizedItem {
+            timestamp: debit_note.timestamp,
+            value: debit_note.total_amount_due.clone(),
+            item: SyncItem::DebitNoteAccept(AcceptDebitNote::new(
+                debit_note.debit_note_id,
+                Acceptance {
+                    total_amount_accepted: debit_note.total_amount_due,
+                    allocation_id: String::new(),
+                },
+                peer_id,
+            )),
+        });
     }
 
-    Ok((
-        PaymentSync {
-            payments,
-            invoice_accepts: invoice_accepts.clone(),
-            invoice_rejects: invoice_rejects.clone(),
-            debit_note_accepts: debit_note_accepts.clone(),
-        },
-        PaymentSyncWithBytes {
-            payments: payments_canonicalized,
-            invoice_accepts,
-            invoice_rejects,
-            debit_note_accepts,
-        },
-    ))
+    Ok(items)
 }
 
-async fn mark_all_sent(db: &DbExecutor, owner_id: NodeId, msg: PaymentSync) -> anyhow::Result<()> {
+/// Splits items into batches, which estimated size doesn't exceed `max_size`,
+/// ordered by document age and value. Items exceeding the limit are sent alone.
+fn into_batches(mut items: Vec<PrioritizedItem>, max_size: usize) -> Vec<SyncBatch> {
+    items.sort_by(|a, b| {
+        a.timestamp
+            .cmp(&b.timestamp)
+            .then_with(|| b.value.cmp(&a.value))
+    });
+
+    let mut batches = Vec::new();
+    let mut batch = SyncBatch::default();
+    let mut batch_size = 0;
+    for PrioritizedItem { item, .. } in items {
+        let size = item.size();
+        if batch.len() > 0 && batch_size + size > max_size {
+            batches.push(std::mem::take(&mut batch));
+            batch_size = 0;
+        }
+        batch_size += size;
+        batch.push(item);
+    }
+    if batch.len() > 0 {
+        batches.push(batch);
+    }
+    batches
+}
+
+/// Sends `batches` in order, until the first one fails. Each delivered batch is marked
+/// as sent immediately, so progress isn't lost, when the peer disconnects in the middle
+/// of the sync. Returns number of delivered items and the error of the failed batch.
+async fn send_batches<F, Fut>(
+    db: &DbExecutor,
+    owner: NodeId,
+    peer: NodeId,
+    batches: Vec<SyncBatch>,
+    mut send: F,
+) -> anyhow::Result<(usize, Option<String>)>
+where
+    F: FnMut(&SyncBatch) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let mut delivered = 0;
+    for (i, batch) in batches.iter().enumerate() {
+        log::debug!(
+            "Sending PaymentSync batch {} ({} items) as [{owner}] to [{peer}].",
+            i + 1,
+            batch.len()
+        );
+        if let Err(e) = send(batch).await {
+            return Ok((delivered, Some(e)));
+        }
+        mark_batch_sent(db, owner, batch).await?;
+        delivered += batch.len();
+    }
+    Ok((delivered, None))
+}
+
+async fn mark_batch_sent(
+    db: &DbExecutor,
+    owner_id: NodeId,
+    batch: &SyncBatch,
+) -> anyhow::Result<()> {
     let payment_dao: PaymentDao = db.as_dao();
     let invoice_dao: InvoiceDao = db.as_dao();
     let debit_note_dao: DebitNoteDao = db.as_dao();
 
-    for payment_send in msg.payments {
+    for payment_send in &batch.msg.payments {
         log::info!(
             "Delivered Payment confirmation [{}] to [{}]",
             payment_send.payment.payment_id,
             payment_send.payment.payee_id
         );
         payment_dao
-            .mark_sent(payment_send.payment.payment_id)
+            .mark_sent(payment_send.payment.payment_id.clone())
             .await?;
     }
 
-    for invoice_accept in msg.invoice_accepts {
+    for invoice_accept in &batch.msg.invoice_accepts {
         log::info!(
             "Delivered Invoice [{}] acceptance to [{}]",
             invoice_accept.invoice_id,
             invoice_accept.issuer_id
         );
         invoice_dao
-            .mark_accept_sent(invoice_accept.invoice_id, owner_id)
+            .mark_accept_sent(invoice_accept.invoice_id.clone(), owner_id)
             .await?;
     }
 
-    for invoice_reject in msg.invoice_rejects {
+    for invoice_reject in &batch.msg.invoice_rejects {
         log::info!(
             "Delivered Invoice [{}] rejection to [{}]",
             invoice_reject.invoice_id,
             invoice_reject.issuer_id
         );
         invoice_dao
-            .mark_reject_sent(invoice_reject.invoice_id, owner_id)
+            .mark_reject_sent(invoice_reject.invoice_id.clone(), owner_id)
             .await?;
     }
 
-    for debit_note_accept in msg.debit_note_accepts {
+    for debit_note_accept in &batch.msg.debit_note_accepts {
         log::info!(
             "Delivered DebitNote [{}] acceptance to [{}]",
             debit_note_accept.debit_note_id,
             debit_note_accept.issuer_id
         );
         debit_note_dao
-            .mark_accept_sent(debit_note_accept.debit_note_id, owner_id)
+            .mark_accept_sent(debit_note_accept.debit_note_id.clone(), owner_id)
             .await?;
     }
 
@@ -198,43 +345,59 @@ async fn send_sync_notifs_for_peer(
             continue;
         }
 
-        let (msg, msg_with_bytes) = payment_sync(db, owner, peer).await?;
-
-        log::debug!("Sending PaymentSync as [{owner}] to [{peer}].");
-        let mut result = ya_net::from(owner)
+        let items = payment_sync(db, owner, peer).await?;
+        let batches = into_batches(items, config.sync_batch_size);
+        let total = batches.iter().map(SyncBatch::len).sum::<usize>();
+        let endpoint = ya_net::from(owner)
             .to(peer)
-            .service(ya_core_model::payment::public::BUS_ID)
-            .call(msg_with_bytes.clone())
-            .await;
-
-        log::debug!("Sending PaymentSync as [{owner}] to [{peer}] result: {result:?}");
-
-        // Centralnet and hybridnet return different errors when the endpoint is not supported, so
-        // we have to resort to checking error message.
-        // This message will be sent even if the node can handle PaymentSyncWithBytes but is not
-        // connected at all, but there is no standard way to differentiate between these cases.
-        if matches!(&result, Err(e) if e.to_string().contains("endpoint address not found")) {
-            log::debug!("Sending PaymentSync as [{owner}] to [{peer}]: PaymentSyncWithBytes endpoint not found, falling back to PaymentSync.");
-            result = ya_net::from(owner)
-                .to(peer)
-                .service(ya_core_model::payment::public::BUS_ID)
-                .call(msg.clone())
-                .await;
-        }
+            .service(ya_core_model::payment::public::BUS_ID);
+        let legacy = Cell::new(false);
+
+        let (delivered, error) = send_batches(db, owner, peer, batches, |batch| {
+            let msg = batch.msg.clone();
+            let msg_with_bytes = batch.msg_with_bytes.clone();
+            let (endpoint, legacy) = (&endpoint, &legacy);
+            async move {
+                let result = match legacy.get() {
+                    false => {
+                        let result = endpoint.call(msg_with_bytes).await;
+                        log::debug!("Sending PaymentSync as [{owner}] to [{peer}] result: {result:?}");
+
+                        // Centralnet and hybridnet return different errors when the endpoint is not supported, so
+                        // we have to resort to checking error message.
+                        // This message will be sent even if the node can handle PaymentSyncWithBytes but is not
+                        // connected at all, but there is no standard way to differentiate between these cases.
+                        if matches!(&result, Err(e) if e.to_string().contains("endpoint address not found"))
+                        {
+                            log::debug!("Sending PaymentSync as [{owner}] to [{peer}]: PaymentSyncWithBytes endpoint not found, falling back to PaymentSync.");
+                            legacy.set(true);
+                            endpoint.call(msg).await
+                        } else {
+                            result
+                        }
+                    }
+                    true => endpoint.call(msg).await,
+                };
+                match result {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+        })
+        .await?;
 
-        if matches!(&result, Ok(Ok(_))) {
-            log::debug!("Delivered PaymentSync to [{peer}] as [{owner}].");
-            mark_all_sent(db, owner, msg).await?;
-        } else {
+        if let Some(err) = error {
             all_delivered = false;
-            let err = match result {
-                Err(x) => x.to_string(),
-                Ok(Err(x)) => x.to_string(),
-                Ok(Ok(_)) => unreachable!(),
-            };
-            log::debug!("Couldn't deliver PaymentSync to [{peer}] as [{owner}]: {err}");
+            log::debug!(
+                "Couldn't deliver PaymentSync to [{peer}] as [{owner}]: {err}. Delivered {delivered} of {total} items."
+            );
             dao.increment_retry(peer, cutoff.naive_utc()).await?;
         }
+
+        if delivered == total {
+            log::debug!("Delivered PaymentSync to [{peer}] as [{owner}].");
+        }
     }
 
     if all_delivered {
@@ -323,6 +486,25 @@ pub fn send_sync_notifs_job(db: DbExecutor, config: Arc<Config>) {
     });
 }
 
+/// Periodically forgets idempotency keys of received payments older than the configured window.
+pub fn purge_idempotency_keys_job(db: DbExecutor, config: Arc<Config>) {
+    let window = config.idempotency_window;
+    tokio::task::spawn_local(async move {
+        loop {
+            let before = Utc::now() - chrono::Duration::from_std(window).unwrap_or_default();
+            match db
+                .as_dao::<IdempotencyKeyDao>()
+                .purge(before.naive_utc())
+                .await
+            {
+                Ok(removed) => log::debug!("Purged {removed} expired payment idempotency keys"),
+                Err(e) => log::error!("Purging payment idempotency keys failed: {e}"),
+            }
+            tokio::time::sleep(IDEMPOTENCY_KEYS_PURGE_INTERVAL).await;
+        }
+    });
+}
+
 async fn send_sync_requests_impl(db: DbExecutor) -> anyhow::Result<()> {
     let invoice_dao: InvoiceDao = db.as_dao();
     let debit_note_dao: DebitNoteDao = db.as_dao();
@@ -375,3 +557,343 @@ pub fn send_sync_requests(db: DbExecutor) {
         }
     });
 }
+
+/// State of payments shared with the peer, which lost its database.
+pub async fn payment_sync_summary(
+    db: &DbExecutor,
+    peer_id: NodeId,
+    since: DateTime<Utc>,
+) -> anyhow::Result<PaymentSyncSummary> {
+    let agreement_dao: AgreementDao = db.as_dao();
+    let invoices = db
+        .as_dao::<InvoiceDao>()
+        .outstanding_with_peer(peer_id, since.naive_utc())
+        .await?;
+
+    let mut summary = PaymentSyncSummary::default();
+    let mut known = HashSet::new();
+    for invoice in invoices {
+        let (owner_id, role) = match invoice.issuer_id == peer_id {
+            true => (
+                invoice.recipient_id,
+                ya_client_model::market::Role::Requestor,
+            ),
+            false => (invoice.issuer_id, ya_client_model::market::Role::Provider),
+        };
+
+        if !known.contains(&invoice.agreement_id) {
+            let agreement = match get_agreement(invoice.agreement_id.clone(), role).await? {
+                Some(agreement) => agreement,
+                None => {
+                    log::debug!(
+                        "Skipping Invoice [{}] in sync summary for [{peer_id}]: Agreement [{}] not found.",
+                        invoice.invoice_id,
+                        invoice.agreement_id
+                    );
+                    continue;
+                }
+            };
+            let totals = match agreement_dao
+                .get(invoice.agreement_id.clone(), owner_id)
+                .await?
+            {
+                Some(totals) => totals,
+                None => continue,
+            };
+            known.insert(invoice.agreement_id.clone());
+            summary.agreements.push(AgreementPaymentSummary {
+                agreement,
+                total_amount_accepted: totals.total_amount_accepted.0,
+                total_amount_paid: totals.total_amount_paid.0,
+            });
+        }
+        summary.invoices.push(invoice);
+    }
+    Ok(summary)
+}
+
+/// Rebuilds payment state of `node_id`, which lost its database, from summaries
+/// of its counterparties.
+pub async fn cold_sync(
+    db: &DbExecutor,
+    node_id: NodeId,
+    peers: Vec<NodeId>,
+    since: DateTime<Utc>,
+) -> ColdSyncResult {
+    let mut result = ColdSyncResult::default();
+
+    for peer_id in peers {
+        log::info!("Requesting payment sync summary from [{peer_id}] as [{node_id}].");
+        let summary = match ya_net::from(node_id)
+            .to(peer_id)
+            .service(payment::public::BUS_ID)
+            .call(PaymentSyncSummaryRequest { since })
+            .timeout(Some(REMOTE_CALL_TIMEOUT))
+            .await
+        {
+            Ok(Ok(Ok(summary))) => summary,
+            Ok(Ok(Err(e))) => {
+                log::warn!("Couldn't get payment sync summary from [{peer_id}]: {e}");
+                result.failed_peers.push(peer_id);
+                continue;
+            }
+            Ok(Err(e)) => {
+                log::warn!("Couldn't get payment sync summary from [{peer_id}]: {e}");
+                result.failed_peers.push(peer_id);
+                continue;
+            }
+            Err(_) => {
+                log::warn!("Couldn't get payment sync summary from [{peer_id}]: timeout");
+                result.failed_peers.push(peer_id);
+                continue;
+            }
+        };
+
+        match apply_sync_summary(db, node_id, peer_id, summary).await {
+            Ok((agreements, invoices)) => {
+                log::info!(
+                    "Restored {agreements} Agreements and {invoices} Invoices from [{peer_id}]."
+                );
+                result.agreements += agreements;
+                result.invoices += invoices;
+                result.synced_peers.push(peer_id);
+            }
+            Err(e) => {
+                log::warn!("Couldn't apply payment sync summary from [{peer_id}]: {e}");
+                result.failed_peers.push(peer_id);
+            }
+        }
+    }
+    result
+}
+
+/// Only documents of Agreements between the node and the peer are restored.
+async fn apply_sync_summary(
+    db: &DbExecutor,
+    node_id: NodeId,
+    peer_id: NodeId,
+    summary: PaymentSyncSummary,
+) -> anyhow::Result<(usize, usize)> {
+    let agreement_dao: AgreementDao = db.as_dao();
+    let activity_dao: ActivityDao = db.as_dao();
+    let invoice_dao: InvoiceDao = db.as_dao();
+
+    let mut agreements = HashMap::new();
+    for entry in summary.agreements {
+        let agreement = entry.agreement;
+        let provider_id = *agreement.provider_id();
+        let requestor_id = *agreement.requestor_id();
+        let role = if provider_id == node_id && requestor_id == peer_id {
+            Role::Provider
+        } else if requestor_id == node_id && provider_id == peer_id {
+            Role::Requestor
+        } else {
+            log::warn!(
+                "Ignoring Agreement [{}] from [{peer_id}]: not concluded with [{node_id}].",
+                agreement.agreement_id
+            );
+            continue;
+        };
+
+        let agreement_id = agreement.agreement_id.clone();
+        agreement_dao
+            .create_if_not_exists(agreement, node_id, role.clone())
+            .await?;
+        agreement_dao
+            .restore_totals(
+                agreement_id.clone(),
+                node_id,
+                entry.total_amount_accepted,
+                entry.total_amount_paid,
+            )
+            .await?;
+        agreements.insert(agreement_id, role);
+    }
+
+    let mut invoices = 0;
+    for invoice in summary.invoices {
+        let role = match agreements.get(&invoice.agreement_id) {
+            Some(Role::Provider) if invoice.issuer_id == node_id => Role::Provider,
+            Some(Role::Requestor) if invoice.recipient_id == node_id => Role::Requestor,
+            _ => {
+                log::warn!(
+                    "Ignoring Invoice [{}] from [{peer_id}]: unknown Agreement or parties.",
+                    invoice.invoice_id
+                );
+                continue;
+            }
+        };
+
+        for activity_id in &invoice.activity_ids {
+            activity_dao
+                .create_if_not_exists(
+                    activity_id.clone(),
+                    node_id,
+                    role.clone(),
+                    invoice.agreement_id.clone(),
+                )
+                .await?;
+        }
+
+        let invoice_id = invoice.invoice_id.clone();
+        match invoice_dao.insert_recovered(invoice, node_id).await {
+            Ok(()) => invoices += 1,
+            Err(e) => log::warn!("Couldn't restore Invoice [{invoice_id}] from [{peer_id}]: {e}"),
+        }
+    }
+
+    Ok((agreements.len(), invoices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use ya_client_model::market::agreement::State;
+    use ya_client_model::market::{Agreement, Demand, Offer};
+    use ya_client_model::payment::{DocumentStatus, Invoice};
+
+    fn invoice_accept(id: &str, timestamp: i64, value: u32) -> PrioritizedItem {
+        PrioritizedItem {
+            timestamp: Utc.timestamp_opt(timestamp, 0).unwrap(),
+            value: BigDecimal::from(value),
+            item: SyncItem::InvoiceAccept(AcceptInvoice::new(
+                id.to_string(),
+                Acceptance {
+                    total_amount_accepted: BigDecimal::from(value),
+                    allocation_id: String::new(),
+                },
+                NodeId::default(),
+            )),
+        }
+    }
+
+    #[test]
+    fn test_into_batches() {
+        let items = vec![
+            invoice_accept("newest", 30, 10),
+            invoice_accept("old-lo", 10, 10),
+            invoice_accept("old-hi", 10, 99),
+            invoice_accept("middle", 20, 10),
+        ];
+        let item_size = items[0].item.size();
+
+        let ids = |batch: &SyncBatch| {
+            batch
+                .msg
+                .invoice_accepts
+                .iter()
+                .map(|accept| accept.invoice_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let batches = into_batches(items.clone(), 2 * item_size + 1);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(ids(&batches[0]), vec!["old-hi", "old-lo"]);
+        assert_eq!(ids(&batches[1]), vec!["middle", "newest"]);
+        assert_eq!(batches[1].msg_with_bytes.invoice_accepts.len(), 2);
+
+        // Items exceeding the limit are sent alone.
+        let batches = into_batches(items.clone(), 1);
+        assert_eq!(batches.len(), 4);
+        assert!(batches.iter().all(|batch| batch.len() == 1));
+
+        assert_eq!(into_batches(items, usize::MAX).len(), 1);
+        assert!(into_batches(vec![], 1).is_empty());
+    }
+
+    fn node_id(n: u8) -> NodeId {
+        format!("0x{:040x}", n).parse().unwrap()
+    }
+
+    fn agreement(requestor_id: NodeId, provider_id: NodeId) -> Agreement {
+        let demand = Demand::new(
+            json!({ "golem.com.payment.chosen-platform": "erc20-holesky-tglm" }),
+            "()".to_string(),
+            "demand_id".to_string(),
+            requestor_id,
+            Utc::now(),
+        );
+        let offer = Offer::new(
+            json!({}),
+            "()".to_string(),
+            "offer_id".to_string(),
+            provider_id,
+            Utc::now(),
+        );
+        Agreement::new(
+            "agreement_id".to_string(),
+            demand,
+            offer,
+            Utc::now(),
+            State::Approved,
+            Utc::now(),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_only_delivered_batches_marked_sent() {
+        let db = DbExecutor::in_memory("payment_sync_batches").unwrap();
+        db.apply_migration(crate::migrations::run_with_output)
+            .unwrap();
+        let (owner, peer) = (node_id(1), node_id(2));
+        db.as_dao::<AgreementDao>()
+            .create_if_not_exists(agreement(owner, peer), owner, Role::Requestor)
+            .await
+            .unwrap();
+
+        let invoice_dao: InvoiceDao = db.as_dao();
+        for i in 0..3 {
+            let invoice_id = format!("invoice-{i}");
+            invoice_dao
+                .insert_received(Invoice {
+                    invoice_id: invoice_id.clone(),
+                    issuer_id: peer,
+                    recipient_id: owner,
+                    payee_addr: peer.to_string(),
+                    payer_addr: owner.to_string(),
+                    payment_platform: "erc20-holesky-tglm".to_string(),
+                    timestamp: Utc.timestamp_opt(i, 0).unwrap(),
+                    agreement_id: "agreement_id".to_string(),
+                    activity_ids: vec![],
+                    amount: BigDecimal::from(1),
+                    payment_due_date: Utc::now(),
+                    status: DocumentStatus::Received,
+                })
+                .await
+                .unwrap();
+            invoice_dao.accept(invoice_id, owner).await.unwrap();
+        }
+
+        let items = payment_sync(&db, owner, peer).await.unwrap();
+        let batches = into_batches(items, 1);
+        assert_eq!(batches.len(), 3);
+
+        // Peer disconnects after the first batch.
+        let mut attempts = 0;
+        let (delivered, error) = send_batches(&db, owner, peer, batches, |_| {
+            attempts += 1;
+            let result = match attempts {
+                1 => Ok(()),
+                _ => Err("session closed".to_string()),
+            };
+            async move { result }
+        })
+        .await
+        .unwrap();
+        assert_eq!((delivered, attempts), (1, 2));
+        assert_eq!(error.as_deref(), Some("session closed"));
+
+        let mut unsent = invoice_dao
+            .unsent_accepted(owner, peer)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|invoice| invoice.invoice_id)
+            .collect::<Vec<_>>();
+        unsent.sort();
+        assert_eq!(unsent, vec!["invoice-1", "invoice-2"]);
+    }
+}
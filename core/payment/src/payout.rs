@@ -0,0 +1,230 @@
+//! Minimum payout thresholds per network.
+//!
+//! Paying every debit note separately may cost more gas than the transferred value.
+//! Debit note payments below the threshold of their network are not scheduled. The
+//! amount is carried over, because next debit note (being cumulative) is paid minus
+//! what was already scheduled, so payment happens once the aggregate passes the threshold.
+//! Invoices are always paid in full, so everything left is settled at agreement end.
+//!
+//! Debit notes are due at the negotiated payment timeout. Deferred payment is scheduled
+//! shortly before the earliest due date, if no later debit note or invoice paid it yet.
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use ya_core_model::payment::local::{PaymentTitle, SchedulePayment};
+
+#[derive(Clone, Debug, Default)]
+pub struct PayoutThresholds {
+    networks: HashMap<String, BigDecimal>,
+}
+
+impl PayoutThresholds {
+    /// Network of `driver-network-token` payment platform.
+    fn network(platform: &str) -> Option<&str> {
+        platform.split('-').nth(1)
+    }
+
+    pub fn threshold(&self, platform: &str) -> Option<&BigDecimal> {
+        self.networks.get(&Self::network(platform)?.to_lowercase())
+    }
+
+    /// Final payments (invoices) are never deferred.
+    pub fn defers(&self, msg: &SchedulePayment) -> bool {
+        match msg.title {
+            PaymentTitle::DebitNote(_) => self
+                .threshold(&msg.payment_platform)
+                .map(|threshold| &msg.amount < threshold)
+                .unwrap_or(false),
+            PaymentTitle::Invoice(_) => false,
+        }
+    }
+}
+
+/// Debit note payment deferred for an activity.
+#[derive(Clone, Debug)]
+struct Deferred {
+    agreement_id: String,
+    payment: SchedulePayment,
+}
+
+/// Debit note payments deferred per activity. Debit notes are cumulative, so only
+/// the latest payment is kept, due at the earliest due date of the deferred ones.
+#[derive(Debug, Default)]
+pub struct DeferredPayouts {
+    activities: HashMap<String, Deferred>,
+}
+
+impl DeferredPayouts {
+    /// Due date of the earliest payment deferred for the activity.
+    pub fn due_date(&self, activity_id: &str) -> Option<DateTime<Utc>> {
+        self.activities
+            .get(activity_id)
+            .map(|deferred| deferred.payment.due_date)
+    }
+
+    pub fn defer(
+        &mut self,
+        activity_id: String,
+        agreement_id: String,
+        mut payment: SchedulePayment,
+    ) {
+        if let Some(due_date) = self.due_date(&activity_id) {
+            payment.due_date = payment.due_date.min(due_date);
+        }
+        self.activities.insert(
+            activity_id,
+            Deferred {
+                agreement_id,
+                payment,
+            },
+        );
+    }
+
+    /// Drops deferred payments, which are included in the payment scheduled for `title`.
+    pub fn settle(&mut self, title: &PaymentTitle) {
+        match title {
+            PaymentTitle::DebitNote(payment) => {
+                self.activities.remove(&payment.activity_id);
+            }
+            PaymentTitle::Invoice(payment) => self
+                .activities
+                .retain(|_, deferred| deferred.agreement_id != payment.agreement_id),
+        }
+    }
+
+    /// Takes deferred payment, unless it was settled or replaced by a later debit note.
+    pub fn take(&mut self, activity_id: &str, document_id: &str) -> Option<SchedulePayment> {
+        match self.activities.get(activity_id) {
+            Some(deferred) if deferred.payment.document_id() == document_id => self
+                .activities
+                .remove(activity_id)
+                .map(|deferred| deferred.payment),
+            _ => None,
+        }
+    }
+}
+
+/// Parses comma separated `network=amount` pairs.
+impl FromStr for PayoutThresholds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let networks = s
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (network, amount) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected `network=amount`, got `{}`", pair))?;
+                let amount = BigDecimal::from_str(amount.trim())
+                    .map_err(|e| format!("Invalid payout threshold for {}: {}", network, e))?;
+                Ok((network.trim().to_lowercase(), amount))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(PayoutThresholds { networks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use ya_core_model::payment::local::{DebitNotePayment, InvoicePayment};
+
+    fn payment(title: PaymentTitle, platform: &str, amount: &str) -> SchedulePayment {
+        SchedulePayment {
+            title,
+            payer_id: Default::default(),
+            payee_id: Default::default(),
+            payer_addr: "0x".to_string(),
+            payee_addr: "0x".to_string(),
+            payment_platform: platform.to_string(),
+            allocation_id: "allocation".to_string(),
+            amount: BigDecimal::from_str(amount).unwrap(),
+            due_date: Utc::now(),
+        }
+    }
+
+    fn debit_note() -> PaymentTitle {
+        debit_note_for("debit-note", "activity")
+    }
+
+    fn debit_note_for(debit_note_id: &str, activity_id: &str) -> PaymentTitle {
+        PaymentTitle::DebitNote(DebitNotePayment {
+            debit_note_id: debit_note_id.to_string(),
+            activity_id: activity_id.to_string(),
+        })
+    }
+
+    #[test]
+    fn defers_only_debit_notes_below_network_threshold() {
+        let thresholds = PayoutThresholds::from_str("Polygon=0.5, mainnet = 10").unwrap();
+
+        assert!(thresholds.defers(&payment(debit_note(), "erc20-polygon-glm", "0.1")));
+        assert!(!thresholds.defers(&payment(debit_note(), "erc20-polygon-glm", "0.5")));
+        assert!(!thresholds.defers(&payment(debit_note(), "erc20-holesky-tglm", "0.1")));
+
+        let invoice = PaymentTitle::Invoice(InvoicePayment {
+            invoice_id: "invoice".to_string(),
+            agreement_id: "agreement".to_string(),
+        });
+        assert!(!thresholds.defers(&payment(invoice, "erc20-mainnet-glm", "0.1")));
+
+        assert!(PayoutThresholds::from_str("").unwrap().networks.is_empty());
+        assert!(PayoutThresholds::from_str("polygon").is_err());
+        assert!(PayoutThresholds::from_str("polygon=abc").is_err());
+    }
+
+    #[test]
+    fn deferred_payment_keeps_earliest_due_date() {
+        let mut deferred = DeferredPayouts::default();
+        let first = payment(
+            debit_note_for("dn-1", "activity"),
+            "erc20-polygon-glm",
+            "0.1",
+        );
+        let mut second = payment(
+            debit_note_for("dn-2", "activity"),
+            "erc20-polygon-glm",
+            "0.2",
+        );
+        second.due_date = first.due_date + chrono::Duration::minutes(2);
+
+        deferred.defer(
+            "activity".to_string(),
+            "agreement".to_string(),
+            first.clone(),
+        );
+        deferred.defer("activity".to_string(), "agreement".to_string(), second);
+        assert_eq!(deferred.due_date("activity"), Some(first.due_date));
+
+        // Replaced by the later debit note.
+        assert!(deferred.take("activity", "dn-1").is_none());
+        let payment = deferred.take("activity", "dn-2").unwrap();
+        assert_eq!(payment.amount, BigDecimal::from_str("0.2").unwrap());
+        assert_eq!(payment.due_date, first.due_date);
+        assert!(deferred.due_date("activity").is_none());
+    }
+
+    #[test]
+    fn invoice_settles_deferred_payments_of_agreement() {
+        let mut deferred = DeferredPayouts::default();
+        let platform = "erc20-polygon-glm";
+        let dn = |id, activity| payment(debit_note_for(id, activity), platform, "0.1");
+        deferred.defer("a1".to_string(), "agreement".to_string(), dn("dn-1", "a1"));
+        deferred.defer("a2".to_string(), "other".to_string(), dn("dn-2", "a2"));
+
+        deferred.settle(&PaymentTitle::Invoice(InvoicePayment {
+            invoice_id: "invoice".to_string(),
+            agreement_id: "agreement".to_string(),
+        }));
+        assert!(deferred.due_date("a1").is_none());
+        assert!(deferred.due_date("a2").is_some());
+
+        deferred.settle(&debit_note_for("dn-3", "a2"));
+        assert!(deferred.due_date("a2").is_none());
+    }
+}
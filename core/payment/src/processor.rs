@@ -1,14 +1,29 @@
 use crate::api::allocations::{forced_release_allocation, release_allocation_after};
+use crate::basket::{self, Candidate};
 use crate::dao::{
-    ActivityDao, AgreementDao, AllocationDao, AllocationStatus, OrderDao, PaymentDao, SyncNotifsDao,
+    ActivityDao, AgreementDao, AllocationDao, AllocationStatus, FeeDao, GasCostDao,
+    NotifyPaymentDao, OrderDao, PaymentDao, PaymentReceiptDao, SyncNotifsDao,
 };
 use crate::error::processor::{
     AccountNotRegistered, GetStatusError, NotifyPaymentError, OrderValidationError,
     SchedulePaymentError, ValidateAllocationError, VerifyPaymentError,
 };
+use crate::error::DbError;
+use crate::fee::FeeHook;
+use crate::fiat::{FiatAnnotator, FiatEntity};
+use crate::gasless;
+use crate::models::fee::{ReadObj as DbFee, WriteObj as FeeObj};
+use crate::models::gas_cost::TxGasCost;
+use crate::models::notify_payment::WriteObj as NotificationObj;
 use crate::models::order::ReadObj as DbOrder;
+use crate::models::payment_receipt::PaymentReceipt;
 use crate::payment_sync::SYNC_NOTIFS_NOTIFY;
+use crate::payout::{DeferredPayouts, PayoutThresholds};
+use crate::receipt;
+use crate::reservation::ReservationLedger;
+use crate::settlement::SettlementLedger;
 use crate::timeout_lock::{MutexTimeoutExt, RwLockTimeoutExt};
+use crate::utils;
 
 use actix_web::web::Data;
 use bigdecimal::{BigDecimal, Zero};
@@ -23,18 +38,21 @@ use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::{Mutex, RwLock};
 
+use ya_client_model::market::{Agreement, Role as MarketRole};
 use ya_client_model::payment::allocation::Deposit;
 use ya_client_model::payment::{
-    Account, ActivityPayment, AgreementPayment, DriverDetails, Network, Payment,
+    Account, ActivityPayment, AgreementPayment, Allocation, DriverDetails, Network, Payment,
 };
 use ya_core_model::driver::{
     self, driver_bus_id, AccountMode, DriverReleaseDeposit, GetAccountBalanceResult,
     GetRpcEndpointsResult, PaymentConfirmation, PaymentDetails, ShutDown, ValidateAllocation,
     ValidateAllocationResult,
 };
+use ya_core_model::events::EventPayload;
 use ya_core_model::payment::local::{
-    GenericError, GetAccountsError, GetDriversError, NotifyPayment, RegisterAccount,
-    RegisterAccountError, RegisterDriver, RegisterDriverError, ReleaseDeposit, SchedulePayment,
+    DriverCapabilities, FeeStatus, GenericError, GetAccountsError, GetDriversError, InvoicePayment,
+    NotifyPayment, PaymentTitle, RegisterAccount, RegisterAccountError, RegisterDriver,
+    RegisterDriverError, ReleaseDeposit, Reservation, SchedulePayment, SettlementStats,
     UnregisterAccount, UnregisterAccountError, UnregisterDriver, UnregisterDriverError,
 };
 use ya_core_model::payment::public::{SendPayment, SendSignedPayment, BUS_ID};
@@ -49,6 +67,22 @@ fn driver_endpoint(driver: &str) -> Endpoint {
     bus::service(driver_bus_id(driver))
 }
 
+/// Agreement of the invoice or debit note paid by `msg`.
+async fn agreement_of(msg: &SchedulePayment) -> Result<Option<Agreement>, crate::error::Error> {
+    match &msg.title {
+        PaymentTitle::Invoice(invoice) => {
+            utils::get_agreement(invoice.agreement_id.clone(), MarketRole::Requestor).await
+        }
+        PaymentTitle::DebitNote(debit_note) => {
+            utils::provider::get_agreement_for_activity(
+                debit_note.activity_id.clone(),
+                MarketRole::Requestor,
+            )
+            .await
+        }
+    }
+}
+
 fn validate_orders(
     orders: &[DbOrder],
     platform: &str,
@@ -62,11 +96,16 @@ fn validate_orders(
         ));
     }
 
+    // Identities share the node, but a transaction batches orders of a single one.
+    let payer_id = orders[0].payer_id;
     let mut total_amount = BigDecimal::zero();
     for order in orders.iter() {
         if order.amount.0 == BigDecimal::zero() {
             return OrderValidationError::zero_amount(order);
         }
+        if order.payer_id != payer_id {
+            return OrderValidationError::payer_id(order, &payer_id);
+        }
         if order.payment_platform != platform {
             return OrderValidationError::platform(order, platform);
         }
@@ -101,6 +140,8 @@ struct DriverRegistry {
     // (platform, address) -> details
     drivers: HashMap<String, DriverDetails>,
     // driver_name -> details
+    capabilities: HashMap<String, DriverCapabilities>,
+    // driver_name -> capabilities
     platforms: HashMap<String, HashMap<String, bool>>, // platform -> (driver_name -> recv_init_required)
 }
 
@@ -109,6 +150,7 @@ impl DriverRegistry {
         let RegisterDriver {
             driver_name,
             details,
+            capabilities,
         } = msg;
         log::trace!(
             "register_driver: driver_name={} details={:?}",
@@ -135,6 +177,7 @@ impl DriverRegistry {
                     .insert(driver_name.clone(), details.recv_init_required);
             }
         }
+        self.capabilities.insert(driver_name.clone(), capabilities);
         self.drivers.insert(driver_name, details);
         Ok(())
     }
@@ -142,6 +185,7 @@ impl DriverRegistry {
     pub fn unregister_driver(&mut self, msg: UnregisterDriver) {
         let driver_name = msg.0;
         let details = self.drivers.remove(&driver_name);
+        self.capabilities.remove(&driver_name);
         if let Some(details) = details {
             for (network_name, network) in details.networks.iter() {
                 for (token, platform) in network.tokens.iter() {
@@ -234,6 +278,10 @@ impl DriverRegistry {
         self.drivers.clone()
     }
 
+    pub fn get_drivers_capabilities(&self) -> HashMap<String, DriverCapabilities> {
+        self.capabilities.clone()
+    }
+
     pub fn get_network(
         &self,
         driver: String,
@@ -310,6 +358,15 @@ const REGISTRY_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
 pub struct PaymentProcessor {
     db_executor: Arc<Mutex<DbExecutor>>,
     registry: RwLock<DriverRegistry>,
+    reservations: Mutex<ReservationLedger>,
+    fiat: FiatAnnotator,
+    settlement: Mutex<SettlementLedger>,
+    payout_thresholds: PayoutThresholds,
+    payout_due_margin: Duration,
+    deferred_payouts: Arc<std::sync::Mutex<DeferredPayouts>>,
+    fee_hook: Option<Arc<dyn FeeHook>>,
+    /// Serializes scheduling of due fees, so none is scheduled twice.
+    fee_scheduling: Mutex<()>,
     in_shutdown: AtomicBool,
 }
 
@@ -328,10 +385,56 @@ impl PaymentProcessor {
         Self {
             db_executor: Arc::new(Mutex::new(db_executor)),
             registry: Default::default(),
+            reservations: Default::default(),
+            fiat: Default::default(),
+            settlement: Default::default(),
+            payout_thresholds: Default::default(),
+            payout_due_margin: Duration::ZERO,
+            deferred_payouts: Default::default(),
+            fee_hook: None,
+            fee_scheduling: Mutex::new(()),
             in_shutdown: AtomicBool::new(false),
         }
     }
 
+    pub fn with_fiat(mut self, fiat: FiatAnnotator) -> Self {
+        self.fiat = fiat;
+        self
+    }
+
+    pub fn fiat(&self) -> &FiatAnnotator {
+        &self.fiat
+    }
+
+    /// Accepted invoices paid less than invoiced by at most `tolerance` are settled
+    /// on payment receipt.
+    pub fn with_settle_tolerance(mut self, tolerance: BigDecimal) -> Self {
+        self.settlement = Mutex::new(SettlementLedger::new(tolerance));
+        self
+    }
+
+    /// Debit note payments below threshold of their network are deferred, but scheduled
+    /// at latest `due_margin` before they are due.
+    pub fn with_payout_thresholds(
+        mut self,
+        thresholds: PayoutThresholds,
+        due_margin: Duration,
+    ) -> Self {
+        self.payout_thresholds = thresholds;
+        self.payout_due_margin = due_margin;
+        self
+    }
+
+    /// Fees computed by the hook are paid along with invoice payments.
+    pub fn with_fee_hook(mut self, fee_hook: Option<Arc<dyn FeeHook>>) -> Self {
+        self.fee_hook = fee_hook;
+        self
+    }
+
+    pub async fn settlement_stats(&self) -> SettlementStats {
+        self.settlement.lock().await.stats()
+    }
+
     pub async fn register_driver(&self, msg: RegisterDriver) -> Result<(), RegisterDriverError> {
         self.registry
             .timeout_write(REGISTRY_LOCK_TIMEOUT)
@@ -389,6 +492,16 @@ impl PaymentProcessor {
             .map_err(|_| GetDriversError::InternalTimeout)
     }
 
+    pub async fn get_drivers_capabilities(
+        &self,
+    ) -> Result<HashMap<String, DriverCapabilities>, GetDriversError> {
+        self.registry
+            .timeout_read(REGISTRY_LOCK_TIMEOUT)
+            .await
+            .map(|registry| registry.get_drivers_capabilities())
+            .map_err(|_| GetDriversError::InternalTimeout)
+    }
+
     pub async fn get_network(
         &self,
         driver: String,
@@ -414,16 +527,101 @@ impl PaymentProcessor {
             .get_platform(driver, network, token)
     }
 
+    /// Processes again notifications of the driver, which were interrupted by a crash.
+    /// Driver has to be registered, because payments are signed by it.
+    pub async fn recover_notifications(&self, driver: String) {
+        let pending = match self.db_executor.timeout_lock(DB_LOCK_TIMEOUT).await {
+            Ok(db) => {
+                db.as_dao::<NotifyPaymentDao>()
+                    .pending(driver.clone())
+                    .await
+            }
+            Err(e) => {
+                log::warn!("Failed to recover payment notifications of [{driver}]: {e}");
+                return;
+            }
+        };
+        let pending = match pending {
+            Ok(pending) => pending,
+            Err(e) => {
+                log::warn!("Failed to recover payment notifications of [{driver}]: {e}");
+                return;
+            }
+        };
+
+        for notification in pending {
+            let msg = match notification.notification() {
+                Ok(msg) => msg,
+                Err(e) => {
+                    log::error!("Invalid stored payment notification: {e}");
+                    continue;
+                }
+            };
+            log::info!(
+                "Recovering payment notification for orders {:?}",
+                msg.order_ids
+            );
+            if let Err(e) = self.notify_payment(msg).await {
+                log::error!("Failed to recover payment notification: {e}");
+            }
+        }
+    }
+
     pub async fn notify_payment(&self, msg: NotifyPayment) -> Result<(), NotifyPaymentError> {
+        if msg.order_ids.is_empty() {
+            return Err(OrderValidationError::new("order_ids is empty").into());
+        }
+
+        // Fee payments have no documents to account.
+        let fees_settled = self
+            .db_executor
+            .timeout_lock(DB_LOCK_TIMEOUT)
+            .await?
+            .as_dao::<FeeDao>()
+            .settle(
+                msg.order_ids.clone(),
+                msg.driver.clone(),
+                format!("0x{}", hex::encode(&msg.confirmation.confirmation)),
+            )
+            .await?;
+        if fees_settled {
+            log::info!("Fee payment for orders {:?} confirmed", msg.order_ids);
+            self.reservations
+                .lock()
+                .await
+                .release_orders(&msg.order_ids);
+            return Ok(());
+        }
+
+        // Notification is persisted before accounting, so it can be recovered after crash.
+        let notification = NotificationObj::new(&msg).map_err(DbError::from)?;
         let driver = msg.driver;
         let payment_platform = msg.platform;
         let payer_addr = msg.sender;
         let payee_addr = msg.recipient;
 
-        if msg.order_ids.is_empty() {
-            return Err(OrderValidationError::new("order_ids is empty").into());
+        let processed = self
+            .db_executor
+            .timeout_lock(DB_LOCK_TIMEOUT)
+            .await?
+            .as_dao::<NotifyPaymentDao>()
+            .record(notification.clone())
+            .await?;
+        if let Some(payment_id) = processed {
+            log::info!(
+                "Payment confirmation for orders {:?} already processed as payment [{}]",
+                msg.order_ids,
+                payment_id
+            );
+            return Ok(());
         }
 
+        // Paid orders are reflected in the account balance.
+        self.reservations
+            .lock()
+            .await
+            .release_orders(&msg.order_ids);
+
         let payer_id: NodeId;
         let payee_id: NodeId;
         let payment_id: String;
@@ -433,8 +631,15 @@ impl PaymentProcessor {
 
             let orders = db_executor
                 .as_dao::<OrderDao>()
-                .get_many(msg.order_ids, driver.clone())
+                .get_many(msg.order_ids.clone(), driver.clone())
                 .await?;
+            if let Some(order) = orders.iter().find(|order| order.is_paid) {
+                return Err(OrderValidationError::new(format!(
+                    "order {} already paid by another transaction",
+                    order.id
+                ))
+                .into());
+            }
             validate_orders(
                 &orders,
                 &payment_platform,
@@ -463,12 +668,25 @@ impl PaymentProcessor {
             }
 
             // FIXME: This is a hack. Payment orders realized by a single transaction are not guaranteed
-            //        to have the same payer and payee IDs. Fixing this requires a major redesign of the
-            //        data model. Payments can no longer by assigned to a single payer and payee.
+            //        to have the same payee ID. Fixing this requires a major redesign of the
+            //        data model. Payments can no longer by assigned to a single payee.
             payer_id = orders.get(0).unwrap().payer_id;
             payee_id = orders.get(0).unwrap().payee_id;
 
             let payment_dao: PaymentDao = db_executor.as_dao();
+            let order_ids = msg.order_ids.clone();
+
+            if let Some(gas_cost) = msg.gas_cost {
+                db_executor
+                    .as_dao::<GasCostDao>()
+                    .insert(TxGasCost {
+                        owner_id: payer_id,
+                        details: msg.confirmation.confirmation.clone(),
+                        platform: payment_platform.clone(),
+                        gas_cost: gas_cost.into(),
+                    })
+                    .await?;
+            }
 
             payment_id = payment_dao
                 .create_new(
@@ -481,8 +699,23 @@ impl PaymentProcessor {
                     msg.confirmation.confirmation,
                     activity_payments,
                     agreement_payments,
+                    msg.order_ids,
+                    notification,
                 )
                 .await?;
+            db_executor
+                .as_dao::<FeeDao>()
+                .mark_due(order_ids, driver.clone())
+                .await?;
+
+            self.fiat.stamp(
+                (*db_executor).clone(),
+                payer_id,
+                FiatEntity::Payment,
+                payment_id.clone(),
+                payment_platform.clone(),
+                msg.amount.clone(),
+            );
 
             let signed_payment = payment_dao
                 .get(payment_id.clone(), payer_id)
@@ -498,12 +731,24 @@ impl PaymentProcessor {
             .send(driver::SignPayment(payment.clone()))
             .await??;
 
+        ya_events::publish(
+            Some(payer_id),
+            EventPayload::PaymentSent {
+                payment_id: payment_id.clone(),
+                platform: payment_platform.clone(),
+                amount: msg.amount.to_string(),
+            },
+        );
         counter!("payment.amount.sent", ya_metrics::utils::cryptocurrency_to_u64(&msg.amount), "platform" => payment_platform);
         // This is unconditional because at this point the invoice *has been paid*.
         // Whether the provider was correctly notified of this fact is another matter.
         counter!("payment.invoices.requestor.paid", 1);
         let msg = SendPayment::new(payment.clone(), signature);
         let msg_with_bytes = SendSignedPayment::new(payment.clone(), signature_canonical);
+        let receipt = receipt::issue(payment.clone())
+            .await
+            .map_err(|e| log::warn!("Failed to issue receipt for payment [{payment_id}]: {e}"))
+            .ok();
 
         let db_executor = Arc::clone(&self.db_executor);
 
@@ -540,6 +785,17 @@ impl PaymentProcessor {
                     )
                     .await?;
 
+                if let Some(receipt) = receipt {
+                    db_executor
+                        .as_dao::<PaymentReceiptDao>()
+                        .upsert(PaymentReceipt::new(receipt.clone(), payer_id))
+                        .await?;
+                    // Receipts are best effort, Provider already has the signed payment.
+                    if mark_sent {
+                        Self::send_to_gsb(payer_id, payee_id, receipt).await.ok();
+                    }
+                }
+
                 if mark_sent {
                     payment_dao.mark_sent(payment_id).await?;
                 } else {
@@ -553,6 +809,7 @@ impl PaymentProcessor {
             .inspect_err(|e| log::error!("Notify payment task failed: {e}")),
         );
 
+        self.schedule_due_fees().await;
         Ok(())
     }
 
@@ -582,7 +839,10 @@ impl PaymentProcessor {
             .await
     }
 
-    pub async fn schedule_payment(&self, msg: SchedulePayment) -> Result<(), SchedulePaymentError> {
+    pub async fn schedule_payment(
+        &self,
+        mut msg: SchedulePayment,
+    ) -> Result<(), SchedulePaymentError> {
         if self.in_shutdown.load(Ordering::SeqCst) {
             return Err(SchedulePaymentError::Shutdown);
         }
@@ -593,6 +853,16 @@ impl PaymentProcessor {
                 &amount
             )));
         }
+        if self.payout_thresholds.defers(&msg) && self.defer_payout(&msg).await? {
+            log::info!(
+                "Payment of {} for {} is below minimum payout on {}. Deferring until next debit note or invoice.",
+                amount,
+                msg.document_id(),
+                msg.payment_platform
+            );
+            return Ok(());
+        }
+        let title = msg.title.clone();
 
         let allocation_status = self
             .db_executor
@@ -602,6 +872,19 @@ impl PaymentProcessor {
             .get(msg.allocation_id.clone(), msg.payer_id)
             .await?;
         let deposit_id = if let AllocationStatus::Active(allocation) = allocation_status {
+            if allocation.deposit.is_none() {
+                let platform = self.choose_platform(&msg, &allocation).await;
+                if platform != msg.payment_platform {
+                    log::info!(
+                        "Paying {} for {} on {} instead of {}",
+                        msg.amount,
+                        msg.document_id(),
+                        platform,
+                        msg.payment_platform
+                    );
+                    msg.payment_platform = platform;
+                }
+            }
             allocation.deposit
         } else {
             None
@@ -613,25 +896,453 @@ impl PaymentProcessor {
             .await?
             .driver(&msg.payment_platform, &msg.payer_addr, AccountMode::SEND)?;
 
-        let order_id = driver_endpoint(&driver)
-            .send(driver::SchedulePayment::new(
-                amount,
-                msg.payer_addr.clone(),
-                msg.payee_addr.clone(),
-                msg.payment_platform.clone(),
-                deposit_id,
-                msg.due_date,
-            ))
-            .await??;
+        // Deposit payments are executed by the lock contract.
+        let gasless = deposit_id.is_none() && self.gasless_accepted(&msg, &driver).await;
+        let reservation_id = self.reserve_funds(&msg, deposit_id.is_some()).await?;
+        let fee = self.fee_payment(&msg);
+
+        let result = async {
+            let order_id = driver_endpoint(&driver)
+                .send(
+                    driver::SchedulePayment::new(
+                        amount,
+                        msg.payer_addr.clone(),
+                        msg.payee_addr.clone(),
+                        msg.payment_platform.clone(),
+                        deposit_id,
+                        msg.due_date,
+                    )
+                    .with_gasless(gasless),
+                )
+                .await??;
+
+            self.db_executor
+                .timeout_lock(DB_LOCK_TIMEOUT)
+                .await?
+                .as_dao::<OrderDao>()
+                .create(msg, order_id.clone(), driver.clone())
+                .await?;
+
+            Ok::<_, SchedulePaymentError>(order_id)
+        }
+        .await;
+
+        let order_id = {
+            let mut reservations = self.reservations.lock().await;
+            match result {
+                Ok(order_id) => {
+                    reservations.assign_order(&reservation_id, order_id.clone());
+                    order_id
+                }
+                Err(e) => {
+                    reservations.release(&reservation_id);
+                    return Err(e);
+                }
+            }
+        };
+        self.deferred_payouts.lock().unwrap().settle(&title);
 
-        self.db_executor
+        if let Some(fee) = fee {
+            self.record_fee(fee, order_id, &driver).await;
+        }
+        Ok(())
+    }
+
+    /// Defers debit note payment, unless payments of the activity are due too soon.
+    /// Deferred payment is scheduled `payout_due_margin` before the earliest due date,
+    /// if no later debit note or invoice pays it before.
+    async fn defer_payout(&self, msg: &SchedulePayment) -> Result<bool, SchedulePaymentError> {
+        let activity_id = match &msg.title {
+            PaymentTitle::DebitNote(debit_note) => debit_note.activity_id.clone(),
+            PaymentTitle::Invoice(_) => return Ok(false),
+        };
+        let due_date = match self.deferred_payouts.lock().unwrap().due_date(&activity_id) {
+            Some(due_date) => due_date.min(msg.due_date),
+            None => msg.due_date,
+        };
+        let delay = match chrono::Duration::from_std(self.payout_due_margin)
+            .ok()
+            .and_then(|margin| due_date.checked_sub_signed(margin))
+            .and_then(|scheduled| (scheduled - Utc::now()).to_std().ok())
+        {
+            Some(delay) => delay,
+            None => return Ok(false),
+        };
+
+        let activity = self
+            .db_executor
             .timeout_lock(DB_LOCK_TIMEOUT)
             .await?
-            .as_dao::<OrderDao>()
-            .create(msg, order_id, driver)
+            .as_dao::<ActivityDao>()
+            .get(activity_id.clone(), msg.payer_id)
             .await?;
+        let agreement_id = match activity {
+            Some(activity) => activity.agreement_id,
+            None => return Ok(false),
+        };
+        self.deferred_payouts
+            .lock()
+            .unwrap()
+            .defer(activity_id.clone(), agreement_id, msg.clone());
+
+        let deferred_payouts = self.deferred_payouts.clone();
+        let document_id = msg.document_id();
+        tokio::task::spawn_local(async move {
+            tokio::time::sleep(delay).await;
+            let payment = deferred_payouts
+                .lock()
+                .unwrap()
+                .take(&activity_id, &document_id);
+            if let Some(payment) = payment {
+                log::info!(
+                    "Scheduling deferred payment of {} for {} before it's due at {}",
+                    payment.amount,
+                    document_id,
+                    payment.due_date
+                );
+                if let Err(e) = bus::service(ya_core_model::payment::local::BUS_ID)
+                    .send(payment)
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|result| result.map_err(|e| e.to_string()))
+                {
+                    log::warn!("Failed to schedule deferred payment for {document_id}: {e}");
+                }
+            }
+        });
+        Ok(true)
+    }
 
-        Ok(())
+    /// Fee payment charged along with the payment, if the fee hook applies.
+    fn fee_payment(&self, msg: &SchedulePayment) -> Option<SchedulePayment> {
+        let fee = self.fee_hook.as_ref()?.fee(msg)?;
+        Some(SchedulePayment {
+            payee_addr: fee.recipient,
+            amount: fee.amount,
+            ..msg.clone()
+        })
+    }
+
+    /// Records the fee in the fee ledger. It's scheduled once the payment of `order_id`
+    /// is confirmed, so fees are never paid for invoices, which weren't.
+    async fn record_fee(&self, fee: SchedulePayment, order_id: String, driver: &str) {
+        let (invoice_id, agreement_id) = match &fee.title {
+            PaymentTitle::Invoice(invoice) => {
+                (invoice.invoice_id.clone(), invoice.agreement_id.clone())
+            }
+            PaymentTitle::DebitNote(_) => return,
+        };
+
+        let entry = FeeObj {
+            owner_id: fee.payer_id,
+            invoice_id: invoice_id.clone(),
+            agreement_id,
+            payment_platform: fee.payment_platform,
+            payer_addr: fee.payer_addr,
+            recipient: fee.payee_addr,
+            amount: fee.amount.into(),
+            driver: driver.to_string(),
+            order_id: None,
+            status: FeeStatus::Pending.to_string(),
+            error: None,
+            payment_order_id: Some(order_id),
+            allocation_id: Some(fee.allocation_id),
+        };
+        let result = match self.db_executor.timeout_lock(DB_LOCK_TIMEOUT).await {
+            Ok(db) => db
+                .as_dao::<FeeDao>()
+                .insert(entry)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            log::error!(
+                "Failed to record fee for Invoice [{}] in the ledger: {}",
+                invoice_id,
+                e
+            );
+        }
+    }
+
+    /// Schedules fees of confirmed payments. Fees, which fail, stay due and are retried
+    /// on the next call.
+    pub async fn schedule_due_fees(&self) {
+        if self.in_shutdown.load(Ordering::SeqCst) {
+            return;
+        }
+        let _guard = self.fee_scheduling.lock().await;
+
+        let fees = match self.db_executor.timeout_lock(DB_LOCK_TIMEOUT).await {
+            Ok(db) => db.as_dao::<FeeDao>().due().await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let fees = match fees {
+            Ok(fees) => fees,
+            Err(e) => {
+                log::error!("Failed to list due fees: {}", e);
+                return;
+            }
+        };
+
+        for fee in fees {
+            let result = match self.schedule_fee(&fee).await {
+                Ok(order_id) => {
+                    log::info!(
+                        "Fee of {} for Invoice [{}] scheduled to {}",
+                        fee.amount.0,
+                        fee.invoice_id,
+                        fee.recipient
+                    );
+                    match self.db_executor.timeout_lock(DB_LOCK_TIMEOUT).await {
+                        Ok(db) => db
+                            .as_dao::<FeeDao>()
+                            .scheduled(fee.id, order_id)
+                            .await
+                            .map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    }
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Failed to schedule fee for Invoice [{}], will retry: {}",
+                        fee.invoice_id,
+                        e
+                    );
+                    match self.db_executor.timeout_lock(DB_LOCK_TIMEOUT).await {
+                        Ok(db) => db
+                            .as_dao::<FeeDao>()
+                            .failed(fee.id, e.to_string())
+                            .await
+                            .map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    }
+                }
+            };
+            if let Err(e) = result {
+                log::error!("Failed to update fee in the ledger: {}", e);
+            }
+        }
+    }
+
+    async fn schedule_fee(&self, fee: &DbFee) -> Result<String, SchedulePaymentError> {
+        let payment = SchedulePayment {
+            title: PaymentTitle::Invoice(InvoicePayment {
+                invoice_id: fee.invoice_id.clone(),
+                agreement_id: fee.agreement_id.clone(),
+            }),
+            payer_id: fee.owner_id,
+            payee_id: Default::default(),
+            payer_addr: fee.payer_addr.clone(),
+            payee_addr: fee.recipient.clone(),
+            payment_platform: fee.payment_platform.clone(),
+            allocation_id: fee.allocation_id.clone().unwrap_or_default(),
+            amount: fee.amount.0.clone(),
+            due_date: Utc::now(),
+        };
+
+        let reservation_id = self.reserve_funds(&payment, false).await?;
+        let order_id = driver_endpoint(&fee.driver)
+            .send(driver::SchedulePayment::new(
+                payment.amount,
+                payment.payer_addr,
+                payment.payee_addr,
+                payment.payment_platform,
+                None,
+                payment.due_date,
+            ))
+            .await
+            .map_err(SchedulePaymentError::from)
+            .and_then(|result| result.map_err(SchedulePaymentError::from));
+
+        let mut reservations = self.reservations.lock().await;
+        match order_id {
+            Ok(order_id) => {
+                reservations.assign_order(&reservation_id, order_id.clone());
+                Ok(order_id)
+            }
+            Err(e) => {
+                reservations.release(&reservation_id);
+                Err(e)
+            }
+        }
+    }
+
+    /// Picks platform of the allocation basket to pay with. Stays on the platform of the
+    /// Agreement, unless another one is accepted by the Provider and able to pay.
+    /// Platforms are checked in order of preference until one is able to pay. Agreement
+    /// is fetched only when another platform than the one of the Agreement is checked.
+    async fn choose_platform(&self, msg: &SchedulePayment, allocation: &Allocation) -> String {
+        let fallbacks = match self.db_executor.timeout_lock(DB_LOCK_TIMEOUT).await {
+            Ok(db) => db
+                .as_dao::<AllocationDao>()
+                .get_fallback_platforms(vec![allocation.allocation_id.clone()])
+                .await
+                .map(|mut fallbacks| fallbacks.remove(&allocation.allocation_id))
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let fallbacks = match fallbacks {
+            Ok(Some(fallbacks)) => fallbacks,
+            Ok(None) => return msg.payment_platform.clone(),
+            Err(e) => {
+                log::warn!(
+                    "Unable to get fallback platforms of allocation [{}]: {}",
+                    allocation.allocation_id,
+                    e
+                );
+                return msg.payment_platform.clone();
+            }
+        };
+
+        let mut agreement = None;
+        let platforms = std::iter::once(allocation.payment_platform.clone()).chain(fallbacks);
+        for platform in platforms {
+            if platform != msg.payment_platform {
+                if !basket::same_token(&platform, &msg.payment_platform) {
+                    continue;
+                }
+                if agreement.is_none() {
+                    agreement = match agreement_of(msg).await {
+                        Ok(Some(agreement)) => Some(agreement),
+                        Ok(None) => return msg.payment_platform.clone(),
+                        Err(e) => {
+                            log::warn!("Unable to get Agreement for {}: {}", msg.document_id(), e);
+                            return msg.payment_platform.clone();
+                        }
+                    };
+                }
+                let accepted = agreement
+                    .as_ref()
+                    .map(|agreement| basket::offer_accepts(agreement, &platform, &msg.payee_addr));
+                if accepted != Some(true) {
+                    continue;
+                }
+            }
+
+            match self
+                .get_status(platform.clone(), msg.payer_addr.clone())
+                .await
+            {
+                Ok(status) => {
+                    let candidate = Candidate {
+                        spendable: status.token_balance
+                            - self.reserved(&platform, &msg.payer_addr).await,
+                        gas: status.gas_details.map(|gas| gas.balance),
+                        platform,
+                    };
+                    if candidate.can_pay(&msg.amount) {
+                        return candidate.platform;
+                    }
+                }
+                Err(e) => log::debug!("Skipping {} for {}: {}", platform, msg.document_id(), e),
+            }
+        }
+
+        msg.payment_platform.clone()
+    }
+
+    /// Payment can be relayed, if the driver supports gasless payments on the platform
+    /// and the Provider accepted them in the Agreement.
+    async fn gasless_accepted(&self, msg: &SchedulePayment, driver: &str) -> bool {
+        let supported = match self.registry.timeout_read(REGISTRY_LOCK_TIMEOUT).await {
+            Ok(registry) => registry
+                .get_drivers_capabilities()
+                .get(driver)
+                .map(|capabilities| {
+                    capabilities
+                        .gasless_platforms
+                        .contains(&msg.payment_platform)
+                })
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        if !supported {
+            return false;
+        }
+
+        match agreement_of(msg).await {
+            Ok(Some(agreement)) => gasless::offer_accepts(&agreement, &msg.payment_platform),
+            Ok(None) => false,
+            Err(e) => {
+                log::warn!("Unable to get Agreement for {}: {}", msg.document_id(), e);
+                false
+            }
+        }
+    }
+
+    /// Reserves funds for scheduled payment, so concurrently scheduled payments
+    /// can't exceed account balance. Payments from deposit aren't covered by the balance.
+    async fn reserve_funds(
+        &self,
+        msg: &SchedulePayment,
+        from_deposit: bool,
+    ) -> Result<String, SchedulePaymentError> {
+        let balance = if from_deposit {
+            None
+        } else {
+            match self
+                .get_status(msg.payment_platform.clone(), msg.payer_addr.clone())
+                .await
+            {
+                Ok(status) => Some(status.token_balance),
+                Err(e) => {
+                    log::warn!(
+                        "Unable to check balance of {} before scheduling payment: {}",
+                        msg.payer_addr,
+                        e
+                    );
+                    None
+                }
+            }
+        };
+
+        self.reservations
+            .lock()
+            .await
+            .try_reserve(msg, balance.as_ref())
+            .map_err(|available| SchedulePaymentError::InsufficientFunds {
+                required: msg.amount.clone(),
+                available,
+            })
+    }
+
+    /// Funds reserved by scheduled, but not yet confirmed payments.
+    pub async fn reserved(&self, platform: &str, address: &str) -> BigDecimal {
+        self.reservations.lock().await.reserved(platform, address)
+    }
+
+    pub async fn get_reservations(
+        &self,
+        platform: Option<String>,
+        address: Option<String>,
+    ) -> Vec<Reservation> {
+        self.reservations
+            .lock()
+            .await
+            .list(platform.as_deref(), address.as_deref())
+    }
+
+    /// Payment for the Agreement on another network of its token is accepted, when our
+    /// Offer advertised the platform.
+    async fn accepts_basket_payment(
+        agreement_id: &str,
+        agreement_platform: &str,
+        payment: &Payment,
+    ) -> bool {
+        if !basket::same_token(agreement_platform, &payment.payment_platform) {
+            return false;
+        }
+        match utils::get_agreement(agreement_id.to_string(), MarketRole::Provider).await {
+            Ok(Some(agreement)) => {
+                basket::offer_accepts(&agreement, &payment.payment_platform, &payment.payee_addr)
+            }
+            Ok(None) => false,
+            Err(e) => {
+                log::warn!("Unable to get Agreement [{}]: {}", agreement_id, e);
+                false
+            }
+        }
     }
 
     pub async fn verify_payment(
@@ -720,10 +1431,18 @@ impl PaymentProcessor {
                         return VerifyPaymentError::agreement_payer(&agreement, payer_addr);
                     }
                     Some(agreement) if agreement.payment_platform != payment.payment_platform => {
-                        return VerifyPaymentError::agreement_platform(
-                            &agreement,
-                            &payment.payment_platform,
-                        );
+                        if !Self::accepts_basket_payment(
+                            &agreement.id,
+                            &agreement.payment_platform,
+                            &payment,
+                        )
+                        .await
+                        {
+                            return VerifyPaymentError::agreement_platform(
+                                &agreement,
+                                &payment.payment_platform,
+                            );
+                        }
                     }
                     _ => (),
                 }
@@ -778,6 +1497,12 @@ impl PaymentProcessor {
                 return VerifyPaymentError::overspending(&details.amount, &all_payment_total);
             }
 
+            let agreement_ids = payment
+                .agreement_payments
+                .iter()
+                .map(|p| p.agreement_id.clone())
+                .collect::<Vec<_>>();
+
             // Insert payment into database (this operation creates and updates all related entities)
             if canonical.is_none() {
                 payment_dao
@@ -788,11 +1513,50 @@ impl PaymentProcessor {
                     .insert_received(payment, payee_id, Some(signature), canonical)
                     .await?;
             }
+
+            self.settle_within_tolerance(&agreement_dao, agreement_ids, payee_id, &platform)
+                .await;
         }
 
         Ok(())
     }
 
+    async fn settle_within_tolerance(
+        &self,
+        agreement_dao: &AgreementDao<'_>,
+        agreement_ids: Vec<String>,
+        payee_id: NodeId,
+        platform: &str,
+    ) {
+        let mut settlement = self.settlement.lock().await;
+        let tolerance = match settlement.tolerance() {
+            Some(tolerance) => tolerance.clone(),
+            None => return,
+        };
+
+        for agreement_id in agreement_ids {
+            match agreement_dao
+                .settle_within_tolerance(agreement_id.clone(), payee_id, tolerance.clone())
+                .await
+            {
+                Ok(Some((invoice_id, discrepancy))) => {
+                    log::info!(
+                        "Invoice [{}] settled within tolerance, {} missing from invoiced amount",
+                        invoice_id,
+                        discrepancy
+                    );
+                    settlement.record(platform, discrepancy);
+                }
+                Ok(None) => (),
+                Err(e) => log::warn!(
+                    "Failed to settle invoice of agreement [{}] within tolerance: {}",
+                    agreement_id,
+                    e
+                ),
+            }
+        }
+    }
+
     pub async fn get_status(
         &self,
         platform: String,
@@ -858,7 +1622,7 @@ impl PaymentProcessor {
             let dao = db.as_dao::<AllocationDao>();
 
             let active = dao
-                .get_for_address(platform.clone(), address.clone(), Some(false))
+                .get_reserving_funds(platform.clone(), address.clone())
                 .await?;
             let past = dao
                 .get_for_address(platform.clone(), address.clone(), Some(true))
@@ -999,3 +1763,205 @@ fn shut_down_driver(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use serde_json::json;
+    use std::sync::Mutex as StdMutex;
+
+    use crate::dao::InvoiceDao;
+    use ya_client_model::market::agreement::State;
+    use ya_client_model::market::{Agreement, Demand, Offer};
+    use ya_client_model::payment::{DocumentStatus, Invoice, NewAllocation};
+    use ya_core_model::driver::GenericError as DriverError;
+    use ya_core_model::market;
+    use ya_core_model::payment::local::InvoicePayment;
+
+    const DRIVER: &str = "basket-test";
+    const PRIMARY: &str = "erc20-primary-glm";
+    const FALLBACK: &str = "erc20-fallback-glm";
+    const PAYER: &str = "0x0000000000000000000000000000000000000001";
+    const PAYEE: &str = "0x0000000000000000000000000000000000000002";
+
+    /// Provider advertises the fallback platform of the allocation only.
+    fn agreement() -> Agreement {
+        let demand = Demand::new(
+            json!({ "golem.com.payment.chosen-platform": FALLBACK }),
+            "()".to_string(),
+            "demand_id".to_string(),
+            PAYER.parse().unwrap(),
+            Utc::now(),
+        );
+        let offer = Offer::new(
+            json!({
+                "golem.com.payment.protocol.version": basket::BASKET_PROTOCOL_VERSION,
+                "golem.com.payment.platform.erc20-fallback-glm.address": PAYEE,
+            }),
+            "()".to_string(),
+            "offer_id".to_string(),
+            PAYEE.parse().unwrap(),
+            Utc::now(),
+        );
+        Agreement::new(
+            "agreement_id".to_string(),
+            demand,
+            offer,
+            Utc::now(),
+            State::Approved,
+            Utc::now(),
+        )
+    }
+
+    /// Binds driver with funds on both platforms and returns platforms of payments
+    /// it scheduled.
+    fn bind_driver() -> Arc<StdMutex<Vec<String>>> {
+        let bus_id = driver_bus_id(DRIVER);
+        let _ = bus::bind(&bus_id, |_: driver::GetAccountBalance| {
+            future::ok::<_, DriverError>(GetAccountBalanceResult {
+                gas_details: None,
+                token_balance: BigDecimal::from(100),
+                block_number: 0,
+                block_datetime: Utc::now(),
+            })
+        });
+
+        let scheduled = Arc::new(StdMutex::new(Vec::new()));
+        let platforms = scheduled.clone();
+        let _ = bus::bind(&bus_id, move |msg: driver::SchedulePayment| {
+            platforms.lock().unwrap().push(msg.platform());
+            future::ok::<_, DriverError>("order_id".to_string())
+        });
+        let _ = bus::bind(market::BUS_ID, |_: market::GetAgreement| {
+            future::ok::<_, market::RpcMessageError>(agreement())
+        });
+        scheduled
+    }
+
+    async fn register_driver(processor: &PaymentProcessor) {
+        let network = |name: &str, platform: &str| {
+            let network = Network {
+                default_token: "GLM".to_string(),
+                tokens: std::iter::once(("GLM".to_string(), platform.to_string())).collect(),
+            };
+            (name.to_string(), network)
+        };
+        processor
+            .register_driver(RegisterDriver {
+                driver_name: DRIVER.to_string(),
+                details: DriverDetails {
+                    default_network: "primary".to_string(),
+                    networks: vec![network("primary", PRIMARY), network("fallback", FALLBACK)]
+                        .into_iter()
+                        .collect(),
+                    recv_init_required: false,
+                },
+                capabilities: Default::default(),
+            })
+            .await
+            .unwrap();
+        for network in ["primary", "fallback"] {
+            processor
+                .register_account(RegisterAccount {
+                    address: PAYER.to_string(),
+                    driver: DRIVER.to_string(),
+                    network: network.to_string(),
+                    token: "GLM".to_string(),
+                    mode: AccountMode::SEND,
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_pay_on_platform_accepted_by_offer() {
+        let db = DbExecutor::in_memory("processor_basket").unwrap();
+        db.apply_migration(crate::migrations::run_with_output)
+            .unwrap();
+        let (payer_id, payee_id): (NodeId, NodeId) =
+            (PAYER.parse().unwrap(), PAYEE.parse().unwrap());
+
+        db.as_dao::<AgreementDao>()
+            .create_if_not_exists(agreement(), payer_id, Role::Requestor)
+            .await
+            .unwrap();
+        let invoice_dao = db.as_dao::<InvoiceDao>();
+        invoice_dao
+            .insert_received(Invoice {
+                invoice_id: "invoice_id".to_string(),
+                issuer_id: payee_id,
+                recipient_id: payer_id,
+                payee_addr: PAYEE.to_string(),
+                payer_addr: PAYER.to_string(),
+                payment_platform: FALLBACK.to_string(),
+                timestamp: Utc::now(),
+                agreement_id: "agreement_id".to_string(),
+                activity_ids: vec![],
+                amount: BigDecimal::from(10),
+                payment_due_date: Utc::now(),
+                status: DocumentStatus::Received,
+            })
+            .await
+            .unwrap();
+        invoice_dao
+            .accept("invoice_id".to_string(), payer_id)
+            .await
+            .unwrap();
+        let allocation_id = db
+            .as_dao::<AllocationDao>()
+            .create(
+                NewAllocation {
+                    address: Some(PAYER.to_string()),
+                    payment_platform: None,
+                    total_amount: BigDecimal::from(50),
+                    timeout: None,
+                    make_deposit: false,
+                    deposit: None,
+                    extend_timeout: None,
+                },
+                payer_id,
+                PRIMARY.to_string(),
+                PAYER.to_string(),
+                vec![(FALLBACK.to_string(), BigDecimal::from(10))],
+            )
+            .await
+            .unwrap();
+
+        let scheduled = bind_driver();
+        let processor = PaymentProcessor::new(db.clone());
+        register_driver(&processor).await;
+
+        processor
+            .schedule_payment(SchedulePayment {
+                title: PaymentTitle::Invoice(InvoicePayment {
+                    invoice_id: "invoice_id".to_string(),
+                    agreement_id: "agreement_id".to_string(),
+                }),
+                payer_id,
+                payee_id,
+                payer_addr: PAYER.to_string(),
+                payee_addr: PAYEE.to_string(),
+                payment_platform: FALLBACK.to_string(),
+                allocation_id,
+                amount: BigDecimal::from(10),
+                due_date: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        // Primary platform is preferred and funded, but the Provider doesn't accept it.
+        assert_eq!(*scheduled.lock().unwrap(), vec![FALLBACK.to_string()]);
+
+        // Payment is taken from the share of the fallback platform.
+        let dao = db.as_dao::<AllocationDao>();
+        for (platform, reserved) in [(FALLBACK, 0), (PRIMARY, 40)] {
+            let allocations = dao
+                .get_reserving_funds(platform.to_string(), PAYER.to_string())
+                .await
+                .unwrap();
+            assert_eq!(allocations[0].remaining_amount, BigDecimal::from(reserved));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Signed payment receipts.
+//!
+//! After a payment is sent, Requestor signs a receipt with its identity key and delivers
+//! it to the Provider, who stores it. Receipt contains the payment, hash of the transaction
+//! realizing it and the issue date, so Provider holds a portable proof of payment, which
+//! can be verified without access to any of the nodes.
+use anyhow::{anyhow, bail};
+use chrono::Utc;
+use std::convert::TryInto;
+
+use ya_client_model::payment::Payment;
+use ya_core_model::identity;
+use ya_core_model::payment::public::{PaymentReceipt, SendPaymentReceipt};
+use ya_core_model::signable::{prepare_signature_hash, Signable};
+use ya_service_bus::typed as bus;
+
+/// Creates receipt for a payment sent by this node and signs it with payer identity.
+pub async fn issue(payment: Payment) -> anyhow::Result<SendPaymentReceipt> {
+    let confirmation =
+        base64::decode(&payment.details).map_err(|e| anyhow!("Invalid payment details: {}", e))?;
+    let receipt = PaymentReceipt {
+        tx_hash: format!("0x{}", hex::encode(confirmation)),
+        issued_at: Utc::now(),
+        payment: payment.remove_private_info(),
+    };
+
+    let payer_id = receipt.payment.payer_id;
+    let signature = bus::service(identity::BUS_ID)
+        .send(identity::Sign {
+            node_id: payer_id,
+            payload: receipt.hash_canonical()?,
+        })
+        .await??;
+    Ok(SendPaymentReceipt::new(receipt, signature))
+}
+
+/// Checks, that signed bytes represent the receipt and were signed by the payer.
+pub fn verify(msg: &SendPaymentReceipt) -> anyhow::Result<()> {
+    msg.receipt.verify_canonical(&msg.signed_bytes)?;
+
+    if msg.signature.len() != 65 {
+        bail!("Invalid signature length: {}", msg.signature.len());
+    }
+    let signature = ethsign::Signature {
+        v: msg.signature[0],
+        r: msg.signature[1..33].try_into()?,
+        s: msg.signature[33..65].try_into()?,
+    };
+    let signer = signature
+        .recover(&prepare_signature_hash(&msg.signed_bytes))
+        .map_err(|e| anyhow!("Can't recover signer: {}", e))?;
+
+    if signer.address() != &msg.receipt.payment.payer_id.into_array() {
+        bail!("Receipt isn't signed by the payer");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use ethsign::SecretKey;
+    use ya_client_model::NodeId;
+
+    fn signed_receipt(secret: &SecretKey) -> SendPaymentReceipt {
+        let payer_id = NodeId::from(secret.public().address().as_ref());
+        let receipt = PaymentReceipt {
+            payment: Payment {
+                payment_id: "payment".to_string(),
+                payer_id,
+                payee_id: NodeId::from([0xca; 20].as_ref()),
+                payer_addr: payer_id.to_string(),
+                payee_addr: "0xcacacacacacacacacacacacacacacacacacacaca".to_string(),
+                payment_platform: "erc20-holesky-tglm".to_string(),
+                amount: BigDecimal::from(10),
+                timestamp: Utc::now(),
+                agreement_payments: vec![],
+                activity_payments: vec![],
+                details: base64::encode([0xab; 32]),
+            },
+            tx_hash: format!("0x{}", hex::encode([0xab; 32])),
+            issued_at: Utc::now(),
+        };
+        let signature = secret.sign(&receipt.hash_canonical().unwrap()).unwrap();
+        let mut bytes = vec![signature.v];
+        bytes.extend_from_slice(&signature.r);
+        bytes.extend_from_slice(&signature.s);
+        SendPaymentReceipt::new(receipt, bytes)
+    }
+
+    #[test]
+    fn verify_receipt() {
+        let secret = SecretKey::from_raw(&[0x42; 32]).unwrap();
+        let msg = signed_receipt(&secret);
+        verify(&msg).unwrap();
+
+        let mut tampered = msg.clone();
+        tampered.receipt.payment.amount = BigDecimal::from(100);
+        assert!(verify(&tampered).is_err());
+
+        let forged = SendPaymentReceipt {
+            receipt: signed_receipt(&SecretKey::from_raw(&[0x43; 32]).unwrap()).receipt,
+            ..signed_receipt(&secret)
+        };
+        assert!(verify(&forged).is_err());
+    }
+}
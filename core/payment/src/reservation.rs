@@ -0,0 +1,130 @@
+//! Ledger of funds reserved by scheduled payments.
+//!
+//! Payment is reserved when it's scheduled and released when the driver refuses it,
+//! or when it gets confirmed on chain. Reservations are not persisted, so they
+//! only guard payments scheduled since the service start.
+use bigdecimal::{BigDecimal, Zero};
+use chrono::Utc;
+use std::collections::HashMap;
+
+use ya_core_model::payment::local::{Reservation, SchedulePayment};
+
+#[derive(Default)]
+pub struct ReservationLedger {
+    reservations: HashMap<String, Reservation>,
+}
+
+impl ReservationLedger {
+    /// Total amount reserved for `payer_addr` on `platform`.
+    pub fn reserved(&self, platform: &str, payer_addr: &str) -> BigDecimal {
+        self.reservations
+            .values()
+            .filter(|r| r.platform == platform && r.payer_addr == payer_addr)
+            .map(|r| &r.amount)
+            .sum()
+    }
+
+    /// Reserves funds for the payment, unless `balance` is too low to cover it
+    /// together with already reserved payments. Returns reservation id or
+    /// the amount still available.
+    pub fn try_reserve(
+        &mut self,
+        msg: &SchedulePayment,
+        balance: Option<&BigDecimal>,
+    ) -> Result<String, BigDecimal> {
+        if let Some(balance) = balance {
+            let available = balance - self.reserved(&msg.payment_platform, &msg.payer_addr);
+            if available < msg.amount {
+                return Err(available.max(BigDecimal::zero()));
+            }
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        self.reservations.insert(
+            id.clone(),
+            Reservation {
+                order_id: None,
+                platform: msg.payment_platform.clone(),
+                payer_addr: msg.payer_addr.clone(),
+                payee_addr: msg.payee_addr.clone(),
+                allocation_id: msg.allocation_id.clone(),
+                amount: msg.amount.clone(),
+                timestamp: Utc::now(),
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn assign_order(&mut self, id: &str, order_id: String) {
+        if let Some(reservation) = self.reservations.get_mut(id) {
+            reservation.order_id = Some(order_id);
+        }
+    }
+
+    pub fn release(&mut self, id: &str) {
+        self.reservations.remove(id);
+    }
+
+    /// Releases reservations of orders, which were paid.
+    pub fn release_orders(&mut self, order_ids: &[String]) {
+        self.reservations.retain(|_, r| match &r.order_id {
+            Some(order_id) => !order_ids.contains(order_id),
+            None => true,
+        });
+    }
+
+    pub fn list(&self, platform: Option<&str>, payer_addr: Option<&str>) -> Vec<Reservation> {
+        let mut reservations = self
+            .reservations
+            .values()
+            .filter(|r| platform.map(|p| r.platform == p).unwrap_or(true))
+            .filter(|r| payer_addr.map(|a| r.payer_addr == a).unwrap_or(true))
+            .cloned()
+            .collect::<Vec<_>>();
+        reservations.sort_by_key(|r| r.timestamp);
+        reservations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ya_core_model::payment::local::{InvoicePayment, PaymentTitle};
+
+    fn payment(amount: u32) -> SchedulePayment {
+        SchedulePayment {
+            title: PaymentTitle::Invoice(InvoicePayment {
+                invoice_id: "invoice".to_string(),
+                agreement_id: "agreement".to_string(),
+            }),
+            payer_id: Default::default(),
+            payee_id: Default::default(),
+            payer_addr: "0xpayer".to_string(),
+            payee_addr: "0xpayee".to_string(),
+            payment_platform: "erc20-holesky-tglm".to_string(),
+            allocation_id: "allocation".to_string(),
+            amount: BigDecimal::from(amount),
+            due_date: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn reservations_do_not_exceed_balance() {
+        let mut ledger = ReservationLedger::default();
+        let balance = BigDecimal::from(10);
+
+        let first = ledger.try_reserve(&payment(6), Some(&balance)).unwrap();
+        assert_eq!(
+            ledger.try_reserve(&payment(6), Some(&balance)),
+            Err(BigDecimal::from(4))
+        );
+        assert_eq!(
+            ledger.reserved("erc20-holesky-tglm", "0xpayer"),
+            BigDecimal::from(6)
+        );
+
+        ledger.assign_order(&first, "order".to_string());
+        ledger.release_orders(&["order".to_string()]);
+        assert!(ledger.try_reserve(&payment(6), Some(&balance)).is_ok());
+    }
+}
@@ -65,6 +65,15 @@ table! {
     }
 }
 
+table! {
+    pay_allocation_platform (allocation_id, platform) {
+        allocation_id -> Text,
+        platform -> Text,
+        priority -> Integer,
+        amount -> Text,
+    }
+}
+
 table! {
     pay_debit_note (id, owner_id) {
         id -> Text,
@@ -116,6 +125,50 @@ table! {
     }
 }
 
+table! {
+    pay_fee (id) {
+        id -> Integer,
+        owner_id -> Text,
+        invoice_id -> Text,
+        agreement_id -> Text,
+        payment_platform -> Text,
+        payer_addr -> Text,
+        recipient -> Text,
+        amount -> Text,
+        driver -> Text,
+        order_id -> Nullable<Text>,
+        status -> Text,
+        tx_id -> Nullable<Text>,
+        error -> Nullable<Text>,
+        timestamp -> Timestamp,
+        payment_order_id -> Nullable<Text>,
+        allocation_id -> Nullable<Text>,
+    }
+}
+
+table! {
+    pay_fiat_annotation (owner_id, entity_type, entity_id) {
+        owner_id -> Text,
+        entity_type -> Text,
+        entity_id -> Text,
+        platform -> Text,
+        amount -> Text,
+        currency -> Text,
+        rate -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    pay_idempotency_key (owner_id, peer_id, key) {
+        key -> Text,
+        owner_id -> Text,
+        peer_id -> Text,
+        payment_id -> Text,
+        created_ts -> Timestamp,
+    }
+}
+
 table! {
     pay_invoice (id, owner_id) {
         id -> Text,
@@ -161,6 +214,29 @@ table! {
     }
 }
 
+table! {
+    pay_journal_entry (owner_id, source) {
+        owner_id -> Text,
+        source -> Text,
+        timestamp -> Timestamp,
+        debit_account -> Text,
+        credit_account -> Text,
+        currency -> Text,
+        amount -> Text,
+        memo -> Nullable<Text>,
+        period_id -> Nullable<Integer>,
+    }
+}
+
+table! {
+    pay_journal_period (id) {
+        id -> Integer,
+        owner_id -> Text,
+        closed_until -> Timestamp,
+        closed_ts -> Timestamp,
+    }
+}
+
 table! {
     pay_order (id, driver) {
         id -> Text,
@@ -196,6 +272,37 @@ table! {
     }
 }
 
+table! {
+    pay_payment_receipt (payment_id, owner_id) {
+        payment_id -> Text,
+        owner_id -> Text,
+        tx_hash -> Text,
+        issued_at -> Timestamp,
+        signature -> Binary,
+        signed_bytes -> Binary,
+    }
+}
+
+table! {
+    pay_notify_payment (driver, details, order_ids) {
+        driver -> Text,
+        details -> Binary,
+        order_ids -> Text,
+        notification -> Text,
+        payment_id -> Nullable<Text>,
+        created_ts -> Timestamp,
+    }
+}
+
+table! {
+    pay_tx_gas_cost (owner_id, details) {
+        owner_id -> Text,
+        details -> Binary,
+        platform -> Text,
+        gas_cost -> Text,
+    }
+}
+
 table! {
     pay_sync_needed_notifs (id) {
         id -> Text,
@@ -205,7 +312,25 @@ table! {
 }
 
 joinable!(pay_activity_payment -> pay_allocation (allocation_id));
+table! {
+    pay_withdrawal (id) {
+        id -> Integer,
+        sender -> Text,
+        recipient -> Text,
+        driver -> Text,
+        network -> Text,
+        token -> Text,
+        amount -> Text,
+        max_fee -> Text,
+        status -> Text,
+        tx_id -> Nullable<Text>,
+        error -> Nullable<Text>,
+        timestamp -> Timestamp,
+    }
+}
+
 joinable!(pay_agreement_payment -> pay_allocation (allocation_id));
+joinable!(pay_allocation_platform -> pay_allocation (allocation_id));
 joinable!(pay_debit_note -> pay_document_status (status));
 joinable!(pay_debit_note_event -> pay_event_type (event_type));
 joinable!(pay_invoice -> pay_document_status (status));
@@ -218,15 +343,25 @@ allow_tables_to_appear_in_same_query!(
     pay_agreement,
     pay_agreement_payment,
     pay_allocation,
+    pay_allocation_platform,
     pay_debit_note,
     pay_debit_note_event,
     pay_debit_note_event_read,
     pay_document_status,
     pay_event_type,
+    pay_fee,
+    pay_fiat_annotation,
+    pay_idempotency_key,
     pay_invoice,
     pay_invoice_event,
     pay_invoice_event_read,
     pay_invoice_x_activity,
+    pay_journal_entry,
+    pay_journal_period,
+    pay_notify_payment,
     pay_order,
     pay_payment,
+    pay_payment_receipt,
+    pay_tx_gas_cost,
+    pay_withdrawal,
 );
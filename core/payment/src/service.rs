@@ -1,4 +1,5 @@
 use crate::dao::{DebitNoteDao, InvoiceDao};
+use crate::fiat::FiatEntity;
 use crate::processor::PaymentProcessor;
 use crate::Config;
 
@@ -7,6 +8,7 @@ use metrics::counter;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use ya_core_model::events::EventPayload;
 use ya_core_model::payment::local::{GenericError, BUS_ID as PAYMENT_BUS_ID};
 use ya_core_model::payment::public::{AcceptDebitNote, AcceptInvoice, PaymentSync, SendPayment};
 
@@ -34,7 +36,7 @@ mod local {
 
     use ya_client_model::{
         payment::{
-            Account, DebitNoteEventType, DocumentStatus, DriverDetails, DriverStatusProperty,
+            DebitNoteEventType, DocumentStatus, DriverDetails, DriverStatusProperty,
             InvoiceEventType,
         },
         NodeId,
@@ -43,6 +45,7 @@ mod local {
     use ya_core_model::payment::public::Ack;
     use ya_core_model::{
         driver::{driver_bus_id, DriverStatus, DriverStatusError},
+        identity,
         payment::local::*,
     };
     use ya_persistence::types::Role;
@@ -59,11 +62,17 @@ mod local {
             .bind_with_processor(notify_payment)
             .bind_with_processor(get_rpc_endpoints)
             .bind_with_processor(get_status)
+            .bind_with_processor(get_reservations)
+            .bind(get_fiat_annotations)
+            .bind(get_agreement_costs)
+            .bind(cold_sync)
+            .bind_with_processor(get_settlement_stats)
             .bind_with_processor(get_invoice_stats)
             .bind_with_processor(get_accounts)
             .bind_with_processor(validate_allocation)
             .bind_with_processor(release_allocations)
             .bind_with_processor(get_drivers)
+            .bind_with_processor(get_drivers_capabilities)
             .bind_with_processor(payment_driver_status)
             .bind_with_processor(handle_status_change)
             .bind_with_processor(release_deposit)
@@ -155,6 +164,10 @@ mod local {
             driver,
             "Register driver finished"
         );
+        if res.is_ok() {
+            // Notifications interrupted by a crash can be processed once driver is available.
+            tokio::task::spawn_local(async move { processor.recover_notifications(driver).await });
+        }
         res
     }
 
@@ -230,16 +243,57 @@ mod local {
         Ok(())
     }
 
+    /// Identity the call was made on behalf of. Calls without one (e.g. from CLI) come
+    /// from the node operator, who sees data of all identities.
+    fn caller_identity(caller: &str) -> Option<NodeId> {
+        NodeId::from_str(caller).ok()
+    }
+
+    /// Identities sharing the node see only their own accounts.
+    fn check_owner(caller: &str, address: &str) -> Result<(), GenericError> {
+        match caller_identity(caller) {
+            Some(caller) if NodeId::from_str(address).ok() != Some(caller) => Err(
+                GenericError::new(format!("Account {} isn't owned by {}", address, caller)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
     async fn get_accounts(
         db: DbExecutor,
         processor: Arc<PaymentProcessor>,
         sender: String,
         msg: GetAccounts,
-    ) -> Result<Vec<Account>, GetAccountsError> {
+    ) -> Result<Vec<OwnedAccount>, GetAccountsError> {
         trace!(entity = "accounts", action = "get", "Get accounts started");
-        let res = processor.get_accounts().await;
+        let accounts = processor.get_accounts().await?;
+        let identities = service(identity::BUS_ID)
+            .call(identity::List {})
+            .await
+            .map_err(|e| GetAccountsError::Identity(e.to_string()))?
+            .map_err(|e| GetAccountsError::Identity(e.to_string()))?;
+
+        let res = accounts
+            .into_iter()
+            .map(|account| {
+                let info = NodeId::from_str(&account.address)
+                    .ok()
+                    .and_then(|address| identities.iter().find(|id| id.node_id == address));
+                OwnedAccount {
+                    owner: info.map(|id| id.node_id),
+                    alias: info.and_then(|id| id.alias.clone()),
+                    is_default: info.map(|id| id.is_default).unwrap_or(false),
+                    account,
+                }
+            })
+            .filter(|account| msg.owner.is_none() || account.owner == msg.owner)
+            .filter(|account| match caller_identity(&sender) {
+                Some(caller) => account.owner == Some(caller),
+                None => true,
+            })
+            .collect();
         trace!(entity = "accounts", action = "get", "Get accounts finished");
-        res
+        Ok(res)
     }
 
     async fn notify_payment(
@@ -330,7 +384,7 @@ mod local {
     async fn get_status(
         db: DbExecutor,
         processor: Arc<PaymentProcessor>,
-        _caller: String,
+        caller: String,
         msg: GetStatus,
     ) -> Result<StatusResult, GenericError> {
         let GetStatus {
@@ -340,6 +394,7 @@ mod local {
             token,
             after_timestamp,
         } = msg;
+        check_owner(&caller, &address)?;
 
         let (network, network_details) = processor
             .get_network(driver.clone(), network)
@@ -389,10 +444,12 @@ mod local {
 
         let (incoming, outgoing, status, reserved) =
             future::try_join4(incoming_fut, outgoing_fut, amount_fut, reserved_fut).await?;
+        let scheduled = processor.reserved(&platform, &address).await;
+        let fiat = processor.fiat().cached_rate(&platform);
 
         Ok(StatusResult {
             amount: status.token_balance,
-            reserved,
+            reserved: reserved + scheduled,
             outgoing,
             incoming,
             driver,
@@ -401,15 +458,75 @@ mod local {
             gas: status.gas_details,
             block_number: 0,
             block_datetime: Default::default(),
+            fiat,
         })
     }
 
-    async fn get_invoice_stats(
+    async fn get_reservations(
+        db: DbExecutor,
+        processor: Arc<PaymentProcessor>,
+        _caller: String,
+        msg: GetReservations,
+    ) -> Result<Vec<Reservation>, GenericError> {
+        Ok(processor.get_reservations(msg.platform, msg.address).await)
+    }
+
+    async fn get_settlement_stats(
         db: DbExecutor,
         processor: Arc<PaymentProcessor>,
         _caller: String,
+        msg: GetSettlementStats,
+    ) -> Result<SettlementStats, GenericError> {
+        Ok(processor.settlement_stats().await)
+    }
+
+    async fn get_fiat_annotations(
+        db: DbExecutor,
+        _caller: String,
+        msg: GetFiatAnnotations,
+    ) -> Result<Vec<FiatAnnotation>, GenericError> {
+        let annotations = db
+            .as_dao::<FiatAnnotationDao>()
+            .list(msg.owner_id, msg.since.map(|since| since.naive_utc()))
+            .await
+            .map_err(GenericError::new)?;
+        Ok(annotations.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_agreement_costs(
+        db: DbExecutor,
+        _caller: String,
+        msg: GetAgreementCosts,
+    ) -> Result<Vec<AgreementCost>, GenericError> {
+        let dao = db.as_dao::<GasCostDao>();
+        let parts = dao
+            .paid_parts(msg.owner_id, msg.since.map(|since| since.naive_utc()))
+            .await
+            .map_err(GenericError::new)?;
+        let fees = dao.fees(msg.owner_id).await.map_err(GenericError::new)?;
+
+        let mut costs = crate::gas::agreement_costs(parts, &fees);
+        if !msg.agreement_ids.is_empty() {
+            costs.retain(|cost| msg.agreement_ids.contains(&cost.agreement_id));
+        }
+        Ok(costs)
+    }
+
+    async fn cold_sync(
+        db: DbExecutor,
+        _caller: String,
+        msg: ColdSync,
+    ) -> Result<ColdSyncResult, GenericError> {
+        Ok(crate::payment_sync::cold_sync(&db, msg.node_id, msg.peers, msg.since).await)
+    }
+
+    async fn get_invoice_stats(
+        db: DbExecutor,
+        processor: Arc<PaymentProcessor>,
+        caller: String,
         msg: GetInvoiceStats,
     ) -> Result<InvoiceStats, GenericError> {
+        check_owner(&caller, &msg.node_id.to_string())?;
         let stats: BTreeMap<(Role, DocumentStatus), StatValue> = async {
             db.as_dao::<InvoiceDao>()
                 .last_invoice_stats(msg.node_id, msg.since)
@@ -417,8 +534,39 @@ mod local {
         }
         .map_err(GenericError::new)
         .await?;
-        let mut output_stats = InvoiceStats::default();
+        let (requestor, provider) = split_invoice_stats(&stats, &msg);
+        let mut output_stats = InvoiceStats {
+            requestor,
+            provider,
+            ..Default::default()
+        };
 
+        if let Some(interval) = msg.interval {
+            let series = db
+                .as_dao::<InvoiceDao>()
+                .invoice_stats_series(msg.node_id, msg.since, interval)
+                .await
+                .map_err(GenericError::new)?;
+            output_stats.series = series
+                .into_iter()
+                .map(|(start, stats)| {
+                    let (requestor, provider) = split_invoice_stats(&stats, &msg);
+                    InvoiceStatsBucket {
+                        start,
+                        requestor,
+                        provider,
+                    }
+                })
+                .collect();
+        }
+        Ok(output_stats)
+    }
+
+    /// Aggregates stats into requestor and provider notes, as requested by `msg`.
+    fn split_invoice_stats(
+        stats: &BTreeMap<(Role, DocumentStatus), StatValue>,
+        msg: &GetInvoiceStats,
+    ) -> (InvoiceStatusNotes, InvoiceStatusNotes) {
         fn aggregate(
             iter: impl Iterator<Item = (DocumentStatus, StatValue)>,
         ) -> InvoiceStatusNotes {
@@ -437,23 +585,26 @@ mod local {
             notes
         }
 
-        if msg.provider {
-            output_stats.provider = aggregate(
+        let notes = |expected: Role| {
+            aggregate(
                 stats
                     .iter()
-                    .filter(|((role, _), _)| matches!(role, Role::Provider))
+                    .filter(|((role, _), _)| *role == expected)
                     .map(|((_, status), value)| (*status, value.clone())),
-            );
-        }
-        if msg.requestor {
-            output_stats.requestor = aggregate(
-                stats
-                    .iter()
-                    .filter(|((role, _), _)| matches!(role, Role::Requestor))
-                    .map(|((_, status), value)| (*status, value.clone())),
-            );
-        }
-        Ok(output_stats)
+            )
+        };
+
+        let requestor = if msg.requestor {
+            notes(Role::Requestor)
+        } else {
+            Default::default()
+        };
+        let provider = if msg.provider {
+            notes(Role::Provider)
+        } else {
+            Default::default()
+        };
+        (requestor, provider)
     }
 
     async fn validate_allocation(
@@ -495,6 +646,15 @@ mod local {
         processor.get_drivers().await
     }
 
+    async fn get_drivers_capabilities(
+        db: DbExecutor,
+        processor: Arc<PaymentProcessor>,
+        _caller: String,
+        msg: GetDriversCapabilities,
+    ) -> Result<HashMap<String, DriverCapabilities>, GetDriversError> {
+        processor.get_drivers_capabilities().await
+    }
+
     async fn payment_driver_status(
         db: DbExecutor,
         processor: Arc<PaymentProcessor>,
@@ -519,11 +679,14 @@ mod local {
 
         let mut status_props = Vec::new();
         for driver in drivers {
-            let result = match service(driver_bus_id(&driver))
-                .call(DriverStatus {
+            let endpoint = service(driver_bus_id(&driver));
+            let result = match ya_net::hedge::send_hedged(
+                &endpoint,
+                DriverStatus {
                     network: msg.network.clone(),
-                })
-                .await
+                },
+            )
+            .await
             {
                 Ok(result) => result,
                 Err(e) => return Err(PaymentDriverStatusError::NoDriver(driver)),
@@ -796,7 +959,10 @@ mod public {
 
     use crate::error::processor::VerifyPaymentError;
     use crate::error::DbError;
-    use crate::payment_sync::{send_sync_notifs_job, send_sync_requests};
+    use crate::models::payment_receipt::PaymentReceipt as DbPaymentReceipt;
+    use crate::payment_sync::{
+        payment_sync_summary, purge_idempotency_keys_job, send_sync_notifs_job, send_sync_requests,
+    };
     use crate::utils::*;
     use crate::{dao::*, payment_sync::SYNC_NOTIFS_NOTIFY};
 
@@ -814,16 +980,20 @@ mod public {
             .bind(accept_debit_note)
             .bind(reject_debit_note)
             .bind(cancel_debit_note)
-            .bind(send_invoice)
+            .bind_with_processor(send_invoice)
             .bind(accept_invoice)
             .bind(reject_invoice)
             .bind(cancel_invoice)
             .bind(sync_request)
+            .bind(sync_summary)
             .bind_with_processor(send_payment)
             .bind_with_processor(send_payment_with_bytes)
+            .bind(send_payment_receipt)
             .bind_with_processor(sync_payment)
             .bind_with_processor(sync_payment_with_bytes);
 
+        purge_idempotency_keys_job(db.clone(), config.clone());
+
         if config.sync_notif_backoff.run_sync_job {
             send_sync_notifs_job(db.clone(), config);
             send_sync_requests(db.clone());
@@ -891,6 +1061,13 @@ mod public {
                 "DebitNote [{debit_note_id}] for Activity [{activity_id}] received from node [{issuer_id}]."
             );
             counter!("payment.debit_notes.requestor.received", 1);
+            ya_events::publish(
+                Some(node_id),
+                EventPayload::DebitNoteReceived {
+                    debit_note_id,
+                    activity_id,
+                },
+            );
             Ok(())
         }
         .await
@@ -951,6 +1128,10 @@ mod public {
             Ok(_) => {
                 log::info!("Node [{sender_id}] accepted DebitNote [{debit_note_id}].");
                 counter!("payment.debit_notes.provider.accepted", 1);
+                ya_events::publish(
+                    Some(node_id),
+                    EventPayload::DebitNoteAccepted { debit_note_id },
+                );
                 Ok(Ack {})
             }
             Err(DbError::Query(e)) => Err(AcceptRejectError::BadRequest(e)),
@@ -978,6 +1159,7 @@ mod public {
 
     async fn send_invoice(
         db: DbExecutor,
+        processor: Arc<PaymentProcessor>,
         sender_id: String,
         msg: SendInvoice,
     ) -> Result<Ack, SendError> {
@@ -985,6 +1167,8 @@ mod public {
         let invoice_id = invoice.invoice_id.clone();
         let agreement_id = invoice.agreement_id.clone();
         let activity_ids = invoice.activity_ids.clone();
+        let platform = invoice.payment_platform.clone();
+        let amount = invoice.amount.clone();
 
         log::debug!(
             "Got SendInvoice [{}] from Node [{}].",
@@ -1060,11 +1244,26 @@ mod public {
             }
 
             db.as_dao::<InvoiceDao>().insert_received(invoice).await?;
+            processor.fiat().stamp(
+                db.clone(),
+                owner_id,
+                FiatEntity::Invoice,
+                invoice_id.clone(),
+                platform,
+                amount,
+            );
 
             log::info!(
                 "Invoice [{invoice_id}] for Agreement [{agreement_id}] received from node [{sender_id}]."
             );
             counter!("payment.invoices.requestor.received", 1);
+            ya_events::publish(
+                Some(owner_id),
+                EventPayload::InvoiceReceived {
+                    invoice_id,
+                    agreement_id,
+                },
+            );
             Ok(())
         }
         .await
@@ -1130,6 +1329,13 @@ mod public {
                     invoice.agreement_id
                 );
                 counter!("payment.invoices.provider.accepted", 1);
+                ya_events::publish(
+                    Some(owner_id),
+                    EventPayload::InvoiceAccepted {
+                        invoice_id,
+                        agreement_id: invoice.agreement_id,
+                    },
+                );
                 Ok(Ack {})
             }
             Err(DbError::Query(e)) => Err(AcceptRejectError::BadRequest(e)),
@@ -1248,7 +1454,16 @@ mod public {
         sender_id: String,
         msg: SendPayment,
     ) -> Result<Ack, SendError> {
-        send_payment_impl(db, processor, sender_id, msg.payment, msg.signature, None).await
+        send_payment_impl(
+            db,
+            processor,
+            sender_id,
+            msg.payment,
+            msg.signature,
+            None,
+            msg.idempotency_key,
+        )
+        .await
     }
 
     async fn send_payment_with_bytes(
@@ -1264,6 +1479,7 @@ mod public {
             msg.payment,
             msg.signature,
             Some(msg.signed_bytes),
+            msg.idempotency_key,
         )
         .await
     }
@@ -1275,14 +1491,30 @@ mod public {
         payment: Payment,
         signature: Vec<u8>,
         canonical: Option<Vec<u8>>,
+        idempotency_key: Option<String>,
     ) -> Result<Ack, SendError> {
         let payment_id = payment.payment_id.clone();
         if sender_id != payment.payer_id.to_string() {
             return Err(SendError::BadRequest("Invalid payer ID".to_owned()));
         }
 
+        let payee_id = payment.payee_id;
+        let payer_id = payment.payer_id;
+        if let Some(key) = &idempotency_key {
+            if is_delivered(&db, key, &payment_id, payee_id, payer_id).await? {
+                debug!(
+                    entity = "payment",
+                    action = "verify",
+                    payment_id,
+                    "Payment already processed, skipping redelivered message."
+                );
+                return Ok(Ack {});
+            }
+        }
+
         let platform = payment.payment_platform.clone();
         let amount = payment.amount.clone();
+        let fiat_amount = amount.clone();
         let num_paid_invoices = payment.agreement_payments.len() as u64;
 
         debug!(
@@ -1296,6 +1528,22 @@ mod public {
             .await
         {
             Ok(_) => {
+                processor.fiat().stamp(
+                    db.clone(),
+                    payee_id,
+                    FiatEntity::Payment,
+                    payment_id.clone(),
+                    platform.clone(),
+                    fiat_amount,
+                );
+                ya_events::publish(
+                    Some(payee_id),
+                    EventPayload::PaymentReceived {
+                        payment_id: payment_id.clone(),
+                        platform: platform.clone(),
+                        amount: amount.to_string(),
+                    },
+                );
                 counter!("payment.amount.received", ya_metrics::utils::cryptocurrency_to_u64(&amount), "platform" => platform);
                 counter!("payment.invoices.provider.paid", num_paid_invoices);
                 Ok(Ack {})
@@ -1307,7 +1555,25 @@ mod public {
                 VerifyPaymentError::Validation(e) => Err(SendError::BadRequest(e)),
                 _ => Err(SendError::ServiceError(e.to_string())),
             },
-        }.log_err_msg("Payment verification failure");
+        };
+
+        // Concurrent delivery of the same message could have been processed in the meantime.
+        let res = match (res, idempotency_key) {
+            (Ok(ack), Some(key)) => db
+                .as_dao::<IdempotencyKeyDao>()
+                .insert(key, payee_id, payer_id, payment_id.clone())
+                .await
+                .map(|_| ack)
+                .map_err(|e| SendError::ServiceError(e.to_string())),
+            (Err(e), Some(key)) => {
+                match is_delivered(&db, &key, &payment_id, payee_id, payer_id).await {
+                    Ok(true) => Ok(Ack {}),
+                    _ => Err(e),
+                }
+            }
+            (res, None) => res,
+        }
+        .log_err_msg("Payment verification failure");
 
         debug!(
             entity = "payment",
@@ -1318,6 +1584,68 @@ mod public {
         res
     }
 
+    async fn is_delivered(
+        db: &DbExecutor,
+        key: &str,
+        payment_id: &str,
+        payee_id: NodeId,
+        payer_id: NodeId,
+    ) -> Result<bool, SendError> {
+        let entry = db
+            .as_dao::<IdempotencyKeyDao>()
+            .get(key.to_string(), payee_id, payer_id)
+            .await
+            .map_err(|e| SendError::ServiceError(e.to_string()))?;
+        match entry {
+            Some(entry) if entry.payment_id != payment_id => Err(SendError::BadRequest(format!(
+                "Idempotency key {} already used for payment {}",
+                key, entry.payment_id
+            ))),
+            entry => Ok(entry.is_some()),
+        }
+    }
+
+    // *************************** RECEIPT ****************************
+
+    async fn send_payment_receipt(
+        db: DbExecutor,
+        sender_id: String,
+        msg: SendPaymentReceipt,
+    ) -> Result<Ack, SendError> {
+        let payment = &msg.receipt.payment;
+        let payment_id = payment.payment_id.clone();
+        let payee_id = payment.payee_id;
+        if sender_id != payment.payer_id.to_string() {
+            return Err(SendError::BadRequest("Invalid payer ID".to_owned()));
+        }
+        crate::receipt::verify(&msg).map_err(|e| SendError::BadRequest(e.to_string()))?;
+
+        // Receipt can only confirm payment, which was already verified.
+        let known = db
+            .as_dao::<PaymentDao>()
+            .get(payment_id.clone(), payee_id)
+            .await
+            .map_err(|e| SendError::ServiceError(e.to_string()))?
+            .ok_or_else(|| SendError::BadRequest(format!("Payment {} not found", payment_id)))?
+            .payload;
+        if known.payer_id != payment.payer_id
+            || known.amount != payment.amount
+            || known.details != payment.details
+        {
+            return Err(SendError::BadRequest(format!(
+                "Receipt doesn't match payment {}",
+                payment_id
+            )));
+        }
+
+        db.as_dao::<PaymentReceiptDao>()
+            .upsert(DbPaymentReceipt::new(msg, payee_id))
+            .await
+            .map_err(|e| SendError::ServiceError(e.to_string()))?;
+        log::info!("Receipt for payment [{payment_id}] received from node [{sender_id}].");
+        Ok(Ack {})
+    }
+
     // **************************** SYNC *****************************
     async fn sync_request(
         db: DbExecutor,
@@ -1336,6 +1664,23 @@ mod public {
         Ok(Ack {})
     }
 
+    async fn sync_summary(
+        db: DbExecutor,
+        sender_id: String,
+        msg: PaymentSyncSummaryRequest,
+    ) -> Result<PaymentSyncSummary, SendError> {
+        let peer_id =
+            NodeId::from_str(&sender_id).expect("sender_id supplied by ya_service_bus is invalid");
+        log::info!(
+            "Peer [{peer_id}] requested payment sync summary since {}.",
+            msg.since
+        );
+
+        payment_sync_summary(&db, peer_id, msg.since)
+            .await
+            .map_err(|e| SendError::ServiceError(e.to_string()))
+    }
+
     async fn sync_payment(
         db: DbExecutor,
         processor: Arc<PaymentProcessor>,
@@ -0,0 +1,89 @@
+//! Provider side settlement of invoices paid slightly less than invoiced.
+//!
+//! Requestors may pay a bit less than the invoiced amount due to rounding or gas dust.
+//! Such invoices would stay accepted forever, although the missing amount will never
+//! be paid. Accepted invoices missing at most the configured tolerance are settled
+//! and the missing amounts are summed up per platform since the service start.
+use bigdecimal::{BigDecimal, Zero};
+use std::collections::HashMap;
+
+use ya_core_model::payment::local::{PlatformDiscrepancy, SettlementStats};
+
+#[derive(Default)]
+pub struct SettlementLedger {
+    tolerance: BigDecimal,
+    platforms: HashMap<String, (u64, BigDecimal)>,
+}
+
+impl SettlementLedger {
+    pub fn new(tolerance: BigDecimal) -> Self {
+        SettlementLedger {
+            tolerance,
+            platforms: Default::default(),
+        }
+    }
+
+    /// Tolerance is disabled when set to zero.
+    pub fn tolerance(&self) -> Option<&BigDecimal> {
+        if self.tolerance > BigDecimal::zero() {
+            Some(&self.tolerance)
+        } else {
+            None
+        }
+    }
+
+    pub fn record(&mut self, platform: &str, discrepancy: BigDecimal) {
+        let (settled, total) = self
+            .platforms
+            .entry(platform.to_string())
+            .or_insert_with(|| (0, BigDecimal::zero()));
+        *settled += 1;
+        *total += discrepancy;
+    }
+
+    pub fn stats(&self) -> SettlementStats {
+        let mut platforms = self
+            .platforms
+            .iter()
+            .map(|(platform, (settled, discrepancy))| PlatformDiscrepancy {
+                platform: platform.clone(),
+                settled: *settled,
+                discrepancy: discrepancy.clone(),
+            })
+            .collect::<Vec<_>>();
+        platforms.sort_by(|a, b| a.platform.cmp(&b.platform));
+        SettlementStats {
+            tolerance: self.tolerance.clone(),
+            platforms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn discrepancies_are_summed_per_platform() {
+        assert!(SettlementLedger::default().tolerance().is_none());
+
+        let mut ledger = SettlementLedger::new(BigDecimal::from_str("0.001").unwrap());
+        assert!(ledger.tolerance().is_some());
+        ledger.record("erc20-polygon-glm", BigDecimal::from_str("0.0002").unwrap());
+        ledger.record(
+            "erc20-holesky-tglm",
+            BigDecimal::from_str("0.0005").unwrap(),
+        );
+        ledger.record("erc20-polygon-glm", BigDecimal::from_str("0.0003").unwrap());
+
+        let stats = ledger.stats();
+        assert_eq!(stats.platforms.len(), 2);
+        assert_eq!(stats.platforms[0].platform, "erc20-holesky-tglm");
+        assert_eq!(stats.platforms[1].settled, 2);
+        assert_eq!(
+            stats.platforms[1].discrepancy,
+            BigDecimal::from_str("0.0005").unwrap()
+        );
+    }
+}
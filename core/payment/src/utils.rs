@@ -3,6 +3,7 @@ use actix_web::HttpResponse;
 use futures::Future;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use ya_client_model::market::agreement::State as AgreementState;
 use ya_client_model::market::{Agreement, Role};
 use ya_core_model::market;
 use ya_service_bus::{typed as bus, RpcEndpoint};
@@ -41,6 +42,17 @@ pub async fn get_agreement(agreement_id: String, role: Role) -> Result<Option<Ag
     }
 }
 
+/// Ids of Agreements in given state.
+pub async fn list_agreement_ids(state: AgreementState) -> Result<Vec<String>, Error> {
+    let agreements = bus::service(market::BUS_ID)
+        .send(market::ListAgreements {
+            state: Some(state),
+            ..Default::default()
+        })
+        .await??;
+    Ok(agreements.into_iter().map(|entry| entry.id).collect())
+}
+
 pub mod provider {
     use crate::error::{Error, ExternalServiceError};
     use ya_client_model::market::{Agreement, Role};
@@ -2,9 +2,24 @@
 use bigdecimal::BigDecimal;
 
 // Workspace uses
-use ya_core_model::driver::{driver_bus_id, Enter, Exit, Fund, Transfer};
+use ya_core_model::driver::{
+    driver_bus_id, BumpTransaction, Enter, EstimateWithdraw, Fund, GetPendingTransactions,
+    PendingTransaction, PendingTransactions, Transfer, Withdraw, WithdrawEstimate,
+};
+use ya_core_model::payment::local as pay;
 use ya_service_bus::typed as bus;
 
+/// Networks supported by the driver, sorted by name.
+pub async fn driver_networks(driver: &str) -> anyhow::Result<Vec<String>> {
+    let drivers = bus::service(pay::BUS_ID).call(pay::GetDrivers {}).await??;
+    let details = drivers
+        .get(driver)
+        .ok_or_else(|| anyhow::anyhow!("Payment driver {} is not registered", driver))?;
+    let mut networks = details.networks.keys().cloned().collect::<Vec<_>>();
+    networks.sort();
+    Ok(networks)
+}
+
 pub async fn fund(
     address: String,
     driver: String,
@@ -31,16 +46,38 @@ pub async fn enter(
     Ok(tx_id)
 }
 
-pub async fn exit(
+pub async fn estimate_withdraw(
     sender: String,
-    to: Option<String>,
+    to: String,
     amount: Option<BigDecimal>,
     driver: String,
-    network: Option<String>,
-    token: Option<String>,
+    network: String,
+) -> anyhow::Result<WithdrawEstimate> {
+    let driver_id = driver_bus_id(driver);
+    let message = EstimateWithdraw {
+        sender,
+        to,
+        amount,
+        network,
+    };
+    let estimate = bus::service(driver_id).call(message).await??;
+    Ok(estimate)
+}
+
+pub async fn withdraw(
+    sender: String,
+    to: String,
+    amount: Option<BigDecimal>,
+    driver: String,
+    network: String,
 ) -> anyhow::Result<String> {
     let driver_id = driver_bus_id(driver);
-    let message = Exit::new(sender, to, amount, network, token);
+    let message = Withdraw {
+        sender,
+        to,
+        amount,
+        network,
+    };
     let tx_id = bus::service(driver_id).call(message).await??;
     Ok(tx_id)
 }
@@ -73,3 +110,25 @@ pub async fn transfer(
     let tx_id = bus::service(driver_id).call(message).await??;
     Ok(tx_id)
 }
+
+pub async fn pending_transactions(
+    driver: String,
+    network: Option<String>,
+    sender: Option<String>,
+) -> anyhow::Result<PendingTransactions> {
+    let driver_id = driver_bus_id(driver);
+    let message = GetPendingTransactions { network, sender };
+    let pending = bus::service(driver_id).call(message).await??;
+    Ok(pending)
+}
+
+pub async fn bump_transaction(
+    driver: String,
+    id: i64,
+    gas_increase: u32,
+) -> anyhow::Result<PendingTransaction> {
+    let driver_id = driver_bus_id(driver);
+    let message = BumpTransaction { id, gas_increase };
+    let tx = bus::service(driver_id).call(message).await??;
+    Ok(tx)
+}
@@ -0,0 +1,133 @@
+//! Webhooks for invoice and payment events.
+//!
+//! Dispatcher subscribes to the local event bus and POSTs received, accepted, due and paid
+//! invoice events to configured URLs, so external billing systems don't have to poll
+//! payment REST API. Delivery, signing and retries are done by `ya_events::webhook`.
+use anyhow::anyhow;
+use std::rc::Rc;
+
+use ya_core_model::events::{
+    EventFilter, EventSource, LifecycleEvent, Subscribe, BUS_ID as EVENTS_BUS_ID,
+};
+use ya_events::webhook::{self, RetryPolicy, WebhookRequest};
+use ya_service_bus::typed as bus;
+
+use crate::config::WebhookConfig;
+
+pub const WEBHOOK_BUS_ID: &str = "/local/payment/webhooks";
+
+const EVENT_TYPES: [&str; 5] = [
+    "InvoiceReceived",
+    "InvoiceAccepted",
+    "InvoiceAcceptanceDue",
+    "PaymentSent",
+    "PaymentReceived",
+];
+
+pub struct WebhookDispatcher {
+    urls: Vec<String>,
+    secret: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl WebhookDispatcher {
+    /// Returns `None`, if no webhook URL is configured.
+    pub fn new(config: &WebhookConfig) -> Option<Self> {
+        let urls = config
+            .webhook_urls
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if urls.is_empty() {
+            return None;
+        }
+
+        Some(WebhookDispatcher {
+            urls,
+            secret: config.webhook_secret.clone(),
+            retry: RetryPolicy {
+                max_retries: config.webhook_max_retries,
+                retry_delay: config.webhook_retry_delay,
+            },
+        })
+    }
+
+    pub async fn bind(self) -> anyhow::Result<()> {
+        let urls = self.urls.len();
+        let dispatcher = Rc::new(self);
+        let _ = bus::bind(WEBHOOK_BUS_ID, move |event: LifecycleEvent| {
+            dispatcher.clone().dispatch(event);
+            async { Ok(()) }
+        });
+
+        bus::service(EVENTS_BUS_ID)
+            .send(Subscribe {
+                endpoint: WEBHOOK_BUS_ID.to_string(),
+                filter: EventFilter {
+                    sources: vec![EventSource::Payment],
+                    event_types: EVENT_TYPES.iter().map(ToString::to_string).collect(),
+                    node_id: None,
+                },
+                since: None,
+            })
+            .await?
+            .map_err(|e| anyhow!("{}", e))?;
+
+        log::info!("Payment webhooks enabled for {} URL(s).", urls);
+        Ok(())
+    }
+
+    fn dispatch(self: Rc<Self>, event: LifecycleEvent) {
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                log::warn!("Failed to serialize webhook event: {}", e);
+                return;
+            }
+        };
+        let event_type = event.payload.event_type();
+
+        for url in self.urls.iter() {
+            let request = WebhookRequest::new(
+                url.clone(),
+                event_type.to_string(),
+                body.clone(),
+                self.secret.as_deref(),
+            );
+            let retry = self.retry;
+            tokio::task::spawn_local(async move {
+                let url = request.url.clone();
+                let delivered = webhook::deliver(request, retry, |_, _, _| async {}).await;
+                if !delivered {
+                    log::warn!("Giving up delivery of webhook {} to {}.", event_type, url);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn disabled_without_urls() {
+        let mut config = WebhookConfig {
+            webhook_urls: " , ".to_string(),
+            webhook_secret: None,
+            webhook_max_retries: 5,
+            webhook_retry_delay: Duration::from_secs(5),
+        };
+        assert!(WebhookDispatcher::new(&config).is_none());
+
+        config.webhook_urls = "http://billing.local/a, http://billing.local/b".to_string();
+        let dispatcher = WebhookDispatcher::new(&config).unwrap();
+        assert_eq!(
+            dispatcher.urls,
+            vec!["http://billing.local/a", "http://billing.local/b"]
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Automatic withdrawal of earnings to an external (cold) wallet.
+//!
+//! When destination address is configured, token balance of the payment identity is
+//! periodically withdrawn from each network of the driver, once it reaches the threshold.
+//! Withdrawal is postponed while its gas fee could exceed the configured limit.
+//! Attempts are recorded, so the history can be listed with `yagna payment withdrawals`.
+use bigdecimal::{BigDecimal, Zero};
+use chrono::Utc;
+use std::rc::Rc;
+
+use ya_core_model::driver::WithdrawEstimate;
+use ya_core_model::identity as id_api;
+use ya_core_model::payment::local::{
+    GenericError, ListWithdrawals, RunAutoWithdrawal, Withdrawal, WithdrawalStatus, BUS_ID,
+};
+use ya_persistence::executor::DbExecutor;
+use ya_service_bus::typed as bus;
+
+use crate::config::AutoWithdrawalConfig;
+use crate::dao::WithdrawalDao;
+use crate::wallet;
+
+pub struct AutoWithdrawal {
+    db: DbExecutor,
+    to: String,
+    threshold: BigDecimal,
+    max_gas_fee: Option<BigDecimal>,
+    driver: String,
+    networks: Vec<String>,
+}
+
+/// Binds history listing and manual runs. Starts periodic withdrawals, if enabled.
+pub fn bind_service(db: &DbExecutor, config: &AutoWithdrawalConfig) {
+    let auto_withdrawal = AutoWithdrawal::new(db.clone(), config).map(Rc::new);
+
+    let db = db.clone();
+    let _ = bus::bind(BUS_ID, move |msg: ListWithdrawals| {
+        let db = db.clone();
+        async move {
+            db.as_dao::<WithdrawalDao>()
+                .list(msg.limit)
+                .await
+                .map_err(GenericError::new)
+        }
+    });
+
+    let runner = auto_withdrawal.clone();
+    let _ = bus::bind(BUS_ID, move |msg: RunAutoWithdrawal| {
+        let runner = runner.clone();
+        async move {
+            match runner {
+                Some(runner) => runner.run(msg.dry_run).await,
+                None => Err(GenericError::new(
+                    "Auto-withdrawal is disabled. Set YA_PAYMENT_AUTO_WITHDRAW_TO to enable it.",
+                )),
+            }
+        }
+    });
+
+    if let Some(auto_withdrawal) = auto_withdrawal {
+        let period = config.auto_withdraw_interval;
+        log::info!(
+            "Auto-withdrawal to {} enabled, every {}.",
+            auto_withdrawal.to,
+            humantime::format_duration(period)
+        );
+        tokio::task::spawn_local(async move {
+            // Drivers aren't registered yet at startup, so the first run is delayed.
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                if let Err(e) = auto_withdrawal.run(false).await {
+                    log::warn!("Auto-withdrawal failed: {}", e);
+                }
+            }
+        });
+    }
+}
+
+impl AutoWithdrawal {
+    /// Returns `None`, if destination address isn't configured.
+    pub fn new(db: DbExecutor, config: &AutoWithdrawalConfig) -> Option<Self> {
+        let to = config.auto_withdraw_to.clone()?;
+        let networks = config
+            .auto_withdraw_networks
+            .split(',')
+            .map(str::trim)
+            .filter(|network| !network.is_empty())
+            .map(ToString::to_string)
+            .collect();
+
+        Some(AutoWithdrawal {
+            db,
+            to,
+            threshold: config.auto_withdraw_threshold.clone(),
+            max_gas_fee: config.auto_withdraw_max_gas_fee.clone(),
+            driver: config.auto_withdraw_driver.clone(),
+            networks,
+        })
+    }
+
+    /// Withdraws balances passing the threshold. In `dry_run` nothing is sent nor recorded.
+    pub async fn run(&self, dry_run: bool) -> Result<Vec<Withdrawal>, GenericError> {
+        let sender = default_identity().await?;
+        let networks = match self.networks.is_empty() {
+            true => wallet::driver_networks(&self.driver)
+                .await
+                .map_err(GenericError::new)?,
+            false => self.networks.clone(),
+        };
+
+        let mut withdrawals = Vec::new();
+        for network in networks {
+            let estimate = match wallet::estimate_withdraw(
+                sender.clone(),
+                self.to.clone(),
+                None,
+                self.driver.clone(),
+                network.clone(),
+            )
+            .await
+            {
+                Ok(estimate) => estimate,
+                Err(e) => {
+                    log::warn!("Failed to estimate withdrawal on {}: {}", network, e);
+                    continue;
+                }
+            };
+
+            let withdrawal = self.withdraw(estimate, dry_run).await;
+            if !dry_run && withdrawal.status != WithdrawalStatus::BelowThreshold {
+                if let Err(e) = self
+                    .db
+                    .as_dao::<WithdrawalDao>()
+                    .insert(withdrawal.clone())
+                    .await
+                {
+                    log::warn!("Failed to record withdrawal on {}: {}", network, e);
+                }
+            }
+            withdrawals.push(withdrawal);
+        }
+        Ok(withdrawals)
+    }
+
+    async fn withdraw(&self, estimate: WithdrawEstimate, dry_run: bool) -> Withdrawal {
+        let mut withdrawal = Withdrawal {
+            sender: estimate.sender.clone(),
+            to: estimate.to.clone(),
+            driver: self.driver.clone(),
+            network: estimate.network.clone(),
+            token: estimate.token.clone(),
+            amount: estimate.amount.clone(),
+            max_fee: estimate.max_fee.clone(),
+            status: policy_status(&estimate, &self.threshold, self.max_gas_fee.as_ref()),
+            tx_id: None,
+            error: None,
+            timestamp: Utc::now(),
+        };
+        if withdrawal.status != WithdrawalStatus::Scheduled {
+            return withdrawal;
+        }
+        if dry_run {
+            withdrawal.status = WithdrawalStatus::DryRun;
+            return withdrawal;
+        }
+
+        match wallet::withdraw(
+            estimate.sender,
+            estimate.to,
+            Some(estimate.amount),
+            self.driver.clone(),
+            estimate.network,
+        )
+        .await
+        {
+            Ok(tx_id) => {
+                log::info!(
+                    "Auto-withdrawal of {} {} on {} scheduled: {}",
+                    withdrawal.amount,
+                    withdrawal.token,
+                    withdrawal.network,
+                    tx_id
+                );
+                withdrawal.tx_id = Some(tx_id);
+            }
+            Err(e) => {
+                withdrawal.status = WithdrawalStatus::Failed;
+                withdrawal.error = Some(e.to_string());
+            }
+        }
+        withdrawal
+    }
+}
+
+/// Status of the withdrawal before it is sent: `Scheduled`, if policy allows it.
+fn policy_status(
+    estimate: &WithdrawEstimate,
+    threshold: &BigDecimal,
+    max_gas_fee: Option<&BigDecimal>,
+) -> WithdrawalStatus {
+    if estimate.amount.is_zero() || &estimate.amount < threshold {
+        WithdrawalStatus::BelowThreshold
+    } else if max_gas_fee
+        .map(|max_gas_fee| &estimate.max_fee > max_gas_fee)
+        .unwrap_or(false)
+    {
+        WithdrawalStatus::GasFeeTooHigh
+    } else if !estimate.sufficient_gas {
+        WithdrawalStatus::InsufficientGas
+    } else {
+        WithdrawalStatus::Scheduled
+    }
+}
+
+async fn default_identity() -> Result<String, GenericError> {
+    bus::service(id_api::BUS_ID)
+        .send(id_api::Get::ByModule(id_api::Module::Payment))
+        .await
+        .map_err(GenericError::new)?
+        .map_err(GenericError::new)?
+        .map(|id| id.node_id.to_string())
+        .ok_or_else(|| GenericError::new("Default identity not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn estimate(amount: &str, max_fee: &str, sufficient_gas: bool) -> WithdrawEstimate {
+        WithdrawEstimate {
+            network: "holesky".to_string(),
+            token: "tGLM".to_string(),
+            sender: "0x01".to_string(),
+            to: "0x02".to_string(),
+            amount: BigDecimal::from_str(amount).unwrap(),
+            token_balance: BigDecimal::from_str(amount).unwrap(),
+            gas_details: None,
+            max_fee: BigDecimal::from_str(max_fee).unwrap(),
+            sufficient_gas,
+        }
+    }
+
+    #[test]
+    fn test_policy_status() {
+        let threshold = BigDecimal::from(10);
+        let max_gas_fee = BigDecimal::from_str("0.01").unwrap();
+
+        let status = |estimate| policy_status(&estimate, &threshold, Some(&max_gas_fee));
+        assert_eq!(
+            status(estimate("9.9", "0.001", true)),
+            WithdrawalStatus::BelowThreshold
+        );
+        assert_eq!(
+            status(estimate("10", "0.02", true)),
+            WithdrawalStatus::GasFeeTooHigh
+        );
+        assert_eq!(
+            status(estimate("10", "0.001", false)),
+            WithdrawalStatus::InsufficientGas
+        );
+        assert_eq!(
+            status(estimate("10", "0.001", true)),
+            WithdrawalStatus::Scheduled
+        );
+        assert_eq!(
+            policy_status(&estimate("0", "0", true), &BigDecimal::zero(), None),
+            WithdrawalStatus::BelowThreshold
+        );
+    }
+}
@@ -4,7 +4,7 @@ use test_context::test_context;
 
 use ya_client_model::payment::allocation::{PaymentPlatform, PaymentPlatformEnum};
 use ya_client_model::payment::{Acceptance, NewAllocation, NewInvoice};
-use ya_core_model::payment::local::GetStatus;
+use ya_core_model::payment::local::{GetAccounts, GetInvoiceStats, GetStatus};
 use ya_framework_basic::async_drop::DroppableTestContext;
 use ya_framework_basic::log::enable_logs;
 use ya_framework_basic::{resource, temp_dir};
@@ -280,3 +280,123 @@ async fn test_validate_allocation(ctx: &mut DroppableTestContext) -> anyhow::Res
     log::info!(" 👍🏻 Example completed successfully ❤️");
     Ok(())
 }
+
+#[cfg_attr(not(feature = "framework-test"), ignore)]
+#[test_context(DroppableTestContext)]
+#[serial_test::serial]
+async fn test_allocation_identity_isolation(ctx: &mut DroppableTestContext) -> anyhow::Result<()> {
+    enable_logs(false);
+
+    let dir = temp_dir!("test_allocation_identity_isolation")?;
+
+    let net = MockNet::new().bind();
+
+    let node = MockNode::new(net, "node-1", dir.path())
+        .with_identity()
+        .with_payment(None)
+        .with_fake_market();
+    node.bind_gsb().await?;
+    node.start_server(ctx).await?;
+
+    let appkey_req = node
+        .get_identity()?
+        .create_from_private_key(&resource!("ci-requestor-1.key.priv"))
+        .await?;
+    let appkey_sub = node
+        .get_identity()?
+        .create_identity_key("sub-requestor")
+        .await?;
+
+    node.get_payment()?
+        .fund_account(Driver::Erc20, &appkey_req.identity.to_string())
+        .await?;
+
+    let requestor = node.rest_payments(&appkey_req.key)?;
+    let sub_requestor = node.rest_payments(&appkey_sub.key)?;
+
+    let accounts = sub_requestor.get_requestor_accounts().await?;
+    assert!(accounts
+        .iter()
+        .all(|account| account.address != appkey_req.identity.to_string()));
+
+    let payment = node.get_payment()?;
+    let accounts = payment
+        .gsb_local_endpoint()
+        .send_as(appkey_sub.identity, GetAccounts::all())
+        .await??;
+    assert!(accounts
+        .iter()
+        .all(|account| account.owner == Some(appkey_sub.identity)));
+
+    log::info!("Attempting to query balance and invoice stats of another identity...");
+    let result = payment
+        .gsb_local_endpoint()
+        .send_as(
+            appkey_sub.identity,
+            GetStatus {
+                address: appkey_req.identity.to_string(),
+                driver: Driver::Erc20.gsb_name(),
+                network: Some("holesky".to_string()),
+                token: None,
+                after_timestamp: 0,
+            },
+        )
+        .await?;
+    assert!(result.is_err());
+    let result = payment
+        .gsb_local_endpoint()
+        .send_as(
+            appkey_sub.identity,
+            GetInvoiceStats::new(appkey_req.identity, Utc::now()),
+        )
+        .await?;
+    assert!(result.is_err());
+    log::info!("Failed to query (as expected).");
+
+    let allocation = requestor
+        .create_allocation(&NewAllocation {
+            address: Some(appkey_req.identity.to_string()),
+            payment_platform: Some(PaymentPlatformEnum::PaymentPlatformName(
+                "erc20-holesky-tglm".to_string(),
+            )),
+            total_amount: BigDecimal::from(1u64),
+            timeout: None,
+            make_deposit: false,
+            deposit: None,
+            extend_timeout: None,
+        })
+        .await?;
+
+    log::info!("Attempting to access allocation of another identity...");
+    let allocations = sub_requestor.get_allocations::<Utc>(None, None).await?;
+    assert!(allocations.is_empty());
+    let result = sub_requestor
+        .get_allocation(&allocation.allocation_id)
+        .await;
+    assert!(result.is_err());
+    let result = sub_requestor
+        .release_allocation(&allocation.allocation_id)
+        .await;
+    assert!(result.is_err());
+    let allocations = requestor.get_allocations::<Utc>(None, None).await?;
+    assert_eq!(allocations, vec![allocation]);
+    log::info!("Failed to access allocation (as expected).");
+
+    log::info!("Attempting to create allocation using account of another identity...");
+    let result = sub_requestor
+        .create_allocation(&NewAllocation {
+            address: Some(appkey_req.identity.to_string()),
+            payment_platform: Some(PaymentPlatformEnum::PaymentPlatformName(
+                "erc20-holesky-tglm".to_string(),
+            )),
+            total_amount: BigDecimal::from(1u64),
+            timeout: None,
+            make_deposit: false,
+            deposit: None,
+            extend_timeout: None,
+        })
+        .await;
+    assert!(result.is_err());
+    log::info!("Failed to create allocation (as expected).");
+    Ok(())
+}
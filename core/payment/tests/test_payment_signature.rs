@@ -6,7 +6,7 @@ use test_context::test_context;
 use ya_client_model::NodeId;
 
 use ya_client_model::payment::{NewInvoice, Payment};
-use ya_core_model::payment::public::SendSignedPayment;
+use ya_core_model::payment::public::{idempotency_key, SendSignedPayment};
 use ya_core_model::signable::Signable;
 use ya_framework_basic::async_drop::DroppableTestContext;
 use ya_framework_basic::log::enable_logs;
@@ -114,6 +114,7 @@ async fn test_payment_signature(ctx: &mut DroppableTestContext) -> anyhow::Resul
         payment: payment.clone().remove_private_info(),
         signature: signature.signature.clone(),
         signed_bytes: signature.signed_bytes.clone(),
+        idempotency_key: None,
     };
 
     log::info!("=== Check if incorrect signature will be rejected.");
@@ -187,8 +188,12 @@ async fn test_payment_signature(ctx: &mut DroppableTestContext) -> anyhow::Resul
     assert!(result.is_err());
 
     log::info!("=== Correct signature should be accepted.");
+    let correct_with_key = SendSignedPayment {
+        idempotency_key: Some(idempotency_key(&payment)),
+        ..correct.clone()
+    };
     payment_gsb
-        .send_as(invoice.recipient_id, correct.clone())
+        .send_as(invoice.recipient_id, correct_with_key.clone())
         .await?
         .unwrap();
 
@@ -198,5 +203,11 @@ async fn test_payment_signature(ctx: &mut DroppableTestContext) -> anyhow::Resul
         .await?;
     assert!(result.is_err());
 
+    log::info!("=== Redelivery with the same idempotency key should be acknowledged.");
+    payment_gsb
+        .send_as(invoice.recipient_id, correct_with_key)
+        .await?
+        .unwrap();
+
     Ok(())
 }
@@ -189,6 +189,16 @@ impl DbExecutor {
         do_with_transaction(&self.pool, label, f).await
     }
 
+    /// Checks that database accepts writes. Statement doesn't modify any rows,
+    /// but requires write lock, so it fails for read-only or locked database.
+    pub async fn probe_writable(&self) -> Result<(), Error> {
+        self.with_transaction("probe_writable", |conn| {
+            conn.execute("DELETE FROM __diesel_schema_migrations WHERE 0")?;
+            Ok(())
+        })
+        .await
+    }
+
     #[allow(unused)]
     pub(crate) async fn execute(&self, query: &str) -> Result<usize, Error> {
         Ok(self.conn()?.execute(query)?)
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use ya_core_model::bus::GsbBindPoints;
-pub use ya_utils_cli::{CommandOutput, ResponseTable};
+pub use ya_utils_cli::{CommandOutput, OutputFormat, ResponseTable};
 
 #[derive(Clone, Debug, Default)]
 pub struct MetricsCtx {
@@ -16,7 +16,7 @@ pub struct MetricsCtx {
 pub struct CliCtx {
     pub data_dir: PathBuf,
     pub gsb_url: Option<url::Url>,
-    pub json_output: bool,
+    pub output_format: OutputFormat,
     pub accept_terms: bool,
     pub quiet: bool,
     pub metrics_ctx: Option<MetricsCtx>,
@@ -25,7 +25,12 @@ pub struct CliCtx {
 
 impl CliCtx {
     pub fn output(&self, output: CommandOutput) -> Result<(), anyhow::Error> {
-        output.print(self.json_output)
+        output.print_as(self.output_format)
+    }
+
+    /// Commands should return objects with stable field names instead of tables.
+    pub fn structured_output(&self) -> bool {
+        self.output_format.is_structured()
     }
 
     pub fn with_prefixed_gsb(mut self, gsb: Option<GsbBindPoints>) -> Self {
@@ -16,6 +16,15 @@ pub struct Identity {
     pub identity: NodeId,
     pub name: String,
     pub role: String,
+    /// Identity of the app-key, when it acts on behalf of `identity` using delegation token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegated_by: Option<NodeId>,
+}
+
+impl Identity {
+    pub fn is_delegated(&self) -> bool {
+        self.delegated_by.is_some()
+    }
 }
 
 impl From<AppKey> for Identity {
@@ -24,6 +33,7 @@ impl From<AppKey> for Identity {
             identity: app_key.identity,
             name: app_key.name,
             role: app_key.role,
+            delegated_by: None,
         }
     }
 }
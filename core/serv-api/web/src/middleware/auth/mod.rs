@@ -1,13 +1,16 @@
 pub mod dummy;
 pub mod ident;
 pub mod resolver;
+pub mod usage;
 
 pub use crate::middleware::auth::ident::Identity;
 pub use crate::middleware::auth::resolver::AppKeyCache;
 
 use actix_service::{Service, Transform};
 use actix_web::dev::{ServiceRequest, ServiceResponse};
-use actix_web::error::{Error, ErrorUnauthorized, ParseError};
+use actix_web::error::{
+    Error, ErrorBadRequest, ErrorForbidden, ErrorInternalServerError, ErrorUnauthorized, ParseError,
+};
 use actix_web::{web, HttpMessage};
 use actix_web_httpauth::headers::authorization::{Bearer, Scheme};
 use futures::future::{ok, Future, Ready};
@@ -17,6 +20,17 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
+use ya_client::model::NodeId;
+use ya_core_model::appkey::DEFAULT_ROLE;
+use ya_core_model::identity;
+use ya_service_bus::{typed as bus, RpcEndpoint};
+
+/// Header with delegation token, which allows app-key identity to act on behalf of other identity.
+pub const DELEGATION_HEADER: &str = "X-Yagna-Delegation";
+/// Header with identity, on behalf of which the request is made instead of the app-key identity.
+/// Accepted only from app-keys with the default (manager) role.
+pub const IDENTITY_HEADER: &str = "X-Yagna-Identity";
+
 pub struct Auth {
     pub(crate) cache: AppKeyCache,
 }
@@ -81,10 +95,22 @@ where
                     .map(|q| q.into_inner().auth_token)
             });
 
+        let selected = req
+            .headers()
+            .get(IDENTITY_HEADER)
+            .map(|value| value.to_str().map(ToString::to_string));
+        let delegation = req
+            .headers()
+            .get(DELEGATION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+        let scope = api_scope(req.path()).map(ToString::to_string);
+        let source_ip = req.peer_addr().map(|addr| addr.ip().to_string());
+
         let cache = self.cache.clone();
         let service = self.service.clone();
 
-        let allowed_uris = vec!["/metrics-api", "/version", "/dashboard"];
+        let allowed_uris = vec!["/metrics-api", "/version", "/dashboard", "/healthz"];
 
         for uri in allowed_uris {
             if req.uri().to_string().starts_with(uri) {
@@ -97,7 +123,22 @@ where
             match header {
                 Some(key) => match cache.get_appkey(&key) {
                     Some(app_key) => {
-                        req.extensions_mut().insert(Identity::from(app_key));
+                        cache.record_usage(&app_key.name, source_ip);
+                        let mut identity = Identity::from(app_key);
+                        if let Some(selected) = selected {
+                            let selected = selected
+                                .ok()
+                                .and_then(|node_id| node_id.parse().ok())
+                                .ok_or_else(|| {
+                                ErrorBadRequest(format!("Invalid {} header", IDENTITY_HEADER))
+                            })?;
+                            identity = selected_identity(identity, selected).await?;
+                        }
+                        if let Some(token) = delegation {
+                            identity =
+                                delegated_identity(identity, &token, scope.as_deref()).await?;
+                        }
+                        req.extensions_mut().insert(identity);
                         let fut = { service.borrow_mut().call(req) };
                         Ok(fut.await?)
                     }
@@ -119,6 +160,85 @@ where
     }
 }
 
+/// Identity selected with the header, if app-key is allowed to use it.
+async fn selected_identity(identity: Identity, selected: NodeId) -> Result<Identity, Error> {
+    if selected == identity.identity {
+        return Ok(identity);
+    }
+    if identity.role != DEFAULT_ROLE {
+        return Err(ErrorForbidden(format!(
+            "Only app-keys with role {} can select identity",
+            DEFAULT_ROLE
+        )));
+    }
+
+    let info = bus::service(identity::BUS_ID)
+        .send(identity::Get::ByNodeId(selected))
+        .await
+        .map_err(ErrorInternalServerError)?
+        .map_err(ErrorInternalServerError)?
+        .filter(|info| !info.deleted)
+        .ok_or_else(|| ErrorForbidden(format!("Unknown identity {}", selected)))?;
+    if info.is_locked {
+        return Err(ErrorForbidden(format!("Identity {} is locked", selected)));
+    }
+
+    log::debug!(
+        "[{}] selected identity [{}] for the request",
+        identity.identity,
+        selected
+    );
+    Ok(Identity {
+        identity: selected,
+        ..identity
+    })
+}
+
+/// Identity of delegation owner, if token allows app-key identity to use requested API.
+async fn delegated_identity(
+    identity: Identity,
+    token: &str,
+    scope: Option<&str>,
+) -> Result<Identity, Error> {
+    let delegation = bus::service(identity::BUS_ID)
+        .send(identity::VerifyDelegation {
+            token: token.to_string(),
+        })
+        .await
+        .map_err(ErrorInternalServerError)?
+        .map_err(|e| {
+            log::debug!("Rejected delegation for [{}]: {e}", identity.identity);
+            ErrorUnauthorized(e.to_string())
+        })?;
+
+    if delegation.delegate != identity.identity {
+        return Err(ErrorForbidden("Delegation was issued for other identity"));
+    }
+    match scope {
+        Some(scope) if delegation.allows(scope) => (),
+        _ => return Err(ErrorForbidden("Delegation doesn't allow to use this API")),
+    }
+
+    log::debug!(
+        "[{}] acts on behalf of [{}] using delegation",
+        identity.identity,
+        delegation.owner
+    );
+    Ok(Identity {
+        identity: delegation.owner,
+        delegated_by: Some(identity.identity),
+        ..identity
+    })
+}
+
+/// Name of the API from request path, e.g. `market` for `/market-api/v1/demands`.
+fn api_scope(path: &str) -> Option<&str> {
+    path.trim_start_matches('/')
+        .split('/')
+        .next()?
+        .strip_suffix("-api")
+}
+
 pub(crate) fn parse_auth<S: Scheme, T: HttpMessage>(msg: &T) -> Result<S, ParseError> {
     let header = msg
         .headers()
@@ -7,11 +7,14 @@ use ya_core_model::appkey::event::AppKeyEvent;
 use ya_core_model::appkey::AppKey;
 use ya_service_bus::{typed as bus, RpcEndpoint};
 
+use crate::middleware::auth::usage::UsageTracker;
+
 pub const BUS_ID: &str = "/local/middleware/auth";
 
 #[derive(Clone)]
 pub struct AppKeyCache {
     appkeys: Arc<RwLock<HashMap<String, AppKey>>>,
+    usage: UsageTracker,
 }
 
 impl AppKeyCache {
@@ -46,11 +49,13 @@ impl AppKeyCache {
 
         let appkey_cache = AppKeyCache {
             appkeys: Arc::new(RwLock::new(mapping)),
+            usage: UsageTracker::default(),
         };
         appkey_cache
             .listen_events()
             .await
             .map_err(|e| anyhow!("Can't build cors middleware: {e}"))?;
+        appkey_cache.usage.spawn_reporting();
         Ok(appkey_cache)
     }
 
@@ -61,6 +66,11 @@ impl AppKeyCache {
         }
     }
 
+    /// Records successful authorization with app-key of given name.
+    pub fn record_usage(&self, name: &str, source_ip: Option<String>) {
+        self.usage.record(name, source_ip)
+    }
+
     pub fn get_allowed_origins(&self, key: &str) -> Vec<String> {
         match self.appkeys.read() {
             Ok(keymap) => keymap
@@ -0,0 +1,95 @@
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ya_core_model::appkey as model;
+use ya_service_bus::{typed as bus, RpcEndpoint};
+
+/// Usage is accumulated in memory and reported to the Identity service in batches,
+/// so that authorization doesn't hit the database on every request.
+const REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Default)]
+pub struct UsageTracker {
+    pending: Arc<Mutex<HashMap<String, model::AppKeyUsage>>>,
+}
+
+impl UsageTracker {
+    pub fn record(&self, name: &str, source_ip: Option<String>) {
+        let now = Utc::now().naive_utc();
+        if let Ok(mut pending) = self.pending.lock() {
+            let usage = pending
+                .entry(name.to_string())
+                .or_insert_with(|| model::AppKeyUsage {
+                    name: name.to_string(),
+                    request_count: 0,
+                    last_used_date: now,
+                    last_source_ip: None,
+                });
+            usage.request_count += 1;
+            usage.last_used_date = now;
+            if source_ip.is_some() {
+                usage.last_source_ip = source_ip;
+            }
+        }
+    }
+
+    fn take(&self) -> Vec<model::AppKeyUsage> {
+        match self.pending.lock() {
+            Ok(mut pending) => pending.drain().map(|(_, usage)| usage).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Puts back usage which couldn't be reported. Entries recorded in the meantime
+    /// are more recent, so only their counters are merged.
+    fn restore(&self, usage: Vec<model::AppKeyUsage>) {
+        if let Ok(mut pending) = self.pending.lock() {
+            for usage in usage {
+                match pending.get_mut(&usage.name) {
+                    Some(newer) => newer.request_count += usage.request_count,
+                    None => {
+                        pending.insert(usage.name.clone(), usage);
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn spawn_reporting(&self) {
+        let this = self.clone();
+        tokio::task::spawn_local(async move {
+            loop {
+                tokio::time::sleep(REPORT_INTERVAL).await;
+                this.report().await;
+            }
+        });
+    }
+
+    async fn report(&self) {
+        let usage = self.take();
+        if usage.is_empty() {
+            return;
+        }
+
+        log::trace!("Reporting usage of {} app-keys.", usage.len());
+
+        let result = bus::service(model::BUS_ID)
+            .send(model::RecordUsage {
+                usage: usage.clone(),
+            })
+            .await;
+        match result {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => {
+                log::warn!("Failed to record app-keys usage: {e}");
+                self.restore(usage);
+            }
+            Err(e) => {
+                log::warn!("Failed to report app-keys usage: {e}");
+                self.restore(usage);
+            }
+        }
+    }
+}
@@ -17,7 +17,7 @@ use tokio_stream::wrappers::LinesStream;
 use ya_client_model::NodeId;
 use ya_core_model as model;
 use ya_core_model::appkey::AppKey;
-use ya_service_api::{CliCtx, CommandOutput};
+use ya_service_api::{CliCtx, CommandOutput, OutputFormat};
 use ya_service_bus::typed as bus;
 
 const APP_NAME: &str = structopt::clap::crate_name!();
@@ -31,6 +31,7 @@ const VAR_YAGNA_APP_KEY: &str = "YAGNA_APP_KEY";
 const VAR_YAGNA_API_URL: &str = "YAGNA_API_URL";
 const VAR_YAGNA_GSB_URL: &str = "YAGNA_GSB_URL";
 const VAR_YAGNA_JSON_OUTPUT: &str = "YAGNA_JSON_OUTPUT";
+const VAR_YAGNA_OUTPUT_FORMAT: &str = "YAGNA_OUTPUT_FORMAT";
 
 pub async fn run<T: StructOpt>(
     cli_ctx: &CliCtx,
@@ -138,7 +139,7 @@ pub enum ExtensionCtx {
     Cli {
         data_dir: PathBuf,
         gsb_url: Option<url::Url>,
-        json_output: bool,
+        output_format: OutputFormat,
     },
     Autostart {
         node_id: NodeId,
@@ -159,9 +160,13 @@ impl ExtensionCtx {
             Self::Cli {
                 data_dir,
                 gsb_url,
-                json_output,
+                output_format,
             } => {
-                command.env(VAR_YAGNA_JSON_OUTPUT, json_output.to_string());
+                command.env(
+                    VAR_YAGNA_JSON_OUTPUT,
+                    (*output_format == OutputFormat::Json).to_string(),
+                );
+                command.env(VAR_YAGNA_OUTPUT_FORMAT, output_format.to_string());
                 (data_dir, gsb_url)
             }
             Self::Autostart {
@@ -198,7 +203,7 @@ impl<'a> From<&'a CliCtx> for ExtensionCtx {
         Self::Cli {
             data_dir: ctx.data_dir.clone(),
             gsb_url: ctx.gsb_url.clone(),
-            json_output: ctx.json_output,
+            output_format: ctx.output_format,
         }
     }
 }
@@ -0,0 +1,217 @@
+//! Daemon health checks.
+//!
+//! Probes of subsystems are aggregated into `HealthReport`, which is served on
+//! `GET /healthz`, returned by `yagna status` and used to feed systemd watchdog.
+use actix_web::{HttpResponse, Responder};
+use std::env;
+use std::future::Future;
+use std::time::Duration;
+
+use ya_client_model::payment::DriverStatusProperty;
+use ya_core_model::identity;
+use ya_core_model::net::local as net;
+use ya_core_model::payment::local as pay;
+use ya_persistence::executor::DbExecutor;
+use ya_service_bus::typed as bus;
+
+use crate::model::{HealthReport, HealthState, ProbeResult};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl ProbeResult {
+    fn healthy(name: &str) -> Self {
+        Self::new(name, HealthState::Healthy, None)
+    }
+
+    fn new(name: &str, state: HealthState, reason: Option<String>) -> Self {
+        ProbeResult {
+            name: name.to_string(),
+            state,
+            reason,
+        }
+    }
+}
+
+impl HealthReport {
+    pub fn new(probes: Vec<ProbeResult>) -> Self {
+        let state = probes
+            .iter()
+            .map(|probe| probe.state)
+            .max()
+            .unwrap_or(HealthState::Healthy);
+        HealthReport { state, probes }
+    }
+}
+
+pub async fn check(db: &DbExecutor) -> HealthReport {
+    let (gsb_identity, net, payment, db) =
+        futures::join!(probe_identity(), probe_net(), probe_payment(), probe_db(db));
+
+    let mut probes = gsb_identity;
+    probes.extend([net, payment, db]);
+    HealthReport::new(probes)
+}
+
+async fn with_timeout<F>(name: &str, probe: F) -> ProbeResult
+where
+    F: Future<Output = ProbeResult>,
+{
+    tokio::time::timeout(PROBE_TIMEOUT, probe)
+        .await
+        .unwrap_or_else(|_| {
+            ProbeResult::new(
+                name,
+                HealthState::Unhealthy,
+                Some(format!("no response within {}s", PROBE_TIMEOUT.as_secs())),
+            )
+        })
+}
+
+/// GSB connection is checked by listing identities, so both probes share one call.
+async fn probe_identity() -> Vec<ProbeResult> {
+    let result = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        bus::service(identity::BUS_ID).send(identity::List {}),
+    )
+    .await;
+
+    let identities = match result {
+        Ok(Ok(Ok(identities))) => identities,
+        Ok(Ok(Err(e))) => {
+            return vec![
+                ProbeResult::healthy("gsb"),
+                ProbeResult::new("identity", HealthState::Unhealthy, Some(e.to_string())),
+            ]
+        }
+        Ok(Err(e)) => {
+            return vec![
+                ProbeResult::new("gsb", HealthState::Unhealthy, Some(e.to_string())),
+                ProbeResult::new(
+                    "identity",
+                    HealthState::Unhealthy,
+                    Some("identity service unreachable".to_string()),
+                ),
+            ]
+        }
+        Err(_) => {
+            let reason = Some(format!("no response within {}s", PROBE_TIMEOUT.as_secs()));
+            return vec![
+                ProbeResult::new("gsb", HealthState::Unhealthy, reason.clone()),
+                ProbeResult::new("identity", HealthState::Unhealthy, reason),
+            ];
+        }
+    };
+
+    let identity = match identities.iter().find(|id| id.is_default) {
+        None => ProbeResult::new(
+            "identity",
+            HealthState::Unhealthy,
+            Some("no default identity".to_string()),
+        ),
+        Some(id) if id.is_locked => ProbeResult::new(
+            "identity",
+            HealthState::Unhealthy,
+            Some(format!("default identity {} is locked", id.node_id)),
+        ),
+        Some(_) => ProbeResult::healthy("identity"),
+    };
+    vec![ProbeResult::healthy("gsb"), identity]
+}
+
+async fn probe_net() -> ProbeResult {
+    with_timeout("net", async {
+        match bus::service(net::BUS_ID).send(net::Status {}).await {
+            Ok(Ok(status)) if status.sessions == 0 => ProbeResult::new(
+                "net",
+                HealthState::Degraded,
+                Some("no active relay session".to_string()),
+            ),
+            Ok(Ok(_)) => ProbeResult::healthy("net"),
+            // Net modes without relay don't report sessions.
+            Ok(Err(net::StatusError::RuntimeException(e))) => {
+                ProbeResult::new("net", HealthState::Healthy, Some(e))
+            }
+            Err(e) => ProbeResult::new("net", HealthState::Unhealthy, Some(e.to_string())),
+        }
+    })
+    .await
+}
+
+async fn probe_payment() -> ProbeResult {
+    with_timeout("payment", async {
+        let result = bus::service(pay::BUS_ID)
+            .call(pay::PaymentDriverStatus {
+                driver: None,
+                network: None,
+            })
+            .await;
+        match result {
+            Ok(Ok(props)) if props.is_empty() => ProbeResult::healthy("payment"),
+            Ok(Ok(props)) => {
+                let reasons = props.iter().map(describe).collect::<Vec<_>>();
+                ProbeResult::new("payment", HealthState::Degraded, Some(reasons.join("; ")))
+            }
+            Ok(Err(e)) => ProbeResult::new("payment", HealthState::Degraded, Some(e.to_string())),
+            Err(e) => ProbeResult::new("payment", HealthState::Unhealthy, Some(e.to_string())),
+        }
+    })
+    .await
+}
+
+fn describe(prop: &DriverStatusProperty) -> String {
+    use DriverStatusProperty::*;
+
+    match prop {
+        CantSign {
+            network, address, ..
+        } => format!("{network}: can't sign payments from {address}"),
+        InsufficientGas { network, .. } => format!("{network}: insufficient gas"),
+        InsufficientToken { network, .. } => format!("{network}: insufficient token"),
+        InvalidChainId { chain_id, .. } => format!("invalid chain id {chain_id}"),
+        RpcError { network, .. } => format!("{network}: RPC endpoints unreliable"),
+        TxStuck { network, .. } => format!("{network}: transactions stuck"),
+    }
+}
+
+async fn probe_db(db: &DbExecutor) -> ProbeResult {
+    with_timeout("db", async {
+        match db.probe_writable().await {
+            Ok(()) => ProbeResult::healthy("db"),
+            Err(e) => ProbeResult::new("db", HealthState::Unhealthy, Some(e.to_string())),
+        }
+    })
+    .await
+}
+
+pub async fn healthz(db: DbExecutor) -> impl Responder {
+    let report = check(&db).await;
+    match report.state {
+        HealthState::Unhealthy => HttpResponse::ServiceUnavailable().json(report),
+        _ => HttpResponse::Ok().json(report),
+    }
+}
+
+/// Notifies systemd watchdog as long as the daemon isn't unhealthy.
+pub async fn watchdog(db: DbExecutor) {
+    let interval = match env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|usec| usec.parse::<u64>().ok())
+    {
+        Some(usec) => Duration::from_micros(usec) / 2,
+        None => return,
+    };
+    log::info!("Feeding systemd watchdog every {:?}", interval);
+
+    loop {
+        let report = check(&db).await;
+        if report.state == HealthState::Unhealthy {
+            log::warn!(
+                "Daemon unhealthy, skipping watchdog notification: {:?}",
+                report
+            );
+        } else if let Err(e) = crate::sd_notify(false, "WATCHDOG=1").await {
+            log::warn!("Failed to notify systemd watchdog: {}", e);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -20,6 +20,7 @@ use std::{
 use structopt::{clap, StructOpt};
 use url::Url;
 use ya_activity::service::Activity as ActivityService;
+use ya_events::EventsService;
 use ya_file_logging::start_logger;
 use ya_gsb_api::GsbApiService;
 use ya_identity::service::Identity as IdentityService;
@@ -30,7 +31,7 @@ use ya_payment::PaymentService;
 use ya_persistence::executor::{DbExecutor, DbMixedExecutor};
 use ya_persistence::service::Persistence as PersistenceService;
 use ya_sb_proto::{DEFAULT_GSB_URL, GSB_URL_ENV_VAR};
-use ya_service_api::{CliCtx, CommandOutput, ResponseTable};
+use ya_service_api::{CliCtx, CommandOutput, OutputFormat, ResponseTable};
 use ya_service_api_interfaces::Provider;
 use ya_service_api_web::{
     middleware::{auth, cors::CorsConfig, Identity},
@@ -46,9 +47,12 @@ use ya_service_bus::typed as gsb;
 
 mod autocomplete;
 mod extension;
+mod health;
 mod model;
+mod shutdown;
 
 use crate::extension::Extension;
+use crate::shutdown::{Shutdown, ShutdownTimeouts};
 use autocomplete::CompleteCommand;
 
 use ya_activity::TrackerRef;
@@ -101,10 +105,21 @@ struct CliArgs {
     )]
     gsb_url: Url,
 
-    /// Return results in JSON format
+    /// Return results in JSON format (shortcut for `--output-format json`)
     #[structopt(long, set = clap::ArgSettings::Global)]
     json: bool,
 
+    /// Format of command results
+    #[structopt(
+        long,
+        env = "YAGNA_OUTPUT_FORMAT",
+        default_value = "table",
+        possible_values = OutputFormat::VARIANTS,
+        case_insensitive = true,
+        set = clap::ArgSettings::Global,
+    )]
+    output_format: OutputFormat,
+
     #[structopt(hidden = true)]
     #[structopt(long, set = clap::ArgSettings::Global)]
     quiet: bool,
@@ -135,7 +150,11 @@ impl TryFrom<&CliArgs> for CliCtx {
         Ok(CliCtx {
             data_dir,
             gsb_url: Some(args.gsb_url.clone()),
-            json_output: args.json,
+            output_format: if args.json {
+                OutputFormat::Json
+            } else {
+                args.output_format
+            },
             quiet: args.quiet,
             accept_terms: if cfg!(feature = "tos") {
                 args.accept_terms
@@ -155,6 +174,8 @@ struct ServiceContext {
     default_db: DbExecutor,
     default_mixed: DbMixedExecutor,
     activity_tracker: ya_activity::TrackerRef,
+    activity_graphs: ya_activity::ExecGraphs,
+    payment_agreement_lock: Arc<ya_payment::AgreementLock>,
 }
 
 impl<S: 'static> Provider<S, DbExecutor> for ServiceContext {
@@ -181,6 +202,18 @@ impl<S: 'static> Provider<S, ya_activity::TrackerRef> for ServiceContext {
     }
 }
 
+impl<S: 'static> Provider<S, ya_activity::ExecGraphs> for ServiceContext {
+    fn component(&self) -> ya_activity::ExecGraphs {
+        self.activity_graphs.clone()
+    }
+}
+
+impl<S: 'static> Provider<S, Arc<ya_payment::AgreementLock>> for ServiceContext {
+    fn component(&self) -> Arc<ya_payment::AgreementLock> {
+        self.payment_agreement_lock.clone()
+    }
+}
+
 impl<S: 'static> Provider<S, CliCtx> for ServiceContext {
     fn component(&self) -> CliCtx {
         self.ctx.clone()
@@ -233,6 +266,8 @@ impl TryFrom<CliCtx> for ServiceContext {
             default_db,
             default_mixed: market_db.1,
             activity_tracker,
+            activity_graphs: Default::default(),
+            payment_agreement_lock: ya_payment::AgreementLock::arc(),
         })
     }
 }
@@ -255,6 +290,8 @@ enum Services {
     //TODO enable VpnService::rest for v2 / or create common scope for v1 and v2
     #[enable(rest)]
     Vpn(VpnService),
+    #[enable(gsb)]
+    Events(EventsService),
     #[enable(gsb, rest, cli)]
     Market(MarketService),
     #[enable(gsb, rest, cli)]
@@ -306,6 +343,9 @@ enum CliCommand {
     #[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
     Extension(ExtensionCommand),
 
+    /// Health of the running daemon and its subsystems
+    Status,
+
     #[structopt(external_subcommand)]
     #[structopt(setting = structopt::clap::AppSettings::Hidden)]
     Other(Vec<String>),
@@ -321,11 +361,55 @@ impl CliCommand {
             CliCommand::Complete(complete) => complete.run_command(ctx),
             CliCommand::Service(service) => service.run_command(ctx).await,
             CliCommand::Extension(ext) => ext.run_command(ctx).await,
+            CliCommand::Status => status(ctx).await,
             CliCommand::Other(args) => extension::run::<CliArgs>(ctx, args).await,
         }
     }
 }
 
+async fn status(ctx: &CliCtx) -> Result<CommandOutput> {
+    let report = match gsb::service(model::BUS_ID)
+        .call(model::HealthRequest {})
+        .await
+    {
+        Ok(Ok(report)) => report,
+        Ok(Err(e)) => anyhow::bail!(e),
+        Err(e) => model::HealthReport::new(vec![model::ProbeResult {
+            name: "gsb".to_string(),
+            state: model::HealthState::Unhealthy,
+            reason: Some(format!("daemon unreachable: {}", e)),
+        }]),
+    };
+
+    let unhealthy = report.state == model::HealthState::Unhealthy;
+    if ctx.structured_output() {
+        ctx.output(CommandOutput::object(&report)?)?;
+    } else {
+        ctx.output(CommandOutput::Table {
+            columns: vec!["probe".into(), "state".into(), "reason".into()],
+            values: report
+                .probes
+                .iter()
+                .map(|probe| {
+                    serde_json::json!([
+                        probe.name,
+                        probe.state,
+                        probe.reason.as_deref().unwrap_or(""),
+                    ])
+                })
+                .collect(),
+            summary: vec![serde_json::json!(["", report.state, ""])],
+            header: None,
+        })?;
+    }
+
+    // Non-zero exit code allows using the command as orchestrator probe.
+    if unhealthy {
+        anyhow::bail!("yagna is unhealthy");
+    }
+    Ok(CommandOutput::NoOutput)
+}
+
 #[derive(StructOpt, Debug)]
 enum ExtensionCommand {
     /// List available extensions
@@ -342,7 +426,7 @@ impl ExtensionCommand {
             ExtensionCommand::List {} => {
                 let extensions = Extension::list();
 
-                if ctx.json_output {
+                if ctx.structured_output() {
                     Self::map(extensions.into_iter())
                 } else {
                     Self::table(extensions.into_iter())
@@ -440,6 +524,9 @@ struct ServiceCommandOpts {
 
     #[structopt(flatten)]
     cors: CorsConfig,
+
+    #[structopt(flatten)]
+    shutdown_timeouts: ShutdownTimeouts,
 }
 
 #[cfg(unix)]
@@ -483,6 +570,7 @@ impl ServiceCommand {
                 log_dir,
                 debug,
                 cors,
+                shutdown_timeouts,
             }) => {
                 let is_rust_log_default =
                     env::var("RUST_LOG").map(|s| s.is_empty()).unwrap_or(true);
@@ -563,6 +651,8 @@ impl ServiceCommand {
                     .unwrap_or_else(num_cpus::get)
                     .clamp(1, 256);
                 let count_started = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+                let health_db = context.default_db.clone();
+                let control_db = context.default_db.clone();
                 let server = HttpServer::new(move || {
                     let app = App::new()
                         .wrap(middleware::Logger::default())
@@ -571,6 +661,12 @@ impl ServiceCommand {
                         .route("/dashboard", web::get().to(redirect_to_dashboard))
                         .route("/dashboard/{_:.*}", web::get().to(dashboard_serve))
                         .route("/me", web::get().to(me))
+                        .route("/me/sign", web::post().to(sign))
+                        .route("/app-keys/usage", web::get().to(app_keys_usage))
+                        .route("/healthz", {
+                            let db = health_db.clone();
+                            web::get().to(move || health::healthz(db.clone()))
+                        })
                         .service(forward_gsb);
                     let rest = Services::rest(app, &context);
                     if count_started.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
@@ -593,27 +689,36 @@ impl ServiceCommand {
                 .workers(number_of_workers)
                 // this is maximum supported timeout for our REST API
                 .keep_alive(std::time::Duration::from_secs(*max_rest_timeout))
+                .shutdown_timeout(shutdown_timeouts.shutdown_rest_timeout)
+                // Signals are handled by the shutdown sequence below.
+                .disable_signals()
                 .bind(api_host_port.clone())
                 .context(format!("Failed to bind http server on {:?}", api_host_port))?
                 .run();
+                let server_handle = server.handle();
+                let server = tokio::task::spawn_local(server);
 
                 let _ = extension::autostart(&ctx.data_dir, api_url, &ctx.gsb_url)
                     .await
                     .map_err(|e| log::warn!("Failed to autostart extensions: {e}"));
 
+                let (stop_tx, stop_rx) = futures::channel::mpsc::unbounded();
                 {
-                    let server_handle = server.handle();
                     gsb::bind(model::BUS_ID, move |request: model::ShutdownRequest| {
                         log::info!(
                             "ShutdownRequest {}",
                             request.graceful.then_some("graceful").unwrap_or("")
                         );
-                        let server_handle = server_handle.clone();
-                        async move {
-                            server_handle.stop(request.graceful).await;
-                            Ok(())
-                        }
+                        let _ = stop_tx.unbounded_send(request.graceful);
+                        future::ok(())
+                    });
+
+                    let db = control_db.clone();
+                    gsb::bind(model::BUS_ID, move |_: model::HealthRequest| {
+                        let db = db.clone();
+                        async move { Ok(health::check(&db).await) }
                     });
+                    tokio::task::spawn_local(health::watchdog(control_db));
                 }
 
                 tokio::spawn(async {
@@ -626,16 +731,54 @@ impl ServiceCommand {
                     }
                 });
 
-                future::try_join(server, sd_notify(false, "READY=1")).await?;
+                sd_notify(false, "READY=1").await?;
+                let graceful = shutdown::requested(stop_rx).await;
+
+                let mut shutdown = Shutdown::new(4);
+                shutdown
+                    .stage("Stopping REST API", None, async move {
+                        // Bounded by the server shutdown timeout.
+                        server_handle.stop(graceful).await;
+                        match server.await {
+                            Ok(Ok(())) => (),
+                            Ok(Err(e)) => log::error!("REST API server failed: {}", e),
+                            Err(e) => log::error!("REST API server task failed: {}", e),
+                        }
+                    })
+                    .await;
+                shutdown
+                    .stage(
+                        "Draining event notifications",
+                        Some(Duration::from_secs(
+                            shutdown_timeouts.shutdown_events_timeout,
+                        )),
+                        EventsService::shut_down(),
+                    )
+                    .await;
+                shutdown
+                    .stage(
+                        "Flushing payments",
+                        // Bounded by PAYMENT_SHUTDOWN_TIMEOUT_SECS.
+                        None,
+                        PaymentService::shut_down(),
+                    )
+                    .await;
+                shutdown
+                    .stage(
+                        "Closing network",
+                        Some(Duration::from_secs(shutdown_timeouts.shutdown_net_timeout)),
+                        async {
+                            NetService::shutdown()
+                                .await
+                                .map_err(|e| log::error!("Error shutting down NET: {}", e))
+                                .ok();
+                        },
+                    )
+                    .await;
+                shutdown.finish();
 
                 log::info!("{} service successfully finished!", app_name);
 
-                PaymentService::shut_down().await;
-                NetService::shutdown()
-                    .await
-                    .map_err(|e| log::error!("Error shutting down NET: {}", e))
-                    .ok();
-
                 logger_handle.shutdown();
                 Ok(CommandOutput::NoOutput)
             }
@@ -685,6 +828,86 @@ async fn me(id: Identity) -> impl Responder {
     web::Json(id)
 }
 
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignPayload {
+    /// Hex encoded payload, or its signature in response.
+    data: String,
+}
+
+/// Signs hex encoded payload with the identity of the app-key.
+async fn sign(id: Identity, payload: web::Json<SignPayload>) -> actix_web::Result<impl Responder> {
+    use ya_core_model::identity;
+    use ya_service_bus::RpcEndpoint;
+
+    let payload = hex::decode(&payload.data).map_err(actix_web::error::ErrorBadRequest)?;
+    let signature = gsb::service(identity::BUS_ID)
+        .send(identity::Sign {
+            node_id: id.identity,
+            payload,
+        })
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(web::Json(SignPayload {
+        data: hex::encode(signature),
+    }))
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppKeyUsage {
+    name: String,
+    role: String,
+    identity: ya_client::model::NodeId,
+    created_date: chrono::NaiveDateTime,
+    request_count: u64,
+    last_used_date: Option<chrono::NaiveDateTime>,
+    last_source_ip: Option<String>,
+}
+
+/// Usage of all app-keys, without their secret values. Available only to the manager role.
+async fn app_keys_usage(id: Identity) -> actix_web::Result<impl Responder> {
+    use ya_core_model::appkey;
+    use ya_service_bus::RpcEndpoint;
+
+    if id.role != appkey::DEFAULT_ROLE {
+        return Err(actix_web::error::ErrorForbidden(format!(
+            "Only app-keys with role {} can list app-keys usage",
+            appkey::DEFAULT_ROLE
+        )));
+    }
+
+    let mut page = 1;
+    let mut usage = vec![];
+    loop {
+        let (keys, pages) = gsb::service(appkey::BUS_ID)
+            .send(appkey::List {
+                identity: None,
+                page,
+                per_page: 20,
+            })
+            .await
+            .map_err(actix_web::error::ErrorInternalServerError)?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+        usage.extend(keys.into_iter().map(|key| AppKeyUsage {
+            name: key.name,
+            role: key.role,
+            identity: key.identity,
+            created_date: key.created_date,
+            request_count: key.request_count,
+            last_used_date: key.last_used_date,
+            last_source_ip: key.last_source_ip,
+        }));
+
+        if page >= pages {
+            break;
+        }
+        page += 1;
+    }
+    Ok(web::Json(usage))
+}
+
 #[actix_web::post("/_gsb/{service:.*}")]
 async fn forward_gsb(
     id: Identity,
@@ -15,3 +15,38 @@ impl RpcMessage for ShutdownRequest {
     type Item = ();
     type Error = String;
 }
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthState {
+    Healthy,
+    /// Daemon works, but some functionality is limited.
+    Degraded,
+    Unhealthy,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub name: String,
+    pub state: HealthState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// The worst state of all probes.
+    pub state: HealthState,
+    pub probes: Vec<ProbeResult>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct HealthRequest {}
+
+impl RpcMessage for HealthRequest {
+    const ID: &'static str = "HealthRequest";
+    type Item = HealthReport;
+    type Error = String;
+}
@@ -0,0 +1,139 @@
+//! Coordinated shutdown of the daemon.
+//!
+//! After REST API stops accepting requests, services are stopped stage by stage,
+//! so that each of them can still use GSB and the network to flush its state.
+//! Network goes down last.
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::prelude::*;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct ShutdownTimeouts {
+    /// Time given to in-flight REST requests to complete on shutdown [seconds]
+    #[structopt(long, env = "YAGNA_SHUTDOWN_REST_TIMEOUT_SECS", default_value = "30")]
+    pub shutdown_rest_timeout: u64,
+    /// Time given to deliver queued events on shutdown [seconds]
+    #[structopt(long, env = "YAGNA_SHUTDOWN_EVENTS_TIMEOUT_SECS", default_value = "5")]
+    pub shutdown_events_timeout: u64,
+    /// Time given to network to disconnect on shutdown [seconds]
+    #[structopt(long, env = "YAGNA_SHUTDOWN_NET_TIMEOUT_SECS", default_value = "40")]
+    pub shutdown_net_timeout: u64,
+}
+
+pub struct Shutdown {
+    stages: usize,
+    current: usize,
+    started: Instant,
+    interrupted: bool,
+}
+
+impl Shutdown {
+    pub fn new(stages: usize) -> Self {
+        log::info!("Shutting down... Hit Ctrl+C again to skip remaining stages.");
+        Shutdown {
+            stages,
+            current: 0,
+            started: Instant::now(),
+            interrupted: false,
+        }
+    }
+
+    /// Runs next stage, unless shutdown was interrupted. Stage without timeout
+    /// is expected to bound its duration by itself.
+    pub async fn stage<F>(&mut self, name: &str, timeout: Option<Duration>, stage: F)
+    where
+        F: Future<Output = ()>,
+    {
+        self.current += 1;
+        if self.interrupted {
+            log::warn!(
+                "Shutdown [{}/{}] {}: skipped",
+                self.current,
+                self.stages,
+                name
+            );
+            return;
+        }
+
+        log::info!("Shutdown [{}/{}] {}...", self.current, self.stages, name);
+        let start = Instant::now();
+
+        let stage = async move {
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, stage).await.is_ok(),
+                None => {
+                    stage.await;
+                    true
+                }
+            }
+        };
+        tokio::select! {
+            finished = stage => match finished {
+                true => log::info!(
+                    "Shutdown [{}/{}] {}: done in {:.1}s",
+                    self.current,
+                    self.stages,
+                    name,
+                    start.elapsed().as_secs_f32()
+                ),
+                false => log::warn!(
+                    "Shutdown [{}/{}] {}: timed out after {:.1}s",
+                    self.current,
+                    self.stages,
+                    name,
+                    start.elapsed().as_secs_f32()
+                ),
+            },
+            _ = tokio::signal::ctrl_c().boxed() => {
+                log::warn!(
+                    "Shutdown [{}/{}] {}: interrupted",
+                    self.current,
+                    self.stages,
+                    name
+                );
+                self.interrupted = true;
+            }
+        }
+    }
+
+    pub fn finish(self) {
+        log::info!(
+            "Shutdown finished in {:.1}s",
+            self.started.elapsed().as_secs_f32()
+        );
+    }
+}
+
+/// Waits for Ctrl+C, SIGTERM or a shutdown request sent over GSB.
+/// Returns whether in-flight REST requests should be completed.
+pub async fn requested(mut requests: UnboundedReceiver<bool>) -> bool {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                log::warn!("Failed to listen for SIGTERM: {}", e);
+                future::pending::<()>().await
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    // Same as actix-web signal handling: SIGINT stops REST API immediately.
+    tokio::select! {
+        _ = tokio::signal::ctrl_c().boxed() => {
+            log::info!("SIGINT received");
+            false
+        }
+        _ = terminate => {
+            log::info!("SIGTERM received");
+            true
+        }
+        graceful = requests.next() => graceful.unwrap_or(true),
+    }
+}
@@ -51,7 +51,7 @@ impl VersionCLI {
 
 async fn show(msg: version::Get, ctx: &CliCtx) -> anyhow::Result<CommandOutput> {
     let version_info = bus::service(version::BUS_ID).send(msg).await??;
-    if ctx.json_output {
+    if ctx.structured_output() {
         return CommandOutput::object(version_info);
     }
     CommandOutput::object(match &version_info.pending {
@@ -0,0 +1,172 @@
+//! DNS responder of a virtual private network.
+//!
+//! Resolves host names assigned by the requestor to virtual IP addresses, so workloads
+//! can address each other by name instead of hardcoding IPs. Queries are answered on
+//! port 53 of requestor's addresses within the network (the gateway by default).
+//! Names are not forwarded anywhere else, unknown ones resolve to NXDOMAIN.
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use trust_dns_resolver::proto::op::{Message, MessageType, ResponseCode};
+use trust_dns_resolver::proto::rr::{Name, RData, Record, RecordType};
+
+use crate::Result;
+use ya_utils_networking::vpn::Error;
+
+pub const DNS_PORT: u16 = 53;
+const TTL: u32 = 60;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Host {
+    pub name: String,
+    pub ip: String,
+}
+
+#[derive(Default)]
+pub struct Hosts {
+    hosts: BTreeMap<String, IpAddr>,
+}
+
+impl Hosts {
+    /// Assigns the name to the address. Previous address of the name is replaced.
+    pub fn add(&mut self, name: &str, ip: IpAddr) -> Result<()> {
+        self.hosts.insert(host_name(name)?, ip);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        match self.hosts.remove(&host_name(name)?) {
+            Some(_) => Ok(()),
+            None => Err(Error::Other(format!("Unknown host name: {name}"))),
+        }
+    }
+
+    /// Removes names of given addresses.
+    pub fn remove_ips(&mut self, ips: &[IpAddr]) {
+        self.hosts.retain(|_, ip| !ips.contains(ip));
+    }
+
+    pub fn list(&self) -> Vec<Host> {
+        self.hosts
+            .iter()
+            .map(|(name, ip)| Host {
+                name: name.clone(),
+                ip: ip.to_string(),
+            })
+            .collect()
+    }
+
+    /// Builds response to the DNS query. `None`, if the packet is not a valid query.
+    pub fn answer(&self, packet: &[u8]) -> Option<Vec<u8>> {
+        let request = Message::from_vec(packet).ok()?;
+        if request.message_type() != MessageType::Query {
+            return None;
+        }
+
+        let mut code = ResponseCode::NoError;
+        let mut answers = Vec::new();
+        for query in request.queries() {
+            let name = query.name().to_utf8().trim_end_matches('.').to_lowercase();
+            let ip = match self.hosts.get(&name) {
+                Some(ip) => *ip,
+                None => {
+                    code = ResponseCode::NXDomain;
+                    continue;
+                }
+            };
+            let rdata = match (query.query_type(), ip) {
+                (RecordType::A, IpAddr::V4(ip)) => RData::A(ip),
+                (RecordType::AAAA, IpAddr::V6(ip)) => RData::AAAA(ip),
+                // Name exists, but there is no record of requested type.
+                _ => continue,
+            };
+            answers.push(Record::from_rdata(query.name().clone(), TTL, rdata));
+        }
+
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(request.op_code())
+            .set_authoritative(true)
+            .set_recursion_desired(request.recursion_desired())
+            .set_response_code(code)
+            .add_queries(request.queries().iter().cloned())
+            .add_answers(answers);
+        response.to_vec().ok()
+    }
+}
+
+/// Lowercase name without trailing dot, if it is a valid host name.
+fn host_name(name: &str) -> Result<String> {
+    let host = name.trim_end_matches('.').to_lowercase();
+    let valid = !host.is_empty()
+        && host.len() <= 253
+        && Name::from_ascii(&host).is_ok()
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    match valid {
+        true => Ok(host),
+        false => Err(Error::Other(format!("Invalid host name: {name}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trust_dns_resolver::proto::op::Query;
+
+    fn query(name: &str, query_type: RecordType) -> Vec<u8> {
+        let mut request = Message::new();
+        request
+            .set_id(7)
+            .set_message_type(MessageType::Query)
+            .set_recursion_desired(true)
+            .add_query(Query::query(Name::from_ascii(name).unwrap(), query_type));
+        request.to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_host_name() {
+        assert_eq!(host_name("Worker-1.").unwrap(), "worker-1");
+        assert_eq!(host_name("db.cluster").unwrap(), "db.cluster");
+        assert!(host_name("").is_err());
+        assert!(host_name("-worker").is_err());
+        assert!(host_name("worker_1").is_err());
+        assert!(host_name("db..cluster").is_err());
+    }
+
+    #[test]
+    fn test_answer() {
+        let mut hosts = Hosts::default();
+        hosts.add("worker-1", "10.0.0.2".parse().unwrap()).unwrap();
+
+        let response =
+            Message::from_vec(&hosts.answer(&query("Worker-1.", RecordType::A)).unwrap()).unwrap();
+        assert_eq!(response.id(), 7);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(
+            response.answers()[0].data(),
+            Some(&RData::A("10.0.0.2".parse().unwrap()))
+        );
+
+        let response =
+            Message::from_vec(&hosts.answer(&query("worker-1", RecordType::AAAA)).unwrap())
+                .unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(response.answers().is_empty());
+
+        let response =
+            Message::from_vec(&hosts.answer(&query("worker-2", RecordType::A)).unwrap()).unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+
+        hosts.remove_ips(&["10.0.0.2".parse().unwrap()]);
+        assert!(hosts.list().is_empty());
+        assert!(hosts.answer(&[0, 1, 2]).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+mod dns;
 mod message;
 mod network;
 mod requestor;
@@ -1,3 +1,4 @@
+use crate::dns::Host;
 use crate::Result;
 use actix::{Message, Recipient};
 use futures::channel::mpsc;
@@ -37,6 +38,23 @@ pub struct RemoveNode {
     pub id: String,
 }
 
+#[derive(Debug, Message)]
+#[rtype(result = "Result<Vec<Host>>")]
+pub struct GetHosts;
+
+#[derive(Debug, Message)]
+#[rtype(result = "Result<()>")]
+pub struct AddHost {
+    pub name: String,
+    pub address: String,
+}
+
+#[derive(Debug, Message)]
+#[rtype(result = "Result<()>")]
+pub struct RemoveHost {
+    pub name: String,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Message)]
 #[rtype(result = "Result<Vec<Connection>>")]
@@ -1,4 +1,5 @@
 use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
 use std::net::IpAddr;
 use std::rc::Rc;
 use std::str::FromStr;
@@ -7,14 +8,16 @@ use actix::prelude::*;
 use futures::channel::oneshot::Canceled;
 use futures::channel::{mpsc, oneshot};
 use futures::{future, future::BoxFuture, Future, FutureExt, SinkExt, StreamExt, TryFutureExt};
-use smoltcp::iface::Route;
+use smoltcp::iface::{Route, SocketHandle};
 use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr, IpEndpoint};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use uuid::Uuid;
 
 use ya_utils_networking::vpn::socket::TCP_CONN_TIMEOUT;
+use ya_utils_networking::vpn::stack::connection::ConnectionMeta;
 use ya_utils_networking::vpn::stack::interface::{add_iface_address, add_iface_route, tap_iface};
 
+use crate::dns::{Hosts, DNS_PORT};
 use crate::message::*;
 use crate::Result;
 
@@ -214,6 +217,8 @@ pub struct Vpn {
     vpn: Network<network::DuoEndpoint<Endpoint>>,
     stack_network: net::Network,
     connections: HashMap<SocketDesc, InternalConnection>,
+    hosts: Hosts,
+    dns: Option<SocketHandle>,
 }
 
 impl Vpn {
@@ -227,8 +232,49 @@ impl Vpn {
             vpn,
             stack_network,
             connections: Default::default(),
+            hosts: Default::default(),
+            dns: None,
         }
     }
+
+    /// Binds DNS responder socket to the first address of the requestor.
+    fn bind_dns(&mut self) -> Result<()> {
+        let ip = self
+            .stack_network
+            .stack
+            .addresses()
+            .first()
+            .map(|cidr| cidr.address())
+            .ok_or_else(|| Error::Other("No IP address found".into()))?;
+        let handle = self
+            .stack_network
+            .bind(Protocol::Udp, IpEndpoint::new(ip, DNS_PORT))?;
+        self.dns = Some(handle);
+        Ok(())
+    }
+
+    /// Answers DNS query, if the packet was received by the responder socket.
+    fn answer_dns(
+        &self,
+        desc: SocketDesc,
+        payload: &[u8],
+    ) -> Option<impl Future<Output = Result<()>> + 'static> {
+        let handle = self.dns?;
+        match desc.local {
+            SocketEndpoint::Ip(endpoint)
+                if desc.protocol == Protocol::Udp && endpoint.port == DNS_PORT => {}
+            _ => return None,
+        }
+
+        let response = self.hosts.answer(payload)?;
+        let meta = ConnectionMeta::try_from(desc).ok()?;
+        let connection = stack::Connection { handle, meta };
+        Some(
+            self.stack_network
+                .send(response, connection)
+                .map_err(|e| Error::Other(e.to_string())),
+        )
+    }
 }
 
 impl Actor for Vpn {
@@ -261,6 +307,10 @@ impl Actor for Vpn {
             .into_actor(self)
             .spawn(ctx);
 
+        if let Err(e) = self.bind_dns() {
+            log::warn!("VPN {id}: unable to start DNS responder: {e}");
+        }
+
         log::info!("VPN {id} started");
     }
 
@@ -387,6 +437,10 @@ impl Handler<RemoveNode> for Vpn {
     type Result = <RemoveNode as Message>::Result;
 
     fn handle(&mut self, msg: RemoveNode, _: &mut Self::Context) -> Self::Result {
+        if let Some(ips) = self.vpn.nodes().get(&msg.id) {
+            let ips = ips.iter().cloned().collect::<Vec<_>>();
+            self.hosts.remove_ips(&ips);
+        }
         self.vpn.remove_node(&msg.id);
 
         let vpn_id = self.vpn.id().clone();
@@ -411,6 +465,41 @@ impl Handler<RemoveNode> for Vpn {
     }
 }
 
+impl Handler<GetHosts> for Vpn {
+    type Result = <GetHosts as Message>::Result;
+
+    fn handle(&mut self, _: GetHosts, _: &mut Self::Context) -> Self::Result {
+        Ok(self.hosts.list())
+    }
+}
+
+impl Handler<AddHost> for Vpn {
+    type Result = <AddHost as Message>::Result;
+
+    fn handle(&mut self, msg: AddHost, _: &mut Self::Context) -> Self::Result {
+        log::info!(
+            "Network: {} resolving host: {} to {}",
+            self.vpn.id(),
+            msg.name,
+            msg.address
+        );
+
+        let ip = to_ip(&msg.address)?;
+        if !self.vpn.as_ref().contains(&ip) {
+            return Err(Error::NetAddrMismatch(ip));
+        }
+        self.hosts.add(&msg.name, ip)
+    }
+}
+
+impl Handler<RemoveHost> for Vpn {
+    type Result = <RemoveHost as Message>::Result;
+
+    fn handle(&mut self, msg: RemoveHost, _: &mut Self::Context) -> Self::Result {
+        self.hosts.remove(&msg.name)
+    }
+}
+
 impl Handler<Connect> for Vpn {
     type Result = ActorResponse<Self, Result<UserConnection>>;
 
@@ -584,6 +673,10 @@ impl Handler<Ingress> for Vpn {
             IngressEvent::Packet { payload, desc, .. } => {
                 ya_packet_trace::packet_trace!("Vpn::Tx::Handler<Ingress>", { &payload });
 
+                if let Some(fut) = self.answer_dns(desc, &payload) {
+                    return ActorResponse::r#async(fut.into_actor(self));
+                }
+
                 if let Some(mut connection) = self.connections.get(&desc).cloned() {
                     log::debug!("[vpn] ingress proxy: send to {:?}", desc.local);
 
@@ -1,5 +1,6 @@
 #![allow(clippy::let_unit_value)]
 
+use crate::dns::Host;
 use crate::message::*;
 use crate::network::VpnSupervisor;
 use actix::prelude::*;
@@ -50,6 +51,9 @@ fn vpn_web_scope(path: &str) -> actix_web::Scope {
         .service(get_nodes)
         .service(add_node)
         .service(remove_node)
+        .service(get_hosts)
+        .service(add_host)
+        .service(remove_host)
         .service(connect_tcp)
 }
 
@@ -199,6 +203,62 @@ async fn remove_node(
     Ok::<_, ApiError>(web::Json(fut.await?))
 }
 
+/// Retrieves host names resolved by the DNS responder of a virtual private network.
+#[actix_web::get("/net/{net_id}/hosts")]
+async fn get_hosts(
+    vpn_sup: web::Data<Arc<Mutex<VpnSupervisor>>>,
+    path: web::Path<PathNetwork>,
+    identity: Identity,
+) -> impl Responder {
+    let path = path.into_inner();
+    let vpn = {
+        let supervisor = vpn_sup.lock().await;
+        supervisor.get_network(&identity.identity, &path.net_id)?
+    };
+    let response = vpn.send(GetHosts {}).await??;
+    Ok::<_, ApiError>(web::Json(response))
+}
+
+/// Assigns a host name to an address within a virtual private network.
+/// Workloads can resolve it with DNS queries sent to requestor's address.
+#[actix_web::post("/net/{net_id}/hosts")]
+async fn add_host(
+    vpn_sup: web::Data<Arc<Mutex<VpnSupervisor>>>,
+    path: web::Path<PathNetwork>,
+    model: web::Json<Host>,
+    identity: Identity,
+) -> impl Responder {
+    let path = path.into_inner();
+    let vpn = {
+        let supervisor = vpn_sup.lock().await;
+        supervisor.get_network(&identity.identity, &path.net_id)?
+    };
+    let host = model.into_inner();
+    let response = vpn
+        .send(AddHost {
+            name: host.name,
+            address: host.ip,
+        })
+        .await??;
+    Ok::<_, ApiError>(web::Json(response))
+}
+
+/// Removes a host name from a virtual private network.
+#[actix_web::delete("/net/{net_id}/hosts/{name}")]
+async fn remove_host(
+    vpn_sup: web::Data<Arc<Mutex<VpnSupervisor>>>,
+    path: web::Path<PathNetworkHost>,
+    identity: Identity,
+) -> impl Responder {
+    let path = path.into_inner();
+    let vpn = {
+        let supervisor = vpn_sup.lock().await;
+        supervisor.get_network(&identity.identity, &path.net_id)?
+    };
+    let response = vpn.send(RemoveHost { name: path.name }).await??;
+    Ok::<_, ApiError>(web::Json(response))
+}
+
 /// Initiates a new TCP connection via WebSockets to the destination address.
 #[actix_web::get("/net/{net_id}/tcp/{ip}/{port}")]
 async fn connect_tcp(
@@ -376,6 +436,12 @@ struct PathNetworkNode {
     node_id: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PathNetworkHost {
+    net_id: String,
+    name: String,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 struct PathConnect {
     net_id: String,
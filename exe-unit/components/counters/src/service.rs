@@ -13,6 +13,9 @@ use std::time::Duration;
 
 use ya_agreement_utils::AgreementView;
 
+/// Prefix of usage counter properties. Runtimes may report custom counters without it.
+pub const USAGE_COUNTER_PREFIX: &str = "golem.usage.";
+
 pub struct CountersServiceBuilder {
     usage_vector: Vec<String>,
     backlog_limit: Option<usize>,
@@ -140,9 +143,17 @@ impl Handler<SetCounter> for CountersService {
     type Result = ();
 
     fn handle(&mut self, msg: SetCounter, ctx: &mut Self::Context) -> Self::Result {
-        match self.counters.get_mut(&msg.name) {
+        if !msg.value.is_finite() || msg.value < 0. {
+            log::warn!("Invalid value of counter {}: {}", msg.name, msg.value);
+            return;
+        }
+        let name = match self.counters.contains_key(&msg.name) {
+            true => msg.name,
+            false => format!("{USAGE_COUNTER_PREFIX}{}", msg.name),
+        };
+        match self.counters.get_mut(&name) {
             Some(provider) => provider.counter.set(msg.value),
-            None => log::debug!("Unknown counter: {}", msg.name),
+            None => log::debug!("Unknown counter: {}", name),
         }
     }
 }
@@ -0,0 +1,209 @@
+use anyhow::bail;
+use reqwest::{RequestBuilder, Url};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/*
+Credentials injected into requests forwarded to the service inside the container.
+They are read from the Provider's local secrets file, so the service can stay
+protected without sharing credentials with Requestors. Headers of the same name
+sent by the Requestor are dropped. `GsbToHttpProxy` loads the file pointed by
+`YA_HTTP_PROXY_SECRETS` env variable.
+
+Path prefixes are matched against the path of the final URL, as normalized by the URL
+parser. Requests with `..` path segments aren't forwarded, since the service could
+resolve them differently.
+
+[
+  { "type": "bearer", "token": "..." },
+  { "type": "basic", "path-prefix": "/admin/", "username": "...", "password": "..." },
+  { "type": "header", "name": "X-Api-Key", "value": "..." }
+]
+ */
+
+const AUTHORIZATION: &str = "authorization";
+pub const SECRETS_ENV: &str = "YA_HTTP_PROXY_SECRETS";
+
+#[derive(Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum Credential {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+    Header { name: String, value: String },
+}
+
+impl Credential {
+    fn header_name(&self) -> String {
+        match self {
+            Credential::Bearer { .. } | Credential::Basic { .. } => AUTHORIZATION.to_string(),
+            Credential::Header { name, .. } => name.to_lowercase(),
+        }
+    }
+
+    fn apply(&self, builder: RequestBuilder) -> RequestBuilder {
+        match self {
+            Credential::Bearer { token } => builder.bearer_auth(token),
+            Credential::Basic { username, password } => {
+                builder.basic_auth(username, Some(password))
+            }
+            Credential::Header { name, value } => builder.header(name, value),
+        }
+    }
+}
+
+// Secret values must not end up in logs.
+impl std::fmt::Debug for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: <hidden>", self.header_name())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuthRule {
+    /// Credential is injected only into requests with matching path. All requests by default.
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    #[serde(flatten)]
+    pub credential: Credential,
+}
+
+/// Credentials of the service behind `GsbToHttpProxy`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct AuthHeaders {
+    rules: Vec<AuthRule>,
+}
+
+impl AuthHeaders {
+    pub fn new(rules: Vec<AuthRule>) -> Self {
+        AuthHeaders { rules }
+    }
+
+    /// Loads rules from JSON secrets file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if std::fs::metadata(path)?.permissions().mode() & 0o077 != 0 {
+                log::warn!(
+                    "Secrets file {} is accessible by other users",
+                    path.display()
+                );
+            }
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid secrets file {}: {e}", path.display()))
+    }
+
+    /// Loads rules from secrets file pointed by `YA_HTTP_PROXY_SECRETS`, if set.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        match std::env::var_os(SECRETS_ENV) {
+            Some(path) => Self::load(&PathBuf::from(path)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn matching<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a Credential> {
+        self.rules
+            .iter()
+            .filter(move |rule| match &rule.path_prefix {
+                Some(prefix) => path.starts_with(prefix.as_str()),
+                None => true,
+            })
+            .map(|rule| &rule.credential)
+    }
+
+    /// Drops Requestor headers overridden by credentials matching `path`.
+    pub fn filter(&self, path: &str, headers: &mut HashMap<String, Vec<String>>) {
+        let overridden = self
+            .matching(path)
+            .map(Credential::header_name)
+            .collect::<Vec<_>>();
+        if !overridden.is_empty() {
+            headers.retain(|name, _| !overridden.contains(&name.to_lowercase()));
+        }
+    }
+
+    pub fn inject(&self, path: &str, builder: RequestBuilder) -> RequestBuilder {
+        self.matching(path)
+            .fold(builder, |builder, credential| credential.apply(builder))
+    }
+}
+
+/// Path of `url`, against which path prefixes are matched.
+pub fn request_path(url: &str) -> anyhow::Result<String> {
+    let decoded = url
+        .to_lowercase()
+        .replace("%2e", ".")
+        .replace("%2f", "/")
+        .replace("%5c", "/")
+        .replace('\\', "/");
+    let path = decoded.split(['?', '#']).next().unwrap_or_default();
+    if path.split('/').any(|segment| segment == "..") {
+        bail!("Path segment '..' is not allowed: {url}");
+    }
+    Ok(Url::parse(url)?.path().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth_headers() -> AuthHeaders {
+        serde_json::from_str(
+            r#"[
+                { "type": "bearer", "token": "t0k3n" },
+                { "type": "header", "path-prefix": "/admin/", "name": "X-Api-Key", "value": "k3y" }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_requestor_headers_are_overridden() {
+        let auth = auth_headers();
+        let mut headers = HashMap::from([
+            (
+                "Authorization".to_string(),
+                vec!["Bearer other".to_string()],
+            ),
+            ("x-api-key".to_string(), vec!["other".to_string()]),
+            ("accept".to_string(), vec!["*/*".to_string()]),
+        ]);
+
+        auth.filter("/public/", &mut headers);
+        let mut names = headers.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["accept", "x-api-key"]);
+
+        auth.filter("/admin/users", &mut headers);
+        assert_eq!(headers.keys().collect::<Vec<_>>(), vec!["accept"]);
+    }
+
+    #[test]
+    fn test_request_path_is_normalized() {
+        let auth = auth_headers();
+        let path = request_path("http://127.0.0.1:8080/./admin/users?page=1").unwrap();
+        assert_eq!(path, "/admin/users");
+        assert_eq!(auth.matching(&path).count(), 2);
+
+        for url in [
+            "http://127.0.0.1:8080/admin/../public",
+            "http://127.0.0.1:8080/admin/%2E%2e/public",
+            "http://127.0.0.1:8080/admin/..%2fpublic",
+            "http://127.0.0.1:8080/admin\\..\\public",
+        ] {
+            assert!(request_path(url).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn test_secrets_are_not_logged() {
+        let formatted = format!("{:?}", auth_headers());
+        assert!(!formatted.contains("t0k3n"));
+        assert!(!formatted.contains("k3y"));
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::response::GsbHttpCallResponse;
+
+const DEFAULT_TTL: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_ENTRIES: usize = 256;
+const DEFAULT_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// Configuration of `GsbToHttpProxy` response cache.
+/// Only successful responses to `GET` requests are cached.
+#[derive(Clone, Debug)]
+pub struct ResponseCacheConfig {
+    /// Time for which response is served from cache.
+    pub ttl: Duration,
+    /// Maximum number of cached responses.
+    pub max_entries: usize,
+    /// Maximum total size of cached response bodies in bytes.
+    /// Bigger responses aren't cached at all.
+    pub max_size: usize,
+    /// Request headers, which are part of the cache key (case insensitive).
+    /// Requests differing in other headers share cached response.
+    pub key_headers: Vec<String>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        ResponseCacheConfig {
+            ttl: DEFAULT_TTL,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_size: DEFAULT_MAX_SIZE,
+            key_headers: vec!["accept".to_string(), "authorization".to_string()],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    response: GsbHttpCallResponse,
+    inserted: Instant,
+    /// Insertion order, used for eviction.
+    seq: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    config: ResponseCacheConfig,
+    entries: HashMap<CacheKey, CacheEntry>,
+    size: usize,
+    next_seq: u64,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(mut config: ResponseCacheConfig) -> Self {
+        config
+            .key_headers
+            .iter_mut()
+            .for_each(|name| *name = name.to_lowercase());
+        ResponseCache {
+            config,
+            entries: HashMap::new(),
+            size: 0,
+            next_seq: 0,
+        }
+    }
+
+    /// Returns key, if request is cacheable.
+    pub(crate) fn key(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &HashMap<String, Vec<String>>,
+    ) -> Option<CacheKey> {
+        if !method.eq_ignore_ascii_case("GET") || forbids_caching(headers) {
+            return None;
+        }
+        let headers = headers
+            .iter()
+            .map(|(name, values)| (name.to_lowercase(), values))
+            .filter(|(name, _)| self.config.key_headers.contains(name))
+            .map(|(name, values)| {
+                let mut values = values.clone();
+                values.sort();
+                (name, values)
+            })
+            .collect();
+        Some(CacheKey {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            headers,
+        })
+    }
+
+    pub(crate) fn get(&mut self, key: &CacheKey) -> Option<GsbHttpCallResponse> {
+        let now = Instant::now();
+        match self.entries.get(key) {
+            Some(entry) if now.duration_since(entry.inserted) < self.config.ttl => {
+                Some(entry.response.clone())
+            }
+            Some(_) => {
+                self.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: CacheKey, response: &GsbHttpCallResponse) {
+        let status = response.header.status_code;
+        let entry_size = response.body.msg_bytes.len();
+        if !(200..300).contains(&status)
+            || entry_size > self.config.max_size
+            || self.config.max_entries == 0
+            || forbids_caching(&response.header.response_headers)
+        {
+            return;
+        }
+
+        self.remove(&key);
+        self.remove_expired();
+        while self.entries.len() >= self.config.max_entries
+            || self.size + entry_size > self.config.max_size
+        {
+            match self.oldest() {
+                Some(oldest) => self.remove(&oldest),
+                None => break,
+            }
+        }
+
+        self.size += entry_size;
+        self.next_seq += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                response: response.clone(),
+                inserted: Instant::now(),
+                seq: self.next_seq,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size -= entry.response.body.msg_bytes.len();
+        }
+    }
+
+    fn remove_expired(&mut self) {
+        let now = Instant::now();
+        let ttl = self.config.ttl;
+        let expired: Vec<CacheKey> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| now.duration_since(entry.inserted) >= ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        expired.iter().for_each(|key| self.remove(key));
+    }
+
+    fn oldest(&self) -> Option<CacheKey> {
+        self.entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.seq)
+            .map(|(key, _)| key.clone())
+    }
+}
+
+fn forbids_caching(headers: &HashMap<String, Vec<String>>) -> bool {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("cache-control"))
+        .flat_map(|(_, values)| values)
+        .any(|value| {
+            let value = value.to_lowercase();
+            value.contains("no-store") || value.contains("no-cache")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> GsbHttpCallResponse {
+        GsbHttpCallResponse::new(body.as_bytes().to_vec(), HashMap::new(), 200)
+    }
+
+    fn headers(accept: &str, other: &str) -> HashMap<String, Vec<String>> {
+        HashMap::from([
+            ("Accept".to_string(), vec![accept.to_string()]),
+            ("X-Request-Id".to_string(), vec![other.to_string()]),
+        ])
+    }
+
+    #[test]
+    fn key_uses_configured_headers_only() {
+        let cache = ResponseCache::new(Default::default());
+
+        let key = cache.key("get", "/status", &headers("text/plain", "1"));
+        assert_eq!(
+            key,
+            cache.key("GET", "/status", &headers("text/plain", "2"))
+        );
+        assert_ne!(key, cache.key("GET", "/status", &headers("text/html", "1")));
+        assert_eq!(
+            None,
+            cache.key("POST", "/status", &headers("text/plain", "1"))
+        );
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted() {
+        let mut cache = ResponseCache::new(ResponseCacheConfig {
+            max_entries: 2,
+            max_size: 8,
+            ..Default::default()
+        });
+        let key = |path: &str| cache.key("GET", path, &HashMap::new()).unwrap();
+        let (first, second, third) = (key("/1"), key("/2"), key("/3"));
+
+        cache.insert(first.clone(), &response("one"));
+        cache.insert(second.clone(), &response("two"));
+        cache.insert(third.clone(), &response("three"));
+        assert_eq!(None, cache.get(&first));
+        assert_eq!(Some(response("two")), cache.get(&second));
+        assert_eq!(Some(response("three")), cache.get(&third));
+
+        // Bigger than max size
+        cache.insert(first.clone(), &response("123456789"));
+        assert_eq!(None, cache.get(&first));
+    }
+
+    #[test]
+    fn expired_entries_are_not_served() {
+        let mut cache = ResponseCache::new(ResponseCacheConfig {
+            ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        let key = cache.key("GET", "/status", &HashMap::new()).unwrap();
+        cache.insert(key.clone(), &response("status"));
+        assert_eq!(None, cache.get(&key));
+    }
+}
@@ -13,6 +13,8 @@ use ya_counters::{Counter, CounterData};
 pub(super) struct Counters {
     requests_count: Option<RequestCounter>,
     requests_duration: Option<SharedRequestsDurationCounter>,
+    cache_hits: Option<RequestCounter>,
+    cache_misses: Option<RequestCounter>,
 }
 
 impl Counters {
@@ -28,6 +30,28 @@ impl Counters {
             .clone()
     }
 
+    pub(super) fn cache_hits_counter(&mut self) -> impl Counter {
+        self.cache_hits.get_or_insert_with(Default::default).clone()
+    }
+
+    pub(super) fn cache_misses_counter(&mut self) -> impl Counter {
+        self.cache_misses
+            .get_or_insert_with(Default::default)
+            .clone()
+    }
+
+    pub(super) fn on_cache_hit(&mut self) {
+        if let Some(counter) = &mut self.cache_hits {
+            counter.on_request();
+        }
+    }
+
+    pub(super) fn on_cache_miss(&mut self) {
+        if let Some(counter) = &mut self.cache_misses {
+            counter.on_request();
+        }
+    }
+
     pub(super) fn on_request(&mut self) -> ResponseHandler {
         if let Some(counter) = &mut self.requests_count {
             counter.on_request();
@@ -1,3 +1,5 @@
+use crate::auth::{self, AuthHeaders};
+use crate::cache::{ResponseCache, ResponseCacheConfig};
 use crate::counters::Counters;
 use crate::headers;
 use crate::message::{GsbHttpCallMessage, GsbHttpCallStreamingMessage};
@@ -6,6 +8,7 @@ use crate::response::{
     GsbHttpCallResponseStreamChunk,
 };
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use ya_counters::Counter;
 
@@ -24,6 +27,8 @@ use ya_service_bus::{typed as bus, Handle};
 pub struct GsbToHttpProxy {
     base_url: String,
     counters: Counters,
+    cache: Option<Arc<Mutex<ResponseCache>>>,
+    auth: Option<Arc<AuthHeaders>>,
 }
 
 #[derive(Error, Debug)]
@@ -44,13 +49,37 @@ impl Display for GsbToHttpProxyError {
 }
 
 impl GsbToHttpProxy {
+    /// Credentials are loaded from secrets file pointed by `YA_HTTP_PROXY_SECRETS`,
+    /// if set. See `with_auth`.
     pub fn new(base_url: String) -> Self {
+        let auth = match AuthHeaders::from_env() {
+            Ok(auth) => auth.map(Arc::new),
+            Err(e) => {
+                log::error!("Credentials won't be injected into forwarded requests: {e}");
+                None
+            }
+        };
         GsbToHttpProxy {
             base_url,
             counters: Default::default(),
+            cache: None,
+            auth,
         }
     }
 
+    /// Enables caching of responses to `GET` requests passed with `pass`.
+    /// Cache is shared between clones of the proxy.
+    pub fn with_cache(mut self, config: ResponseCacheConfig) -> Self {
+        self.cache = Some(Arc::new(Mutex::new(ResponseCache::new(config))));
+        self
+    }
+
+    /// Injects credentials into forwarded requests, replacing Requestor's headers.
+    pub fn with_auth(mut self, auth: AuthHeaders) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
     pub fn bind(&mut self, gsb_path: &str) -> Handle {
         let this = self.clone();
         bus::bind(gsb_path, move |message: GsbHttpCallMessage| {
@@ -73,6 +102,25 @@ impl GsbToHttpProxy {
 
         let mut counters = self.counters.clone();
 
+        let cache_key = self.cache.as_ref().and_then(|cache| {
+            let cache = cache.lock().ok()?;
+            cache.key(&message.method, &message.path, &message.headers)
+        });
+        if let Some(key) = &cache_key {
+            let cached = self
+                .cache
+                .as_ref()
+                .and_then(|cache| cache.lock().ok()?.get(key));
+            match cached {
+                Some(response) => {
+                    log::debug!("Serving {} from cache", &url);
+                    counters.on_cache_hit();
+                    return response;
+                }
+                None => counters.on_cache_miss(),
+            }
+        }
+
         let method = match Method::from_bytes(message.method.to_uppercase().as_bytes()) {
             Ok(method) => method,
             Err(err) => {
@@ -82,7 +130,27 @@ impl GsbToHttpProxy {
                 )
             }
         };
-        let builder = Self::create_request_builder(method, &url, message.headers, message.body);
+        let auth = match self
+            .auth
+            .as_deref()
+            .map(|auth| (auth, auth::request_path(&url)))
+        {
+            Some((auth, Ok(path))) => Some((auth, path)),
+            Some((_, Err(err))) => {
+                return GsbHttpCallResponse::with_message(
+                    err.to_string().into_bytes(),
+                    StatusCode::BAD_REQUEST.as_u16(),
+                )
+            }
+            None => None,
+        };
+        let builder = Self::create_request_builder(
+            method,
+            &url,
+            auth.as_ref().map(|(auth, path)| (*auth, path.as_str())),
+            message.headers,
+            message.body,
+        );
 
         log::debug!("Calling {}", &url);
         let response_handler = counters.on_request();
@@ -98,7 +166,14 @@ impl GsbToHttpProxy {
                 response_handler.on_response();
                 match response.bytes().await {
                     Ok(bytes) => {
-                        GsbHttpCallResponse::new(bytes.to_vec(), response_headers, status_code)
+                        let response =
+                            GsbHttpCallResponse::new(bytes.to_vec(), response_headers, status_code);
+                        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                            if let Ok(mut cache) = cache.lock() {
+                                cache.insert(key, &response);
+                            }
+                        }
+                        response
                     }
                     Err(err) => GsbHttpCallResponse::with_message(
                         format!("Error in response: {err}").into_bytes(),
@@ -116,7 +191,8 @@ impl GsbToHttpProxy {
     fn create_request_builder(
         method: Method,
         url: &String,
-        headers: HashMap<String, Vec<String>>,
+        auth: Option<(&AuthHeaders, &str)>,
+        mut headers: HashMap<String, Vec<String>>,
         body: Option<Vec<u8>>,
     ) -> RequestBuilder {
         let mut builder = reqwest::Client::new().request(method, url);
@@ -124,7 +200,13 @@ impl GsbToHttpProxy {
             Some(body) => builder.body(body),
             None => builder,
         };
-        headers::add(builder, headers)
+        match auth {
+            Some((auth, path)) => {
+                auth.filter(path, &mut headers);
+                auth.inject(path, headers::add(builder, headers))
+            }
+            None => headers::add(builder, headers),
+        }
     }
 
     pub fn pass_streaming(
@@ -136,35 +218,32 @@ impl GsbToHttpProxy {
         let (tx, mut rx) = mpsc::channel(1);
 
         let mut counters = self.counters.clone();
+        let auth = self.auth.clone();
         tokio::task::spawn_local(
             async move {
                 let method = match Method::from_bytes(message.method.to_uppercase().as_bytes()) {
                     Ok(method) => method,
                     Err(_) => {
-                        tx.send(
-                            GsbHttpCallResponseHeader {
-                                response_headers: Default::default(),
-                                status_code: StatusCode::METHOD_NOT_ALLOWED.as_u16(),
-                            }
-                            .into(),
-                        )
-                        .await?;
-
-                        tx.send(
-                            GsbHttpCallResponseBody {
-                                msg_bytes: format!("Invalid method {}", message.method)
-                                    .into_bytes(),
-                            }
-                            .into(),
-                        )
-                        .await?;
-
-                        return Ok(());
+                        let msg = format!("Invalid method {}", message.method);
+                        return Self::send_error(&tx, StatusCode::METHOD_NOT_ALLOWED, msg).await;
+                    }
+                };
+                let auth = match auth.as_deref().map(|auth| (auth, auth::request_path(&url))) {
+                    Some((auth, Ok(path))) => Some((auth, path)),
+                    Some((_, Err(err))) => {
+                        return Self::send_error(&tx, StatusCode::BAD_REQUEST, err.to_string())
+                            .await;
                     }
+                    None => None,
                 };
 
-                let builder =
-                    Self::create_request_builder(method, &url, message.headers, message.body);
+                let builder = Self::create_request_builder(
+                    method,
+                    &url,
+                    auth.as_ref().map(|(auth, path)| (*auth, path.as_str())),
+                    message.headers,
+                    message.body,
+                );
 
                 log::debug!("Calling {}", &url);
                 let response_handler = counters.on_request();
@@ -222,6 +301,29 @@ impl GsbToHttpProxy {
         Box::pin(stream)
     }
 
+    async fn send_error(
+        tx: &mpsc::Sender<GsbHttpCallResponseStreamChunk>,
+        status_code: StatusCode,
+        msg: String,
+    ) -> anyhow::Result<()> {
+        tx.send(
+            GsbHttpCallResponseHeader {
+                response_headers: Default::default(),
+                status_code: status_code.as_u16(),
+            }
+            .into(),
+        )
+        .await?;
+        tx.send(
+            GsbHttpCallResponseBody {
+                msg_bytes: msg.into_bytes(),
+            }
+            .into(),
+        )
+        .await?;
+        Ok(())
+    }
+
     fn collect_headers(response: &Response) -> HashMap<String, Vec<String>> {
         let mut response_headers: HashMap<String, Vec<String>> = HashMap::new();
         response
@@ -249,12 +351,21 @@ impl GsbToHttpProxy {
     pub fn requests_duration_counter(&mut self) -> impl Counter {
         self.counters.requests_duration_counter()
     }
+
+    pub fn cache_hits_counter(&mut self) -> impl Counter {
+        self.counters.cache_hits_counter()
+    }
+
+    pub fn cache_misses_counter(&mut self) -> impl Counter {
+        self.counters.cache_misses_counter()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
+    use crate::cache::ResponseCacheConfig;
     use crate::gsb_to_http::GsbToHttpProxy;
     use crate::message::{GsbHttpCallMessage, GsbHttpCallStreamingMessage};
     use crate::response::GsbHttpCallResponseStreamChunk;
@@ -375,6 +486,69 @@ mod tests {
         assert!(requests_duration_counter.frame().unwrap() > 0.0);
     }
 
+    #[actix_web::test]
+    async fn cached_responses_test() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/endpoint")
+            .with_status(200)
+            .with_body("response")
+            .expect(1)
+            .create();
+
+        let mut gsb_call =
+            GsbToHttpProxy::new(server.url()).with_cache(ResponseCacheConfig::default());
+        let mut requests_counter = gsb_call.requests_counter();
+        let mut cache_hits_counter = gsb_call.cache_hits_counter();
+        let mut cache_misses_counter = gsb_call.cache_misses_counter();
+
+        for _ in 0..3 {
+            let response = gsb_call.clone().pass(message()).await;
+            assert_eq!("response".as_bytes(), response.body.msg_bytes);
+        }
+
+        mock.assert();
+        assert_eq!(1.0, requests_counter.frame().unwrap());
+        assert_eq!(2.0, cache_hits_counter.frame().unwrap());
+        assert_eq!(1.0, cache_misses_counter.frame().unwrap());
+    }
+
+    #[actix_web::test]
+    async fn injected_auth_test() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/endpoint")
+            .match_header("authorization", "Bearer s3cr3t")
+            .with_status(200)
+            .with_body("response")
+            .expect(2)
+            .create();
+
+        let auth = serde_json::from_str(r#"[{ "type": "bearer", "token": "s3cr3t" }]"#).unwrap();
+        let gsb_call = GsbToHttpProxy::new(server.url()).with_auth(auth);
+
+        let mut message = message();
+        let response = gsb_call.clone().pass(message.clone()).await;
+        assert_eq!("response".as_bytes(), response.body.msg_bytes);
+
+        message.headers.insert(
+            "Authorization".to_string(),
+            vec!["Bearer requestor".to_string()],
+        );
+        let response = gsb_call.clone().pass(message.clone()).await;
+        assert_eq!("response".as_bytes(), response.body.msg_bytes);
+
+        // Service could resolve the path outside of the matched prefix.
+        message.path = "/endpoint/../admin".to_string();
+        let response = gsb_call.clone().pass(message).await;
+        assert_eq!(
+            StatusCode::BAD_REQUEST.as_u16(),
+            response.header.status_code
+        );
+
+        mock.assert();
+    }
+
     async fn run_10_requests(mut gsb_call_proxy: GsbToHttpProxy) {
         let message = message();
         for _ in 0..10 {
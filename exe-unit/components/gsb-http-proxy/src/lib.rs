@@ -1,3 +1,5 @@
+pub mod auth;
+pub mod cache;
 pub mod counters;
 pub mod error;
 pub mod gsb_to_http;
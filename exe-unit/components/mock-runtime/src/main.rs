@@ -10,6 +10,14 @@ async fn main() -> anyhow::Result<()> {
         env::set_var("RUST_LOG", "debug")
     }
     env_logger::init();
+    if env::args().any(|arg| arg == "capabilities") {
+        let capabilities = serde_json::json!({
+            "version": 1,
+            "commands": ["stdin-streaming"],
+        });
+        println!("{}", capabilities);
+        return Ok(());
+    }
     if env::var("X_SERVER").is_ok() {
         run(|event_emitter| RuntimeMock {
             handler: event_emitter,
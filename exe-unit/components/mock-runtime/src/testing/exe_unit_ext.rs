@@ -89,6 +89,12 @@ pub fn exe_unit_config(
             agreement: agreement_path.to_path_buf(),
             cache_dir: temp_dir.join("cache"),
             work_dir: temp_dir.join("work"),
+            output_limit: 0,
+            spill_output: false,
+            security_profile_file: None,
+            cgroup_audit_interval: 0,
+            transfer_bandwidth_limit: 0,
+            metrics_push: false,
         },
         binary: binary.as_ref().to_path_buf(),
         runtime_args: vec![],
@@ -98,6 +104,7 @@ pub fn exe_unit_config(
         },
         sec_key: None,
         requestor_pub_key: None,
+        secrets_key_file: None,
         service_id: Some(Uuid::new_v4().to_simple().to_string()),
         report_url: None,
     }
@@ -162,6 +169,9 @@ impl ExeUnitExt for ExeUnitHandle {
             batch_id: batch_id.clone(),
             exe_script,
             timeout: None,
+            interactive: Vec::new(),
+            metrics_push_url: None,
+            run_options: Default::default(),
         };
         self.addr
             .send(RpcEnvelope::with_caller(String::new(), msg))
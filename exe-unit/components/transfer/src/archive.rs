@@ -21,6 +21,14 @@ use ya_utils_path::normalize_path;
 use zip::tokio::read::read_zipfile_from_stream;
 use zip::write::FileOptions;
 
+/// In-memory file appended to an archive after files read from disk
+#[derive(Clone, Debug)]
+pub struct ArchiveEntry {
+    /// Path within the archive
+    pub name: PathBuf,
+    pub content: Vec<u8>,
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub enum ArchiveFormat {
     Tar,
@@ -77,7 +85,7 @@ impl TryFrom<&TransferArgs> for ArchiveFormat {
 
     fn try_from(args: &TransferArgs) -> Result<Self, Self::Error> {
         match &args.format {
-            Some(format) => ArchiveFormat::try_from(format.as_str()),
+            Some(format) => ArchiveFormat::try_from(crate::sync::archive_format(format)),
             None => Err(Error::OutputFormat("No archive format specified".into())),
         }
     }
@@ -99,6 +107,7 @@ pub enum FileEvent {
 pub async fn archive<'a, P, R>(
     path_iter: impl Iterator<Item = R> + 'static,
     path_root: P,
+    entries: Vec<ArchiveEntry>,
     format: ArchiveFormat,
     evt_sender: Sender<FileEvent>,
 ) -> Pin<Box<dyn Stream<Item = Result<TransferData, Error>> + Send + Sync + 'a>>
@@ -106,12 +115,13 @@ where
     P: AsRef<Path> + Send + Sync + 'a,
     R: AsRef<Path> + Unpin + Send + Sync + 'static,
 {
-    archive_stream(path_iter, path_root, format, evt_sender).await
+    archive_stream(path_iter, path_root, entries, format, evt_sender).await
 }
 
 pub async fn archive_stream<'a, B, E, P, R>(
     path_iter: impl Iterator<Item = R> + 'static,
     path_root: P,
+    entries: Vec<ArchiveEntry>,
     format: ArchiveFormat,
     evt_sender: Sender<FileEvent>,
 ) -> Pin<Box<dyn Stream<Item = Result<B, E>> + Send + Sync + 'a>>
@@ -128,13 +138,13 @@ where
 
     match format {
         ArchiveFormat::Tar => Box::pin(
-            archive_tar(path_iter, path_root, evt_sender)
+            archive_tar(path_iter, path_root, entries, evt_sender)
                 .await
                 .map(BytesResult::convert),
         ),
         ArchiveFormat::TarBz2 => Box::pin(
             codec_stream(BzEncoder::new(
-                archive_tar(path_iter, path_root, evt_sender)
+                archive_tar(path_iter, path_root, entries, evt_sender)
                     .await
                     .into_async_read(),
             ))
@@ -142,7 +152,7 @@ where
         ),
         ArchiveFormat::TarGz => Box::pin(
             codec_stream(GzipEncoder::new(
-                archive_tar(path_iter, path_root, evt_sender)
+                archive_tar(path_iter, path_root, entries, evt_sender)
                     .await
                     .into_async_read(),
             ))
@@ -150,7 +160,7 @@ where
         ),
         ArchiveFormat::TarXz => Box::pin(
             codec_stream(XzEncoder::new(
-                archive_tar(path_iter, path_root, evt_sender)
+                archive_tar(path_iter, path_root, entries, evt_sender)
                     .await
                     .into_async_read(),
             ))
@@ -160,6 +170,7 @@ where
             archive_zip(
                 path_iter,
                 path_root,
+                entries,
                 match format {
                     ArchiveFormat::ZipStored => zip::CompressionMethod::Stored,
                     _ => zip::CompressionMethod::Deflated,
@@ -174,6 +185,7 @@ where
 async fn archive_tar<'a, P, R>(
     path_iter: impl Iterator<Item = R> + 'static,
     path_root: P,
+    entries: Vec<ArchiveEntry>,
     mut evt_sender: Sender<FileEvent>,
 ) -> impl Stream<Item = Result<Bytes, io::Error>> + Unpin + Send + Sync + 'a
 where
@@ -186,7 +198,7 @@ where
 
     let fut = async move {
         let mut path_iter = path_iter.peekable();
-        if path_iter.peek().is_none() {
+        if path_iter.peek().is_none() && entries.is_empty() {
             return Err(io::Error::from(io::ErrorKind::InvalidData));
         }
 
@@ -218,6 +230,22 @@ where
                 .await;
         }
 
+        for entry in entries {
+            let mut header = tokio_tar::Header::new_gnu();
+            header.set_size(entry.content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            );
+            header.set_cksum();
+            builder
+                .append_data(&mut header, &entry.name, entry.content.as_slice())
+                .await?;
+        }
+
         builder.finish().await?;
         Ok(())
     }
@@ -234,6 +262,7 @@ where
 async fn archive_zip<'a, B, E, P, R>(
     path_iter: impl Iterator<Item = R> + 'static,
     path_root: P,
+    entries: Vec<ArchiveEntry>,
     method: zip::CompressionMethod,
     mut evt_sender: Sender<FileEvent>,
 ) -> Pin<Box<dyn Stream<Item = Result<B, E>> + Send + Sync + 'a>>
@@ -259,7 +288,7 @@ where
     let output_f = output.clone();
 
     let fut = async move {
-        if path_items.is_empty() {
+        if path_items.is_empty() && entries.is_empty() {
             return Err(io::Error::from(io::ErrorKind::InvalidData));
         }
 
@@ -302,6 +331,12 @@ where
                 .await;
         }
 
+        for entry in entries {
+            zip.start_file_from_path(&entry.name, options)
+                .map_err(io_error)?;
+            io::Write::write_all(&mut zip, &entry.content)?;
+        }
+
         zip.finish().map_err(io_error)?;
         Ok::<_, io::Error>(())
     }
@@ -1,6 +1,7 @@
 use crate::archive::ArchiveFormat;
 use crate::archive::{archive, extract};
 use crate::error::Error;
+use crate::sync::{self, is_sync};
 use crate::traverse::PathTraverse;
 use crate::{abortable_sink, abortable_stream, UrlExt};
 use crate::{TransferContext, TransferData, TransferProvider, TransferSink, TransferStream};
@@ -153,6 +154,7 @@ impl TransferProvider<TransferData, Error> for DirTransferProvider {
     fn source(&self, url: &Url, ctx: &TransferContext) -> TransferStream<TransferData, Error> {
         let dir = Path::new(&extract_file_url(url)).to_owned();
         let args = ctx.args.clone();
+        let session = ctx.sync.clone();
         log::debug!("Transfer source directory: {}", dir.display());
 
         let (stream, tx, abort_reg) = TransferStream::<TransferData, Error>::create(1);
@@ -161,7 +163,15 @@ impl TransferProvider<TransferData, Error> for DirTransferProvider {
         spawn_local(async move {
             let fut = async move {
                 let format = ArchiveFormat::try_from(&args)?;
-                let path_iter = args.traverse(&dir)?;
+                let mut entries = vec![];
+                let path_iter: Box<dyn Iterator<Item = PathBuf>> = if is_sync(&args) {
+                    let (paths, manifest) =
+                        sync::source_paths(&dir, args.traverse(&dir)?, session.as_ref())?;
+                    entries.push(manifest);
+                    Box::new(paths.into_iter())
+                } else {
+                    args.traverse(&dir)?
+                };
 
                 let (evt_tx, mut evt_rx) = futures::channel::mpsc::channel(1);
                 spawn_local(async move {
@@ -170,7 +180,7 @@ impl TransferProvider<TransferData, Error> for DirTransferProvider {
                     }
                 });
 
-                archive(path_iter, dir, format, evt_tx)
+                archive(path_iter, dir, entries, format, evt_tx)
                     .await
                     .forward(tx.sink_map_err(Error::from).with(|b| ready(Ok(Ok(b)))))
                     .await
@@ -200,7 +210,10 @@ impl TransferProvider<TransferData, Error> for DirTransferProvider {
                     }
                 });
 
-                extract(rx, dir, format, evt_tx).await?;
+                extract(rx, dir.clone(), format, evt_tx).await?;
+                if is_sync(&args) {
+                    sync::prune(&dir, args.traverse(&dir)?)?;
+                }
                 Ok::<(), Error>(())
             };
 
@@ -17,11 +17,15 @@ use ya_service_bus::RpcEndpoint;
 
 pub struct GftpTransferProvider {
     concurrency: usize,
+    block_concurrency: usize,
 }
 
 impl Default for GftpTransferProvider {
     fn default() -> Self {
-        GftpTransferProvider { concurrency: 8 }
+        GftpTransferProvider {
+            concurrency: 8,
+            block_concurrency: gftp::DEFAULT_BLOCK_CONCURRENCY,
+        }
     }
 }
 
@@ -33,6 +37,7 @@ impl TransferProvider<TransferData, Error> for GftpTransferProvider {
     fn source(&self, url: &Url, ctx: &TransferContext) -> TransferStream<TransferData, Error> {
         let url = url.clone();
         let concurrency = self.concurrency;
+        let block_concurrency = self.block_concurrency;
         let chunk_size = DEFAULT_CHUNK_SIZE;
         let state = ctx.state.clone();
 
@@ -45,6 +50,27 @@ impl TransferProvider<TransferData, Error> for GftpTransferProvider {
                     .map_err(|_| Error::InvalidUrlError("Invalid gftp URL".to_owned()))?;
 
                 let remote = node_id.service_transfer(&model::file_bus_id(&hash));
+
+                if let Some(manifest) = gftp::fetch_manifest(&remote, &hash)
+                    .await
+                    .map_err(gftp_error)?
+                {
+                    state.set_size(Some(manifest.file_size));
+                    let (remote, manifest) = (&remote, &manifest);
+
+                    return futures::stream::iter(0..manifest.num_blocks())
+                        .map(|index| async move {
+                            gftp::download_block(remote, manifest, index)
+                                .await
+                                .map(TransferData::from)
+                                .map_err(gftp_error)
+                        })
+                        .buffered(block_concurrency)
+                        .map(Ok)
+                        .forward(tx.sink_map_err(Error::from))
+                        .await;
+                }
+
                 let meta = remote.send(model::GetMetadata {}).await??;
                 state.set_size(Some(meta.file_size));
                 let n = (meta.file_size + chunk_size - 1) / chunk_size;
@@ -153,3 +179,15 @@ impl TransferProvider<TransferData, Error> for GftpTransferProvider {
         sink
     }
 }
+
+/// Recovers typed errors from `gftp` crate, so transient GSB failures can be retried
+fn gftp_error(error: anyhow::Error) -> Error {
+    let error = match error.downcast::<ya_service_bus::error::Error>() {
+        Ok(e) => return Error::Gsb(e),
+        Err(error) => error,
+    };
+    match error.downcast::<GftpError>() {
+        Ok(e) => Error::Gftp(e),
+        Err(error) => Error::Other(error.to_string()),
+    }
+}
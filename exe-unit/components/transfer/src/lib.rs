@@ -9,6 +9,9 @@ mod http;
 mod location;
 mod progress;
 mod retry;
+mod s3;
+pub mod sync;
+mod throttle;
 pub mod transfer;
 mod traverse;
 
@@ -25,7 +28,7 @@ use futures::prelude::*;
 use futures::task::{Context, Poll};
 use url::Url;
 
-pub use crate::archive::{archive, extract, ArchiveFormat};
+pub use crate::archive::{archive, extract, ArchiveEntry, ArchiveFormat};
 pub use crate::container::ContainerTransferProvider;
 use crate::error::Error;
 pub use crate::file::{DirTransferProvider, FileTransferProvider};
@@ -36,6 +39,10 @@ pub use crate::location::{TransferUrl, UrlExt};
 use crate::progress::{progress_report_channel, ProgressReporter};
 pub use crate::progress::{wrap_sink_with_progress_reporting, wrap_stream_with_progress_reporting};
 pub use crate::retry::Retry;
+pub use crate::s3::S3TransferProvider;
+use crate::sync::SyncSession;
+use crate::throttle::throttle_channel;
+pub use crate::throttle::Throttle;
 pub use crate::traverse::PathTraverse;
 
 use ya_client_model::activity::TransferArgs;
@@ -74,6 +81,7 @@ where
 
             let stream = with_hash_stream(src.source(&src_url.url, ctx), src_url, dst_url, ctx)?;
             let sink = progress_report_channel(dst.destination(&dst_url.url, ctx), ctx);
+            let sink = throttle_channel(sink, ctx);
 
             transfer(stream, sink).await?;
             Ok::<_, Error>(())
@@ -302,6 +310,10 @@ pub struct TransferContext {
     pub state: TransferState,
     pub args: TransferArgs,
     pub progress: ProgressReporter,
+    /// Directory synchronization state, set for `sync+` archive formats
+    pub sync: Option<SyncSession>,
+    /// Bandwidth limit shared with other transfers of the activity
+    pub throttle: Option<Throttle>,
 }
 
 impl TransferContext {
@@ -314,6 +326,8 @@ impl TransferContext {
             args,
             state,
             progress: ProgressReporter::default(),
+            sync: None,
+            throttle: None,
         }
     }
 
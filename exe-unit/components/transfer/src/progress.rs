@@ -48,13 +48,16 @@ impl ProgressReporter {
         });
     }
 
-    /// TODO: implement `update_step`
-    pub fn report_progress(&self, progress: u64, size: Option<u64>) {
-        let update_interval: Duration = self
-            .config
+    pub fn update_interval(&self) -> Duration {
+        self.config
             .update_interval
             .map(Into::into)
-            .unwrap_or(Duration::from_secs(1));
+            .unwrap_or(Duration::from_secs(1))
+    }
+
+    /// TODO: implement `update_step`
+    pub fn report_progress(&self, progress: u64, size: Option<u64>) {
+        let update_interval = self.update_interval();
         let _update_step = self.config.update_step;
 
         if let Some(inner) = self.inner.lock().unwrap().as_mut() {
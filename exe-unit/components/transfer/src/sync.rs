@@ -0,0 +1,353 @@
+//! Delta synchronization of directories.
+//!
+//! Directory transfers with a `sync+` prefixed archive format (e.g. `sync+tar.gz`)
+//! only include files that were added or modified (by size or modification time)
+//! since the last successful transfer between the same source and destination.
+//! Each archive carries a manifest of all files matching the transfer fileset,
+//! which is used by the receiving side to remove files deleted at the source.
+//! The manifest is generated in memory, so the source directory is left intact.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use ya_client_model::activity::TransferArgs;
+use ya_utils_path::normalize_path;
+
+use crate::archive::ArchiveEntry;
+use crate::error::Error;
+use crate::TransferUrl;
+
+pub const SYNC_FORMAT_PREFIX: &str = "sync+";
+pub const MANIFEST_FILE_NAME: &str = ".ya-sync-manifest.json";
+
+/// Returns `true` if the transfer was requested in delta synchronization mode
+pub fn is_sync(args: &TransferArgs) -> bool {
+    args.format
+        .as_deref()
+        .map(|f| f.to_lowercase().starts_with(SYNC_FORMAT_PREFIX))
+        .unwrap_or(false)
+}
+
+/// Strips the synchronization mode prefix from an archive format
+pub fn archive_format(format: &str) -> &str {
+    match format.get(..SYNC_FORMAT_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(SYNC_FORMAT_PREFIX) => {
+            &format[SYNC_FORMAT_PREFIX.len()..]
+        }
+        _ => format,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncEntry {
+    pub size: u64,
+    pub mtime_secs: u64,
+    pub mtime_nanos: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncManifest {
+    /// Files relative to the synchronized directory, using `/` as a separator
+    pub files: BTreeMap<String, SyncEntry>,
+}
+
+impl SyncManifest {
+    /// Builds a manifest of regular files within `root`
+    pub fn scan(
+        root: impl AsRef<Path>,
+        paths: impl IntoIterator<Item = PathBuf>,
+    ) -> Result<Self, Error> {
+        let root = root.as_ref();
+        let mut files = BTreeMap::new();
+
+        for path in paths {
+            let metadata = std::fs::metadata(&path)?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let name = relative_name(root, &path)?;
+            if name == MANIFEST_FILE_NAME {
+                continue;
+            }
+
+            let mtime = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            files.insert(
+                name,
+                SyncEntry {
+                    size: metadata.len(),
+                    mtime_secs: mtime.as_secs(),
+                    mtime_nanos: mtime.subsec_nanos(),
+                },
+            );
+        }
+
+        Ok(SyncManifest { files })
+    }
+
+    /// Lists files added or modified since `previous`
+    pub fn changed<'a>(&'a self, previous: Option<&'a SyncManifest>) -> Vec<&'a str> {
+        self.files
+            .iter()
+            .filter(|(name, entry)| {
+                previous
+                    .and_then(|p| p.files.get(name.as_str()))
+                    .map(|prev| prev != *entry)
+                    .unwrap_or(true)
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn read(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = dir.as_ref().join(MANIFEST_FILE_NAME);
+        let content = std::fs::read(path)?;
+        serde_json::from_slice(&content).map_err(|e| Error::Other(e.to_string()))
+    }
+
+    /// Archive entry placing the manifest in the root of the synchronized directory
+    pub fn entry(&self) -> Result<ArchiveEntry, Error> {
+        Ok(ArchiveEntry {
+            name: PathBuf::from(MANIFEST_FILE_NAME),
+            content: serde_json::to_vec(self).map_err(|e| Error::Other(e.to_string()))?,
+        })
+    }
+}
+
+/// Prepares the list of paths to archive when sending `dir` in synchronization mode,
+/// along with the manifest entry, which has to be appended to the archive.
+pub fn source_paths(
+    dir: &Path,
+    paths: impl IntoIterator<Item = PathBuf>,
+    session: Option<&SyncSession>,
+) -> Result<(Vec<PathBuf>, ArchiveEntry), Error> {
+    let dir = normalize_path(dir)?;
+    let manifest = SyncManifest::scan(&dir, paths)?;
+    let previous = session.and_then(|s| s.previous());
+
+    let result = manifest
+        .changed(previous.as_ref())
+        .into_iter()
+        .map(|name| dir.join(name))
+        .collect::<Vec<_>>();
+
+    log::info!(
+        "Synchronizing {} out of {} files from {}",
+        result.len(),
+        manifest.files.len(),
+        dir.display()
+    );
+
+    let entry = manifest.entry()?;
+    if let Some(session) = session {
+        session.stage(manifest);
+    }
+    Ok((result, entry))
+}
+
+/// Removes files from `dir` which are not present in the received manifest
+pub fn prune(dir: &Path, paths: impl IntoIterator<Item = PathBuf>) -> Result<(), Error> {
+    let dir = normalize_path(dir)?;
+    let manifest = SyncManifest::read(&dir)?;
+    let local = SyncManifest::scan(&dir, paths)?;
+
+    for name in local.files.keys() {
+        if !manifest.files.contains_key(name) {
+            log::debug!("Removing {} deleted at the synchronization source", name);
+            std::fs::remove_file(dir.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+fn relative_name(root: &Path, path: &Path) -> Result<String, Error> {
+    let relative = path
+        .strip_prefix(root)
+        .map_err(|e| Error::Other(e.to_string()))?;
+    Ok(relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Stores manifests of successfully synchronized directories, keyed by source
+/// and destination URLs. Persisted in the ExeUnit working directory, so delta
+/// transfers work across ExeUnit restarts within the same activity.
+#[derive(Clone, Default)]
+pub struct SyncStore {
+    path: Option<PathBuf>,
+    inner: Rc<RefCell<HashMap<String, SyncManifest>>>,
+}
+
+impl SyncStore {
+    pub fn load(path: PathBuf) -> Self {
+        let manifests = std::fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+
+        SyncStore {
+            path: Some(path),
+            inner: Rc::new(RefCell::new(manifests)),
+        }
+    }
+
+    pub fn session(&self, src: &TransferUrl, dst: &TransferUrl) -> SyncSession {
+        SyncSession {
+            key: format!("{} -> {}", src.url, dst.url),
+            store: self.clone(),
+            staged: Default::default(),
+        }
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            let content = serde_json::to_vec(&*self.inner.borrow())
+                .map_err(|e| Error::Other(e.to_string()))?;
+            std::fs::write(path, content)?;
+        }
+        Ok(())
+    }
+}
+
+/// Synchronization state of a single transfer
+#[derive(Clone)]
+pub struct SyncSession {
+    key: String,
+    store: SyncStore,
+    staged: Rc<RefCell<Option<SyncManifest>>>,
+}
+
+impl SyncSession {
+    fn previous(&self) -> Option<SyncManifest> {
+        self.store.inner.borrow().get(&self.key).cloned()
+    }
+
+    fn stage(&self, manifest: SyncManifest) {
+        self.staged.borrow_mut().replace(manifest);
+    }
+
+    /// Records the staged manifest after the transfer has been completed
+    pub fn commit(&self) -> Result<(), Error> {
+        if let Some(manifest) = self.staged.borrow_mut().take() {
+            self.store
+                .inner
+                .borrow_mut()
+                .insert(self.key.clone(), manifest);
+            self.store.save()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathTraverse;
+
+    fn entries(dir: &Path) -> Vec<PathBuf> {
+        TransferArgs::default().traverse(dir).unwrap().collect()
+    }
+
+    #[test]
+    fn format_prefix() {
+        assert_eq!(archive_format("sync+tar.gz"), "tar.gz");
+        assert_eq!(archive_format("SYNC+zip"), "zip");
+        assert_eq!(archive_format("tar"), "tar");
+        assert!(is_sync(&TransferArgs {
+            format: Some("sync+tar".into()),
+            ..Default::default()
+        }));
+        assert!(!is_sync(&TransferArgs::default()));
+    }
+
+    #[test]
+    fn delta() -> anyhow::Result<()> {
+        let dir = tempdir::TempDir::new("test-sync")?;
+        let store_dir = tempdir::TempDir::new("test-sync-store")?;
+        std::fs::create_dir_all(dir.path().join("sub"))?;
+        std::fs::write(dir.path().join("a.txt"), "a")?;
+        std::fs::write(dir.path().join("sub").join("b.txt"), "b")?;
+
+        let store = SyncStore::load(store_dir.path().join("store.json"));
+        let src = TransferUrl::parse("container:/out", "container")?;
+        let dst = TransferUrl::parse("http://host/out", "container")?;
+
+        let session = store.session(&src, &dst);
+        let (paths, manifest) = source_paths(dir.path(), entries(dir.path()), Some(&session))?;
+        assert_eq!(paths.len(), 2);
+        assert_eq!(manifest.name, PathBuf::from(MANIFEST_FILE_NAME));
+        session.commit()?;
+
+        std::fs::write(dir.path().join("sub").join("b.txt"), "bb")?;
+        let session = store.session(&src, &dst);
+        let (paths, manifest) = source_paths(dir.path(), entries(dir.path()), Some(&session))?;
+        let root = normalize_path(dir.path())?;
+        assert_eq!(paths, vec![root.join("sub/b.txt")]);
+
+        let manifest: SyncManifest = serde_json::from_slice(&manifest.content)?;
+        assert_eq!(manifest.files.len(), 2);
+
+        let reloaded = SyncStore::load(store_dir.path().join("store.json"));
+        assert!(reloaded.session(&src, &dst).previous().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn prune_deleted() -> anyhow::Result<()> {
+        let dir = tempdir::TempDir::new("test-sync")?;
+        std::fs::write(dir.path().join("kept"), "1")?;
+        let root = normalize_path(dir.path())?;
+        let manifest = SyncManifest::scan(&root, entries(dir.path()))?.entry()?;
+        std::fs::write(root.join(&manifest.name), &manifest.content)?;
+
+        std::fs::write(dir.path().join("stale"), "2")?;
+        prune(dir.path(), entries(dir.path()))?;
+
+        assert!(dir.path().join("kept").exists());
+        assert!(!dir.path().join("stale").exists());
+        Ok(())
+    }
+
+    fn snapshot(dir: &Path) -> std::io::Result<Vec<(PathBuf, u64, std::time::SystemTime)>> {
+        let mut files = entries(dir)
+            .into_iter()
+            .map(|path| {
+                let metadata = std::fs::metadata(&path)?;
+                Ok((path, metadata.len(), metadata.modified()?))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        files.sort();
+        Ok(files)
+    }
+
+    #[test]
+    fn source_unchanged() -> anyhow::Result<()> {
+        let dir = tempdir::TempDir::new("test-sync")?;
+        std::fs::create_dir_all(dir.path().join("sub"))?;
+        std::fs::write(dir.path().join("a.txt"), "a")?;
+        std::fs::write(dir.path().join("sub").join("b.txt"), "b")?;
+
+        let before = snapshot(dir.path())?;
+        let dir_mtime = std::fs::metadata(dir.path())?.modified()?;
+
+        let store = SyncStore::default();
+        let src = TransferUrl::parse("container:/out", "container")?;
+        let dst = TransferUrl::parse("http://host/out", "container")?;
+        let session = store.session(&src, &dst);
+        source_paths(dir.path(), entries(dir.path()), Some(&session))?;
+        session.commit()?;
+
+        assert_eq!(snapshot(dir.path())?, before);
+        assert_eq!(std::fs::metadata(dir.path())?.modified()?, dir_mtime);
+        assert!(!dir.path().join(MANIFEST_FILE_NAME).exists());
+        Ok(())
+    }
+}
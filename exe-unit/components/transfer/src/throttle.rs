@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::{abortable_sink, TransferContext, TransferData, TransferSink};
+
+use futures::{SinkExt, StreamExt, TryFutureExt};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::spawn_local;
+use tokio::time::Instant;
+
+type Sink = TransferSink<TransferData, Error>;
+
+/// Token bucket limiting bandwidth of all transfers sharing it.
+#[derive(Clone)]
+pub struct Throttle {
+    inner: Arc<Mutex<Bucket>>,
+}
+
+struct Bucket {
+    /// Bytes per second.
+    rate: u64,
+    /// Can be negative, when transfers took more than available.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Throttle {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Throttle {
+            inner: Arc::new(Mutex::new(Bucket {
+                rate: bytes_per_sec.max(1),
+                tokens: 0.,
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.inner.lock().unwrap().rate
+    }
+
+    /// Takes `bytes` from the bucket and waits until its debt is paid off.
+    pub async fn acquire(&self, bytes: u64) {
+        let delay = self.inner.lock().unwrap().take(bytes, Instant::now());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+impl Bucket {
+    /// Bucket holds at most one second worth of tokens, so idle transfers can't burst above
+    /// the limit for longer. Returns time, after which taken bytes fit into the limit.
+    fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        let rate = self.rate as f64;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate) - bytes as f64;
+        self.updated = now;
+
+        match self.tokens < 0. {
+            true => Duration::from_secs_f64(-self.tokens / rate),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+/// Wraps a sink to limit its bandwidth, if throttling is set in the context.
+/// Current rate is reported as progress message once per progress update interval.
+pub fn throttle_channel(dest: Sink, ctx: &TransferContext) -> Sink {
+    let throttle = match ctx.throttle.clone() {
+        Some(throttle) => throttle,
+        None => return dest,
+    };
+    let report = ctx.reporter();
+    let interval = report.update_interval();
+    wrap_sink_with_throttling(
+        dest,
+        throttle,
+        move |rate, limit| {
+            report.report_message(format!(
+                "Transfer rate: {} B/s (limit: {} B/s)",
+                rate, limit
+            ))
+        },
+        interval,
+    )
+}
+
+fn wrap_sink_with_throttling<F>(
+    mut dest: Sink,
+    throttle: Throttle,
+    report: F,
+    interval: Duration,
+) -> Sink
+where
+    F: Fn(u64, u64) + 'static,
+{
+    let (mut sink, mut rx, res_tx) = Sink::create(0);
+    sink.res_rx = dest.res_rx.take();
+
+    spawn_local(async move {
+        let fut = async move {
+            let mut measured = 0u64;
+            let mut since = Instant::now();
+
+            while let Some(result) = rx.next().await {
+                let data = result?;
+                let data_len = data.as_ref().len() as u64;
+                throttle.acquire(data_len).await;
+
+                measured += data_len;
+                let elapsed = since.elapsed();
+                if elapsed >= interval {
+                    report(
+                        (measured as f64 / elapsed.as_secs_f64()) as u64,
+                        throttle.limit(),
+                    );
+                    measured = 0;
+                    since = Instant::now();
+                }
+
+                dest.send(data).await?;
+                if data_len == 0 {
+                    break;
+                }
+            }
+
+            Ok::<(), Error>(())
+        }
+        .map_err(|error| {
+            log::error!("Error forwarding data: {}", error);
+            error
+        });
+
+        abortable_sink(fut, res_tx).await
+    });
+
+    sink
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_delays_bytes_above_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            rate: 1000,
+            tokens: 0.,
+            updated: start,
+        };
+
+        assert_eq!(bucket.take(500, start), Duration::from_millis(500));
+        // Debt is paid off after 500ms, then next 1000 bytes take another second.
+        let now = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(1000, now), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn bucket_bursts_at_most_one_second() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            rate: 1000,
+            tokens: 0.,
+            updated: start,
+        };
+
+        let idle = start + Duration::from_secs(60);
+        assert_eq!(bucket.take(1000, idle), Duration::ZERO);
+        assert_eq!(bucket.take(1000, idle), Duration::from_secs(1));
+    }
+}
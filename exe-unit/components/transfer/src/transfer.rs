@@ -12,9 +12,11 @@ use crate::cache::{Cache, CachePath};
 use crate::error::Error;
 use crate::error::Error as TransferError;
 pub use crate::progress::ProgressConfig;
+use crate::sync::{self, SyncStore};
 use crate::{
     transfer_with, ContainerTransferProvider, FileTransferProvider, GftpTransferProvider,
-    HttpTransferProvider, Retry, TransferContext, TransferData, TransferProvider, TransferUrl,
+    HttpTransferProvider, Retry, S3TransferProvider, Throttle, TransferContext, TransferData,
+    TransferProvider, TransferUrl,
 };
 
 use ya_client_model::activity::exe_script_command::ProgressArgs;
@@ -25,6 +27,8 @@ use ya_utils_futures::abort::Abort;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+const SYNC_STORE_FILE_NAME: &str = ".transfer-sync.json";
+
 macro_rules! actor_try {
     ($expr:expr) => {
         match $expr {
@@ -166,6 +170,8 @@ pub struct TransferServiceContext {
 
     pub deploy_retry: Option<Retry>,
     pub transfer_retry: Option<Retry>,
+    /// Bandwidth limit in bytes per second, shared by all transfers of the activity
+    pub bandwidth_limit: Option<u64>,
 }
 
 /// Handles resources transfers.
@@ -177,6 +183,8 @@ pub struct TransferService {
 
     deploy_retry: Retry,
     transfer_retry: Retry,
+    sync: SyncStore,
+    throttle: Option<Throttle>,
 
     abort_handles: Rc<RefCell<HashSet<Abort>>>,
 }
@@ -190,6 +198,8 @@ impl TransferService {
             task_package: ctx.task_package,
             deploy_retry: ctx.deploy_retry.unwrap_or_default(),
             transfer_retry: ctx.transfer_retry.unwrap_or_default(),
+            sync: SyncStore::load(ctx.work_dir.join(SYNC_STORE_FILE_NAME)),
+            throttle: ctx.bandwidth_limit.map(Throttle::new),
             abort_handles: Default::default(),
         }
     }
@@ -221,6 +231,7 @@ impl TransferService {
         let provider_vec: Vec<Rc<dyn TransferProvider<TransferData, TransferError>>> = vec![
             Rc::new(GftpTransferProvider::default()),
             Rc::new(HttpTransferProvider::default()),
+            Rc::new(S3TransferProvider),
         ];
         for provider in provider_vec {
             for scheme in provider.schemes() {
@@ -360,6 +371,7 @@ impl Handler<DeployImage> for TransferService {
 
         let mut ctx = TransferContext::default();
         ctx.state.retry_with(self.deploy_retry.clone());
+        ctx.throttle = self.throttle.clone();
         ctx.progress
             .register_reporter(deploy.progress_config, 1, Some("Bytes".to_string()));
 
@@ -380,8 +392,12 @@ impl Handler<TransferResource> for TransferService {
         let src = actor_try!(self.provider(&src_url));
         let dst = actor_try!(self.provider(&dst_url));
 
-        let mut ctx = TransferContext::default();
+        let mut ctx = TransferContext::from(msg.args);
+        if sync::is_sync(&ctx.args) {
+            ctx.sync = Some(self.sync.session(&src_url, &dst_url));
+        }
         ctx.state.retry_with(self.transfer_retry.clone());
+        ctx.throttle = self.throttle.clone();
         ctx.progress
             .register_reporter(msg.progress_config, 1, Some("Bytes".to_string()));
 
@@ -398,6 +414,9 @@ impl Handler<TransferResource> for TransferService {
                     .await
                     .map_err(TransferError::from)??;
             }
+            if let Some(sync) = ctx.sync.as_ref() {
+                sync.commit()?;
+            }
             log::info!(
                 "Transfer of {:?} to {:?} finished",
                 src_url.url,
@@ -163,6 +163,9 @@ async fn exec_and_wait(args: &Cli) -> anyhow::Result<()> {
         batch_id: BATCH_ID.to_string(),
         exe_script: exe_script.clone(),
         timeout: None,
+        interactive: Vec::new(),
+        metrics_push_url: None,
+        run_options: Default::default(),
     };
 
     let _ = exe_unit_service.send(exec.clone()).await?;
@@ -244,6 +247,9 @@ async fn exec_and_wait(args: &Cli) -> anyhow::Result<()> {
             batch_id,
             exe_script: exe_script.clone(),
             timeout: None,
+            interactive: Vec::new(),
+            metrics_push_url: None,
+            run_options: Default::default(),
         };
 
         let _ = exe_unit_service.send(exec.clone()).await?;
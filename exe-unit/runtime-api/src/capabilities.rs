@@ -0,0 +1,124 @@
+use std::fmt;
+use std::io::Cursor;
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the capability handshake implemented by this crate.
+pub const CAPABILITIES_VERSION: u32 = 1;
+
+/// Optional features of the runtime, which ExeUnit can't assume are supported.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Saving and restoring state of the running task.
+    Checkpoint,
+    /// Suspending and resuming the running task.
+    Pause,
+    /// Passing host devices (GPUs) through to the task.
+    Gpu,
+    /// Streaming stdin to commands while they are running.
+    StdinStreaming,
+    /// Environment, working directory and user of `run` commands.
+    RunOptions,
+    /// Confining the task with `--security-profile` and `--security-profile-file`.
+    SecurityProfile,
+    /// Capability introduced by a newer version of the handshake.
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::Checkpoint => "checkpoint",
+            Capability::Pause => "pause",
+            Capability::Gpu => "gpu",
+            Capability::StdinStreaming => "stdin-streaming",
+            Capability::RunOptions => "run-options",
+            Capability::SecurityProfile => "security-profile",
+            Capability::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Response of the runtime to the `capabilities` command.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeCapabilities {
+    pub version: u32,
+    #[serde(default)]
+    pub commands: Vec<Capability>,
+}
+
+impl RuntimeCapabilities {
+    pub fn new(commands: Vec<Capability>) -> Self {
+        RuntimeCapabilities {
+            version: CAPABILITIES_VERSION,
+            commands,
+        }
+    }
+
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> anyhow::Result<RuntimeCapabilities> {
+        let b: &[u8] = bytes.as_ref();
+        let idx = match b.iter().position(|&ch| ch == b'{') {
+            Some(idx) => idx,
+            None => {
+                let text = String::from_utf8_lossy(b);
+                anyhow::bail!("invalid capabilities response: {}", text);
+            }
+        };
+
+        let mut capabilities: RuntimeCapabilities =
+            serde_json::from_reader(Cursor::new(&b[idx..]))?;
+        if capabilities.version > CAPABILITIES_VERSION {
+            log::warn!(
+                "runtime capabilities version {} is newer than supported {}",
+                capabilities.version,
+                CAPABILITIES_VERSION
+            );
+        }
+        capabilities.commands.retain(|c| *c != Capability::Unknown);
+        Ok(capabilities)
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.commands.contains(&capability)
+    }
+
+    /// Capabilities from `required`, which the runtime doesn't support.
+    pub fn missing(&self, required: &[Capability]) -> Vec<Capability> {
+        required
+            .iter()
+            .filter(|c| !self.supports(**c))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = RuntimeCapabilities::from_bytes(
+            r#"{
+            "version": 2,
+            "commands": ["gpu", "stdin-streaming", "migrate"]
+        }"#,
+        )
+        .unwrap();
+
+        assert_eq!(capabilities.version, 2);
+        assert_eq!(
+            capabilities.commands,
+            vec![Capability::Gpu, Capability::StdinStreaming]
+        );
+        assert_eq!(
+            capabilities.missing(&[Capability::Pause, Capability::Gpu]),
+            vec![Capability::Pause]
+        );
+        assert!(RuntimeCapabilities::from_bytes("unknown command").is_err());
+    }
+}
@@ -2,10 +2,17 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use ya_agreement_utils::agreement::{try_from_path, AgreementView, Error};
 use ya_counters::{MemCounter, StorageCounter};
 
+use crate::metrics::{self, MetricsPush};
+use crate::security::SecurityProfile;
+
+const MAX_DURATION_PROPERTY: &str = "properties/golem/srv/comp/activity/max-duration-sec";
+const TRANSFER_BANDWIDTH_PROPERTY: &str = "properties/golem/srv/comp/transfer/bandwidth-limit";
+
 #[derive(Clone, Debug)]
 pub struct Agreement {
     pub inner: AgreementView,
@@ -13,6 +20,16 @@ pub struct Agreement {
     pub usage_vector: Vec<String>,
     pub usage_limits: HashMap<String, f64>,
     pub infrastructure: HashMap<String, f64>,
+    /// Host devices offered by the Provider and requested by the Requestor.
+    pub devices: Vec<String>,
+    /// Security profile confining the runtime.
+    pub security_profile: SecurityProfile,
+    /// Wallclock cap of the activity. The lower of values set in the Offer and the Demand.
+    pub max_duration: Option<Duration>,
+    /// Transfer bandwidth cap in bytes per second. The lower of the Offer and the Demand values.
+    pub transfer_bandwidth: Option<u64>,
+    /// Collector, which the Requestor wants metrics pushed to.
+    pub metrics_push: Option<MetricsPush>,
 }
 
 impl Agreement {
@@ -42,12 +59,69 @@ impl TryFrom<Value> for Agreement {
         .filter_map(|(id, inf)| infra.get(inf).map(|v| (id.to_string(), *v)))
         .collect();
 
+        let offered = agreement
+            .pointer_typed::<Vec<String>>("/offer/properties/golem/runtime/devices")
+            .unwrap_or_default();
+        let devices = agreement
+            .pointer_typed::<Vec<String>>("/demand/properties/golem/runtime/devices")
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|device| offered.contains(device))
+            .collect();
+
+        let security_profile = match agreement
+            .pointer_typed::<String>("/offer/properties/golem/runtime/security/profile")
+        {
+            Ok(profile) => profile
+                .parse()
+                .map_err(|e: crate::error::Error| Error::InvalidValue(e.to_string()))?,
+            Err(Error::NoKey(_)) => SecurityProfile::default(),
+            Err(e) => return Err(e),
+        };
+
+        let max_duration = ["offer", "demand"]
+            .iter()
+            .filter_map(|side| {
+                let pointer = format!("/{}/{}", side, MAX_DURATION_PROPERTY);
+                agreement.pointer_typed::<f64>(&pointer).ok()
+            })
+            .filter(|secs| secs.is_finite() && *secs > 0.)
+            .map(Duration::from_secs_f64)
+            .min();
+
+        let transfer_bandwidth = ["offer", "demand"]
+            .iter()
+            .filter_map(|side| {
+                let pointer = format!("/{}/{}", side, TRANSFER_BANDWIDTH_PROPERTY);
+                agreement.pointer_typed::<u64>(&pointer).ok()
+            })
+            .filter(|limit| *limit > 0)
+            .min();
+
+        let metrics_push = match agreement.pointer_typed::<String>(metrics::PUSH_URL_PROPERTY) {
+            Ok(url) => {
+                let interval = agreement
+                    .pointer_typed::<f64>(metrics::PUSH_INTERVAL_PROPERTY)
+                    .ok()
+                    .filter(|secs| secs.is_finite() && *secs > 0.)
+                    .map(Duration::from_secs_f64);
+                Some(MetricsPush::new(&url, interval).map_err(Error::InvalidValue)?)
+            }
+            Err(Error::NoKey(_)) => None,
+            Err(e) => return Err(e),
+        };
+
         Ok(Agreement {
             inner: agreement,
             task_package,
             usage_vector,
             usage_limits: limits,
             infrastructure: infra,
+            devices,
+            security_profile,
+            max_duration,
+            transfer_bandwidth,
+            metrics_push,
         })
     }
 }
@@ -69,6 +143,73 @@ mod tests {
     fn example_agreement() {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         path.push("examples/agreement.json");
-        Agreement::try_from(&path).unwrap();
+        let agreement = Agreement::try_from(&path).unwrap();
+        assert_eq!(agreement.max_duration, None);
+    }
+
+    #[test]
+    fn negotiated_max_duration() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("examples/agreement.json");
+        let mut value = try_from_path(&path).unwrap();
+
+        value["offer"]["properties"]["golem"]["srv"]["comp"]["activity"]["max-duration-sec"] =
+            3600.into();
+        let agreement = Agreement::try_from(value.clone()).unwrap();
+        assert_eq!(agreement.max_duration, Some(Duration::from_secs(3600)));
+
+        value["demand"]["properties"]["golem"]["srv"]["comp"]["activity"]["max-duration-sec"] =
+            600.into();
+        let agreement = Agreement::try_from(value).unwrap();
+        assert_eq!(agreement.max_duration, Some(Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn negotiated_transfer_bandwidth() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("examples/agreement.json");
+        let mut value = try_from_path(&path).unwrap();
+        assert_eq!(
+            Agreement::try_from(value.clone())
+                .unwrap()
+                .transfer_bandwidth,
+            None
+        );
+
+        value["demand"]["properties"]["golem"]["srv"]["comp"]["transfer"]["bandwidth-limit"] =
+            10_000_000.into();
+        let agreement = Agreement::try_from(value.clone()).unwrap();
+        assert_eq!(agreement.transfer_bandwidth, Some(10_000_000));
+
+        value["offer"]["properties"]["golem"]["srv"]["comp"]["transfer"]["bandwidth-limit"] =
+            1_000_000.into();
+        let agreement = Agreement::try_from(value).unwrap();
+        assert_eq!(agreement.transfer_bandwidth, Some(1_000_000));
+    }
+
+    #[test]
+    fn metrics_push_target() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("examples/agreement.json");
+        let mut value = try_from_path(&path).unwrap();
+        assert_eq!(
+            Agreement::try_from(value.clone()).unwrap().metrics_push,
+            None
+        );
+
+        value["demand"]["properties"]["golem"]["srv"]["comp"]["metrics"] = serde_json::json!({
+            "push-url": "http://10.0.0.1:4318/v1/metrics",
+            "push-interval-sec": 30,
+        });
+        let push = Agreement::try_from(value.clone())
+            .unwrap()
+            .metrics_push
+            .unwrap();
+        assert_eq!(push.url.as_str(), "http://10.0.0.1:4318/v1/metrics");
+        assert_eq!(push.interval, Duration::from_secs(30));
+
+        value["demand"]["properties"]["golem"]["srv"]["comp"]["metrics"]["push-url"] =
+            "ftp://10.0.0.1".into();
+        assert!(Agreement::try_from(value).is_err());
     }
 }
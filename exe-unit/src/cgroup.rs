@@ -0,0 +1,246 @@
+//! Audit of resource limits enforced with cgroups.
+//!
+//! Runtime processes inherit the cgroup of the ExeUnit, so limits applied to it (or to any of
+//! its ancestors) by the host or the runtime confine the whole activity. Limits are compared
+//! with the negotiated `golem.inf.cpu.threads` and `golem.inf.mem.gib`, to detect hosts and
+//! runtimes, which silently ignore them.
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const PROC_CGROUP: &str = "/proc/self/cgroup";
+
+/// Relative tolerance for rounding done by hosts and runtimes (e.g. to memory pages).
+const TOLERANCE: f64 = 0.05;
+const GIB: f64 = 1024. * 1024. * 1024.;
+
+/// Effective limits of a cgroup. `None` stands for no limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CgroupLimits {
+    /// CPU time quota in cores.
+    pub cpu: Option<f64>,
+    /// Memory limit in bytes.
+    pub memory: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Discrepancy {
+    Cpu {
+        negotiated: f64,
+        actual: Option<f64>,
+    },
+    Memory {
+        negotiated: u64,
+        actual: Option<u64>,
+    },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discrepancy::Cpu { negotiated, actual } => {
+                write!(f, "CPU quota of {} cores not enforced (", negotiated)?;
+                match actual {
+                    Some(actual) => write!(f, "{} cores)", actual),
+                    None => write!(f, "unlimited)"),
+                }
+            }
+            Discrepancy::Memory { negotiated, actual } => {
+                write!(f, "memory limit of {} B not enforced (", negotiated)?;
+                match actual {
+                    Some(actual) => write!(f, "{} B)", actual),
+                    None => write!(f, "unlimited)"),
+                }
+            }
+        }
+    }
+}
+
+impl CgroupLimits {
+    /// Reads effective limits of the cgroup of the current process.
+    /// Returns `None`, if cgroups are not available.
+    pub fn current() -> Option<Self> {
+        let cgroups = std::fs::read_to_string(PROC_CGROUP).ok()?;
+        let root = Path::new(CGROUP_ROOT);
+
+        // cgroup v2
+        if let Some(path) = cgroup_path(&cgroups, "") {
+            return Some(CgroupLimits {
+                cpu: effective(root, &path, |dir| parse_cpu_max(&read(dir, "cpu.max")?)),
+                memory: effective(root, &path, |dir| {
+                    parse_memory_max(&read(dir, "memory.max")?)
+                }),
+            });
+        }
+
+        // cgroup v1
+        let cpu_path = cgroup_path(&cgroups, "cpu");
+        let memory_path = cgroup_path(&cgroups, "memory");
+        if cpu_path.is_none() && memory_path.is_none() {
+            return None;
+        }
+        Some(CgroupLimits {
+            cpu: cpu_path.and_then(|path| {
+                effective(&root.join("cpu"), &path, |dir| {
+                    let quota = read(dir, "cpu.cfs_quota_us")?;
+                    parse_cfs_quota(&quota, &read(dir, "cpu.cfs_period_us")?)
+                })
+            }),
+            memory: memory_path.and_then(|path| {
+                effective(&root.join("memory"), &path, |dir| {
+                    parse_memory_max(&read(dir, "memory.limit_in_bytes")?)
+                })
+            }),
+        })
+    }
+
+    /// Compares limits with those negotiated in the Agreement.
+    pub fn audit(&self, infrastructure: &HashMap<String, f64>) -> Vec<Discrepancy> {
+        let mut discrepancies = Vec::new();
+
+        if let Some(negotiated) = infrastructure.get("cpu.threads").copied() {
+            if exceeds(self.cpu, negotiated) {
+                discrepancies.push(Discrepancy::Cpu {
+                    negotiated,
+                    actual: self.cpu,
+                });
+            }
+        }
+        if let Some(gib) = infrastructure.get("mem.gib").copied() {
+            let negotiated = (gib * GIB) as u64;
+            if exceeds(self.memory.map(|m| m as f64), negotiated as f64) {
+                discrepancies.push(Discrepancy::Memory {
+                    negotiated,
+                    actual: self.memory,
+                });
+            }
+        }
+        discrepancies
+    }
+}
+
+fn exceeds(actual: Option<f64>, negotiated: f64) -> bool {
+    match actual {
+        Some(actual) => actual > negotiated * (1. + TOLERANCE),
+        None => true,
+    }
+}
+
+/// Path of the cgroup from `/proc/<pid>/cgroup` for the given v1 controller
+/// or the v2 unified hierarchy, when `controller` is empty.
+fn cgroup_path(cgroups: &str, controller: &str) -> Option<PathBuf> {
+    cgroups.lines().find_map(|line| {
+        let mut parts = line.splitn(3, ':');
+        let (id, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+        let matches = match controller.is_empty() {
+            true => id == "0" && controllers.is_empty(),
+            false => controllers.split(',').any(|c| c == controller),
+        };
+        matches.then(|| PathBuf::from(path.trim_start_matches('/')))
+    })
+}
+
+fn read(dir: &Path, file: &str) -> Option<String> {
+    std::fs::read_to_string(dir.join(file)).ok()
+}
+
+/// The lowest limit set on the cgroup or any of its ancestors.
+fn effective<T, F>(root: &Path, path: &Path, limit: F) -> Option<T>
+where
+    T: PartialOrd,
+    F: Fn(&Path) -> Option<T>,
+{
+    path.ancestors()
+        .filter_map(|dir| limit(&root.join(dir)))
+        .fold(None, |min, limit| match min {
+            Some(min) if min <= limit => Some(min),
+            _ => Some(limit),
+        })
+}
+
+/// Parses `cpu.max` (e.g. `200000 100000` or `max 100000`).
+fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut parts = content.split_whitespace();
+    let quota = parts.next()?;
+    let period = parts.next().unwrap_or("100000");
+    parse_cfs_quota(quota, period)
+}
+
+fn parse_cfs_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota = quota.trim().parse::<i64>().ok().filter(|q| *q > 0)?;
+    let period = period.trim().parse::<i64>().ok().filter(|p| *p > 0)?;
+    Some(quota as f64 / period as f64)
+}
+
+/// Parses `memory.max` or `memory.limit_in_bytes`. v1 reports no limit as a huge number.
+fn parse_memory_max(content: &str) -> Option<u64> {
+    content
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|limit| *limit < i64::MAX as u64 / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_limits() {
+        assert_eq!(parse_cpu_max("200000 100000"), Some(2.));
+        assert_eq!(parse_cpu_max("max 100000"), None);
+        assert_eq!(parse_cfs_quota("-1", "100000"), None);
+        assert_eq!(parse_memory_max("1073741824\n"), Some(1073741824));
+        assert_eq!(parse_memory_max("max"), None);
+        assert_eq!(parse_memory_max("9223372036854771712"), None);
+    }
+
+    #[test]
+    fn parse_cgroup_path() {
+        let v2 = "0::/system.slice/golem.service\n";
+        assert_eq!(
+            cgroup_path(v2, ""),
+            Some(PathBuf::from("system.slice/golem.service"))
+        );
+        assert_eq!(cgroup_path(v2, "memory"), None);
+
+        let v1 = "4:memory:/user.slice\n3:cpu,cpuacct:/golem\n";
+        assert_eq!(cgroup_path(v1, ""), None);
+        assert_eq!(cgroup_path(v1, "cpu"), Some(PathBuf::from("golem")));
+        assert_eq!(cgroup_path(v1, "memory"), Some(PathBuf::from("user.slice")));
+    }
+
+    #[test]
+    fn audit_limits() {
+        let infrastructure: HashMap<String, f64> =
+            vec![("cpu.threads".to_string(), 2.), ("mem.gib".to_string(), 1.)]
+                .into_iter()
+                .collect();
+
+        let enforced = CgroupLimits {
+            cpu: Some(2.),
+            memory: Some(GIB as u64),
+        };
+        assert!(enforced.audit(&infrastructure).is_empty());
+
+        let ignored = CgroupLimits {
+            cpu: Some(4.),
+            memory: None,
+        };
+        assert_eq!(
+            ignored.audit(&infrastructure),
+            vec![
+                Discrepancy::Cpu {
+                    negotiated: 2.,
+                    actual: Some(4.)
+                },
+                Discrepancy::Memory {
+                    negotiated: GIB as u64,
+                    actual: None
+                },
+            ]
+        );
+        assert!(ignored.audit(&HashMap::new()).is_empty());
+    }
+}
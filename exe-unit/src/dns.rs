@@ -6,6 +6,9 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use trust_dns_resolver::config;
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::proto::op::{Message, MessageType, Query, ResponseCode};
+use trust_dns_resolver::proto::rr::{Name, RData, Record};
 use trust_dns_resolver::TokioAsyncResolver;
 
 #[derive(Clone)]
@@ -26,6 +29,31 @@ impl StableResolver {
         Ok(response.into_iter().collect())
     }
 
+    /// Resolves single question from workload's DNS query.
+    /// Negative answers are returned as response code instead of an error.
+    pub async fn lookup(&self, query: &Query) -> Result<Vec<Record>, ResponseCode> {
+        log::debug!(
+            "Resolving {} record of '{}'",
+            query.query_type(),
+            query.name()
+        );
+
+        match self
+            .resolver
+            .lookup(query.name().clone(), query.query_type())
+            .await
+        {
+            Ok(lookup) => Ok(lookup.records().to_vec()),
+            Err(e) => match e.kind() {
+                ResolveErrorKind::NoRecordsFound { response_code, .. } => Err(*response_code),
+                _ => {
+                    log::debug!("Failed to resolve '{}': {}", query.name(), e);
+                    Err(ResponseCode::ServFail)
+                }
+            },
+        }
+    }
+
     pub fn stable_dns(&self) -> IpAddr {
         self.stable_dns
     }
@@ -71,14 +99,32 @@ pub async fn resolver() -> anyhow::Result<StableResolver> {
 
 pub const DNS_PORT: u16 = 53;
 
-pub fn dns_servers() -> impl Iterator<Item = IpAddr> {
-    use trust_dns_resolver::config::*;
+/// Lowercase host name without trailing dot, comparable with hosts from manifest urls.
+pub fn host_name(name: &Name) -> String {
+    name.to_utf8().trim_end_matches('.').to_lowercase()
+}
+
+pub fn record_ip(record: &Record) -> Option<IpAddr> {
+    match record.data()? {
+        RData::A(ip) => Some(IpAddr::V4(*ip)),
+        RData::AAAA(ip) => Some(IpAddr::V6(*ip)),
+        _ => None,
+    }
+}
 
-    GOOGLE_IPS
-        .iter()
-        .cloned()
-        .chain(CLOUDFLARE_IPS.iter().cloned())
-        .chain(QUAD9_IPS.iter().cloned())
+/// Builds response to workload's DNS `request`.
+pub fn response(request: &Message, code: ResponseCode, answers: Vec<Record>) -> Message {
+    let mut response = Message::new();
+    response
+        .set_id(request.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(request.op_code())
+        .set_recursion_desired(request.recursion_desired())
+        .set_recursion_available(true)
+        .set_response_code(code)
+        .add_queries(request.queries().iter().cloned())
+        .add_answers(answers);
+    response
 }
 
 // Do not use it in CI
@@ -66,6 +66,8 @@ pub enum Error {
     Acl(#[from] crate::acl::Error),
     #[error(transparent)]
     Validation(#[from] crate::manifest::ValidationError),
+    #[error(transparent)]
+    Secrets(#[from] crate::secrets::SecretsError),
     #[error("{0}")]
     Other(String),
     #[cfg(feature = "sgx")]
@@ -151,6 +153,7 @@ impl From<Error> for RpcError {
             Error::Net(e) => RpcError::Service(e.to_string()),
             Error::Acl(e) => RpcError::Forbidden(e.to_string()),
             Error::Validation(e) => RpcError::BadRequest(e.to_string()),
+            Error::Secrets(e) => RpcError::BadRequest(e.to_string()),
             Error::Other(e) => RpcError::Service(e),
             #[cfg(feature = "sgx")]
             Error::Crypto(e) => RpcError::Service(e.to_string()),
@@ -1,11 +1,12 @@
 use actix::dev::IntervalFunc;
 use actix::{
     Actor, ActorFutureExt, ActorStreamExt, Addr, AsyncContext, Context, ContextFutureSpawner,
-    Handler, Message, ResponseFuture, Running, StreamHandler, WrapFuture,
+    Handler, Message, ResponseFuture, Running, SpawnHandle, StreamHandler, WrapFuture,
 };
 use chrono::Utc;
 use futures::channel::{mpsc, oneshot};
 use futures::{FutureExt, SinkExt};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::time::Duration;
 use tokio::sync::broadcast;
@@ -18,6 +19,7 @@ use ya_client_model::activity::exe_script_command::VolumeMount;
 use ya_client_model::activity::{ActivityUsage, CommandOutput, ExeScriptCommand, State, StatePair};
 use ya_core_model::activity;
 use ya_core_model::activity::local::Credentials;
+use ya_runtime_api::capabilities::{Capability, RuntimeCapabilities};
 use ya_runtime_api::deploy;
 use ya_runtime_api::deploy::ContainerVolume;
 use ya_service_bus::{actix_rpc, RpcEndpoint, RpcMessage};
@@ -28,12 +30,17 @@ use ya_transfer::transfer::{
 
 use crate::acl::Acl;
 use crate::agreement::Agreement;
+use crate::cgroup::CgroupLimits;
 use crate::error::Error;
 use crate::message::{
     ExecuteCommand, GetStdOut, Initialize, RuntimeEvent, SetState, Shutdown, ShutdownReason,
     SignExeScript, Stop, UpdateDeployment,
 };
+use crate::metrics::{self, Metrics, MetricsPush};
+use crate::output::OutputConfig;
 use crate::runtime::{Runtime, RuntimeMode};
+use crate::secrets::{redact, SecretsKey};
+use crate::security::AppliedSecurityProfile;
 use crate::service::{self, ServiceAddr, ServiceControl};
 use crate::state::{ExeUnitState, StateError, Supervision};
 use crate::Result;
@@ -42,6 +49,10 @@ lazy_static::lazy_static! {
     static ref DEFAULT_REPORT_INTERVAL: Duration = Duration::from_secs(1u64);
 }
 
+/// Fractions of the activity duration limit, at which Requestor is warned
+/// through activity state reason.
+const DURATION_LIMIT_WARNINGS: [f64; 2] = [0.8, 0.95];
+
 #[derive(Clone, Debug, Default, Message)]
 #[rtype(result = "Result<broadcast::Receiver<()>>")]
 pub struct FinishNotifier {}
@@ -55,6 +66,9 @@ pub struct ExeUnit<R: Runtime> {
     pub(crate) transfers: Addr<TransferService>,
     pub(crate) services: Vec<Box<dyn ServiceControl>>,
     pub(crate) shutdown_tx: broadcast::Sender<()>,
+    /// Latest values of counters reported by the runtime.
+    pub(crate) runtime_metrics: BTreeMap<String, f64>,
+    metrics_push: Option<SpawnHandle>,
 }
 
 impl<R: Runtime> ExeUnit<R> {
@@ -78,6 +92,8 @@ impl<R: Runtime> ExeUnit<R> {
                 Box::new(ServiceAddr::new(runtime)),
             ],
             shutdown_tx,
+            runtime_metrics: BTreeMap::new(),
+            metrics_push: None,
         }
     }
 
@@ -95,6 +111,14 @@ impl<R: Runtime> ExeUnit<R> {
         Ok(supervisor_template.patch(runtime_template))
     }
 
+    pub fn capabilities(
+        binary: PathBuf,
+        args: Vec<String>,
+    ) -> crate::Result<Option<RuntimeCapabilities>> {
+        use crate::runtime::process::RuntimeProcess;
+        RuntimeProcess::capabilities(binary, args)
+    }
+
     pub fn test(binary: PathBuf, args: Vec<String>) -> crate::Result<std::process::Output> {
         use crate::runtime::process::RuntimeProcess;
         RuntimeProcess::test(binary, args)
@@ -113,6 +137,123 @@ impl<R: Runtime> ExeUnit<R> {
         context.spawn(fut.into_actor(self));
     }
 
+    /// Starts pushing metrics to the collector, replacing the previous one.
+    pub(crate) fn push_metrics(
+        &mut self,
+        target: MetricsPush,
+        ctx: &mut Context<Self>,
+    ) -> Result<()> {
+        self.ctx.check_metrics_push(&target)?;
+        if let Some(handle) = self.metrics_push.take() {
+            ctx.cancel_future(handle);
+        }
+        log::info!(
+            "Pushing metrics to {} every {}s",
+            target.url,
+            target.interval.as_secs_f64()
+        );
+
+        // Redirects would lead to endpoints, which were not checked against the manifest
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| Error::Other(format!("Metrics push client: {e}")))?;
+        let handle = ctx.run_interval(target.interval, move |this, ctx| {
+            let snapshot = Metrics {
+                activity_id: this.ctx.activity_id.clone(),
+                agreement_id: this.ctx.agreement.inner.id.clone(),
+                usage: Default::default(),
+                runtime: this.runtime_metrics.clone(),
+                timestamp: Utc::now(),
+            };
+            let fut = push_metrics(
+                client.clone(),
+                target.url.clone(),
+                this.counters.clone(),
+                this.ctx.agreement.usage_vector.clone(),
+                snapshot,
+            );
+            ctx.spawn(fut.into_actor(this));
+        });
+        self.metrics_push = Some(handle);
+        Ok(())
+    }
+
+    /// Schedules warnings and graceful shutdown of the activity at the negotiated
+    /// wallclock cap, which is measured from the ExeUnit start.
+    fn enforce_duration_limit(&mut self, ctx: &mut Context<Self>) {
+        let limit = match self.ctx.agreement.max_duration {
+            Some(limit) => limit,
+            None => return,
+        };
+        log::info!("Activity duration limited to {}s", limit.as_secs());
+
+        for fraction in DURATION_LIMIT_WARNINGS {
+            let remaining = limit.mul_f64(1. - fraction);
+            ctx.run_later(limit.mul_f64(fraction), move |this, ctx| {
+                if !this.state.inner.alive() {
+                    return;
+                }
+                let reason = format!(
+                    "Activity will be terminated in {}s: duration limit of {}s",
+                    remaining.as_secs(),
+                    limit.as_secs()
+                );
+                log::warn!("{}", reason);
+                let set_state = SetState::new(this.state.inner, reason);
+                ctx.address().do_send(set_state);
+            });
+        }
+
+        ctx.run_later(limit, move |_, ctx| {
+            log::warn!("Activity duration limit of {}s exceeded", limit.as_secs());
+            ctx.address()
+                .do_send(Shutdown(ShutdownReason::DurationLimitExceeded(limit)));
+        });
+    }
+
+    /// Periodically verifies, that limits of the cgroup match the negotiated resources.
+    /// Requestor is warned about discrepancies through activity state reason.
+    fn audit_resource_limits(&mut self, ctx: &mut Context<Self>) {
+        let interval = match self.ctx.cgroup_audit_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        let mut reported = Vec::new();
+        ctx.run_interval(interval, move |this, ctx| {
+            if !this.state.inner.alive() {
+                return;
+            }
+            let limits = match CgroupLimits::current() {
+                Some(limits) => limits,
+                None => {
+                    log::debug!("Cgroups are not available. Skipping resource limit audit");
+                    return;
+                }
+            };
+
+            let discrepancies = limits.audit(&this.ctx.agreement.infrastructure);
+            if discrepancies == reported {
+                return;
+            }
+            if discrepancies.is_empty() {
+                log::info!("Negotiated resource limits are enforced: {:?}", limits);
+            } else {
+                let reason = discrepancies
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                log::warn!("Resource limit discrepancy: {}", reason);
+                let reason = format!("Resource limit discrepancy: {}", reason);
+                ctx.address()
+                    .do_send(SetState::new(this.state.inner, reason));
+            }
+            reported = discrepancies;
+        });
+    }
+
     pub(crate) async fn stop_runtime(runtime: Addr<R>, reason: ShutdownReason) {
         if let Err(e) = runtime
             .send(Shutdown(reason))
@@ -147,6 +288,7 @@ impl<R: Runtime> RuntimeRef<R> {
         exec: activity::Exec,
         runtime: Addr<R>,
         transfers: Addr<TransferService>,
+        output: OutputConfig,
         mut events: mpsc::Sender<RuntimeEvent>,
         mut control: oneshot::Receiver<()>,
     ) {
@@ -157,14 +299,17 @@ impl<R: Runtime> RuntimeRef<R> {
                 break;
             }
 
+            let is_run = matches!(command, ExeScriptCommand::Run { .. });
             let runtime_cmd = ExecuteCommand {
                 batch_id: batch_id.clone(),
                 command: command.clone(),
                 tx: events.clone(),
                 idx,
+                interactive: is_run && exec.interactive.contains(&idx),
+                run_options: exec.run_options.get(&idx).filter(|_| is_run).cloned(),
             };
 
-            let evt = RuntimeEvent::started(batch_id.clone(), idx, command.clone());
+            let evt = RuntimeEvent::started(batch_id.clone(), idx, redact(&command));
             if let Err(e) = events.send(evt).await {
                 log::error!("Unable to report event: {:?}", e);
             }
@@ -173,7 +318,8 @@ impl<R: Runtime> RuntimeRef<R> {
                 if runtime_cmd.stateless() {
                     self.exec_stateless(&runtime_cmd).await
                 } else {
-                    self.exec_stateful(runtime_cmd, &runtime, &transfers).await
+                    self.exec_stateful(runtime_cmd, &runtime, &transfers, &output)
+                        .await
                 }
             } {
                 Ok(_) => (0, None),
@@ -230,6 +376,7 @@ impl<R: Runtime> RuntimeRef<R> {
         runtime_cmd: ExecuteCommand,
         runtime: &Addr<R>,
         transfer_service: &Addr<TransferService>,
+        output: &OutputConfig,
     ) -> crate::Result<()> {
         let state = self.send(crate::message::GetState {}).await?.0;
         let state_pre = match (&state.0, &state.1) {
@@ -258,10 +405,10 @@ impl<R: Runtime> RuntimeRef<R> {
         };
         self.send(SetState::from(state_pre)).await?;
 
-        log::info!("Executing command: {:?}", runtime_cmd.command);
+        log::info!("Executing command: {:?}", redact(&runtime_cmd.command));
 
         let result = async {
-            self.pre_runtime(&runtime_cmd, runtime, transfer_service)
+            self.pre_runtime(&runtime_cmd, runtime, transfer_service, output)
                 .await?;
 
             let exit_code = runtime.send(runtime_cmd.clone()).await??;
@@ -269,7 +416,7 @@ impl<R: Runtime> RuntimeRef<R> {
                 return Err(Error::CommandExitCodeError(exit_code));
             }
 
-            self.post_runtime(&runtime_cmd, runtime, transfer_service)
+            self.post_runtime(&runtime_cmd, runtime, transfer_service, output)
                 .await?;
 
             Ok(())
@@ -294,6 +441,7 @@ impl<R: Runtime> RuntimeRef<R> {
         runtime_cmd: &ExecuteCommand,
         runtime: &Addr<R>,
         transfer_service: &Addr<TransferService>,
+        output: &OutputConfig,
     ) -> crate::Result<()> {
         match &runtime_cmd.command {
             ExeScriptCommand::Transfer {
@@ -319,9 +467,10 @@ impl<R: Runtime> RuntimeRef<R> {
                 hosts,
                 progress,
                 volumes,
+                env,
                 ..
             } => {
-                let volumes = if let Some(v) = &volumes {
+                let mut volumes = if let Some(v) = &volumes {
                     v.clone()
                         .as_volumes()
                         .iter()
@@ -341,6 +490,7 @@ impl<R: Runtime> RuntimeRef<R> {
                     Vec::new()
                 };
 
+                volumes.extend(output.spill_volume());
                 transfer_service.send(AddVolumes::new(volumes)).await??;
 
                 // TODO: We should pass `task_package` here not in `TransferService` initialization.
@@ -355,6 +505,7 @@ impl<R: Runtime> RuntimeRef<R> {
                         task_package,
                         networks: Some(net.clone()),
                         hosts: Some(hosts.clone()),
+                        env: Some(env.clone()),
                         ..Default::default()
                     })
                     .await??;
@@ -369,6 +520,7 @@ impl<R: Runtime> RuntimeRef<R> {
         runtime_cmd: &ExecuteCommand,
         runtime: &Addr<R>,
         transfer_service: &Addr<TransferService>,
+        output: &OutputConfig,
     ) -> crate::Result<()> {
         if let ExeScriptCommand::Deploy { .. } = &runtime_cmd.command {
             let mut runtime_mode = RuntimeMode::ProcessPerCommand;
@@ -379,11 +531,12 @@ impl<R: Runtime> RuntimeRef<R> {
                 })
                 .await?;
 
-            if let Some(output) = stdout {
-                let deployment = deploy::DeployResult::from_bytes(output).map_err(|e| {
+            if let Some(stdout) = stdout {
+                let mut deployment = deploy::DeployResult::from_bytes(stdout).map_err(|e| {
                     log::error!("Deployment failed: {}", e);
                     Error::CommandError(e.to_string())
                 })?;
+                deployment.vols.extend(output.spill_volume());
                 transfer_service
                     .send(AddVolumes::new(deployment.vols))
                     .await??;
@@ -424,7 +577,14 @@ impl<R: Runtime> Actor for ExeUnit<R> {
             {
                 actix_rpc::bind::<activity::Exec>(&srv_id, addr.clone().recipient());
                 actix_rpc::bind::<activity::GetExecBatchResults>(&srv_id, addr.clone().recipient());
+                actix_rpc::bind::<activity::GetExecBatchState>(&srv_id, addr.clone().recipient());
                 actix_rpc::bind::<activity::GetRunningCommand>(&srv_id, addr.clone().recipient());
+                actix_rpc::bind::<activity::WriteStdin>(&srv_id, addr.clone().recipient());
+                actix_rpc::bind::<activity::OpenTerminal>(&srv_id, addr.clone().recipient());
+                actix_rpc::bind::<activity::WriteTerminal>(&srv_id, addr.clone().recipient());
+                actix_rpc::bind::<activity::ResizeTerminal>(&srv_id, addr.clone().recipient());
+                actix_rpc::bind::<activity::CloseTerminal>(&srv_id, addr.clone().recipient());
+                actix_rpc::binds::<activity::StreamTerminal>(&srv_id, addr.clone().recipient());
                 actix_rpc::binds::<activity::StreamExecBatchResults>(
                     &srv_id,
                     addr.clone().recipient(),
@@ -435,6 +595,13 @@ impl<R: Runtime> Actor for ExeUnit<R> {
         IntervalFunc::new(*DEFAULT_REPORT_INTERVAL, Self::report_usage)
             .finish()
             .spawn(ctx);
+        self.enforce_duration_limit(ctx);
+        self.audit_resource_limits(ctx);
+        if let Some(target) = self.ctx.agreement.metrics_push.clone() {
+            if let Err(e) = self.push_metrics(target, ctx) {
+                log::warn!("Not pushing metrics: {}", e);
+            }
+        }
 
         log::info!("Initializing manifests");
         self.ctx
@@ -496,9 +663,23 @@ pub struct ExeUnitContext {
     pub agreement: Agreement,
     pub work_dir: PathBuf,
     pub cache_dir: PathBuf,
+    pub output: OutputConfig,
+    /// How often cgroup limits are compared with the Agreement. `None` disables the audit.
+    pub cgroup_audit_interval: Option<Duration>,
+    /// Bandwidth limit shared by transfers of the activity. The lower of Agreement and CLI limits.
+    pub transfer_bandwidth: Option<u64>,
+    /// Whether metrics may be pushed to collectors requested by the Requestor.
+    pub metrics_push: bool,
     pub runtime_args: Vec<String>,
+    pub security: AppliedSecurityProfile,
+    /// Reported by the runtime. `None` for runtimes predating the capability handshake,
+    /// which are assumed to support none of the optional capabilities.
+    pub capabilities: Option<RuntimeCapabilities>,
     pub acl: Acl,
     pub credentials: Option<Credentials>,
+    /// Decrypts secrets passed in `Deploy` environment. `None`, if Provider doesn't accept them.
+    #[derivative(Debug = "ignore")]
+    pub secrets: Option<SecretsKey>,
     #[cfg(feature = "sgx")]
     #[derivative(Debug = "ignore")]
     pub crypto: crate::crypto::Crypto,
@@ -517,6 +698,58 @@ impl ExeUnitContext {
             None => Ok(()),
         }
     }
+
+    /// Metrics are pushed only when enabled by the Provider, to collectors whitelisted
+    /// in the outbound access of the app manifest.
+    pub fn check_metrics_push(&self, target: &MetricsPush) -> crate::Result<()> {
+        if !self.metrics_push {
+            return Err(Error::Other("Metrics push is disabled".to_string()));
+        }
+        Ok(self.supervise.manifest.validate_outbound(&target.url)?)
+    }
+
+    /// Runtimes, which don't report capabilities, are given no optional arguments.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities
+            .as_ref()
+            .map(|capabilities| capabilities.supports(capability))
+            .unwrap_or(false)
+    }
+
+    /// Rejects the batch before it is started, when it needs capabilities,
+    /// which the runtime doesn't support or doesn't report.
+    pub fn check_capabilities(&self, exec: &activity::Exec) -> crate::Result<()> {
+        let mut required = Vec::new();
+        if !exec.interactive.is_empty() {
+            required.push(Capability::StdinStreaming);
+        }
+        if !exec.run_options.is_empty() {
+            required.push(Capability::RunOptions);
+        }
+        let deploys = exec
+            .exe_script
+            .iter()
+            .any(|cmd| matches!(cmd, ExeScriptCommand::Deploy { .. }));
+        if deploys && !self.agreement.devices.is_empty() {
+            required.push(Capability::Gpu);
+        }
+
+        let missing = required
+            .into_iter()
+            .filter(|capability| !self.supports(*capability))
+            .collect::<Vec<_>>();
+        match missing.as_slice() {
+            [] => Ok(()),
+            missing => Err(Error::CommandError(format!(
+                "runtime doesn't support: {}",
+                missing
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
 }
 
 impl From<&ExeUnitContext> for TransferServiceContext {
@@ -527,6 +760,7 @@ impl From<&ExeUnitContext> for TransferServiceContext {
             cache_dir: val.cache_dir.clone(),
             work_dir: val.work_dir.clone(),
             transfer_retry: None,
+            bandwidth_limit: val.transfer_bandwidth,
         }
     }
 }
@@ -601,6 +835,23 @@ async fn report_usage<R: Runtime>(
     }
 }
 
+async fn push_metrics(
+    client: reqwest::Client,
+    url: url::Url,
+    counters: Addr<CountersService>,
+    usage_vector: Vec<String>,
+    mut snapshot: Metrics,
+) {
+    match counters.send(GetCounters).await {
+        Ok(Ok(values)) => snapshot.usage = usage_vector.into_iter().zip(values).collect(),
+        Ok(Err(e)) => log::debug!("Unable to retrieve metrics: {:?}", e),
+        Err(e) => log::debug!("Unable to retrieve metrics: {:?}", e),
+    }
+    if let Err(e) = metrics::push(&client, &url, &snapshot).await {
+        log::warn!("Pushing metrics to {} failed: {}", url, e);
+    }
+}
+
 impl<R: Runtime> Handler<FinishNotifier> for ExeUnit<R> {
     type Result = Result<broadcast::Receiver<()>>;
 
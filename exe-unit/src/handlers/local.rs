@@ -27,6 +27,7 @@ impl<R: Runtime> StreamHandler<RuntimeEvent> for ExeUnit<R> {
                 _ => log::error!("Batch {} event error: unknown batch", event.batch_id),
             },
             RuntimeEvent::Counter { name, value } => {
+                self.runtime_metrics.insert(name.clone(), value);
                 let addr = self.counters.clone();
                 let fut = async move {
                     let _ = addr.send(SetCounter { name, value }).await;
@@ -53,7 +54,7 @@ impl<R: Runtime> Handler<SetState> for ExeUnit<R> {
     type Result = ActorResponse<Self, ()>;
 
     fn handle(&mut self, update: SetState, _ctx: &mut Context<Self>) -> Self::Result {
-        if self.state.inner == update.state {
+        if self.state.inner == update.state && update.reason.is_none() {
             return ActorResponse::reply(());
         }
 
@@ -65,22 +66,25 @@ impl<R: Runtime> Handler<SetState> for ExeUnit<R> {
             return ActorResponse::reply(());
         }
 
-        let credentials = match &update.state {
-            activity::StatePair(activity::State::Initialized, None) => self.ctx.credentials.clone(),
-            _ => None,
+        let initialized = update.state == activity::StatePair(activity::State::Initialized, None);
+        let credentials = match initialized {
+            true => self.ctx.credentials.clone(),
+            false => None,
         };
-        let fut = report(
-            self.ctx.report_url.clone().unwrap(),
-            SetActivityState::new(
-                self.ctx.activity_id.clone().unwrap(),
-                activity::ActivityState {
-                    state: update.state,
-                    reason: update.reason,
-                    error_message: None,
-                },
-                credentials,
-            ),
+        let mut msg = SetActivityState::new(
+            self.ctx.activity_id.clone().unwrap(),
+            activity::ActivityState {
+                state: update.state,
+                reason: update.reason,
+                error_message: None,
+            },
+            credentials,
         );
+        // Applied security profile is recorded for auditability.
+        if initialized {
+            msg = msg.with_security_profile(self.ctx.security.to_string());
+        }
+        let fut = report(self.ctx.report_url.clone().unwrap(), msg);
 
         ActorResponse::r#async(
             async move {
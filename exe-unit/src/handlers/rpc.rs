@@ -8,22 +8,34 @@ use tokio::time::timeout;
 
 #[cfg(feature = "sgx")]
 use ya_client_model::activity::encrypted::RpcMessageError as SgxMessageError;
-use ya_client_model::activity::{ActivityState, ActivityUsage, ExeScriptCommandResult};
+use ya_client_model::activity::{
+    ActivityState, ActivityUsage, ExeScriptCommand, ExeScriptCommandResult, State,
+};
 use ya_core_model::activity::*;
 use ya_counters::message::GetCounters;
 use ya_service_bus::{Error as RpcError, RpcEnvelope, RpcStreamCall};
 
 use crate::error::Error;
 use crate::manifest::{ManifestValidatorExt, ScriptValidator};
-use crate::message::GetBatchResults;
+use crate::message::{self, GetBatchResults};
+use crate::metrics::MetricsPush;
 use crate::runtime::Runtime;
+use crate::secrets::redact_script;
+use crate::state::DEFAULT_STATE_OUTPUT_LIMIT;
 use crate::{ExeUnit, RuntimeRef};
 
+/// Entry point of terminal sessions, when not requested otherwise.
+const DEFAULT_TERMINAL_SHELL: &str = "/bin/sh";
+
 impl<R: Runtime> Handler<RpcEnvelope<Exec>> for ExeUnit<R> {
     type Result = <RpcEnvelope<Exec> as Message>::Result;
 
     fn handle(&mut self, msg: RpcEnvelope<Exec>, ctx: &mut Self::Context) -> Self::Result {
-        log::debug!("Received Exec message: {:?}", msg.as_ref());
+        log::debug!(
+            "Received Exec message: batch {} {:?}",
+            msg.batch_id,
+            redact_script(&msg.exe_script)
+        );
         self.ctx.verify_activity_id(&msg.activity_id)?;
 
         let batch_id = msg.batch_id.clone();
@@ -39,15 +51,26 @@ impl<R: Runtime> Handler<RpcEnvelope<Exec>> for ExeUnit<R> {
             let m = format!("Manifest violation in ExeScript: {}", e);
             return Err(RpcMessageError::BadRequest(m));
         }
+        if let Err(e) = self.ctx.check_capabilities(&msg) {
+            return Err(RpcMessageError::BadRequest(e.to_string()));
+        }
+        if let Some(url) = &msg.metrics_push_url {
+            let interval = self.ctx.agreement.metrics_push.as_ref().map(|p| p.interval);
+            let target = MetricsPush::new(url, interval).map_err(RpcMessageError::BadRequest)?;
+            self.push_metrics(target, ctx)
+                .map_err(|e| RpcMessageError::Forbidden(e.to_string()))?;
+        }
 
         let (tx, rx) = oneshot::channel();
-        self.state.start_batch(msg.clone(), tx);
+        self.state
+            .start_batch(msg.clone(), tx, self.ctx.output.clone());
 
         RuntimeRef::from_ctx(ctx)
             .exec(
                 msg,
                 self.runtime.clone(),
                 self.transfers.clone(),
+                self.ctx.output.clone(),
                 self.events.tx.clone(),
                 rx,
             )
@@ -130,6 +153,228 @@ impl<R: Runtime> Handler<RpcEnvelope<GetRunningCommand>> for ExeUnit<R> {
     }
 }
 
+impl<R: Runtime> Handler<RpcEnvelope<GetExecBatchState>> for ExeUnit<R> {
+    type Result = <RpcEnvelope<GetExecBatchState> as Message>::Result;
+
+    fn handle(
+        &mut self,
+        msg: RpcEnvelope<GetExecBatchState>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        self.ctx.verify_activity_id(&msg.activity_id)?;
+        let batch = self
+            .state
+            .batches
+            .get(&msg.batch_id)
+            .ok_or_else(|| RpcMessageError::NotFound(format!("batch_id = {}", msg.batch_id)))?;
+
+        let output_limit = msg.output_limit.unwrap_or(DEFAULT_STATE_OUTPUT_LIMIT);
+        Ok(batch.batch_state(output_limit))
+    }
+}
+
+impl<R: Runtime> Handler<RpcEnvelope<WriteStdin>> for ExeUnit<R> {
+    type Result = ActorResponse<Self, Result<usize, RpcMessageError>>;
+
+    fn handle(&mut self, msg: RpcEnvelope<WriteStdin>, _: &mut Self::Context) -> Self::Result {
+        if let Err(err) = self.ctx.verify_activity_id(&msg.activity_id) {
+            return ActorResponse::reply(Err(err.into()));
+        }
+        if msg.data.len() > STDIN_CHUNK_MAX_SIZE {
+            let m = format!(
+                "Stdin chunk too large: {} B (max: {} B)",
+                msg.data.len(),
+                STDIN_CHUNK_MAX_SIZE
+            );
+            return ActorResponse::reply(Err(RpcMessageError::BadRequest(m)));
+        }
+        if !self.state.batches.contains_key(&msg.batch_id) {
+            let err = RpcMessageError::NotFound(format!("batch_id = {}", msg.batch_id));
+            return ActorResponse::reply(Err(err));
+        }
+
+        let msg = msg.into_inner();
+        let runtime = self.runtime.clone();
+        let fut = async move {
+            let write = message::WriteStdin {
+                batch_id: msg.batch_id,
+                idx: msg.idx,
+                data: msg.data,
+                close: msg.close,
+            };
+            match runtime.send(write).await {
+                Ok(result) => result.map_err(Into::into),
+                Err(e) => Err(Error::from(e).into()),
+            }
+        };
+
+        ActorResponse::r#async(fut.into_actor(self))
+    }
+}
+
+impl<R: Runtime> Handler<RpcEnvelope<OpenTerminal>> for ExeUnit<R> {
+    type Result = ActorResponse<Self, Result<String, RpcMessageError>>;
+
+    fn handle(&mut self, msg: RpcEnvelope<OpenTerminal>, _: &mut Self::Context) -> Self::Result {
+        if let Err(err) = self.ctx.verify_activity_id(&msg.activity_id) {
+            return ActorResponse::reply(Err(err.into()));
+        }
+        if self.state.inner.0 != State::Ready {
+            let m = "Terminal can be opened in a started activity only".to_string();
+            return ActorResponse::reply(Err(RpcMessageError::BadRequest(m)));
+        }
+
+        let msg = msg.into_inner();
+        let open = message::OpenTerminal {
+            entry_point: msg
+                .entry_point
+                .unwrap_or_else(|| DEFAULT_TERMINAL_SHELL.to_string()),
+            args: msg.args,
+            size: msg.size,
+        };
+
+        let run = ExeScriptCommand::Run {
+            entry_point: open.entry_point.clone(),
+            args: open.args.clone(),
+            capture: None,
+        };
+        let validator = self.ctx.supervise.manifest.validator::<ScriptValidator>();
+        if let Err(e) = validator.with(|c| c.validate(std::iter::once(&run))) {
+            let m = format!("Manifest violation in terminal command: {}", e);
+            return ActorResponse::reply(Err(RpcMessageError::BadRequest(m)));
+        }
+
+        let runtime = self.runtime.clone();
+        let fut = async move {
+            match runtime.send(open).await {
+                Ok(result) => result.map_err(RpcMessageError::from),
+                Err(e) => Err(Error::from(e).into()),
+            }
+        };
+
+        ActorResponse::r#async(fut.into_actor(self).map(|result, act, _| {
+            let (terminal_id, output) = result?;
+            act.state.terminals.insert(terminal_id.clone(), output);
+            Ok(terminal_id)
+        }))
+    }
+}
+
+impl<R: Runtime> Handler<RpcEnvelope<WriteTerminal>> for ExeUnit<R> {
+    type Result = ActorResponse<Self, Result<usize, RpcMessageError>>;
+
+    fn handle(&mut self, msg: RpcEnvelope<WriteTerminal>, _: &mut Self::Context) -> Self::Result {
+        if let Err(err) = self.ctx.verify_activity_id(&msg.activity_id) {
+            return ActorResponse::reply(Err(err.into()));
+        }
+        if msg.data.len() > STDIN_CHUNK_MAX_SIZE {
+            let m = format!(
+                "Terminal input chunk too large: {} B (max: {} B)",
+                msg.data.len(),
+                STDIN_CHUNK_MAX_SIZE
+            );
+            return ActorResponse::reply(Err(RpcMessageError::BadRequest(m)));
+        }
+
+        let msg = msg.into_inner();
+        let runtime = self.runtime.clone();
+        let fut = async move {
+            let write = message::WriteTerminal {
+                terminal_id: msg.terminal_id,
+                data: msg.data,
+            };
+            match runtime.send(write).await {
+                Ok(result) => result.map_err(Into::into),
+                Err(e) => Err(Error::from(e).into()),
+            }
+        };
+
+        ActorResponse::r#async(fut.into_actor(self))
+    }
+}
+
+impl<R: Runtime> Handler<RpcEnvelope<ResizeTerminal>> for ExeUnit<R> {
+    type Result = ActorResponse<Self, Result<(), RpcMessageError>>;
+
+    fn handle(&mut self, msg: RpcEnvelope<ResizeTerminal>, _: &mut Self::Context) -> Self::Result {
+        if let Err(err) = self.ctx.verify_activity_id(&msg.activity_id) {
+            return ActorResponse::reply(Err(err.into()));
+        }
+
+        let msg = msg.into_inner();
+        let runtime = self.runtime.clone();
+        let fut = async move {
+            let resize = message::ResizeTerminal {
+                terminal_id: msg.terminal_id,
+                size: msg.size,
+            };
+            match runtime.send(resize).await {
+                Ok(result) => result.map_err(Into::into),
+                Err(e) => Err(Error::from(e).into()),
+            }
+        };
+
+        ActorResponse::r#async(fut.into_actor(self))
+    }
+}
+
+impl<R: Runtime> Handler<RpcEnvelope<CloseTerminal>> for ExeUnit<R> {
+    type Result = ActorResponse<Self, Result<(), RpcMessageError>>;
+
+    fn handle(&mut self, msg: RpcEnvelope<CloseTerminal>, _: &mut Self::Context) -> Self::Result {
+        if let Err(err) = self.ctx.verify_activity_id(&msg.activity_id) {
+            return ActorResponse::reply(Err(err.into()));
+        }
+
+        let msg = msg.into_inner();
+        self.state.terminals.remove(&msg.terminal_id);
+
+        let runtime = self.runtime.clone();
+        let fut = async move {
+            let close = message::CloseTerminal {
+                terminal_id: msg.terminal_id,
+            };
+            match runtime.send(close).await {
+                Ok(result) => result.map_err(Into::into),
+                Err(e) => Err(Error::from(e).into()),
+            }
+        };
+
+        ActorResponse::r#async(fut.into_actor(self))
+    }
+}
+
+impl<R: Runtime> Handler<RpcStreamCall<StreamTerminal>> for ExeUnit<R> {
+    type Result = ActorResponse<Self, Result<(), RpcError>>;
+
+    fn handle(
+        &mut self,
+        msg: RpcStreamCall<StreamTerminal>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        if let Err(e) = self.ctx.verify_activity_id(&msg.body.activity_id) {
+            return ActorResponse::reply(Err(RpcError::GsbBadRequest(e.to_string())));
+        }
+        let output = match self.state.terminals.remove(&msg.body.terminal_id) {
+            Some(output) => output,
+            None => {
+                let msg = format!(
+                    "Unknown or already streamed terminal: {}",
+                    msg.body.terminal_id
+                );
+                return ActorResponse::reply(Err(RpcError::GsbBadRequest(msg)));
+            }
+        };
+
+        let rx = output.map(|data| Ok::<_, RpcError>(Ok(data)));
+        let reply = msg
+            .reply
+            .sink_map_err(|e| RpcError::GsbFailure(e.to_string()));
+
+        ActorResponse::r#async(async move { rx.forward(reply).await }.into_actor(self))
+    }
+}
+
 impl<R: Runtime> Handler<RpcEnvelope<GetExecBatchResults>> for ExeUnit<R> {
     type Result = ActorResponse<Self, Result<Vec<ExeScriptCommandResult>, RpcMessageError>>;
 
@@ -241,6 +486,9 @@ impl<R: Runtime> Handler<RpcEnvelope<sgx::CallEncryptedService>> for ExeUnit<R>
                         batch_id,
                         timeout,
                         exe_script,
+                        interactive: Vec::new(),
+                        metrics_push_url: None,
+                        run_options: Default::default(),
                     };
                     Response::Exec(
                         me.send(RpcEnvelope::local(msg))
@@ -3,12 +3,14 @@ extern crate derive_more;
 
 use actix::prelude::*;
 use anyhow::{bail, Context};
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::path::PathBuf;
 use structopt::clap;
 
 use ya_client_model::activity::ExeScriptCommand;
 use ya_core_model::activity;
+use ya_runtime_api::capabilities::Capability;
 use ya_service_bus::RpcEnvelope;
 use ya_transfer::transfer::TransferService;
 use ya_utils_path::normalize_path;
@@ -17,12 +19,16 @@ use crate::agreement::Agreement;
 use crate::error::Error;
 use crate::manifest::ManifestContext;
 use crate::message::{GetState, GetStateResponse, Register};
+use crate::output::OutputConfig;
 use crate::runtime::process::RuntimeProcess;
+use crate::secrets::SecretsKey;
+use crate::security::AppliedSecurityProfile;
 use crate::service::signal::SignalMonitor;
 use crate::state::Supervision;
 
 mod acl;
 pub mod agreement;
+pub mod cgroup;
 #[cfg(feature = "sgx")]
 pub mod crypto;
 pub mod error;
@@ -30,10 +36,13 @@ mod handlers;
 pub mod logger;
 pub mod manifest;
 pub mod message;
+pub mod metrics;
 mod network;
 mod notify;
 mod output;
 pub mod runtime;
+pub mod secrets;
+pub mod security;
 pub mod service;
 pub mod state;
 
@@ -79,6 +88,13 @@ pub struct Cli {
     )]
     #[allow(dead_code)]
     pub requestor_pub_key: Option<String>,
+    /// Provider key decrypting secrets passed with Deploy command. Generated, if missing
+    #[structopt(
+        long,
+        env = "EXE_UNIT_SECRETS_KEY_FILE",
+        set = clap::ArgSettings::Global,
+    )]
+    pub secrets_key_file: Option<PathBuf>,
     #[structopt(subcommand)]
     pub command: Command,
 }
@@ -129,6 +145,8 @@ pub enum Command {
     },
     /// Print an offer template in JSON format
     OfferTemplate,
+    /// Print capabilities reported by the runtime in JSON format
+    Capabilities,
     /// Run runtime's test command
     Test,
 }
@@ -144,6 +162,25 @@ pub struct RunArgs {
     /// Common cache directory
     #[structopt(long, short)]
     pub cache_dir: PathBuf,
+    /// Maximum number of bytes captured from stdout or stderr of a command (0: unlimited)
+    #[structopt(long, env = "EXE_UNIT_OUTPUT_LIMIT", default_value = "16777216")]
+    pub output_limit: usize,
+    /// Write full output of commands exceeding the limit to files, which can be transferred
+    #[structopt(long, env = "EXE_UNIT_SPILL_OUTPUT")]
+    pub spill_output: bool,
+    /// Profile file used, when `custom` security profile was negotiated
+    #[structopt(long, env = "EXE_UNIT_SECURITY_PROFILE_FILE")]
+    pub security_profile_file: Option<PathBuf>,
+    /// Interval in seconds of verifying, that cgroup limits match the Agreement (0: disabled)
+    #[structopt(long, env = "EXE_UNIT_CGROUP_AUDIT_INTERVAL", default_value = "0")]
+    pub cgroup_audit_interval: u64,
+    /// Transfer bandwidth limit per activity in bytes per second (0: unlimited)
+    #[structopt(long, env = "EXE_UNIT_TRANSFER_BANDWIDTH_LIMIT", default_value = "0")]
+    pub transfer_bandwidth_limit: u64,
+    /// Push metrics to collectors requested by the Requestor. Collectors must be
+    /// whitelisted in the outbound access of the app manifest
+    #[structopt(long, env = "EXE_UNIT_METRICS_PUSH")]
+    pub metrics_push: bool,
 }
 
 fn create_path(path: &PathBuf) -> anyhow::Result<PathBuf> {
@@ -175,6 +212,7 @@ pub async fn send_script(
     exe_unit: Addr<ExeUnit<RuntimeProcess>>,
     activity_id: Option<String>,
     exe_script: Vec<ExeScriptCommand>,
+    run_options: BTreeMap<usize, activity::RunOptions>,
 ) -> anyhow::Result<String> {
     use crate::state::{State, StatePair};
     use std::time::Duration;
@@ -193,7 +231,10 @@ pub async fn send_script(
         }
     }
 
-    log::debug!("Executing commands: {:?}", exe_script);
+    log::debug!(
+        "Executing commands: {:?}",
+        secrets::redact_script(&exe_script)
+    );
 
     let batch_id = hex::encode(rand::random::<[u8; 16]>());
     let msg = activity::Exec {
@@ -201,6 +242,9 @@ pub async fn send_script(
         batch_id: batch_id.clone(),
         exe_script,
         timeout: None,
+        interactive: Vec::new(),
+        metrics_push_url: None,
+        run_options,
     };
 
     exe_unit
@@ -232,7 +276,7 @@ pub async fn run(mut cli: Cli) -> anyhow::Result<()> {
             let contents = std::fs::read_to_string(&input).map_err(|e| {
                 anyhow::anyhow!("Cannot read commands from file {}: {e}", input.display())
             })?;
-            let contents = serde_json::from_str(&contents).map_err(|e| {
+            let contents = activity::exe_script::parse(&contents).map_err(|e| {
                 anyhow::anyhow!(
                     "Cannot deserialize commands from file {}: {e}",
                     input.display(),
@@ -254,10 +298,23 @@ pub async fn run(mut cli: Cli) -> anyhow::Result<()> {
         }
         Command::OfferTemplate => {
             let args = cli.runtime_arg.clone();
-            let offer_template = ExeUnit::<RuntimeProcess>::offer_template(cli.binary, args)?;
+            let mut offer_template = ExeUnit::<RuntimeProcess>::offer_template(cli.binary, args)?;
+            if let Some(path) = cli.secrets_key_file {
+                let key = SecretsKey::load_or_create(&path)?;
+                offer_template.set_property(
+                    secrets::PUB_KEY_PROPERTY,
+                    serde_json::Value::String(hex::encode(key.pub_key().serialize())),
+                );
+            }
             println!("{}", serde_json::to_string(&offer_template)?);
             return Ok(());
         }
+        Command::Capabilities => {
+            let args = cli.runtime_arg.clone();
+            let capabilities = ExeUnit::<RuntimeProcess>::capabilities(cli.binary, args)?;
+            println!("{}", serde_json::to_string(&capabilities)?);
+            return Ok(());
+        }
         Command::Test => {
             let args = cli.runtime_arg.clone();
             let output = ExeUnit::<RuntimeProcess>::test(cli.binary, args)?;
@@ -279,11 +336,17 @@ pub async fn run(mut cli: Cli) -> anyhow::Result<()> {
         sec_key: cli.sec_key,
         args,
         requestor_pub_key: cli.requestor_pub_key,
+        secrets_key_file: cli.secrets_key_file,
     })
     .await?;
 
-    if let Some(exe_script) = commands {
-        tokio::task::spawn(send_script(exe_unit.clone(), ctx_activity_id, exe_script));
+    if let Some((exe_script, run_options)) = commands {
+        tokio::task::spawn(send_script(
+            exe_unit.clone(),
+            ctx_activity_id,
+            exe_script,
+            run_options,
+        ));
     }
 
     exe_unit.send(FinishNotifier {}).await??.recv().await?;
@@ -303,6 +366,7 @@ pub struct ExeUnitConfig {
     pub sec_key: Option<String>,
     #[allow(dead_code)]
     pub requestor_pub_key: Option<String>,
+    pub secrets_key_file: Option<PathBuf>,
 }
 
 // Mut is necessary in case of sgx compilation :(((((
@@ -345,6 +409,35 @@ pub async fn exe_unit(mut config: ExeUnitConfig) -> anyhow::Result<Addr<ExeUnit<
     log::info!("Manifest-enabled features: {:?}", manifest_ctx.features());
     log::info!("User-provided payload: {:?}", agreement.task_package);
 
+    let secrets = config
+        .secrets_key_file
+        .as_deref()
+        .map(SecretsKey::load)
+        .transpose()
+        .context("Cannot load secrets key")?;
+
+    let capabilities =
+        RuntimeProcess::capabilities(config.binary.clone(), config.runtime_args.clone())
+            .context("Runtime capability handshake failed")?;
+    log::info!("Runtime capabilities: {:?}", capabilities);
+
+    let security = AppliedSecurityProfile::resolve(
+        agreement.security_profile,
+        args.security_profile_file.as_deref(),
+        capabilities
+            .as_ref()
+            .map(|caps| caps.supports(Capability::SecurityProfile))
+            .unwrap_or(false),
+    )
+    .context("Cannot apply security profile")?;
+    log::info!("Applied security profile: {}", security);
+
+    let transfer_bandwidth = Some(args.transfer_bandwidth_limit)
+        .filter(|limit| *limit > 0)
+        .into_iter()
+        .chain(agreement.transfer_bandwidth)
+        .min();
+
     let ctx = ExeUnitContext {
         supervise: Supervision {
             hardware: config.supervise.hardware,
@@ -354,11 +447,21 @@ pub async fn exe_unit(mut config: ExeUnitConfig) -> anyhow::Result<Addr<ExeUnit<
         activity_id: config.service_id.clone(),
         report_url: config.report_url,
         agreement,
+        output: OutputConfig::new(args.output_limit, args.spill_output, &work_dir),
+        cgroup_audit_interval: match args.cgroup_audit_interval {
+            0 => None,
+            secs => Some(std::time::Duration::from_secs(secs)),
+        },
+        transfer_bandwidth,
+        metrics_push: args.metrics_push,
         work_dir,
         cache_dir,
         runtime_args: config.runtime_args,
+        security,
+        capabilities,
         acl: Default::default(),
         credentials: None,
+        secrets,
         #[cfg(feature = "sgx")]
         crypto: init_crypto(
             config.sec_key.replace("<hidden>".into()),
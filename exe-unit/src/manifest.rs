@@ -9,12 +9,15 @@ use anyhow::Context;
 use futures::prelude::*;
 use serde_json::{Map, Value};
 use structopt::StructOpt;
+use trust_dns_resolver::proto::op::{Message, ResponseCode};
 use url::Url;
 
-use crate::dns::{StableResolver, DNS_PORT};
+use crate::dns::{self, StableResolver, DNS_PORT};
 use ya_agreement_utils::AgreementView;
 use ya_client_model::activity::ExeScriptCommand;
-use ya_manifest_utils::{read_manifest, AppManifest, ArgMatch, Command, Feature, Script};
+use ya_manifest_utils::{
+    read_manifest, AppManifest, ArgMatch, Command, Feature, OutboundAccess, Script,
+};
 use ya_manifest_utils::{Policy, PolicyConfig};
 use ya_utils_networking::vpn::Protocol;
 
@@ -66,6 +69,33 @@ impl ManifestContext {
             .and_then(|m| m.find_payload(std::env::consts::ARCH, std::env::consts::OS))
     }
 
+    /// Checks, that ExeUnit may connect to `url` on behalf of the Requestor.
+    /// Only hosts and ports whitelisted in the manifest's outbound access are allowed.
+    pub fn validate_outbound(&self, url: &Url) -> Result<(), ValidationError> {
+        let access = (*self.manifest)
+            .as_ref()
+            .and_then(|manifest| manifest.get_outbound_access());
+        let allowed = match access {
+            Some(OutboundAccess::Unrestricted) => true,
+            Some(OutboundAccess::Urls(urls)) => urls.iter().any(|allowed| {
+                allowed.scheme() != "udp"
+                    && allowed.port_or_known_default() == url.port_or_known_default()
+                    && matches!(
+                        (allowed.host_str(), url.host_str()),
+                        (Some(a), Some(b)) if a.eq_ignore_ascii_case(b)
+                    )
+            }),
+            None => false,
+        };
+        match allowed {
+            true => Ok(()),
+            false => Err(ValidationError::Url(format!(
+                "{} is not whitelisted in manifest outbound access",
+                url
+            ))),
+        }
+    }
+
     pub fn build_validators<'a>(&self) -> future::LocalBoxFuture<'a, anyhow::Result<ValidatorMap>> {
         if self.manifest.is_none()
             || self
@@ -392,10 +422,73 @@ pub struct UrlValidator {
 }
 
 enum AllowedAccess {
-    Urls(HashSet<(Protocol, IpAddr, u16)>),
+    Urls(WhitelistAccess),
     Unrestricted,
 }
 
+/// Outbound access restricted to urls listed in manifest.
+///
+/// Workload's DNS queries are answered by ExeUnit, so only whitelisted names
+/// can be resolved. Addresses resolved this way are pinned, and only pinned
+/// addresses can be connected to.
+#[derive(Default)]
+struct WhitelistAccess {
+    /// Protocols and ports allowed for each whitelisted host name.
+    hosts: HashMap<String, HashSet<(Protocol, u16)>>,
+    pinned: RwLock<HashSet<(Protocol, IpAddr, u16)>>,
+}
+
+impl WhitelistAccess {
+    fn try_new<'a>(urls: impl Iterator<Item = &'a Url>) -> anyhow::Result<Self> {
+        let mut access = WhitelistAccess::default();
+        for url in urls {
+            let protocol = match url.scheme() {
+                "udp" => Protocol::Udp,
+                _ => Protocol::Tcp,
+            };
+            let port = url
+                .port_or_known_default()
+                .ok_or_else(|| anyhow::anyhow!("unknown port: {}", url))?;
+
+            match url.host() {
+                Some(url::Host::Domain(host)) => {
+                    access
+                        .hosts
+                        .entry(host.to_lowercase())
+                        .or_default()
+                        .insert((protocol, port));
+                }
+                Some(url::Host::Ipv4(ip)) => access.pin(protocol, ip.into(), port),
+                Some(url::Host::Ipv6(ip)) => access.pin(protocol, ip.into(), port),
+                None => anyhow::bail!("invalid url: {}", url),
+            }
+        }
+        Ok(access)
+    }
+
+    fn pin(&self, protocol: Protocol, ip: IpAddr, port: u16) {
+        let mut pinned = self.pinned.write().unwrap();
+        if pinned.insert((protocol, ip, port)) {
+            log::debug!("Pinned whitelisted address {}:{} ({})", ip, port, protocol);
+        }
+    }
+
+    /// Allows connections to `ips` on protocols and ports whitelisted for `host`.
+    fn pin_host(&self, host: &str, ips: impl IntoIterator<Item = IpAddr>) {
+        if let Some(endpoints) = self.hosts.get(host) {
+            for ip in ips {
+                for (protocol, port) in endpoints {
+                    self.pin(*protocol, ip, *port);
+                }
+            }
+        }
+    }
+
+    fn is_allowed(&self, proto: Protocol, ip: IpAddr, port: u16) -> bool {
+        self.pinned.read().unwrap().contains(&(proto, ip, port))
+    }
+}
+
 impl ManifestValidator for UrlValidator {
     const VALIDATOR: Validator = Validator::Url;
 
@@ -418,18 +511,21 @@ impl ManifestValidator for UrlValidator {
                 match access {
                     ya_manifest_utils::OutboundAccess::Urls(urls) => {
                         let resolver = crate::dns::resolver().await?;
+                        let access = WhitelistAccess::try_new(urls.iter())?;
 
-                        // by default we whitelist well known dns servers.
-                        let mut set = crate::dns::dns_servers()
-                            .map(|ip| (Protocol::Udp, ip, DNS_PORT))
-                            .collect::<HashSet<_, _>>();
-
-                        let ips = resolve_ips(&resolver, urls.iter()).await?;
+                        // Pin addresses known upfront, so workloads connecting
+                        // without resolving names first keep working.
+                        for host in access.hosts.keys() {
+                            access.pin_host(host, resolver.ips(host).await?);
+                        }
 
-                        set.extend(ips);
+                        log::info!(
+                            "Mediating DNS queries of whitelisted hosts using {}",
+                            resolver.stable_dns()
+                        );
 
                         Ok(Some(Self {
-                            inner: Arc::new(AllowedAccess::Urls(set)),
+                            inner: Arc::new(AllowedAccess::Urls(access)),
                             resolver: Some(Arc::new(resolver)),
                         }))
                     }
@@ -449,8 +545,8 @@ impl ManifestValidator for UrlValidator {
 impl UrlValidator {
     pub fn validate(&self, proto: Protocol, ip: IpAddr, port: u16) -> Result<(), ValidationError> {
         match self.inner.as_ref() {
-            AllowedAccess::Urls(urls) => urls
-                .contains(&(proto, ip, port))
+            AllowedAccess::Urls(access) => access
+                .is_allowed(proto, ip, port)
                 .then_some(())
                 .ok_or_else(|| {
                     ValidationError::Url(format!(
@@ -462,34 +558,42 @@ impl UrlValidator {
         }
     }
 
-    pub fn stable_dns(&self) -> Option<IpAddr> {
-        self.resolver.as_ref().map(|r| r.stable_dns())
+    /// Workload's DNS traffic should be answered with `mediate_dns`
+    /// instead of being forwarded to the internet.
+    pub fn mediates_dns(&self, proto: Protocol, port: u16) -> bool {
+        proto == Protocol::Udp
+            && port == DNS_PORT
+            && matches!(self.inner.as_ref(), AllowedAccess::Urls(_))
     }
-}
 
-async fn resolve_ips<'a>(
-    resolver: &StableResolver,
-    urls: impl Iterator<Item = &'a Url>,
-) -> anyhow::Result<HashSet<(Protocol, IpAddr, u16)>> {
-    futures::stream::iter(urls)
-        .map(Ok)
-        .try_fold(HashSet::default(), |mut set, url| async move {
-            let protocol = match url.scheme() {
-                "udp" => Protocol::Udp,
-                _ => Protocol::Tcp,
-            };
-            let port = url
-                .port_or_known_default()
-                .ok_or_else(|| anyhow::anyhow!("unknown port: {}", url))?;
-            let host = url
-                .host_str()
-                .ok_or_else(|| anyhow::anyhow!("invalid url: {}", url))?;
+    /// Answers workload's DNS query. Names not whitelisted in manifest are refused,
+    /// addresses of whitelisted names are pinned and can be connected to afterwards.
+    pub async fn mediate_dns(&self, packet: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let (access, resolver) = match (self.inner.as_ref(), self.resolver.as_ref()) {
+            (AllowedAccess::Urls(access), Some(resolver)) => (access, resolver),
+            _ => anyhow::bail!("DNS mediation is enabled only for whitelisted outbound access"),
+        };
 
-            let ips: HashSet<IpAddr> = resolver.ips(host).await?;
-            set.extend(ips.into_iter().map(|ip| (protocol, ip, port)));
-            Ok(set)
-        })
-        .await
+        let request = Message::from_vec(packet).context("invalid DNS query")?;
+        let mut answers = Vec::new();
+
+        for query in request.queries() {
+            let host = dns::host_name(query.name());
+            if !access.hosts.contains_key(&host) {
+                log::info!("Refused DNS query of not whitelisted host '{}'", host);
+                return Ok(dns::response(&request, ResponseCode::Refused, vec![]).to_vec()?);
+            }
+
+            match resolver.lookup(query).await {
+                Ok(records) => {
+                    access.pin_host(&host, records.iter().filter_map(dns::record_ip));
+                    answers.extend(records);
+                }
+                Err(code) => return Ok(dns::response(&request, code, vec![]).to_vec()?),
+            }
+        }
+        Ok(dns::response(&request, ResponseCode::NoError, answers).to_vec()?)
+    }
 }
 
 #[cfg(test)]
@@ -710,4 +814,82 @@ mod tests {
         .unwrap();
         validator.validate(&commands).unwrap();
     }
+
+    #[test]
+    fn url_whitelist_pins_resolved_addresses() {
+        let urls = ["https://api.golem.network", "udp://1.2.3.4:9000"]
+            .iter()
+            .map(|url| Url::parse(url).unwrap())
+            .collect::<Vec<_>>();
+        let validator = UrlValidator {
+            inner: Arc::new(AllowedAccess::Urls(
+                WhitelistAccess::try_new(urls.iter()).unwrap(),
+            )),
+            resolver: None,
+        };
+        let resolved: IpAddr = "5.6.7.8".parse().unwrap();
+
+        assert!(validator.mediates_dns(Protocol::Udp, DNS_PORT));
+        assert!(!validator.mediates_dns(Protocol::Tcp, 443));
+        validator
+            .validate(Protocol::Udp, "1.2.3.4".parse().unwrap(), 9000)
+            .unwrap();
+        assert!(validator.validate(Protocol::Tcp, resolved, 443).is_err());
+
+        if let AllowedAccess::Urls(access) = validator.inner.as_ref() {
+            access.pin_host("example.com", [resolved]);
+            assert!(validator.validate(Protocol::Tcp, resolved, 443).is_err());
+
+            access.pin_host("api.golem.network", [resolved]);
+        }
+        validator.validate(Protocol::Tcp, resolved, 443).unwrap();
+        assert!(validator.validate(Protocol::Tcp, resolved, 80).is_err());
+        assert!(validator.validate(Protocol::Udp, resolved, 443).is_err());
+    }
+
+    #[test]
+    fn outbound_whitelist() {
+        let manifest = |out: serde_json::Value| -> ManifestContext {
+            let manifest = serde_json::from_value(serde_json::json!({
+                "version": "0.1.0",
+                "createdAt": "2022-07-26T12:51:00.000000Z",
+                "expiresAt": "2100-01-01T00:01:00.000000Z",
+                "payload": [],
+                "compManifest": {
+                    "version": "0.1.0",
+                    "net": { "inet": { "out": out } }
+                }
+            }))
+            .unwrap();
+            ManifestContext {
+                manifest: Arc::new(Some(manifest)),
+                ..Default::default()
+            }
+        };
+        let url = |url: &str| Url::parse(url).unwrap();
+
+        let ctx = manifest(serde_json::json!({
+            "protocols": ["https"],
+            "urls": ["https://Collector.example.com", "udp://10.0.0.1:4318"]
+        }));
+        ctx.validate_outbound(&url("https://collector.example.com:443/v1/metrics"))
+            .unwrap();
+        assert!(ctx
+            .validate_outbound(&url("http://collector.example.com/v1/metrics"))
+            .is_err());
+        assert!(ctx
+            .validate_outbound(&url("http://10.0.0.1:4318/v1/metrics"))
+            .is_err());
+        assert!(ctx
+            .validate_outbound(&url("http://169.254.169.254/latest"))
+            .is_err());
+
+        let ctx = manifest(serde_json::json!({ "unrestricted": { "urls": true } }));
+        ctx.validate_outbound(&url("http://10.0.0.1:4318/v1/metrics"))
+            .unwrap();
+
+        assert!(ManifestContext::default()
+            .validate_outbound(&url("http://10.0.0.1:4318/v1/metrics"))
+            .is_err());
+    }
 }
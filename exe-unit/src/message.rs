@@ -16,6 +16,7 @@ use ya_client_model::activity::exe_script_command::Network;
 use ya_client_model::activity::{
     CommandOutput, CommandProgress, ExeScriptCommand, ExeScriptCommandResult,
 };
+use ya_core_model::activity::{RunOptions, TerminalSize};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Message)]
 #[rtype(result = "GetStateResponse")]
@@ -75,13 +76,22 @@ impl From<StatePair> for SetState {
 }
 
 #[derive(Clone, Debug, Message, derive_more::Display)]
-#[display(fmt = "Command: {:?} (batch = {}[{}])", command, batch_id, idx)]
+#[display(
+    fmt = "Command: {:?} (batch = {}[{}])",
+    "crate::secrets::redact(command)",
+    batch_id,
+    idx
+)]
 #[rtype(result = "Result<i32>")]
 pub struct ExecuteCommand {
     pub batch_id: String,
     pub idx: usize,
     pub command: ExeScriptCommand,
     pub tx: mpsc::Sender<RuntimeEvent>,
+    /// Command reads stdin streamed with [`WriteStdin`].
+    pub interactive: bool,
+    /// Process options of `Run` command.
+    pub run_options: Option<RunOptions>,
 }
 
 impl ExecuteCommand {
@@ -99,6 +109,8 @@ impl ExecuteCommand {
                 batch_id: self.batch_id,
                 idx: self.idx,
                 tx: self.tx,
+                interactive: self.interactive,
+                run_options: self.run_options,
             },
         )
     }
@@ -165,6 +177,47 @@ pub struct CommandContext {
     pub batch_id: String,
     pub idx: usize,
     pub tx: mpsc::Sender<RuntimeEvent>,
+    pub interactive: bool,
+    pub run_options: Option<RunOptions>,
+}
+
+/// Write to stdin of a running interactive command.
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "Result<usize>")]
+pub struct WriteStdin {
+    pub batch_id: String,
+    pub idx: usize,
+    pub data: Vec<u8>,
+    pub close: bool,
+}
+
+/// Open a pseudo-terminal session. Returns terminal id and its output.
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "Result<(String, mpsc::Receiver<Vec<u8>>)>")]
+pub struct OpenTerminal {
+    pub entry_point: String,
+    pub args: Vec<String>,
+    pub size: TerminalSize,
+}
+
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "Result<usize>")]
+pub struct WriteTerminal {
+    pub terminal_id: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "Result<()>")]
+pub struct ResizeTerminal {
+    pub terminal_id: String,
+    pub size: TerminalSize,
+}
+
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "Result<()>")]
+pub struct CloseTerminal {
+    pub terminal_id: String,
 }
 
 #[derive(Clone, Debug, Default, Message)]
@@ -174,6 +227,9 @@ pub struct UpdateDeployment {
     pub runtime_mode: Option<RuntimeMode>,
     pub networks: Option<Vec<Network>>,
     pub hosts: Option<HashMap<String, String>>,
+    /// Environment of runtime processes. Secrets are still encrypted and are decrypted
+    /// by `RuntimeProcess`, when the deployment is updated.
+    pub env: Option<HashMap<String, String>>,
 }
 
 #[derive(Clone, Debug, Message)]
@@ -224,6 +280,8 @@ pub enum ShutdownReason {
     Interrupted(i32),
     #[error("Usage limit exceeded: {0}")]
     UsageLimitExceeded(String),
+    #[error("Activity duration limit of {}s exceeded", .0.as_secs())]
+    DurationLimitExceeded(std::time::Duration),
     #[error("{0}")]
     Error(#[from] Error),
 }
@@ -0,0 +1,157 @@
+//! Push of usage counters and custom runtime metrics to the Requestor's collector.
+//!
+//! Requestor sets an OTLP/HTTP endpoint in the Demand (`golem.srv.comp.metrics.push-url`)
+//! or in the `Exec` request, which overrides it for the rest of the activity. Metrics are
+//! posted periodically as OTLP/JSON gauges, in addition to usage reported in debit notes.
+//!
+//! Push is disabled unless the Provider enables it with `EXE_UNIT_METRICS_PUSH`, and the
+//! collector has to be whitelisted in the outbound access of the app manifest.
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use url::Url;
+
+pub const PUSH_URL_PROPERTY: &str = "/demand/properties/golem/srv/comp/metrics/push-url";
+pub const PUSH_INTERVAL_PROPERTY: &str =
+    "/demand/properties/golem/srv/comp/metrics/push-interval-sec";
+
+const DEFAULT_PUSH_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_PUSH_INTERVAL: Duration = Duration::from_secs(1);
+const SCOPE: &str = "ya-exe-unit";
+
+/// Collector endpoint requested by the Requestor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetricsPush {
+    pub url: Url,
+    pub interval: Duration,
+}
+
+impl MetricsPush {
+    /// Accepts `http(s)` URLs only. Interval is clamped to 1s.
+    pub fn new(url: &str, interval: Option<Duration>) -> Result<Self, String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid metrics push URL {url}: {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!(
+                "Unsupported metrics push URL scheme: {}",
+                url.scheme()
+            ));
+        }
+        Ok(MetricsPush {
+            url,
+            interval: interval
+                .unwrap_or(DEFAULT_PUSH_INTERVAL)
+                .max(MIN_PUSH_INTERVAL),
+        })
+    }
+}
+
+/// Snapshot of metrics of a single activity.
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    pub activity_id: Option<String>,
+    pub agreement_id: String,
+    /// Values of the usage vector counters.
+    pub usage: BTreeMap<String, f64>,
+    /// Latest values of counters reported by the runtime.
+    pub runtime: BTreeMap<String, f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Metrics {
+    /// OTLP/JSON `ExportMetricsServiceRequest` with a gauge per metric.
+    pub fn to_otlp_json(&self) -> Value {
+        let time = self
+            .timestamp
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_string();
+        let metrics = self
+            .usage
+            .iter()
+            .chain(self.runtime.iter())
+            .map(|(name, value)| {
+                json!({
+                    "name": name,
+                    "gauge": {
+                        "dataPoints": [{ "asDouble": value, "timeUnixNano": time }]
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut attributes = vec![attribute("service.name", SCOPE)];
+        attributes.push(attribute("golem.agreement.id", &self.agreement_id));
+        if let Some(activity_id) = &self.activity_id {
+            attributes.push(attribute("golem.activity.id", activity_id));
+        }
+
+        json!({
+            "resourceMetrics": [{
+                "resource": { "attributes": attributes },
+                "scopeMetrics": [{
+                    "scope": { "name": SCOPE },
+                    "metrics": metrics
+                }]
+            }]
+        })
+    }
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+pub async fn push(client: &reqwest::Client, url: &Url, metrics: &Metrics) -> anyhow::Result<()> {
+    client
+        .post(url.clone())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(&metrics.to_otlp_json())?)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_target() {
+        let push = MetricsPush::new("http://10.0.0.1:4318/v1/metrics", None).unwrap();
+        assert_eq!(push.interval, DEFAULT_PUSH_INTERVAL);
+
+        let push = MetricsPush::new("https://collector", Some(Duration::from_millis(10))).unwrap();
+        assert_eq!(push.interval, MIN_PUSH_INTERVAL);
+
+        assert!(MetricsPush::new("file:///etc/passwd", None).is_err());
+        assert!(MetricsPush::new("collector", None).is_err());
+    }
+
+    #[test]
+    fn otlp_json_gauges() {
+        let metrics = Metrics {
+            activity_id: Some("activity".to_string()),
+            agreement_id: "agreement".to_string(),
+            usage: [("golem.usage.cpu_sec".to_string(), 1.5)].into(),
+            runtime: [("golem.usage.gpu-sec".to_string(), 3.)].into(),
+            timestamp: DateTime::from_timestamp(1, 0).unwrap(),
+        };
+        let json = metrics.to_otlp_json();
+        let resource = &json["resourceMetrics"][0];
+
+        assert_eq!(
+            resource["resource"]["attributes"][2]["value"]["stringValue"],
+            "activity"
+        );
+        let gauges = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+        assert_eq!(gauges.len(), 2);
+        assert_eq!(gauges[0]["name"], "golem.usage.cpu_sec");
+        assert_eq!(gauges[0]["gauge"]["dataPoints"][0]["asDouble"], 1.5);
+        assert_eq!(
+            gauges[1]["gauge"]["dataPoints"][0]["timeUnixNano"],
+            "1000000000"
+        );
+    }
+}
@@ -43,11 +43,10 @@ use ya_utils_networking::vpn::{
     EtherFrame, EtherType, IpPacket, PeekPacket, SocketEndpoint, TcpPacket, UdpPacket,
 };
 
-use crate::dns::DNS_PORT;
 use crate::manifest::UrlValidator;
 use crate::message::Shutdown;
 use crate::network::Endpoint;
-use crate::{dns, Error, Result};
+use crate::{Error, Result};
 
 // 10.0.0.0/8 is a reserved private address space
 const IP4_ADDRESS: Ipv4Addr = Ipv4Addr::new(10, 42, 42, 1);
@@ -607,11 +606,17 @@ impl Proxy {
 
         log::debug!("[inet] connect to {desc:?}, using handle: {handle}");
 
-        let (mut ip, port) = (
+        let (ip, port) = (
             conv_ip_addr(meta.local.addr).map_err(|e| ProxyingError::routeable(conn, e))?,
             meta.local.port,
         );
-        if let Some(ref filter) = self.filter {
+        // DNS queries are answered by ExeUnit itself, regardless of the DNS server used.
+        let dns_mediator = self
+            .filter
+            .as_ref()
+            .filter(|filter| filter.mediates_dns(meta.protocol, port))
+            .cloned();
+        if let (Some(filter), None) = (&self.filter, &dns_mediator) {
             filter
                 .validate(meta.protocol, ip, port)
                 .map_err(|e| ProxyingError::routeable(conn, e.into()))?;
@@ -633,51 +638,60 @@ impl Proxy {
         let proxy2 = proxy.clone();
         let network2 = network.clone();
 
-        if let Some(stable_dns) = self.filter.as_ref().and_then(|f| f.stable_dns()) {
-            if port == DNS_PORT
-                && meta.protocol == Protocol::Udp
-                && dns::dns_servers().any(|dns_ip| ip == dns_ip)
-            {
-                ip = stable_dns;
-            }
-        }
-
-        tokio::task::spawn_local(async move {
-            let maybe_tx_rx = match meta.protocol {
-                Protocol::Tcp => inet_tcp_proxy(ip, port).await,
-                Protocol::Udp => inet_udp_proxy(ip, port).await,
-                other => Err(NetError::ProtocolNotSupported(other.to_string()).into()),
-            }
-            .map_err(|e| ProxyingError::routeable(conn, e))
-            .log_warn();
+        if let Some(filter) = dns_mediator {
+            tokio::task::spawn_local(async move {
+                while let Some(query) = proxy_rx.next().await {
+                    let filter = filter.clone();
+                    let mut proxy_tx = proxy_tx.clone();
 
-            match maybe_tx_rx {
-                Ok((mut tcp_tx, mut tcp_rx)) => {
                     tokio::task::spawn_local(async move {
-                        while let Some(data) = proxy_rx.next().await {
-                            tcp_tx.send(data).await.log_err().unwrap();
-                        }
-                    });
-
-                    tokio::task::spawn_local(async move {
-                        while let Some(data) = tcp_rx.next().await {
-                            proxy_tx
-                                .send(data.map(Into::<Bytes>::into))
-                                .await
-                                .log_err()
-                                .unwrap();
+                        match filter.mediate_dns(&query).await {
+                            Ok(response) => {
+                                let _ = proxy_tx.send(Ok(Bytes::from(response))).await;
+                            }
+                            Err(e) => log::debug!("[inet] DNS mediation error: {}", e),
                         }
                     });
                 }
-                Err(_) => {
-                    let handle = match get_handle(&network2, &meta) {
-                        Some(handle_from_net) => handle_from_net,
-                        None => handle,
-                    };
-                    let _ = proxy2.disconnect(handle).await;
+            });
+        } else {
+            tokio::task::spawn_local(async move {
+                let maybe_tx_rx = match meta.protocol {
+                    Protocol::Tcp => inet_tcp_proxy(ip, port).await,
+                    Protocol::Udp => inet_udp_proxy(ip, port).await,
+                    other => Err(NetError::ProtocolNotSupported(other.to_string()).into()),
                 }
-            }
-        });
+                .map_err(|e| ProxyingError::routeable(conn, e))
+                .log_warn();
+
+                match maybe_tx_rx {
+                    Ok((mut tcp_tx, mut tcp_rx)) => {
+                        tokio::task::spawn_local(async move {
+                            while let Some(data) = proxy_rx.next().await {
+                                tcp_tx.send(data).await.log_err().unwrap();
+                            }
+                        });
+
+                        tokio::task::spawn_local(async move {
+                            while let Some(data) = tcp_rx.next().await {
+                                proxy_tx
+                                    .send(data.map(Into::<Bytes>::into))
+                                    .await
+                                    .log_err()
+                                    .unwrap();
+                            }
+                        });
+                    }
+                    Err(_) => {
+                        let handle = match get_handle(&network2, &meta) {
+                            Some(handle_from_net) => handle_from_net,
+                            None => handle,
+                        };
+                        let _ = proxy2.disconnect(handle).await;
+                    }
+                }
+            });
+        }
 
         Ok(async move {
             while let Some(bytes) = inet_rx.next().await {
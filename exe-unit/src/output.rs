@@ -1,10 +1,64 @@
 use futures::channel::mpsc;
 use futures::StreamExt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 use tokio_util::codec::{BytesCodec, FramedRead};
 use ya_client_model::activity::{CaptureFormat, CaptureMode, CapturePart, CommandOutput};
+use ya_runtime_api::deploy::ContainerVolume;
 
 use crate::message::RuntimeEvent;
 
+/// Name of the work dir subdirectory with spilled output.
+const SPILL_VOLUME_NAME: &str = "output-spill";
+/// Container path, under which spilled output can be transferred.
+const SPILL_VOLUME_PATH: &str = "/.output";
+
+/// Limits of output captured from `Run` commands.
+#[derive(Clone, Debug, Default)]
+pub struct OutputConfig {
+    /// Maximum number of bytes captured from a single output stream of a command.
+    pub limit: Option<usize>,
+    /// Write full output of commands exceeding the limit to files in work dir.
+    pub spill: Option<PathBuf>,
+}
+
+impl OutputConfig {
+    pub fn new(limit: usize, spill: bool, work_dir: &std::path::Path) -> Self {
+        OutputConfig {
+            limit: (limit > 0).then_some(limit),
+            spill: spill.then(|| work_dir.join(SPILL_VOLUME_NAME)),
+        }
+    }
+
+    /// Volume making spilled output files available to `Transfer` commands.
+    pub fn spill_volume(&self) -> Option<ContainerVolume> {
+        self.spill.as_ref().map(|_| ContainerVolume {
+            name: SPILL_VOLUME_NAME.to_string(),
+            path: SPILL_VOLUME_PATH.to_string(),
+        })
+    }
+
+    pub(crate) fn capture(
+        &self,
+        mode: Option<CaptureMode>,
+        batch_id: &str,
+        idx: usize,
+        stream: &str,
+    ) -> CapturedOutput {
+        let mut output = CapturedOutput::with_limit(mode, self.limit);
+        if let Some(dir) = self.spill.as_ref().filter(|_| output.captures()) {
+            let name = format!("{batch_id}-{idx}.{stream}");
+            output.spill = Some(OutputSpill {
+                path: dir.join(&name),
+                url: format!("container:{SPILL_VOLUME_PATH}/{name}"),
+                file: None,
+            });
+        }
+        output
+    }
+}
+
 pub(crate) async fn forward_output<F, R>(read: R, tx: &mpsc::Sender<RuntimeEvent>, f: F)
 where
     F: Fn(Vec<u8>) -> RuntimeEvent + 'static,
@@ -28,27 +82,124 @@ pub(crate) struct CapturedOutput {
     pub format: CaptureFormat,
     head: CaptureBuffer,
     tail: CaptureBuffer,
+    total: usize,
+    spill: Option<OutputSpill>,
 }
 
 impl CapturedOutput {
-    pub fn all() -> Self {
+    fn new(stream: bool, format: CaptureFormat, head: CaptureBuffer, tail: CaptureBuffer) -> Self {
         CapturedOutput {
-            stream: true,
-            format: CaptureFormat::default(),
-            head: CaptureBuffer::all(),
-            tail: CaptureBuffer::discard(),
+            stream,
+            format,
+            head,
+            tail,
+            total: 0,
+            spill: None,
         }
     }
 
+    pub fn all() -> Self {
+        Self::new(
+            true,
+            CaptureFormat::default(),
+            CaptureBuffer::all(),
+            CaptureBuffer::discard(),
+        )
+    }
+
     pub fn discard() -> Self {
-        CapturedOutput {
-            stream: false,
-            format: CaptureFormat::default(),
-            head: CaptureBuffer::discard(),
-            tail: CaptureBuffer::discard(),
+        Self::new(
+            false,
+            CaptureFormat::default(),
+            CaptureBuffer::discard(),
+            CaptureBuffer::discard(),
+        )
+    }
+
+    /// Output capture with requested part limited to `limit` bytes.
+    pub fn with_limit(maybe_mode: Option<CaptureMode>, limit: Option<usize>) -> Self {
+        let cap = |size: usize| limit.map(|limit| size.min(limit)).unwrap_or(size);
+        let unlimited = || match limit {
+            Some(limit) => CaptureBuffer::capped(limit),
+            None => CaptureBuffer::all(),
+        };
+
+        let mode = match maybe_mode {
+            Some(mode) => mode,
+            None => return CapturedOutput::discard(),
+        };
+        match mode {
+            CaptureMode::AtEnd { part, format } => {
+                let (head, tail) = match part {
+                    Some(CapturePart::Head(limit)) => {
+                        (CaptureBuffer::capped(cap(limit)), CaptureBuffer::discard())
+                    }
+                    Some(CapturePart::Tail(limit)) => {
+                        (CaptureBuffer::discard(), CaptureBuffer::ring(cap(limit)))
+                    }
+                    Some(CapturePart::HeadTail(limit)) => {
+                        let limit = cap(limit);
+                        let head_limit = (limit + 1) / 2;
+                        let tail_limit = limit - head_limit;
+                        (
+                            CaptureBuffer::capped(head_limit),
+                            CaptureBuffer::ring(tail_limit),
+                        )
+                    }
+                    None => (unlimited(), CaptureBuffer::discard()),
+                };
+                Self::new(false, format.unwrap_or_default(), head, tail)
+            }
+            CaptureMode::Stream { limit, format } => Self::new(
+                true,
+                format.unwrap_or_default(),
+                match limit {
+                    Some(limit) => CaptureBuffer::capped(cap(limit)),
+                    None => unlimited(),
+                },
+                CaptureBuffer::discard(),
+            ),
         }
     }
 
+    /// Output is requested to be captured.
+    fn captures(&self) -> bool {
+        !matches!(
+            (&self.head, &self.tail),
+            (CaptureBuffer::Discard, CaptureBuffer::Discard)
+        )
+    }
+
+    fn captured(&self) -> usize {
+        self.head.as_slice().map(<[u8]>::len).unwrap_or(0)
+            + self.tail.as_slice().map(<[u8]>::len).unwrap_or(0)
+    }
+
+    /// Completes capturing and returns truncation marker, if not all output was captured.
+    /// Spill file is kept only for truncated output.
+    pub fn finish(&mut self, name: &str) -> Option<String> {
+        let truncated = self.captures() && self.total > self.captured();
+        let spill = self.spill.take().and_then(|spill| match truncated {
+            true => spill.close(),
+            false => {
+                spill.discard();
+                None
+            }
+        });
+
+        truncated.then(|| {
+            let mut marker = format!(
+                "{name} truncated: captured {} of {} B",
+                self.captured(),
+                self.total
+            );
+            if let Some(url) = spill {
+                marker.push_str(&format!(", full output: {url}"));
+            }
+            marker
+        })
+    }
+
     pub fn output(&self) -> Option<CommandOutput> {
         let head = self.head.as_slice().unwrap_or(&[]);
         let tail = self.tail.as_slice().unwrap_or(&[]);
@@ -73,6 +224,16 @@ impl CapturedOutput {
     }
 
     pub fn write<B: AsRef<[u8]> + ?Sized>(&mut self, bytes: &B) -> Option<CommandOutput> {
+        self.total += bytes.as_ref().len();
+        if let Some(spill) = self.spill.as_mut() {
+            if let Err(e) = spill.write(bytes.as_ref()) {
+                log::warn!("Unable to spill output to {}: {e}", spill.path.display());
+                if let Some(spill) = self.spill.take() {
+                    spill.discard();
+                }
+            }
+        }
+
         let bytes_head = self.head.write(bytes);
         let bytes_tail = self.tail.write(bytes);
         let bytes = bytes_head.or(bytes_tail);
@@ -87,46 +248,46 @@ impl CapturedOutput {
 
 impl From<Option<CaptureMode>> for CapturedOutput {
     fn from(maybe_mode: Option<CaptureMode>) -> Self {
-        let mode = match maybe_mode {
-            Some(mode) => mode,
-            None => return CapturedOutput::discard(),
-        };
-        match mode {
-            CaptureMode::AtEnd { part, format } => {
-                let (head, tail) = match part {
-                    Some(CapturePart::Head(limit)) => {
-                        (CaptureBuffer::capped(limit), CaptureBuffer::discard())
-                    }
-                    Some(CapturePart::Tail(limit)) => {
-                        (CaptureBuffer::discard(), CaptureBuffer::ring(limit))
-                    }
-                    Some(CapturePart::HeadTail(limit)) => {
-                        let head_limit = (limit + 1) / 2;
-                        let tail_limit = limit - head_limit;
-                        (
-                            CaptureBuffer::capped(head_limit),
-                            CaptureBuffer::ring(tail_limit),
-                        )
-                    }
-                    None => (CaptureBuffer::all(), CaptureBuffer::discard()),
-                };
+        CapturedOutput::with_limit(maybe_mode, None)
+    }
+}
+
+/// Full command output written to a file in work dir.
+struct OutputSpill {
+    path: PathBuf,
+    url: String,
+    file: Option<BufWriter<File>>,
+}
 
-                CapturedOutput {
-                    stream: false,
-                    format: format.unwrap_or_default(),
-                    head,
-                    tail,
+impl OutputSpill {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => {
+                if let Some(dir) = self.path.parent() {
+                    std::fs::create_dir_all(dir)?;
                 }
+                self.file.insert(BufWriter::new(File::create(&self.path)?))
             }
-            CaptureMode::Stream { limit, format } => CapturedOutput {
-                stream: true,
-                format: format.unwrap_or_default(),
-                head: match limit {
-                    Some(limit) => CaptureBuffer::capped(limit),
-                    None => CaptureBuffer::all(),
-                },
-                tail: CaptureBuffer::discard(),
-            },
+        };
+        file.write_all(bytes)
+    }
+
+    /// Returns url of the spill file for `Transfer` command.
+    fn close(mut self) -> Option<String> {
+        match self.file.take().map(|mut file| file.flush()) {
+            Some(Ok(_)) => Some(self.url),
+            Some(Err(e)) => {
+                log::warn!("Unable to spill output to {}: {e}", self.path.display());
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn discard(mut self) {
+        if self.file.take().is_some() {
+            let _ = std::fs::remove_file(&self.path);
         }
     }
 }
@@ -260,6 +421,30 @@ mod tests {
         assert_eq!(buf.as_slice(), Some(&[0, 1, 2, 3, 4][..]));
     }
 
+    #[test]
+    fn output_limit() {
+        let mode = CaptureMode::AtEnd {
+            part: None,
+            format: None,
+        };
+        let mut output = CapturedOutput::with_limit(Some(mode.clone()), Some(4));
+        output.write(&[0, 1, 2]);
+        assert_eq!(output.finish("stdout"), None);
+
+        let mut output = CapturedOutput::with_limit(Some(mode), Some(4));
+        output.write(&[0, 1, 2]);
+        output.write(&[3, 4, 5]);
+        assert!(matches!(output.output(), Some(CommandOutput::Bin(b)) if b == [0, 1, 2, 3]));
+        assert_eq!(
+            output.finish("stdout").as_deref(),
+            Some("stdout truncated: captured 4 of 6 B")
+        );
+
+        let mut output = CapturedOutput::with_limit(None, Some(4));
+        output.write(&[0, 1, 2, 3, 4, 5]);
+        assert_eq!(output.finish("stdout"), None);
+    }
+
     #[test]
     fn ring_buffer() {
         let mut buf = CaptureBuffer::ring(5);
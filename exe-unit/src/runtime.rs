@@ -10,6 +10,11 @@ pub trait Runtime:
     + Handler<Shutdown>
     + Handler<ExecuteCommand>
     + Handler<UpdateDeployment>
+    + Handler<WriteStdin>
+    + Handler<OpenTerminal>
+    + Handler<WriteTerminal>
+    + Handler<ResizeTerminal>
+    + Handler<CloseTerminal>
 {
 }
 
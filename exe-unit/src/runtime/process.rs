@@ -9,11 +9,15 @@ use std::sync::Arc;
 use actix::prelude::*;
 use futures::future::{self, LocalBoxFuture};
 use futures::{FutureExt, TryFutureExt};
-use tokio::process::Command;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::mpsc;
 
 use ya_agreement_utils::agreement::OfferTemplate;
 use ya_client_model::activity::{CommandOutput, ExeScriptCommand};
+use ya_core_model::activity::RunOptions;
 use ya_manifest_utils::Feature;
+use ya_runtime_api::capabilities::{Capability, RuntimeCapabilities};
 use ya_runtime_api::server::{spawn, RunProcess, RuntimeControl, RuntimeService};
 use ya_utils_process::{kill, ProcessTree, SystemError};
 
@@ -22,6 +26,7 @@ use crate::error::Error;
 use crate::manifest::{ManifestContext, UrlValidator};
 use crate::message::{
     CommandContext, ExecuteCommand, RuntimeEvent, Shutdown, ShutdownReason, UpdateDeployment,
+    WriteStdin,
 };
 use crate::network::inet::start_inet;
 use crate::network::inet::Inet;
@@ -30,13 +35,19 @@ use crate::network::Endpoint;
 use crate::output::forward_output;
 use crate::runtime::event::EventMonitor;
 use crate::runtime::{Runtime, RuntimeMode};
+use crate::secrets::{decrypt_env, SecretsKey};
+use crate::security::AppliedSecurityProfile;
 use crate::state::Deployment;
 use crate::ExeUnitContext;
 
+mod terminal;
+
 const PROCESS_KILL_TIMEOUT_SECONDS_ENV_VAR: &str = "PROCESS_KILL_TIMEOUT_SECONDS";
 const DEFAULT_PROCESS_KILL_TIMEOUT_SECONDS: i64 = 5;
 const MIN_PROCESS_KILL_TIMEOUT_SECONDS: i64 = 1;
 const SERVICE_PROTOCOL_VERSION: &str = "0.1.0";
+/// Number of stdin chunks buffered for an interactive command, before writers have to wait.
+const STDIN_BUFFER_CHUNKS: usize = 16;
 
 fn process_kill_timeout_seconds() -> i64 {
     let limit = std::env::var(PROCESS_KILL_TIMEOUT_SECONDS_ENV_VAR)
@@ -50,6 +61,9 @@ pub struct RuntimeProcess {
     binary: PathBuf,
     deployment: Deployment,
     children: HashSet<ChildProcess>,
+    stdin: HashMap<StdinKey, mpsc::Sender<Vec<u8>>>,
+    terminals: HashMap<String, terminal::Terminal>,
+    next_terminal: u64,
     service: Option<ProcessService>,
     monitor: Option<EventMonitor>,
     acl: Acl,
@@ -64,6 +78,9 @@ impl RuntimeProcess {
             binary,
             deployment: Default::default(),
             children: Default::default(),
+            stdin: Default::default(),
+            terminals: Default::default(),
+            next_terminal: 0,
             service: None,
             monitor: None,
             acl: ctx.acl.clone(),
@@ -123,6 +140,33 @@ impl RuntimeProcess {
         }
     }
 
+    /// Capability handshake. Returns `None` for runtimes, which don't implement
+    /// the `capabilities` command.
+    pub fn capabilities(
+        binary: PathBuf,
+        mut args: Vec<String>,
+    ) -> Result<Option<RuntimeCapabilities>, Error> {
+        args.push("capabilities".to_string());
+
+        let result = Self::run_cmd(binary.clone(), args)?;
+        if !result.status.success() {
+            log::info!(
+                "Runtime doesn't report its capabilities; assuming legacy runtime [{}]",
+                binary.display()
+            );
+            return Ok(None);
+        }
+        RuntimeCapabilities::from_bytes(&result.stdout)
+            .map(Some)
+            .map_err(|e| {
+                Error::Other(format!(
+                    "Invalid runtime capabilities [{}]: {}",
+                    binary.display(),
+                    e
+                ))
+            })
+    }
+
     pub fn test(binary: PathBuf, mut args: Vec<String>) -> Result<std::process::Output, Error> {
         args.push("test".to_string());
         Ok(Self::run_cmd(binary, args)?)
@@ -184,6 +228,14 @@ impl RuntimeProcess {
             }
         }
 
+        for device in &self.ctx.devices {
+            args.arg("--device");
+            args.arg(device);
+        }
+        if self.ctx.supports(Capability::SecurityProfile) {
+            args.args(self.ctx.security.runtime_args());
+        }
+
         args.args(self.ctx.runtime_args.iter());
 
         Ok(args)
@@ -192,11 +244,11 @@ impl RuntimeProcess {
 
 impl RuntimeProcess {
     fn handle_process_command<'f>(
-        &self,
+        &mut self,
         cmd: ExecuteCommand,
         address: Addr<Self>,
     ) -> LocalBoxFuture<'f, Result<i32, Error>> {
-        log::trace!("Handle process command: {cmd:?}");
+        log::trace!("Handle process command: {cmd}");
 
         let mut rt_args = match self.args() {
             Ok(args) => args,
@@ -219,7 +271,9 @@ impl RuntimeProcess {
             ExeScriptCommand::Run {
                 entry_point, args, ..
             } => rt_args
-                .args(["run", "--entrypoint"])
+                .arg("run")
+                .args(ctx.run_options.iter().flat_map(run_options_args))
+                .arg("--entrypoint")
                 .arg(entry_point)
                 .arg("--")
                 .args(args),
@@ -228,6 +282,17 @@ impl RuntimeProcess {
 
         let binary = self.binary.clone();
         let work_dir = self.ctx.work_dir.clone();
+        let security = self.ctx.security.clone();
+        let env = self.deployment.env.clone();
+
+        // Registered before spawning the process, so no input sent after
+        // the command was started is rejected.
+        let stdin = ctx.interactive.then(|| {
+            let key = (ctx.batch_id.clone(), ctx.idx);
+            let (tx, rx) = mpsc::channel(STDIN_BUFFER_CHUNKS);
+            self.stdin.insert(key.clone(), tx);
+            (StdinGuard::new(key, address.clone()), rx)
+        });
 
         log::info!(
             "Executing {:?} with {:?} from path {:?}",
@@ -237,13 +302,18 @@ impl RuntimeProcess {
         );
 
         async move {
-            let mut child = Command::new(binary)
+            let mut command = security_command(&binary, &security);
+            command
                 .current_dir(&work_dir)
                 .args(rt_args)
+                .envs(env)
                 .kill_on_drop(true)
                 .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?;
+                .stderr(Stdio::piped());
+            if stdin.is_some() {
+                command.stdin(Stdio::piped());
+            }
+            let mut child = command.spawn()?;
 
             let idx = ctx.idx;
             let id = ctx.batch_id.clone();
@@ -266,7 +336,20 @@ impl RuntimeProcess {
             };
             let _guard = ChildProcessGuard::new(proc, address.clone());
 
-            let result = future::join3(child.wait(), stdout, stderr).await;
+            let writer = match (stdin, child.stdin.take()) {
+                (Some((guard, rx)), Some(pipe)) => forward_stdin(pipe, rx, guard).boxed_local(),
+                _ => future::pending().boxed_local(),
+            };
+            let exited = async {
+                let wait = child.wait();
+                futures::pin_mut!(wait);
+                match future::select(wait, writer).await {
+                    future::Either::Left((result, _)) => result,
+                    future::Either::Right((_, wait)) => wait.await,
+                }
+            };
+
+            let result = future::join3(exited, stdout, stderr).await;
             Ok(result.0?.code().unwrap_or(-1))
         }
         .boxed_local()
@@ -277,7 +360,7 @@ impl RuntimeProcess {
         cmd: ExecuteCommand,
         address: Addr<Self>,
     ) -> LocalBoxFuture<'f, Result<i32, Error>> {
-        log::trace!("Handle service command: {cmd:?}");
+        log::trace!("Handle service command: {cmd}");
 
         let (cmd, ctx) = cmd.split();
         match cmd {
@@ -340,9 +423,10 @@ impl RuntimeProcess {
                 std::env::current_dir()
             );
 
-            let mut command = Command::new(&rt_binary);
+            let mut command = security_command(&rt_binary, &rt_ctx.security);
             command.current_dir(&rt_ctx.work_dir);
             command.args(rt_args);
+            command.envs(&deployment.env);
 
             let service = spawn(command, monitor.clone())
                 .map_err(Error::runtime)
@@ -398,6 +482,12 @@ impl RuntimeProcess {
         entry_point: String,
         mut args: Vec<String>,
     ) -> LocalBoxFuture<'f, Result<i32, Error>> {
+        if ctx.interactive {
+            let err =
+                Error::CommandError("stdin streaming is not supported in service mode".into());
+            return Box::pin(future::err(err));
+        }
+
         let (service, ctrl) = match self.service.as_ref() {
             Some(svc) => (svc.service.clone(), svc.control.clone()),
             None => return Box::pin(future::err(Error::runtime("START command not run"))),
@@ -410,6 +500,9 @@ impl RuntimeProcess {
             args
         );
 
+        // Deployment environment (including secrets) is passed to the workload,
+        // `run` options can override its variables.
+        let mut env = self.deployment.env.clone();
         let mut monitor = self.monitor.get_or_insert_with(Default::default).clone();
         let exec = async move {
             let name = Path::new(&entry_point)
@@ -417,9 +510,14 @@ impl RuntimeProcess {
                 .ok_or_else(|| Error::runtime("Invalid binary name"))?;
             args.insert(0, name.to_string_lossy().to_string());
 
+            let options = ctx.run_options.clone().unwrap_or_default();
+            env.extend(options.env);
             let run_process = RunProcess {
                 bin: entry_point,
                 args,
+                work_dir: options.cwd.unwrap_or_default(),
+                env: env.into_iter().collect(),
+                user: options.user.unwrap_or_default(),
                 ..Default::default()
             };
 
@@ -494,10 +592,45 @@ impl Handler<UpdateDeployment> for RuntimeProcess {
         if let Some(hosts) = msg.hosts {
             self.deployment.hosts.extend(hosts);
         }
+        if let Some(env) = msg.env {
+            let env = decrypt_env(&env, self.ctx.secrets.as_ref())?;
+            self.deployment.env.extend(env);
+        }
         Ok(())
     }
 }
 
+impl Handler<WriteStdin> for RuntimeProcess {
+    type Result = ResponseFuture<<WriteStdin as Message>::Result>;
+
+    fn handle(&mut self, msg: WriteStdin, _: &mut Self::Context) -> Self::Result {
+        let key = (msg.batch_id, msg.idx);
+        // Dropping the last sender closes the pipe, once buffered data is written.
+        let tx = match msg.close {
+            true => self.stdin.remove(&key),
+            false => self.stdin.get(&key).cloned(),
+        };
+
+        async move {
+            let (batch_id, idx) = key;
+            let tx = tx.ok_or_else(|| {
+                Error::CommandError(format!(
+                    "Command {batch_id}[{idx}] is not running or doesn't accept stdin"
+                ))
+            })?;
+
+            let len = msg.data.len();
+            if len > 0 {
+                tx.send(msg.data).await.map_err(|_| {
+                    Error::CommandError(format!("Stdin of command {batch_id}[{idx}] is closed"))
+                })?;
+            }
+            Ok(len)
+        }
+        .boxed_local()
+    }
+}
+
 impl Handler<SetProcessService> for RuntimeProcess {
     type Result = <SetProcessService as Message>::Result;
 
@@ -544,6 +677,14 @@ impl Handler<RemoveChildProcess> for RuntimeProcess {
     }
 }
 
+impl Handler<RemoveStdin> for RuntimeProcess {
+    type Result = <RemoveStdin as Message>::Result;
+
+    fn handle(&mut self, msg: RemoveStdin, _: &mut Self::Context) -> Self::Result {
+        self.stdin.remove(&msg.0);
+    }
+}
+
 impl Handler<Shutdown> for RuntimeProcess {
     type Result = ResponseFuture<Result<(), Error>>;
 
@@ -552,6 +693,8 @@ impl Handler<Shutdown> for RuntimeProcess {
         let proc = self.service.take();
         let vpn = self.vpn.take();
         let inet = self.inet.take();
+        self.stdin.clear();
+        self.terminals.clear();
         let mut children = std::mem::take(&mut self.children);
 
         log::info!("Shutting down the runtime process: {:?}", msg.0);
@@ -580,7 +723,10 @@ struct RuntimeProcessContext {
     supervise_image: bool,
     supervise_hardware: bool,
     infrastructure: HashMap<String, f64>,
+    devices: Vec<String>,
+    security: AppliedSecurityProfile,
     manifest: ManifestContext,
+    secrets: Option<SecretsKey>,
 }
 
 impl<'a> From<&'a ExeUnitContext> for RuntimeProcessContext {
@@ -591,11 +737,25 @@ impl<'a> From<&'a ExeUnitContext> for RuntimeProcessContext {
             supervise_image: ctx.supervise.image,
             supervise_hardware: ctx.supervise.hardware,
             infrastructure: ctx.agreement.infrastructure.clone(),
+            devices: match ctx.supports(Capability::Gpu) {
+                true => ctx.agreement.devices.clone(),
+                false => Vec::new(),
+            },
+            security: ctx.security.clone(),
             manifest: ctx.supervise.manifest.clone(),
+            secrets: ctx.secrets.clone(),
         }
     }
 }
 
+/// Runtime command executed within the applied security profile.
+fn security_command(binary: &Path, security: &AppliedSecurityProfile) -> Command {
+    let (program, args) = security.program(binary);
+    let mut command = Command::new(program);
+    command.args(args);
+    command
+}
+
 #[derive(Clone, Hash, Eq, PartialEq, From)]
 enum ChildProcess {
     #[from]
@@ -638,6 +798,54 @@ impl Drop for ChildProcessGuard {
     }
 }
 
+type StdinKey = (String, usize);
+
+/// Unregisters stdin of a command, when the command finishes.
+struct StdinGuard {
+    key: StdinKey,
+    addr: Addr<RuntimeProcess>,
+}
+
+impl StdinGuard {
+    fn new(key: StdinKey, addr: Addr<RuntimeProcess>) -> Self {
+        StdinGuard { key, addr }
+    }
+}
+
+impl Drop for StdinGuard {
+    fn drop(&mut self) {
+        self.addr.do_send(RemoveStdin(self.key.clone()));
+    }
+}
+
+/// Writes chunks to process stdin until the channel is closed or the process stops reading.
+async fn forward_stdin(mut pipe: ChildStdin, mut rx: mpsc::Receiver<Vec<u8>>, guard: StdinGuard) {
+    while let Some(data) = rx.recv().await {
+        if let Err(e) = pipe.write_all(&data).await {
+            log::debug!("Unable to write to stdin of command {:?}: {e}", guard.key);
+            return;
+        }
+    }
+}
+
+/// Runtime arguments passing process options of `run` command.
+fn run_options_args(options: &RunOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    for (name, value) in &options.env {
+        args.push("--env".to_string());
+        args.push(format!("{}={}", name, value));
+    }
+    if let Some(cwd) = &options.cwd {
+        args.push("--cwd".to_string());
+        args.push(cwd.clone());
+    }
+    if let Some(user) = &options.user {
+        args.push("--user".to_string());
+        args.push(user.clone());
+    }
+    args
+}
+
 #[derive(Clone, Default)]
 struct CommandArgs {
     inner: Vec<OsString>,
@@ -741,3 +949,7 @@ struct AddChildProcess(ChildProcess);
 #[derive(Message)]
 #[rtype("()")]
 struct RemoveChildProcess(ChildProcess);
+
+#[derive(Message)]
+#[rtype("()")]
+struct RemoveStdin(StdinKey);
@@ -0,0 +1,394 @@
+//! Pseudo-terminal sessions.
+//!
+//! Terminal runs its entry point the same way as a `Run` command in the process per command
+//! mode, with stdio attached to a PTY, so interactive shells and full-screen programs work.
+//! The session lasts until all processes attached to the terminal exit or it is closed.
+//!
+//! Service mode runtimes keep a single process and have no means of attaching another one
+//! to a terminal, so sessions are rejected there. PTYs are supported on unix hosts only.
+use std::io;
+
+use actix::prelude::*;
+use futures::channel::mpsc as output_channel;
+use futures::future::{self, FutureExt};
+use futures::SinkExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use ya_utils_process::ProcessTree;
+
+use super::{
+    process_kill_timeout_seconds, security_command, ChildProcess, ChildProcessGuard,
+    RuntimeProcess, STDIN_BUFFER_CHUNKS,
+};
+use crate::error::Error;
+use crate::message::{CloseTerminal, OpenTerminal, ResizeTerminal, WriteTerminal};
+use crate::runtime::RuntimeMode;
+
+/// Number of output chunks buffered, before the terminal stops being read.
+const OUTPUT_BUFFER_CHUNKS: usize = 16;
+const READ_BUFFER_SIZE: usize = 16 * 1024;
+
+pub(super) struct Terminal {
+    pty: pty::Pty,
+    input: mpsc::Sender<Vec<u8>>,
+}
+
+impl Handler<OpenTerminal> for RuntimeProcess {
+    type Result = <OpenTerminal as Message>::Result;
+
+    fn handle(&mut self, msg: OpenTerminal, ctx: &mut Self::Context) -> Self::Result {
+        if let RuntimeMode::Service = self.deployment.runtime_mode {
+            return Err(Error::CommandError(
+                "terminal sessions are not supported in service mode".into(),
+            ));
+        }
+
+        let mut args = self.args()?;
+        args.args(["run", "--entrypoint"])
+            .arg(&msg.entry_point)
+            .arg("--")
+            .args(&msg.args);
+
+        let (pty, slave) = pty::Pty::open(msg.size)?;
+        let reader = pty.try_clone()?;
+        let writer = pty.try_clone()?;
+
+        let mut command = security_command(&self.binary, &self.ctx.security);
+        command
+            .current_dir(&self.ctx.work_dir)
+            .args(args)
+            .envs(&self.deployment.env)
+            .kill_on_drop(true)
+            .stdin(slave.try_clone()?)
+            .stdout(slave.try_clone()?)
+            .stderr(slave);
+        pty::set_controlling(&mut command);
+
+        log::info!("Opening terminal with {} {:?}", msg.entry_point, msg.args);
+        let mut child = command.spawn()?;
+        // Slave ends held by the command would keep the terminal open after processes exit.
+        drop(command);
+
+        let pid = child
+            .id()
+            .ok_or_else(|| Error::runtime("Missing process id"))?;
+        let proc = ChildProcess::from(ProcessTree::try_new(pid).map_err(Error::runtime)?);
+
+        let terminal_id = self.next_terminal.to_string();
+        self.next_terminal += 1;
+
+        let (input_tx, input_rx) = mpsc::channel(STDIN_BUFFER_CHUNKS);
+        let (output_tx, output_rx) = output_channel::channel(OUTPUT_BUFFER_CHUNKS);
+        self.terminals.insert(
+            terminal_id.clone(),
+            Terminal {
+                pty,
+                input: input_tx,
+            },
+        );
+
+        let guard = TerminalGuard {
+            terminal_id: terminal_id.clone(),
+            addr: ctx.address(),
+        };
+        let child_guard = ChildProcessGuard::new(proc.clone(), ctx.address());
+
+        tokio::task::spawn_local(async move {
+            let _child_guard = child_guard;
+            let output = read_output(reader, output_tx);
+            let input = write_input(writer, input_rx);
+            futures::pin_mut!(output, input);
+            // Input ends, when the terminal is closed.
+            future::select(output, input).await;
+
+            log::info!("Terminal {} closed", guard.terminal_id);
+            let _ = proc.kill(process_kill_timeout_seconds()).await;
+            let _ = child.wait().await;
+        });
+
+        Ok((terminal_id, output_rx))
+    }
+}
+
+impl Handler<WriteTerminal> for RuntimeProcess {
+    type Result = ResponseFuture<<WriteTerminal as Message>::Result>;
+
+    fn handle(&mut self, msg: WriteTerminal, _: &mut Self::Context) -> Self::Result {
+        let input = self
+            .terminals
+            .get(&msg.terminal_id)
+            .map(|terminal| terminal.input.clone());
+
+        async move {
+            let terminal_id = msg.terminal_id;
+            let input = input.ok_or_else(|| unknown_terminal(&terminal_id))?;
+
+            let len = msg.data.len();
+            if len > 0 {
+                input.send(msg.data).await.map_err(|_| {
+                    Error::CommandError(format!("Terminal {terminal_id} is closed"))
+                })?;
+            }
+            Ok(len)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<ResizeTerminal> for RuntimeProcess {
+    type Result = <ResizeTerminal as Message>::Result;
+
+    fn handle(&mut self, msg: ResizeTerminal, _: &mut Self::Context) -> Self::Result {
+        let terminal = self
+            .terminals
+            .get(&msg.terminal_id)
+            .ok_or_else(|| unknown_terminal(&msg.terminal_id))?;
+        Ok(terminal.pty.resize(msg.size)?)
+    }
+}
+
+impl Handler<CloseTerminal> for RuntimeProcess {
+    type Result = <CloseTerminal as Message>::Result;
+
+    fn handle(&mut self, msg: CloseTerminal, _: &mut Self::Context) -> Self::Result {
+        // Dropping the input sender ends the session.
+        self.terminals
+            .remove(&msg.terminal_id)
+            .map(|_| ())
+            .ok_or_else(|| unknown_terminal(&msg.terminal_id))
+    }
+}
+
+impl Handler<RemoveTerminal> for RuntimeProcess {
+    type Result = <RemoveTerminal as Message>::Result;
+
+    fn handle(&mut self, msg: RemoveTerminal, _: &mut Self::Context) -> Self::Result {
+        self.terminals.remove(&msg.0);
+    }
+}
+
+fn unknown_terminal(terminal_id: &str) -> Error {
+    Error::CommandError(format!("Terminal {terminal_id} is not open"))
+}
+
+async fn read_output(
+    pty: std::fs::File,
+    mut tx: output_channel::Sender<Vec<u8>>,
+) -> io::Result<()> {
+    let mut pty = tokio::fs::File::from_std(pty);
+    let mut buf = vec![0u8; READ_BUFFER_SIZE];
+    loop {
+        // Reading fails with EIO, once all slave ends are closed.
+        let n = match pty.read(&mut buf).await {
+            Ok(0) | Err(_) => return Ok(()),
+            Ok(n) => n,
+        };
+        if tx.send(buf[..n].to_vec()).await.is_err() {
+            // Nobody is listening, but processes may still need their input.
+            return future::pending().await;
+        }
+    }
+}
+
+async fn write_input(pty: std::fs::File, mut rx: mpsc::Receiver<Vec<u8>>) -> io::Result<()> {
+    let mut pty = tokio::fs::File::from_std(pty);
+    while let Some(data) = rx.recv().await {
+        pty.write_all(&data).await?;
+        pty.flush().await?;
+    }
+    Ok(())
+}
+
+/// Unregisters the terminal, when its session ends.
+struct TerminalGuard {
+    terminal_id: String,
+    addr: Addr<RuntimeProcess>,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        self.addr.do_send(RemoveTerminal(self.terminal_id.clone()));
+    }
+}
+
+#[derive(Message)]
+#[rtype("()")]
+struct RemoveTerminal(String);
+
+#[cfg(unix)]
+mod pty {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    use ya_core_model::activity::TerminalSize;
+
+    pub struct Pty {
+        master: File,
+    }
+
+    impl Pty {
+        /// Opens a new PTY. Returns its master and slave ends.
+        pub fn open(size: TerminalSize) -> io::Result<(Pty, File)> {
+            let mut master = -1;
+            let mut slave = -1;
+            let mut size = winsize(size);
+            let result = unsafe {
+                libc::openpty(
+                    &mut master,
+                    &mut slave,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    &mut size,
+                )
+            };
+            if result != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let (master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+            Ok((Pty { master }, slave))
+        }
+
+        pub fn try_clone(&self) -> io::Result<File> {
+            self.master.try_clone()
+        }
+
+        pub fn resize(&self, size: TerminalSize) -> io::Result<()> {
+            let size = winsize(size);
+            match unsafe {
+                libc::ioctl(
+                    self.master.as_raw_fd(),
+                    libc::TIOCSWINSZ as _,
+                    &size as *const _,
+                )
+            } {
+                0 => Ok(()),
+                _ => Err(io::Error::last_os_error()),
+            }
+        }
+    }
+
+    /// Makes the terminal attached to stdin the controlling terminal of a new session.
+    pub fn set_controlling(command: &mut tokio::process::Command) {
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    fn winsize(size: TerminalSize) -> libc::winsize {
+        libc::winsize {
+            ws_row: size.rows,
+            ws_col: size.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod pty {
+    use std::fs::File;
+    use std::io;
+
+    use ya_core_model::activity::TerminalSize;
+
+    pub struct Pty;
+
+    impl Pty {
+        pub fn open(_: TerminalSize) -> io::Result<(Pty, File)> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "terminal sessions are supported on unix systems only",
+            ))
+        }
+
+        pub fn try_clone(&self) -> io::Result<File> {
+            unreachable!()
+        }
+
+        pub fn resize(&self, _: TerminalSize) -> io::Result<()> {
+            unreachable!()
+        }
+    }
+
+    pub fn set_controlling(_: &mut tokio::process::Command) {}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use ya_core_model::activity::TerminalSize;
+
+    /// Runs `script` attached to a new terminal, writes `input` after the first line of
+    /// output, and returns everything written to the terminal until the process exits.
+    async fn run_attached(script: &str, input: &[u8], resize: Option<TerminalSize>) -> String {
+        let (pty, slave) = pty::Pty::open(TerminalSize { cols: 80, rows: 24 }).unwrap();
+        let mut command = tokio::process::Command::new("sh");
+        command
+            .args(["-c", script])
+            .kill_on_drop(true)
+            .stdin(slave.try_clone().unwrap())
+            .stdout(slave.try_clone().unwrap())
+            .stderr(slave);
+        pty::set_controlling(&mut command);
+        let mut child = command.spawn().unwrap();
+        drop(command);
+
+        let (input_tx, input_rx) = mpsc::channel(STDIN_BUFFER_CHUNKS);
+        let (output_tx, mut output_rx) = output_channel::channel(OUTPUT_BUFFER_CHUNKS);
+        let reader = tokio::task::spawn_local(read_output(pty.try_clone().unwrap(), output_tx));
+        let writer = tokio::task::spawn_local(write_input(pty.try_clone().unwrap(), input_rx));
+
+        let mut output = String::new();
+        while let Some(chunk) = output_rx.next().await {
+            output.push_str(&String::from_utf8_lossy(&chunk));
+            if !input.is_empty() && output.contains('\n') {
+                if let Some(size) = resize {
+                    pty.resize(size).unwrap();
+                }
+                input_tx.send(input.to_vec()).await.unwrap();
+                break;
+            }
+        }
+        while let Some(chunk) = output_rx.next().await {
+            output.push_str(&String::from_utf8_lossy(&chunk));
+        }
+
+        assert!(child.wait().await.unwrap().success());
+        reader.await.unwrap().unwrap();
+        drop(input_tx);
+        writer.await.unwrap().unwrap();
+        output
+    }
+
+    #[actix_rt::test]
+    async fn process_is_attached_to_terminal() {
+        let output = run_attached("test -t 0 && test -t 1 && stty size", b"", None).await;
+        assert_eq!(output.trim(), "24 80");
+    }
+
+    #[actix_rt::test]
+    async fn input_and_resize_reach_process() {
+        let output = run_attached(
+            "stty -echo; stty size; read line; echo \"got $line\"; stty size",
+            b"hello\n",
+            Some(TerminalSize {
+                cols: 120,
+                rows: 40,
+            }),
+        )
+        .await;
+        let lines: Vec<_> = output.lines().map(str::trim).collect();
+        assert_eq!(lines, vec!["24 80", "got hello", "40 120"]);
+    }
+}
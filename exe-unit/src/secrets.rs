@@ -0,0 +1,216 @@
+//! Environment variables and secrets passed to the runtime with the `Deploy` command.
+//!
+//! Values of `Deploy.env` prefixed with `secret:` are encrypted by the Requestor with
+//! the public key published by the Provider in `golem.srv.comp.secrets.pub-key` Offer
+//! property. Secrets are decrypted just before they are passed to runtime processes
+//! and never leave the ExeUnit in plain text: commands are logged and reported back
+//! with secret values redacted.
+//!
+//! Encrypted value is a hex encoded concatenation of an ephemeral secp256k1 public key
+//! (compressed, 33 bytes), AES-256-GCM nonce (12 bytes) and the ciphertext. Encryption
+//! key is SHA3-256 of ECDH shared secret of the ephemeral key and the Provider key.
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use secp256k1::ecdh::SharedSecret;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use sha3::Digest;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use ya_client_model::activity::ExeScriptCommand;
+
+pub const PUB_KEY_PROPERTY: &str = "golem.srv.comp.secrets.pub-key";
+pub const SECRET_PREFIX: &str = "secret:";
+const REDACTED: &str = "secret:<hidden>";
+const NONCE_LEN: usize = 12;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SecretsError {
+    #[error("Provider doesn't accept secrets, environment variable {0} can't be decrypted")]
+    NotSupported(String),
+    #[error("Invalid secret value of environment variable {0}")]
+    Invalid(String),
+    #[error("Invalid secrets key file {0}: {1}")]
+    Key(String, String),
+}
+
+/// Provider key decrypting secrets. The same key is used by all ExeUnits of the Provider.
+#[derive(Clone)]
+pub struct SecretsKey {
+    sec_key: SecretKey,
+}
+
+impl SecretsKey {
+    pub fn load(path: &Path) -> Result<Self, SecretsError> {
+        let hex_key = std::fs::read_to_string(path).map_err(|e| key_error(path, e))?;
+        let bytes = hex::decode(hex_key.trim()).map_err(|e| key_error(path, e))?;
+        let sec_key = SecretKey::from_slice(&bytes).map_err(|e| key_error(path, e))?;
+        Ok(SecretsKey { sec_key })
+    }
+
+    /// Generates a new key readable only by the owner, if the file doesn't exist yet.
+    pub fn load_or_create(path: &Path) -> Result<Self, SecretsError> {
+        if !path.exists() {
+            let (sec_key, _) = Secp256k1::new().generate_keypair(&mut rand::thread_rng());
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            options
+                .open(path)
+                .and_then(|mut file| file.write_all(hex::encode(sec_key.secret_bytes()).as_bytes()))
+                .map_err(|e| key_error(path, e))?;
+            log::info!("Generated secrets key {}", path.display());
+        }
+        Self::load(path)
+    }
+
+    pub fn pub_key(&self) -> PublicKey {
+        PublicKey::from_secret_key(&Secp256k1::new(), &self.sec_key)
+    }
+
+    fn decrypt(&self, name: &str, value: &str) -> Result<String, SecretsError> {
+        let invalid = || SecretsError::Invalid(name.to_string());
+
+        let bytes = hex::decode(value).map_err(|_| invalid())?;
+        if bytes.len() <= secp256k1::constants::PUBLIC_KEY_SIZE + NONCE_LEN {
+            return Err(invalid());
+        }
+        let (pub_key, rest) = bytes.split_at(secp256k1::constants::PUBLIC_KEY_SIZE);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let pub_key = PublicKey::from_slice(pub_key).map_err(|_| invalid())?;
+        let plaintext = cipher(&pub_key, &self.sec_key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+}
+
+fn key_error(path: &Path, e: impl ToString) -> SecretsError {
+    SecretsError::Key(path.display().to_string(), e.to_string())
+}
+
+fn cipher(pub_key: &PublicKey, sec_key: &SecretKey) -> Aes256Gcm {
+    let shared = SharedSecret::new(pub_key, sec_key);
+    let key = sha3::Sha3_256::digest(&shared.secret_bytes());
+    Aes256Gcm::new_from_slice(key.as_slice()).expect("SHA3-256 output is a valid AES-256 key")
+}
+
+/// Encrypts secret value for the Provider publishing `pub_key`.
+pub fn encrypt(pub_key: &PublicKey, value: &str) -> String {
+    let (eph_sec_key, eph_pub_key) = Secp256k1::new().generate_keypair(&mut rand::thread_rng());
+    let nonce = rand::random::<[u8; NONCE_LEN]>();
+    let ciphertext = cipher(pub_key, &eph_sec_key)
+        .encrypt(Nonce::from_slice(&nonce), value.as_bytes())
+        .expect("AES-GCM encryption failed");
+
+    let mut bytes = eph_pub_key.serialize().to_vec();
+    bytes.extend(nonce);
+    bytes.extend(ciphertext);
+    format!("{}{}", SECRET_PREFIX, hex::encode(bytes))
+}
+
+/// Environment of runtime processes with secrets decrypted.
+pub fn decrypt_env(
+    env: &HashMap<String, String>,
+    key: Option<&SecretsKey>,
+) -> Result<HashMap<String, String>, SecretsError> {
+    env.iter()
+        .map(|(name, value)| match value.strip_prefix(SECRET_PREFIX) {
+            Some(encrypted) => {
+                let key = key.ok_or_else(|| SecretsError::NotSupported(name.clone()))?;
+                Ok((name.clone(), key.decrypt(name, encrypted)?))
+            }
+            None => Ok((name.clone(), value.clone())),
+        })
+        .collect()
+}
+
+/// Command with secret values replaced, safe for logging and reporting.
+pub fn redact(command: &ExeScriptCommand) -> ExeScriptCommand {
+    let mut command = command.clone();
+    if let ExeScriptCommand::Deploy { env, .. } = &mut command {
+        env.values_mut()
+            .filter(|value| value.starts_with(SECRET_PREFIX))
+            .for_each(|value| *value = REDACTED.to_string());
+    }
+    command
+}
+
+pub fn redact_script(script: &[ExeScriptCommand]) -> Vec<ExeScriptCommand> {
+    script.iter().map(redact).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> SecretsKey {
+        let (sec_key, _) = Secp256k1::new().generate_keypair(&mut rand::thread_rng());
+        SecretsKey { sec_key }
+    }
+
+    #[test]
+    fn secrets_are_decrypted() {
+        let key = key();
+        let env = HashMap::from([
+            ("PLAIN".to_string(), "value".to_string()),
+            ("TOKEN".to_string(), encrypt(&key.pub_key(), "s3cr3t")),
+        ]);
+
+        let decrypted = decrypt_env(&env, Some(&key)).unwrap();
+        assert_eq!(decrypted["PLAIN"], "value");
+        assert_eq!(decrypted["TOKEN"], "s3cr3t");
+
+        assert!(decrypt_env(&env, None).is_err());
+        assert!(decrypt_env(&env, Some(&self::key())).is_err());
+    }
+
+    #[test]
+    fn generated_key_is_reused() {
+        let dir = tempdir::TempDir::new("secrets").unwrap();
+        let path = dir.path().join("secrets.key");
+
+        let key = SecretsKey::load_or_create(&path).unwrap();
+        assert_eq!(
+            SecretsKey::load_or_create(&path).unwrap().pub_key(),
+            key.pub_key()
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let key = key();
+        let command = ExeScriptCommand::Deploy {
+            net: Default::default(),
+            progress: None,
+            env: HashMap::from([
+                ("PLAIN".to_string(), "value".to_string()),
+                ("TOKEN".to_string(), encrypt(&key.pub_key(), "s3cr3t")),
+            ]),
+            hosts: Default::default(),
+            hostname: None,
+            volumes: None,
+        };
+
+        match redact(&command) {
+            ExeScriptCommand::Deploy { env, .. } => {
+                assert_eq!(env["PLAIN"], "value");
+                assert_eq!(env["TOKEN"], REDACTED);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
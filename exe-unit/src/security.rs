@@ -0,0 +1,207 @@
+//! Security profiles confining runtime processes.
+//!
+//! Profile is negotiated in the Agreement. Predefined profiles map to AppArmor profiles,
+//! which have to be installed on the host by the Provider, since none are shipped.
+//! Runtimes advertising `security-profile` capability additionally receive the profile
+//! name (and the `custom` profile file) and are responsible for applying it.
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::Error;
+
+const APPARMOR_PROFILE_PREFIX: &str = "yagna-runtime-";
+#[cfg(target_os = "linux")]
+const APPARMOR_PROFILES: &str = "/sys/kernel/security/apparmor/profiles";
+const APPARMOR_EXEC: &str = "aa-exec";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SecurityProfile {
+    /// Confinement is mandatory, ExeUnit fails if it can't be applied.
+    Strict,
+    /// Confinement is applied, when available on the host.
+    #[default]
+    Default,
+    /// Runtime is not confined.
+    Permissive,
+    /// Profile read from file configured by the Provider and applied by the runtime.
+    Custom,
+}
+
+impl SecurityProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecurityProfile::Strict => "strict",
+            SecurityProfile::Default => "default",
+            SecurityProfile::Permissive => "permissive",
+            SecurityProfile::Custom => "custom",
+        }
+    }
+}
+
+impl FromStr for SecurityProfile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(SecurityProfile::Strict),
+            "default" => Ok(SecurityProfile::Default),
+            "permissive" => Ok(SecurityProfile::Permissive),
+            "custom" => Ok(SecurityProfile::Custom),
+            _ => Err(Error::Other(format!("unknown security profile: {s}"))),
+        }
+    }
+}
+
+impl fmt::Display for SecurityProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Security profile resolved on the host.
+#[derive(Clone, Debug, Default)]
+pub struct AppliedSecurityProfile {
+    pub profile: SecurityProfile,
+    /// AppArmor profile, which the runtime is executed in.
+    pub apparmor: Option<String>,
+    /// Profile file passed to the runtime.
+    pub file: Option<PathBuf>,
+}
+
+impl AppliedSecurityProfile {
+    /// `runtime_support` tells, if the runtime advertises `security-profile` capability.
+    pub fn resolve(
+        profile: SecurityProfile,
+        file: Option<&Path>,
+        runtime_support: bool,
+    ) -> Result<Self, Error> {
+        let mut applied = AppliedSecurityProfile {
+            profile,
+            ..Default::default()
+        };
+
+        match profile {
+            SecurityProfile::Strict | SecurityProfile::Default => {
+                let name = format!("{APPARMOR_PROFILE_PREFIX}{profile}");
+                if apparmor_profile_loaded(&name) {
+                    applied.apparmor = Some(name);
+                } else if profile == SecurityProfile::Strict {
+                    return Err(Error::Other(format!(
+                        "security profile {profile} requires AppArmor profile {name} to be loaded"
+                    )));
+                } else {
+                    log::warn!("AppArmor profile {name} not loaded, runtime won't be confined");
+                }
+            }
+            SecurityProfile::Permissive => (),
+            SecurityProfile::Custom if !runtime_support => {
+                return Err(Error::Other(
+                    "custom security profile is not supported by the runtime".into(),
+                ))
+            }
+            SecurityProfile::Custom => match file {
+                Some(path) if path.is_file() => applied.file = Some(path.to_path_buf()),
+                Some(path) => {
+                    return Err(Error::Other(format!(
+                        "security profile file {} does not exist",
+                        path.display()
+                    )))
+                }
+                None => {
+                    return Err(Error::Other(
+                        "custom security profile requires a profile file".into(),
+                    ))
+                }
+            },
+        }
+        Ok(applied)
+    }
+
+    /// Arguments informing the runtime about the profile. Passed only to runtimes
+    /// supporting security profiles and never for the `default` profile.
+    pub fn runtime_args(&self) -> Vec<OsString> {
+        if self.profile == SecurityProfile::Default {
+            return Vec::new();
+        }
+        let mut args = vec![
+            OsString::from("--security-profile"),
+            OsString::from(self.profile.as_str()),
+        ];
+        if let Some(file) = &self.file {
+            args.push("--security-profile-file".into());
+            args.push(file.into());
+        }
+        args
+    }
+
+    /// Program and its leading arguments executing the runtime binary.
+    pub fn program(&self, binary: &Path) -> (PathBuf, Vec<OsString>) {
+        match &self.apparmor {
+            Some(name) => (
+                PathBuf::from(APPARMOR_EXEC),
+                vec!["-p".into(), name.into(), "--".into(), binary.into()],
+            ),
+            None => (binary.to_path_buf(), Vec::new()),
+        }
+    }
+}
+
+impl fmt::Display for AppliedSecurityProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.profile)?;
+        if let Some(name) = &self.apparmor {
+            write!(f, " (apparmor: {name})")?;
+        }
+        if let Some(file) = &self.file {
+            write!(f, " (file: {})", file.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn apparmor_profile_loaded(name: &str) -> bool {
+    // Entries are formatted as `<name> (<mode>)`
+    std::fs::read_to_string(APPARMOR_PROFILES)
+        .map(|profiles| {
+            profiles
+                .lines()
+                .any(|line| line.rsplit_once(" (").map(|(n, _)| n) == Some(name))
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apparmor_profile_loaded(_name: &str) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_profile_requires_file() {
+        assert!(AppliedSecurityProfile::resolve(SecurityProfile::Custom, None, true).is_err());
+
+        let file = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        assert!(
+            AppliedSecurityProfile::resolve(SecurityProfile::Custom, Some(&file), false).is_err()
+        );
+        let applied =
+            AppliedSecurityProfile::resolve(SecurityProfile::Custom, Some(&file), true).unwrap();
+        assert_eq!(applied.apparmor, None);
+        assert_eq!(applied.runtime_args().len(), 4);
+    }
+
+    #[test]
+    fn permissive_profile_is_not_confined() {
+        let applied =
+            AppliedSecurityProfile::resolve(SecurityProfile::Permissive, None, false).unwrap();
+        let binary = Path::new("/usr/lib/yagna/plugins/runtime");
+        assert_eq!(applied.program(binary), (binary.to_path_buf(), Vec::new()));
+        assert_eq!(applied.to_string(), "permissive");
+    }
+}
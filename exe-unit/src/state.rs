@@ -14,15 +14,19 @@ use tokio::sync::broadcast;
 pub use ya_client_model::activity::activity_state::{State, StatePair};
 use ya_client_model::activity::exe_script_command::Network;
 use ya_client_model::activity::*;
-use ya_core_model::activity::Exec;
+use ya_core_model::activity::{Exec, ExecBatchState, ExecCommandState, ExecCommandStatus};
 use ya_utils_networking::vpn::common::{to_ip, to_net};
 use ya_utils_networking::vpn::Error as NetError;
 
 use crate::error::Error;
 use crate::manifest::ManifestContext;
 use crate::notify::Notify;
-use crate::output::CapturedOutput;
+use crate::output::{CapturedOutput, OutputConfig};
 use crate::runtime::RuntimeMode;
+use crate::secrets::redact;
+
+/// Default number of trailing characters of command outputs included in batch state.
+pub const DEFAULT_STATE_OUTPUT_LIMIT: usize = 4096;
 
 fn invalid_state_err_msg(state_pair: &StatePair) -> String {
     match state_pair {
@@ -67,12 +71,20 @@ pub(crate) struct ExeUnitState {
     pub inner: StatePair,
     pub last_batch: Option<String>,
     pub batches: HashMap<String, Batch>,
+    /// Output of open terminals, until it is streamed.
+    pub terminals: HashMap<String, mpsc::Receiver<Vec<u8>>>,
 }
 
 impl ExeUnitState {
-    pub fn start_batch(&mut self, script: Exec, control: oneshot::Sender<()>) {
+    pub fn start_batch(
+        &mut self,
+        script: Exec,
+        control: oneshot::Sender<()>,
+        output: OutputConfig,
+    ) {
         let batch_id = script.batch_id.clone();
-        self.batches.insert(batch_id, Batch::new(script, control));
+        self.batches
+            .insert(batch_id, Batch::new(script, control, output));
     }
 
     pub fn report(&self) -> ExeUnitReport {
@@ -97,16 +109,18 @@ pub(crate) struct Batch {
     pub control: Option<oneshot::Sender<()>>,
     pub notifier: Notify<usize>,
     pub stream: Broadcast<RuntimeEvent>,
+    output: OutputConfig,
 }
 
 impl Batch {
-    pub fn new(exec: Exec, control: oneshot::Sender<()>) -> Self {
+    pub fn new(exec: Exec, control: oneshot::Sender<()>, output: OutputConfig) -> Self {
         Batch {
             exec,
             results: Default::default(),
             control: Some(control),
             notifier: Default::default(),
             stream: Default::default(),
+            output,
         }
     }
 
@@ -127,7 +141,7 @@ impl Batch {
         let idx = event.index;
         let stream_event = match &event.kind {
             RuntimeEventKind::Started { command: _ } => {
-                self.state(idx).map(|_| ())?;
+                self.state(idx)?.started = Some(Utc::now());
                 Some(event)
             }
             RuntimeEventKind::Finished {
@@ -135,6 +149,13 @@ impl Batch {
                 message,
             } => {
                 let state = self.state(idx)?;
+                let truncated = [state.stdout.finish("stdout"), state.stderr.finish("stderr")];
+                let message = message
+                    .iter()
+                    .cloned()
+                    .chain(truncated.into_iter().flatten())
+                    .reduce(|message, marker| format!("{message}; {marker}"));
+
                 state.date = Utc::now();
                 state.message = message.clone();
                 state.result = Some(match return_code {
@@ -142,7 +163,13 @@ impl Batch {
                     _ => CommandResult::Error,
                 });
                 self.notifier.notify(idx);
-                Some(event)
+                Some(RuntimeEvent {
+                    kind: RuntimeEventKind::Finished {
+                        return_code: *return_code,
+                        message,
+                    },
+                    ..event
+                })
             }
             RuntimeEventKind::StdOut(out) => {
                 let state = self.state(idx)?;
@@ -190,7 +217,7 @@ impl Batch {
 
         match result {
             Some((idx, msg)) => self.exec.exe_script.get(idx).map(|c| {
-                let mut state = ExeScriptCommandState::from(c.clone());
+                let mut state = ExeScriptCommandState::from(redact(c));
                 state.progress = msg;
                 state
             }),
@@ -220,6 +247,56 @@ impl Batch {
             .collect::<Vec<_>>()
     }
 
+    pub fn batch_state(&self, output_limit: usize) -> ExecBatchState {
+        let now = Utc::now();
+        let commands = (0..self.total())
+            .map(|idx| match self.results.get(idx) {
+                Some(s) => {
+                    let (status, finished_at) = match s.result {
+                        Some(CommandResult::Ok) => (ExecCommandStatus::Ok, Some(s.date)),
+                        Some(CommandResult::Error) => (ExecCommandStatus::Error, Some(s.date)),
+                        None if s.started.is_some() => (ExecCommandStatus::Running, None),
+                        None => (ExecCommandStatus::Pending, None),
+                    };
+                    let duration_ms = s.started.map(|started| {
+                        let end = finished_at.unwrap_or(now);
+                        (end - started).num_milliseconds().max(0) as u64
+                    });
+                    ExecCommandState {
+                        index: idx as u32,
+                        status,
+                        started_at: s.started,
+                        finished_at,
+                        duration_ms,
+                        stdout: s.stdout.output_string().map(|o| tail(o, output_limit)),
+                        stderr: s.stderr.output_string().map(|o| tail(o, output_limit)),
+                        message: s.message.clone(),
+                    }
+                }
+                None => ExecCommandState {
+                    index: idx as u32,
+                    status: ExecCommandStatus::Pending,
+                    started_at: None,
+                    finished_at: None,
+                    duration_ms: None,
+                    stdout: None,
+                    stderr: None,
+                    message: None,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let is_batch_finished = self.done() == self.total()
+            || commands
+                .iter()
+                .any(|c| c.status == ExecCommandStatus::Error);
+        ExecBatchState {
+            batch_id: self.exec.batch_id.clone(),
+            commands,
+            is_batch_finished,
+        }
+    }
+
     #[inline]
     fn state(&mut self, idx: usize) -> Result<&mut CommandState, Error> {
         let exe_script = &self.exec.exe_script;
@@ -228,12 +305,22 @@ impl Batch {
         if idx >= exe_script.len() {
             return Err(Error::runtime(format!("unknown command index: {}", idx)));
         } else if idx >= available {
+            let batch_id = &self.exec.batch_id;
+            let output = &self.output;
             let iter = exe_script
                 .iter()
+                .enumerate()
                 .skip(available)
                 .take(idx - available + 1)
-                .map(|cmd| match cmd {
-                    ExeScriptCommand::Run { capture, .. } => CommandState::from(capture),
+                .map(|(i, cmd)| match cmd {
+                    ExeScriptCommand::Run {
+                        capture: Some(capture),
+                        ..
+                    } => CommandState::new(
+                        output.capture(capture.stdout.clone(), batch_id, i, "stdout"),
+                        output.capture(capture.stderr.clone(), batch_id, i, "stderr"),
+                    ),
+                    ExeScriptCommand::Run { capture: None, .. } => CommandState::discard(),
                     _ => CommandState::all(),
                 });
             self.results.extend(iter);
@@ -295,6 +382,7 @@ impl<T: Clone> Default for Broadcast<T> {
 
 pub(crate) struct CommandState {
     pub result: Option<CommandResult>,
+    pub started: Option<DateTime<Utc>>,
     pub stdout: CapturedOutput,
     pub stderr: CapturedOutput,
     pub message: Option<String>,
@@ -305,6 +393,7 @@ impl CommandState {
     fn new(stdout: CapturedOutput, stderr: CapturedOutput) -> Self {
         CommandState {
             result: None,
+            started: None,
             stdout,
             stderr,
             message: None,
@@ -374,6 +463,14 @@ impl std::fmt::Display for ExeUnitReport {
     }
 }
 
+/// Keeps last `limit` characters of the output.
+fn tail(output: String, limit: usize) -> String {
+    match output.char_indices().rev().nth(limit) {
+        Some((idx, c)) => output[idx + c.len_utf8()..].to_string(),
+        None => output,
+    }
+}
+
 fn output_bytes(output: &CommandOutput) -> &[u8] {
     match output {
         CommandOutput::Bin(vec) => vec.as_slice(),
@@ -387,6 +484,8 @@ pub(crate) struct Deployment {
     pub task_package: Option<PathBuf>,
     pub networks: HashMap<String, DeploymentNetwork>,
     pub hosts: HashMap<String, String>,
+    /// Contains decrypted secrets, so it must not be logged.
+    pub env: HashMap<String, String>,
 }
 
 #[derive(Clone, Debug)]
@@ -430,3 +529,84 @@ impl Deployment {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(commands: usize) -> Batch {
+        let exec = Exec {
+            activity_id: "activity".to_string(),
+            batch_id: "batch".to_string(),
+            exe_script: (0..commands)
+                .map(|_| ExeScriptCommand::Terminate {})
+                .collect(),
+            timeout: None,
+            interactive: Default::default(),
+            metrics_push_url: None,
+            run_options: Default::default(),
+        };
+        Batch::new(exec, oneshot::channel().0, OutputConfig::default())
+    }
+
+    fn event(idx: usize, kind: RuntimeEventKind) -> RuntimeEvent {
+        RuntimeEvent::new("batch".to_string(), idx, kind)
+    }
+
+    #[test]
+    fn batch_state_reports_each_command() {
+        let mut batch = batch(3);
+        let started = |idx| {
+            event(
+                idx,
+                RuntimeEventKind::Started {
+                    command: ExeScriptCommand::Terminate {},
+                },
+            )
+        };
+        batch.handle_event(started(0)).unwrap();
+        batch
+            .handle_event(event(
+                0,
+                RuntimeEventKind::StdOut(CommandOutput::Str("output".to_string())),
+            ))
+            .unwrap();
+        batch
+            .handle_event(event(
+                0,
+                RuntimeEventKind::Finished {
+                    return_code: 0,
+                    message: None,
+                },
+            ))
+            .unwrap();
+        batch.handle_event(started(1)).unwrap();
+
+        let state = batch.batch_state(3);
+        let statuses = state.commands.iter().map(|c| c.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                ExecCommandStatus::Ok,
+                ExecCommandStatus::Running,
+                ExecCommandStatus::Pending
+            ]
+        );
+        assert!(!state.is_batch_finished);
+
+        let first = &state.commands[0];
+        assert_eq!(first.stdout.as_deref(), Some("put"));
+        assert!(first.started_at.unwrap() <= first.finished_at.unwrap());
+        assert!(first.duration_ms.is_some());
+        assert!(state.commands[1].finished_at.is_none());
+        assert!(state.commands[2].started_at.is_none());
+    }
+
+    #[test]
+    fn tail_keeps_last_characters() {
+        assert_eq!(tail("abcdef".to_string(), 2), "ef");
+        assert_eq!(tail("zażółć".to_string(), 3), "ółć");
+        assert_eq!(tail("abc".to_string(), 5), "abc");
+        assert_eq!(tail("abc".to_string(), 0), "");
+    }
+}
@@ -204,6 +204,32 @@ impl RealIdentity {
         Ok(())
     }
 
+    pub async fn set_module_default(
+        &self,
+        module: identity::Module,
+        id: Option<NodeId>,
+    ) -> anyhow::Result<()> {
+        self.gsb_identity()
+            .local()
+            .send(identity::SetModuleDefault {
+                module,
+                node_id: id,
+            })
+            .await
+            .map_err(anyhow::Error::msg)??;
+        Ok(())
+    }
+
+    pub async fn module_default(&self, module: identity::Module) -> anyhow::Result<NodeId> {
+        self.gsb_identity()
+            .local()
+            .send(identity::Get::ByModule(module))
+            .await
+            .map_err(anyhow::Error::msg)??
+            .map(|info| info.node_id)
+            .ok_or_else(|| anyhow!("No identity for module {module}"))
+    }
+
     fn gsb_identity(&self) -> GsbBindPoints {
         self.gsb
             .clone()
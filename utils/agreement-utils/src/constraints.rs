@@ -118,6 +118,8 @@ pub enum ConstraintOperator {
     NotEqual,
     LessThan,
     GreaterThan,
+    LessOrEqual,
+    GreaterOrEqual,
 }
 
 impl fmt::Display for ConstraintOperator {
@@ -130,6 +132,8 @@ impl fmt::Display for ConstraintOperator {
                 ConstraintOperator::NotEqual => "<>",
                 ConstraintOperator::LessThan => "<",
                 ConstraintOperator::GreaterThan => ">",
+                ConstraintOperator::LessOrEqual => "<=",
+                ConstraintOperator::GreaterOrEqual => ">=",
             }
         )
     }
@@ -222,6 +226,12 @@ impl ConstraintKey {
     pub fn not_equal_to(self, value: ConstraintValue) -> ConstraintExpr {
         self.with_operator_value(ConstraintOperator::NotEqual, value)
     }
+    pub fn greater_or_equal(self, value: ConstraintValue) -> ConstraintExpr {
+        self.with_operator_value(ConstraintOperator::GreaterOrEqual, value)
+    }
+    pub fn less_or_equal(self, value: ConstraintValue) -> ConstraintExpr {
+        self.with_operator_value(ConstraintOperator::LessOrEqual, value)
+    }
 }
 
 #[macro_export]
@@ -0,0 +1,197 @@
+//! Typed builder of Requestor Demands.
+//!
+//! Helpers emit property names and constraint syntax expected by Providers, so mistakes
+//! are caught at compile time instead of surfacing as Demands, which never match.
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use ya_client_model::market::NewDemand;
+
+use crate::{ConstraintKey, Constraints};
+
+pub const EXPIRATION_PROPERTY: &str = "golem.srv.comp.expiration";
+pub const TASK_PACKAGE_PROPERTY: &str = "golem.srv.comp.task_package";
+pub const SUBNET_PROPERTY: &str = "golem.node.debug.subnet";
+pub const CHOSEN_PLATFORM_PROPERTY: &str = "golem.com.payment.chosen-platform";
+pub const RUNTIME_NAME_PROPERTY: &str = "golem.runtime.name";
+pub const CPU_THREADS_PROPERTY: &str = "golem.inf.cpu.threads";
+pub const MEM_GIB_PROPERTY: &str = "golem.inf.mem.gib";
+pub const STORAGE_GIB_PROPERTY: &str = "golem.inf.storage.gib";
+
+fn platform_address_property(platform: &str) -> String {
+    format!("golem.com.payment.platform.{}.address", platform)
+}
+
+/// Payment platform, e.g. `erc20-holesky-tglm`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentPlatform {
+    pub driver: String,
+    pub network: String,
+    pub token: String,
+}
+
+impl PaymentPlatform {
+    pub fn new(
+        driver: impl Into<String>,
+        network: impl Into<String>,
+        token: impl Into<String>,
+    ) -> Self {
+        PaymentPlatform {
+            driver: driver.into(),
+            network: network.into(),
+            token: token.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for PaymentPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}",
+            self.driver,
+            self.network,
+            self.token.to_lowercase()
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct DemandBuilder {
+    properties: Map<String, Value>,
+    constraints: Vec<Constraints>,
+}
+
+impl DemandBuilder {
+    /// Agreements negotiated from the Demand expire at `expiration`.
+    pub fn new(expiration: DateTime<Utc>) -> Self {
+        DemandBuilder {
+            properties: Map::new(),
+            constraints: Vec::new(),
+        }
+        .property(EXPIRATION_PROPERTY, expiration.timestamp_millis())
+    }
+
+    /// Negotiates only with Providers from the subnet.
+    pub fn subnet(self, subnet: impl Into<String>) -> Self {
+        let subnet = subnet.into();
+        self.property(SUBNET_PROPERTY, subnet.clone())
+            .equal(SUBNET_PROPERTY, subnet)
+    }
+
+    /// Requires ExeUnit runtime, e.g. `vm` or `wasmtime`.
+    pub fn runtime(self, name: impl Into<String>) -> Self {
+        self.equal(RUNTIME_NAME_PROPERTY, name.into())
+    }
+
+    /// Image deployed by the runtime, e.g. `hash:sha3:<hash>:<url>`.
+    pub fn task_package(self, task_package: impl Into<String>) -> Self {
+        self.property(TASK_PACKAGE_PROPERTY, task_package.into())
+    }
+
+    pub fn min_cpu_threads(self, threads: u32) -> Self {
+        self.at_least(CPU_THREADS_PROPERTY, threads)
+    }
+
+    pub fn min_mem_gib(self, mem_gib: f64) -> Self {
+        self.at_least(MEM_GIB_PROPERTY, mem_gib)
+    }
+
+    pub fn min_storage_gib(self, storage_gib: f64) -> Self {
+        self.at_least(STORAGE_GIB_PROPERTY, storage_gib)
+    }
+
+    /// Pays from `address` on the platform. Only Providers accepting the platform match.
+    pub fn payment_platform(self, platform: &PaymentPlatform, address: impl Into<String>) -> Self {
+        let platform = platform.to_string();
+        let address_property = platform_address_property(&platform);
+        self.property(&address_property, address.into())
+            .property(CHOSEN_PLATFORM_PROPERTY, platform)
+            .constraint(Constraints::new_single(ConstraintKey::new(format!(
+                "{}=*",
+                address_property
+            ))))
+    }
+
+    /// Sets property not covered by typed helpers.
+    pub fn property(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.properties.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Adds constraint not covered by typed helpers. Constraints are joined with `&`.
+    pub fn constraint(mut self, constraints: Constraints) -> Self {
+        self.constraints.push(constraints);
+        self
+    }
+
+    fn equal(self, name: &str, value: impl Into<Value>) -> Self {
+        self.constraint(Constraints::new_single(
+            ConstraintKey::new(name).equal_to(ConstraintKey::new(value)),
+        ))
+    }
+
+    fn at_least(self, name: &str, value: impl Into<Value>) -> Self {
+        self.constraint(Constraints::new_single(
+            ConstraintKey::new(name).greater_or_equal(ConstraintKey::new(value)),
+        ))
+    }
+
+    pub fn constraints(&self) -> String {
+        self.constraints
+            .iter()
+            .cloned()
+            .reduce(Constraints::and)
+            .map(|constraints| constraints.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn build(self) -> NewDemand {
+        let constraints = self.constraints();
+        NewDemand::new(Value::Object(self.properties), constraints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn demand_syntax() {
+        let expiration = Utc.timestamp_millis_opt(1700000000000).unwrap();
+        let platform = PaymentPlatform::new("erc20", "holesky", "tGLM");
+
+        let demand = DemandBuilder::new(expiration)
+            .subnet("public")
+            .runtime("vm")
+            .task_package("hash:sha3:abcd:http://registry/image")
+            .min_cpu_threads(2)
+            .min_mem_gib(0.5)
+            .payment_platform(&platform, "0xa")
+            .build();
+
+        assert_eq!(
+            demand.properties,
+            serde_json::json!({
+                "golem.srv.comp.expiration": 1700000000000i64,
+                "golem.node.debug.subnet": "public",
+                "golem.srv.comp.task_package": "hash:sha3:abcd:http://registry/image",
+                "golem.com.payment.platform.erc20-holesky-tglm.address": "0xa",
+                "golem.com.payment.chosen-platform": "erc20-holesky-tglm",
+            })
+        );
+        assert_eq!(
+            demand.constraints,
+            "(&\n  (golem.node.debug.subnet=public)\n  (golem.runtime.name=vm)\n  \
+             (golem.inf.cpu.threads>=2)\n  (golem.inf.mem.gib>=0.5)\n  \
+             (golem.com.payment.platform.erc20-holesky-tglm.address=*)\n)"
+        );
+    }
+
+    #[test]
+    fn empty_constraints() {
+        let demand = DemandBuilder::new(Utc::now()).build();
+        assert_eq!(demand.constraints, "");
+    }
+}
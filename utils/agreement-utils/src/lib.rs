@@ -1,10 +1,12 @@
 pub mod agreement;
 mod constraints;
+pub mod demand;
 pub mod proposal;
 pub mod template;
 mod typed_props;
 
 pub use agreement::{AgreementView, Error, OfferTemplate};
 pub use constraints::*;
+pub use demand::DemandBuilder;
 pub use proposal::ProposalView;
 pub use typed_props::*;
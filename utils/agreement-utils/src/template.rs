@@ -1,3 +1,4 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::collections::HashMap;
@@ -5,6 +6,7 @@ use std::fmt::Formatter;
 
 use crate::agreement::{flatten, flatten_value, PROPERTY_TAG};
 use crate::Error;
+use ya_property_schema::Property;
 
 /// TODO: Could we use Constraints instead of String?? This would require parsing string.
 ///  It is complicated, but we could use code from resolver to do this.
@@ -46,6 +48,15 @@ impl OfferTemplate {
         properties.insert(key.to_string(), value);
     }
 
+    /// Sets property using typed accessor from `ya_property_schema`.
+    pub fn set_typed<T: DeserializeOwned + Serialize>(
+        &mut self,
+        property: Property<T>,
+        value: T,
+    ) -> Result<(), Error> {
+        Ok(property.set(self.properties.as_object_mut().unwrap(), value)?)
+    }
+
     pub fn add_constraints(&mut self, constraints: String) {
         if self.constraints.is_empty() {
             self.constraints = constraints;
@@ -46,7 +46,7 @@ impl Default for NodeInfo {
             subnet: None,
             geo_country_code: None,
             is_public: false,
-            protocol_version: 3,
+            protocol_version: 4,
         }
     }
 }
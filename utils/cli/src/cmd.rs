@@ -1,5 +1,52 @@
 use anyhow::Result;
 use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Format of command output. Structured formats (JSON, YAML) share the same field names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    pub const VARIANTS: &'static [&'static str] = &["table", "json", "yaml"];
+
+    /// Commands should return objects instead of tables formatted for humans.
+    pub fn is_structured(&self) -> bool {
+        !matches!(self, OutputFormat::Table)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            _ => anyhow::bail!(
+                "unknown output format '{}', expected one of: {}",
+                s,
+                Self::VARIANTS.join(", ")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+        })
+    }
+}
 
 #[derive(Debug)]
 pub enum CommandOutput {
@@ -26,10 +73,39 @@ impl CommandOutput {
     }
 
     pub fn print(&self, json_output: bool) -> Result<()> {
-        if json_output {
-            self.print_json()?;
+        self.print_as(if json_output {
+            OutputFormat::Json
         } else {
-            self.print_plain()?;
+            OutputFormat::Table
+        })
+    }
+
+    pub fn print_as(&self, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Table => self.print_plain(),
+            OutputFormat::Json => self.print_json(),
+            OutputFormat::Yaml => self.print_yaml(),
+        }
+    }
+
+    fn print_yaml(&self) -> anyhow::Result<()> {
+        match self {
+            CommandOutput::NoOutput => println!("null"),
+            CommandOutput::Table {
+                columns, values, ..
+            } => crate::table::print_yaml_table(columns, values)?,
+            CommandOutput::MultiTable { tables } => {
+                for table in tables {
+                    if let CommandOutput::Table {
+                        columns, values, ..
+                    } = table
+                    {
+                        println!("---");
+                        crate::table::print_yaml_table(columns, values)?
+                    }
+                }
+            }
+            CommandOutput::Object(value) => print!("{}", serde_yaml::to_string(&value)?),
         }
         Ok(())
     }
@@ -1,5 +1,5 @@
 mod cmd;
 mod table;
 
-pub use cmd::CommandOutput;
+pub use cmd::{CommandOutput, OutputFormat};
 pub use table::ResponseTable;
@@ -75,6 +75,23 @@ pub fn print_json_table(
     columns: &Vec<String>,
     values: &Vec<serde_json::Value>,
 ) -> Result<(), anyhow::Error> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&table_value(columns, values))?
+    );
+    Ok(())
+}
+
+pub fn print_yaml_table(
+    columns: &Vec<String>,
+    values: &Vec<serde_json::Value>,
+) -> Result<(), anyhow::Error> {
+    print!("{}", serde_yaml::to_string(&table_value(columns, values))?);
+    Ok(())
+}
+
+/// Table rows as objects keyed by column names, if all rows have value for each column.
+fn table_value(columns: &[String], values: &[serde_json::Value]) -> serde_json::Value {
     let columns_size_eq_values_size = values.iter().all(|row| match row {
         serde_json::Value::Array(values) => values.len() == columns.len(),
         _ => false,
@@ -93,17 +110,13 @@ pub fn print_json_table(
                 _ => unreachable!(),
             })
             .collect();
-        println!("{}", serde_json::to_string_pretty(&serde_json::json!(kvs))?)
+        serde_json::json!(kvs)
     } else {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&serde_json::json!({
-                "headers": columns,
-                "values": values
-            }))?
-        )
+        serde_json::json!({
+            "headers": columns,
+            "values": values
+        })
     }
-    Ok(())
 }
 
 pub struct ResponseTable {
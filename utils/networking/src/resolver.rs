@@ -40,6 +40,35 @@ pub async fn resolve_srv_record(record: &str) -> std::io::Result<String> {
     Ok(addr)
 }
 
+/// Performs lookup of all the Service Records (SRV) of `prefix` in the `DEFAULT_LOOKUP_DOMAIN`.
+/// If successful responds with targets in the format of `hostname:port`, ordered by priority.
+pub async fn resolve_yagna_srv_records(prefix: &str) -> std::io::Result<Vec<String>> {
+    let record = format!("{}.{}", prefix.trim_end_matches('.'), DEFAULT_LOOKUP_DOMAIN);
+    let resolver: TokioAsyncResolver =
+        TokioAsyncResolver::tokio(ResolverConfig::google(), ResolverOpts::default())?;
+    let lookup = resolver.srv_lookup(record).await?;
+
+    let mut records = lookup.iter().collect::<Vec<_>>();
+    records.sort_by_key(|srv| srv.priority());
+
+    let addrs = records
+        .into_iter()
+        .map(|srv| {
+            format!(
+                "{}:{}",
+                srv.target().to_string().trim_end_matches('.'),
+                srv.port()
+            )
+        })
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return Err(IoError::from(IoErrorKind::NotFound));
+    }
+
+    log::debug!("Resolved addresses: {:?}", addrs);
+    Ok(addrs)
+}
+
 pub async fn resolve_href_record(record: &str) -> std::io::Result<Vec<String>> {
     eprintln!("record={record}");
     let opts = ResolverOpts::default();
@@ -0,0 +1,275 @@
+//! Registry of properties defined by Golem standards.
+//!
+//! Each property has a type, optional unit and deprecation note. Typed accessors
+//! (e.g. [`inf::MEM_GIB`]) are generated from the same definitions, so Offer and Demand
+//! builders and subscription validation share a single source of truth.
+//! Properties are addressed by their flat names (as in `OfferTemplate`).
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Type of property value. Numeric properties defined by standards are never negative.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropertyType {
+    String,
+    Boolean,
+    /// Non-negative integer.
+    Integer,
+    /// Non-negative number.
+    Number,
+    StringList,
+    NumberList,
+}
+
+impl PropertyType {
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            PropertyType::String => value.is_string(),
+            PropertyType::Boolean => value.is_boolean(),
+            PropertyType::Integer => value.is_u64(),
+            PropertyType::Number => value.as_f64().map(|v| v >= 0.).unwrap_or(false),
+            PropertyType::StringList => value
+                .as_array()
+                .map(|items| items.iter().all(Value::is_string))
+                .unwrap_or(false),
+            PropertyType::NumberList => value
+                .as_array()
+                .map(|items| items.iter().all(Value::is_number))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl fmt::Display for PropertyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PropertyType::String => "string",
+            PropertyType::Boolean => "boolean",
+            PropertyType::Integer => "non-negative integer",
+            PropertyType::Number => "non-negative number",
+            PropertyType::StringList => "list of strings",
+            PropertyType::NumberList => "list of numbers",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Definition {
+    pub name: &'static str,
+    pub kind: PropertyType,
+    pub unit: Option<&'static str>,
+    pub deprecated: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaIssue {
+    TypeMismatch(PropertyType),
+    Deprecated(&'static str),
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaIssue::TypeMismatch(kind) => write!(f, "{kind} expected"),
+            SchemaIssue::Deprecated(note) => write!(f, "deprecated, {note}"),
+        }
+    }
+}
+
+/// Typed accessor of a property in flat properties map.
+pub struct Property<T> {
+    name: &'static str,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Property<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Property<T> {}
+
+impl<T> Property<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Property {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T: DeserializeOwned + Serialize> Property<T> {
+    /// Returns `None` if the property is not set.
+    pub fn get(&self, properties: &Map<String, Value>) -> Result<Option<T>, serde_json::Error> {
+        properties
+            .get(self.name)
+            .map(|value| T::deserialize(value))
+            .transpose()
+    }
+
+    pub fn set(&self, properties: &mut Map<String, Value>, value: T) -> serde_json::Result<()> {
+        properties.insert(self.name.to_string(), serde_json::to_value(value)?);
+        Ok(())
+    }
+}
+
+pub struct Registry {
+    definitions: HashMap<&'static str, &'static Definition>,
+}
+
+impl Registry {
+    /// Registry of properties defined by Golem standards.
+    pub fn golem() -> &'static Registry {
+        &GOLEM
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'static Definition> {
+        self.definitions.get(name).copied()
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item = &'static Definition> + '_ {
+        self.definitions.values().copied()
+    }
+
+    /// Checks value of a property. Unknown properties are not reported.
+    pub fn check(&self, name: &str, value: &Value) -> Vec<SchemaIssue> {
+        let mut issues = Vec::new();
+        if let Some(definition) = self.get(name) {
+            if !definition.kind.matches(value) {
+                issues.push(SchemaIssue::TypeMismatch(definition.kind));
+            }
+            if let Some(note) = definition.deprecated {
+                issues.push(SchemaIssue::Deprecated(note));
+            }
+        }
+        issues
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GOLEM: Registry = Registry {
+        definitions: DEFINITIONS.iter().map(|d| (d.name, d)).collect(),
+    };
+}
+
+macro_rules! schema {
+    (@opt $value:literal) => { Some($value) };
+    (@opt) => { None };
+    ($(
+        $module:ident {
+            $( $id:ident: $ty:ty = $name:literal ($kind:ident $(, unit = $unit:literal)? $(, deprecated = $dep:literal)?); )*
+        }
+    )*) => {
+        $(
+            pub mod $module {
+                use super::Property;
+                $( pub const $id: Property<$ty> = Property::new($name); )*
+            }
+        )*
+
+        static DEFINITIONS: &[Definition] = &[
+            $($(
+                Definition {
+                    name: $name,
+                    kind: PropertyType::$kind,
+                    unit: schema!(@opt $($unit)?),
+                    deprecated: schema!(@opt $($dep)?),
+                },
+            )*)*
+        ];
+    };
+}
+
+schema! {
+    inf {
+        CPU_ARCHITECTURE: String = "golem.inf.cpu.architecture" (String);
+        CPU_CAPABILITIES: Vec<String> = "golem.inf.cpu.capabilities" (StringList);
+        CPU_CORES: u32 = "golem.inf.cpu.cores" (Integer);
+        CPU_THREADS: u32 = "golem.inf.cpu.threads" (Integer);
+        MEM_GIB: f64 = "golem.inf.mem.gib" (Number, unit = "GiB");
+        STORAGE_GIB: f64 = "golem.inf.storage.gib" (Number, unit = "GiB");
+    }
+    runtime {
+        NAME: String = "golem.runtime.name" (String);
+        VERSION: String = "golem.runtime.version" (String);
+        CAPABILITIES: Vec<String> = "golem.runtime.capabilities" (StringList);
+        DEVICES: Vec<String> = "golem.runtime.devices" (StringList);
+    }
+    srv {
+        COMP_EXPIRATION: u64 = "golem.srv.comp.expiration" (Integer, unit = "ms");
+        COMP_TASK_PACKAGE: String = "golem.srv.comp.task_package" (String);
+        CAPS_MULTI_ACTIVITY: bool = "golem.srv.caps.multi-activity" (Boolean);
+        CAPS_PAYLOAD_MANIFEST: bool = "golem.srv.caps.payload-manifest" (Boolean);
+        CAPS_RESTART_ALLOWED: bool = "golem.srv.caps.restart-allowed" (Boolean);
+    }
+    node {
+        ID_NAME: String = "golem.node.id.name" (String);
+        DEBUG_SUBNET: String = "golem.node.debug.subnet" (String);
+        GEO_COUNTRY_CODE: String = "golem.node.geo.country_code" (String);
+        NET_IS_PUBLIC: bool = "golem.node.net.is-public" (Boolean);
+    }
+    com {
+        USAGE_VECTOR: Vec<String> = "golem.com.usage.vector" (StringList);
+        PRICING_MODEL: String = "golem.com.pricing.model" (String);
+        PRICING_LINEAR_COEFFS: Vec<f64> = "golem.com.pricing.model.linear.coeffs" (NumberList);
+        SCHEME: String = "golem.com.scheme" (String);
+        DEBIT_NOTE_INTERVAL: u32 = "golem.com.scheme.payu.debit-note.interval-sec?" (Integer, unit = "s");
+        PAYMENT_TIMEOUT: u32 = "golem.com.scheme.payu.payment-timeout-sec?" (Integer, unit = "s");
+        DEBIT_NOTE_ACCEPT_TIMEOUT: u32 = "golem.com.payment.debit-notes.accept-timeout?" (Integer, unit = "s");
+        PAYMENT_CHOSEN_PLATFORM: String = "golem.com.payment.chosen-platform" (String);
+        PAYMENT_PROTOCOL_VERSION: u32 = "golem.com.payment.protocol.version" (Integer);
+        LEGACY_DEBIT_NOTE_INTERVAL: u32 = "golem.com.scheme.payu.interval_sec" (
+            Integer,
+            unit = "s",
+            deprecated = "use golem.com.scheme.payu.debit-note.interval-sec?"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn registry_reports_type_mismatch_and_deprecation() {
+        let registry = Registry::golem();
+        assert_eq!(registry.get("golem.inf.mem.gib").unwrap().unit, Some("GiB"));
+        assert!(registry.check("golem.inf.mem.gib", &json!(0.5)).is_empty());
+        assert_eq!(
+            registry.check("golem.inf.cpu.threads", &json!("four")),
+            vec![SchemaIssue::TypeMismatch(PropertyType::Integer)]
+        );
+        assert!(matches!(
+            registry.check("golem.com.scheme.payu.interval_sec", &json!(120))[..],
+            [SchemaIssue::Deprecated(_)]
+        ));
+        assert!(registry
+            .check("golem.inf.unknown", &json!("four"))
+            .is_empty());
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let mut properties = Map::new();
+        com::USAGE_VECTOR
+            .set(&mut properties, vec!["golem.usage.cpu_sec".to_string()])
+            .unwrap();
+        assert_eq!(
+            com::USAGE_VECTOR.get(&properties).unwrap(),
+            Some(vec!["golem.usage.cpu_sec".to_string()])
+        );
+        assert_eq!(inf::MEM_GIB.get(&properties).unwrap(), None);
+
+        properties.insert(inf::CPU_THREADS.name().to_string(), json!("four"));
+        assert!(inf::CPU_THREADS.get(&properties).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Requestor side pool of Agreements.
+//!
+//! `AgreementPool` subscribes a Demand and keeps the configured number of approved
+//! Agreements. Agreements close to expiration and the ones released as failed
+//! are terminated and replaced by new ones negotiated from incoming Proposals.
+//! Applications borrow Agreements using `acquire` and give them back by dropping
+//! returned `PooledAgreement`.
+mod state;
+
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use ya_client::market::MarketRequestorApi;
+use ya_client::model::market::proposal::State as ProposalState;
+use ya_client::model::market::{AgreementProposal, NewDemand, Proposal, Reason, RequestorEvent};
+
+use crate::state::PoolState;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PoolError {
+    #[error("Agreement pool is closed")]
+    Closed,
+    #[error("Market API error: {0}")]
+    Api(#[from] ya_client::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    /// Number of Agreements maintained by the pool.
+    pub size: usize,
+    /// Validity of created Agreements.
+    pub agreement_expiration: Duration,
+    /// Agreements expiring within this margin are replaced.
+    pub expiration_margin: Duration,
+    /// Time to wait for Provider's approval.
+    pub approval_timeout: Duration,
+    /// Timeout of a single market events collect call.
+    pub collect_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            size: 1,
+            agreement_expiration: Duration::from_secs(30 * 60),
+            expiration_margin: Duration::from_secs(60),
+            approval_timeout: Duration::from_secs(30),
+            collect_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+struct Shared {
+    state: Mutex<PoolState>,
+    /// Wakes up tasks waiting in `acquire`.
+    available: Notify,
+    /// Wakes up maintenance loop after Agreement was released.
+    released: Notify,
+}
+
+#[derive(Clone)]
+pub struct AgreementPool {
+    shared: Arc<Shared>,
+    api: MarketRequestorApi,
+    subscription_id: String,
+}
+
+/// Agreement borrowed from the pool. Dropping it returns Agreement to the pool.
+pub struct PooledAgreement {
+    shared: Arc<Shared>,
+    agreement_id: String,
+    valid_to: DateTime<Utc>,
+    failed: bool,
+}
+
+impl PooledAgreement {
+    pub fn agreement_id(&self) -> &str {
+        &self.agreement_id
+    }
+
+    pub fn valid_to(&self) -> DateTime<Utc> {
+        self.valid_to
+    }
+
+    /// Marks Agreement as unusable. It will be terminated and replaced.
+    pub fn fail(mut self) {
+        self.failed = true;
+    }
+}
+
+impl Drop for PooledAgreement {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.release(&self.agreement_id, self.failed);
+        drop(state);
+
+        self.shared.available.notify_waiters();
+        self.shared.released.notify_one();
+    }
+}
+
+impl AgreementPool {
+    /// Subscribes `demand` and spawns pool maintenance on current thread's `LocalSet`
+    /// (e.g. actix runtime).
+    pub async fn start(
+        api: MarketRequestorApi,
+        demand: NewDemand,
+        config: PoolConfig,
+    ) -> Result<AgreementPool, PoolError> {
+        let subscription_id = api.subscribe(&demand).await?;
+        log::info!(
+            "Agreement pool of size {} subscribed Demand [{}]",
+            config.size,
+            subscription_id
+        );
+
+        let pool = AgreementPool {
+            shared: Arc::new(Shared {
+                state: Mutex::new(PoolState::new(config.size)),
+                available: Notify::new(),
+                released: Notify::new(),
+            }),
+            api,
+            subscription_id,
+        };
+        tokio::task::spawn_local(pool.clone().maintain(demand, config));
+        Ok(pool)
+    }
+
+    /// Waits until any Agreement in the pool is available.
+    pub async fn acquire(&self) -> Result<PooledAgreement, PoolError> {
+        loop {
+            let available = self.shared.available.notified();
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                if state.is_closed() {
+                    return Err(PoolError::Closed);
+                }
+                if let Some((agreement_id, valid_to)) = state.take_idle() {
+                    return Ok(PooledAgreement {
+                        shared: self.shared.clone(),
+                        agreement_id,
+                        valid_to,
+                        failed: false,
+                    });
+                }
+            }
+            available.await;
+        }
+    }
+
+    /// Number of approved Agreements and number of them currently acquired.
+    pub fn stats(&self) -> (usize, usize) {
+        let state = self.shared.state.lock().unwrap();
+        (state.len(), state.busy())
+    }
+
+    /// Unsubscribes Demand and terminates all Agreements in the pool.
+    pub async fn close(&self) -> Result<(), PoolError> {
+        let agreements = self.shared.state.lock().unwrap().close();
+        self.shared.available.notify_waiters();
+        self.shared.released.notify_one();
+
+        self.api.unsubscribe(&self.subscription_id).await?;
+        for agreement_id in agreements {
+            self.terminate(&agreement_id, "Agreement pool closed").await;
+        }
+        Ok(())
+    }
+
+    async fn maintain(self, demand: NewDemand, config: PoolConfig) {
+        loop {
+            let (to_terminate, missing) = {
+                let mut state = self.shared.state.lock().unwrap();
+                if state.is_closed() {
+                    return;
+                }
+                (
+                    state.drain_unusable(Utc::now(), config.expiration_margin),
+                    state.missing(),
+                )
+            };
+
+            for (agreement_id, reason) in to_terminate {
+                self.terminate(&agreement_id, reason).await;
+            }
+
+            if missing == 0 {
+                let released = self.shared.released.notified();
+                tokio::select! {
+                    _ = released => (),
+                    _ = tokio::time::sleep(config.expiration_margin / 2) => (),
+                }
+                continue;
+            }
+
+            let events = match self
+                .api
+                .collect(
+                    &self.subscription_id,
+                    Some(config.collect_timeout.as_secs_f32()),
+                    Some(missing as i32 * 4),
+                )
+                .await
+            {
+                Ok(events) => events,
+                Err(e) => {
+                    log::warn!("Agreement pool failed to collect market events: {}", e);
+                    tokio::time::sleep(config.collect_timeout).await;
+                    continue;
+                }
+            };
+
+            for event in events {
+                if let RequestorEvent::ProposalEvent { proposal, .. } = event {
+                    if let Err(e) = self.handle_proposal(&demand, &config, proposal).await {
+                        log::debug!("Agreement pool skipped Proposal: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle_proposal(
+        &self,
+        demand: &NewDemand,
+        config: &PoolConfig,
+        proposal: Proposal,
+    ) -> Result<(), PoolError> {
+        match proposal.state {
+            ProposalState::Initial => {
+                self.api
+                    .counter_proposal(demand, &self.subscription_id, &proposal.proposal_id)
+                    .await?;
+            }
+            ProposalState::Draft => {
+                if self.shared.state.lock().unwrap().missing() == 0 {
+                    return Ok(());
+                }
+                self.create_agreement(config, &proposal).await?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    async fn create_agreement(
+        &self,
+        config: &PoolConfig,
+        proposal: &Proposal,
+    ) -> Result<(), PoolError> {
+        let valid_to = Utc::now()
+            + chrono::Duration::from_std(config.agreement_expiration)
+                .unwrap_or_else(|_| chrono::Duration::minutes(30));
+        let agreement_id = self
+            .api
+            .create_agreement(&AgreementProposal::new(
+                proposal.proposal_id.clone(),
+                valid_to,
+            ))
+            .await?;
+        self.api.confirm_agreement(&agreement_id, None).await?;
+
+        if let Err(e) = self
+            .api
+            .wait_for_approval(&agreement_id, Some(config.approval_timeout.as_secs_f32()))
+            .await
+        {
+            log::info!(
+                "Agreement [{}] with [{}] not approved: {}",
+                agreement_id,
+                proposal.issuer_id,
+                e
+            );
+            if let Err(e) = self
+                .api
+                .cancel_agreement(&agreement_id, &Some(Reason::new("Agreement not approved")))
+                .await
+            {
+                log::debug!("Failed to cancel Agreement [{}]: {}", agreement_id, e);
+            }
+            return Err(e.into());
+        }
+
+        log::info!(
+            "Agreement [{}] with [{}] added to the pool",
+            agreement_id,
+            proposal.issuer_id
+        );
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .insert(agreement_id, valid_to);
+        self.shared.available.notify_waiters();
+        Ok(())
+    }
+
+    async fn terminate(&self, agreement_id: &str, reason: &str) {
+        log::info!(
+            "Terminating pooled Agreement [{}]: {}",
+            agreement_id,
+            reason
+        );
+        if let Err(e) = self
+            .api
+            .terminate_agreement(agreement_id, &Some(Reason::new(reason)))
+            .await
+        {
+            log::warn!("Failed to terminate Agreement [{}]: {}", agreement_id, e);
+        }
+    }
+}
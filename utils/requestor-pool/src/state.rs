@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time::Duration;
+
+struct Entry {
+    valid_to: DateTime<Utc>,
+    busy: bool,
+    failed: bool,
+}
+
+/// Bookkeeping of pooled Agreements, independent of Market API.
+pub(crate) struct PoolState {
+    size: usize,
+    agreements: HashMap<String, Entry>,
+    closed: bool,
+}
+
+impl PoolState {
+    pub fn new(size: usize) -> Self {
+        PoolState {
+            size,
+            agreements: HashMap::new(),
+            closed: false,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn len(&self) -> usize {
+        self.agreements.len()
+    }
+
+    pub fn busy(&self) -> usize {
+        self.agreements.values().filter(|e| e.busy).count()
+    }
+
+    /// Number of Agreements to negotiate to fill the pool.
+    pub fn missing(&self) -> usize {
+        self.size.saturating_sub(self.agreements.len())
+    }
+
+    pub fn insert(&mut self, agreement_id: String, valid_to: DateTime<Utc>) {
+        self.agreements.insert(
+            agreement_id,
+            Entry {
+                valid_to,
+                busy: false,
+                failed: false,
+            },
+        );
+    }
+
+    /// Borrows idle Agreement expiring last.
+    pub fn take_idle(&mut self) -> Option<(String, DateTime<Utc>)> {
+        let (agreement_id, entry) = self
+            .agreements
+            .iter_mut()
+            .filter(|(_, e)| !e.busy && !e.failed)
+            .max_by_key(|(_, e)| e.valid_to)?;
+        entry.busy = true;
+        Some((agreement_id.clone(), entry.valid_to))
+    }
+
+    pub fn release(&mut self, agreement_id: &str, failed: bool) {
+        if let Some(entry) = self.agreements.get_mut(agreement_id) {
+            entry.busy = false;
+            entry.failed |= failed;
+        }
+    }
+
+    /// Removes idle Agreements, which failed or are about to expire.
+    pub fn drain_unusable(
+        &mut self,
+        now: DateTime<Utc>,
+        margin: Duration,
+    ) -> Vec<(String, &'static str)> {
+        let deadline =
+            now + chrono::Duration::from_std(margin).unwrap_or_else(|_| chrono::Duration::zero());
+        let unusable = self
+            .agreements
+            .iter()
+            .filter(|(_, e)| !e.busy)
+            .filter_map(|(id, e)| {
+                if e.failed {
+                    Some((id.clone(), "Agreement failed"))
+                } else if e.valid_to <= deadline {
+                    Some((id.clone(), "Agreement expiring"))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for (agreement_id, _) in &unusable {
+            self.agreements.remove(agreement_id);
+        }
+        unusable
+    }
+
+    /// Closes the pool and returns all Agreements to terminate.
+    pub fn close(&mut self) -> Vec<String> {
+        self.closed = true;
+        self.agreements.drain().map(|(id, _)| id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_and_expiring_agreements_are_replaced() {
+        let now = Utc::now();
+        let mut state = PoolState::new(3);
+        state.insert("a".to_string(), now + chrono::Duration::minutes(30));
+        state.insert("b".to_string(), now + chrono::Duration::minutes(20));
+        state.insert("c".to_string(), now + chrono::Duration::seconds(30));
+        assert_eq!(state.missing(), 0);
+
+        let (first, _) = state.take_idle().unwrap();
+        assert_eq!(first, "a");
+        let (second, _) = state.take_idle().unwrap();
+        assert_eq!(second, "b");
+        assert_eq!(state.busy(), 2);
+
+        state.release(&first, true);
+        let mut unusable = state.drain_unusable(now, Duration::from_secs(60));
+        unusable.sort();
+        assert_eq!(
+            unusable,
+            vec![
+                ("a".to_string(), "Agreement failed"),
+                ("c".to_string(), "Agreement expiring")
+            ]
+        );
+        assert_eq!(state.missing(), 2);
+        assert!(state.take_idle().is_none());
+
+        state.release(&second, false);
+        assert_eq!(state.take_idle().unwrap().0, "b");
+    }
+}